@@ -0,0 +1,300 @@
+//! Leica GSI-8 and GSI-16 reader.
+//!
+//! A GSI block is one line of words. Each word starts with a two digit word
+//! index (WI), four characters of word information whose last character is
+//! the unit code, a sign and the data (8 digits for GSI-8, 16 for GSI-16).
+//! GSI-16 blocks are prefixed with `*`.
+//!
+//! Supported word indices: 11 point id, 21 Hz, 22 V (zenith), 31 slope
+//! distance, 41-49 code block, 71 remark/code, 81-83 target E/N/H, 84-86
+//! station E/N/H, 87 reflector height and 88 instrument height.
+
+use super::{invalid, RawReducer, RawSurvey};
+use std::f64::consts::PI;
+use std::io;
+use survey_cad::geometry::Point3;
+
+const FOOT: f64 = 0.3048;
+
+/// Returns `true` when `line` looks like a GSI block.
+pub fn is_gsi(line: &str) -> bool {
+    let line = line.trim_start_matches('*');
+    let b = line.as_bytes();
+    b.len() >= 8 && b[0].is_ascii_digit() && b[1].is_ascii_digit() && matches!(b[6], b'+' | b'-')
+}
+
+#[derive(Debug)]
+struct Word<'a> {
+    wi: u32,
+    unit: u8,
+    negative: bool,
+    data: &'a str,
+}
+
+fn parse_word(word: &str) -> io::Result<Word<'_>> {
+    let b = word.as_bytes();
+    if b.len() < 8 || !matches!(b[6], b'+' | b'-') {
+        return Err(invalid(format!("malformed GSI word '{word}'")));
+    }
+    let wi = word[..2]
+        .parse()
+        .map_err(|_| invalid(format!("invalid GSI word index in '{word}'")))?;
+    Ok(Word {
+        wi,
+        unit: b[5],
+        negative: b[6] == b'-',
+        data: &word[7..],
+    })
+}
+
+impl Word<'_> {
+    fn text(&self) -> String {
+        let t = self.data.trim().trim_start_matches('0');
+        if t.is_empty() {
+            "0".to_string()
+        } else {
+            t.to_string()
+        }
+    }
+
+    fn integer(&self) -> io::Result<f64> {
+        let v: f64 = self
+            .data
+            .trim()
+            .parse::<u64>()
+            .map_err(|_| invalid(format!("invalid GSI value '{}'", self.data)))?
+            as f64;
+        Ok(if self.negative { -v } else { v })
+    }
+
+    /// Linear value in metres.
+    fn length(&self) -> io::Result<f64> {
+        let v = self.integer()?;
+        Ok(match self.unit {
+            b'1' => v / 1000.0 * FOOT,
+            b'6' => v / 10_000.0,
+            b'7' => v / 10_000.0 * FOOT,
+            b'8' => v / 100_000.0,
+            _ => v / 1000.0,
+        })
+    }
+
+    /// Angle in radians.
+    fn angle(&self) -> io::Result<f64> {
+        let v = self.integer()?;
+        Ok(match self.unit {
+            b'2' => v / 100_000.0 * PI / 200.0,
+            b'3' => (v / 100_000.0).to_radians(),
+            b'4' => {
+                let a = v.abs() as u64;
+                let tenths = (a % 10) as f64;
+                let sec = ((a / 10) % 100) as f64;
+                let min = ((a / 1000) % 100) as f64;
+                let deg = (a / 100_000) as f64;
+                v.signum() * (deg + min / 60.0 + (sec + tenths / 10.0) / 3600.0).to_radians()
+            }
+            b'5' => v / 10_000.0 * 2.0 * PI / 6400.0,
+            _ => {
+                return Err(invalid(format!(
+                    "unsupported GSI angle unit '{}'",
+                    self.unit as char
+                )))
+            }
+        })
+    }
+}
+
+#[derive(Debug, Default)]
+struct Block {
+    point: Option<String>,
+    hz: Option<f64>,
+    v: Option<f64>,
+    sd: Option<f64>,
+    code: Option<String>,
+    target: [Option<f64>; 3],
+    station: [Option<f64>; 3],
+    target_height: Option<f64>,
+    instrument_height: Option<f64>,
+}
+
+/// Parses the contents of a GSI-8 or GSI-16 file.
+///
+/// A block carrying station coordinates (WI 84/85) without a horizontal
+/// angle starts a new setup. The first measurement after a setup is taken as
+/// the backsight.
+pub fn parse_leica_gsi(text: &str) -> io::Result<RawSurvey> {
+    let mut r = RawReducer::default();
+    let mut target_height = 0.0;
+    let mut instrument_height = 0.0;
+    let mut pending_code: Option<String> = None;
+
+    for (no, line) in text.lines().enumerate() {
+        let line = line.trim().trim_start_matches('*');
+        if line.is_empty() {
+            continue;
+        }
+        let mut block = Block::default();
+        let mut code_block = false;
+        for word in line.split_whitespace() {
+            let w = parse_word(word).map_err(|e| invalid(format!("line {}: {e}", no + 1)))?;
+            match w.wi {
+                11 => block.point = Some(w.text()),
+                21 => block.hz = Some(w.angle()?),
+                22 => block.v = Some(w.angle()?),
+                31 => block.sd = Some(w.length()?),
+                41 => {
+                    code_block = true;
+                    block.code = Some(w.text());
+                }
+                42..=49 if code_block => {
+                    let info = w.text();
+                    if info != "0" {
+                        let code = block.code.get_or_insert_with(String::new);
+                        code.push(' ');
+                        code.push_str(&info);
+                    }
+                }
+                71 => block.code = Some(w.text()),
+                81..=83 => block.target[(w.wi - 81) as usize] = Some(w.length()?),
+                84..=86 => block.station[(w.wi - 84) as usize] = Some(w.length()?),
+                87 => block.target_height = Some(w.length()?),
+                88 => block.instrument_height = Some(w.length()?),
+                _ => {}
+            }
+        }
+        if code_block {
+            pending_code = block.code;
+            continue;
+        }
+        if let Some(th) = block.target_height {
+            target_height = th;
+        }
+        if let Some(ih) = block.instrument_height {
+            instrument_height = ih;
+        }
+        let Some(name) = block.point else {
+            continue;
+        };
+        let code = block.code.or_else(|| pending_code.take());
+
+        if block.hz.is_none() && (block.station[0].is_some() || block.station[1].is_some()) {
+            let pos = Point3::new(
+                block.station[0].unwrap_or(0.0),
+                block.station[1].unwrap_or(0.0),
+                block.station[2].unwrap_or(0.0),
+            );
+            r.setup(&name, instrument_height, Some(pos));
+            continue;
+        }
+        if let (Some(e), Some(n)) = (block.target[0], block.target[1]) {
+            r.coordinate(
+                &name,
+                Point3::new(e, n, block.target[2].unwrap_or(0.0)),
+                code.as_deref(),
+            );
+        }
+        if let (Some(hz), Some(v), Some(sd)) = (block.hz, block.v, block.sd) {
+            if r.awaiting_backsight() {
+                r.backsight(&name, None, Some(hz));
+            }
+            r.observe(&name, hz, v, sd, target_height, code.as_deref());
+        }
+    }
+    Ok(r.finish())
+}
+
+/// Reads a Leica GSI-8 or GSI-16 file.
+pub fn read_leica_gsi(path: &str) -> io::Result<RawSurvey> {
+    parse_leica_gsi(&std::fs::read_to_string(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::assert_close;
+    use super::*;
+    use survey_cad::surveying::ObservationData;
+
+    const GSI8: &str = "\
+110001+00000001 84..10+01000000 85..10+02000000 86..10+00100000 88..10+00001500
+110002+00000002 21.324+00000000 22.324+09000000 31..00+00100000 87..10+00001500
+410003+0000TREE
+110004+00000003 21.324+09000000 22.324+09000000 31..00+00010000 87..10+00001500
+";
+
+    const GSI16: &str = "\
+*110001+000000000000STN1 84..10+0000000001000000 85..10+0000000002000000 86..10+0000000000100000 88..10+0000000000001600
+*110002+00000000000000BS 21.322+0000000000000000 22.322+0000000010000000 31..00+0000000000100000 81..00+0000000001000000 82..00+0000000002100000 83..00+0000000000100000
+*110003+0000000000000010 21.322+0000000010000000 22.322+0000000010000000 31..00+0000000000020000 87..10+0000000000001600 71....+00000000000000MH
+";
+
+    #[test]
+    fn gsi8_setup_backsight_and_shot() {
+        let s = parse_leica_gsi(GSI8).unwrap();
+        assert_eq!(s.observations.len(), 3);
+        match &s.observations[0].data {
+            ObservationData::Setup {
+                station,
+                instrument_height,
+                backsight,
+                ..
+            } => {
+                assert_eq!(station, "1");
+                assert!((instrument_height - 1.5).abs() < 1e-9);
+                assert_eq!(backsight.as_deref(), Some("2"));
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+        match &s.observations[2].data {
+            ObservationData::TotalStation {
+                from,
+                to,
+                horiz_angle,
+                vert_angle,
+                slope_distance,
+                target_height,
+                code,
+                ..
+            } => {
+                assert_eq!(from, "1");
+                assert_eq!(to, "3");
+                assert!((horiz_angle - 90f64.to_radians()).abs() < 1e-9);
+                assert!((vert_angle - 90f64.to_radians()).abs() < 1e-9);
+                assert!((slope_distance - 10.0).abs() < 1e-9);
+                assert!((target_height - 1.5).abs() < 1e-9);
+                assert_eq!(code.as_deref(), Some("TREE"));
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+        assert_close(
+            s.point("1").unwrap().point,
+            Point3::new(1000.0, 2000.0, 100.0),
+        );
+        assert_close(
+            s.point("2").unwrap().point,
+            Point3::new(1000.0, 2100.0, 100.0),
+        );
+        assert_close(
+            s.point("3").unwrap().point,
+            Point3::new(1010.0, 2000.0, 100.0),
+        );
+    }
+
+    #[test]
+    fn gsi16_gon_with_target_coordinates() {
+        let s = parse_leica_gsi(GSI16).unwrap();
+        assert_close(
+            s.point("BS").unwrap().point,
+            Point3::new(1000.0, 2100.0, 100.0),
+        );
+        // 100 gon clockwise from north with a 20 m horizontal shot
+        let p = s.point("10").unwrap();
+        assert_close(p.point, Point3::new(1020.0, 2000.0, 100.0));
+        assert_eq!(p.description.as_deref(), Some("MH"));
+        assert_eq!(p.number, Some(10));
+    }
+
+    #[test]
+    fn rejects_malformed_word() {
+        assert!(parse_leica_gsi("110001+00000001 21.32\n").is_err());
+    }
+}
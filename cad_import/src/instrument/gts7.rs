@@ -0,0 +1,173 @@
+//! Topcon GTS-7 reader.
+//!
+//! GTS-7 files contain one record per line: a keyword followed by comma
+//! separated values. Angles are packed `DDD.MMSS`.
+//!
+//! | Keyword        | Values                               |
+//! |----------------|--------------------------------------|
+//! | `STN`          | station, instrument height, code     |
+//! | `XYZ`          | easting, northing, elevation         |
+//! | `NEZ`          | northing, easting, elevation         |
+//! | `BKB`          | backsight, azimuth, circle reading   |
+//! | `BS`           | backsight, target height             |
+//! | `SS` / `FS`    | point, target height, code           |
+//! | `SD`           | Hz, zenith, slope distance           |
+//!
+//! Coordinate records apply to the point named by the preceding `STN`, `BS`,
+//! `SS` or `FS` record.
+
+use super::{dms_packed_to_rad, parse_num, RawReducer, RawSurvey};
+use std::io;
+use survey_cad::geometry::Point3;
+
+const KEYWORDS: &[&str] = &[
+    "JOB", "DATE", "NAME", "INST", "UNITS", "SCALE", "ATMOS", "STN", "XYZ", "NEZ", "BKB", "BS",
+    "SS", "FS", "SD", "HD", "CO", "PT",
+];
+
+fn split(line: &str) -> (&str, Vec<&str>) {
+    let line = line.trim();
+    let (kw, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    (kw, rest.split(',').map(str::trim).collect())
+}
+
+/// Returns `true` when `line` starts with a GTS-7 keyword.
+pub fn is_gts7(line: &str) -> bool {
+    let (kw, _) = split(line);
+    KEYWORDS.contains(&kw)
+}
+
+fn value(fields: &[&str], i: usize) -> io::Result<Option<f64>> {
+    match fields.get(i) {
+        Some(s) if !s.is_empty() => parse_num(s).map(Some),
+        _ => Ok(None),
+    }
+}
+
+/// Parses the contents of a Topcon GTS-7 file.
+pub fn parse_topcon_gts7(text: &str) -> io::Result<RawSurvey> {
+    let mut r = RawReducer::default();
+    let mut last_point = String::new();
+    let mut target = String::new();
+    let mut target_height = 0.0;
+    let mut code: Option<String> = None;
+    let mut to_backsight = false;
+
+    for (no, line) in text.lines().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (kw, f) = split(line);
+        let res: io::Result<()> = (|| {
+            match kw {
+                "STN" => {
+                    last_point = f.first().unwrap_or(&"").to_string();
+                    r.setup(&last_point, value(&f, 1)?.unwrap_or(0.0), None);
+                }
+                "XYZ" | "NEZ" => {
+                    let (a, b) = (value(&f, 0)?.unwrap_or(0.0), value(&f, 1)?.unwrap_or(0.0));
+                    let (e, n) = if kw == "XYZ" { (a, b) } else { (b, a) };
+                    let z = value(&f, 2)?.unwrap_or(0.0);
+                    r.coordinate(&last_point, Point3::new(e, n, z), None);
+                }
+                "BKB" => {
+                    let bs = f.first().unwrap_or(&"");
+                    let az = value(&f, 1)?.map(dms_packed_to_rad);
+                    let circle = value(&f, 2)?.map(dms_packed_to_rad);
+                    r.backsight(bs, az, circle);
+                }
+                "BS" | "SS" | "FS" => {
+                    target = f.first().unwrap_or(&"").to_string();
+                    last_point = target.clone();
+                    if let Some(th) = value(&f, 1)? {
+                        target_height = th;
+                    }
+                    code = f.get(2).filter(|c| !c.is_empty()).map(|c| c.to_string());
+                    to_backsight = kw == "BS";
+                }
+                "SD" => {
+                    let ha = value(&f, 0)?.map(dms_packed_to_rad).unwrap_or(0.0);
+                    let va = value(&f, 1)?
+                        .map(dms_packed_to_rad)
+                        .unwrap_or(std::f64::consts::FRAC_PI_2);
+                    let sd = value(&f, 2)?.unwrap_or(0.0);
+                    if to_backsight && r.awaiting_backsight() {
+                        r.backsight(&target, None, Some(ha));
+                    }
+                    r.observe(&target, ha, va, sd, target_height, code.as_deref());
+                }
+                _ => {}
+            }
+            Ok(())
+        })();
+        res.map_err(|e| io::Error::new(e.kind(), format!("line {}: {e}", no + 1)))?;
+    }
+    Ok(r.finish())
+}
+
+/// Reads a Topcon GTS-7 file.
+pub fn read_topcon_gts7(path: &str) -> io::Result<RawSurvey> {
+    parse_topcon_gts7(&std::fs::read_to_string(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::assert_close;
+    use super::*;
+    use survey_cad::surveying::ObservationData;
+
+    const GTS7: &str = "\
+JOB     TEST
+STN 1,1.450,CTRL
+XYZ 1000.000,5000.000,50.000
+BS 2,1.450
+XYZ 1000.000,5100.000,50.000
+SD 0.0000,90.0000,100.000
+SS 10,1.450,TREE
+SD 45.0000,90.0000,20.000
+SS 11,1.450
+SD 90.3000,89.3000,10.000
+";
+
+    #[test]
+    fn gts7_setup_backsight_and_shots() {
+        let s = parse_topcon_gts7(GTS7).unwrap();
+        assert_eq!(s.observations.len(), 4);
+        match &s.observations[0].data {
+            ObservationData::Setup {
+                station,
+                instrument_height,
+                backsight,
+                backsight_azimuth,
+                ..
+            } => {
+                assert_eq!(station, "1");
+                assert!((instrument_height - 1.45).abs() < 1e-9);
+                assert_eq!(backsight.as_deref(), Some("2"));
+                assert!(backsight_azimuth.unwrap().abs() < 1e-9);
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+        let d = 20.0 * std::f64::consts::FRAC_1_SQRT_2;
+        let p = s.point("10").unwrap();
+        assert_close(p.point, Point3::new(1000.0 + d, 5000.0 + d, 50.0));
+        assert_eq!(p.description.as_deref(), Some("TREE"));
+        match &s.observations[3].data {
+            ObservationData::TotalStation {
+                horiz_angle,
+                vert_angle,
+                ..
+            } => {
+                assert!((horiz_angle - 90.5f64.to_radians()).abs() < 1e-9);
+                assert!((vert_angle - 89.5f64.to_radians()).abs() < 1e-9);
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+
+    #[test]
+    fn detects_keywords() {
+        assert!(is_gts7("STN 1,1.5"));
+        assert!(!is_gts7("1,100.0,200.0,50.0,TEST"));
+    }
+}
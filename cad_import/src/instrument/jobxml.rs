@@ -0,0 +1,205 @@
+//! Trimble JobXML reader.
+//!
+//! The `FieldBook` records are processed in file order: `StationRecord`
+//! starts a setup, `TargetRecord` sets the target height, `BackBearingRecord`
+//! orients the setup and `PointRecord` provides either raw `Circle`
+//! observations or `Grid` coordinates. Coordinates from the `Reductions`
+//! section take precedence over values reduced from the raw observations.
+//! Angles are decimal degrees.
+
+use super::{invalid, parse_num, RawReducer, RawSurvey};
+use chrono::NaiveDate;
+use roxmltree::{Document, Node};
+use std::collections::HashMap;
+use std::io;
+use survey_cad::geometry::Point3;
+
+fn child<'a, 'i>(node: Node<'a, 'i>, name: &str) -> Option<Node<'a, 'i>> {
+    node.children().find(|c| c.has_tag_name(name))
+}
+
+fn text<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    child(node, name)
+        .and_then(|c| c.text())
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+}
+
+fn number(node: Node, name: &str) -> io::Result<Option<f64>> {
+    text(node, name).map(parse_num).transpose()
+}
+
+fn grid(node: Node) -> io::Result<Option<Point3>> {
+    let Some(g) = child(node, "Grid").or_else(|| child(node, "ComputedGrid")) else {
+        return Ok(None);
+    };
+    match (number(g, "North")?, number(g, "East")?) {
+        (Some(n), Some(e)) => Ok(Some(Point3::new(
+            e,
+            n,
+            number(g, "Elevation")?.unwrap_or(0.0),
+        ))),
+        _ => Ok(None),
+    }
+}
+
+/// Parses the contents of a Trimble JobXML file.
+pub fn parse_trimble_jobxml(text_in: &str) -> io::Result<RawSurvey> {
+    let doc = Document::parse(text_in).map_err(|e| invalid(e.to_string()))?;
+    let root = doc.root_element();
+    if !root.has_tag_name("JOBFile") {
+        return Err(invalid("missing JOBFile element"));
+    }
+    let mut r = RawReducer::default();
+    let mut targets: HashMap<String, f64> = HashMap::new();
+    let mut target_height = 0.0;
+
+    if let Some(book) = child(root, "FieldBook") {
+        for rec in book.children().filter(|n| n.is_element()) {
+            if let Some(date) = rec
+                .attribute("TimeStamp")
+                .and_then(|t| t.get(..10))
+                .and_then(|d| NaiveDate::parse_from_str(d, "%Y-%m-%d").ok())
+            {
+                r.date = date;
+            }
+            match rec.tag_name().name() {
+                "StationRecord" => {
+                    let name = text(rec, "StationName").unwrap_or("");
+                    let ih = number(rec, "TheodoliteHeight")?.unwrap_or(0.0);
+                    r.setup(name, ih, None);
+                }
+                "TargetRecord" => {
+                    let th = number(rec, "TargetHeight")?.unwrap_or(0.0);
+                    if let Some(id) = rec.attribute("ID") {
+                        targets.insert(id.to_string(), th);
+                    }
+                    target_height = th;
+                }
+                "BackBearingRecord" => {
+                    let bs = text(rec, "BackSight").unwrap_or("");
+                    let circle = number(rec, "Face1HorizontalCircle")?.map(f64::to_radians);
+                    r.backsight(bs, None, circle);
+                }
+                "PointRecord" => {
+                    if text(rec, "Deleted") == Some("true") {
+                        continue;
+                    }
+                    let name = text(rec, "Name").unwrap_or("");
+                    let code = text(rec, "Code");
+                    if let Some(p) = grid(rec)? {
+                        r.coordinate(name, p, code);
+                    }
+                    let Some(circle) = child(rec, "Circle") else {
+                        continue;
+                    };
+                    let (Some(ha), Some(va), Some(sd)) = (
+                        number(circle, "HorizontalCircle")?,
+                        number(circle, "VerticalCircle")?,
+                        number(circle, "EDMDistance")?,
+                    ) else {
+                        continue;
+                    };
+                    let th = text(rec, "TargetID")
+                        .and_then(|id| targets.get(id).copied())
+                        .unwrap_or(target_height);
+                    let is_bs = text(rec, "Classification") == Some("BackSight");
+                    if is_bs && r.awaiting_backsight() {
+                        r.backsight(name, None, Some(ha.to_radians()));
+                    }
+                    r.observe(name, ha.to_radians(), va.to_radians(), sd, th, code);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    if let Some(red) = child(root, "Reductions") {
+        for p in red.children().filter(|n| n.has_tag_name("Point")) {
+            let name = text(p, "Name").unwrap_or("");
+            if let Some(pos) = grid(p)? {
+                r.coordinate(name, pos, text(p, "Code"));
+            }
+        }
+    }
+    Ok(r.finish())
+}
+
+/// Reads a Trimble JobXML file.
+pub fn read_trimble_jobxml(path: &str) -> io::Result<RawSurvey> {
+    parse_trimble_jobxml(&std::fs::read_to_string(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::assert_close;
+    use super::*;
+    use survey_cad::surveying::ObservationData;
+
+    const JOB: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<JOBFile jobName="TEST" version="5.6">
+  <FieldBook>
+    <PointRecord ID="1" TimeStamp="2024-03-05T10:00:00">
+      <Name>CP1</Name><Code>CTRL</Code><Method>KeyedIn</Method>
+      <Grid><North>5000.000</North><East>1000.000</East><Elevation>100.000</Elevation></Grid>
+    </PointRecord>
+    <PointRecord ID="2">
+      <Name>CP2</Name><Code>CTRL</Code>
+      <Grid><North>5000.000</North><East>1100.000</East><Elevation>100.000</Elevation></Grid>
+    </PointRecord>
+    <StationRecord ID="3"><StationName>CP1</StationName><TheodoliteHeight>1.500</TheodoliteHeight></StationRecord>
+    <TargetRecord ID="4"><TargetHeight>2.000</TargetHeight></TargetRecord>
+    <BackBearingRecord ID="5"><Station>CP1</Station><BackSight>CP2</BackSight><Face1HorizontalCircle>0.0</Face1HorizontalCircle></BackBearingRecord>
+    <PointRecord ID="6">
+      <Name>CP2</Name><Code>CTRL</Code><Classification>BackSight</Classification>
+      <StationID>3</StationID><TargetID>4</TargetID>
+      <Circle><HorizontalCircle>0.0</HorizontalCircle><VerticalCircle>90.0</VerticalCircle><EDMDistance>100.000</EDMDistance></Circle>
+    </PointRecord>
+    <PointRecord ID="7">
+      <Name>100</Name><Code>EP</Code>
+      <StationID>3</StationID><TargetID>4</TargetID>
+      <Circle><HorizontalCircle>90.0</HorizontalCircle><VerticalCircle>90.0</VerticalCircle><EDMDistance>50.000</EDMDistance></Circle>
+    </PointRecord>
+    <PointRecord ID="8">
+      <Name>101</Name><Deleted>true</Deleted>
+      <Circle><HorizontalCircle>10.0</HorizontalCircle><VerticalCircle>90.0</VerticalCircle><EDMDistance>5.0</EDMDistance></Circle>
+    </PointRecord>
+  </FieldBook>
+  <Reductions>
+    <Point><Name>CP1</Name><Grid><North>5000.000</North><East>1000.000</East><Elevation>100.000</Elevation></Grid></Point>
+  </Reductions>
+</JOBFile>"#;
+
+    #[test]
+    fn jobxml_station_backsight_and_shot() {
+        let s = parse_trimble_jobxml(JOB).unwrap();
+        assert_eq!(
+            s.observations[0].date,
+            NaiveDate::from_ymd_opt(2024, 3, 5).unwrap()
+        );
+        match &s.observations[0].data {
+            ObservationData::Setup {
+                station,
+                backsight,
+                backsight_azimuth,
+                ..
+            } => {
+                assert_eq!(station, "CP1");
+                assert_eq!(backsight.as_deref(), Some("CP2"));
+                assert!((backsight_azimuth.unwrap() - 90f64.to_radians()).abs() < 1e-9);
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+        assert_eq!(s.observations.len(), 3);
+        // circle 90 with orientation 90 points south
+        let p = s.point("100").unwrap();
+        assert_close(p.point, Point3::new(1000.0, 4950.0, 99.5));
+        assert_eq!(p.description.as_deref(), Some("EP"));
+        assert!(s.point("101").is_none());
+    }
+
+    #[test]
+    fn rejects_other_xml() {
+        assert!(parse_trimble_jobxml("<LandXML/>").is_err());
+    }
+}
@@ -0,0 +1,407 @@
+//! Readers for raw data files produced by total station controllers.
+//!
+//! Each supported format is parsed into a [`RawSurvey`] holding the
+//! coordinate records found in the file together with the setups and raw
+//! observations as [`ObservationRecord`]s. Points that are only observed are
+//! reduced to coordinates from the current setup and backsight orientation.
+
+use super::SurveyPoint;
+use chrono::{Local, NaiveDate};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use survey_cad::geometry::Point3;
+use survey_cad::surveying::{ObsType, ObservationData, ObservationRecord};
+
+pub mod gsi;
+pub mod gts7;
+pub mod jobxml;
+pub mod sdr;
+
+pub use gsi::{parse_leica_gsi, read_leica_gsi};
+pub use gts7::{parse_topcon_gts7, read_topcon_gts7};
+pub use jobxml::{parse_trimble_jobxml, read_trimble_jobxml};
+pub use sdr::{parse_sokkia_sdr, parse_trimble_dc, read_sokkia_sdr, read_trimble_dc};
+
+/// Contents of a raw instrument file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RawSurvey {
+    /// Coordinates stored in the file or reduced from observations.
+    pub points: Vec<SurveyPoint>,
+    /// Point identifiers as written in the file, parallel to `points`.
+    pub names: Vec<String>,
+    /// Setups and raw observations in file order.
+    pub observations: Vec<ObservationRecord>,
+}
+
+impl RawSurvey {
+    /// Returns the coordinate record for the named point, if present.
+    pub fn point(&self, name: &str) -> Option<&SurveyPoint> {
+        let i = self.names.iter().position(|n| n == name)?;
+        self.points.get(i)
+    }
+}
+
+/// Converts a packed `DDD.MMSS` angle to radians.
+pub(crate) fn dms_packed_to_rad(value: f64) -> f64 {
+    let sign = value.signum();
+    let v = value.abs();
+    let deg = v.trunc();
+    let rest = (v - deg) * 100.0;
+    // round to suppress representation error in the packed minutes/seconds
+    let min = (rest + 1e-9).trunc();
+    let sec = (rest - min) * 100.0;
+    sign * (deg + min / 60.0 + sec / 3600.0).to_radians()
+}
+
+pub(crate) fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+pub(crate) fn parse_num(s: &str) -> io::Result<f64> {
+    s.trim()
+        .parse::<f64>()
+        .map_err(|e| invalid(format!("invalid number '{}': {e}", s.trim())))
+}
+
+#[derive(Debug, Clone)]
+struct ActiveSetup {
+    station: String,
+    instrument_height: f64,
+    orientation: f64,
+    record: usize,
+}
+
+/// Accumulates setups, observations and coordinates while a raw file is read
+/// and reduces observed points to coordinates.
+#[derive(Debug)]
+pub(crate) struct RawReducer {
+    survey: RawSurvey,
+    coords: HashMap<String, usize>,
+    setup: Option<ActiveSetup>,
+    /// Date assigned to new observation records.
+    pub date: NaiveDate,
+}
+
+impl Default for RawReducer {
+    fn default() -> Self {
+        Self {
+            survey: RawSurvey::default(),
+            coords: HashMap::new(),
+            setup: None,
+            date: Local::now().date_naive(),
+        }
+    }
+}
+
+impl RawReducer {
+    fn record(&self, data: ObservationData) -> ObservationRecord {
+        ObservationRecord {
+            id: None,
+            obs_type: ObsType::TotalStation,
+            date: self.date,
+            instrument: None,
+            crew: None,
+            control_point: self.setup.as_ref().map(|s| s.station.clone()),
+            data,
+        }
+    }
+
+    fn position(&self, name: &str) -> Option<Point3> {
+        self.coords.get(name).map(|&i| self.survey.points[i].point)
+    }
+
+    /// Stores or replaces the coordinates of `name`.
+    pub fn coordinate(&mut self, name: &str, point: Point3, code: Option<&str>) {
+        let code = code.map(str::trim).filter(|c| !c.is_empty());
+        let sp = SurveyPoint {
+            number: name.parse().ok(),
+            point,
+            description: code.map(str::to_string),
+            codes: code
+                .map(|c| c.split_whitespace().map(str::to_string).collect())
+                .unwrap_or_default(),
+        };
+        match self.coords.get(name) {
+            Some(&i) => {
+                let existing = &mut self.survey.points[i];
+                if sp.description.is_none() {
+                    existing.point = point;
+                } else {
+                    *existing = sp;
+                }
+            }
+            None => {
+                self.coords
+                    .insert(name.to_string(), self.survey.points.len());
+                self.survey.points.push(sp);
+                self.survey.names.push(name.to_string());
+            }
+        }
+    }
+
+    /// Starts a new instrument setup over `station`.
+    pub fn setup(&mut self, station: &str, instrument_height: f64, position: Option<Point3>) {
+        if let Some(p) = position {
+            self.coordinate(station, p, None);
+        }
+        self.setup = Some(ActiveSetup {
+            station: station.to_string(),
+            instrument_height,
+            orientation: 0.0,
+            record: self.survey.observations.len(),
+        });
+        let rec = self.record(ObservationData::Setup {
+            station: station.to_string(),
+            instrument_height,
+            backsight: None,
+            backsight_azimuth: None,
+            backsight_circle: None,
+        });
+        self.survey.observations.push(rec);
+    }
+
+    /// Returns `true` when the active setup has no backsight recorded yet.
+    pub fn awaiting_backsight(&self) -> bool {
+        self.setup.as_ref().is_some_and(|s| {
+            matches!(
+                self.survey.observations[s.record].data,
+                ObservationData::Setup {
+                    backsight: None,
+                    ..
+                }
+            )
+        })
+    }
+
+    /// Records the backsight of the active setup. When `azimuth` is not
+    /// supplied it is computed from the station and backsight coordinates.
+    /// Angles are in radians.
+    pub fn backsight(&mut self, name: &str, azimuth: Option<f64>, circle: Option<f64>) {
+        let Some(setup) = self.setup.clone() else {
+            return;
+        };
+        let azimuth = azimuth.or_else(|| {
+            let from = self.position(&setup.station)?;
+            let to = self.position(name)?;
+            Some((to.x - from.x).atan2(to.y - from.y))
+        });
+        if let ObservationData::Setup {
+            backsight,
+            backsight_azimuth,
+            backsight_circle,
+            ..
+        } = &mut self.survey.observations[setup.record].data
+        {
+            *backsight = Some(name.to_string());
+            if azimuth.is_some() {
+                *backsight_azimuth = azimuth;
+            }
+            if circle.is_some() {
+                *backsight_circle = circle;
+            }
+            if let Some(az) = *backsight_azimuth {
+                let orientation = az - backsight_circle.unwrap_or(0.0);
+                if let Some(s) = self.setup.as_mut() {
+                    s.orientation = orientation;
+                }
+            }
+        }
+    }
+
+    /// Records a raw shot from the active setup to `to`. Angles are in
+    /// radians with `zenith` measured from the vertical. Points without
+    /// coordinates are reduced from the setup.
+    pub fn observe(
+        &mut self,
+        to: &str,
+        horiz_angle: f64,
+        zenith: f64,
+        slope_distance: f64,
+        target_height: f64,
+        code: Option<&str>,
+    ) {
+        let code = code.map(str::trim).filter(|c| !c.is_empty());
+        let (from, ih) = self
+            .setup
+            .as_ref()
+            .map(|s| (s.station.clone(), s.instrument_height))
+            .unwrap_or_default();
+        let rec = self.record(ObservationData::TotalStation {
+            from: from.clone(),
+            to: to.to_string(),
+            horiz_angle,
+            vert_angle: zenith,
+            slope_distance,
+            instrument_height: ih,
+            target_height,
+            code: code.map(str::to_string),
+        });
+        self.survey.observations.push(rec);
+
+        if self.coords.contains_key(to) {
+            return;
+        }
+        let Some(setup) = self.setup.as_ref() else {
+            return;
+        };
+        let Some(stn) = self.position(&from) else {
+            return;
+        };
+        let azimuth = horiz_angle + setup.orientation;
+        let hd = slope_distance * zenith.sin();
+        let dz = slope_distance * zenith.cos() + ih - target_height;
+        let p = Point3::new(
+            stn.x + hd * azimuth.sin(),
+            stn.y + hd * azimuth.cos(),
+            stn.z + dz,
+        );
+        self.coordinate(to, p, code);
+    }
+
+    pub fn finish(self) -> RawSurvey {
+        self.survey
+    }
+}
+
+/// Parses a simple comma or whitespace separated raw file into survey points.
+/// The expected order is point number, northing, easting, elevation, optional description.
+fn parse_simple_raw(path: &str) -> io::Result<Vec<SurveyPoint>> {
+    let file = File::open(path)?;
+    let reader = BufReader::new(file);
+    let mut pts = Vec::new();
+    for line in reader.lines() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let fields: Vec<&str> = if line.contains(',') {
+            line.split(',').collect()
+        } else {
+            line.split_whitespace().collect()
+        };
+        if fields.len() < 4 {
+            continue;
+        }
+        let number = fields[0].parse::<u32>().ok();
+        let n: f64 = fields[1]
+            .parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let e: f64 = fields[2]
+            .parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let z: f64 = fields[3]
+            .parse()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let desc = if fields.len() > 4 {
+            Some(fields[4..].join(" "))
+        } else {
+            None
+        };
+        pts.push(SurveyPoint {
+            number,
+            point: Point3::new(e, n, z),
+            description: desc,
+            codes: Vec::new(),
+        });
+    }
+    Ok(pts)
+}
+
+fn first_line(text: &str) -> &str {
+    text.lines()
+        .map(str::trim)
+        .find(|l| !l.is_empty())
+        .unwrap_or("")
+}
+
+/// Reads a Leica GSI-8/GSI-16 file into survey points. Files that are not GSI
+/// are read as simple `number,northing,easting,elevation,description` lists.
+pub fn read_leica_raw(path: &str) -> io::Result<Vec<SurveyPoint>> {
+    let text = std::fs::read_to_string(path)?;
+    if gsi::is_gsi(first_line(&text)) {
+        return Ok(parse_leica_gsi(&text)?.points);
+    }
+    parse_simple_raw(path)
+}
+
+/// Reads a Trimble JobXML or DC file into survey points. Files in neither
+/// format are read as simple coordinate lists.
+pub fn read_trimble_raw(path: &str) -> io::Result<Vec<SurveyPoint>> {
+    let text = std::fs::read_to_string(path)?;
+    let first = first_line(&text);
+    if first.starts_with('<') {
+        return Ok(parse_trimble_jobxml(&text)?.points);
+    }
+    if sdr::is_sdr(first) {
+        return Ok(parse_trimble_dc(&text)?.points);
+    }
+    parse_simple_raw(path)
+}
+
+/// Reads a Topcon GTS-7 file into survey points. Files that are not GTS-7
+/// are read as simple coordinate lists.
+pub fn read_topcon_raw(path: &str) -> io::Result<Vec<SurveyPoint>> {
+    let text = std::fs::read_to_string(path)?;
+    if gts7::is_gts7(first_line(&text)) {
+        return Ok(parse_topcon_gts7(&text)?.points);
+    }
+    parse_simple_raw(path)
+}
+
+/// Reads a Sokkia SDR33 file into survey points. Files that are not SDR are
+/// read as simple coordinate lists.
+pub fn read_sokkia_raw(path: &str) -> io::Result<Vec<SurveyPoint>> {
+    let text = std::fs::read_to_string(path)?;
+    if sdr::is_sdr(first_line(&text)) {
+        return Ok(parse_sokkia_sdr(&text)?.points);
+    }
+    parse_simple_raw(path)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub fn assert_close(a: Point3, b: Point3) {
+        assert!(
+            (a.x - b.x).abs() < 1e-3 && (a.y - b.y).abs() < 1e-3 && (a.z - b.z).abs() < 1e-3,
+            "{a:?} != {b:?}"
+        );
+    }
+
+    #[test]
+    fn packed_dms() {
+        let r = dms_packed_to_rad(90.3000);
+        assert!((r - 90.5_f64.to_radians()).abs() < 1e-9);
+        let r = dms_packed_to_rad(45.1530);
+        assert!((r - (45.0 + 15.0 / 60.0 + 30.0 / 3600.0_f64).to_radians()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn reducer_reduces_shot_from_backsight() {
+        let mut r = RawReducer::default();
+        r.coordinate("2", Point3::new(1000.0, 1100.0, 50.0), None);
+        r.setup("1", 1.5, Some(Point3::new(1000.0, 1000.0, 50.0)));
+        r.backsight("2", None, Some(0.0));
+        r.observe(
+            "3",
+            90f64.to_radians(),
+            90f64.to_radians(),
+            10.0,
+            1.5,
+            Some("TREE"),
+        );
+        let survey = r.finish();
+        assert_close(
+            survey.point("3").unwrap().point,
+            Point3::new(1010.0, 1000.0, 50.0),
+        );
+        assert_eq!(
+            survey.point("3").unwrap().description.as_deref(),
+            Some("TREE")
+        );
+        assert_eq!(survey.observations.len(), 2);
+    }
+}
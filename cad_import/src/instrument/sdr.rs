@@ -0,0 +1,272 @@
+//! Sokkia SDR33 and Trimble DC readers.
+//!
+//! Both formats use fixed width records starting with a two digit record type
+//! and a two character derivation code (e.g. `09F1`). SDR33 uses 4 character
+//! point ids and 10 character values while the Trimble DC format widens every
+//! field to 16 characters. Angles are decimal degrees.
+//!
+//! | Record | Fields                                         |
+//! |--------|------------------------------------------------|
+//! | `02`   | station, northing, easting, elevation, IH, code |
+//! | `03`   | target height                                  |
+//! | `07`   | station, backsight, azimuth, circle reading     |
+//! | `08`   | point, northing, easting, elevation, code       |
+//! | `09`   | from, to, slope distance, zenith, Hz, code      |
+
+use super::{parse_num, RawReducer, RawSurvey};
+use std::io;
+use survey_cad::geometry::Point3;
+
+#[derive(Debug, Clone, Copy)]
+struct Layout {
+    id: usize,
+    value: usize,
+}
+
+const SDR33: Layout = Layout { id: 4, value: 10 };
+const DC: Layout = Layout { id: 16, value: 16 };
+
+/// Returns `true` when `line` looks like an SDR/DC record.
+pub fn is_sdr(line: &str) -> bool {
+    let b = line.as_bytes();
+    b.len() >= 4
+        && b[0].is_ascii_digit()
+        && b[1].is_ascii_digit()
+        && b[2].is_ascii_alphanumeric()
+        && b[3].is_ascii_alphanumeric()
+        && !b[2].is_ascii_digit()
+}
+
+/// Sequential reader over the fixed width fields of a record.
+struct Fields<'a> {
+    line: &'a str,
+    pos: usize,
+}
+
+impl<'a> Fields<'a> {
+    fn new(line: &'a str) -> Self {
+        Self { line, pos: 4 }
+    }
+
+    fn take(&mut self, width: usize) -> &'a str {
+        let start = self.pos.min(self.line.len());
+        let end = (self.pos + width).min(self.line.len());
+        self.pos += width;
+        self.line.get(start..end).unwrap_or("").trim()
+    }
+
+    fn rest(&mut self) -> &'a str {
+        let start = self.pos.min(self.line.len());
+        self.pos = self.line.len();
+        self.line.get(start..).unwrap_or("").trim()
+    }
+
+    fn number(&mut self, width: usize) -> io::Result<Option<f64>> {
+        let s = self.take(width);
+        if s.is_empty() {
+            Ok(None)
+        } else {
+            parse_num(s).map(Some)
+        }
+    }
+}
+
+fn parse(text: &str, layout: Layout) -> io::Result<RawSurvey> {
+    let mut r = RawReducer::default();
+    let mut target_height = 0.0;
+    let with_line =
+        |no: usize, e: io::Error| io::Error::new(e.kind(), format!("line {}: {e}", no + 1));
+
+    for (no, line) in text.lines().enumerate() {
+        let line = line.trim_end();
+        if line.len() < 4 {
+            continue;
+        }
+        let mut f = Fields::new(line);
+        let res: io::Result<()> = (|| {
+            match &line[..2] {
+                "02" => {
+                    let stn = f.take(layout.id);
+                    let n = f.number(layout.value)?;
+                    let e = f.number(layout.value)?;
+                    let z = f.number(layout.value)?;
+                    let ih = f.number(layout.value)?.unwrap_or(0.0);
+                    let pos = match (n, e) {
+                        (Some(n), Some(e)) => Some(Point3::new(e, n, z.unwrap_or(0.0))),
+                        _ => None,
+                    };
+                    r.setup(stn, ih, pos);
+                }
+                "03" => {
+                    if let Some(th) = f.number(layout.value)? {
+                        target_height = th;
+                    }
+                }
+                "07" => {
+                    let _stn = f.take(layout.id);
+                    let bs = f.take(layout.id);
+                    let az = f.number(layout.value)?.map(f64::to_radians);
+                    let circle = f.number(layout.value)?.map(f64::to_radians);
+                    r.backsight(bs, az, circle);
+                }
+                "08" => {
+                    let pt = f.take(layout.id);
+                    let n = f.number(layout.value)?.unwrap_or(0.0);
+                    let e = f.number(layout.value)?.unwrap_or(0.0);
+                    let z = f.number(layout.value)?.unwrap_or(0.0);
+                    let code = f.rest();
+                    r.coordinate(pt, Point3::new(e, n, z), Some(code));
+                }
+                "09" => {
+                    let _from = f.take(layout.id);
+                    let to = f.take(layout.id);
+                    let sd = f.number(layout.value)?.unwrap_or(0.0);
+                    let va = f.number(layout.value)?.unwrap_or(90.0).to_radians();
+                    let ha = f.number(layout.value)?.unwrap_or(0.0).to_radians();
+                    let code = f.rest();
+                    r.observe(to, ha, va, sd, target_height, Some(code));
+                }
+                _ => {}
+            }
+            Ok(())
+        })();
+        res.map_err(|e| with_line(no, e))?;
+    }
+    Ok(r.finish())
+}
+
+/// Parses the contents of a Sokkia SDR33 file.
+pub fn parse_sokkia_sdr(text: &str) -> io::Result<RawSurvey> {
+    parse(text, SDR33)
+}
+
+/// Parses the contents of a Trimble DC file.
+pub fn parse_trimble_dc(text: &str) -> io::Result<RawSurvey> {
+    parse(text, DC)
+}
+
+/// Reads a Sokkia SDR33 file.
+pub fn read_sokkia_sdr(path: &str) -> io::Result<RawSurvey> {
+    parse_sokkia_sdr(&std::fs::read_to_string(path)?)
+}
+
+/// Reads a Trimble DC file.
+pub fn read_trimble_dc(path: &str) -> io::Result<RawSurvey> {
+    parse_trimble_dc(&std::fs::read_to_string(path)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::tests::assert_close;
+    use super::*;
+    use survey_cad::surveying::ObservationData;
+
+    fn record(
+        kind: &str,
+        id: usize,
+        value: usize,
+        ids: &[&str],
+        values: &[&str],
+        code: &str,
+    ) -> String {
+        let mut s = kind.to_string();
+        for i in ids {
+            s.push_str(&format!("{i:<id$}"));
+        }
+        for v in values {
+            s.push_str(&format!("{v:>value$}"));
+        }
+        s.push_str(code);
+        s
+    }
+
+    fn sample(id: usize, value: usize) -> String {
+        [
+            "00NMSDR33 V04-04.02    01-Jan-24 00:00 113111".to_string(),
+            record(
+                "02TP",
+                id,
+                value,
+                &["1"],
+                &["5000.000", "1000.000", "100.000", "1.550"],
+                "STN",
+            ),
+            record(
+                "08KI",
+                id,
+                value,
+                &["2"],
+                &["5100.000", "1000.000", "101.000"],
+                "CP",
+            ),
+            record("07TP", id, value, &["1", "2"], &["0.0000", "0.0000"], ""),
+            "03NM     1.300".to_string(),
+            record(
+                "09F1",
+                id,
+                value,
+                &["1", "3"],
+                &["25.000", "90.0000", "270.0000"],
+                "FH",
+            ),
+        ]
+        .join("\n")
+    }
+
+    #[test]
+    fn sdr33_records() {
+        let s = parse_sokkia_sdr(&sample(4, 10)).unwrap();
+        assert_close(
+            s.point("1").unwrap().point,
+            Point3::new(1000.0, 5000.0, 100.0),
+        );
+        assert_close(
+            s.point("2").unwrap().point,
+            Point3::new(1000.0, 5100.0, 101.0),
+        );
+        // 270 degrees from north, IH 1.55 and HT 1.30
+        let p = s.point("3").unwrap();
+        assert_close(p.point, Point3::new(975.0, 5000.0, 100.25));
+        assert_eq!(p.codes, vec!["FH".to_string()]);
+        assert_eq!(s.observations.len(), 2);
+        match &s.observations[0].data {
+            ObservationData::Setup {
+                backsight,
+                backsight_azimuth,
+                ..
+            } => {
+                assert_eq!(backsight.as_deref(), Some("2"));
+                assert_eq!(*backsight_azimuth, Some(0.0));
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+
+    #[test]
+    fn trimble_dc_records() {
+        let s = parse_trimble_dc(&sample(16, 16)).unwrap();
+        assert_close(
+            s.point("3").unwrap().point,
+            Point3::new(975.0, 5000.0, 100.25),
+        );
+        match &s.observations[1].data {
+            ObservationData::TotalStation {
+                instrument_height,
+                target_height,
+                slope_distance,
+                ..
+            } => {
+                assert!((instrument_height - 1.55).abs() < 1e-9);
+                assert!((target_height - 1.3).abs() < 1e-9);
+                assert!((slope_distance - 25.0).abs() < 1e-9);
+            }
+            other => panic!("unexpected {other:?}"),
+        }
+    }
+
+    #[test]
+    fn reports_line_of_bad_value() {
+        let err = parse_sokkia_sdr("08KI2   abc").unwrap_err();
+        assert!(err.to_string().contains("line 1"));
+    }
+}
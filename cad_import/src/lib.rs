@@ -36,6 +36,7 @@ pub fn read_points_dxf(path: &str) -> io::Result<Vec<Point>> {
         .into_iter()
         .filter_map(|e| match e {
             DxfEntity::Point { point, .. } => Some(point),
+            DxfEntity::Point3D { point, .. } => Some(Point::new(point.x, point.y)),
             _ => None,
         })
         .collect())
@@ -320,4 +321,19 @@ mod tests {
             std::fs::remove_file(path).ok();
         }
     }
+
+    #[test]
+    fn raw_readers_detect_instrument_formats() {
+        let path = std::env::temp_dir().join("leica_detect.gsi");
+        std::fs::write(
+            &path,
+            "110001+00000007 81..00+00001000 82..00+00002000 83..00+00000300\n",
+        )
+        .unwrap();
+        let pts = instrument::read_leica_raw(path.to_str().unwrap()).unwrap();
+        assert_eq!(pts.len(), 1);
+        assert_eq!(pts[0].number, Some(7));
+        assert_eq!(pts[0].point, Point3::new(1.0, 2.0, 0.3));
+        std::fs::remove_file(path).ok();
+    }
 }
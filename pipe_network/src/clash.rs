@@ -0,0 +1,435 @@
+//! Clearance checks between networks, corridor subgrades and surfaces.
+
+use std::fs::File;
+use std::io::{self, Write};
+
+use serde::{Deserialize, Serialize};
+use survey_cad::corridor::Corridor;
+use survey_cad::dtm::Tin;
+use survey_cad::geometry::{Point, Point3};
+use survey_cad::io::dxf::DxfProperties;
+use survey_cad::io::DxfEntity;
+
+use crate::model::network_model;
+use crate::Network;
+
+/// Spacing of surface samples along a pipe (m).
+const SURFACE_SAMPLE_SPACING: f64 = 1.0;
+
+/// Layer used for clash markers.
+pub const CLASH_LAYER: &str = "CLASH";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ClashKind {
+    /// Between pipes or structures of two networks.
+    Network,
+    /// Between a pipe and a surface.
+    Surface,
+}
+
+/// Clearance shortfall between `item` and `other`. Items are named
+/// `network/id`; negative clearances are overlaps.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Clash {
+    pub kind: ClashKind,
+    pub item: String,
+    pub other: String,
+    /// Midpoint of the closest approach.
+    pub location: Point3,
+    pub required: f64,
+    pub actual: f64,
+}
+
+/// Pipe as a segment swept by a sphere of `radius`, or structure as a
+/// vertical cylinder of `radius` from `a` up to `b`.
+struct Body {
+    label: String,
+    a: Point3,
+    b: Point3,
+    radius: f64,
+    cylinder: bool,
+}
+
+fn bodies(name: &str, net: &Network) -> Vec<Body> {
+    let model = network_model(net);
+    let pipes = model.pipes.into_iter().map(|p| Body {
+        label: format!("{name}/{}", p.id),
+        a: p.start,
+        b: p.end,
+        radius: p.outer_radius,
+        cylinder: false,
+    });
+    let structures = model.structures.into_iter().map(|s| Body {
+        label: format!("{name}/{}", s.id),
+        a: s.base,
+        b: Point3::new(s.base.x, s.base.y, s.base.z + s.height),
+        radius: s.outer_radius,
+        cylinder: true,
+    });
+    pipes.chain(structures).collect()
+}
+
+fn sub(a: Point3, b: Point3) -> [f64; 3] {
+    [a.x - b.x, a.y - b.y, a.z - b.z]
+}
+
+fn dot(a: [f64; 3], b: [f64; 3]) -> f64 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn lerp(a: Point3, b: Point3, t: f64) -> Point3 {
+    Point3::new(
+        a.x + (b.x - a.x) * t,
+        a.y + (b.y - a.y) * t,
+        a.z + (b.z - a.z) * t,
+    )
+}
+
+/// Closest points between segments `p1`-`q1` and `p2`-`q2`.
+fn closest_points(p1: Point3, q1: Point3, p2: Point3, q2: Point3) -> (Point3, Point3) {
+    let (d1, d2, r) = (sub(q1, p1), sub(q2, p2), sub(p1, p2));
+    let (a, e, f) = (dot(d1, d1), dot(d2, d2), dot(d2, r));
+    let (s, t) = if a <= f64::EPSILON && e <= f64::EPSILON {
+        (0.0, 0.0)
+    } else if a <= f64::EPSILON {
+        (0.0, (f / e).clamp(0.0, 1.0))
+    } else {
+        let c = dot(d1, r);
+        if e <= f64::EPSILON {
+            ((-c / a).clamp(0.0, 1.0), 0.0)
+        } else {
+            let b = dot(d1, d2);
+            let denom = a * e - b * b;
+            let mut s = if denom > f64::EPSILON {
+                ((b * f - c * e) / denom).clamp(0.0, 1.0)
+            } else {
+                0.0
+            };
+            let mut t = (b * s + f) / e;
+            if t < 0.0 {
+                t = 0.0;
+                s = (-c / a).clamp(0.0, 1.0);
+            } else if t > 1.0 {
+                t = 1.0;
+                s = ((b - c) / a).clamp(0.0, 1.0);
+            }
+            (s, t)
+        }
+    };
+    (lerp(p1, q1, s), lerp(p2, q2, t))
+}
+
+/// Signed distance from `p` to a vertical cylinder and the closest point on
+/// it, with negative distances inside.
+fn cylinder_distance(p: Point3, cyl: &Body) -> (f64, Point3) {
+    let (dx, dy) = (p.x - cyl.a.x, p.y - cyl.a.y);
+    let d = dx.hypot(dy);
+    let radial = d - cyl.radius;
+    let axial = (cyl.a.z - p.z).max(p.z - cyl.b.z);
+    let scale = if radial > 0.0 { cyl.radius / d } else { 1.0 };
+    let closest = Point3::new(
+        cyl.a.x + dx * scale,
+        cyl.a.y + dy * scale,
+        p.z.max(cyl.a.z).min(cyl.b.z),
+    );
+    let distance = if radial <= 0.0 && axial <= 0.0 {
+        radial.max(axial)
+    } else {
+        radial.max(0.0).hypot(axial.max(0.0))
+    };
+    (distance, closest)
+}
+
+/// Outside to outside distance between two bodies, negative where they
+/// overlap, and the midpoint of the closest approach.
+fn separation(x: &Body, y: &Body) -> (f64, Point3) {
+    match (x.cylinder, y.cylinder) {
+        (false, false) => {
+            let (p, q) = closest_points(x.a, x.b, y.a, y.b);
+            let d = sub(p, q);
+            (dot(d, d).sqrt() - x.radius - y.radius, lerp(p, q, 0.5))
+        }
+        (true, true) => {
+            let radial = (x.a.x - y.a.x).hypot(x.a.y - y.a.y) - x.radius - y.radius;
+            let axial = (x.a.z - y.b.z).max(y.a.z - x.b.z);
+            let distance = if radial <= 0.0 && axial <= 0.0 {
+                radial.max(axial)
+            } else {
+                radial.max(0.0).hypot(axial.max(0.0))
+            };
+            let (lo, hi) = (x.a.z.max(y.a.z), x.b.z.min(y.b.z));
+            let mid = lerp(x.a, y.a, 0.5);
+            (distance, Point3::new(mid.x, mid.y, (lo + hi) / 2.0))
+        }
+        (true, false) => separation(y, x),
+        (false, true) => {
+            // the distance to a convex solid is convex along the pipe axis,
+            // so a golden section search finds its minimum
+            let at = |t: f64| cylinder_distance(lerp(x.a, x.b, t), y).0;
+            let (mut lo, mut hi) = (0.0, 1.0);
+            let ratio = (5f64.sqrt() - 1.0) / 2.0;
+            for _ in 0..80 {
+                let (t1, t2) = (hi - ratio * (hi - lo), lo + ratio * (hi - lo));
+                if at(t1) <= at(t2) {
+                    hi = t2;
+                } else {
+                    lo = t1;
+                }
+            }
+            let p = lerp(x.a, x.b, (lo + hi) / 2.0);
+            let (distance, q) = cylinder_distance(p, y);
+            (distance - x.radius, lerp(p, q, 0.5))
+        }
+    }
+}
+
+/// Checks every pipe and structure against those of the other networks and
+/// reports pairs closer than `clearance` outside to outside.
+pub fn network_clashes(networks: &[(&str, &Network)], clearance: f64) -> Vec<Clash> {
+    let bodies: Vec<Vec<Body>> = networks
+        .iter()
+        .map(|(name, net)| bodies(name, net))
+        .collect();
+    let mut clashes = Vec::new();
+    for (i, first) in bodies.iter().enumerate() {
+        for second in &bodies[i + 1..] {
+            for x in first {
+                for y in second {
+                    let (actual, location) = separation(x, y);
+                    if actual < clearance {
+                        clashes.push(Clash {
+                            kind: ClashKind::Network,
+                            item: x.label.clone(),
+                            other: y.label.clone(),
+                            location,
+                            required: clearance,
+                            actual,
+                        });
+                    }
+                }
+            }
+        }
+    }
+    clashes
+}
+
+/// Checks pipes against a surface sampled along their axes and reports the
+/// point of least cover of each pipe with less than `min_cover` from its
+/// crown up to the surface. Cover is negative where the pipe is above the
+/// surface.
+pub fn surface_clashes(
+    networks: &[(&str, &Network)],
+    surface: &Tin,
+    surface_name: &str,
+    min_cover: f64,
+) -> Vec<Clash> {
+    let mut clashes = Vec::new();
+    for (name, net) in networks {
+        for pipe in network_model(net).pipes {
+            let d = sub(pipe.end, pipe.start);
+            let n = ((d[0].hypot(d[1]) / SURFACE_SAMPLE_SPACING).ceil() as usize).max(1);
+            let worst = (0..=n)
+                .filter_map(|i| {
+                    let p = lerp(pipe.start, pipe.end, i as f64 / n as f64);
+                    let z = surface.elevation_at(p.x, p.y)?;
+                    Some((p, z, z - (p.z + pipe.outer_radius)))
+                })
+                .min_by(|a, b| a.2.total_cmp(&b.2));
+            if let Some((p, z, actual)) = worst {
+                if actual < min_cover {
+                    clashes.push(Clash {
+                        kind: ClashKind::Surface,
+                        item: format!("{name}/{}", pipe.id),
+                        other: surface_name.to_string(),
+                        location: Point3::new(p.x, p.y, z),
+                        required: min_cover,
+                        actual,
+                    });
+                }
+            }
+        }
+    }
+    clashes
+}
+
+/// Checks pipe cover below a corridor subgrade, taken as the design
+/// surface lowered by `pavement_depth`.
+pub fn corridor_clashes(
+    networks: &[(&str, &Network)],
+    corridor: &Corridor,
+    pavement_depth: f64,
+    min_cover: f64,
+) -> Vec<Clash> {
+    let mut subgrade = corridor.design_surface().clone();
+    for v in &mut subgrade.vertices {
+        v.z -= pavement_depth;
+    }
+    surface_clashes(networks, &subgrade, "subgrade", min_cover)
+}
+
+/// Writes clashes to a CSV file.
+pub fn write_clash_csv(path: &str, clashes: &[Clash]) -> io::Result<()> {
+    let mut file = File::create(path)?;
+    writeln!(file, "kind,item,other,x,y,z,required,actual")?;
+    for c in clashes {
+        writeln!(
+            file,
+            "{:?},{},{},{},{},{},{},{}",
+            c.kind, c.item, c.other, c.location.x, c.location.y, c.location.z, c.required, c.actual
+        )?;
+    }
+    Ok(())
+}
+
+/// Circle markers of the given radius with an `item/other` label at each
+/// clash, on [`CLASH_LAYER`].
+pub fn clash_markers(clashes: &[Clash], radius: f64) -> Vec<DxfEntity> {
+    let mut entities = Vec::new();
+    for c in clashes {
+        entities.push(DxfEntity::Circle {
+            center: c.location,
+            radius,
+            props: DxfProperties::on_layer(CLASH_LAYER),
+        });
+        entities.push(DxfEntity::Text {
+            position: Point::new(c.location.x + radius, c.location.y + radius),
+            height: radius,
+            value: format!("{} x {} ({:.2})", c.item, c.other, c.actual),
+            layer: Some(CLASH_LAYER.to_string()),
+        });
+    }
+    entities
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Pipe, Structure, DEFAULT_MANNING_N};
+
+    fn network(prefix: &str, a: (f64, f64), b: (f64, f64), invert: f64) -> Network {
+        let structure = |id: &str, (x, y): (f64, f64)| Structure {
+            id: format!("{prefix}{id}"),
+            x,
+            y,
+            z: 12.0,
+            ..Default::default()
+        };
+        Network {
+            structures: vec![structure("1", a), structure("2", b)],
+            pipes: vec![Pipe {
+                id: format!("{prefix}P"),
+                from: format!("{prefix}1"),
+                to: format!("{prefix}2"),
+                diameter: 0.3,
+                c: 120.0,
+                start_invert: invert,
+                end_invert: invert,
+                design_flow: 0.0,
+                manning_n: DEFAULT_MANNING_N,
+            }],
+        }
+    }
+
+    #[test]
+    fn crossing_pipes_and_surface() {
+        let storm = network("S", (0.0, 0.0), (40.0, 0.0), 10.0);
+        let gas = network("G", (20.0, -20.0), (20.0, 20.0), 10.6);
+        let nets = [("storm", &storm), ("gas", &gas)];
+
+        let clashes = network_clashes(&nets, 0.3);
+        assert_eq!(clashes.len(), 1);
+        let c = &clashes[0];
+        assert_eq!((c.item.as_str(), c.other.as_str()), ("storm/SP", "gas/GP"));
+        // Centres 0.6 apart less two outside radii of 0.2.
+        assert!((c.actual - 0.2).abs() < 1e-9);
+        assert!((c.location.x - 20.0).abs() < 1e-9 && c.location.y.abs() < 1e-9);
+        assert!(network_clashes(&nets, 0.1).is_empty());
+
+        let surface = Tin::from_points(vec![
+            Point3::new(-10.0, -30.0, 11.0),
+            Point3::new(50.0, -30.0, 11.0),
+            Point3::new(50.0, 30.0, 11.0),
+            Point3::new(-10.0, 30.0, 11.0),
+        ]);
+        let clashes = surface_clashes(&nets, &surface, "subgrade", 0.5);
+        assert_eq!(clashes.len(), 1);
+        assert_eq!(clashes[0].item, "gas/GP");
+        assert!((clashes[0].actual - 0.05).abs() < 1e-9);
+
+        let markers = clash_markers(&clashes, 0.5);
+        assert_eq!(markers.len(), 2);
+        let dir = tempfile::tempdir().unwrap();
+        let csv = dir.path().join("clashes.csv");
+        write_clash_csv(csv.to_str().unwrap(), &clashes).unwrap();
+        let text = std::fs::read_to_string(&csv).unwrap();
+        assert!(text
+            .lines()
+            .nth(1)
+            .unwrap()
+            .starts_with("Surface,gas/GP,subgrade"));
+    }
+
+    #[test]
+    fn pipe_rising_through_surface() {
+        let surface = Tin::from_points(vec![
+            Point3::new(-10.0, -30.0, 11.0),
+            Point3::new(50.0, -30.0, 11.0),
+            Point3::new(50.0, 30.0, 11.0),
+            Point3::new(-10.0, 30.0, 11.0),
+        ]);
+        // buried at the start, 1 m out of the ground at the end
+        let mut outfall = network("O", (0.0, 0.0), (40.0, 0.0), 10.0);
+        outfall.pipes[0].end_invert = 12.0;
+        let body = &network_model(&outfall).pipes[0];
+        let clashes = surface_clashes(&[("outfall", &outfall)], &surface, "existing", 0.5);
+        assert_eq!(clashes.len(), 1);
+        let expected = 11.0 - (body.end.z + body.outer_radius);
+        assert!(expected < -1.0);
+        assert!((clashes[0].actual - expected).abs() < 1e-9);
+        assert!((clashes[0].location.x - body.end.x).abs() < 1e-9);
+
+        // wholly above the surface is still a clash
+        let mut bridge = network("B", (0.0, 0.0), (40.0, 0.0), 12.0);
+        bridge.pipes[0].end_invert = 12.0;
+        let clashes = surface_clashes(&[("bridge", &bridge)], &surface, "existing", 0.0);
+        assert_eq!(clashes.len(), 1);
+        assert!(clashes[0].actual < -1.0);
+    }
+
+    #[test]
+    fn structures_are_finite_cylinders() {
+        let storm = network("S", (0.0, 0.0), (40.0, 0.0), 10.0);
+        let model = network_model(&storm);
+        let manhole = &model.structures[0];
+        let rim = manhole.base.z + manhole.height;
+
+        // a gas main crossing 0.2 m over the rim, clear of the sides
+        let mut gas = network("G", (0.0, -20.0), (0.0, 20.0), 0.0);
+        for s in &mut gas.structures {
+            s.z = 15.0;
+        }
+        let body = &network_model(&gas).pipes[0];
+        let (inner, outer) = (gas.pipes[0].diameter / 2.0, body.outer_radius);
+        gas.pipes[0].start_invert = rim + 0.2 + outer - inner;
+        gas.pipes[0].end_invert = gas.pipes[0].start_invert;
+        let clashes = network_clashes(&[("storm", &storm), ("gas", &gas)], 0.5);
+        assert_eq!(clashes.len(), 1);
+        assert_eq!(clashes[0].other, "gas/GP");
+        assert!((clashes[0].actual - 0.2).abs() < 1e-6);
+        // anywhere along the flat lid is equally close
+        let location = clashes[0].location;
+        assert!(location.x.abs() < 1e-6 && location.y.abs() < manhole.outer_radius + 1e-6);
+        assert!((location.z - rim - (0.2 + outer) / 2.0).abs() < 1e-6);
+
+        // a neighbouring manhole 0.1 m clear of the first, side by side
+        let gap = 2.0 * manhole.outer_radius + 0.1;
+        let water = network("W", (-gap, 0.0), (-gap, -40.0), 10.0);
+        let clashes = network_clashes(&[("storm", &storm), ("water", &water)], 0.3);
+        assert_eq!(clashes.len(), 1);
+        assert_eq!(
+            (clashes[0].item.as_str(), clashes[0].other.as_str()),
+            ("storm/S1", "water/W1")
+        );
+        assert!((clashes[0].actual - 0.1).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,603 @@
+//! Gravity sewer hydraulics and design flows.
+//!
+//! Capacities use Manning's equation for full and part-full circular pipes.
+//! Storm design flows use the rational method with rainfall intensities
+//! from an IDF curve at the time of concentration, which is carried down
+//! the network by adding pipe travel times. Sanitary design flows apply a
+//! peaking factor to the accumulated population. All units are SI: metres,
+//! seconds, hectares and millimetres per hour.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{Network, Pipe};
+
+/// Depth ratio at which a circular pipe carries its largest flow.
+pub const MAX_FLOW_DEPTH_RATIO: f64 = 0.938;
+
+/// Default Manning roughness, typical of concrete and PVC pipe.
+pub const DEFAULT_MANNING_N: f64 = 0.013;
+
+/// Flow area and wetted perimeter of a circular pipe flowing at `depth`.
+fn section(diameter: f64, depth: f64) -> (f64, f64) {
+    let y = depth.clamp(0.0, diameter);
+    let theta = 2.0 * (1.0 - 2.0 * y / diameter).clamp(-1.0, 1.0).acos();
+    let area = diameter * diameter / 8.0 * (theta - theta.sin());
+    (area, diameter * theta / 2.0)
+}
+
+/// Manning flow (m^3/s) of a circular pipe flowing full.
+pub fn manning_full_flow(diameter: f64, slope: f64, n: f64) -> f64 {
+    manning_partial_flow(diameter, slope, n, diameter).flow
+}
+
+/// Manning velocity (m/s) of a circular pipe flowing full.
+pub fn manning_full_velocity(diameter: f64, slope: f64, n: f64) -> f64 {
+    manning_partial_flow(diameter, slope, n, diameter).velocity
+}
+
+/// Hydraulic state of a part-full circular pipe.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PartialFlow {
+    pub depth: f64,
+    pub area: f64,
+    pub wetted_perimeter: f64,
+    pub hydraulic_radius: f64,
+    pub flow: f64,
+    pub velocity: f64,
+}
+
+/// Manning flow and velocity of a circular pipe flowing at `depth`.
+pub fn manning_partial_flow(diameter: f64, slope: f64, n: f64, depth: f64) -> PartialFlow {
+    let (area, wetted_perimeter) = if diameter > 0.0 {
+        section(diameter, depth)
+    } else {
+        (0.0, 0.0)
+    };
+    let hydraulic_radius = if wetted_perimeter > 0.0 {
+        area / wetted_perimeter
+    } else {
+        0.0
+    };
+    let velocity = if n > 0.0 && slope > 0.0 {
+        hydraulic_radius.powf(2.0 / 3.0) * slope.sqrt() / n
+    } else {
+        0.0
+    };
+    PartialFlow {
+        depth: depth.clamp(0.0, diameter.max(0.0)),
+        area,
+        wetted_perimeter,
+        hydraulic_radius,
+        flow: area * velocity,
+        velocity,
+    }
+}
+
+/// Normal depth (m) carrying `flow`, or `None` when the flow exceeds the
+/// largest part-full capacity and the pipe surcharges.
+pub fn normal_depth(flow: f64, diameter: f64, slope: f64, n: f64) -> Option<f64> {
+    if flow <= 0.0 {
+        return Some(0.0);
+    }
+    let (mut lo, mut hi) = (0.0, MAX_FLOW_DEPTH_RATIO * diameter);
+    if manning_partial_flow(diameter, slope, n, hi).flow < flow {
+        return None;
+    }
+    for _ in 0..60 {
+        let mid = 0.5 * (lo + hi);
+        if manning_partial_flow(diameter, slope, n, mid).flow < flow {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    Some(hi)
+}
+
+/// Velocity (m/s) at normal depth, or the full-flow velocity when the pipe
+/// surcharges.
+pub fn gravity_velocity(flow: f64, diameter: f64, slope: f64, n: f64) -> f64 {
+    match normal_depth(flow, diameter, slope, n) {
+        Some(d) if d > 0.0 => manning_partial_flow(diameter, slope, n, d).velocity,
+        Some(_) => 0.0,
+        None => manning_full_velocity(diameter, slope, n),
+    }
+}
+
+/// Rational method peak runoff (m^3/s) for a runoff coefficient, rainfall
+/// intensity in mm/h and area in hectares.
+pub fn rational_flow(runoff_coefficient: f64, intensity: f64, area: f64) -> f64 {
+    runoff_coefficient * intensity * area / 360.0
+}
+
+/// Intensity-duration-frequency curve `i = a / (t + b)^c` with `t` in
+/// minutes and `i` in mm/h.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct IdfCurve {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+}
+
+impl IdfCurve {
+    /// Rainfall intensity (mm/h) for a storm lasting `duration` minutes.
+    pub fn intensity(&self, duration: f64) -> f64 {
+        self.a / (duration.max(0.0) + self.b).powf(self.c)
+    }
+}
+
+/// Area draining to a structure.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Catchment {
+    /// Structure receiving the runoff.
+    pub structure: String,
+    /// Area in hectares.
+    pub area: f64,
+    pub runoff_coefficient: f64,
+    /// Overland flow time to the inlet (min).
+    pub inlet_time: f64,
+}
+
+/// Sanitary peaking factor applied to average dry weather flow.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum PeakingFactor {
+    /// `1 + 14 / (4 + sqrt(P / 1000))`
+    Harmon,
+    /// `5 / (P / 1000)^0.2`, limited to 5
+    Babbitt,
+    Constant(f64),
+}
+
+impl PeakingFactor {
+    pub fn factor(&self, population: f64) -> f64 {
+        let thousands = population.max(0.0) / 1000.0;
+        match self {
+            PeakingFactor::Harmon => 1.0 + 14.0 / (4.0 + thousands.sqrt()),
+            PeakingFactor::Babbitt if thousands > 0.0 => (5.0 / thousands.powf(0.2)).min(5.0),
+            PeakingFactor::Babbitt => 5.0,
+            PeakingFactor::Constant(f) => *f,
+        }
+    }
+}
+
+/// Sanitary load entering at a structure.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SanitaryLoad {
+    pub structure: String,
+    pub population: f64,
+    /// Infiltration and inflow added without peaking (m^3/s).
+    pub infiltration: f64,
+}
+
+/// Storm design flow of one pipe.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StormFlow {
+    pub pipe: String,
+    /// Contributing area (ha).
+    pub area: f64,
+    /// Sum of runoff coefficient times area (ha).
+    pub ca: f64,
+    /// Time of concentration at the pipe inlet (min).
+    pub time_of_concentration: f64,
+    /// Intensity at the time of concentration (mm/h).
+    pub intensity: f64,
+    pub flow: f64,
+    /// Time to flow through the pipe (min).
+    pub travel_time: f64,
+}
+
+/// Sanitary design flow of one pipe.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SanitaryFlow {
+    pub pipe: String,
+    pub population: f64,
+    pub peaking_factor: f64,
+    pub average_flow: f64,
+    pub infiltration: f64,
+    pub flow: f64,
+}
+
+impl Network {
+    /// Horizontal length of a pipe between its structures.
+    pub fn pipe_length(&self, pipe: &Pipe) -> Option<f64> {
+        let find = |id: &str| self.structures.iter().find(|s| s.id == id);
+        let (a, b) = (find(&pipe.from)?, find(&pipe.to)?);
+        Some((b.x - a.x).hypot(b.y - a.y))
+    }
+
+    /// Pipe indices ordered so every pipe comes after the pipes draining
+    /// into its upstream structure. Pipes in loops come last.
+    pub fn upstream_order(&self) -> Vec<usize> {
+        let mut incoming: HashMap<&str, usize> = HashMap::new();
+        for p in &self.pipes {
+            *incoming.entry(p.to.as_str()).or_default() += 1;
+        }
+        let mut order = Vec::with_capacity(self.pipes.len());
+        let mut done = vec![false; self.pipes.len()];
+        let mut ready: Vec<usize> = (0..self.pipes.len())
+            .filter(|&i| !incoming.contains_key(self.pipes[i].from.as_str()))
+            .collect();
+        while let Some(i) = ready.pop() {
+            if done[i] {
+                continue;
+            }
+            done[i] = true;
+            order.push(i);
+            let to = self.pipes[i].to.as_str();
+            let left = incoming.get_mut(to).unwrap();
+            *left -= 1;
+            if *left == 0 {
+                ready.extend((0..self.pipes.len()).filter(|&j| self.pipes[j].from == to));
+            }
+        }
+        order.extend((0..self.pipes.len()).filter(|&i| !done[i]));
+        order
+    }
+}
+
+/// Sets each pipe's `design_flow` by the rational method and returns the
+/// flow computation per pipe in network order.
+///
+/// Runoff accumulates downstream. The time of concentration at a structure
+/// is the longest of its catchments' inlet times and the arrival times
+/// through the pipes draining into it, where each pipe's travel time uses
+/// the velocity at its design flow. Where a structure has several outlet
+/// pipes each carries the full flow.
+pub fn rational_design_flows(
+    net: &mut Network,
+    catchments: &[Catchment],
+    idf: &IdfCurve,
+) -> Vec<StormFlow> {
+    // (area, CA, time of concentration) arriving at each structure
+    let mut arriving: HashMap<String, (f64, f64, f64)> = HashMap::new();
+    for c in catchments {
+        let e = arriving.entry(c.structure.clone()).or_default();
+        e.0 += c.area;
+        e.1 += c.runoff_coefficient * c.area;
+        e.2 = e.2.max(c.inlet_time);
+    }
+    let mut results = Vec::new();
+    for i in net.upstream_order() {
+        let length = net.pipe_length(&net.pipes[i]).unwrap_or(0.0);
+        let pipe = &mut net.pipes[i];
+        let (area, ca, tc) = arriving.get(&pipe.from).copied().unwrap_or_default();
+        let intensity = idf.intensity(tc);
+        let flow = rational_flow(1.0, intensity, ca);
+        pipe.design_flow = flow;
+        let velocity = gravity_velocity(flow, pipe.diameter, pipe.slope(length), pipe.manning_n);
+        let travel_time = if velocity > 0.0 {
+            length / velocity / 60.0
+        } else {
+            0.0
+        };
+        let down = arriving.entry(pipe.to.clone()).or_default();
+        down.0 += area;
+        down.1 += ca;
+        down.2 = down.2.max(tc + travel_time);
+        results.push(StormFlow {
+            pipe: pipe.id.clone(),
+            area,
+            ca,
+            time_of_concentration: tc,
+            intensity,
+            flow,
+            travel_time,
+        });
+    }
+    results
+}
+
+/// Sets each pipe's `design_flow` from the accumulated sanitary loads.
+/// `per_capita` is the average dry weather flow in litres per person per
+/// day.
+pub fn sanitary_design_flows(
+    net: &mut Network,
+    loads: &[SanitaryLoad],
+    per_capita: f64,
+    peaking: PeakingFactor,
+) -> Vec<SanitaryFlow> {
+    // (population, infiltration) arriving at each structure
+    let mut arriving: HashMap<String, (f64, f64)> = HashMap::new();
+    for l in loads {
+        let e = arriving.entry(l.structure.clone()).or_default();
+        e.0 += l.population;
+        e.1 += l.infiltration;
+    }
+    let mut results = Vec::new();
+    for i in net.upstream_order() {
+        let pipe = &mut net.pipes[i];
+        let (population, infiltration) = arriving.get(&pipe.from).copied().unwrap_or_default();
+        let average_flow = population * per_capita / 1000.0 / 86_400.0;
+        let peaking_factor = peaking.factor(population);
+        let flow = average_flow * peaking_factor + infiltration;
+        pipe.design_flow = flow;
+        let down = arriving.entry(pipe.to.clone()).or_default();
+        down.0 += population;
+        down.1 += infiltration;
+        results.push(SanitaryFlow {
+            pipe: pipe.id.clone(),
+            population,
+            peaking_factor,
+            average_flow,
+            infiltration,
+            flow,
+        });
+    }
+    results
+}
+
+/// Limits used when choosing pipe sizes.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SizingCriteria {
+    /// Available internal diameters (m).
+    pub diameters: Vec<f64>,
+    /// Largest depth to diameter ratio at design flow, e.g. 0.8 for
+    /// sanitary or 1.0 for storm sewers designed flowing full.
+    pub max_depth_ratio: f64,
+    /// Self-cleansing velocity at design flow (m/s).
+    pub min_velocity: f64,
+    pub max_velocity: f64,
+}
+
+impl Default for SizingCriteria {
+    fn default() -> Self {
+        Self {
+            diameters: vec![
+                0.2, 0.25, 0.3, 0.375, 0.45, 0.525, 0.6, 0.675, 0.75, 0.9, 1.05, 1.2, 1.35, 1.5,
+                1.8, 2.1, 2.4,
+            ],
+            max_depth_ratio: 0.8,
+            min_velocity: 0.6,
+            max_velocity: 3.0,
+        }
+    }
+}
+
+/// Outcome of sizing one pipe.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SizingResult {
+    pub pipe: String,
+    pub diameter: f64,
+    pub full_capacity: f64,
+    /// Depth to diameter ratio at design flow, `None` when surcharged.
+    pub depth_ratio: Option<f64>,
+    pub velocity: f64,
+    pub meets_capacity: bool,
+    pub meets_velocity: bool,
+}
+
+/// Sizes every pipe to the smallest available diameter that carries its
+/// design flow within the depth ratio and velocity limits, never smaller
+/// than the pipes draining into it. When no size meets the velocity limits
+/// the smallest size with enough capacity is used, and when none has enough
+/// capacity the largest is used; the result records which limits are met.
+pub fn size_pipes(net: &mut Network, criteria: &SizingCriteria) -> Vec<SizingResult> {
+    let mut sizes = criteria.diameters.clone();
+    sizes.sort_by(f64::total_cmp);
+    let mut upstream_max: HashMap<String, f64> = HashMap::new();
+    let mut results = Vec::new();
+    for i in net.upstream_order() {
+        let length = net.pipe_length(&net.pipes[i]).unwrap_or(0.0);
+        let pipe = &mut net.pipes[i];
+        let slope = pipe.slope(length);
+        let minimum = upstream_max.get(&pipe.from).copied().unwrap_or(0.0);
+        let evaluate = |d: f64| {
+            let depth = normal_depth(pipe.design_flow, d, slope, pipe.manning_n);
+            let ratio = depth.map(|y| y / d);
+            let capacity = ratio.is_some_and(|r| r <= criteria.max_depth_ratio + 1e-9);
+            let velocity = gravity_velocity(pipe.design_flow, d, slope, pipe.manning_n);
+            let in_range = pipe.design_flow <= 0.0
+                || (velocity >= criteria.min_velocity && velocity <= criteria.max_velocity);
+            (d, ratio, velocity, capacity, in_range)
+        };
+        let candidates: Vec<_> = sizes
+            .iter()
+            .copied()
+            .filter(|d| *d >= minimum - 1e-9)
+            .map(evaluate)
+            .collect();
+        let chosen = candidates
+            .iter()
+            .find(|c| c.3 && c.4)
+            .or_else(|| candidates.iter().find(|c| c.3))
+            .or(candidates.last())
+            .copied()
+            .unwrap_or_else(|| evaluate(pipe.diameter.max(minimum)));
+        let (diameter, depth_ratio, velocity, meets_capacity, meets_velocity) = chosen;
+        pipe.diameter = diameter;
+        let down = upstream_max.entry(pipe.to.clone()).or_default();
+        *down = down.max(diameter);
+        results.push(SizingResult {
+            pipe: pipe.id.clone(),
+            diameter,
+            full_capacity: manning_full_flow(diameter, slope, pipe.manning_n),
+            depth_ratio,
+            velocity,
+            meets_capacity,
+            meets_velocity,
+        });
+    }
+    results
+}
+
+/// Alternates [`rational_design_flows`] and [`size_pipes`] until the sizes,
+/// and with them the travel times, stop changing.
+pub fn design_storm_network(
+    net: &mut Network,
+    catchments: &[Catchment],
+    idf: &IdfCurve,
+    criteria: &SizingCriteria,
+) -> (Vec<StormFlow>, Vec<SizingResult>) {
+    let mut flows = rational_design_flows(net, catchments, idf);
+    let mut sizing = size_pipes(net, criteria);
+    for _ in 0..10 {
+        let before: Vec<f64> = net.pipes.iter().map(|p| p.diameter).collect();
+        flows = rational_design_flows(net, catchments, idf);
+        sizing = size_pipes(net, criteria);
+        if net.pipes.iter().zip(&before).all(|(p, d)| p.diameter == *d) {
+            break;
+        }
+    }
+    (flows, sizing)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Structure;
+
+    fn structure(id: &str, x: f64) -> Structure {
+        Structure {
+            id: id.into(),
+            x,
+            y: 0.0,
+            z: 10.0,
+            ..Default::default()
+        }
+    }
+
+    fn pipe(id: &str, from: &str, to: &str, start: f64, end: f64) -> Pipe {
+        Pipe {
+            id: id.into(),
+            from: from.into(),
+            to: to.into(),
+            diameter: 0.3,
+            c: 120.0,
+            start_invert: start,
+            end_invert: end,
+            design_flow: 0.0,
+            manning_n: DEFAULT_MANNING_N,
+        }
+    }
+
+    /// Two branches A->C and B->C joining into C->D, all at 1% grade.
+    fn branched() -> Network {
+        Network {
+            structures: vec![
+                structure("A", 0.0),
+                structure("B", 0.0),
+                structure("C", 100.0),
+                structure("D", 200.0),
+            ],
+            pipes: vec![
+                pipe("CD", "C", "D", 7.0, 6.0),
+                pipe("AC", "A", "C", 8.0, 7.0),
+                pipe("BC", "B", "C", 8.0, 7.0),
+            ],
+        }
+    }
+
+    #[test]
+    fn manning_capacity() {
+        // 300 mm at 1%, n = 0.013: about 0.097 m^3/s and 1.37 m/s
+        let q = manning_full_flow(0.3, 0.01, 0.013);
+        assert!((q - 0.0969).abs() < 1e-3, "{q}");
+        assert!((manning_full_velocity(0.3, 0.01, 0.013) - 1.371).abs() < 1e-2);
+        let half = manning_partial_flow(0.3, 0.01, 0.013, 0.15);
+        assert!((half.flow - q / 2.0).abs() < 1e-9);
+        assert!((half.velocity - manning_full_velocity(0.3, 0.01, 0.013)).abs() < 1e-9);
+        let peak = manning_partial_flow(0.3, 0.01, 0.013, MAX_FLOW_DEPTH_RATIO * 0.3).flow;
+        assert!(peak > q * 1.07);
+        let y = normal_depth(0.05, 0.3, 0.01, 0.013).unwrap();
+        assert!((manning_partial_flow(0.3, 0.01, 0.013, y).flow - 0.05).abs() < 1e-9);
+        assert_eq!(normal_depth(0.2, 0.3, 0.01, 0.013), None);
+    }
+
+    #[test]
+    fn rational_method_carries_time_of_concentration() {
+        let mut net = branched();
+        let idf = IdfCurve {
+            a: 1500.0,
+            b: 10.0,
+            c: 0.8,
+        };
+        let catchments = vec![
+            Catchment {
+                structure: "A".into(),
+                area: 1.0,
+                runoff_coefficient: 0.5,
+                inlet_time: 10.0,
+            },
+            Catchment {
+                structure: "B".into(),
+                area: 2.0,
+                runoff_coefficient: 0.7,
+                inlet_time: 15.0,
+            },
+        ];
+        let flows = rational_design_flows(&mut net, &catchments, &idf);
+        assert_eq!(flows.last().unwrap().pipe, "CD");
+        let cd = flows.last().unwrap();
+        let bc = flows.iter().find(|f| f.pipe == "BC").unwrap();
+        assert!((cd.ca - 1.9).abs() < 1e-9);
+        assert!((cd.time_of_concentration - (15.0 + bc.travel_time)).abs() < 1e-9);
+        assert!((bc.flow - 0.7 * 2.0 * idf.intensity(15.0) / 360.0).abs() < 1e-12);
+        assert!((net.pipes[0].design_flow - cd.flow).abs() < 1e-12);
+    }
+
+    #[test]
+    fn sanitary_peaking_and_sizing() {
+        assert!((PeakingFactor::Harmon.factor(1000.0) - 3.8).abs() < 1e-9);
+        assert!((PeakingFactor::Babbitt.factor(1000.0) - 5.0).abs() < 1e-9);
+        let mut net = branched();
+        let loads = vec![
+            SanitaryLoad {
+                structure: "A".into(),
+                population: 4000.0,
+                infiltration: 0.002,
+            },
+            SanitaryLoad {
+                structure: "B".into(),
+                population: 12000.0,
+                infiltration: 0.004,
+            },
+        ];
+        let flows = sanitary_design_flows(&mut net, &loads, 350.0, PeakingFactor::Harmon);
+        let cd = flows.iter().find(|f| f.pipe == "CD").unwrap();
+        assert_eq!(cd.population, 16000.0);
+        assert!((cd.flow - (16000.0 * 0.35 / 86_400.0 * 2.75 + 0.006)).abs() < 1e-9);
+
+        let sizing = size_pipes(&mut net, &SizingCriteria::default());
+        for r in &sizing {
+            assert!(r.meets_capacity && r.meets_velocity, "{r:?}");
+            assert!(r.depth_ratio.unwrap() <= 0.8);
+        }
+        let size = |id: &str| net.pipes.iter().find(|p| p.id == id).unwrap().diameter;
+        assert!(size("CD") >= size("BC") && size("BC") >= size("AC"));
+        // one size down would not carry the flow within the depth limit
+        let smaller = SizingCriteria::default()
+            .diameters
+            .into_iter()
+            .rfind(|d| *d < size("BC"))
+            .unwrap();
+        assert!(normal_depth(net.pipes[2].design_flow, smaller, 0.01, 0.013)
+            .is_none_or(|y| y / smaller > 0.8));
+    }
+
+    #[test]
+    fn storm_design_converges() {
+        let mut net = branched();
+        let idf = IdfCurve {
+            a: 1500.0,
+            b: 10.0,
+            c: 0.8,
+        };
+        let catchments = vec![Catchment {
+            structure: "A".into(),
+            area: 5.0,
+            runoff_coefficient: 0.8,
+            inlet_time: 10.0,
+        }];
+        let criteria = SizingCriteria {
+            max_depth_ratio: 1.0,
+            ..Default::default()
+        };
+        let (flows, sizing) = design_storm_network(&mut net, &catchments, &idf, &criteria);
+        assert_eq!(flows.len(), 3);
+        assert!(sizing.iter().all(|s| s.meets_capacity));
+        let ac = net.pipes.iter().find(|p| p.id == "AC").unwrap();
+        assert!(ac.diameter > 0.3);
+    }
+}
@@ -0,0 +1,424 @@
+//! Structure inverts, drops and hydraulic/energy grade lines.
+//!
+//! Grades are computed upstream from the outfall tailwater. Pipes flowing
+//! part full set the water level at their upstream end to the normal depth;
+//! surcharged pipes add the full-flow Manning friction loss. Losses through
+//! structures use the K-coefficient method, `h = K V^2 / 2g`, with the
+//! velocity of the outlet pipe. Water in a structure is treated as still, so
+//! its HGL equals its EGL.
+
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use survey_cad::dtm::Tin;
+
+use crate::{gravity, Network, StructureKind};
+
+/// Acceleration due to gravity (m/s^2).
+pub const GRAVITY: f64 = 9.81;
+
+/// Typical junction loss coefficient for flow turning `deflection` degrees
+/// through a structure. Structures without inflow use the value for the
+/// beginning of a run.
+pub fn junction_k(kind: StructureKind, deflection: Option<f64>) -> f64 {
+    const MANHOLE: [(f64, f64); 5] = [
+        (0.0, 0.15),
+        (22.5, 0.45),
+        (45.0, 0.75),
+        (60.0, 0.85),
+        (90.0, 1.0),
+    ];
+    const INLET: [(f64, f64); 5] = [
+        (0.0, 0.5),
+        (22.5, 0.7),
+        (45.0, 1.1),
+        (60.0, 1.25),
+        (90.0, 1.5),
+    ];
+    let table = match kind {
+        StructureKind::Outfall => return 0.0,
+        StructureKind::Inlet => &INLET,
+        StructureKind::Manhole | StructureKind::Junction => &MANHOLE,
+    };
+    let Some(angle) = deflection else {
+        return 1.25;
+    };
+    let angle = angle.abs().min(90.0);
+    table
+        .windows(2)
+        .find(|w| angle <= w[1].0)
+        .map(|w| w[0].1 + (w[1].1 - w[0].1) * (angle - w[0].0) / (w[1].0 - w[0].0))
+        .unwrap_or(table[table.len() - 1].1)
+}
+
+/// Difference between an inflow pipe's invert and an outflow pipe's invert
+/// at a structure.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DropConnection {
+    pub structure: String,
+    pub pipe_in: String,
+    pub pipe_out: String,
+    /// Invert in minus invert out; negative values are adverse.
+    pub drop: f64,
+}
+
+impl Network {
+    /// Lowest invert of the pipes connected to a structure.
+    pub fn structure_invert(&self, id: &str) -> Option<f64> {
+        self.pipes
+            .iter()
+            .filter_map(|p| {
+                if p.from == id {
+                    Some(p.start_invert)
+                } else if p.to == id {
+                    Some(p.end_invert)
+                } else {
+                    None
+                }
+            })
+            .reduce(f64::min)
+    }
+
+    /// Elevation of the structure floor including its sump.
+    pub fn sump_elevation(&self, id: &str) -> Option<f64> {
+        let sump = self.structures.iter().find(|s| s.id == id)?.sump;
+        self.structure_invert(id).map(|inv| inv - sump)
+    }
+
+    /// Drops between every inflow and outflow pipe pair at each structure.
+    pub fn drops(&self) -> Vec<DropConnection> {
+        let mut drops = Vec::new();
+        for s in &self.structures {
+            for pipe_in in self.pipes.iter().filter(|p| p.to == s.id) {
+                for pipe_out in self.pipes.iter().filter(|p| p.from == s.id) {
+                    drops.push(DropConnection {
+                        structure: s.id.clone(),
+                        pipe_in: pipe_in.id.clone(),
+                        pipe_out: pipe_out.id.clone(),
+                        drop: pipe_in.end_invert - pipe_out.start_invert,
+                    });
+                }
+            }
+        }
+        drops
+    }
+
+    /// Sets structure rims from a surface. Returns the ids of structures
+    /// outside the surface, which keep their elevation.
+    pub fn set_rims_from_tin(&mut self, tin: &Tin) -> Vec<String> {
+        let mut missed = Vec::new();
+        for s in &mut self.structures {
+            match tin.elevation_at(s.x, s.y) {
+                Some(z) => s.z = z,
+                None => missed.push(s.id.clone()),
+            }
+        }
+        missed
+    }
+
+    /// Deflection in degrees between a structure's main inflow, the one
+    /// with the largest design flow, and the given outflow pipe.
+    fn deflection(&self, structure: &str, pipe_out: usize) -> Option<f64> {
+        let idx = self.structure_index();
+        let direction = |from: &str, to: &str| {
+            let (a, b) = (&self.structures[idx[from]], &self.structures[idx[to]]);
+            (b.y - a.y).atan2(b.x - a.x)
+        };
+        let main = self
+            .pipes
+            .iter()
+            .filter(|p| p.to == structure && idx.contains_key(p.from.as_str()))
+            .max_by(|a, b| a.design_flow.total_cmp(&b.design_flow))?;
+        let out = &self.pipes[pipe_out];
+        if !idx.contains_key(out.to.as_str()) || !idx.contains_key(structure) {
+            return Some(0.0);
+        }
+        let turn = direction(&out.from, &out.to) - direction(&main.from, &main.to);
+        Some(turn.sin().atan2(turn.cos()).to_degrees().abs())
+    }
+}
+
+/// Boundary condition and limits for [`hydraulic_grade_lines`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct GradeOptions {
+    /// Water surface elevation at the outfalls.
+    pub tailwater: f64,
+    /// Minimum cover over pipe crowns (m).
+    pub min_cover: f64,
+    /// Minimum distance from the HGL to the rim (m).
+    pub min_freeboard: f64,
+}
+
+/// Grades at both ends of a pipe.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PipeGrade {
+    pub pipe: String,
+    pub velocity: f64,
+    pub velocity_head: f64,
+    /// Full-flow Manning friction slope.
+    pub friction_slope: f64,
+    pub surcharged: bool,
+    pub hgl_up: f64,
+    pub hgl_down: f64,
+    pub egl_up: f64,
+    pub egl_down: f64,
+}
+
+/// Water level in a structure.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StructureGrade {
+    pub structure: String,
+    pub k: f64,
+    pub junction_loss: f64,
+    pub hgl: f64,
+    /// Rim elevation minus HGL.
+    pub freeboard: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ViolationKind {
+    /// Pipe crown closer to the rim than the minimum cover.
+    Cover,
+    /// HGL closer to the rim than the minimum freeboard.
+    Freeboard,
+    MinSlope,
+    MaxSlope,
+    /// Invert drop through a structure below the required drop.
+    Drop,
+    /// Pipe longer than the maximum structure spacing.
+    Spacing,
+}
+
+/// A pipe or structure failing a limit.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Violation {
+    pub id: String,
+    pub kind: ViolationKind,
+    pub value: f64,
+    pub limit: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GradeResults {
+    pub pipes: Vec<PipeGrade>,
+    pub structures: Vec<StructureGrade>,
+    pub violations: Vec<Violation>,
+}
+
+/// Computes the HGL and EGL through the network from the outfall tailwater
+/// using each pipe's `design_flow`, and flags cover and freeboard
+/// violations.
+pub fn hydraulic_grade_lines(net: &Network, options: &GradeOptions) -> GradeResults {
+    let mut levels: HashMap<&str, f64> = HashMap::new();
+    let mut structure_grades: HashMap<&str, (f64, f64, f64)> = HashMap::new();
+    let mut results = GradeResults::default();
+    let structures_by_id: HashMap<&str, &crate::Structure> =
+        net.structures.iter().map(|s| (s.id.as_str(), s)).collect();
+    for i in net.upstream_order().into_iter().rev() {
+        let pipe = &net.pipes[i];
+        let length = net.pipe_length(pipe).unwrap_or(0.0);
+        let (d, q, n) = (pipe.diameter, pipe.design_flow, pipe.manning_n);
+        let slope = pipe.slope(length);
+        let area = std::f64::consts::PI * d * d / 4.0;
+        let conveyance = area * (d / 4.0).powf(2.0 / 3.0) / n;
+        let friction_slope = if conveyance > 0.0 {
+            (q / conveyance).powi(2)
+        } else {
+            0.0
+        };
+        let depth = gravity::normal_depth(q, d, slope, n);
+        let downstream = levels
+            .get(pipe.to.as_str())
+            .copied()
+            .unwrap_or(options.tailwater);
+        let surcharged = depth.is_none() || downstream > pipe.end_invert + d;
+        let (hgl_down, hgl_up, velocity) = if surcharged {
+            let up = downstream + friction_slope * length;
+            let free = pipe.start_invert + depth.unwrap_or(d);
+            let velocity = if area > 0.0 { q / area } else { 0.0 };
+            (downstream, up.max(free), velocity)
+        } else {
+            // backwater below the crown is carried upstream as a level pool
+            let y = depth.unwrap_or(0.0);
+            let down = downstream.max(pipe.end_invert + y);
+            let velocity = gravity::gravity_velocity(q, d, slope, n);
+            (down, (pipe.start_invert + y).max(downstream), velocity)
+        };
+        let velocity_head = velocity * velocity / (2.0 * GRAVITY);
+        let egl_up = hgl_up + velocity_head;
+        let upstream = structures_by_id.get(pipe.from.as_str());
+        let k = upstream.and_then(|s| s.junction_k).unwrap_or_else(|| {
+            junction_k(
+                upstream.map(|s| s.kind).unwrap_or_default(),
+                net.deflection(&pipe.from, i),
+            )
+        });
+        let junction_loss = k * velocity_head;
+        let level = egl_up + junction_loss;
+        let entry = levels.entry(pipe.from.as_str()).or_insert(f64::MIN);
+        if level > *entry {
+            *entry = level;
+            structure_grades.insert(pipe.from.as_str(), (k, junction_loss, level));
+        }
+        for (end, invert) in [(&pipe.from, pipe.start_invert), (&pipe.to, pipe.end_invert)] {
+            if let Some(s) = structures_by_id.get(end.as_str()) {
+                let cover = s.z - (invert + d);
+                if cover < options.min_cover {
+                    results.violations.push(Violation {
+                        id: pipe.id.clone(),
+                        kind: ViolationKind::Cover,
+                        value: cover,
+                        limit: options.min_cover,
+                    });
+                }
+            }
+        }
+        results.pipes.push(PipeGrade {
+            pipe: pipe.id.clone(),
+            velocity,
+            velocity_head,
+            friction_slope,
+            surcharged,
+            hgl_up,
+            hgl_down,
+            egl_up,
+            egl_down: hgl_down + velocity_head,
+        });
+    }
+    for s in &net.structures {
+        let (k, junction_loss, hgl) = match structure_grades.get(s.id.as_str()) {
+            Some(g) => *g,
+            None if net.pipes.iter().any(|p| p.to == s.id) => (0.0, 0.0, options.tailwater),
+            None => continue,
+        };
+        let freeboard = s.z - hgl;
+        if freeboard < options.min_freeboard {
+            results.violations.push(Violation {
+                id: s.id.clone(),
+                kind: ViolationKind::Freeboard,
+                value: freeboard,
+                limit: options.min_freeboard,
+            });
+        }
+        results.structures.push(StructureGrade {
+            structure: s.id.clone(),
+            k,
+            junction_loss,
+            hgl,
+            freeboard,
+        });
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Pipe, Structure, DEFAULT_MANNING_N};
+    use survey_cad::geometry::Point3;
+
+    fn structure(id: &str, x: f64, y: f64, kind: StructureKind) -> Structure {
+        Structure {
+            id: id.into(),
+            x,
+            y,
+            z: 10.0,
+            kind,
+            ..Default::default()
+        }
+    }
+
+    fn pipe(id: &str, from: &str, to: &str, start: f64, end: f64, flow: f64) -> Pipe {
+        Pipe {
+            id: id.into(),
+            from: from.into(),
+            to: to.into(),
+            diameter: 0.45,
+            c: 120.0,
+            start_invert: start,
+            end_invert: end,
+            design_flow: flow,
+            manning_n: DEFAULT_MANNING_N,
+        }
+    }
+
+    /// A -> B -> O with a 90 degree bend at B and a 0.1 m drop.
+    fn network() -> Network {
+        Network {
+            structures: vec![
+                structure("A", 0.0, 50.0, StructureKind::Inlet),
+                structure("B", 0.0, 0.0, StructureKind::Manhole),
+                structure("O", 50.0, 0.0, StructureKind::Outfall),
+            ],
+            pipes: vec![
+                pipe("AB", "A", "B", 8.5, 8.2, 0.1),
+                pipe("BO", "B", "O", 8.1, 7.8, 0.1),
+            ],
+        }
+    }
+
+    #[test]
+    fn inverts_drops_and_rims() {
+        let mut net = network();
+        net.structures[1].sump = 0.3;
+        assert_eq!(net.structure_invert("B"), Some(8.1));
+        assert!((net.sump_elevation("B").unwrap() - 7.8).abs() < 1e-12);
+        let drops = net.drops();
+        assert_eq!(drops.len(), 1);
+        assert!((drops[0].drop - 0.1).abs() < 1e-12);
+        assert_eq!(junction_k(StructureKind::Manhole, Some(90.0)), 1.0);
+        assert!((junction_k(StructureKind::Manhole, Some(33.75)) - 0.6).abs() < 1e-12);
+        assert_eq!(net.deflection("B", 1), Some(90.0));
+
+        let tin = Tin::from_points(vec![
+            Point3::new(-10.0, -10.0, 11.0),
+            Point3::new(60.0, -10.0, 11.0),
+            Point3::new(60.0, 60.0, 11.0),
+            Point3::new(-10.0, 60.0, 11.0),
+        ]);
+        net.structures
+            .push(structure("X", 100.0, 100.0, StructureKind::Manhole));
+        assert_eq!(net.set_rims_from_tin(&tin), vec!["X".to_string()]);
+        assert!((net.structures[0].z - 11.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn grades_from_tailwater() {
+        let net = network();
+        let options = GradeOptions {
+            tailwater: 7.9,
+            min_cover: 1.0,
+            min_freeboard: 0.3,
+        };
+        let res = hydraulic_grade_lines(&net, &options);
+        let bo = res.pipes.iter().find(|p| p.pipe == "BO").unwrap();
+        let y = gravity::normal_depth(0.1, 0.45, 0.006, DEFAULT_MANNING_N).unwrap();
+        assert!(!bo.surcharged);
+        assert!((bo.hgl_down - (7.8 + y)).abs() < 1e-9);
+        assert!((bo.egl_up - bo.hgl_up - bo.velocity_head).abs() < 1e-12);
+        let b = res.structures.iter().find(|s| s.structure == "B").unwrap();
+        assert_eq!(b.k, 1.0);
+        assert!((b.hgl - (bo.egl_up + bo.velocity_head)).abs() < 1e-9);
+        assert!(res.violations.is_empty(), "{:?}", res.violations);
+
+        // a high tailwater surcharges the outlet and floods the manhole
+        let res = hydraulic_grade_lines(
+            &net,
+            &GradeOptions {
+                tailwater: 9.9,
+                ..options
+            },
+        );
+        let bo = res.pipes.iter().find(|p| p.pipe == "BO").unwrap();
+        assert!(bo.surcharged);
+        assert!((bo.hgl_up - (9.9 + bo.friction_slope * 50.0)).abs() < 1e-9);
+        assert!(res
+            .violations
+            .iter()
+            .any(|v| v.id == "B" && v.kind == ViolationKind::Freeboard));
+        assert!(res
+            .violations
+            .iter()
+            .any(|v| v.id == "O" && v.kind == ViolationKind::Freeboard));
+    }
+}
@@ -5,13 +5,95 @@ use std::io::{self, Write};
 use roxmltree::Document;
 
 use serde::{Deserialize, Serialize};
+use survey_cad::geometry::Point3;
+use survey_cad::io::landxml;
+
+pub mod clash;
+pub mod gravity;
+pub mod hgl;
+pub mod model;
+pub mod pressure;
+pub mod profile;
+pub mod rules;
+pub use clash::{
+    clash_markers, corridor_clashes, network_clashes, surface_clashes, write_clash_csv, Clash,
+    ClashKind,
+};
+pub use gravity::{
+    design_storm_network, gravity_velocity, manning_full_flow, manning_full_velocity,
+    manning_partial_flow, normal_depth, rational_design_flows, rational_flow,
+    sanitary_design_flows, size_pipes, Catchment, IdfCurve, PartialFlow, PeakingFactor,
+    SanitaryFlow, SanitaryLoad, SizingCriteria, SizingResult, StormFlow, DEFAULT_MANNING_N,
+};
+pub use hgl::{
+    hydraulic_grade_lines, junction_k, DropConnection, GradeOptions, GradeResults, PipeGrade,
+    StructureGrade, Violation, ViolationKind, GRAVITY,
+};
+pub use model::{network_model, NetworkModel, PipeBody, StructureBody};
+pub use pressure::{
+    read_inp, solve_pressure_network, write_inp, FlowUnits, HeadCurve, HeadlossFormula, Junction,
+    LinkResult, LinkState, NodeResult, PipeStatus, PressurePipe, PressureResults, Pump, Reservoir,
+    SolverOptions, Tank, Valve, ValveKind, WaterNetwork,
+};
+pub use profile::{network_plan, pipe_profile};
+pub use rules::{
+    check_design, layout_inverts, read_slope_limits_csv, DesignRules, MatchMode, SlopeLimit,
+};
+
+/// Role of a structure, used to pick junction loss coefficients.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StructureKind {
+    #[default]
+    Manhole,
+    Inlet,
+    Junction,
+    Outfall,
+}
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl std::str::FromStr for StructureKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "" | "manhole" => Ok(Self::Manhole),
+            "inlet" => Ok(Self::Inlet),
+            "junction" => Ok(Self::Junction),
+            "outfall" => Ok(Self::Outfall),
+            other => Err(format!("unknown structure kind '{other}'")),
+        }
+    }
+}
+
+impl std::fmt::Display for StructureKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Manhole => "manhole",
+            Self::Inlet => "inlet",
+            Self::Junction => "junction",
+            Self::Outfall => "outfall",
+        })
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Structure {
     pub id: String,
     pub x: f64,
     pub y: f64,
+    /// Rim elevation
     pub z: f64,
+    #[serde(default)]
+    pub kind: StructureKind,
+    /// Depth of the sump below the lowest pipe invert (m)
+    #[serde(default)]
+    pub sump: f64,
+    /// Inside diameter or width of the structure (m)
+    #[serde(default)]
+    pub diameter: Option<f64>,
+    /// Junction loss coefficient overriding the one derived from the kind
+    /// and pipe deflection
+    #[serde(default)]
+    pub junction_k: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,6 +110,38 @@ pub struct Pipe {
     /// Design flow for the pipe (m^3/s)
     #[serde(default)]
     pub design_flow: f64,
+    /// Manning roughness used for gravity flow
+    #[serde(default = "default_manning_n")]
+    pub manning_n: f64,
+}
+
+fn default_manning_n() -> f64 {
+    DEFAULT_MANNING_N
+}
+
+impl Pipe {
+    /// Invert slope over the given horizontal length.
+    pub fn slope(&self, length: f64) -> f64 {
+        pipe_slope(self.start_invert, self.end_invert, length)
+    }
+
+    /// Outside diameter using a wall of `D/12 + 25 mm`, typical of
+    /// reinforced concrete pipe.
+    pub fn outer_diameter(&self) -> f64 {
+        self.diameter + 2.0 * (self.diameter / 12.0 + 0.025)
+    }
+}
+
+/// Inside diameter assumed for structures without one (m).
+pub const DEFAULT_STRUCTURE_DIAMETER: f64 = 1.2;
+/// Wall thickness of structures (m).
+pub const STRUCTURE_WALL: f64 = 0.15;
+
+impl Structure {
+    /// Inside diameter, falling back to [`DEFAULT_STRUCTURE_DIAMETER`].
+    pub fn inside_diameter(&self) -> f64 {
+        self.diameter.unwrap_or(DEFAULT_STRUCTURE_DIAMETER)
+    }
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
@@ -76,11 +190,26 @@ pub fn read_network_csv(structs: &str, pipes: &str) -> io::Result<Network> {
         if parts.len() < 4 {
             continue;
         }
+        let optional = |i: usize| -> io::Result<Option<f64>> {
+            match parts.get(i).map(|v| v.trim()) {
+                Some(v) if !v.is_empty() => parse_num(v).map(Some),
+                _ => Ok(None),
+            }
+        };
         network.structures.push(Structure {
             id: parts[0].trim().to_string(),
             x: parse_num(parts[1])?,
             y: parse_num(parts[2])?,
             z: parse_num(parts[3])?,
+            kind: match parts.get(4) {
+                Some(v) => v
+                    .parse()
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?,
+                None => StructureKind::default(),
+            },
+            sump: optional(5)?.unwrap_or(0.0),
+            diameter: optional(6)?,
+            junction_k: optional(7)?,
         });
     }
     for line in p_lines.lines() {
@@ -109,6 +238,10 @@ pub fn read_network_csv(structs: &str, pipes: &str) -> io::Result<Network> {
                 Some(v) => parse_num(v)?,
                 None => 0.0,
             },
+            manning_n: match parts.get(8) {
+                Some(v) => parse_num(v)?,
+                None => DEFAULT_MANNING_N,
+            },
         });
     }
     Ok(network)
@@ -144,33 +277,25 @@ pub fn read_slope_rules_csv(path: &str) -> io::Result<Vec<SlopeRule>> {
 pub fn write_network_csv(net: &Network, structs: &str, pipes: &str) -> io::Result<()> {
     let mut s_file = std::fs::File::create(structs)?;
     for s in &net.structures {
-        writeln!(s_file, "{},{},{},{}", s.id, s.x, s.y, s.z)?;
-    }
-    let mut p_file = std::fs::File::create(pipes)?;
-    for p in &net.pipes {
+        let opt = |v: Option<f64>| v.map(|v| v.to_string()).unwrap_or_default();
         writeln!(
-            p_file,
+            s_file,
             "{},{},{},{},{},{},{},{}",
-            p.id, p.from, p.to, p.diameter, p.c, p.start_invert, p.end_invert, p.design_flow
+            s.id,
+            s.x,
+            s.y,
+            s.z,
+            s.kind,
+            s.sump,
+            opt(s.diameter),
+            opt(s.junction_k)
         )?;
     }
-    Ok(())
-}
-
-pub fn write_network_landxml(path: &str, net: &Network) -> io::Result<()> {
-    let mut xml = String::new();
-    xml.push_str("<?xml version=\"1.0\"?>\n<LandXML>\n  <PipeNetworks>\n");
-    xml.push_str("    <Structs>\n");
-    for s in &net.structures {
-        xml.push_str(&format!(
-            "      <Struct id=\"{}\" x=\"{}\" y=\"{}\" z=\"{}\"/>\n",
-            s.id, s.x, s.y, s.z
-        ));
-    }
-    xml.push_str("    </Structs>\n    <Pipes>\n");
+    let mut p_file = std::fs::File::create(pipes)?;
     for p in &net.pipes {
-        xml.push_str(&format!(
-            "      <Pipe id=\"{}\" from=\"{}\" to=\"{}\" diameter=\"{}\" c=\"{}\" startInv=\"{}\" endInv=\"{}\" designFlow=\"{}\"/>\n",
+        writeln!(
+            p_file,
+            "{},{},{},{},{},{},{},{},{}",
             p.id,
             p.from,
             p.to,
@@ -178,16 +303,118 @@ pub fn write_network_landxml(path: &str, net: &Network) -> io::Result<()> {
             p.c,
             p.start_invert,
             p.end_invert,
-            p.design_flow
-        ));
+            p.design_flow,
+            p.manning_n
+        )?;
     }
-    xml.push_str("    </Pipes>\n  </PipeNetworks>\n</LandXML>\n");
-    std::fs::write(path, xml)
+    Ok(())
+}
+
+impl From<&Network> for landxml::PipeNetwork {
+    fn from(net: &Network) -> Self {
+        landxml::PipeNetwork {
+            name: "Network".to_string(),
+            kind: "other".to_string(),
+            structures: net
+                .structures
+                .iter()
+                .map(|s| landxml::PipeStructure {
+                    name: s.id.clone(),
+                    center: Point3::new(s.x, s.y, s.z),
+                    sump: net.sump_elevation(&s.id),
+                    diameter: s.diameter,
+                    ..Default::default()
+                })
+                .collect(),
+            pipes: net
+                .pipes
+                .iter()
+                .map(|p| landxml::NetworkPipe {
+                    name: p.id.clone(),
+                    start: p.from.clone(),
+                    end: p.to.clone(),
+                    diameter: p.diameter,
+                    start_invert: Some(p.start_invert),
+                    end_invert: Some(p.end_invert),
+                    properties: vec![
+                        ("c".to_string(), p.c.to_string()),
+                        ("designFlow".to_string(), p.design_flow.to_string()),
+                        ("manningN".to_string(), p.manning_n.to_string()),
+                    ],
+                    ..Default::default()
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<&landxml::PipeNetwork> for Network {
+    type Error = io::Error;
+
+    fn try_from(net: &landxml::PipeNetwork) -> io::Result<Self> {
+        let property = |p: &landxml::NetworkPipe, label, default| match p.property(label) {
+            Some(v) => parse_num(v),
+            None => Ok(default),
+        };
+        let mut network = Network {
+            structures: net
+                .structures
+                .iter()
+                .map(|s| Structure {
+                    id: s.name.clone(),
+                    x: s.center.x,
+                    y: s.center.y,
+                    z: s.rim.unwrap_or(s.center.z),
+                    diameter: s.diameter,
+                    ..Default::default()
+                })
+                .collect(),
+            pipes: net
+                .pipes
+                .iter()
+                .map(|p| {
+                    Ok(Pipe {
+                        id: p.name.clone(),
+                        from: p.start.clone(),
+                        to: p.end.clone(),
+                        diameter: p.diameter,
+                        c: property(p, "c", 100.0)?,
+                        start_invert: p.start_invert.unwrap_or(0.0),
+                        end_invert: p.end_invert.unwrap_or(0.0),
+                        design_flow: property(p, "designFlow", 0.0)?,
+                        manning_n: property(p, "manningN", DEFAULT_MANNING_N)?,
+                    })
+                })
+                .collect::<io::Result<_>>()?,
+        };
+        // LandXML stores the sump as an elevation rather than a depth
+        for (i, s) in net.structures.iter().enumerate() {
+            if let (Some(sump), Some(invert)) = (s.sump, network.structure_invert(&s.name)) {
+                network.structures[i].sump = (invert - sump).max(0.0);
+            }
+        }
+        Ok(network)
+    }
+}
+
+/// Writes the network as a LandXML 1.2 `<PipeNetwork>`. Roughness and
+/// design flow are stored as pipe properties.
+pub fn write_network_landxml(path: &str, net: &Network) -> io::Result<()> {
+    landxml::write_landxml_pipe_networks(path, &[net.into()])
 }
 
+/// Reads the first `<PipeNetwork>` of a LandXML file. Files written by
+/// earlier versions, with `<Structs>` directly under `<PipeNetworks>`, are
+/// still accepted.
 pub fn read_network_landxml(path: &str) -> io::Result<Network> {
     let xml = std::fs::read_to_string(path)?;
     let doc = Document::parse(&xml).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    if doc.descendants().any(|n| n.has_tag_name("PipeNetwork")) {
+        return match landxml::read_landxml_pipe_networks(path)?.first() {
+            Some(net) => Network::try_from(net),
+            None => Ok(Network::default()),
+        };
+    }
     let mut network = Network::default();
     if let Some(structs) = doc.descendants().find(|n| n.has_tag_name("Structs")) {
         for s in structs.children().filter(|c| c.has_tag_name("Struct")) {
@@ -205,6 +432,7 @@ pub fn read_network_landxml(path: &str) -> io::Result<Network> {
                     Some(v) => parse_num(v)?,
                     None => 0.0,
                 },
+                ..Default::default()
             });
         }
     }
@@ -234,45 +462,29 @@ pub fn read_network_landxml(path: &str) -> io::Result<Network> {
                     Some(v) => parse_num(v)?,
                     None => 0.0,
                 },
+                manning_n: DEFAULT_MANNING_N,
             });
         }
     }
     Ok(network)
 }
 
-/// Apply slope design rules to compute pipe inverts.
+/// Apply slope design rules to compute pipe inverts, with crowns at the
+/// rims and one slope per diameter. [`layout_inverts`] covers the full rule
+/// set.
 pub fn apply_slope_rules(net: &mut Network, rules: &[SlopeRule]) {
-    let idx: HashMap<String, usize> = net
-        .structures
-        .iter()
-        .enumerate()
-        .map(|(i, s)| (s.id.clone(), i))
-        .collect();
-    for pipe in &mut net.pipes {
-        if let Some(&start_idx) = idx.get(pipe.from.as_str()) {
-            let a = &net.structures[start_idx];
-            // compute pipe length if we know the end structure
-            let length = idx
-                .get(pipe.to.as_str())
-                .map(|&b_idx| {
-                    let b = &net.structures[b_idx];
-                    ((b.x - a.x).powi(2) + (b.y - a.y).powi(2)).sqrt()
-                })
-                .unwrap_or(0.0);
-            pipe.start_invert = a.z;
-            let slope = rules
-                .iter()
-                .filter(|r| pipe.diameter >= r.min_diameter)
-                .max_by(|a, b| {
-                    a.min_diameter
-                        .partial_cmp(&b.min_diameter)
-                        .unwrap_or(Ordering::Equal)
-                })
-                .map(|r| r.slope)
-                .unwrap_or(0.0);
-            pipe.end_invert = pipe.start_invert - slope * length;
-        }
-    }
+    let rules = DesignRules {
+        slopes: rules
+            .iter()
+            .map(|r| SlopeLimit {
+                min_diameter: r.min_diameter,
+                min_slope: r.slope,
+                max_slope: r.slope,
+            })
+            .collect(),
+        ..Default::default()
+    };
+    layout_inverts(net, &rules, None, None);
 }
 
 /// Calculates head loss using the Hazen-Williams equation (SI units).
@@ -283,6 +495,31 @@ pub fn hazen_williams_headloss(flow: f64, length: f64, diameter: f64, c: f64) ->
     10.67 * length * flow.powf(1.852) / (c.powf(1.852) * diameter.powf(4.8704))
 }
 
+/// Kinematic viscosity of water at about 20 C (m^2/s).
+pub const WATER_VISCOSITY: f64 = 1.0e-6;
+
+/// Darcy friction factor from the Swamee-Jain approximation of
+/// Colebrook-White, or `64 / Re` for laminar flow. `roughness` is the
+/// absolute roughness in metres.
+pub fn friction_factor(flow: f64, diameter: f64, roughness: f64) -> f64 {
+    let area = std::f64::consts::PI * diameter * diameter / 4.0;
+    let reynolds = flow.abs() / area * diameter / WATER_VISCOSITY;
+    if reynolds < 2000.0 {
+        return 64.0 / reynolds.max(1e-9);
+    }
+    let term = roughness / (3.7 * diameter) + 5.74 / reynolds.powf(0.9);
+    0.25 / term.log10().powi(2)
+}
+
+/// Calculates head loss using the Darcy-Weisbach equation (SI units).
+pub fn darcy_weisbach_headloss(flow: f64, length: f64, diameter: f64, roughness: f64) -> f64 {
+    if diameter <= 0.0 || flow == 0.0 {
+        return 0.0;
+    }
+    let f = friction_factor(flow, diameter, roughness);
+    8.0 * f * length * flow * flow / (GRAVITY * std::f64::consts::PI.powi(2) * diameter.powi(5))
+}
+
 /// Computes hydraulic grade line drop along a pipe.
 pub fn hydraulic_grade(start_elev: f64, headloss: f64) -> f64 {
     start_elev - headloss
@@ -332,6 +569,15 @@ pub struct DetailedPipeAnalysis {
     pub friction_slope: f64,
     pub start_grade: f64,
     pub end_grade: f64,
+    /// Manning capacity flowing full
+    #[serde(default)]
+    pub full_capacity: f64,
+    /// Normal depth at design flow, `None` when surcharged
+    #[serde(default)]
+    pub normal_depth: Option<f64>,
+    /// Manning velocity at normal depth
+    #[serde(default)]
+    pub gravity_velocity: f64,
 }
 
 /// Analyze each pipe in a network using its `design_flow`.
@@ -364,7 +610,8 @@ pub fn analyze_network(net: &Network) -> Vec<PipeAnalysis> {
     results
 }
 
-/// Perform detailed analysis including velocity and friction slope
+/// Perform detailed analysis including velocity and friction slope, plus
+/// Manning capacity and normal depth for gravity flow
 pub fn analyze_network_detailed(net: &Network) -> Vec<DetailedPipeAnalysis> {
     let idx = net.structure_index();
     let mut results = Vec::new();
@@ -384,6 +631,9 @@ pub fn analyze_network_detailed(net: &Network) -> Vec<DetailedPipeAnalysis> {
             let start_grade = a.z;
             let end_grade = hydraulic_grade(start_grade, headloss);
             results.push(DetailedPipeAnalysis {
+                full_capacity: manning_full_flow(pipe.diameter, slope, pipe.manning_n),
+                normal_depth: normal_depth(flow, pipe.diameter, slope, pipe.manning_n),
+                gravity_velocity: gravity_velocity(flow, pipe.diameter, slope, pipe.manning_n),
                 id: pipe.id.clone(),
                 length,
                 design_flow: flow,
@@ -418,7 +668,7 @@ pub fn write_detailed_analysis_csv(path: &str, results: &[DetailedPipeAnalysis])
     for r in results {
         writeln!(
             file,
-            "{},{},{},{},{},{},{},{},{}",
+            "{},{},{},{},{},{},{},{},{},{},{},{}",
             r.id,
             r.length,
             r.design_flow,
@@ -427,7 +677,10 @@ pub fn write_detailed_analysis_csv(path: &str, results: &[DetailedPipeAnalysis])
             r.velocity,
             r.friction_slope,
             r.start_grade,
-            r.end_grade
+            r.end_grade,
+            r.full_capacity,
+            r.normal_depth.map(|d| d.to_string()).unwrap_or_default(),
+            r.gravity_velocity
         )?;
     }
     Ok(())
@@ -456,7 +709,7 @@ pub fn write_detailed_analysis_landxml(
     xml.push_str("<?xml version=\"1.0\"?>\n<LandXML>\n  <PipeResults>\n");
     for r in results {
         xml.push_str(&format!(
-            "    <Pipe id=\"{}\" length=\"{}\" designFlow=\"{}\" slope=\"{}\" headloss=\"{}\" velocity=\"{}\" frictionSlope=\"{}\" startGrade=\"{}\" endGrade=\"{}\"/>\n",
+            "    <Pipe id=\"{}\" length=\"{}\" designFlow=\"{}\" slope=\"{}\" headloss=\"{}\" velocity=\"{}\" frictionSlope=\"{}\" startGrade=\"{}\" endGrade=\"{}\" fullCapacity=\"{}\"{} gravityVelocity=\"{}\"/>\n",
             r.id,
             r.length,
             r.design_flow,
@@ -465,7 +718,12 @@ pub fn write_detailed_analysis_landxml(
             r.velocity,
             r.friction_slope,
             r.start_grade,
-            r.end_grade
+            r.end_grade,
+            r.full_capacity,
+            r.normal_depth
+                .map(|d| format!(" normalDepth=\"{d}\""))
+                .unwrap_or_default(),
+            r.gravity_velocity
         ));
     }
     xml.push_str("  </PipeResults>\n</LandXML>\n");
@@ -492,12 +750,14 @@ mod tests {
                     x: 0.0,
                     y: 0.0,
                     z: 0.0,
+                    ..Default::default()
                 },
                 Structure {
                     id: "S2".into(),
                     x: 1.0,
                     y: 1.0,
                     z: 0.5,
+                    ..Default::default()
                 },
             ],
             pipes: vec![Pipe {
@@ -509,6 +769,7 @@ mod tests {
                 start_invert: 1.0,
                 end_invert: 0.5,
                 design_flow: 0.1,
+                manning_n: DEFAULT_MANNING_N,
             }],
         };
         let file = NamedTempFile::new().unwrap();
@@ -551,12 +812,14 @@ mod tests {
                     x: 0.0,
                     y: 0.0,
                     z: 1.0,
+                    ..Default::default()
                 },
                 Structure {
                     id: "B".into(),
                     x: 10.0,
                     y: 0.0,
                     z: 1.0,
+                    ..Default::default()
                 },
             ],
             pipes: vec![Pipe {
@@ -568,6 +831,7 @@ mod tests {
                 start_invert: 1.0,
                 end_invert: 0.9,
                 design_flow: 0.2,
+                manning_n: DEFAULT_MANNING_N,
             }],
         };
         let res = analyze_network(&net);
@@ -584,12 +848,14 @@ mod tests {
                     x: 0.0,
                     y: 0.0,
                     z: 2.0,
+                    ..Default::default()
                 },
                 Structure {
                     id: "B".into(),
                     x: 20.0,
                     y: 0.0,
                     z: 2.0,
+                    ..Default::default()
                 },
             ],
             pipes: vec![Pipe {
@@ -601,6 +867,7 @@ mod tests {
                 start_invert: 0.0,
                 end_invert: 0.0,
                 design_flow: 0.4,
+                manning_n: DEFAULT_MANNING_N,
             }],
         };
         let rules = vec![SlopeRule {
@@ -662,12 +929,14 @@ mod tests {
                     x: 0.0,
                     y: 0.0,
                     z: 1.0,
+                    ..Default::default()
                 },
                 Structure {
                     id: "B".into(),
                     x: 10.0,
                     y: 0.0,
                     z: 1.0,
+                    ..Default::default()
                 },
             ],
             pipes: vec![Pipe {
@@ -679,6 +948,7 @@ mod tests {
                 start_invert: 1.0,
                 end_invert: 1.0,
                 design_flow: 0.0,
+                manning_n: DEFAULT_MANNING_N,
             }],
         };
         let rules = vec![
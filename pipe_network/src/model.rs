@@ -0,0 +1,161 @@
+//! 3D bodies of pipes and structures for viewing and clash detection.
+
+use serde::{Deserialize, Serialize};
+use survey_cad::geometry::Point3;
+use survey_cad::truck_integration::{cylinder_between, Solid};
+
+use crate::{Network, STRUCTURE_WALL};
+
+/// Pipe barrel between the inside faces of its structures. The axis runs
+/// through the pipe centre.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipeBody {
+    pub id: String,
+    pub start: Point3,
+    pub end: Point3,
+    pub inner_radius: f64,
+    pub outer_radius: f64,
+}
+
+/// Cylindrical structure from the underside of its base slab to the rim.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StructureBody {
+    pub id: String,
+    /// Centre of the base.
+    pub base: Point3,
+    pub height: f64,
+    pub inner_radius: f64,
+    pub outer_radius: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NetworkModel {
+    pub pipes: Vec<PipeBody>,
+    pub structures: Vec<StructureBody>,
+}
+
+impl PipeBody {
+    /// Solid of the pipe's outside diameter.
+    pub fn solid(&self) -> Option<Solid> {
+        cylinder_between(self.start, self.end, self.outer_radius)
+    }
+}
+
+impl StructureBody {
+    /// Solid of the structure's outside diameter.
+    pub fn solid(&self) -> Option<Solid> {
+        let top = Point3::new(self.base.x, self.base.y, self.base.z + self.height);
+        cylinder_between(self.base, top, self.outer_radius)
+    }
+}
+
+impl NetworkModel {
+    /// Truck solids of every pipe and structure, skipping degenerate ones.
+    pub fn solids(&self) -> Vec<Solid> {
+        self.pipes
+            .iter()
+            .filter_map(PipeBody::solid)
+            .chain(self.structures.iter().filter_map(StructureBody::solid))
+            .collect()
+    }
+}
+
+/// Builds pipe and structure bodies from the network. Pipes are trimmed to
+/// the inside face of the structures at each end; structures run from their
+/// sump less the wall thickness up to the rim.
+pub fn network_model(net: &Network) -> NetworkModel {
+    let idx = net.structure_index();
+    let pipes = net
+        .pipes
+        .iter()
+        .filter_map(|p| {
+            let a = &net.structures[*idx.get(p.from.as_str())?];
+            let b = &net.structures[*idx.get(p.to.as_str())?];
+            let length = (b.x - a.x).hypot(b.y - a.y);
+            let (ra, rb) = (a.inside_diameter() / 2.0, b.inside_diameter() / 2.0);
+            if length <= ra + rb {
+                return None;
+            }
+            let (dx, dy) = ((b.x - a.x) / length, (b.y - a.y) / length);
+            let slope = (p.end_invert - p.start_invert) / length;
+            let r = p.diameter / 2.0;
+            Some(PipeBody {
+                id: p.id.clone(),
+                start: Point3::new(
+                    a.x + dx * ra,
+                    a.y + dy * ra,
+                    p.start_invert + slope * ra + r,
+                ),
+                end: Point3::new(b.x - dx * rb, b.y - dy * rb, p.end_invert - slope * rb + r),
+                inner_radius: r,
+                outer_radius: p.outer_diameter() / 2.0,
+            })
+        })
+        .collect();
+    let structures = net
+        .structures
+        .iter()
+        .map(|s| {
+            let floor = net.sump_elevation(&s.id).unwrap_or(s.z - s.sump) - STRUCTURE_WALL;
+            let inner = s.inside_diameter() / 2.0;
+            StructureBody {
+                id: s.id.clone(),
+                base: Point3::new(s.x, s.y, floor),
+                height: s.z - floor,
+                inner_radius: inner,
+                outer_radius: inner + STRUCTURE_WALL,
+            }
+        })
+        .collect();
+    NetworkModel { pipes, structures }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Pipe, Structure, DEFAULT_MANNING_N};
+
+    #[test]
+    fn bodies_trimmed_to_structures() {
+        let net = Network {
+            structures: vec![
+                Structure {
+                    id: "A".into(),
+                    z: 12.0,
+                    sump: 0.3,
+                    ..Default::default()
+                },
+                Structure {
+                    id: "B".into(),
+                    x: 50.0,
+                    z: 11.0,
+                    diameter: Some(1.5),
+                    ..Default::default()
+                },
+            ],
+            pipes: vec![Pipe {
+                id: "P1".into(),
+                from: "A".into(),
+                to: "B".into(),
+                diameter: 0.6,
+                c: 120.0,
+                start_invert: 10.0,
+                end_invert: 9.5,
+                design_flow: 0.0,
+                manning_n: DEFAULT_MANNING_N,
+            }],
+        };
+        let model = network_model(&net);
+        let p = &model.pipes[0];
+        assert!((p.start.x - 0.6).abs() < 1e-9);
+        assert!((p.end.x - 49.25).abs() < 1e-9);
+        assert!((p.start.z - (10.0 - 0.01 * 0.6 + 0.3)).abs() < 1e-9);
+        assert!((p.outer_radius - 0.375).abs() < 1e-9);
+
+        let a = &model.structures[0];
+        assert!((a.base.z - (10.0 - 0.3 - STRUCTURE_WALL)).abs() < 1e-9);
+        assert!((a.height - (12.0 - a.base.z)).abs() < 1e-9);
+        assert!((model.structures[1].outer_radius - 0.9).abs() < 1e-9);
+        assert_eq!(model.solids().len(), 3);
+    }
+}
@@ -0,0 +1,509 @@
+//! EPANET `.inp` input files.
+//!
+//! Reads and writes the `[TITLE]`, `[JUNCTIONS]`, `[RESERVOIRS]`,
+//! `[TANKS]`, `[PIPES]`, `[PUMPS]`, `[VALVES]`, `[CURVES]`, `[OPTIONS]` and
+//! `[COORDINATES]` sections. Other sections, such as patterns and controls
+//! for extended period runs, are skipped. Values are converted between the
+//! file's flow units and SI on reading and back on writing.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io;
+
+use serde::{Deserialize, Serialize};
+
+use super::{
+    HeadCurve, HeadlossFormula, Junction, PipeStatus, PressurePipe, Pump, Reservoir, Tank, Valve,
+    ValveKind, WaterNetwork,
+};
+use crate::parse_num;
+
+/// EPANET flow units. US units imply feet, inches and psi; SI units imply
+/// metres, millimetres and metres of head.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FlowUnits {
+    Cfs,
+    Gpm,
+    Mgd,
+    Imgd,
+    Afd,
+    #[default]
+    Lps,
+    Lpm,
+    Mld,
+    Cmh,
+    Cmd,
+}
+
+const FLOW_UNITS: [(FlowUnits, &str, f64); 10] = [
+    (FlowUnits::Cfs, "CFS", 0.028316847),
+    (FlowUnits::Gpm, "GPM", 6.30901964e-5),
+    (FlowUnits::Mgd, "MGD", 0.0438126364),
+    (FlowUnits::Imgd, "IMGD", 0.0526167498),
+    (FlowUnits::Afd, "AFD", 0.0142764101),
+    (FlowUnits::Lps, "LPS", 0.001),
+    (FlowUnits::Lpm, "LPM", 1.0 / 60_000.0),
+    (FlowUnits::Mld, "MLD", 1.0 / 86.4),
+    (FlowUnits::Cmh, "CMH", 1.0 / 3600.0),
+    (FlowUnits::Cmd, "CMD", 1.0 / 86_400.0),
+];
+
+impl FlowUnits {
+    fn entry(self) -> &'static (FlowUnits, &'static str, f64) {
+        FLOW_UNITS.iter().find(|(u, _, _)| *u == self).unwrap()
+    }
+
+    pub fn name(self) -> &'static str {
+        self.entry().1
+    }
+
+    /// Cubic metres per second in one unit of flow.
+    pub fn to_si(self) -> f64 {
+        self.entry().2
+    }
+
+    pub fn is_us(self) -> bool {
+        matches!(
+            self,
+            FlowUnits::Cfs | FlowUnits::Gpm | FlowUnits::Mgd | FlowUnits::Imgd | FlowUnits::Afd
+        )
+    }
+}
+
+/// Multipliers from file units to SI.
+struct Scale {
+    flow: f64,
+    length: f64,
+    diameter: f64,
+    /// Darcy-Weisbach roughness, millifeet or millimetres.
+    roughness: f64,
+    pressure: f64,
+}
+
+impl Scale {
+    fn new(units: FlowUnits) -> Self {
+        if units.is_us() {
+            Scale {
+                flow: units.to_si(),
+                length: 0.3048,
+                diameter: 0.0254,
+                roughness: 0.0003048,
+                pressure: 0.70307,
+            }
+        } else {
+            Scale {
+                flow: units.to_si(),
+                length: 1.0,
+                diameter: 0.001,
+                roughness: 0.001,
+                pressure: 1.0,
+            }
+        }
+    }
+}
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn field<'a>(fields: &[&'a str], i: usize, section: &str) -> io::Result<&'a str> {
+    fields
+        .get(i)
+        .copied()
+        .ok_or_else(|| invalid(format!("missing field {} in [{section}]", i + 1)))
+}
+
+fn num(fields: &[&str], i: usize, section: &str) -> io::Result<f64> {
+    parse_num(field(fields, i, section)?)
+}
+
+fn num_or(fields: &[&str], i: usize, default: f64) -> io::Result<f64> {
+    fields.get(i).map_or(Ok(default), |v| parse_num(v))
+}
+
+/// Parses the text of an `.inp` file.
+pub fn parse_inp(text: &str) -> io::Result<WaterNetwork> {
+    let mut sections: HashMap<String, Vec<&str>> = HashMap::new();
+    let mut current = String::new();
+    for raw in text.lines() {
+        let line = raw.split(';').next().unwrap_or("").trim();
+        if line.is_empty() {
+            continue;
+        }
+        if line.starts_with('[') {
+            current = line
+                .trim_matches(|c| c == '[' || c == ']')
+                .to_ascii_uppercase();
+            continue;
+        }
+        sections.entry(current.clone()).or_default().push(line);
+    }
+    let lines = |name: &str| sections.get(name).cloned().unwrap_or_default();
+
+    let mut net = WaterNetwork::default();
+    for line in lines("OPTIONS") {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let value = fields.last().copied().unwrap_or("").to_ascii_uppercase();
+        match fields[0].to_ascii_uppercase().as_str() {
+            "UNITS" => {
+                net.units = FLOW_UNITS
+                    .iter()
+                    .find(|(_, name, _)| *name == value)
+                    .map(|(u, _, _)| *u)
+                    .ok_or_else(|| invalid(format!("unknown flow units '{value}'")))?;
+            }
+            "HEADLOSS" => {
+                net.headloss = match value.as_str() {
+                    "H-W" => HeadlossFormula::HazenWilliams,
+                    "D-W" => HeadlossFormula::DarcyWeisbach,
+                    _ => return Err(invalid(format!("unsupported headloss formula '{value}'"))),
+                };
+            }
+            _ => {}
+        }
+    }
+    let s = Scale::new(net.units);
+    let roughness = match net.headloss {
+        HeadlossFormula::HazenWilliams => 1.0,
+        HeadlossFormula::DarcyWeisbach => s.roughness,
+    };
+
+    net.title = lines("TITLE").join("\n");
+    for line in lines("JUNCTIONS") {
+        let f: Vec<&str> = line.split_whitespace().collect();
+        net.junctions.push(Junction {
+            id: field(&f, 0, "JUNCTIONS")?.to_string(),
+            elevation: num(&f, 1, "JUNCTIONS")? * s.length,
+            demand: num_or(&f, 2, 0.0)? * s.flow,
+        });
+    }
+    for line in lines("RESERVOIRS") {
+        let f: Vec<&str> = line.split_whitespace().collect();
+        net.reservoirs.push(Reservoir {
+            id: field(&f, 0, "RESERVOIRS")?.to_string(),
+            head: num(&f, 1, "RESERVOIRS")? * s.length,
+        });
+    }
+    for line in lines("TANKS") {
+        let f: Vec<&str> = line.split_whitespace().collect();
+        net.tanks.push(Tank {
+            id: field(&f, 0, "TANKS")?.to_string(),
+            elevation: num(&f, 1, "TANKS")? * s.length,
+            init_level: num(&f, 2, "TANKS")? * s.length,
+            min_level: num_or(&f, 3, 0.0)? * s.length,
+            max_level: num_or(&f, 4, 0.0)? * s.length,
+            diameter: num_or(&f, 5, 0.0)? * s.length,
+        });
+    }
+    for line in lines("PIPES") {
+        let f: Vec<&str> = line.split_whitespace().collect();
+        let status = match f.get(7).map(|v| v.to_ascii_uppercase()).as_deref() {
+            None | Some("OPEN") => PipeStatus::Open,
+            Some("CLOSED") => PipeStatus::Closed,
+            Some("CV") => PipeStatus::CheckValve,
+            Some(other) => return Err(invalid(format!("unknown pipe status '{other}'"))),
+        };
+        net.pipes.push(PressurePipe {
+            id: field(&f, 0, "PIPES")?.to_string(),
+            from: field(&f, 1, "PIPES")?.to_string(),
+            to: field(&f, 2, "PIPES")?.to_string(),
+            length: num(&f, 3, "PIPES")? * s.length,
+            diameter: num(&f, 4, "PIPES")? * s.diameter,
+            roughness: num(&f, 5, "PIPES")? * roughness,
+            minor_loss: num_or(&f, 6, 0.0)?,
+            status,
+        });
+    }
+    for line in lines("PUMPS") {
+        let f: Vec<&str> = line.split_whitespace().collect();
+        let mut pump = Pump {
+            id: field(&f, 0, "PUMPS")?.to_string(),
+            from: field(&f, 1, "PUMPS")?.to_string(),
+            to: field(&f, 2, "PUMPS")?.to_string(),
+            curve: String::new(),
+            speed: 1.0,
+        };
+        for pair in f[3..].chunks(2) {
+            let value = field(pair, 1, "PUMPS")?;
+            match pair[0].to_ascii_uppercase().as_str() {
+                "HEAD" => pump.curve = value.to_string(),
+                "SPEED" => pump.speed = parse_num(value)?,
+                "PATTERN" => {}
+                other => return Err(invalid(format!("unsupported pump keyword '{other}'"))),
+            }
+        }
+        if pump.curve.is_empty() {
+            return Err(invalid(format!("pump '{}' has no head curve", pump.id)));
+        }
+        net.pumps.push(pump);
+    }
+    for line in lines("VALVES") {
+        let f: Vec<&str> = line.split_whitespace().collect();
+        let kind = field(&f, 4, "VALVES")?.to_ascii_uppercase();
+        let (kind, setting) = match kind.as_str() {
+            "PRV" => (ValveKind::Prv, num(&f, 5, "VALVES")? * s.pressure),
+            "TCV" => (ValveKind::Tcv, num(&f, 5, "VALVES")?),
+            other => return Err(invalid(format!("unsupported valve type '{other}'"))),
+        };
+        net.valves.push(Valve {
+            id: field(&f, 0, "VALVES")?.to_string(),
+            from: field(&f, 1, "VALVES")?.to_string(),
+            to: field(&f, 2, "VALVES")?.to_string(),
+            diameter: num(&f, 3, "VALVES")? * s.diameter,
+            kind,
+            setting,
+            minor_loss: num_or(&f, 6, 0.0)?,
+        });
+    }
+    for line in lines("CURVES") {
+        let f: Vec<&str> = line.split_whitespace().collect();
+        let id = field(&f, 0, "CURVES")?;
+        let point = (
+            num(&f, 1, "CURVES")? * s.flow,
+            num(&f, 2, "CURVES")? * s.length,
+        );
+        match net.curves.iter_mut().find(|c| c.id == id) {
+            Some(c) => c.points.push(point),
+            None => net.curves.push(HeadCurve {
+                id: id.to_string(),
+                points: vec![point],
+            }),
+        }
+    }
+    for line in lines("COORDINATES") {
+        let f: Vec<&str> = line.split_whitespace().collect();
+        net.coordinates.push((
+            field(&f, 0, "COORDINATES")?.to_string(),
+            num(&f, 1, "COORDINATES")?,
+            num(&f, 2, "COORDINATES")?,
+        ));
+    }
+    Ok(net)
+}
+
+/// Reads a network from an EPANET `.inp` file.
+pub fn read_inp(path: &str) -> io::Result<WaterNetwork> {
+    parse_inp(&std::fs::read_to_string(path)?)
+}
+
+/// Formats a value without trailing zeros or float noise.
+fn fmt(v: f64) -> String {
+    let s = format!("{v:.6}");
+    let s = s.trim_end_matches('0').trim_end_matches('.');
+    if s == "-0" {
+        "0".to_string()
+    } else {
+        s.to_string()
+    }
+}
+
+/// Formats the network as `.inp` text in its flow units.
+pub fn format_inp(net: &WaterNetwork) -> String {
+    let s = Scale::new(net.units);
+    let roughness = match net.headloss {
+        HeadlossFormula::HazenWilliams => 1.0,
+        HeadlossFormula::DarcyWeisbach => s.roughness,
+    };
+    let mut out = String::new();
+    writeln!(out, "[TITLE]").unwrap();
+    if !net.title.is_empty() {
+        writeln!(out, "{}", net.title).unwrap();
+    }
+    writeln!(out, "\n[JUNCTIONS]\n;ID\tElev\tDemand").unwrap();
+    for j in &net.junctions {
+        writeln!(
+            out,
+            "{}\t{}\t{}",
+            j.id,
+            fmt(j.elevation / s.length),
+            fmt(j.demand / s.flow)
+        )
+        .unwrap();
+    }
+    writeln!(out, "\n[RESERVOIRS]\n;ID\tHead").unwrap();
+    for r in &net.reservoirs {
+        writeln!(out, "{}\t{}", r.id, fmt(r.head / s.length)).unwrap();
+    }
+    writeln!(
+        out,
+        "\n[TANKS]\n;ID\tElevation\tInitLevel\tMinLevel\tMaxLevel\tDiameter\tMinVol"
+    )
+    .unwrap();
+    for t in &net.tanks {
+        writeln!(
+            out,
+            "{}\t{}\t{}\t{}\t{}\t{}\t0",
+            t.id,
+            fmt(t.elevation / s.length),
+            fmt(t.init_level / s.length),
+            fmt(t.min_level / s.length),
+            fmt(t.max_level / s.length),
+            fmt(t.diameter / s.length)
+        )
+        .unwrap();
+    }
+    writeln!(
+        out,
+        "\n[PIPES]\n;ID\tNode1\tNode2\tLength\tDiameter\tRoughness\tMinorLoss\tStatus"
+    )
+    .unwrap();
+    for p in &net.pipes {
+        let status = match p.status {
+            PipeStatus::Open => "Open",
+            PipeStatus::Closed => "Closed",
+            PipeStatus::CheckValve => "CV",
+        };
+        writeln!(
+            out,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            p.id,
+            p.from,
+            p.to,
+            fmt(p.length / s.length),
+            fmt(p.diameter / s.diameter),
+            fmt(p.roughness / roughness),
+            fmt(p.minor_loss),
+            status
+        )
+        .unwrap();
+    }
+    writeln!(out, "\n[PUMPS]\n;ID\tNode1\tNode2\tParameters").unwrap();
+    for p in &net.pumps {
+        write!(out, "{}\t{}\t{}\tHEAD {}", p.id, p.from, p.to, p.curve).unwrap();
+        if p.speed != 1.0 {
+            write!(out, "\tSPEED {}", fmt(p.speed)).unwrap();
+        }
+        writeln!(out).unwrap();
+    }
+    writeln!(
+        out,
+        "\n[VALVES]\n;ID\tNode1\tNode2\tDiameter\tType\tSetting\tMinorLoss"
+    )
+    .unwrap();
+    for v in &net.valves {
+        let (kind, setting) = match v.kind {
+            ValveKind::Prv => ("PRV", v.setting / s.pressure),
+            ValveKind::Tcv => ("TCV", v.setting),
+        };
+        writeln!(
+            out,
+            "{}\t{}\t{}\t{}\t{}\t{}\t{}",
+            v.id,
+            v.from,
+            v.to,
+            fmt(v.diameter / s.diameter),
+            kind,
+            fmt(setting),
+            fmt(v.minor_loss)
+        )
+        .unwrap();
+    }
+    writeln!(out, "\n[CURVES]\n;ID\tX-Value\tY-Value").unwrap();
+    for c in &net.curves {
+        for (q, h) in &c.points {
+            writeln!(out, "{}\t{}\t{}", c.id, fmt(q / s.flow), fmt(h / s.length)).unwrap();
+        }
+    }
+    let headloss = match net.headloss {
+        HeadlossFormula::HazenWilliams => "H-W",
+        HeadlossFormula::DarcyWeisbach => "D-W",
+    };
+    writeln!(
+        out,
+        "\n[OPTIONS]\n Units\t{}\n Headloss\t{}",
+        net.units.name(),
+        headloss
+    )
+    .unwrap();
+    writeln!(out, "\n[COORDINATES]\n;Node\tX-Coord\tY-Coord").unwrap();
+    for (id, x, y) in &net.coordinates {
+        writeln!(out, "{id}\t{x}\t{y}").unwrap();
+    }
+    writeln!(out, "\n[END]").unwrap();
+    out
+}
+
+/// Writes the network to an EPANET `.inp` file.
+pub fn write_inp(path: &str, net: &WaterNetwork) -> io::Result<()> {
+    std::fs::write(path, format_inp(net))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pressure::{solve_pressure_network, SolverOptions};
+    use tempfile::NamedTempFile;
+
+    const NET: &str = "\
+[TITLE]
+Two loop test
+
+[JUNCTIONS]
+;ID  Elev  Demand
+ J1  700   0      ; source node
+ J2  710   150
+ J3  690   250
+
+[RESERVOIRS]
+ R1  800
+
+[PIPES]
+ P1  R1  J1  3000  18  130
+ P2  J1  J2  5000  12  130  0  Open
+ P3  J1  J3  5000  10  130  0  CV
+ P4  J2  J3  5000   8  130
+
+[VALVES]
+ V1  J2  J3  6  TCV  2  0
+
+[CURVES]
+ C1  500  150
+
+[OPTIONS]
+ Units      GPM
+ Headloss   H-W
+
+[COORDINATES]
+ J1  10  20
+
+[END]
+";
+
+    #[test]
+    fn reads_us_units() {
+        let net = parse_inp(NET).unwrap();
+        assert_eq!(net.title, "Two loop test");
+        assert_eq!(net.units, FlowUnits::Gpm);
+        assert_eq!(net.junctions.len(), 3);
+        assert!((net.junctions[1].elevation - 710.0 * 0.3048).abs() < 1e-9);
+        assert!((net.junctions[1].demand - 150.0 * 6.30901964e-5).abs() < 1e-12);
+        assert!((net.pipes[0].diameter - 18.0 * 0.0254).abs() < 1e-12);
+        assert_eq!(net.pipes[2].status, PipeStatus::CheckValve);
+        assert_eq!(net.valves[0].kind, ValveKind::Tcv);
+        assert_eq!(net.curves[0].points.len(), 1);
+        assert_eq!(net.coordinates, vec![("J1".to_string(), 10.0, 20.0)]);
+        let res = solve_pressure_network(&net, &SolverOptions::default()).unwrap();
+        assert!(res.converged);
+    }
+
+    #[test]
+    fn round_trip() {
+        let net = parse_inp(NET).unwrap();
+        let file = NamedTempFile::new().unwrap();
+        let path = file.path().to_str().unwrap();
+        write_inp(path, &net).unwrap();
+        let back = read_inp(path).unwrap();
+        assert_eq!(back.units, net.units);
+        assert_eq!(back.pipes.len(), net.pipes.len());
+        for (a, b) in back.pipes.iter().zip(&net.pipes) {
+            assert_eq!(a.status, b.status);
+            assert!((a.length - b.length).abs() < 1e-6);
+            assert!((a.diameter - b.diameter).abs() < 1e-9);
+        }
+        for (a, b) in back.junctions.iter().zip(&net.junctions) {
+            assert!((a.demand - b.demand).abs() < 1e-9);
+        }
+        assert_eq!(back.valves, net.valves);
+        assert!(parse_inp("[VALVES]\n V1 A B 100 FCV 5\n").is_err());
+    }
+}
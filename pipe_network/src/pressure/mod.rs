@@ -0,0 +1,984 @@
+//! Pressurised water distribution networks.
+//!
+//! Networks may contain loops, junction demands, reservoirs, tanks, pumps
+//! with head curves, pressure reducing and throttle control valves, and
+//! pipes with check valves. They are solved for a single steady state with
+//! the global gradient algorithm of Todini and Pilati, as used by EPANET,
+//! with tanks held at their initial level. All values are SI: metres and
+//! cubic metres per second.
+
+use std::collections::{BTreeSet, HashMap};
+use std::io;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{darcy_weisbach_headloss, hazen_williams_headloss, GRAVITY};
+
+pub mod inp;
+
+pub use inp::{read_inp, write_inp, FlowUnits};
+
+/// Resistance standing in for a closed link.
+const BIG: f64 = 1e8;
+/// Smallest head loss gradient, keeping links with no flow solvable.
+const RQTOL: f64 = 1e-7;
+/// Head tolerance for status changes (m).
+const HTOL: f64 = 1.5e-4;
+/// Flow tolerance for status changes (m^3/s).
+const QTOL: f64 = 2.8e-6;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HeadlossFormula {
+    /// Roughness is the Hazen-Williams C factor.
+    #[default]
+    HazenWilliams,
+    /// Roughness is the absolute roughness in metres.
+    DarcyWeisbach,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Junction {
+    pub id: String,
+    pub elevation: f64,
+    /// Demand drawn from the network (m^3/s).
+    pub demand: f64,
+}
+
+/// Source with a fixed head.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Reservoir {
+    pub id: String,
+    pub head: f64,
+}
+
+/// Storage tank, held at its initial level.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Tank {
+    pub id: String,
+    /// Elevation of the tank bottom.
+    pub elevation: f64,
+    pub init_level: f64,
+    pub min_level: f64,
+    pub max_level: f64,
+    pub diameter: f64,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PipeStatus {
+    #[default]
+    Open,
+    Closed,
+    /// Check valve allowing flow only from `from` to `to`.
+    CheckValve,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PressurePipe {
+    pub id: String,
+    pub from: String,
+    pub to: String,
+    pub length: f64,
+    pub diameter: f64,
+    /// Hazen-Williams C or Darcy-Weisbach roughness, per the network's
+    /// [`HeadlossFormula`].
+    pub roughness: f64,
+    pub minor_loss: f64,
+    pub status: PipeStatus,
+}
+
+/// Pump adding head from `from` to `to` along a head curve.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pump {
+    pub id: String,
+    pub from: String,
+    pub to: String,
+    pub curve: String,
+    /// Relative speed applied through the affinity laws.
+    pub speed: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ValveKind {
+    /// Pressure reducing valve; the setting is the downstream pressure (m).
+    Prv,
+    /// Throttle control valve; the setting is a minor loss coefficient.
+    Tcv,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Valve {
+    pub id: String,
+    pub from: String,
+    pub to: String,
+    pub diameter: f64,
+    pub kind: ValveKind,
+    pub setting: f64,
+    pub minor_loss: f64,
+}
+
+/// Pump head curve as `(flow, head)` points.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HeadCurve {
+    pub id: String,
+    pub points: Vec<(f64, f64)>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WaterNetwork {
+    pub title: String,
+    pub junctions: Vec<Junction>,
+    pub reservoirs: Vec<Reservoir>,
+    pub tanks: Vec<Tank>,
+    pub pipes: Vec<PressurePipe>,
+    pub pumps: Vec<Pump>,
+    pub valves: Vec<Valve>,
+    pub curves: Vec<HeadCurve>,
+    /// Node map coordinates.
+    pub coordinates: Vec<(String, f64, f64)>,
+    pub headloss: HeadlossFormula,
+    /// Units used when writing `.inp` files.
+    pub units: FlowUnits,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SolverOptions {
+    pub max_iterations: usize,
+    /// Convergence limit on the sum of flow changes over the sum of flows.
+    pub accuracy: f64,
+}
+
+impl Default for SolverOptions {
+    fn default() -> Self {
+        Self {
+            max_iterations: 200,
+            accuracy: 1e-3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LinkState {
+    Open,
+    Closed,
+    /// Pressure reducing valve holding its setting.
+    Active,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NodeResult {
+    pub id: String,
+    pub head: f64,
+    /// Head above the node elevation (m).
+    pub pressure: f64,
+    /// Flow leaving the network at the node; negative for sources.
+    pub demand: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LinkResult {
+    pub id: String,
+    pub flow: f64,
+    pub velocity: f64,
+    /// Head at `from` minus head at `to`; negative across running pumps.
+    pub headloss: f64,
+    pub state: LinkState,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PressureResults {
+    pub nodes: Vec<NodeResult>,
+    pub links: Vec<LinkResult>,
+    pub iterations: usize,
+    pub converged: bool,
+}
+
+/// Pump curve fitted to `h = h0 - r q^n` or interpolated between points.
+#[derive(Debug, Clone)]
+enum PumpModel {
+    Power { h0: f64, r: f64, n: f64 },
+    Table(Vec<(f64, f64)>),
+}
+
+impl PumpModel {
+    /// Fits one-point curves with a shutoff head of 4/3 the design head
+    /// and a maximum flow of twice the design flow, and three-point curves
+    /// starting at zero flow with a power function, as EPANET does.
+    fn fit(curve: &HeadCurve) -> io::Result<Self> {
+        let pts = &curve.points;
+        match pts.as_slice() {
+            [(q1, h1)] if *q1 > 0.0 && *h1 > 0.0 => Ok(PumpModel::Power {
+                h0: 4.0 / 3.0 * h1,
+                r: h1 / (3.0 * q1 * q1),
+                n: 2.0,
+            }),
+            [(q0, h0), (q1, h1), (q2, h2)]
+                if *q0 == 0.0 && q1 > q0 && q2 > q1 && h0 > h1 && h1 > h2 =>
+            {
+                let n = ((h0 - h2) / (h0 - h1)).ln() / (q2 / q1).ln();
+                Ok(PumpModel::Power {
+                    h0: *h0,
+                    r: (h0 - h1) / q1.powf(n),
+                    n,
+                })
+            }
+            _ if pts.len() >= 2 => {
+                let mut pts = pts.clone();
+                pts.sort_by(|a, b| a.0.total_cmp(&b.0));
+                Ok(PumpModel::Table(pts))
+            }
+            _ => Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("pump curve '{}' has no usable points", curve.id),
+            )),
+        }
+    }
+
+    /// Head gain and its derivative with respect to flow at `speed`.
+    fn gain(&self, flow: f64, speed: f64) -> (f64, f64) {
+        let q = flow.max(0.0);
+        match self {
+            PumpModel::Power { h0, r, n } => {
+                let r = r * speed.powf(2.0 - n);
+                (
+                    h0 * speed * speed - r * q.powf(*n),
+                    -n * r * q.powf(n - 1.0),
+                )
+            }
+            PumpModel::Table(pts) => {
+                let q = q / speed.max(1e-9);
+                let i = pts
+                    .windows(2)
+                    .position(|w| q <= w[1].0)
+                    .unwrap_or(pts.len() - 2);
+                let ((q0, h0), (q1, h1)) = (pts[i], pts[i + 1]);
+                let slope = (h1 - h0) / (q1 - q0);
+                (speed * speed * (h0 + slope * (q - q0)), speed * slope)
+            }
+        }
+    }
+
+    /// Flow used to start the iterations.
+    fn initial_flow(&self) -> f64 {
+        match self {
+            PumpModel::Power { h0, r, n } if *r > 0.0 => (h0 / (2.0 * r)).powf(1.0 / n),
+            PumpModel::Table(pts) => pts[pts.len() / 2].0,
+            _ => 1e-3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum End {
+    Junction(usize),
+    Fixed(f64),
+}
+
+enum LinkKind<'a> {
+    Pipe(&'a PressurePipe),
+    Pump(&'a Pump, PumpModel),
+    Valve(&'a Valve),
+}
+
+struct Link<'a> {
+    id: &'a str,
+    from: End,
+    to: End,
+    kind: LinkKind<'a>,
+}
+
+/// Head loss and its gradient through a pipe or open valve.
+fn pipe_loss(
+    formula: HeadlossFormula,
+    flow: f64,
+    length: f64,
+    diameter: f64,
+    roughness: f64,
+    minor_loss: f64,
+) -> (f64, f64) {
+    let q = flow.abs();
+    let (mut h, mut dh) = if length > 0.0 {
+        let h = match formula {
+            HeadlossFormula::HazenWilliams => {
+                hazen_williams_headloss(q, length, diameter, roughness)
+            }
+            HeadlossFormula::DarcyWeisbach => {
+                darcy_weisbach_headloss(q, length, diameter, roughness)
+            }
+        };
+        let exponent = match formula {
+            HeadlossFormula::HazenWilliams => 1.852,
+            HeadlossFormula::DarcyWeisbach => 2.0,
+        };
+        (h, if q > 0.0 { exponent * h / q } else { 0.0 })
+    } else {
+        (0.0, 0.0)
+    };
+    if minor_loss > 0.0 && diameter > 0.0 {
+        let m = 8.0 * minor_loss / (GRAVITY * std::f64::consts::PI.powi(2) * diameter.powi(4));
+        h += m * q * q;
+        dh += 2.0 * m * q;
+    }
+    (h * flow.signum(), dh.max(RQTOL))
+}
+
+fn area(diameter: f64) -> f64 {
+    std::f64::consts::PI * diameter * diameter / 4.0
+}
+
+/// Sparse symmetric positive definite matrix over the junctions, solved by
+/// Cholesky factorisation. The fill-in depends only on the network topology,
+/// so it is found once with a minimum degree ordering and reused for every
+/// iteration, as EPANET does.
+struct SparseCholesky {
+    /// Position of each junction in the elimination order.
+    position: Vec<usize>,
+    /// Start of each column's entries in `rows` and `values`.
+    col_start: Vec<usize>,
+    /// Positions of the entries below the diagonal, ascending per column.
+    rows: Vec<usize>,
+    diag: Vec<f64>,
+    values: Vec<f64>,
+}
+
+impl SparseCholesky {
+    /// Builds the pattern for `n` junctions joined by the `(i, j)` pairs.
+    fn new(n: usize, pairs: impl IntoIterator<Item = (usize, usize)>) -> Self {
+        let mut adjacent = vec![BTreeSet::new(); n];
+        for (i, j) in pairs {
+            if i != j {
+                adjacent[i].insert(j);
+                adjacent[j].insert(i);
+            }
+        }
+        let mut queue: BTreeSet<(usize, usize)> = (0..n).map(|i| (adjacent[i].len(), i)).collect();
+        let mut position = vec![0; n];
+        let mut columns = Vec::with_capacity(n);
+        while let Some((_, node)) = queue.pop_first() {
+            position[node] = columns.len();
+            let neighbours = std::mem::take(&mut adjacent[node]);
+            for &a in &neighbours {
+                queue.remove(&(adjacent[a].len(), a));
+                adjacent[a].remove(&node);
+                adjacent[a].extend(neighbours.iter().filter(|&&b| b != a));
+                queue.insert((adjacent[a].len(), a));
+            }
+            columns.push(neighbours);
+        }
+        let mut col_start = vec![0];
+        let mut rows = Vec::new();
+        for column in columns {
+            let mut col: Vec<usize> = column.into_iter().map(|i| position[i]).collect();
+            col.sort_unstable();
+            rows.extend(col);
+            col_start.push(rows.len());
+        }
+        Self {
+            position,
+            col_start,
+            diag: vec![0.0; n],
+            values: vec![0.0; rows.len()],
+            rows,
+        }
+    }
+
+    fn clear(&mut self) {
+        self.diag.fill(0.0);
+        self.values.fill(0.0);
+    }
+
+    /// Index into `values` of the entry at positions `row > col`.
+    fn slot(col_start: &[usize], rows: &[usize], row: usize, col: usize) -> usize {
+        let s = col_start[col];
+        s + rows[s..col_start[col + 1]].binary_search(&row).unwrap()
+    }
+
+    /// Adds `value` at junctions `(i, j)`, and at `(j, i)` when off the diagonal.
+    fn add(&mut self, i: usize, j: usize, value: f64) {
+        let (pi, pj) = (self.position[i], self.position[j]);
+        if pi == pj {
+            self.diag[pi] += value;
+        } else {
+            let slot = Self::slot(&self.col_start, &self.rows, pi.max(pj), pi.min(pj));
+            self.values[slot] += value;
+        }
+    }
+
+    /// Factorises in place and solves for the junction values.
+    fn solve(&mut self, b: &[f64]) -> io::Result<Vec<f64>> {
+        let n = self.diag.len();
+        let (col_start, rows, values) = (&self.col_start, &self.rows, &mut self.values);
+        for k in 0..n {
+            if self.diag[k] < 1e-300 {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "network has junctions without a path to a fixed head",
+                ));
+            }
+            let d = self.diag[k].sqrt();
+            self.diag[k] = d;
+            let (s, e) = (col_start[k], col_start[k + 1]);
+            for a in s..e {
+                values[a] /= d;
+                let (i, lik) = (rows[a], values[a]);
+                self.diag[i] -= lik * lik;
+                for c in s..a {
+                    let slot = Self::slot(col_start, rows, i, rows[c]);
+                    values[slot] -= lik * values[c];
+                }
+            }
+        }
+        let mut x = vec![0.0; n];
+        for (i, &p) in self.position.iter().enumerate() {
+            x[p] = b[i];
+        }
+        for k in 0..n {
+            x[k] /= self.diag[k];
+            for a in col_start[k]..col_start[k + 1] {
+                x[rows[a]] -= values[a] * x[k];
+            }
+        }
+        for k in (0..n).rev() {
+            let sum: f64 = (col_start[k]..col_start[k + 1])
+                .map(|a| values[a] * x[rows[a]])
+                .sum();
+            x[k] = (x[k] - sum) / self.diag[k];
+        }
+        Ok(self.position.iter().map(|&p| x[p]).collect())
+    }
+}
+
+/// Solves junction heads and link flows. Results that do not converge
+/// within the iteration limit are returned with `converged` unset.
+pub fn solve_pressure_network(
+    net: &WaterNetwork,
+    options: &SolverOptions,
+) -> io::Result<PressureResults> {
+    let mut ends: HashMap<&str, End> = HashMap::new();
+    for (i, j) in net.junctions.iter().enumerate() {
+        ends.insert(&j.id, End::Junction(i));
+    }
+    for r in &net.reservoirs {
+        ends.insert(&r.id, End::Fixed(r.head));
+    }
+    for t in &net.tanks {
+        ends.insert(&t.id, End::Fixed(t.elevation + t.init_level));
+    }
+    let end = |id: &str| {
+        ends.get(id).copied().ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("unknown node '{id}'"))
+        })
+    };
+    let mut links = Vec::new();
+    for p in &net.pipes {
+        links.push(Link {
+            id: &p.id,
+            from: end(&p.from)?,
+            to: end(&p.to)?,
+            kind: LinkKind::Pipe(p),
+        });
+    }
+    for p in &net.pumps {
+        let curve = net.curves.iter().find(|c| c.id == p.curve).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("unknown curve '{}'", p.curve),
+            )
+        })?;
+        links.push(Link {
+            id: &p.id,
+            from: end(&p.from)?,
+            to: end(&p.to)?,
+            kind: LinkKind::Pump(p, PumpModel::fit(curve)?),
+        });
+    }
+    for v in &net.valves {
+        links.push(Link {
+            id: &v.id,
+            from: end(&v.from)?,
+            to: end(&v.to)?,
+            kind: LinkKind::Valve(v),
+        });
+    }
+
+    let n = net.junctions.len();
+    let prv_head = |link: &Link| match (&link.kind, link.to) {
+        (LinkKind::Valve(v), End::Junction(b)) if v.kind == ValveKind::Prv => {
+            Some(net.junctions[b].elevation + v.setting)
+        }
+        _ => None,
+    };
+    let mut flows: Vec<f64> = links
+        .iter()
+        .map(|l| match &l.kind {
+            LinkKind::Pipe(p) => 0.3048 * area(p.diameter),
+            LinkKind::Pump(_, model) => model.initial_flow(),
+            LinkKind::Valve(v) => 0.3048 * area(v.diameter),
+        })
+        .collect();
+    let mut states: Vec<LinkState> = links
+        .iter()
+        .map(|l| match &l.kind {
+            LinkKind::Pipe(p) if p.status == PipeStatus::Closed => LinkState::Closed,
+            _ if prv_head(l).is_some() => LinkState::Active,
+            _ => LinkState::Open,
+        })
+        .collect();
+    let mut matrix = SparseCholesky::new(
+        n,
+        links.iter().filter_map(|l| match (l.from, l.to) {
+            (End::Junction(i), End::Junction(j)) => Some((i, j)),
+            _ => None,
+        }),
+    );
+    let mut heads = vec![0.0; n];
+    let mut converged = false;
+    let mut iterations = 0;
+    while iterations < options.max_iterations && !converged {
+        iterations += 1;
+        matrix.clear();
+        let mut f: Vec<f64> = net.junctions.iter().map(|j| -j.demand).collect();
+        let mut coeffs = vec![(0.0, 0.0); links.len()];
+        for (k, link) in links.iter().enumerate() {
+            let q = flows[k];
+            if states[k] == LinkState::Active {
+                // the valve fixes its downstream head and passes whatever
+                // flow balances that junction
+                let (End::Junction(b), Some(hset)) = (link.to, prv_head(link)) else {
+                    continue;
+                };
+                if let End::Junction(a) = link.from {
+                    f[a] -= q;
+                }
+                f[b] += q + BIG * hset;
+                matrix.add(b, b, BIG);
+                continue;
+            }
+            let (p, y) = if states[k] == LinkState::Closed {
+                (1.0 / BIG, q)
+            } else {
+                let (h, dh) = match &link.kind {
+                    LinkKind::Pipe(p) => pipe_loss(
+                        net.headloss,
+                        q,
+                        p.length,
+                        p.diameter,
+                        p.roughness,
+                        p.minor_loss,
+                    ),
+                    LinkKind::Pump(p, model) => {
+                        let (gain, slope) = model.gain(q, p.speed);
+                        (-gain, (-slope).max(RQTOL))
+                    }
+                    LinkKind::Valve(v) => {
+                        let k = match v.kind {
+                            ValveKind::Tcv => v.setting,
+                            ValveKind::Prv => v.minor_loss,
+                        };
+                        pipe_loss(net.headloss, q, 0.0, v.diameter, 0.0, k)
+                    }
+                };
+                (1.0 / dh, h / dh)
+            };
+            coeffs[k] = (p, y);
+            match (link.from, link.to) {
+                (End::Junction(i), End::Junction(j)) => {
+                    matrix.add(i, i, p);
+                    matrix.add(j, j, p);
+                    matrix.add(i, j, -p);
+                    f[i] -= q - y;
+                    f[j] += q - y;
+                }
+                (End::Junction(i), End::Fixed(h)) => {
+                    matrix.add(i, i, p);
+                    f[i] += p * h - (q - y);
+                }
+                (End::Fixed(h), End::Junction(j)) => {
+                    matrix.add(j, j, p);
+                    f[j] += p * h + (q - y);
+                }
+                (End::Fixed(_), End::Fixed(_)) => {}
+            }
+        }
+        heads = matrix.solve(&f)?;
+        let head = |e: End| match e {
+            End::Junction(i) => heads[i],
+            End::Fixed(h) => h,
+        };
+
+        let mut new_flows = flows.clone();
+        for (k, link) in links.iter().enumerate() {
+            if states[k] != LinkState::Active {
+                let (p, y) = coeffs[k];
+                new_flows[k] = flows[k] - y + p * (head(link.from) - head(link.to));
+            }
+        }
+        for (k, link) in links.iter().enumerate() {
+            if states[k] == LinkState::Active {
+                let End::Junction(b) = link.to else {
+                    continue;
+                };
+                let mut q = net.junctions[b].demand;
+                for (j, other) in links.iter().enumerate() {
+                    if j == k {
+                        continue;
+                    }
+                    if matches!(other.from, End::Junction(i) if i == b) {
+                        q += new_flows[j];
+                    }
+                    if matches!(other.to, End::Junction(i) if i == b) {
+                        q -= new_flows[j];
+                    }
+                }
+                new_flows[k] = q;
+            }
+        }
+        let change: f64 = new_flows
+            .iter()
+            .zip(&flows)
+            .map(|(a, b)| (a - b).abs())
+            .sum();
+        let total: f64 = new_flows.iter().map(|q| q.abs()).sum();
+        flows = new_flows;
+
+        let mut changed = false;
+        for (k, link) in links.iter().enumerate() {
+            let (ha, hb, q) = (head(link.from), head(link.to), flows[k]);
+            let state = states[k];
+            let next = match &link.kind {
+                LinkKind::Pipe(p) if p.status == PipeStatus::CheckValve => match state {
+                    LinkState::Open if q < -QTOL || ha - hb < -HTOL => LinkState::Closed,
+                    LinkState::Closed if ha - hb > HTOL => LinkState::Open,
+                    s => s,
+                },
+                LinkKind::Pump(p, model) => {
+                    let shutoff = model.gain(0.0, p.speed).0;
+                    match state {
+                        LinkState::Open if hb - ha > shutoff + HTOL || q < -QTOL => {
+                            LinkState::Closed
+                        }
+                        LinkState::Closed if hb - ha < shutoff - HTOL => LinkState::Open,
+                        s => s,
+                    }
+                }
+                _ => match prv_head(link) {
+                    Some(hset) => match state {
+                        LinkState::Active if q < -QTOL => LinkState::Closed,
+                        LinkState::Active if ha < hset - HTOL => LinkState::Open,
+                        LinkState::Open if q < -QTOL => LinkState::Closed,
+                        LinkState::Open if hb > hset + HTOL => LinkState::Active,
+                        LinkState::Closed if ha > hset + HTOL && hb < hset - HTOL => {
+                            LinkState::Active
+                        }
+                        LinkState::Closed if ha < hset - HTOL && ha > hb + HTOL => LinkState::Open,
+                        s => s,
+                    },
+                    None => state,
+                },
+            };
+            if next != state {
+                states[k] = next;
+                changed = true;
+            }
+        }
+        converged = !changed && (total == 0.0 || change / total <= options.accuracy);
+    }
+
+    let head = |e: End| match e {
+        End::Junction(i) => heads[i],
+        End::Fixed(h) => h,
+    };
+    let mut outflow: HashMap<&str, f64> = HashMap::new();
+    let link_ends = net
+        .pipes
+        .iter()
+        .map(|p| (&p.from, &p.to))
+        .chain(net.pumps.iter().map(|p| (&p.from, &p.to)))
+        .chain(net.valves.iter().map(|v| (&v.from, &v.to)));
+    for ((from, to), q) in link_ends.zip(&flows) {
+        *outflow.entry(from).or_default() -= q;
+        *outflow.entry(to).or_default() += q;
+    }
+    let mut nodes: Vec<NodeResult> = net
+        .junctions
+        .iter()
+        .zip(&heads)
+        .map(|(j, h)| NodeResult {
+            id: j.id.clone(),
+            head: *h,
+            pressure: h - j.elevation,
+            demand: j.demand,
+        })
+        .collect();
+    for r in &net.reservoirs {
+        nodes.push(NodeResult {
+            id: r.id.clone(),
+            head: r.head,
+            pressure: 0.0,
+            demand: outflow.get(r.id.as_str()).copied().unwrap_or(0.0),
+        });
+    }
+    for t in &net.tanks {
+        nodes.push(NodeResult {
+            id: t.id.clone(),
+            head: t.elevation + t.init_level,
+            pressure: t.init_level,
+            demand: outflow.get(t.id.as_str()).copied().unwrap_or(0.0),
+        });
+    }
+    let links = links
+        .iter()
+        .zip(flows.iter().zip(&states))
+        .map(|(link, (&q, &state))| {
+            let diameter = match &link.kind {
+                LinkKind::Pipe(p) => p.diameter,
+                LinkKind::Valve(v) => v.diameter,
+                LinkKind::Pump(..) => 0.0,
+            };
+            let flow = if state == LinkState::Closed { 0.0 } else { q };
+            LinkResult {
+                id: link.id.to_string(),
+                flow,
+                velocity: if diameter > 0.0 {
+                    flow.abs() / area(diameter)
+                } else {
+                    0.0
+                },
+                headloss: head(link.from) - head(link.to),
+                state,
+            }
+        })
+        .collect();
+    Ok(PressureResults {
+        nodes,
+        links,
+        iterations,
+        converged,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn junction(id: &str, elevation: f64, demand: f64) -> Junction {
+        Junction {
+            id: id.into(),
+            elevation,
+            demand,
+        }
+    }
+
+    fn pipe(id: &str, from: &str, to: &str, length: f64, diameter: f64) -> PressurePipe {
+        PressurePipe {
+            id: id.into(),
+            from: from.into(),
+            to: to.into(),
+            length,
+            diameter,
+            roughness: 120.0,
+            ..Default::default()
+        }
+    }
+
+    fn reservoir(id: &str, head: f64) -> Reservoir {
+        Reservoir {
+            id: id.into(),
+            head,
+        }
+    }
+
+    fn link<'a>(res: &'a PressureResults, id: &str) -> &'a LinkResult {
+        res.links.iter().find(|l| l.id == id).unwrap()
+    }
+
+    fn node<'a>(res: &'a PressureResults, id: &str) -> &'a NodeResult {
+        res.nodes.iter().find(|n| n.id == id).unwrap()
+    }
+
+    #[test]
+    fn looped_network_balances() {
+        // R feeds a loop A-B-C-D with demands at B and D
+        let net = WaterNetwork {
+            reservoirs: vec![reservoir("R", 100.0)],
+            junctions: vec![
+                junction("A", 50.0, 0.0),
+                junction("B", 50.0, 0.02),
+                junction("C", 45.0, 0.01),
+                junction("D", 48.0, 0.03),
+            ],
+            pipes: vec![
+                pipe("RA", "R", "A", 500.0, 0.3),
+                pipe("AB", "A", "B", 300.0, 0.2),
+                pipe("BC", "B", "C", 300.0, 0.15),
+                pipe("CD", "C", "D", 300.0, 0.15),
+                pipe("DA", "D", "A", 300.0, 0.2),
+            ],
+            ..Default::default()
+        };
+        let res = solve_pressure_network(&net, &SolverOptions::default()).unwrap();
+        assert!(res.converged);
+        assert!((link(&res, "RA").flow - 0.06).abs() < 1e-6);
+        assert!((node(&res, "R").demand + 0.06).abs() < 1e-6);
+        // continuity at every junction
+        for j in &net.junctions {
+            let inflow: f64 = net
+                .pipes
+                .iter()
+                .map(|p| {
+                    let q = link(&res, &p.id).flow;
+                    (if p.to == j.id { q } else { 0.0 }) - (if p.from == j.id { q } else { 0.0 })
+                })
+                .sum();
+            assert!((inflow - j.demand).abs() < 1e-5, "{}: {inflow}", j.id);
+        }
+        // head losses around the loop sum to zero and match Hazen-Williams
+        let loop_loss: f64 = ["AB", "BC", "CD", "DA"]
+            .iter()
+            .map(|id| link(&res, id).headloss)
+            .sum();
+        assert!(loop_loss.abs() < 1e-3, "{loop_loss}");
+        let ra = link(&res, "RA");
+        let hw = hazen_williams_headloss(ra.flow, 500.0, 0.3, 120.0);
+        assert!((ra.headloss - hw).abs() < 1e-3);
+        assert!((node(&res, "A").pressure - (100.0 - hw - 50.0)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn darcy_weisbach_matches_single_pipe() {
+        let net = WaterNetwork {
+            reservoirs: vec![reservoir("R", 30.0)],
+            junctions: vec![junction("J", 0.0, 0.05)],
+            pipes: vec![PressurePipe {
+                roughness: 0.0001,
+                ..pipe("P", "R", "J", 1000.0, 0.25)
+            }],
+            headloss: HeadlossFormula::DarcyWeisbach,
+            ..Default::default()
+        };
+        let res = solve_pressure_network(&net, &SolverOptions::default()).unwrap();
+        let h = darcy_weisbach_headloss(0.05, 1000.0, 0.25, 0.0001);
+        assert!((node(&res, "J").head - (30.0 - h)).abs() < 1e-3);
+    }
+
+    #[test]
+    fn pump_lifts_to_upper_reservoir() {
+        let net = WaterNetwork {
+            reservoirs: vec![reservoir("Low", 10.0), reservoir("High", 40.0)],
+            junctions: vec![junction("S", 10.0, 0.0), junction("D", 10.0, 0.0)],
+            pipes: vec![
+                pipe("In", "Low", "S", 10.0, 0.3),
+                pipe("Out", "D", "High", 1000.0, 0.3),
+            ],
+            pumps: vec![Pump {
+                id: "P".into(),
+                from: "S".into(),
+                to: "D".into(),
+                curve: "C".into(),
+                speed: 1.0,
+            }],
+            curves: vec![HeadCurve {
+                id: "C".into(),
+                points: vec![(0.05, 36.0)],
+            }],
+            ..Default::default()
+        };
+        let res = solve_pressure_network(&net, &SolverOptions::default()).unwrap();
+        assert!(res.converged);
+        let q = link(&res, "P").flow;
+        assert!(q > 0.0);
+        let gain = -link(&res, "P").headloss;
+        // one-point curve: h = 48 - 4800 q^2
+        assert!((gain - (48.0 - 4800.0 * q * q)).abs() < 1e-3);
+        let losses = link(&res, "In").headloss + link(&res, "Out").headloss;
+        assert!((gain - 30.0 - losses).abs() < 1e-3);
+
+        // against a head above shutoff the pump closes
+        let mut high = net.clone();
+        high.reservoirs[1].head = 70.0;
+        let res = solve_pressure_network(&high, &SolverOptions::default()).unwrap();
+        assert_eq!(link(&res, "P").state, LinkState::Closed);
+        assert!(link(&res, "P").flow.abs() < 1e-9);
+    }
+
+    #[test]
+    fn prv_and_check_valve() {
+        let net = WaterNetwork {
+            reservoirs: vec![reservoir("R", 100.0), reservoir("Back", 90.0)],
+            junctions: vec![
+                junction("A", 20.0, 0.0),
+                junction("B", 20.0, 0.02),
+                junction("C", 20.0, 0.0),
+            ],
+            pipes: vec![
+                pipe("RA", "R", "A", 200.0, 0.2),
+                pipe("BC", "B", "C", 200.0, 0.2),
+                PressurePipe {
+                    status: PipeStatus::CheckValve,
+                    ..pipe("CV", "C", "Back", 200.0, 0.2)
+                },
+            ],
+            valves: vec![Valve {
+                id: "V".into(),
+                from: "A".into(),
+                to: "B".into(),
+                diameter: 0.2,
+                kind: ValveKind::Prv,
+                setting: 40.0,
+                minor_loss: 0.0,
+            }],
+            ..Default::default()
+        };
+        let res = solve_pressure_network(&net, &SolverOptions::default()).unwrap();
+        assert!(res.converged);
+        assert_eq!(link(&res, "V").state, LinkState::Active);
+        assert!((node(&res, "B").pressure - 40.0).abs() < 1e-3);
+        assert!((link(&res, "V").flow - 0.02).abs() < 1e-6);
+        // the downstream reservoir at 90 m would otherwise backfeed B
+        assert_eq!(link(&res, "CV").state, LinkState::Closed);
+        assert!(link(&res, "CV").flow.abs() < 1e-9);
+    }
+
+    #[test]
+    fn sparse_cholesky_solves_grid() {
+        // 4x4 grid Laplacian with one corner tied to a fixed head
+        let n = 16;
+        let mut pairs = Vec::new();
+        for i in 0..n {
+            if i % 4 != 3 {
+                pairs.push((i, i + 1));
+            }
+            if i + 4 < n {
+                pairs.push((i, i + 4));
+            }
+        }
+        let mut matrix = SparseCholesky::new(n, pairs.iter().copied());
+        let mut dense = vec![vec![0.0; n]; n];
+        for (k, &(i, j)) in pairs.iter().enumerate() {
+            let p = 1.0 + k as f64;
+            matrix.add(i, i, p);
+            matrix.add(j, j, p);
+            matrix.add(i, j, -p);
+            dense[i][i] += p;
+            dense[j][j] += p;
+            dense[i][j] -= p;
+            dense[j][i] -= p;
+        }
+        matrix.add(0, 0, 2.0);
+        dense[0][0] += 2.0;
+        let expected: Vec<f64> = (0..n).map(|i| 10.0 + i as f64).collect();
+        let b: Vec<f64> = dense
+            .iter()
+            .map(|row| row.iter().zip(&expected).map(|(a, x)| a * x).sum())
+            .collect();
+        let x = matrix.solve(&b).unwrap();
+        for (x, e) in x.iter().zip(&expected) {
+            assert!((x - e).abs() < 1e-9);
+        }
+
+        let mut floating = SparseCholesky::new(2, [(0, 1)]);
+        floating.add(0, 0, 1.0);
+        floating.add(1, 1, 1.0);
+        floating.add(0, 1, -1.0);
+        assert!(floating.solve(&[0.0, 0.0]).is_err());
+    }
+}
@@ -0,0 +1,292 @@
+//! Profile and plan sheet data for gravity networks, written to SVG by
+//! [`survey_cad::sheet`].
+
+use std::io;
+
+use survey_cad::dtm::Tin;
+use survey_cad::geometry::Point;
+use survey_cad::sheet::{
+    PlanPipe, PlanStructure, ProfileCrossing, ProfilePipe, ProfileStructure, UtilityPlan,
+    UtilityProfile,
+};
+
+use crate::hgl::GradeResults;
+use crate::rules::ground_profile;
+use crate::{Network, Pipe, Structure};
+
+fn invalid(msg: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+/// Plan position of a crossing along segment `a`-`b` of another segment
+/// `c`-`d`, as the parameters on both segments.
+fn segment_crossing(a: Point, b: Point, c: Point, d: Point) -> Option<(f64, f64)> {
+    let (r, s) = ((b.x - a.x, b.y - a.y), (d.x - c.x, d.y - c.y));
+    let denom = r.0 * s.1 - r.1 * s.0;
+    if denom.abs() < f64::EPSILON {
+        return None;
+    }
+    let (qx, qy) = (c.x - a.x, c.y - a.y);
+    let t = (qx * s.1 - qy * s.0) / denom;
+    let u = (qx * r.1 - qy * r.0) / denom;
+    (t > 0.0 && t < 1.0 && u > 0.0 && u < 1.0).then_some((t, u))
+}
+
+/// Builds a profile along `path`, a list of connected pipe ids in either
+/// direction. Ground comes from `surface` where given and the structure
+/// rims otherwise; the HGL is taken from `grades` and crossings are the
+/// pipes of `crossing` networks passing over or under the path in plan.
+pub fn pipe_profile(
+    net: &Network,
+    path: &[&str],
+    surface: Option<&Tin>,
+    grades: Option<&GradeResults>,
+    crossing: &[&Network],
+) -> io::Result<UtilityProfile> {
+    let pipes = path
+        .iter()
+        .map(|id| {
+            net.pipes
+                .iter()
+                .find(|p| p.id == *id)
+                .ok_or_else(|| invalid(format!("unknown pipe {id}")))
+        })
+        .collect::<io::Result<Vec<&Pipe>>>()?;
+    let structure = |id: &str| {
+        net.structures
+            .iter()
+            .find(|s| s.id == id)
+            .ok_or_else(|| invalid(format!("unknown structure {id}")))
+    };
+    let Some(first) = pipes.first() else {
+        return Ok(UtilityProfile::default());
+    };
+    let mut at = match pipes.get(1) {
+        Some(next) if first.from == next.from || first.from == next.to => first.to.as_str(),
+        _ => first.from.as_str(),
+    };
+
+    let mut profile = UtilityProfile::default();
+    let mut station = 0.0;
+    let mut structures: Vec<(&Structure, f64, f64)> = Vec::new();
+    for (i, pipe) in pipes.iter().enumerate() {
+        if pipes[..i].iter().any(|p| p.id == pipe.id) {
+            return Err(invalid(format!("pipe {} repeats in the path", pipe.id)));
+        }
+        let reversed = pipe.from != at;
+        if reversed && pipe.to != at {
+            return Err(invalid(format!(
+                "pipe {} is not connected to {at}",
+                pipe.id
+            )));
+        }
+        let (a, b) = if reversed {
+            (structure(&pipe.to)?, structure(&pipe.from)?)
+        } else {
+            (structure(&pipe.from)?, structure(&pipe.to)?)
+        };
+        let (inv_a, inv_b) = if reversed {
+            (pipe.end_invert, pipe.start_invert)
+        } else {
+            (pipe.start_invert, pipe.end_invert)
+        };
+        let length = (b.x - a.x).hypot(b.y - a.y);
+        if i == 0 {
+            structures.push((a, station, inv_a));
+        }
+        structures.push((b, station + length, inv_b));
+
+        let mut ground = ground_profile(net, pipe, surface);
+        if reversed {
+            ground.reverse();
+            for g in &mut ground {
+                g.0 = length - g.0;
+            }
+        }
+        let skip = usize::from(i > 0);
+        profile.ground.extend(
+            ground
+                .into_iter()
+                .skip(skip)
+                .map(|(d, z)| Point::new(station + d, z)),
+        );
+
+        profile.pipes.push(ProfilePipe {
+            label: pipe.id.clone(),
+            start: Point::new(station, inv_a),
+            end: Point::new(station + length, inv_b),
+            inner_diameter: pipe.diameter,
+            outer_diameter: pipe.outer_diameter(),
+        });
+
+        if let Some(g) = grades.and_then(|g| g.pipes.iter().find(|g| g.pipe == pipe.id)) {
+            let (up, down) = if reversed {
+                (g.hgl_down, g.hgl_up)
+            } else {
+                (g.hgl_up, g.hgl_down)
+            };
+            profile.hgl.push(Point::new(station, up));
+            profile.hgl.push(Point::new(station + length, down));
+        }
+
+        let (pa, pb) = (Point::new(a.x, a.y), Point::new(b.x, b.y));
+        for other in crossing {
+            let idx = other.structure_index();
+            for p in &other.pipes {
+                let (Some(&c), Some(&d)) = (idx.get(p.from.as_str()), idx.get(p.to.as_str()))
+                else {
+                    continue;
+                };
+                let (c, d) = (&other.structures[c], &other.structures[d]);
+                let (pc, pd) = (Point::new(c.x, c.y), Point::new(d.x, d.y));
+                if let Some((t, u)) = segment_crossing(pa, pb, pc, pd) {
+                    let invert = p.start_invert + (p.end_invert - p.start_invert) * u;
+                    profile.crossings.push(ProfileCrossing {
+                        label: p.id.clone(),
+                        station: station + length * t,
+                        centre: invert + p.diameter / 2.0,
+                        diameter: p.outer_diameter(),
+                    });
+                }
+            }
+        }
+
+        station += length;
+        at = if reversed {
+            pipe.from.as_str()
+        } else {
+            pipe.to.as_str()
+        };
+    }
+    profile.structures = structures
+        .into_iter()
+        .map(|(s, station, invert)| ProfileStructure {
+            label: s.id.clone(),
+            station,
+            width: s.inside_diameter(),
+            floor: net.sump_elevation(&s.id).unwrap_or(invert),
+            rim: s.z,
+        })
+        .collect();
+    Ok(profile)
+}
+
+/// Plan view of every pipe and structure in the network.
+pub fn network_plan(net: &Network) -> UtilityPlan {
+    let idx = net.structure_index();
+    let pipes = net
+        .pipes
+        .iter()
+        .filter_map(|p| {
+            let a = &net.structures[*idx.get(p.from.as_str())?];
+            let b = &net.structures[*idx.get(p.to.as_str())?];
+            Some(PlanPipe {
+                label: p.id.clone(),
+                start: Point::new(a.x, a.y),
+                end: Point::new(b.x, b.y),
+            })
+        })
+        .collect();
+    let structures = net
+        .structures
+        .iter()
+        .map(|s| PlanStructure {
+            label: s.id.clone(),
+            position: Point::new(s.x, s.y),
+            diameter: s.inside_diameter(),
+        })
+        .collect();
+    UtilityPlan { pipes, structures }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hgl::{hydraulic_grade_lines, GradeOptions};
+    use crate::DEFAULT_MANNING_N;
+    use survey_cad::sheet::{write_utility_plan_svg, write_utility_profile_svg};
+
+    fn structure(id: &str, x: f64, y: f64, z: f64) -> Structure {
+        Structure {
+            id: id.into(),
+            x,
+            y,
+            z,
+            sump: 0.3,
+            ..Default::default()
+        }
+    }
+
+    fn pipe(id: &str, from: &str, to: &str, start: f64, end: f64) -> Pipe {
+        Pipe {
+            id: id.into(),
+            from: from.into(),
+            to: to.into(),
+            diameter: 0.3,
+            c: 120.0,
+            start_invert: start,
+            end_invert: end,
+            design_flow: 0.05,
+            manning_n: DEFAULT_MANNING_N,
+        }
+    }
+
+    #[test]
+    fn profile_along_reversed_path() {
+        let net = Network {
+            structures: vec![
+                structure("A", 0.0, 0.0, 12.0),
+                structure("B", 40.0, 0.0, 11.5),
+                structure("C", 40.0, 30.0, 11.0),
+            ],
+            pipes: vec![
+                pipe("P1", "A", "B", 10.0, 9.6),
+                pipe("P2", "B", "C", 9.5, 9.2),
+            ],
+        };
+        let water = Network {
+            structures: vec![
+                structure("W1", 20.0, -10.0, 12.0),
+                structure("W2", 20.0, 10.0, 12.0),
+            ],
+            pipes: vec![pipe("W", "W1", "W2", 10.5, 10.5)],
+        };
+        let grades = hydraulic_grade_lines(
+            &net,
+            &GradeOptions {
+                tailwater: 9.4,
+                min_cover: 0.0,
+                min_freeboard: 0.0,
+            },
+        );
+        let profile = pipe_profile(&net, &["P2", "P1"], None, Some(&grades), &[&water]).unwrap();
+
+        let labels: Vec<&str> = profile
+            .structures
+            .iter()
+            .map(|s| s.label.as_str())
+            .collect();
+        assert_eq!(labels, ["C", "B", "A"]);
+        assert!((profile.structures[2].station - 70.0).abs() < 1e-9);
+        assert!((profile.structures[0].floor - 8.9).abs() < 1e-9);
+        assert_eq!(profile.ground.len(), 3);
+        assert!((profile.pipes[0].start.y - 9.2).abs() < 1e-9);
+        assert!(profile.pipes[0].outer_diameter > profile.pipes[0].inner_diameter);
+        assert_eq!(profile.hgl.len(), 4);
+        assert_eq!(profile.crossings.len(), 1);
+        assert!((profile.crossings[0].station - 50.0).abs() < 1e-9);
+        assert!((profile.crossings[0].centre - 10.65).abs() < 1e-9);
+
+        assert!(pipe_profile(&net, &["P1", "P1"], None, None, &[]).is_err());
+        assert!(pipe_profile(&net, &["X"], None, None, &[]).is_err());
+
+        let dir = tempfile::tempdir().unwrap();
+        let svg = dir.path().join("profile.svg");
+        write_utility_profile_svg(svg.to_str().unwrap(), &profile, 0.5, 0.05, 10.0).unwrap();
+        let text = std::fs::read_to_string(&svg).unwrap();
+        assert!(text.contains("<ellipse") && text.contains(">P1<"));
+        let plan = dir.path().join("plan.svg");
+        write_utility_plan_svg(plan.to_str().unwrap(), &network_plan(&net), 0.5).unwrap();
+        assert!(std::fs::read_to_string(&plan).unwrap().contains(">C<"));
+    }
+}
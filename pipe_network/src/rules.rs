@@ -0,0 +1,384 @@
+//! Design rule checks and automatic invert layout.
+
+use std::cmp::Ordering;
+use std::io;
+
+use serde::{Deserialize, Serialize};
+use survey_cad::dtm::Tin;
+
+use crate::{parse_num, Network, Pipe, Violation, ViolationKind};
+
+/// Spacing of ground samples used for cover along a pipe (m).
+const COVER_SAMPLE_SPACING: f64 = 5.0;
+
+/// Slope range for pipes of at least `min_diameter`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SlopeLimit {
+    pub min_diameter: f64,
+    pub min_slope: f64,
+    pub max_slope: f64,
+}
+
+/// How inverts line up where pipes meet in a structure.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MatchMode {
+    #[default]
+    Inverts,
+    /// Outlet crown no higher than the inlet crown.
+    Crowns,
+}
+
+/// Rule set applied by [`check_design`] and [`layout_inverts`].
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DesignRules {
+    pub slopes: Vec<SlopeLimit>,
+    /// Minimum ground cover over pipe crowns (m).
+    pub min_cover: f64,
+    pub match_mode: MatchMode,
+    /// Minimum drop through a structure in addition to crown matching (m).
+    pub min_drop: f64,
+    /// Maximum distance between structures (m).
+    pub max_spacing: Option<f64>,
+}
+
+impl DesignRules {
+    /// Slope range of the limit with the largest `min_diameter` not above
+    /// `diameter`, or any non-negative slope when none applies.
+    pub fn slope_limits(&self, diameter: f64) -> (f64, f64) {
+        self.slopes
+            .iter()
+            .filter(|r| diameter >= r.min_diameter)
+            .max_by(|a, b| {
+                a.min_diameter
+                    .partial_cmp(&b.min_diameter)
+                    .unwrap_or(Ordering::Equal)
+            })
+            .map(|r| (r.min_slope, r.max_slope))
+            .unwrap_or((0.0, f64::INFINITY))
+    }
+
+    /// Smallest invert drop from an inlet pipe to an outlet pipe.
+    pub fn required_drop(&self, inlet_diameter: f64, outlet_diameter: f64) -> f64 {
+        match self.match_mode {
+            MatchMode::Inverts => self.min_drop,
+            MatchMode::Crowns => self.min_drop + (outlet_diameter - inlet_diameter).max(0.0),
+        }
+    }
+}
+
+/// Reads slope limits from `diameter,min_slope[,max_slope]` lines. Files
+/// with one slope per diameter, as read by
+/// [`read_slope_rules_csv`](crate::read_slope_rules_csv), give minimum
+/// slopes without a maximum.
+pub fn read_slope_limits_csv(path: &str) -> io::Result<Vec<SlopeLimit>> {
+    let mut limits = Vec::new();
+    for line in std::fs::read_to_string(path)?.lines() {
+        let parts: Vec<&str> = line.split(',').collect();
+        if parts.len() < 2 {
+            continue;
+        }
+        limits.push(SlopeLimit {
+            min_diameter: parse_num(parts[0])?,
+            min_slope: parse_num(parts[1])?,
+            max_slope: match parts.get(2) {
+                Some(v) if !v.trim().is_empty() => parse_num(v)?,
+                _ => f64::INFINITY,
+            },
+        });
+    }
+    limits.sort_by(|a, b| {
+        a.min_diameter
+            .partial_cmp(&b.min_diameter)
+            .unwrap_or(Ordering::Equal)
+    });
+    Ok(limits)
+}
+
+/// Ground elevations `(distance, elevation)` along a pipe. The ends use the
+/// surface at the structures, falling back to their rims, and interior
+/// samples are taken from the surface when one is given.
+pub(crate) fn ground_profile(net: &Network, pipe: &Pipe, surface: Option<&Tin>) -> Vec<(f64, f64)> {
+    let find = |id: &str| net.structures.iter().find(|s| s.id == id);
+    let (Some(a), Some(b)) = (find(&pipe.from), find(&pipe.to)) else {
+        return Vec::new();
+    };
+    let length = (b.x - a.x).hypot(b.y - a.y);
+    let at = |x: f64, y: f64| surface.and_then(|t| t.elevation_at(x, y));
+    let mut profile = vec![(0.0, at(a.x, a.y).unwrap_or(a.z))];
+    if surface.is_some() {
+        let n = (length / COVER_SAMPLE_SPACING).ceil() as usize;
+        for i in 1..n {
+            let t = i as f64 / n as f64;
+            if let Some(z) = at(a.x + (b.x - a.x) * t, a.y + (b.y - a.y) * t) {
+                profile.push((length * t, z));
+            }
+        }
+    }
+    profile.push((length, at(b.x, b.y).unwrap_or(b.z)));
+    profile
+}
+
+/// Checks slopes, cover, structure drops and spacing. Pipe violations use
+/// the pipe id and drop violations the structure id.
+pub fn check_design(net: &Network, rules: &DesignRules, surface: Option<&Tin>) -> Vec<Violation> {
+    const EPS: f64 = 1e-9;
+    let mut violations = Vec::new();
+    let mut flag = |id: &str, kind, value, limit| {
+        violations.push(Violation {
+            id: id.to_string(),
+            kind,
+            value,
+            limit,
+        })
+    };
+    for pipe in &net.pipes {
+        let Some(length) = net.pipe_length(pipe) else {
+            continue;
+        };
+        let slope = pipe.slope(length);
+        let (min_slope, max_slope) = rules.slope_limits(pipe.diameter);
+        if slope < min_slope - EPS {
+            flag(&pipe.id, ViolationKind::MinSlope, slope, min_slope);
+        }
+        if slope > max_slope + EPS {
+            flag(&pipe.id, ViolationKind::MaxSlope, slope, max_slope);
+        }
+        let cover = ground_profile(net, pipe, surface)
+            .into_iter()
+            .map(|(d, z)| {
+                let t = if length > 0.0 { d / length } else { 0.0 };
+                let invert = pipe.start_invert + (pipe.end_invert - pipe.start_invert) * t;
+                z - invert - pipe.diameter
+            })
+            .reduce(f64::min);
+        if let Some(cover) = cover.filter(|c| *c < rules.min_cover - EPS) {
+            flag(&pipe.id, ViolationKind::Cover, cover, rules.min_cover);
+        }
+        if let Some(max) = rules.max_spacing.filter(|m| length > m + EPS) {
+            flag(&pipe.id, ViolationKind::Spacing, length, max);
+        }
+    }
+    for s in &net.structures {
+        for pipe_in in net.pipes.iter().filter(|p| p.to == s.id) {
+            for pipe_out in net.pipes.iter().filter(|p| p.from == s.id) {
+                let drop = pipe_in.end_invert - pipe_out.start_invert;
+                let required = rules.required_drop(pipe_in.diameter, pipe_out.diameter);
+                if drop < required - EPS {
+                    flag(&s.id, ViolationKind::Drop, drop, required);
+                }
+            }
+        }
+    }
+    violations
+}
+
+/// Sets pipe inverts to satisfy the rules and returns the violations that
+/// remain.
+///
+/// A first pass walks downstream from the upstream ends finding the highest
+/// invert each pipe end may take while keeping cover, minimum slope and the
+/// drops into downstream pipes. The inverts are then set from the outfall
+/// upstream, keeping each pipe as shallow as those limits allow and within
+/// its maximum slope. Outfall pipes end no lower than `outfall_invert`
+/// when given, and otherwise at their highest allowed invert.
+pub fn layout_inverts(
+    net: &mut Network,
+    rules: &DesignRules,
+    surface: Option<&Tin>,
+    outfall_invert: Option<f64>,
+) -> Vec<Violation> {
+    let order = net.upstream_order();
+    let n = net.pipes.len();
+    let mut start_max = vec![f64::INFINITY; n];
+    let mut end_max = vec![f64::INFINITY; n];
+    for &i in &order {
+        let pipe = &net.pipes[i];
+        let profile = ground_profile(net, pipe, surface);
+        let (min_slope, _) = rules.slope_limits(pipe.diameter);
+        let length = net.pipe_length(pipe).unwrap_or(0.0);
+        let mut start = profile
+            .first()
+            .map_or(f64::INFINITY, |(_, z)| z - rules.min_cover - pipe.diameter);
+        for (j, q) in net.pipes.iter().enumerate() {
+            if q.to == pipe.from && end_max[j].is_finite() {
+                start = start.min(end_max[j] - rules.required_drop(q.diameter, pipe.diameter));
+            }
+        }
+        // steepen where the ground falls faster than the minimum slope
+        let mut slope = min_slope;
+        for &(d, z) in profile.iter().skip(1) {
+            if d > 0.0 {
+                slope = slope.max((start - (z - rules.min_cover - pipe.diameter)) / d);
+            }
+        }
+        start_max[i] = start;
+        end_max[i] = start - slope * length;
+    }
+    for &i in order.iter().rev() {
+        let pipe = &net.pipes[i];
+        let length = net.pipe_length(pipe).unwrap_or(0.0);
+        let (min_slope, max_slope) = rules.slope_limits(pipe.diameter);
+        let outlets: Vec<f64> = net
+            .pipes
+            .iter()
+            .filter(|r| r.from == pipe.to)
+            .map(|r| r.start_invert + rules.required_drop(pipe.diameter, r.diameter))
+            .collect();
+        let lowest = if outlets.is_empty() {
+            outfall_invert
+        } else {
+            outlets.into_iter().reduce(f64::max)
+        };
+        let mut end = end_max[i];
+        if let Some(lowest) = lowest {
+            end = if end.is_finite() {
+                end.max(lowest)
+            } else {
+                lowest
+            };
+        }
+        if !end.is_finite() {
+            continue;
+        }
+        let start = start_max[i]
+            .min(end + max_slope * length)
+            .max(end + min_slope * length);
+        let pipe = &mut net.pipes[i];
+        pipe.start_invert = start;
+        pipe.end_invert = end;
+    }
+    check_design(net, rules, surface)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Structure, DEFAULT_MANNING_N};
+    use survey_cad::geometry::Point3;
+
+    fn structure(id: &str, x: f64, z: f64) -> Structure {
+        Structure {
+            id: id.into(),
+            x,
+            y: 0.0,
+            z,
+            ..Default::default()
+        }
+    }
+
+    fn pipe(id: &str, from: &str, to: &str, diameter: f64) -> Pipe {
+        Pipe {
+            id: id.into(),
+            from: from.into(),
+            to: to.into(),
+            diameter,
+            c: 120.0,
+            start_invert: 0.0,
+            end_invert: 0.0,
+            design_flow: 0.0,
+            manning_n: DEFAULT_MANNING_N,
+        }
+    }
+
+    fn rules() -> DesignRules {
+        DesignRules {
+            slopes: vec![
+                SlopeLimit {
+                    min_diameter: 0.0,
+                    min_slope: 0.005,
+                    max_slope: 0.05,
+                },
+                SlopeLimit {
+                    min_diameter: 0.45,
+                    min_slope: 0.003,
+                    max_slope: 0.05,
+                },
+            ],
+            min_cover: 1.0,
+            match_mode: MatchMode::Crowns,
+            min_drop: 0.03,
+            max_spacing: Some(120.0),
+        }
+    }
+
+    /// A and B drain to C, which drains through a larger pipe to the
+    /// outfall O. The ground dips between C and O.
+    fn network() -> Network {
+        Network {
+            structures: vec![
+                structure("A", 0.0, 12.0),
+                structure("B", 0.0, 12.0),
+                structure("C", 100.0, 11.0),
+                structure("O", 200.0, 10.0),
+            ],
+            pipes: vec![
+                pipe("AC", "A", "C", 0.3),
+                pipe("BC", "B", "C", 0.375),
+                pipe("CO", "C", "O", 0.45),
+            ],
+        }
+    }
+
+    #[test]
+    fn checks_report_each_rule() {
+        let mut net = network();
+        for (p, (s, e)) in net
+            .pipes
+            .iter_mut()
+            .zip([(10.0, 9.9), (10.8, 9.6), (9.8, 8.0)])
+        {
+            p.start_invert = s;
+            p.end_invert = e;
+        }
+        let violations = check_design(&net, &rules(), None);
+        let has = |id: &str, kind| violations.iter().any(|v| v.id == id && v.kind == kind);
+        assert!(has("AC", ViolationKind::MinSlope));
+        assert!(has("BC", ViolationKind::Cover));
+        // outlet crown 10.25 is above the AC inlet crown 10.2 less the drop
+        assert!(has("C", ViolationKind::Drop));
+        assert!(!has("CO", ViolationKind::MaxSlope));
+        assert!(violations.iter().all(|v| v.kind != ViolationKind::Spacing));
+
+        let short = DesignRules {
+            max_spacing: Some(50.0),
+            ..rules()
+        };
+        assert_eq!(
+            check_design(&net, &short, None)
+                .iter()
+                .filter(|v| v.kind == ViolationKind::Spacing)
+                .count(),
+            3
+        );
+    }
+
+    #[test]
+    fn layout_satisfies_rules() {
+        let mut net = network();
+        let surface = Tin::from_points(vec![
+            Point3::new(-10.0, -10.0, 12.0),
+            Point3::new(-10.0, 10.0, 12.0),
+            Point3::new(100.0, -10.0, 11.0),
+            Point3::new(100.0, 10.0, 11.0),
+            Point3::new(150.0, -10.0, 9.0),
+            Point3::new(150.0, 10.0, 9.0),
+            Point3::new(210.0, -10.0, 10.0),
+            Point3::new(210.0, 10.0, 10.0),
+        ]);
+        let rules = rules();
+        let remaining = layout_inverts(&mut net, &rules, Some(&surface), None);
+        assert!(remaining.is_empty(), "{remaining:?}");
+        let co = &net.pipes[2];
+        // the dip to 9.0 at mid-length controls the cover of CO
+        let mid = (co.start_invert + co.end_invert) / 2.0;
+        assert!(mid + co.diameter <= 9.0 - rules.min_cover + 1e-9);
+        // shallowest layout: cover is exactly met at the head of AC
+        let ground = surface.elevation_at(0.0, 0.0).unwrap();
+        assert!((net.pipes[0].start_invert - (ground - 1.0 - 0.3)).abs() < 1e-9);
+
+        // a high outfall cannot be met without losing cover
+        let remaining = layout_inverts(&mut net, &rules, Some(&surface), Some(8.5));
+        assert!(remaining.iter().any(|v| v.kind == ViolationKind::Cover));
+        assert!((net.pipes[2].end_invert - 8.5).abs() < 1e-9);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::crs::CoordinateTransform;
 use crate::geometry::{distance, Arc, Point, Point3};
 
 /// Euler spiral segment described analytically.
@@ -249,6 +250,52 @@ impl HorizontalAlignment {
         }
         None
     }
+
+    /// Applies a coordinate transformation to every element. Curves and
+    /// spirals are mapped using the local rotation and scale at their centre
+    /// or start point.
+    pub fn transform_with(&mut self, transform: &impl CoordinateTransform) {
+        for elem in &mut self.elements {
+            match elem {
+                HorizontalElement::Tangent { start, end } => {
+                    *start = map_point(transform, *start);
+                    *end = map_point(transform, *end);
+                }
+                HorizontalElement::Curve { arc } => map_arc(transform, arc),
+                HorizontalElement::Spiral { spiral } => {
+                    let (s, rot, scale) = similarity(transform, spiral.start);
+                    spiral.start = s;
+                    spiral.orientation += rot;
+                    spiral.length *= scale;
+                    spiral.start_radius *= scale;
+                    spiral.end_radius *= scale;
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn map_point(transform: &impl CoordinateTransform, p: Point) -> Point {
+    transform
+        .transform(p.x, p.y, 0.0)
+        .map(|(x, y, _)| Point::new(x, y))
+        .unwrap_or(p)
+}
+
+/// Image of `p` with the local rotation and scale of the transformation.
+fn similarity(transform: &impl CoordinateTransform, p: Point) -> (Point, f64, f64) {
+    let o = map_point(transform, p);
+    let e = map_point(transform, Point::new(p.x + 1.0, p.y));
+    let (dx, dy) = (e.x - o.x, e.y - o.y);
+    (o, dy.atan2(dx), dx.hypot(dy))
+}
+
+pub(crate) fn map_arc(transform: &impl CoordinateTransform, arc: &mut Arc) {
+    let (c, rot, scale) = similarity(transform, arc.center);
+    arc.center = c;
+    arc.radius *= scale;
+    arc.start_angle += rot;
+    arc.end_angle += rot;
 }
 
 /// Builder for [`HorizontalAlignment`].
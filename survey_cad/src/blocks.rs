@@ -0,0 +1,839 @@
+//! Block definitions, inserts and symbol libraries.
+//!
+//! A [`BlockDefinition`] is a named group of entities drawn relative to a
+//! base point, with attribute definitions for the values each insert
+//! carries. A [`BlockInsert`] places a definition with a scale and rotation.
+//! A [`SymbolLibrary`] holds the definitions of a DXF `BLOCKS` section and
+//! resolves the block names produced by
+//! [`field_to_finish`](crate::surveying::point_db::PointDatabase::field_to_finish).
+
+use std::collections::BTreeMap;
+use std::f64::consts::TAU;
+use std::io;
+
+use crate::geometry::edit::PathSegment;
+use crate::geometry::{Arc, Point, Point3, Polyline3};
+use crate::io::dxf::{
+    read_dxf_document, DxfAttribute, DxfBlock, DxfDocument, DxfEntity, DxfProperties,
+};
+use crate::surveying::BlockRef;
+
+/// Largest angle subtended by a chord when arcs are flattened under a
+/// non-uniform scale.
+const CHORD_ANGLE: f64 = TAU / 64.0;
+
+/// Geometry of a block, in block coordinates inside a definition and in
+/// world coordinates once an insert is exploded.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BlockEntity {
+    Point(Point3),
+    Line(Point3, Point3),
+    Circle {
+        center: Point3,
+        radius: f64,
+    },
+    Arc {
+        arc: Arc,
+        elevation: f64,
+    },
+    Polyline(Polyline3),
+    Text {
+        position: Point3,
+        height: f64,
+        /// Rotation in radians.
+        rotation: f64,
+        value: String,
+    },
+    /// Nested insert of another block, left in place by
+    /// [`SymbolLibrary::explode`] when its block is undefined or already
+    /// being exploded.
+    Insert(BlockInsert),
+}
+
+impl BlockEntity {
+    /// Converts a drawable DXF entity or a nested insert. Attribute
+    /// definitions and uninterpreted entities return `None`.
+    pub fn from_dxf(entity: &DxfEntity) -> Option<Self> {
+        let flat = |p: Point| Point3::new(p.x, p.y, 0.0);
+        Some(match entity {
+            DxfEntity::Insert { .. } => Self::Insert(BlockInsert::from_dxf(entity)?),
+            DxfEntity::Point { point, .. } => Self::Point(flat(*point)),
+            DxfEntity::Point3D { point, .. } => Self::Point(*point),
+            DxfEntity::Line { line, .. } => Self::Line(flat(line.start), flat(line.end)),
+            DxfEntity::Line3D { start, end, .. } => Self::Line(*start, *end),
+            DxfEntity::Circle { center, radius, .. } => Self::Circle {
+                center: *center,
+                radius: *radius,
+            },
+            DxfEntity::Arc { arc, .. } => Self::Arc {
+                arc: *arc,
+                elevation: 0.0,
+            },
+            DxfEntity::Arc3D { arc, elevation, .. } => Self::Arc {
+                arc: *arc,
+                elevation: *elevation,
+            },
+            DxfEntity::Text {
+                position,
+                height,
+                value,
+                ..
+            } => Self::Text {
+                position: flat(*position),
+                height: *height,
+                rotation: 0.0,
+                value: value.clone(),
+            },
+            DxfEntity::Text3D {
+                position,
+                height,
+                rotation,
+                value,
+                ..
+            }
+            | DxfEntity::MText {
+                position,
+                height,
+                rotation,
+                value,
+                ..
+            } => Self::Text {
+                position: *position,
+                height: *height,
+                rotation: *rotation,
+                value: value.clone(),
+            },
+            e => Self::Polyline(e.polyline3()?),
+        })
+    }
+
+    /// Converts to a DXF entity with the given properties.
+    pub fn to_dxf(&self, props: DxfProperties) -> DxfEntity {
+        match self {
+            Self::Point(point) => DxfEntity::Point3D {
+                point: *point,
+                props,
+            },
+            Self::Line(start, end) => DxfEntity::Line3D {
+                start: *start,
+                end: *end,
+                props,
+            },
+            Self::Circle { center, radius } => DxfEntity::Circle {
+                center: *center,
+                radius: *radius,
+                props,
+            },
+            Self::Arc { arc, elevation } => DxfEntity::Arc3D {
+                arc: *arc,
+                elevation: *elevation,
+                props,
+            },
+            Self::Polyline(pl) => DxfEntity::from_polyline3(pl, props),
+            Self::Text {
+                position,
+                height,
+                rotation,
+                value,
+            } => DxfEntity::Text3D {
+                position: *position,
+                height: *height,
+                rotation: *rotation,
+                value: value.clone(),
+                props,
+            },
+            Self::Insert(insert) => BlockInsert {
+                layer: props.layer,
+                ..insert.clone()
+            }
+            .to_dxf(None),
+        }
+        .simplified()
+    }
+}
+
+/// Attribute carried by each insert of a block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AttributeDefinition {
+    pub tag: String,
+    pub prompt: String,
+    pub default: String,
+    /// Text position in block coordinates.
+    pub position: Point3,
+    pub height: f64,
+    /// Rotation in radians.
+    pub rotation: f64,
+}
+
+impl AttributeDefinition {
+    pub fn new(tag: &str, position: Point3, height: f64) -> Self {
+        Self {
+            tag: tag.to_string(),
+            prompt: tag.to_string(),
+            default: String::new(),
+            position,
+            height,
+            rotation: 0.0,
+        }
+    }
+}
+
+/// Named group of entities drawn relative to `base`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockDefinition {
+    pub name: String,
+    pub base: Point3,
+    pub entities: Vec<BlockEntity>,
+    pub attributes: Vec<AttributeDefinition>,
+}
+
+impl BlockDefinition {
+    pub fn new(name: &str, base: Point3, entities: Vec<BlockEntity>) -> Self {
+        Self {
+            name: name.to_string(),
+            base,
+            entities,
+            attributes: Vec::new(),
+        }
+    }
+
+    /// Builds a definition from DXF entities, taking attribute definitions
+    /// from any `ATTDEF`s and skipping entities that can't be drawn.
+    pub fn from_entities(name: &str, base: Point3, entities: &[DxfEntity]) -> Self {
+        let mut def = Self::new(name, base, Vec::new());
+        for e in entities {
+            if let DxfEntity::AttributeDefinition {
+                tag,
+                prompt,
+                default,
+                position,
+                height,
+                rotation,
+                ..
+            } = e
+            {
+                def.attributes.push(AttributeDefinition {
+                    tag: tag.clone(),
+                    prompt: prompt.clone(),
+                    default: default.clone(),
+                    position: *position,
+                    height: *height,
+                    rotation: *rotation,
+                });
+            } else if let Some(b) = BlockEntity::from_dxf(e) {
+                def.entities.push(b);
+            }
+        }
+        def
+    }
+
+    pub fn from_dxf(block: &DxfBlock) -> Self {
+        Self::from_entities(&block.name, block.base_point, &block.entities)
+    }
+
+    /// DXF block with every entity on layer `0` so inserts take the layer
+    /// they are placed on.
+    pub fn to_dxf(&self) -> DxfBlock {
+        let mut entities: Vec<DxfEntity> = self
+            .entities
+            .iter()
+            .map(|e| e.to_dxf(DxfProperties::default()))
+            .collect();
+        entities.extend(
+            self.attributes
+                .iter()
+                .map(|a| DxfEntity::AttributeDefinition {
+                    tag: a.tag.clone(),
+                    prompt: a.prompt.clone(),
+                    default: a.default.clone(),
+                    position: a.position,
+                    height: a.height,
+                    rotation: a.rotation,
+                    props: DxfProperties::default(),
+                }),
+        );
+        let mut block = DxfBlock::new(&self.name, self.base, entities);
+        if !self.attributes.is_empty() {
+            block.flags |= 2;
+        }
+        block
+    }
+
+    pub fn attribute(&self, tag: &str) -> Option<&AttributeDefinition> {
+        self.attributes
+            .iter()
+            .find(|a| a.tag.eq_ignore_ascii_case(tag))
+    }
+}
+
+/// Placement of a block with its attribute values keyed by tag.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BlockInsert {
+    pub name: String,
+    pub position: Point3,
+    pub scale: [f64; 3],
+    /// Rotation in radians.
+    pub rotation: f64,
+    pub attributes: BTreeMap<String, String>,
+    pub layer: String,
+}
+
+impl BlockInsert {
+    pub fn new(name: &str, position: Point3) -> Self {
+        Self {
+            name: name.to_string(),
+            position,
+            scale: [1.0; 3],
+            rotation: 0.0,
+            attributes: BTreeMap::new(),
+            layer: "0".into(),
+        }
+    }
+
+    /// Maps a point in the coordinates of a block with base point `base`
+    /// to world coordinates.
+    pub fn to_world(&self, base: Point3, p: Point3) -> Point3 {
+        let [sx, sy, sz] = self.scale;
+        let (x, y) = ((p.x - base.x) * sx, (p.y - base.y) * sy);
+        let (sin, cos) = self.rotation.sin_cos();
+        Point3::new(
+            self.position.x + x * cos - y * sin,
+            self.position.y + x * sin + y * cos,
+            self.position.z + (p.z - base.z) * sz,
+        )
+    }
+
+    /// Returns `true` if the X and Y scales have the same magnitude, so
+    /// circles stay circles.
+    fn is_uniform(&self) -> bool {
+        let (sx, sy) = (self.scale[0].abs(), self.scale[1].abs());
+        (sx - sy).abs() <= 1e-9 * sx.max(sy)
+    }
+
+    /// Returns `true` if the insert is mirrored, reversing arcs.
+    fn is_mirrored(&self) -> bool {
+        self.scale[0] * self.scale[1] < 0.0
+    }
+
+    /// Value shown for an attribute, falling back to its default.
+    pub fn attribute_value<'a>(&'a self, def: &'a AttributeDefinition) -> &'a str {
+        self.attributes
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(&def.tag))
+            .map_or(def.default.as_str(), |(_, v)| v.as_str())
+    }
+
+    /// Geometry of `def` placed by this insert, with each attribute drawn as
+    /// text. Circles and arcs become polylines when X and Y are scaled
+    /// differently. Nested inserts are placed but not exploded.
+    pub fn explode(&self, def: &BlockDefinition) -> Vec<BlockEntity> {
+        self.place(def, &def.entities)
+    }
+
+    /// Places `entities`, drawn in the coordinates of `def`, and the
+    /// attributes of `def`.
+    fn place(&self, def: &BlockDefinition, entities: &[BlockEntity]) -> Vec<BlockEntity> {
+        let mut out: Vec<BlockEntity> = entities
+            .iter()
+            .map(|e| self.transform(def.base, e))
+            .collect();
+        for a in &def.attributes {
+            let value = self.attribute_value(a);
+            if value.is_empty() {
+                continue;
+            }
+            out.push(BlockEntity::Text {
+                position: self.to_world(def.base, a.position),
+                height: a.height * self.scale[1].abs(),
+                rotation: a.rotation + self.rotation,
+                value: value.to_string(),
+            });
+        }
+        out
+    }
+
+    fn transform(&self, base: Point3, e: &BlockEntity) -> BlockEntity {
+        let world = |p: Point3| self.to_world(base, p);
+        let flat = |p: Point3| Point::new(p.x, p.y);
+        match e {
+            BlockEntity::Point(p) => BlockEntity::Point(world(*p)),
+            BlockEntity::Line(a, b) => BlockEntity::Line(world(*a), world(*b)),
+            BlockEntity::Circle { center, radius } if self.is_uniform() => BlockEntity::Circle {
+                center: world(*center),
+                radius: radius * self.scale[0].abs(),
+            },
+            BlockEntity::Circle { center, radius } => {
+                let arc = Arc::new(Point::new(center.x, center.y), *radius, 0.0, TAU);
+                let mut pl = self.flatten_arc(base, &arc, center.z);
+                pl.vertices.pop();
+                pl.closed = true;
+                BlockEntity::Polyline(pl)
+            }
+            BlockEntity::Arc { arc, elevation } if self.is_uniform() => {
+                let c = world(Point3::new(arc.center.x, arc.center.y, *elevation));
+                let start = flat(world(Point3::new(
+                    arc.center.x + arc.radius * arc.start_angle.cos(),
+                    arc.center.y + arc.radius * arc.start_angle.sin(),
+                    *elevation,
+                )));
+                let mut sweep = arc.sweep();
+                if self.is_mirrored() {
+                    sweep = -sweep;
+                }
+                let seg = PathSegment::Arc {
+                    center: flat(c),
+                    radius: arc.radius * self.scale[0].abs(),
+                    start: (start.y - c.y).atan2(start.x - c.x),
+                    sweep,
+                };
+                BlockEntity::Arc {
+                    arc: seg.to_arc().unwrap(),
+                    elevation: c.z,
+                }
+            }
+            BlockEntity::Arc { arc, elevation } => {
+                BlockEntity::Polyline(self.flatten_arc(base, arc, *elevation))
+            }
+            BlockEntity::Polyline(pl) if self.is_uniform() || !pl.has_arcs() => {
+                let sign = if self.is_mirrored() { -1.0 } else { 1.0 };
+                BlockEntity::Polyline(Polyline3 {
+                    vertices: pl.vertices.iter().map(|p| world(*p)).collect(),
+                    bulges: pl.bulges.iter().map(|b| b * sign).collect(),
+                    closed: pl.closed,
+                })
+            }
+            BlockEntity::Polyline(pl) => {
+                let mut out =
+                    Polyline3::new(pl.densify(CHORD_ANGLE).into_iter().map(world).collect());
+                out.closed = pl.closed;
+                BlockEntity::Polyline(out)
+            }
+            BlockEntity::Text {
+                position,
+                height,
+                rotation,
+                value,
+            } => BlockEntity::Text {
+                position: world(*position),
+                height: height * self.scale[1].abs(),
+                rotation: rotation + self.rotation,
+                value: value.clone(),
+            },
+            // exact when X and Y are scaled alike; a mirror turns the
+            // nested rotation the other way
+            BlockEntity::Insert(inner) => BlockEntity::Insert(BlockInsert {
+                position: world(inner.position),
+                scale: [0, 1, 2].map(|k| inner.scale[k] * self.scale[k]),
+                rotation: self.rotation
+                    + if self.is_mirrored() {
+                        -inner.rotation
+                    } else {
+                        inner.rotation
+                    },
+                ..inner.clone()
+            }),
+        }
+    }
+
+    /// Arc flattened to chords in world coordinates.
+    fn flatten_arc(&self, base: Point3, arc: &Arc, elevation: f64) -> Polyline3 {
+        let sweep = arc.sweep();
+        let steps = ((sweep.abs() / CHORD_ANGLE).ceil() as usize).max(1);
+        let vertices = (0..=steps)
+            .map(|i| {
+                let t = arc.start_angle + sweep * i as f64 / steps as f64;
+                self.to_world(
+                    base,
+                    Point3::new(
+                        arc.center.x + arc.radius * t.cos(),
+                        arc.center.y + arc.radius * t.sin(),
+                        elevation,
+                    ),
+                )
+            })
+            .collect();
+        Polyline3::new(vertices)
+    }
+
+    /// Reads an `INSERT`, or `None` for any other entity.
+    pub fn from_dxf(entity: &DxfEntity) -> Option<Self> {
+        let DxfEntity::Insert {
+            block,
+            position,
+            scale,
+            rotation,
+            attributes,
+            props,
+        } = entity
+        else {
+            return None;
+        };
+        Some(Self {
+            name: block.clone(),
+            position: *position,
+            scale: *scale,
+            rotation: *rotation,
+            attributes: attributes
+                .iter()
+                .map(|a| (a.tag.clone(), a.value.clone()))
+                .collect(),
+            layer: props.layer.clone(),
+        })
+    }
+
+    /// Writes the insert as an `INSERT` with an `ATTRIB` for each attribute
+    /// of `def`, placed as the definition places it. Values without a
+    /// definition are written at the insertion point.
+    pub fn to_dxf(&self, def: Option<&BlockDefinition>) -> DxfEntity {
+        let props = DxfProperties::on_layer(&self.layer);
+        let height_scale = self.scale[1].abs();
+        let mut attributes = Vec::new();
+        if let Some(def) = def {
+            for a in &def.attributes {
+                attributes.push(DxfAttribute {
+                    tag: a.tag.clone(),
+                    value: self.attribute_value(a).to_string(),
+                    position: self.to_world(def.base, a.position),
+                    height: a.height * height_scale,
+                    rotation: a.rotation + self.rotation,
+                    props: props.clone(),
+                });
+            }
+        }
+        for (tag, value) in &self.attributes {
+            if def.is_some_and(|d| d.attribute(tag).is_some()) {
+                continue;
+            }
+            attributes.push(DxfAttribute {
+                tag: tag.clone(),
+                value: value.clone(),
+                position: self.position,
+                height: height_scale,
+                rotation: self.rotation,
+                props: props.clone(),
+            });
+        }
+        DxfEntity::Insert {
+            block: self.name.clone(),
+            position: self.position,
+            scale: self.scale,
+            rotation: self.rotation,
+            attributes,
+            props,
+        }
+    }
+}
+
+impl From<&BlockRef> for BlockInsert {
+    fn from(r: &BlockRef) -> Self {
+        let mut insert = Self::new(&r.name, r.location);
+        insert.attributes = r.attributes.clone();
+        insert
+    }
+}
+
+/// Block definitions looked up by name, ignoring case.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SymbolLibrary {
+    pub blocks: Vec<BlockDefinition>,
+}
+
+impl SymbolLibrary {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Named blocks of a DXF document. Anonymous blocks such as model
+    /// space and dimension graphics are skipped.
+    pub fn from_document(doc: &DxfDocument) -> Self {
+        Self {
+            blocks: doc
+                .blocks
+                .iter()
+                .filter(|b| !b.name.starts_with('*'))
+                .map(BlockDefinition::from_dxf)
+                .collect(),
+        }
+    }
+
+    /// Loads the block definitions of a DXF file.
+    pub fn from_dxf(path: &str) -> io::Result<Self> {
+        Ok(Self::from_document(&read_dxf_document(path)?))
+    }
+
+    pub fn get(&self, name: &str) -> Option<&BlockDefinition> {
+        self.blocks
+            .iter()
+            .find(|b| b.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Adds a definition, replacing any with the same name.
+    pub fn insert(&mut self, def: BlockDefinition) {
+        match self
+            .blocks
+            .iter_mut()
+            .find(|b| b.name.eq_ignore_ascii_case(&def.name))
+        {
+            Some(b) => *b = def,
+            None => self.blocks.push(def),
+        }
+    }
+
+    /// World geometry of an insert, or `None` if its block isn't defined.
+    /// Nested inserts are exploded in turn, except those of undefined
+    /// blocks and of blocks that insert themselves, which are kept as
+    /// [`BlockEntity::Insert`].
+    pub fn explode(&self, insert: &BlockInsert) -> Option<Vec<BlockEntity>> {
+        self.explode_nested(insert, &mut Vec::new())
+    }
+
+    /// `open` names the blocks being exploded further up.
+    fn explode_nested(
+        &self,
+        insert: &BlockInsert,
+        open: &mut Vec<String>,
+    ) -> Option<Vec<BlockEntity>> {
+        let def = self.get(&insert.name)?;
+        open.push(def.name.clone());
+        let mut entities = Vec::new();
+        for e in &def.entities {
+            let nested = match e {
+                BlockEntity::Insert(inner)
+                    if !open.iter().any(|n| n.eq_ignore_ascii_case(&inner.name)) =>
+                {
+                    self.explode_nested(inner, open)
+                }
+                _ => None,
+            };
+            match nested {
+                Some(parts) => entities.extend(parts),
+                None => entities.push(e.clone()),
+            }
+        }
+        open.pop();
+        Some(insert.place(def, &entities))
+    }
+
+    /// Document holding the inserts in model space and the definitions they
+    /// use in the `BLOCKS` section.
+    pub fn to_document(&self, inserts: &[BlockInsert]) -> io::Result<DxfDocument> {
+        let mut used: Vec<&BlockDefinition> = Vec::new();
+        let mut entities = Vec::new();
+        for insert in inserts {
+            let def = self.get(&insert.name).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("unknown block {}", insert.name),
+                )
+            })?;
+            if !used.iter().any(|d| d.name == def.name) {
+                used.push(def);
+            }
+            entities.push(insert.to_dxf(Some(def)));
+        }
+        // definitions used by nested inserts, once each
+        let mut k = 0;
+        while k < used.len() {
+            for e in &used[k].entities {
+                let BlockEntity::Insert(inner) = e else {
+                    continue;
+                };
+                let def = self.get(&inner.name).ok_or_else(|| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("unknown block {} in {}", inner.name, used[k].name),
+                    )
+                })?;
+                if !used.iter().any(|d| d.name == def.name) {
+                    used.push(def);
+                }
+            }
+            k += 1;
+        }
+        let mut doc = DxfDocument::from_entities(entities);
+        doc.blocks = used.into_iter().map(BlockDefinition::to_dxf).collect();
+        Ok(doc)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::io::dxf::{dxf_to_bytes, parse_dxf};
+    use std::f64::consts::FRAC_PI_2;
+
+    fn close(a: Point3, b: Point3) -> bool {
+        (a.x - b.x).abs() < 1e-9 && (a.y - b.y).abs() < 1e-9 && (a.z - b.z).abs() < 1e-9
+    }
+
+    fn manhole() -> BlockDefinition {
+        let mut def = BlockDefinition::new(
+            "MH",
+            Point3::new(0.0, 0.0, 0.0),
+            vec![
+                BlockEntity::Circle {
+                    center: Point3::new(0.0, 0.0, 0.0),
+                    radius: 0.6,
+                },
+                BlockEntity::Line(Point3::new(-0.6, 0.0, 0.0), Point3::new(0.6, 0.0, 0.0)),
+            ],
+        );
+        let mut id = AttributeDefinition::new("ID", Point3::new(1.0, 0.0, 0.0), 0.5);
+        id.default = "MH-?".into();
+        def.attributes.push(id);
+        def.attributes.push(AttributeDefinition::new(
+            "RIM",
+            Point3::new(1.0, -1.0, 0.0),
+            0.5,
+        ));
+        def
+    }
+
+    #[test]
+    fn inserts_round_trip_through_dxf() {
+        let mut lib = SymbolLibrary::new();
+        lib.insert(manhole());
+        let refs = BlockRef {
+            location: Point3::new(50.0, 60.0, 99.5),
+            name: "mh".into(),
+            attributes: [("ID".to_string(), "MH-12".to_string())].into(),
+        };
+        let mut insert = BlockInsert::from(&refs);
+        insert.scale = [2.0, 2.0, 1.0];
+        insert.rotation = FRAC_PI_2;
+        insert.layer = "STRM".into();
+
+        let doc = lib.to_document(std::slice::from_ref(&insert)).unwrap();
+        let read = parse_dxf(&dxf_to_bytes(&doc)).unwrap();
+        let lib2 = SymbolLibrary::from_document(&read);
+        assert_eq!(lib2, lib);
+        let DxfEntity::Insert { attributes, .. } = &read.entities[0] else {
+            panic!("expected an insert");
+        };
+        assert_eq!(attributes.len(), 2);
+        assert!(close(attributes[0].position, Point3::new(50.0, 62.0, 99.5)));
+        assert_eq!(attributes[1].value, "");
+        let back = BlockInsert::from_dxf(&read.entities[0]).unwrap();
+        assert_eq!(back.attributes["ID"], "MH-12");
+        assert_eq!(back.layer, "STRM");
+        assert!((back.rotation - FRAC_PI_2).abs() < 1e-12);
+
+        let parts = lib2.explode(&back).unwrap();
+        assert_eq!(parts.len(), 3);
+        assert!(
+            matches!(parts[0], BlockEntity::Circle { radius, .. } if (radius - 1.2).abs() < 1e-12)
+        );
+        let BlockEntity::Line(a, b) = parts[1] else {
+            panic!("expected a line");
+        };
+        assert!(close(a, Point3::new(50.0, 58.8, 99.5)));
+        assert!(close(b, Point3::new(50.0, 61.2, 99.5)));
+        assert!(
+            matches!(&parts[2], BlockEntity::Text { value, height, .. } if value == "MH-12" && *height == 1.0)
+        );
+
+        let missing = BlockInsert::new("TREE", insert.position);
+        assert!(lib.to_document(&[missing]).is_err());
+    }
+
+    #[test]
+    fn nested_inserts_explode_and_round_trip() {
+        let origin = Point3::new(0.0, 0.0, 0.0);
+        let mut lib = SymbolLibrary::new();
+        lib.insert(manhole());
+        let mut left = BlockInsert::new("MH", Point3::new(-2.0, 0.0, 0.0));
+        left.rotation = FRAC_PI_2;
+        let right = BlockInsert::new("mh", Point3::new(2.0, 0.0, 0.0));
+        lib.insert(BlockDefinition::new(
+            "PAIR",
+            origin,
+            vec![BlockEntity::Insert(left), BlockEntity::Insert(right)],
+        ));
+        // a block that inserts itself
+        lib.insert(BlockDefinition::new(
+            "LOOP",
+            origin,
+            vec![
+                BlockEntity::Line(origin, Point3::new(1.0, 0.0, 0.0)),
+                BlockEntity::Insert(BlockInsert::new("loop", Point3::new(5.0, 0.0, 0.0))),
+            ],
+        ));
+
+        let mut pair = BlockInsert::new("PAIR", Point3::new(100.0, 0.0, 0.0));
+        pair.scale = [2.0, 2.0, 1.0];
+        let parts = lib.explode(&pair).unwrap();
+        assert_eq!(parts.len(), 6);
+        let BlockEntity::Line(a, b) = parts[1] else {
+            panic!("expected a line");
+        };
+        assert!(close(a, Point3::new(96.0, -1.2, 0.0)));
+        assert!(close(b, Point3::new(96.0, 1.2, 0.0)));
+        assert!(matches!(
+            parts[3],
+            BlockEntity::Circle { center, radius } if close(center, Point3::new(104.0, 0.0, 0.0)) && (radius - 1.2).abs() < 1e-12
+        ));
+
+        let parts = lib
+            .explode(&BlockInsert::new("LOOP", Point3::new(0.0, 10.0, 0.0)))
+            .unwrap();
+        assert_eq!(parts.len(), 2);
+        assert!(matches!(
+            &parts[1],
+            BlockEntity::Insert(i) if close(i.position, Point3::new(5.0, 10.0, 0.0))
+        ));
+
+        let doc = lib.to_document(std::slice::from_ref(&pair)).unwrap();
+        let read = SymbolLibrary::from_document(&parse_dxf(&dxf_to_bytes(&doc)).unwrap());
+        assert_eq!(read.blocks.len(), 2);
+        assert_eq!(read.get("PAIR"), lib.get("PAIR"));
+        assert_eq!(read.explode(&pair), lib.explode(&pair));
+
+        lib.insert(BlockDefinition::new(
+            "PAIR",
+            origin,
+            vec![BlockEntity::Insert(BlockInsert::new("TREE", origin))],
+        ));
+        assert!(lib.to_document(&[pair]).is_err());
+    }
+
+    #[test]
+    fn mirrored_and_stretched_inserts() {
+        let def = BlockDefinition::new(
+            "ARC",
+            Point3::new(1.0, 0.0, 0.0),
+            vec![
+                BlockEntity::Arc {
+                    arc: Arc::new(Point::new(1.0, 0.0), 1.0, 0.0, FRAC_PI_2),
+                    elevation: 0.0,
+                },
+                BlockEntity::Circle {
+                    center: Point3::new(1.0, 0.0, 0.0),
+                    radius: 1.0,
+                },
+            ],
+        );
+        let mut insert = BlockInsert::new("ARC", Point3::new(10.0, 0.0, 0.0));
+        insert.scale = [-1.0, 1.0, 1.0];
+        let parts = insert.explode(&def);
+        // Mirrored in X the quarter arc runs from 90° to 180°.
+        let BlockEntity::Arc { arc, .. } = parts[0] else {
+            panic!("expected an arc");
+        };
+        assert!(close(
+            Point3::new(arc.center.x, arc.center.y, 0.0),
+            Point3::new(10.0, 0.0, 0.0)
+        ));
+        assert!((arc.start_angle - FRAC_PI_2).abs() < 1e-9);
+        assert!((arc.sweep() - FRAC_PI_2).abs() < 1e-9);
+
+        insert.scale = [2.0, 1.0, 1.0];
+        let parts = insert.explode(&def);
+        let BlockEntity::Polyline(ellipse) = &parts[1] else {
+            panic!("expected a polyline");
+        };
+        assert!(ellipse.closed);
+        assert_eq!(ellipse.vertices.len(), 64);
+        assert!(close(ellipse.vertices[0], Point3::new(12.0, 0.0, 0.0)));
+        assert!(close(ellipse.vertices[16], Point3::new(10.0, 1.0, 0.0)));
+    }
+}
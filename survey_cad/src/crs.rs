@@ -1,8 +1,18 @@
 //! Coordinate reference system utilities built on top of the `proj` crate.
 
+use crate::geoid::{GeoidGrid, HeightConversion};
+use crate::surveying::gnss::{GRS80_A, GRS80_F};
 use proj::Proj;
 use rusqlite::Connection;
 
+/// Common interface for objects that map coordinates from one system to
+/// another, such as [`CrsTransformer`] or a fitted
+/// [`Transformation`](crate::transformation::Transformation).
+pub trait CoordinateTransform {
+    /// Transforms a 3D coordinate, returning `None` on failure.
+    fn transform(&self, x: f64, y: f64, z: f64) -> Option<(f64, f64, f64)>;
+}
+
 /// Reusable transformation object between two coordinate reference systems.
 ///
 /// The underlying PROJ context is not thread safe, therefore
@@ -12,6 +22,8 @@ use rusqlite::Connection;
 pub struct CrsTransformer {
     ctx: *mut proj_sys::PJ_CONTEXT,
     pj: *mut proj_sys::PJ,
+    source: Crs,
+    geoid: Option<(GeoidGrid, HeightConversion)>,
     // raw pointers are Send + Sync by default, but the PROJ context isn't
     // thread-safe. Use `Rc` to opt-out of automatic Send/Sync impls.
     _nosend: std::marker::PhantomData<std::rc::Rc<()>>,
@@ -57,13 +69,40 @@ impl CrsTransformer {
             Some(Self {
                 ctx,
                 pj,
+                source: source.clone(),
+                geoid: None,
                 _nosend: std::marker::PhantomData,
             })
         }
     }
 
+    /// Applies a geoid model to the heights of every transformed point.
+    ///
+    /// The geoid separation is looked up at the source position, so points
+    /// outside the grid fail to transform instead of silently keeping the
+    /// wrong height.
+    pub fn with_geoid(mut self, geoid: GeoidGrid, conversion: HeightConversion) -> Self {
+        self.geoid = Some((geoid, conversion));
+        self
+    }
+
     /// Transforms a 3D point using the prepared transformation.
     pub fn transform(&self, x: f64, y: f64, z: f64) -> Option<(f64, f64, f64)> {
+        let adjust = match &self.geoid {
+            Some((grid, conversion)) => {
+                let (lon, lat) = self.source.geographic_position(x, y)?;
+                Some((grid.undulation(lat, lon)?, *conversion))
+            }
+            None => None,
+        };
+        let (x, y, z) = self.transform_raw(x, y, z)?;
+        match adjust {
+            Some((n, conversion)) => Some((x, y, conversion.apply(z, n))),
+            None => Some((x, y, z)),
+        }
+    }
+
+    fn transform_raw(&self, x: f64, y: f64, z: f64) -> Option<(f64, f64, f64)> {
         use proj_sys::*;
         unsafe {
             let coord = PJ_COORD {
@@ -78,6 +117,12 @@ impl CrsTransformer {
     }
 }
 
+impl CoordinateTransform for CrsTransformer {
+    fn transform(&self, x: f64, y: f64, z: f64) -> Option<(f64, f64, f64)> {
+        CrsTransformer::transform(self, x, y, z)
+    }
+}
+
 /// Representation of a coordinate reference system.
 ///
 /// A CRS is stored internally as a definition string which can be an EPSG
@@ -125,6 +170,35 @@ impl Crs {
         &self.definition
     }
 
+    /// OGC WKT 1 of this CRS in the GDAL dialect, converting EPSG codes and
+    /// Proj4 strings through PROJ. WKT definitions are returned unchanged.
+    pub fn to_wkt(&self) -> Option<String> {
+        use proj_sys::*;
+        use std::ffi::{CStr, CString};
+
+        if self.definition.contains('[') {
+            return Some(self.definition.clone());
+        }
+        let definition = CString::new(self.definition.as_str()).ok()?;
+        unsafe {
+            let ctx = proj_context_create();
+            if ctx.is_null() {
+                return None;
+            }
+            let pj = proj_create(ctx, definition.as_ptr());
+            let mut wkt = None;
+            if !pj.is_null() {
+                let text = proj_as_wkt(ctx, pj, PJ_WKT_TYPE_PJ_WKT1_GDAL, std::ptr::null());
+                if !text.is_null() {
+                    wkt = Some(CStr::from_ptr(text).to_string_lossy().into_owned());
+                }
+                proj_destroy(pj);
+            }
+            proj_context_destroy(ctx);
+            wkt
+        }
+    }
+
     /// Common global CRS definition: WGS84 (EPSG:4326).
     pub fn wgs84() -> Self {
         Self::from_epsg(4326)
@@ -146,6 +220,139 @@ impl Crs {
         Self::from_epsg(3400)
     }
 
+    /// Returns `true` for geographic (longitude/latitude) systems.
+    pub fn is_geographic(&self) -> bool {
+        const GEOGRAPHIC: [u32; 10] = [4326, 4979, 4269, 4617, 4954, 4955, 6318, 6319, 4258, 4937];
+        if let Some(code) = self.epsg {
+            return GEOGRAPHIC.contains(&code);
+        }
+        let def = self.definition.trim_start().to_ascii_uppercase();
+        def.contains("+PROJ=LONGLAT")
+            || def.contains("+PROJ=LATLONG")
+            || ["GEOGCS", "GEOGCRS", "GEOGRAPHICCRS"]
+                .iter()
+                .any(|k| def.starts_with(k))
+    }
+
+    /// Returns the `(longitude, latitude)` in degrees of a coordinate in this
+    /// CRS, used to look up geoid separations.
+    pub fn geographic_position(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        if self.is_geographic() {
+            Some((x, y))
+        } else {
+            self.transform_point(&Crs::wgs84(), x, y)
+        }
+    }
+
+    /// Reference ellipsoid of a Proj4 definition, from `+ellps`, `+datum`
+    /// or explicit `+a` with `+rf` or `+b`. GRS80 when none is given.
+    fn proj4_ellipsoid(&self) -> Ellipsoid {
+        let mut named = None;
+        let (mut a, mut rf, mut b) = (None, None, None);
+        for token in self.definition.split_whitespace() {
+            match token.trim_start_matches('+').split_once('=') {
+                Some(("ellps", v)) | Some(("datum", v)) => named = named.or(Ellipsoid::named(v)),
+                Some(("a", v)) => a = v.parse::<f64>().ok(),
+                Some(("rf", v)) => rf = v.parse::<f64>().ok(),
+                Some(("b", v)) => b = v.parse::<f64>().ok(),
+                _ => {}
+            }
+        }
+        match (a, rf, b) {
+            (Some(a), Some(rf), _) => Ellipsoid { a, f: 1.0 / rf },
+            (Some(a), None, Some(b)) => Ellipsoid { a, f: (a - b) / a },
+            (Some(a), None, None) => Ellipsoid { a, f: 0.0 },
+            _ => named.unwrap_or(Ellipsoid::GRS80),
+        }
+    }
+
+    /// Transverse Mercator parameters for UTM, Alberta 3TM/10TM and
+    /// `+proj=tmerc`/`+proj=utm` definitions, on the ellipsoid of the CRS.
+    pub fn transverse_mercator(&self) -> Option<TransverseMercator> {
+        if let Some(code) = self.epsg {
+            return epsg_transverse_mercator(code);
+        }
+        let ellipsoid = self.proj4_ellipsoid();
+        let mut proj = None;
+        let mut params = std::collections::HashMap::new();
+        for token in self.definition.split_whitespace() {
+            let token = token.trim_start_matches('+');
+            match token.split_once('=') {
+                Some(("proj", v)) => proj = Some(v.to_string()),
+                Some((k, v)) => {
+                    params.insert(k.to_string(), v.to_string());
+                }
+                None => {
+                    params.insert(token.to_string(), String::new());
+                }
+            }
+        }
+        let get = |k: &str| params.get(k).and_then(|v| v.parse::<f64>().ok());
+        match proj.as_deref()? {
+            "utm" => Some(TransverseMercator {
+                ellipsoid,
+                ..TransverseMercator::utm(get("zone")? as u32, params.contains_key("south"))
+            }),
+            "tmerc" => Some(TransverseMercator {
+                central_meridian: get("lon_0").unwrap_or(0.0),
+                latitude_of_origin: get("lat_0").unwrap_or(0.0),
+                scale: get("k_0").or_else(|| get("k")).unwrap_or(1.0),
+                false_easting: get("x_0").unwrap_or(0.0),
+                false_northing: get("y_0").unwrap_or(0.0),
+                ellipsoid,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns the `(latitude, longitude)` in degrees of a projected
+    /// coordinate, using the Transverse Mercator parameters when known.
+    fn latitude_longitude(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        match self.transverse_mercator() {
+            Some(tm) => Some(tm.inverse(x, y)),
+            None => self.geographic_position(x, y).map(|(lon, lat)| (lat, lon)),
+        }
+    }
+
+    /// Grid (point) scale factor at a projected coordinate.
+    pub fn grid_scale_factor(&self, x: f64, y: f64) -> Option<f64> {
+        let tm = self.transverse_mercator()?;
+        let (lat, lon) = tm.inverse(x, y);
+        Some(tm.scale_factor(lat, lon))
+    }
+
+    /// Line scale factor between two projected coordinates using Simpson's
+    /// rule over the end and mid points.
+    pub fn line_scale_factor(&self, a: (f64, f64), b: (f64, f64)) -> Option<f64> {
+        let k1 = self.grid_scale_factor(a.0, a.1)?;
+        let km = self.grid_scale_factor((a.0 + b.0) / 2.0, (a.1 + b.1) / 2.0)?;
+        let k2 = self.grid_scale_factor(b.0, b.1)?;
+        Some((k1 + 4.0 * km + k2) / 6.0)
+    }
+
+    /// Meridian convergence in radians at a projected coordinate.
+    pub fn convergence(&self, x: f64, y: f64) -> Option<f64> {
+        let tm = self.transverse_mercator()?;
+        let (lat, lon) = tm.inverse(x, y);
+        Some(tm.convergence(lat, lon))
+    }
+
+    /// Elevation factor at a coordinate for the given ellipsoidal height.
+    pub fn elevation_factor(&self, x: f64, y: f64, ellipsoidal_height: f64) -> Option<f64> {
+        let (lat, _) = self.latitude_longitude(x, y)?;
+        let ellipsoid = self
+            .transverse_mercator()
+            .map_or(Ellipsoid::GRS80, |tm| tm.ellipsoid);
+        Some(ellipsoid.elevation_factor(lat, ellipsoidal_height))
+    }
+
+    /// Combined scale factor (grid scale factor times elevation factor) at a
+    /// projected coordinate. Ground distances multiplied by this value give
+    /// grid distances.
+    pub fn combined_scale_factor(&self, x: f64, y: f64, ellipsoidal_height: f64) -> Option<f64> {
+        Some(self.grid_scale_factor(x, y)? * self.elevation_factor(x, y, ellipsoidal_height)?)
+    }
+
     /// Transforms an `(x, y)` coordinate from this CRS to the target CRS.
     pub fn transform_point(&self, target: &Crs, x: f64, y: f64) -> Option<(f64, f64)> {
         let proj = Proj::new_known_crs(&self.definition, &target.definition, None).ok()?;
@@ -165,6 +372,215 @@ impl Crs {
     }
 }
 
+/// Reference ellipsoid given by its semi-major axis in metres and
+/// flattening.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ellipsoid {
+    pub a: f64,
+    pub f: f64,
+}
+
+impl Ellipsoid {
+    /// GRS80, used by NAD83 and NAD83(CSRS).
+    pub const GRS80: Self = Self {
+        a: GRS80_A,
+        f: GRS80_F,
+    };
+    /// WGS84.
+    pub const WGS84: Self = Self {
+        a: 6_378_137.0,
+        f: 1.0 / 298.257_223_563,
+    };
+    /// Clarke 1866, used by NAD27.
+    pub const CLARKE_1866: Self = Self {
+        a: 6_378_206.4,
+        f: 1.0 / 294.978_698_213_898,
+    };
+
+    /// Ellipsoid of a Proj4 `+ellps` or `+datum` name.
+    pub fn named(name: &str) -> Option<Self> {
+        match name.to_ascii_uppercase().as_str() {
+            "GRS80" | "NAD83" => Some(Self::GRS80),
+            "WGS84" => Some(Self::WGS84),
+            "CLRK66" | "NAD27" => Some(Self::CLARKE_1866),
+            _ => None,
+        }
+    }
+
+    /// First eccentricity squared.
+    pub fn e2(&self) -> f64 {
+        self.f * (2.0 - self.f)
+    }
+
+    /// Elevation (height) factor `R / (R + h)` for an ellipsoidal height
+    /// `h` at a latitude in degrees, using the Gaussian mean radius of
+    /// curvature.
+    pub fn elevation_factor(&self, lat: f64, ellipsoidal_height: f64) -> f64 {
+        let e2 = self.e2();
+        let sin = lat.to_radians().sin();
+        let w = 1.0 - e2 * sin * sin;
+        let r = self.a * (1.0 - e2).sqrt() / w;
+        r / (r + ellipsoidal_height)
+    }
+}
+
+/// Transverse Mercator projection parameters.
+///
+/// Used to compute grid scale factors and meridian convergence without a
+/// round trip through PROJ. Angles are in degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TransverseMercator {
+    pub central_meridian: f64,
+    pub latitude_of_origin: f64,
+    pub scale: f64,
+    pub false_easting: f64,
+    pub false_northing: f64,
+    pub ellipsoid: Ellipsoid,
+}
+
+impl TransverseMercator {
+    /// UTM zone parameters on GRS80.
+    pub fn utm(zone: u32, south: bool) -> Self {
+        Self {
+            central_meridian: zone as f64 * 6.0 - 183.0,
+            latitude_of_origin: 0.0,
+            scale: 0.9996,
+            false_easting: 500_000.0,
+            false_northing: if south { 10_000_000.0 } else { 0.0 },
+            ellipsoid: Ellipsoid::GRS80,
+        }
+    }
+
+    /// 3° Transverse Mercator (e.g. Alberta 3TM) on GRS80 with the given
+    /// reference meridian in degrees west.
+    pub fn three_tm(meridian_west: f64) -> Self {
+        Self {
+            central_meridian: -meridian_west,
+            latitude_of_origin: 0.0,
+            scale: 0.9999,
+            false_easting: 0.0,
+            false_northing: 0.0,
+            ellipsoid: Ellipsoid::GRS80,
+        }
+    }
+
+    fn e2(&self) -> f64 {
+        self.ellipsoid.e2()
+    }
+
+    fn meridian_arc(&self, lat: f64) -> f64 {
+        let e2 = self.e2();
+        let (e4, e6) = (e2 * e2, e2 * e2 * e2);
+        self.ellipsoid.a
+            * ((1.0 - e2 / 4.0 - 3.0 * e4 / 64.0 - 5.0 * e6 / 256.0) * lat
+                - (3.0 * e2 / 8.0 + 3.0 * e4 / 32.0 + 45.0 * e6 / 1024.0) * (2.0 * lat).sin()
+                + (15.0 * e4 / 256.0 + 45.0 * e6 / 1024.0) * (4.0 * lat).sin()
+                - (35.0 * e6 / 3072.0) * (6.0 * lat).sin())
+    }
+
+    /// Projects a latitude/longitude in degrees to easting and northing.
+    pub fn forward(&self, lat: f64, lon: f64) -> (f64, f64) {
+        let e2 = self.e2();
+        let ep2 = e2 / (1.0 - e2);
+        let phi = lat.to_radians();
+        let (sin, cos) = phi.sin_cos();
+        let n = self.ellipsoid.a / (1.0 - e2 * sin * sin).sqrt();
+        let t = phi.tan().powi(2);
+        let c = ep2 * cos * cos;
+        let a = (lon - self.central_meridian).to_radians() * cos;
+        let m = self.meridian_arc(phi) - self.meridian_arc(self.latitude_of_origin.to_radians());
+        let x = self.false_easting
+            + self.scale
+                * n
+                * (a + (1.0 - t + c) * a.powi(3) / 6.0
+                    + (5.0 - 18.0 * t + t * t + 72.0 * c - 58.0 * ep2) * a.powi(5) / 120.0);
+        let y = self.false_northing
+            + self.scale
+                * (m + n
+                    * phi.tan()
+                    * (a * a / 2.0
+                        + (5.0 - t + 9.0 * c + 4.0 * c * c) * a.powi(4) / 24.0
+                        + (61.0 - 58.0 * t + t * t + 600.0 * c - 330.0 * ep2) * a.powi(6) / 720.0));
+        (x, y)
+    }
+
+    /// Converts easting and northing back to latitude and longitude in
+    /// degrees.
+    pub fn inverse(&self, x: f64, y: f64) -> (f64, f64) {
+        let e2 = self.e2();
+        let ep2 = e2 / (1.0 - e2);
+        let a = self.ellipsoid.a;
+        let m = self.meridian_arc(self.latitude_of_origin.to_radians())
+            + (y - self.false_northing) / self.scale;
+        let mu = m / (a * (1.0 - e2 / 4.0 - 3.0 * e2 * e2 / 64.0 - 5.0 * e2.powi(3) / 256.0));
+        let e1 = (1.0 - (1.0 - e2).sqrt()) / (1.0 + (1.0 - e2).sqrt());
+        let phi1 = mu
+            + (3.0 * e1 / 2.0 - 27.0 * e1.powi(3) / 32.0) * (2.0 * mu).sin()
+            + (21.0 * e1 * e1 / 16.0 - 55.0 * e1.powi(4) / 32.0) * (4.0 * mu).sin()
+            + (151.0 * e1.powi(3) / 96.0) * (6.0 * mu).sin()
+            + (1097.0 * e1.powi(4) / 512.0) * (8.0 * mu).sin();
+        let (sin, cos) = phi1.sin_cos();
+        let c1 = ep2 * cos * cos;
+        let t1 = phi1.tan().powi(2);
+        let w = 1.0 - e2 * sin * sin;
+        let n1 = a / w.sqrt();
+        let r1 = a * (1.0 - e2) / w.powf(1.5);
+        let d = (x - self.false_easting) / (n1 * self.scale);
+        let lat = phi1
+            - (n1 * phi1.tan() / r1)
+                * (d * d / 2.0
+                    - (5.0 + 3.0 * t1 + 10.0 * c1 - 4.0 * c1 * c1 - 9.0 * ep2) * d.powi(4) / 24.0
+                    + (61.0 + 90.0 * t1 + 298.0 * c1 + 45.0 * t1 * t1
+                        - 252.0 * ep2
+                        - 3.0 * c1 * c1)
+                        * d.powi(6)
+                        / 720.0);
+        let dlon = (d - (1.0 + 2.0 * t1 + c1) * d.powi(3) / 6.0
+            + (5.0 - 2.0 * c1 + 28.0 * t1 - 3.0 * c1 * c1 + 8.0 * ep2 + 24.0 * t1 * t1)
+                * d.powi(5)
+                / 120.0)
+            / cos;
+        (lat.to_degrees(), self.central_meridian + dlon.to_degrees())
+    }
+
+    /// Point scale factor at a latitude/longitude in degrees.
+    pub fn scale_factor(&self, lat: f64, lon: f64) -> f64 {
+        let e2 = self.e2();
+        let ep2 = e2 / (1.0 - e2);
+        let phi = lat.to_radians();
+        let cos = phi.cos();
+        let t = phi.tan().powi(2);
+        let c = ep2 * cos * cos;
+        let a = (lon - self.central_meridian).to_radians() * cos;
+        self.scale
+            * (1.0
+                + (1.0 + c) * a * a / 2.0
+                + (5.0 - 4.0 * t + 42.0 * c + 13.0 * c * c - 28.0 * ep2) * a.powi(4) / 24.0
+                + (61.0 - 148.0 * t + 16.0 * t * t) * a.powi(6) / 720.0)
+    }
+
+    /// Meridian convergence in radians at a latitude/longitude in degrees.
+    /// Positive east of the central meridian, so that
+    /// `geodetic azimuth = grid azimuth + convergence`.
+    pub fn convergence(&self, lat: f64, lon: f64) -> f64 {
+        let e2 = self.e2();
+        let ep2 = e2 / (1.0 - e2);
+        let phi = lat.to_radians();
+        let (sin, cos) = phi.sin_cos();
+        let c = ep2 * cos * cos;
+        let t = phi.tan().powi(2);
+        let l = (lon - self.central_meridian).to_radians();
+        let lc2 = (l * cos).powi(2);
+        l * sin * (1.0 + lc2 * (1.0 + 3.0 * c + 2.0 * c * c) / 3.0 + lc2 * lc2 * (2.0 - t) / 15.0)
+    }
+}
+
+/// Elevation (height) factor `R / (R + h)` on GRS80; see
+/// [`Ellipsoid::elevation_factor`].
+pub fn elevation_factor(lat: f64, ellipsoidal_height: f64) -> f64 {
+    Ellipsoid::GRS80.elevation_factor(lat, ellipsoidal_height)
+}
+
 /// Simple CRS information record loaded from the PROJ database.
 #[derive(Debug, Clone)]
 pub struct CrsEntry {
@@ -174,6 +590,65 @@ pub struct CrsEntry {
     pub name: String,
 }
 
+fn epsg_transverse_mercator(code: u32) -> Option<TransverseMercator> {
+    const CSRS_UTM: [(u32, u32); 15] = [
+        (2955, 11),
+        (2956, 12),
+        (2957, 13),
+        (2958, 17),
+        (2959, 18),
+        (2960, 19),
+        (2961, 20),
+        (2962, 21),
+        (3154, 7),
+        (3155, 8),
+        (3156, 9),
+        (3157, 10),
+        (3158, 14),
+        (3159, 15),
+        (3160, 16),
+    ];
+    let ten_tm = |false_easting| TransverseMercator {
+        central_meridian: -115.0,
+        latitude_of_origin: 0.0,
+        scale: 0.9992,
+        false_easting,
+        false_northing: 0.0,
+        ellipsoid: Ellipsoid::GRS80,
+    };
+    let on = |ellipsoid, tm: TransverseMercator| TransverseMercator { ellipsoid, ..tm };
+    match code {
+        // NAD27 UTM on Clarke 1866
+        26701..=26722 => Some(on(
+            Ellipsoid::CLARKE_1866,
+            TransverseMercator::utm(code - 26700, false),
+        )),
+        26901..=26923 => Some(TransverseMercator::utm(code - 26900, false)),
+        32601..=32660 => Some(on(
+            Ellipsoid::WGS84,
+            TransverseMercator::utm(code - 32600, false),
+        )),
+        32701..=32760 => Some(on(
+            Ellipsoid::WGS84,
+            TransverseMercator::utm(code - 32700, true),
+        )),
+        // NAD83 and NAD83(CSRS) Alberta 3TM, reference meridians 111-120 W
+        3776..=3779 => Some(TransverseMercator::three_tm(
+            111.0 + 3.0 * (code - 3776) as f64,
+        )),
+        3780..=3783 => Some(TransverseMercator::three_tm(
+            111.0 + 3.0 * (code - 3780) as f64,
+        )),
+        // Alberta 10TM (AEP Forest and Resource)
+        3400 | 3402 => Some(ten_tm(500_000.0)),
+        3401 | 3403 => Some(ten_tm(0.0)),
+        _ => CSRS_UTM
+            .iter()
+            .find(|(c, _)| *c == code)
+            .map(|(_, zone)| TransverseMercator::utm(*zone, false)),
+    }
+}
+
 /// Loads available coordinate reference systems from the system PROJ database.
 pub fn list_known_crs() -> Vec<CrsEntry> {
     let path = "/usr/share/proj/proj.db";
@@ -214,4 +689,80 @@ mod tests {
         let (x, y) = wgs84.transform_point(&webm, 0.0, 0.0).unwrap();
         assert!(x.abs() < 1e-6 && y.abs() < 1e-6);
     }
+
+    #[test]
+    fn transverse_mercator_round_trip() {
+        let tm = Crs::from_epsg(26912).transverse_mercator().unwrap();
+        assert_eq!(tm.central_meridian, -111.0);
+        let (x, y) = tm.forward(0.0, -111.0);
+        assert!((x - 500_000.0).abs() < 1e-6 && y.abs() < 1e-6);
+        let (x, y) = tm.forward(53.5, -113.5);
+        let (lat, lon) = tm.inverse(x, y);
+        assert!((lat - 53.5).abs() < 1e-8);
+        assert!((lon + 113.5).abs() < 1e-8);
+        // the series agree with each other away from the meridian
+        let k = Crs::from_epsg(26912).grid_scale_factor(x, y).unwrap();
+        assert!((k - tm.scale_factor(53.5, -113.5)).abs() < 1e-12);
+        assert!(k > 0.9996);
+    }
+
+    #[test]
+    fn scale_factor_and_convergence() {
+        let crs = Crs::from_proj4("+proj=utm +zone=12 +ellps=GRS80 +units=m +no_defs");
+        let k0 = crs.grid_scale_factor(500_000.0, 5_900_000.0).unwrap();
+        assert!((k0 - 0.9996).abs() < 1e-9);
+        let (x, y) = crs.transverse_mercator().unwrap().forward(53.5, -108.0);
+        let gamma = crs.convergence(x, y).unwrap().to_degrees();
+        // roughly dlon * sin(lat) east of the central meridian
+        assert!((gamma - 3.0 * 53.5f64.to_radians().sin()).abs() < 0.01);
+        let ef = elevation_factor(53.5, 650.0);
+        assert!((ef - 0.999898).abs() < 1e-6);
+        let csf = crs.combined_scale_factor(x, y, 650.0).unwrap();
+        assert!((csf - crs.grid_scale_factor(x, y).unwrap() * ef).abs() < 1e-12);
+        let three_tm = Crs::from_epsg(3777).transverse_mercator().unwrap();
+        assert_eq!(
+            (three_tm.central_meridian, three_tm.scale),
+            (-114.0, 0.9999)
+        );
+        assert!(Crs::web_mercator().transverse_mercator().is_none());
+    }
+
+    #[test]
+    fn ellipsoid_follows_the_datum() {
+        let nad27 = Crs::from_epsg(26712).transverse_mercator().unwrap();
+        assert_eq!(nad27.ellipsoid, Ellipsoid::CLARKE_1866);
+        assert_eq!(nad27.central_meridian, -111.0);
+        let proj4 = Crs::from_proj4("+proj=utm +zone=12 +datum=NAD27 +units=m +no_defs");
+        assert_eq!(proj4.transverse_mercator().unwrap(), nad27);
+        let explicit = Crs::from_proj4(
+            "+proj=tmerc +lon_0=-111 +k=0.9996 +x_0=500000 +a=6378206.4 +b=6356583.8",
+        );
+        assert!(
+            (explicit.transverse_mercator().unwrap().ellipsoid.f - nad27.ellipsoid.f).abs() < 1e-12
+        );
+        assert_eq!(
+            Crs::from_epsg(32612)
+                .transverse_mercator()
+                .unwrap()
+                .ellipsoid,
+            Ellipsoid::WGS84
+        );
+        assert_eq!(
+            Crs::from_epsg(26912)
+                .transverse_mercator()
+                .unwrap()
+                .ellipsoid,
+            Ellipsoid::GRS80
+        );
+
+        // the same latitude projects tens of metres apart on the two
+        // ellipsoids
+        let nad83 = Crs::from_epsg(26912).transverse_mercator().unwrap();
+        let (_, y27) = nad27.forward(53.5, -113.5);
+        let (_, y83) = nad83.forward(53.5, -113.5);
+        assert!((y27 - y83).abs() > 50.0);
+        let (lat, lon) = nad27.inverse(nad27.forward(53.5, -113.5).0, y27);
+        assert!((lat - 53.5).abs() < 1e-8 && (lon + 113.5).abs() < 1e-8);
+        assert!((nad27.scale_factor(53.5, -113.5) - nad83.scale_factor(53.5, -113.5)).abs() < 1e-6);
+    }
 }
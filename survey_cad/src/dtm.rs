@@ -1,4 +1,6 @@
-use crate::geometry::{polygon_area, Point, Point3, Polyline};
+use std::collections::HashMap;
+
+use crate::geometry::{polygon_area, Point, Point3, Polyline, Polyline3};
 
 /// Classification for breaklines when building constrained TINs.
 #[derive(Debug, Clone, Copy)]
@@ -266,6 +268,47 @@ impl Tin {
         Tin::from_points_constrained(self.vertices.clone(), Some(breaklines), None)
     }
 
+    /// Builds a constrained TIN from `points` and polyline breaklines such
+    /// as feature lines. Breakline vertices are added to the surface, reusing
+    /// points at the same plan position, and arcs are split into chords
+    /// subtending at most `max_angle` radians.
+    pub fn from_points_and_breaklines(
+        mut points: Vec<Point3>,
+        breaklines: &[Polyline3],
+        max_angle: f64,
+    ) -> Result<Self, cdt::Error> {
+        const TOL: f64 = 1e-9;
+        // plan positions snapped to a grid of the tolerance; the cells
+        // around a vertex are probed so matches across a cell edge count
+        let cell = |p: Point3| ((p.x / TOL).round() as i64, (p.y / TOL).round() as i64);
+        let mut index: HashMap<(i64, i64), usize> = HashMap::with_capacity(points.len());
+        for (i, p) in points.iter().enumerate().rev() {
+            index.insert(cell(*p), i);
+        }
+        let mut edges = Vec::new();
+        for bl in breaklines {
+            let mut ids = Vec::new();
+            for v in bl.densify(max_angle) {
+                let (cx, cy) = cell(v);
+                let found = (cx - 1..=cx + 1)
+                    .flat_map(|x| (cy - 1..=cy + 1).map(move |y| (x, y)))
+                    .filter_map(|k| index.get(&k).copied())
+                    .find(|&i| (points[i].x - v.x).hypot(points[i].y - v.y) < TOL);
+                ids.push(found.unwrap_or_else(|| {
+                    points.push(v);
+                    index.insert((cx, cy), points.len() - 1);
+                    points.len() - 1
+                }));
+            }
+            edges.extend(ids.windows(2).map(|w| (w[0], w[1])));
+            if bl.closed && ids.len() > 2 {
+                edges.push((ids[ids.len() - 1], ids[0]));
+            }
+        }
+        edges.retain(|(a, b)| a != b);
+        Tin::from_points_constrained(points, Some(&edges), None)
+    }
+
     /// Builds a constrained TIN using classified breaklines. Only hard
     /// breaklines are enforced; soft breaklines are ignored when
     /// constructing the triangulation.
@@ -986,6 +1029,25 @@ mod tests {
             .any(|t| t.contains(&0) && t.contains(&2)));
     }
 
+    #[test]
+    fn polyline_breaklines_add_vertices() {
+        let pts = vec![
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(20.0, 0.0, 0.0),
+            Point3::new(20.0, 20.0, 0.0),
+            Point3::new(0.0, 20.0, 0.0),
+        ];
+        let curb = Polyline3::with_bulges(
+            vec![Point3::new(0.0, 0.0, 0.0), Point3::new(20.0, 20.0, 1.0)],
+            vec![0.2],
+        );
+        let tin = Tin::from_points_and_breaklines(pts, &[curb], 0.1).unwrap();
+        // Both ends reuse corners; eight chords add seven interior points
+        // with interpolated elevations.
+        assert_eq!(tin.vertices.len(), 4 + 7);
+        assert_eq!(tin.vertices[10].z, 0.875);
+    }
+
     #[test]
     fn slope_projection_simple() {
         let pts = vec![
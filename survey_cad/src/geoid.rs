@@ -0,0 +1,561 @@
+//! Geoid models for converting between ellipsoidal and orthometric heights.
+//!
+//! Grids are loaded from local files so no network access or PROJ grid
+//! configuration is required. Three common distribution formats are read:
+//!
+//! * **GTX** – NOAA/PROJ vertical grid (used for GEOID18 and most PROJ
+//!   geoid grids).
+//! * **BYN** – NRCan binary grid (used for CGG2013a and HTv2.0).
+//! * **NTv2** (`.gsb`) – grid shift files distributed for height conversion
+//!   store the geoid separation in the latitude shift field. Only the first
+//!   sub-grid is used.
+//!
+//! Whatever the source format, grids are held south to north and west to
+//! east with latitudes and longitudes in decimal degrees and the geoid
+//! separation `N` in metres, so that `h = H + N`.
+
+use serde::{Deserialize, Serialize};
+use std::io;
+
+fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+/// Direction of a height conversion.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeightConversion {
+    /// GNSS ellipsoidal height `h` to orthometric height `H = h - N`.
+    EllipsoidalToOrthometric,
+    /// Orthometric height `H` to ellipsoidal height `h = H + N`.
+    OrthometricToEllipsoidal,
+}
+
+impl HeightConversion {
+    /// Applies the conversion to `height` given the geoid separation `n`.
+    pub fn apply(self, height: f64, n: f64) -> f64 {
+        match self {
+            Self::EllipsoidalToOrthometric => height - n,
+            Self::OrthometricToEllipsoidal => height + n,
+        }
+    }
+}
+
+/// Vertical datum that heights in a project or export refer to.
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct VerticalDatum {
+    /// Datum name such as `"CGVD2013"` or `"NAVD88"`.
+    pub name: String,
+    /// Geoid model used to realise the datum, e.g. `"CGG2013a"` or `"GEOID18"`.
+    #[serde(default)]
+    pub geoid_model: Option<String>,
+}
+
+impl VerticalDatum {
+    pub fn new(name: &str, geoid_model: Option<&str>) -> Self {
+        Self {
+            name: name.to_string(),
+            geoid_model: geoid_model.map(|s| s.to_string()),
+        }
+    }
+}
+
+/// Regular latitude/longitude grid of geoid separations.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GeoidGrid {
+    pub name: String,
+    /// Latitude of the southern row in degrees.
+    pub south: f64,
+    /// Longitude of the western column in degrees.
+    pub west: f64,
+    /// Latitude spacing in degrees.
+    pub dlat: f64,
+    /// Longitude spacing in degrees.
+    pub dlon: f64,
+    pub rows: usize,
+    pub cols: usize,
+    /// Row-major separations starting at the south-west corner. Missing
+    /// values are stored as `NaN`.
+    pub values: Vec<f32>,
+}
+
+impl GeoidGrid {
+    fn value(&self, row: usize, col: usize) -> Option<f64> {
+        let v = *self.values.get(row * self.cols + col)?;
+        (!v.is_nan()).then_some(v as f64)
+    }
+
+    /// Returns the bilinearly interpolated geoid separation at the given
+    /// latitude and longitude in degrees, or `None` outside the grid.
+    pub fn undulation(&self, lat: f64, lon: f64) -> Option<f64> {
+        if self.rows < 2 || self.cols < 2 {
+            return None;
+        }
+        let mut x = (lon - self.west) / self.dlon;
+        let width = (self.cols - 1) as f64;
+        if x < 0.0 || x > width {
+            // grids such as GEOID18 use 0..360 longitudes
+            let wrapped = (lon - self.west).rem_euclid(360.0) / self.dlon;
+            if wrapped > width {
+                return None;
+            }
+            x = wrapped;
+        }
+        let y = (lat - self.south) / self.dlat;
+        if y < 0.0 || y > (self.rows - 1) as f64 {
+            return None;
+        }
+        let c = (x.floor() as usize).min(self.cols - 2);
+        let r = (y.floor() as usize).min(self.rows - 2);
+        let (fx, fy) = (x - c as f64, y - r as f64);
+        let mut n = 0.0;
+        for (dr, dc, w) in [
+            (0, 0, (1.0 - fx) * (1.0 - fy)),
+            (0, 1, fx * (1.0 - fy)),
+            (1, 0, (1.0 - fx) * fy),
+            (1, 1, fx * fy),
+        ] {
+            // cells without weight may be empty, e.g. at the coastline
+            if w > 0.0 {
+                n += w * self.value(r + dr, c + dc)?;
+            }
+        }
+        Some(n)
+    }
+
+    /// Converts an ellipsoidal height to an orthometric height.
+    pub fn orthometric_height(&self, lat: f64, lon: f64, h: f64) -> Option<f64> {
+        self.convert(lat, lon, h, HeightConversion::EllipsoidalToOrthometric)
+    }
+
+    /// Converts an orthometric height to an ellipsoidal height.
+    pub fn ellipsoidal_height(&self, lat: f64, lon: f64, h: f64) -> Option<f64> {
+        self.convert(lat, lon, h, HeightConversion::OrthometricToEllipsoidal)
+    }
+
+    /// Applies `conversion` to `height` at the given latitude and longitude.
+    pub fn convert(
+        &self,
+        lat: f64,
+        lon: f64,
+        height: f64,
+        conversion: HeightConversion,
+    ) -> Option<f64> {
+        self.undulation(lat, lon)
+            .map(|n| conversion.apply(height, n))
+    }
+}
+
+/// Endian aware cursor over a binary grid file.
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+    big_endian: bool,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8], big_endian: bool) -> Self {
+        Self {
+            data,
+            pos: 0,
+            big_endian,
+        }
+    }
+
+    fn bytes<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        let slice = self
+            .data
+            .get(self.pos..self.pos + N)
+            .ok_or_else(|| invalid("unexpected end of grid file"))?;
+        self.pos += N;
+        let mut out = [0u8; N];
+        out.copy_from_slice(slice);
+        if !self.big_endian {
+            out.reverse();
+        }
+        Ok(out)
+    }
+
+    fn i16(&mut self) -> io::Result<i16> {
+        self.bytes().map(i16::from_be_bytes)
+    }
+
+    fn i32(&mut self) -> io::Result<i32> {
+        self.bytes().map(i32::from_be_bytes)
+    }
+
+    fn f32(&mut self) -> io::Result<f32> {
+        self.bytes().map(f32::from_be_bytes)
+    }
+
+    fn f64(&mut self) -> io::Result<f64> {
+        self.bytes().map(f64::from_be_bytes)
+    }
+
+    fn text(&mut self, len: usize) -> io::Result<String> {
+        let slice = self
+            .data
+            .get(self.pos..self.pos + len)
+            .ok_or_else(|| invalid("unexpected end of grid file"))?;
+        self.pos += len;
+        Ok(String::from_utf8_lossy(slice).trim().to_string())
+    }
+}
+
+fn dimension(value: i32, what: &str) -> io::Result<usize> {
+    usize::try_from(value)
+        .ok()
+        .filter(|v| *v > 0)
+        .ok_or_else(|| invalid(format!("invalid {what} count {value}")))
+}
+
+/// Parses a GTX grid. The header holds the south-west corner, the spacing
+/// and the grid size in big-endian order, followed by `f32` values.
+pub fn parse_gtx(name: &str, data: &[u8]) -> io::Result<GeoidGrid> {
+    let mut r = Reader::new(data, true);
+    let south = r.f64()?;
+    let west = r.f64()?;
+    let dlat = r.f64()?;
+    let dlon = r.f64()?;
+    let rows = dimension(r.i32()?, "row")?;
+    let cols = dimension(r.i32()?, "column")?;
+    let mut values = Vec::with_capacity(rows * cols);
+    for _ in 0..rows * cols {
+        let v = r.f32()?;
+        // -88.8888 marks cells without data
+        values.push(if (v + 88.8888).abs() < 1e-3 {
+            f32::NAN
+        } else {
+            v
+        });
+    }
+    Ok(GeoidGrid {
+        name: name.to_string(),
+        south,
+        west,
+        dlat,
+        dlon,
+        rows,
+        cols,
+        values,
+    })
+}
+
+/// Serialises a grid in GTX format.
+pub fn write_gtx(path: &str, grid: &GeoidGrid) -> io::Result<()> {
+    let mut out = Vec::with_capacity(40 + grid.values.len() * 4);
+    for v in [grid.south, grid.west, grid.dlat, grid.dlon] {
+        out.extend_from_slice(&v.to_be_bytes());
+    }
+    out.extend_from_slice(&(grid.rows as i32).to_be_bytes());
+    out.extend_from_slice(&(grid.cols as i32).to_be_bytes());
+    for v in &grid.values {
+        let v = if v.is_nan() { -88.8888f32 } else { *v };
+        out.extend_from_slice(&v.to_be_bytes());
+    }
+    std::fs::write(path, out)
+}
+
+/// Parses an NRCan BYN grid. The 80 byte header stores its own byte order;
+/// rows run from north to south and values are scaled integers.
+pub fn parse_byn(name: &str, data: &[u8]) -> io::Result<GeoidGrid> {
+    if data.len() < 80 {
+        return Err(invalid("BYN header is truncated"));
+    }
+    // nByteOrder at offset 44: 0 = big endian, 1 = little endian
+    let big_endian = match (data[44], data[45]) {
+        (0, 0) => true,
+        (1, 0) => false,
+        _ => return Err(invalid("unrecognised BYN byte order")),
+    };
+    let mut r = Reader::new(data, big_endian);
+    let south = r.i32()? as f64;
+    let north = r.i32()? as f64;
+    let west = r.i32()? as f64;
+    let east = r.i32()? as f64;
+    let dlat = r.i16()? as f64;
+    let dlon = r.i16()? as f64;
+    let _global = r.i16()?;
+    let _kind = r.i16()?;
+    let factor = r.f64()?;
+    let size = r.i16()?;
+    // nScale follows the datum and ellipsoid codes; Wo and GM come after it
+    r.pos = 46;
+    let scale = r.i16()?;
+    // boundaries are arc seconds, or milli arc seconds when scaled; the
+    // spacing is always in arc seconds
+    let unit = if scale == 1 { 3_600_000.0 } else { 3600.0 };
+    let (south, north, west, east) = (south / unit, north / unit, west / unit, east / unit);
+    let (dlat, dlon) = (dlat / 3600.0, dlon / 3600.0);
+    if dlat <= 0.0 || dlon <= 0.0 || factor == 0.0 {
+        return Err(invalid("invalid BYN grid spacing"));
+    }
+    let rows = ((north - south) / dlat).round() as usize + 1;
+    let cols = ((east - west) / dlon).round() as usize + 1;
+    r.pos = 80;
+    let mut north_first = Vec::with_capacity(rows * cols);
+    for _ in 0..rows * cols {
+        let v = match size {
+            2 => {
+                let v = r.i16()?;
+                (v != i16::MAX).then_some(v as f64)
+            }
+            4 => {
+                let v = r.i32()?;
+                (v != i32::MAX && v as f64 != 9999.0 * factor).then_some(v as f64)
+            }
+            _ => return Err(invalid(format!("unsupported BYN value size {size}"))),
+        };
+        north_first.push(v.map_or(f32::NAN, |v| (v / factor) as f32));
+    }
+    let values = north_first.chunks(cols).rev().flatten().copied().collect();
+    Ok(GeoidGrid {
+        name: name.to_string(),
+        south,
+        west,
+        dlat,
+        dlon,
+        rows,
+        cols,
+        values,
+    })
+}
+
+/// Parses the first sub-grid of an NTv2 file, using the latitude shift
+/// field as the geoid separation.
+pub fn parse_ntv2(name: &str, data: &[u8]) -> io::Result<GeoidGrid> {
+    if data.len() < 16 || &data[..8] != b"NUM_OREC" {
+        return Err(invalid("missing NTv2 overview header"));
+    }
+    // NUM_OREC is 11, so its first byte tells the byte order
+    let big_endian = data[8] == 0;
+    let mut r = Reader::new(data, big_endian);
+    r.pos = 8;
+    let overview = r.i32()?;
+    r.pos = 16;
+    let mut sub_records = 11;
+    let mut units = String::from("SECONDS");
+    for _ in 1..overview {
+        match r.text(8)?.as_str() {
+            "NUM_SREC" => {
+                sub_records = r.i32()?;
+                r.pos += 4;
+            }
+            "GS_TYPE" => units = r.text(8)?.to_ascii_uppercase(),
+            _ => r.pos += 8,
+        }
+    }
+    let mut header = std::collections::HashMap::new();
+    let mut count = 0;
+    for _ in 0..sub_records {
+        let key = r.text(8)?;
+        match key.as_str() {
+            "SUB_NAME" | "PARENT" | "CREATED" | "UPDATED" => r.pos += 8,
+            "GS_COUNT" => {
+                count = r.i32()?;
+                r.pos += 4;
+            }
+            _ => {
+                header.insert(key, r.f64()?);
+            }
+        }
+    }
+    let get = |k: &str| {
+        header
+            .get(k)
+            .copied()
+            .ok_or_else(|| invalid(format!("missing NTv2 field {k}")))
+    };
+    let unit = match units.as_str() {
+        "MINUTES" => 60.0,
+        "DEGREES" => 1.0,
+        _ => 3600.0,
+    };
+    let (s, n) = (get("S_LAT")?, get("N_LAT")?);
+    // NTv2 longitudes are positive west
+    let (e, w) = (get("E_LONG")?, get("W_LONG")?);
+    let (dlat, dlon) = (get("LAT_INC")?, get("LONG_INC")?);
+    if dlat <= 0.0 || dlon <= 0.0 {
+        return Err(invalid("invalid NTv2 grid spacing"));
+    }
+    let rows = ((n - s) / dlat).round() as usize + 1;
+    let cols = ((w - e) / dlon).round() as usize + 1;
+    if count as usize != rows * cols {
+        return Err(invalid(format!(
+            "NTv2 GS_COUNT {count} does not match {rows}x{cols} grid"
+        )));
+    }
+    let mut values = Vec::with_capacity(rows * cols);
+    for _ in 0..rows {
+        // each row is stored from east to west
+        let mut row = Vec::with_capacity(cols);
+        for _ in 0..cols {
+            row.push(r.f32()?);
+            r.pos += 12;
+        }
+        row.reverse();
+        values.extend(row);
+    }
+    Ok(GeoidGrid {
+        name: name.to_string(),
+        south: s / unit,
+        west: -w / unit,
+        dlat: dlat / unit,
+        dlon: dlon / unit,
+        rows,
+        cols,
+        values,
+    })
+}
+
+/// Reads a geoid grid, choosing the format from the file extension
+/// (`.gtx`, `.byn` or `.gsb`).
+pub fn read_geoid_grid(path: &str) -> io::Result<GeoidGrid> {
+    let p = std::path::Path::new(path);
+    let name = p
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let ext = p
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    let data = std::fs::read(path)?;
+    match ext.as_str() {
+        "gtx" => parse_gtx(&name, &data),
+        "byn" => parse_byn(&name, &data),
+        "gsb" => parse_ntv2(&name, &data),
+        _ => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("unknown geoid grid format '{ext}'"),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> GeoidGrid {
+        GeoidGrid {
+            name: "test".into(),
+            south: 45.0,
+            west: -76.0,
+            dlat: 0.5,
+            dlon: 0.5,
+            rows: 3,
+            cols: 3,
+            values: vec![
+                -30.0, -31.0, -32.0, -33.0, -34.0, -35.0, -36.0, -37.0, -38.0,
+            ],
+        }
+    }
+
+    #[test]
+    fn bilinear_interpolation() {
+        let g = sample();
+        assert_eq!(g.undulation(45.0, -76.0), Some(-30.0));
+        let n = g.undulation(45.25, -75.75).unwrap();
+        assert!((n - -32.0).abs() < 1e-9);
+        assert_eq!(g.undulation(44.0, -76.0), None);
+        // 0..360 longitudes resolve to the same cell
+        let wrapped = GeoidGrid {
+            west: 284.0,
+            ..sample()
+        };
+        assert_eq!(wrapped.undulation(45.0, -75.5), Some(-31.0));
+    }
+
+    #[test]
+    fn height_conversions_are_inverse() {
+        let g = sample();
+        let h = g.orthometric_height(45.5, -75.5, 100.0).unwrap();
+        assert!((h - 134.0).abs() < 1e-9);
+        let back = g.ellipsoidal_height(45.5, -75.5, h).unwrap();
+        assert!((back - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn byn_rows_are_flipped() {
+        let mut data = vec![0u8; 80];
+        let put_i32 =
+            |d: &mut Vec<u8>, off: usize, v: i32| d[off..off + 4].copy_from_slice(&v.to_le_bytes());
+        put_i32(&mut data, 0, 45 * 3600);
+        put_i32(&mut data, 4, 46 * 3600);
+        put_i32(&mut data, 8, -76 * 3600);
+        put_i32(&mut data, 12, -75 * 3600);
+        data[16..18].copy_from_slice(&3600i16.to_le_bytes());
+        data[18..20].copy_from_slice(&3600i16.to_le_bytes());
+        data[24..32].copy_from_slice(&1000f64.to_le_bytes());
+        data[32..34].copy_from_slice(&2i16.to_le_bytes());
+        // nByteOrder, nScale, then the Wo and GM doubles
+        data[44..46].copy_from_slice(&1i16.to_le_bytes());
+        data[46..48].copy_from_slice(&0i16.to_le_bytes());
+        data[48..56].copy_from_slice(&62_636_856.0f64.to_le_bytes());
+        data[56..64].copy_from_slice(&3.986_004_418e14f64.to_le_bytes());
+        // north row first
+        for v in [-20000i16, -21000, -10000, i16::MAX] {
+            data.extend_from_slice(&v.to_le_bytes());
+        }
+        let g = parse_byn("byn", &data).unwrap();
+        assert_eq!((g.rows, g.cols), (2, 2));
+        assert_eq!(g.undulation(45.0, -76.0), Some(-10.0));
+        assert_eq!(g.undulation(46.0, -75.0), Some(-21.0));
+        assert!(g.values[1].is_nan());
+        assert_eq!(g.undulation(45.5, -75.5), None);
+
+        // scaled boundaries are milli arc seconds, the spacing isn't
+        put_i32(&mut data, 0, 45 * 3_600_000);
+        put_i32(&mut data, 4, 46 * 3_600_000);
+        put_i32(&mut data, 8, -76 * 3_600_000);
+        put_i32(&mut data, 12, -75 * 3_600_000);
+        data[46..48].copy_from_slice(&1i16.to_le_bytes());
+        let scaled = parse_byn("byn", &data).unwrap();
+        assert_eq!((scaled.rows, scaled.cols), (2, 2));
+        assert_eq!(scaled.undulation(45.0, -76.0), Some(-10.0));
+    }
+
+    #[test]
+    fn ntv2_rows_run_east_to_west() {
+        let mut data = Vec::new();
+        let int = |d: &mut Vec<u8>, k: &str, v: i32| {
+            d.extend_from_slice(format!("{k:<8}").as_bytes());
+            d.extend_from_slice(&v.to_le_bytes());
+            d.extend_from_slice(&[0; 4]);
+        };
+        int(&mut data, "NUM_OREC", 11);
+        int(&mut data, "NUM_SREC", 11);
+        int(&mut data, "NUM_FILE", 1);
+        data.extend_from_slice(b"GS_TYPE SECONDS ");
+        for k in [
+            "VERSION", "SYSTEM_F", "SYSTEM_T", "MAJOR_F", "MINOR_F", "MAJOR_T", "MINOR_T",
+        ] {
+            data.extend_from_slice(format!("{k:<8}{:<8}", "").as_bytes());
+        }
+        for k in ["SUB_NAME", "PARENT", "CREATED", "UPDATED"] {
+            data.extend_from_slice(format!("{k:<8}{:<8}", "X").as_bytes());
+        }
+        for (k, v) in [
+            ("S_LAT", 45.0 * 3600.0),
+            ("N_LAT", 46.0 * 3600.0),
+            ("E_LONG", 75.0 * 3600.0),
+            ("W_LONG", 76.0 * 3600.0),
+            ("LAT_INC", 3600.0),
+            ("LONG_INC", 3600.0),
+        ] {
+            data.extend_from_slice(format!("{k:<8}").as_bytes());
+            data.extend_from_slice(&f64::to_le_bytes(v));
+        }
+        int(&mut data, "GS_COUNT", 4);
+        // south row then north row, each from east to west
+        for v in [-11f32, -10.0, -13.0, -12.0] {
+            for f in [v, 0.0, 0.0, 0.0] {
+                data.extend_from_slice(&f.to_le_bytes());
+            }
+        }
+        let g = parse_ntv2("gsb", &data).unwrap();
+        assert_eq!((g.rows, g.cols), (2, 2));
+        assert_eq!((g.south, g.west), (45.0, -76.0));
+        assert_eq!(g.undulation(45.0, -76.0), Some(-10.0));
+        assert_eq!(g.undulation(46.0, -75.0), Some(-13.0));
+    }
+}
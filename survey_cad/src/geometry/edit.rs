@@ -0,0 +1,922 @@
+//! Drafting edits on line and arc paths: offset, trim, extend, fillet,
+//! chamfer, break and join.
+
+use std::collections::VecDeque;
+use std::f64::consts::{PI, TAU};
+
+use super::intersect::{
+    circle_intersections, line_circle_intersections, line_line_params, line_point_at,
+};
+use super::{distance, Arc, Line, Point};
+
+/// Distance below which points are treated as coincident.
+const EPS: f64 = 1e-9;
+
+/// Straight or circular piece of a path, traversed from start to end.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub enum PathSegment {
+    Line(Line),
+    /// Arc starting at angle `start` and sweeping `sweep` radians,
+    /// counter-clockwise when positive.
+    Arc {
+        center: Point,
+        radius: f64,
+        start: f64,
+        sweep: f64,
+    },
+}
+
+fn angle_of(center: Point, p: Point) -> f64 {
+    (p.y - center.y).atan2(p.x - center.x)
+}
+
+/// Angle from `from` to `to` in the direction of `sweep`, with the same
+/// sign as `sweep`.
+fn signed_delta(from: f64, to: f64, sweep: f64) -> f64 {
+    if sweep >= 0.0 {
+        (to - from).rem_euclid(TAU)
+    } else {
+        -(from - to).rem_euclid(TAU)
+    }
+}
+
+/// Shorter signed angle from `from` to `to`.
+fn short_delta(from: f64, to: f64) -> f64 {
+    let d = (to - from).rem_euclid(TAU);
+    if d > PI {
+        d - TAU
+    } else {
+        d
+    }
+}
+
+fn unit(from: Point, to: Point) -> Option<(f64, f64)> {
+    let len = distance(from, to);
+    (len > EPS).then(|| ((to.x - from.x) / len, (to.y - from.y) / len))
+}
+
+impl PathSegment {
+    /// Segment between `start` and `end` with a DXF bulge, straight when
+    /// the bulge is zero.
+    pub fn from_bulge(start: Point, end: Point, bulge: f64) -> Self {
+        match Arc::from_bulge(start, end, bulge) {
+            Some(arc) if bulge > 0.0 => Self::Arc {
+                center: arc.center,
+                radius: arc.radius,
+                start: arc.start_angle,
+                sweep: arc.sweep(),
+            },
+            Some(arc) => Self::Arc {
+                center: arc.center,
+                radius: arc.radius,
+                start: arc.end_angle,
+                sweep: -arc.sweep(),
+            },
+            None => Self::Line(Line::new(start, end)),
+        }
+    }
+
+    /// Counter-clockwise arc segment from an [`Arc`].
+    pub fn from_arc(arc: &Arc) -> Self {
+        Self::Arc {
+            center: arc.center,
+            radius: arc.radius,
+            start: arc.start_angle,
+            sweep: arc.sweep(),
+        }
+    }
+
+    /// DXF bulge of the segment, zero for lines.
+    pub fn bulge(&self) -> f64 {
+        match self {
+            Self::Line(_) => 0.0,
+            Self::Arc { sweep, .. } => (sweep / 4.0).tan(),
+        }
+    }
+
+    /// The arc as a counter-clockwise [`Arc`], or `None` for lines.
+    pub fn to_arc(&self) -> Option<Arc> {
+        match *self {
+            Self::Line(_) => None,
+            Self::Arc {
+                center,
+                radius,
+                start,
+                sweep,
+            } if sweep >= 0.0 => Some(Arc::new(center, radius, start, start + sweep)),
+            Self::Arc {
+                center,
+                radius,
+                start,
+                sweep,
+            } => Some(Arc::new(center, radius, start + sweep, start)),
+        }
+    }
+
+    /// Point at parameter `t`, from 0 at the start to 1 at the end.
+    pub fn point_at(&self, t: f64) -> Point {
+        match *self {
+            Self::Line(l) => line_point_at(&l, t),
+            Self::Arc {
+                center,
+                radius,
+                start,
+                sweep,
+            } => {
+                let a = start + sweep * t;
+                Point::new(center.x + radius * a.cos(), center.y + radius * a.sin())
+            }
+        }
+    }
+
+    pub fn start_point(&self) -> Point {
+        self.point_at(0.0)
+    }
+
+    pub fn end_point(&self) -> Point {
+        self.point_at(1.0)
+    }
+
+    pub fn length(&self) -> f64 {
+        match self {
+            Self::Line(l) => l.length(),
+            Self::Arc { radius, sweep, .. } => radius * sweep.abs(),
+        }
+    }
+
+    /// Parameter of `p` on the segment's line or circle. Lines are
+    /// unbounded; arcs give values in `0..` measured in the sweep direction
+    /// from the start.
+    pub fn param_of(&self, p: Point) -> f64 {
+        match *self {
+            Self::Line(l) => {
+                let (dx, dy) = (l.end.x - l.start.x, l.end.y - l.start.y);
+                let len_sq = dx * dx + dy * dy;
+                if len_sq < EPS * EPS {
+                    0.0
+                } else {
+                    ((p.x - l.start.x) * dx + (p.y - l.start.y) * dy) / len_sq
+                }
+            }
+            Self::Arc {
+                center,
+                start,
+                sweep,
+                ..
+            } => {
+                if sweep.abs() < EPS {
+                    return 0.0;
+                }
+                signed_delta(start, angle_of(center, p), sweep) / sweep
+            }
+        }
+    }
+
+    /// Closest point on the segment to `p`.
+    pub fn nearest_point(&self, p: Point) -> Point {
+        match self {
+            Self::Line(l) => l.nearest_point(p),
+            Self::Arc { .. } => self.to_arc().map_or(p, |a| a.nearest_point(p)),
+        }
+    }
+
+    /// Returns `true` if `p` lies on the segment.
+    pub fn contains(&self, p: Point) -> bool {
+        distance(self.nearest_point(p), p) < 1e-6
+    }
+
+    /// The same geometry traversed the other way.
+    pub fn reversed(&self) -> Self {
+        match *self {
+            Self::Line(l) => Self::Line(Line::new(l.end, l.start)),
+            Self::Arc {
+                center,
+                radius,
+                start,
+                sweep,
+            } => Self::Arc {
+                center,
+                radius,
+                start: start + sweep,
+                sweep: -sweep,
+            },
+        }
+    }
+
+    /// Piece of the segment between parameters `t0` and `t1`.
+    pub fn sub(&self, t0: f64, t1: f64) -> Self {
+        match *self {
+            Self::Line(l) => Self::Line(Line::new(line_point_at(&l, t0), line_point_at(&l, t1))),
+            Self::Arc {
+                center,
+                radius,
+                start,
+                sweep,
+            } => Self::Arc {
+                center,
+                radius,
+                start: start + sweep * t0,
+                sweep: sweep * (t1 - t0),
+            },
+        }
+    }
+
+    /// Moves the start to `p`, which should lie on the segment's line or
+    /// circle.
+    pub fn with_start(&self, p: Point) -> Self {
+        match *self {
+            Self::Line(l) => Self::Line(Line::new(p, l.end)),
+            Self::Arc {
+                center,
+                radius,
+                start,
+                sweep,
+            } => {
+                let new_start = angle_of(center, p);
+                Self::Arc {
+                    center,
+                    radius,
+                    start: new_start,
+                    sweep: signed_delta(new_start, start + sweep, sweep),
+                }
+            }
+        }
+    }
+
+    /// Moves the end to `p`, which should lie on the segment's line or
+    /// circle.
+    pub fn with_end(&self, p: Point) -> Self {
+        match *self {
+            Self::Line(l) => Self::Line(Line::new(l.start, p)),
+            Self::Arc {
+                center,
+                radius,
+                start,
+                sweep,
+            } => Self::Arc {
+                center,
+                radius,
+                start,
+                sweep: signed_delta(start, angle_of(center, p), sweep),
+            },
+        }
+    }
+
+    /// Copy moved `dist` to the left of the direction of travel, or `None`
+    /// when an arc would collapse.
+    pub fn offset(&self, dist: f64) -> Option<Self> {
+        match *self {
+            Self::Line(l) => {
+                let (ux, uy) = unit(l.start, l.end)?;
+                let (nx, ny) = (-uy * dist, ux * dist);
+                Some(Self::Line(Line::new(
+                    Point::new(l.start.x + nx, l.start.y + ny),
+                    Point::new(l.end.x + nx, l.end.y + ny),
+                )))
+            }
+            Self::Arc {
+                center,
+                radius,
+                start,
+                sweep,
+            } => {
+                let radius = radius - dist * sweep.signum();
+                (radius > EPS).then_some(Self::Arc {
+                    center,
+                    radius,
+                    start,
+                    sweep,
+                })
+            }
+        }
+    }
+}
+
+/// Intersections of the lines and full circles carrying `a` and `b`.
+fn carrier_intersections(a: &PathSegment, b: &PathSegment) -> Vec<Point> {
+    match (a, b) {
+        (PathSegment::Line(l), PathSegment::Line(m)) => line_line_params(l, m)
+            .map(|(t, _)| line_point_at(l, t))
+            .into_iter()
+            .collect(),
+        (PathSegment::Line(l), PathSegment::Arc { center, radius, .. })
+        | (PathSegment::Arc { center, radius, .. }, PathSegment::Line(l)) => {
+            line_circle_intersections(l, *center, *radius)
+                .into_iter()
+                .map(|(_, p)| p)
+                .collect()
+        }
+        (
+            PathSegment::Arc {
+                center: c0,
+                radius: r0,
+                ..
+            },
+            PathSegment::Arc {
+                center: c1,
+                radius: r1,
+                ..
+            },
+        ) => circle_intersections(*c0, *r0, *c1, *r1),
+    }
+}
+
+/// Points where two segments cross.
+pub fn segment_intersections(a: &PathSegment, b: &PathSegment) -> Vec<Point> {
+    carrier_intersections(a, b)
+        .into_iter()
+        .filter(|p| a.contains(*p) && b.contains(*p))
+        .collect()
+}
+
+/// Segments between `vertices`, each with the bulge of the segment that
+/// starts there. Closed paths return to the first vertex.
+pub fn path_from_bulges(vertices: &[(Point, f64)], closed: bool) -> Vec<PathSegment> {
+    let n = vertices.len();
+    let count = if closed && n >= 2 {
+        n
+    } else {
+        n.saturating_sub(1)
+    };
+    (0..count)
+        .map(|i| {
+            let ((a, bulge), (b, _)) = (vertices[i], vertices[(i + 1) % n]);
+            PathSegment::from_bulge(a, b, bulge)
+        })
+        .collect()
+}
+
+/// Vertices and bulges of a connected path, the inverse of
+/// [`path_from_bulges`]. Closed paths omit the repeated end vertex.
+pub fn path_to_bulges(path: &[PathSegment], closed: bool) -> Vec<(Point, f64)> {
+    let mut vertices: Vec<(Point, f64)> =
+        path.iter().map(|s| (s.start_point(), s.bulge())).collect();
+    if let (false, Some(last)) = (closed, path.last()) {
+        vertices.push((last.end_point(), 0.0));
+    }
+    vertices
+}
+
+/// Arc around `center` joining two offset segments that no longer meet.
+fn round_join(center: Point, from: Point, to: Point) -> PathSegment {
+    let start = angle_of(center, from);
+    PathSegment::Arc {
+        center,
+        radius: distance(center, from),
+        start,
+        sweep: short_delta(start, angle_of(center, to)),
+    }
+}
+
+/// Offsets a connected path by `dist` to the left of its direction.
+/// Neighbouring segments are extended or trimmed to meet; where they no
+/// longer meet, a round join is added around the original vertex. Arcs that
+/// would collapse are dropped.
+pub fn offset_path(path: &[PathSegment], dist: f64, closed: bool) -> Vec<PathSegment> {
+    let pieces: Vec<(PathSegment, Point)> = path
+        .iter()
+        .filter_map(|s| Some((s.offset(dist)?, s.end_point())))
+        .collect();
+    let n = pieces.len();
+    let mut segs: Vec<PathSegment> = pieces.iter().map(|p| p.0).collect();
+    let mut joins: Vec<Option<PathSegment>> = vec![None; n];
+    let count = if closed { n } else { n.saturating_sub(1) };
+    for i in 0..count {
+        let j = (i + 1) % n;
+        let vertex = pieces[i].1;
+        if distance(segs[i].end_point(), segs[j].start_point()) < EPS {
+            continue;
+        }
+        let joint = carrier_intersections(&segs[i], &segs[j])
+            .into_iter()
+            .min_by(|p, q| distance(*p, vertex).total_cmp(&distance(*q, vertex)));
+        match joint {
+            Some(p) => {
+                segs[i] = segs[i].with_end(p);
+                segs[j] = segs[j].with_start(p);
+            }
+            None => {
+                joins[i] = Some(round_join(
+                    vertex,
+                    segs[i].end_point(),
+                    segs[j].start_point(),
+                ))
+            }
+        }
+    }
+    segs.into_iter()
+        .zip(joins)
+        .flat_map(|(s, join)| std::iter::once(s).chain(join))
+        .collect()
+}
+
+/// Parameters in `0..1` where `segment` crosses any boundary, sorted.
+fn cut_params(segment: &PathSegment, boundaries: &[PathSegment]) -> Vec<f64> {
+    let mut cuts: Vec<f64> = boundaries
+        .iter()
+        .flat_map(|b| segment_intersections(segment, b))
+        .map(|p| segment.param_of(p))
+        .filter(|t| *t > EPS && *t < 1.0 - EPS)
+        .collect();
+    cuts.sort_by(f64::total_cmp);
+    cuts.dedup_by(|a, b| (*a - *b).abs() < EPS);
+    cuts
+}
+
+/// Removes the piece of `segment` between the boundary crossings on either
+/// side of `pick`. Returns the remaining pieces, or the segment unchanged
+/// when no boundary crosses it.
+pub fn trim(segment: &PathSegment, boundaries: &[PathSegment], pick: Point) -> Vec<PathSegment> {
+    let cuts = cut_params(segment, boundaries);
+    if cuts.is_empty() {
+        return vec![*segment];
+    }
+    let t = segment
+        .param_of(segment.nearest_point(pick))
+        .clamp(0.0, 1.0);
+    let before = cuts.iter().rev().find(|c| **c < t).copied();
+    let after = cuts.iter().find(|c| **c > t).copied();
+    before
+        .map(|c| segment.sub(0.0, c))
+        .into_iter()
+        .chain(after.map(|c| segment.sub(c, 1.0)))
+        .collect()
+}
+
+/// Extends the end of `segment` nearer to `pick` to the closest boundary
+/// it reaches. Returns `None` when no boundary lies in the way.
+pub fn extend(
+    segment: &PathSegment,
+    boundaries: &[PathSegment],
+    pick: Point,
+) -> Option<PathSegment> {
+    let at_start = distance(segment.start_point(), pick) < distance(segment.end_point(), pick);
+    let seg = if at_start {
+        segment.reversed()
+    } else {
+        *segment
+    };
+    let target = boundaries
+        .iter()
+        .flat_map(|b| {
+            carrier_intersections(&seg, b)
+                .into_iter()
+                .filter(|p| b.contains(*p))
+        })
+        .map(|p| (seg.param_of(p), p))
+        .filter(|(t, _)| *t > 1.0 + EPS)
+        .min_by(|a, b| a.0.total_cmp(&b.0))?;
+    let extended = seg.with_end(target.1);
+    Some(if at_start {
+        extended.reversed()
+    } else {
+        extended
+    })
+}
+
+/// Index of the segment of `path` closest to `p`.
+fn nearest_segment(path: &[PathSegment], p: Point) -> Option<usize> {
+    (0..path.len()).min_by(|a, b| {
+        let da = distance(path[*a].nearest_point(p), p);
+        let db = distance(path[*b].nearest_point(p), p);
+        da.total_cmp(&db)
+    })
+}
+
+/// Joins the pieces before and after a cut; a closed path becomes a
+/// single open path starting after the cut.
+fn reopen(
+    before: Vec<PathSegment>,
+    after: Vec<PathSegment>,
+    closed: bool,
+) -> Vec<Vec<PathSegment>> {
+    let paths = if closed {
+        vec![[after, before].concat()]
+    } else {
+        vec![before, after]
+    };
+    paths.into_iter().filter(|p| !p.is_empty()).collect()
+}
+
+/// Pieces of `path` between positions `from` and `to`, where position
+/// `k + t` is parameter `t` along segment `k`.
+fn span(path: &[PathSegment], from: f64, to: f64) -> Vec<PathSegment> {
+    let mut pieces = Vec::new();
+    for (k, seg) in path.iter().enumerate() {
+        let (t0, t1) = ((from - k as f64).max(0.0), (to - k as f64).min(1.0));
+        if t1 - t0 > EPS {
+            pieces.push(if t0 == 0.0 && t1 == 1.0 {
+                *seg
+            } else {
+                seg.sub(t0, t1)
+            });
+        }
+    }
+    pieces
+}
+
+/// Trims `path` between the boundary crossings on either side of `pick`,
+/// walking along the path from the segment nearest the pick as CAD TRIM
+/// does. The ends of an open path stand in for a missing crossing.
+/// Returns the remaining paths, or `None` when no boundary crosses the
+/// path or a closed path only once.
+pub fn trim_path(
+    path: &[PathSegment],
+    closed: bool,
+    boundaries: &[PathSegment],
+    pick: Point,
+) -> Option<Vec<Vec<PathSegment>>> {
+    let i = nearest_segment(path, pick)?;
+    let n = path.len() as f64;
+    let t = path[i].param_of(path[i].nearest_point(pick));
+    let at = i as f64 + t.clamp(0.0, 1.0);
+    let mut cuts: Vec<f64> = path
+        .iter()
+        .enumerate()
+        .flat_map(|(k, seg)| {
+            boundaries
+                .iter()
+                .flat_map(move |b| segment_intersections(seg, b))
+                .map(move |p| k as f64 + seg.param_of(p).clamp(0.0, 1.0))
+        })
+        .map(|s| if closed && s >= n - EPS { 0.0 } else { s })
+        .filter(|s| (s - at).abs() > EPS)
+        .collect();
+    cuts.sort_by(f64::total_cmp);
+    cuts.dedup_by(|a, b| (*a - *b).abs() < EPS);
+    let before = cuts.iter().rev().find(|s| **s < at).copied();
+    let after = cuts.iter().find(|s| **s > at).copied();
+    if !closed {
+        if before.is_none() && after.is_none() {
+            return None;
+        }
+        let before = span(path, 0.0, before.unwrap_or(0.0));
+        let after = span(path, after.unwrap_or(n), n);
+        return Some(reopen(before, after, false));
+    }
+    match (before, after) {
+        (Some(b), Some(a)) => Some(reopen(span(path, 0.0, b), span(path, a, n), true)),
+        // the cut wraps past the start of the path
+        _ if cuts.len() >= 2 => {
+            let (a, b) = (cuts[0], cuts[cuts.len() - 1]);
+            Some(vec![span(path, a, b)])
+        }
+        _ => None,
+    }
+}
+
+/// Extends the end of an open `path` nearer to `pick` with [`extend`].
+pub fn extend_path(
+    path: &[PathSegment],
+    boundaries: &[PathSegment],
+    pick: Point,
+) -> Option<Vec<PathSegment>> {
+    let last = path.len().checked_sub(1)?;
+    let (i, end) = if distance(path[0].start_point(), pick) < distance(path[last].end_point(), pick)
+    {
+        (0, path[0].start_point())
+    } else {
+        (last, path[last].end_point())
+    };
+    let mut extended = path.to_vec();
+    extended[i] = extend(&path[i], boundaries, end)?;
+    Some(extended)
+}
+
+/// Two lines trimmed or extended to meet, with the fillet arc or chamfer
+/// line between them.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Corner {
+    pub first: Line,
+    pub second: Line,
+    /// Runs from the end of `first` at the corner to `second`; `None` for a
+    /// sharp corner.
+    pub connector: Option<PathSegment>,
+}
+
+/// Corner point of two lines and the unit directions from it towards the
+/// far end of each line.
+fn corner_frame(first: &Line, second: &Line) -> Option<(Point, Point, Point)> {
+    let (t, _) = line_line_params(first, second)?;
+    let pi = line_point_at(first, t);
+    let far = |l: &Line| {
+        if distance(l.start, pi) > distance(l.end, pi) {
+            l.start
+        } else {
+            l.end
+        }
+    };
+    Some((pi, far(first), far(second)))
+}
+
+/// Replaces the end of `line` nearest the corner with `p`.
+fn retarget(line: &Line, far: Point, p: Point) -> Line {
+    if line.start == far {
+        Line::new(far, p)
+    } else {
+        Line::new(p, far)
+    }
+}
+
+/// Fillets two lines with an arc of `radius`, keeping the end of each line
+/// away from their intersection. A zero radius joins them at a sharp
+/// corner. Returns `None` for parallel lines or a radius too large for
+/// the lines.
+pub fn fillet(first: &Line, second: &Line, radius: f64) -> Option<Corner> {
+    let (pi, far1, far2) = corner_frame(first, second)?;
+    let (u1, u2) = (unit(pi, far1)?, unit(pi, far2)?);
+    let theta = (u1.0 * u2.0 + u1.1 * u2.1).clamp(-1.0, 1.0).acos();
+    if !(EPS..=PI - EPS).contains(&theta) {
+        return None;
+    }
+    if radius <= 0.0 {
+        return Some(Corner {
+            first: retarget(first, far1, pi),
+            second: retarget(second, far2, pi),
+            connector: None,
+        });
+    }
+    let tangent = radius / (theta / 2.0).tan();
+    if tangent > distance(pi, far1) || tangent > distance(pi, far2) {
+        return None;
+    }
+    let tp1 = Point::new(pi.x + u1.0 * tangent, pi.y + u1.1 * tangent);
+    let tp2 = Point::new(pi.x + u2.0 * tangent, pi.y + u2.1 * tangent);
+    let bis = unit(pi, Point::new(pi.x + u1.0 + u2.0, pi.y + u1.1 + u2.1))?;
+    let h = radius / (theta / 2.0).sin();
+    let center = Point::new(pi.x + bis.0 * h, pi.y + bis.1 * h);
+    let start = angle_of(center, tp1);
+    Some(Corner {
+        first: retarget(first, far1, tp1),
+        second: retarget(second, far2, tp2),
+        connector: Some(PathSegment::Arc {
+            center,
+            radius,
+            start,
+            sweep: short_delta(start, angle_of(center, tp2)),
+        }),
+    })
+}
+
+/// Chamfers two lines `d1` along the first and `d2` along the second from
+/// their intersection.
+pub fn chamfer(first: &Line, second: &Line, d1: f64, d2: f64) -> Option<Corner> {
+    let (pi, far1, far2) = corner_frame(first, second)?;
+    let (u1, u2) = (unit(pi, far1)?, unit(pi, far2)?);
+    if d1 > distance(pi, far1) || d2 > distance(pi, far2) {
+        return None;
+    }
+    let c1 = Point::new(pi.x + u1.0 * d1, pi.y + u1.1 * d1);
+    let c2 = Point::new(pi.x + u2.0 * d2, pi.y + u2.1 * d2);
+    Some(Corner {
+        first: retarget(first, far1, c1),
+        second: retarget(second, far2, c2),
+        connector: (distance(c1, c2) > EPS).then_some(PathSegment::Line(Line::new(c1, c2))),
+    })
+}
+
+/// Splits `segment` at the point nearest `p`. Returns `None` when that is
+/// one of its ends.
+pub fn break_at(segment: &PathSegment, p: Point) -> Option<(PathSegment, PathSegment)> {
+    let t = segment.param_of(segment.nearest_point(p));
+    (t > EPS && t < 1.0 - EPS).then(|| (segment.sub(0.0, t), segment.sub(t, 1.0)))
+}
+
+/// Splits a path at the point nearest `p`. An open path gives the pieces
+/// before and after; a closed path opens into one path starting there.
+pub fn break_path(path: &[PathSegment], closed: bool, p: Point) -> Vec<Vec<PathSegment>> {
+    let Some(i) = nearest_segment(path, p) else {
+        return Vec::new();
+    };
+    let mut before = path[..i].to_vec();
+    let mut after = Vec::new();
+    match break_at(&path[i], p) {
+        Some((a, b)) => {
+            before.push(a);
+            after.push(b);
+        }
+        None if distance(path[i].start_point(), p) < distance(path[i].end_point(), p) => {
+            after.push(path[i])
+        }
+        None => before.push(path[i]),
+    }
+    after.extend_from_slice(&path[i + 1..]);
+    reopen(before, after, closed)
+}
+
+/// Order in which pieces with the given start and end points chain
+/// together when their ends meet within `tol`. Each chain lists piece
+/// indices from its start, with whether the piece runs reversed.
+pub fn join_order(ends: &[(Point, Point)], tol: f64) -> Vec<Vec<(usize, bool)>> {
+    let near = |a: Point, b: Point| distance(a, b) <= tol;
+    let oriented = |(i, reversed): (usize, bool)| {
+        let (s, e) = ends[i];
+        if reversed {
+            (e, s)
+        } else {
+            (s, e)
+        }
+    };
+    let mut remaining: Vec<usize> = (0..ends.len()).collect();
+    let mut chains = Vec::new();
+    while !remaining.is_empty() {
+        let mut chain = VecDeque::from([(remaining.remove(0), false)]);
+        loop {
+            let head = oriented(chain[0]).0;
+            let tail = oriented(chain[chain.len() - 1]).1;
+            let Some(k) = remaining.iter().position(|&i| {
+                let (s, e) = ends[i];
+                near(tail, s) || near(tail, e) || near(head, e) || near(head, s)
+            }) else {
+                break;
+            };
+            let i = remaining.remove(k);
+            let (s, e) = ends[i];
+            if near(tail, s) || near(tail, e) {
+                chain.push_back((i, !near(tail, s)));
+            } else {
+                chain.push_front((i, !near(head, e)));
+            }
+        }
+        chains.push(chain.into());
+    }
+    chains
+}
+
+/// Chains paths whose ends meet within `tol` into longer paths, reversing
+/// pieces as needed.
+pub fn join(paths: &[Vec<PathSegment>], tol: f64) -> Vec<Vec<PathSegment>> {
+    let paths: Vec<&Vec<PathSegment>> = paths.iter().filter(|p| !p.is_empty()).collect();
+    let ends: Vec<(Point, Point)> = paths
+        .iter()
+        .map(|p| (p[0].start_point(), p[p.len() - 1].end_point()))
+        .collect();
+    join_order(&ends, tol)
+        .into_iter()
+        .map(|order| {
+            let mut chain: Vec<PathSegment> = Vec::new();
+            for (i, reversed) in order {
+                let mut piece: Vec<PathSegment> = if reversed {
+                    paths[i].iter().rev().map(PathSegment::reversed).collect()
+                } else {
+                    paths[i].clone()
+                };
+                if let Some(last) = chain.last() {
+                    piece[0] = piece[0].with_start(last.end_point());
+                }
+                chain.extend(piece);
+            }
+            chain
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(x0: f64, y0: f64, x1: f64, y1: f64) -> PathSegment {
+        PathSegment::Line(Line::new(Point::new(x0, y0), Point::new(x1, y1)))
+    }
+
+    fn close(a: Point, b: Point) -> bool {
+        distance(a, b) < 1e-9
+    }
+
+    #[test]
+    fn offset_path_with_arc() {
+        // L-shape with a quarter-circle bend turning left.
+        let path = path_from_bulges(
+            &[
+                (Point::new(0.0, 0.0), 0.0),
+                (Point::new(10.0, 0.0), (PI / 8.0).tan()),
+                (Point::new(15.0, 5.0), 0.0),
+                (Point::new(15.0, 10.0), 0.0),
+            ],
+            false,
+        );
+        let off = offset_path(&path, 1.0, false);
+        assert_eq!(off.len(), 3);
+        assert!(close(off[0].start_point(), Point::new(0.0, 1.0)));
+        assert!(close(off[0].end_point(), Point::new(10.0, 1.0)));
+        let PathSegment::Arc { radius, .. } = off[1] else {
+            panic!("expected arc");
+        };
+        assert!((radius - 4.0).abs() < 1e-9);
+        assert!(close(off[2].end_point(), Point::new(14.0, 10.0)));
+
+        // Square corner offset outward meets at a mitre.
+        let square = vec![line(0.0, 0.0, 10.0, 0.0), line(10.0, 0.0, 10.0, 10.0)];
+        let off = offset_path(&square, -1.0, false);
+        assert!(close(off[0].end_point(), Point::new(11.0, -1.0)));
+        assert!(close(off[1].start_point(), Point::new(11.0, -1.0)));
+
+        let back = path_to_bulges(&path, false);
+        assert_eq!(back.len(), 4);
+        assert!((back[1].1 - (PI / 8.0).tan()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn trim_extend_and_break() {
+        let seg = line(0.0, 0.0, 10.0, 0.0);
+        let bounds = [line(3.0, -1.0, 3.0, 1.0), line(6.0, -1.0, 6.0, 1.0)];
+        let pieces = trim(&seg, &bounds, Point::new(4.0, 0.2));
+        assert_eq!(pieces.len(), 2);
+        assert!(close(pieces[0].end_point(), Point::new(3.0, 0.0)));
+        assert!(close(pieces[1].start_point(), Point::new(6.0, 0.0)));
+        let pieces = trim(&seg, &bounds, Point::new(9.0, 0.0));
+        assert_eq!(pieces.len(), 1);
+        assert!(close(pieces[0].end_point(), Point::new(6.0, 0.0)));
+
+        let short = line(0.0, 0.0, 2.0, 0.0);
+        let ext = extend(&short, &bounds, Point::new(1.9, 0.0)).unwrap();
+        assert!(close(ext.end_point(), Point::new(3.0, 0.0)));
+        assert!(extend(&short, &bounds, Point::new(0.1, 0.0)).is_none());
+
+        let arc = PathSegment::from_arc(&Arc::new(Point::new(0.0, 0.0), 5.0, 0.0, PI / 4.0));
+        let ext = extend(&arc, &[line(-10.0, 0.0, 10.0, 10.0)], Point::new(3.6, 3.5));
+        assert!(ext.unwrap().end_point().y > 3.6);
+
+        let (a, b) = break_at(&seg, Point::new(4.0, 1.0)).unwrap();
+        assert!(
+            close(a.end_point(), Point::new(4.0, 0.0)) && close(b.start_point(), a.end_point())
+        );
+        assert!(break_at(&seg, Point::new(-1.0, 0.0)).is_none());
+
+        let square = path_from_bulges(
+            &[
+                (Point::new(0.0, 0.0), 0.0),
+                (Point::new(10.0, 0.0), 0.0),
+                (Point::new(10.0, 10.0), 0.0),
+                (Point::new(0.0, 10.0), 0.0),
+            ],
+            true,
+        );
+        let opened = break_path(&square, true, Point::new(4.0, 0.0));
+        assert_eq!(opened.len(), 1);
+        assert_eq!(opened[0].len(), 5);
+        assert!(close(opened[0][0].start_point(), Point::new(4.0, 0.0)));
+        let trimmed = trim_path(&square, true, &bounds, Point::new(4.0, 0.5)).unwrap();
+        assert_eq!(trimmed.len(), 1);
+        assert!(close(trimmed[0][0].start_point(), Point::new(6.0, 0.0)));
+        assert!(close(trimmed[0][4].end_point(), Point::new(3.0, 0.0)));
+        // the right side has no crossing, so the trim runs on round the
+        // square to the crossings on the bottom
+        let trimmed = trim_path(&square, true, &bounds, Point::new(10.0, 5.0)).unwrap();
+        assert_eq!(trimmed, vec![vec![square[0].sub(0.3, 0.6)]]);
+        let side = [line(-1.0, 5.0, 1.0, 5.0)];
+        assert!(trim_path(&square, true, &side, Point::new(10.0, 5.0)).is_none());
+
+        // the picked segment of an open path isn't crossed; the cut walks
+        // back to the first segment and on to the end of the path
+        let open = path_from_bulges(
+            &[
+                (Point::new(0.0, 0.0), 0.0),
+                (Point::new(5.0, 0.0), 0.0),
+                (Point::new(5.0, 5.0), 0.0),
+            ],
+            false,
+        );
+        let trimmed = trim_path(&open, false, &bounds, Point::new(5.0, 4.0)).unwrap();
+        assert_eq!(trimmed.len(), 1);
+        assert_eq!(trimmed[0], vec![open[0].sub(0.0, 0.6)]);
+        assert!(trim_path(&open, false, &side, Point::new(5.0, 4.0)).is_none());
+
+        let open = vec![line(0.0, -5.0, 0.0, 0.0), short];
+        let extended = extend_path(&open, &bounds, Point::new(1.5, 0.0)).unwrap();
+        assert!(close(extended[1].end_point(), Point::new(3.0, 0.0)));
+        assert!(extend_path(&open, &bounds, Point::new(0.0, -4.0)).is_none());
+    }
+
+    #[test]
+    fn fillet_chamfer_and_join() {
+        let a = Line::new(Point::new(0.0, 0.0), Point::new(10.0, 0.0));
+        let b = Line::new(Point::new(10.0, 0.0), Point::new(10.0, 10.0));
+        let corner = fillet(&a, &b, 2.0).unwrap();
+        assert!(close(corner.first.end, Point::new(8.0, 0.0)));
+        assert!(close(corner.second.start, Point::new(10.0, 2.0)));
+        let arc = corner.connector.unwrap();
+        assert!(close(arc.start_point(), Point::new(8.0, 0.0)));
+        assert!(close(arc.end_point(), Point::new(10.0, 2.0)));
+        assert!(fillet(&a, &b, 20.0).is_none());
+
+        let corner = chamfer(&a, &b, 1.0, 2.0).unwrap();
+        assert!(close(corner.first.end, Point::new(9.0, 0.0)));
+        assert!(close(corner.second.start, Point::new(10.0, 2.0)));
+
+        let pieces = vec![
+            vec![PathSegment::Line(corner.second)],
+            vec![PathSegment::Line(corner.first)],
+            vec![corner.connector.unwrap().reversed()],
+            vec![line(50.0, 50.0, 60.0, 50.0)],
+        ];
+        let joined = join(&pieces, 1e-6);
+        assert_eq!(joined.len(), 2);
+        assert_eq!(joined[0].len(), 3);
+        let ends = (joined[0][0].start_point(), joined[0][2].end_point());
+        assert!(
+            close(ends.0, Point::new(0.0, 0.0)) && close(ends.1, Point::new(10.0, 10.0))
+                || close(ends.1, Point::new(0.0, 0.0)) && close(ends.0, Point::new(10.0, 10.0))
+        );
+    }
+}
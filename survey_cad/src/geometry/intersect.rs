@@ -0,0 +1,125 @@
+//! Intersections between line segments and circular arcs.
+
+use super::{Arc, Line, Point};
+
+/// Parameter tolerance used to accept points at segment ends.
+const PARAM_EPS: f64 = 1e-9;
+
+/// Parameters along `a` and `b` where their infinite lines cross, or `None`
+/// when they are parallel.
+pub fn line_line_params(a: &Line, b: &Line) -> Option<(f64, f64)> {
+    let r = (a.end.x - a.start.x, a.end.y - a.start.y);
+    let s = (b.end.x - b.start.x, b.end.y - b.start.y);
+    let denom = r.0 * s.1 - r.1 * s.0;
+    if denom.abs() < f64::EPSILON {
+        return None;
+    }
+    let q = (b.start.x - a.start.x, b.start.y - a.start.y);
+    Some((
+        (q.0 * s.1 - q.1 * s.0) / denom,
+        (q.0 * r.1 - q.1 * r.0) / denom,
+    ))
+}
+
+/// Point at parameter `t` along `line`.
+pub fn line_point_at(line: &Line, t: f64) -> Point {
+    Point::new(
+        line.start.x + (line.end.x - line.start.x) * t,
+        line.start.y + (line.end.y - line.start.y) * t,
+    )
+}
+
+fn on_segment(t: f64) -> bool {
+    (-PARAM_EPS..=1.0 + PARAM_EPS).contains(&t)
+}
+
+/// Intersection of two line segments.
+pub fn segment_intersection(a: &Line, b: &Line) -> Option<Point> {
+    let (t, u) = line_line_params(a, b)?;
+    (on_segment(t) && on_segment(u)).then(|| line_point_at(a, t))
+}
+
+/// Points where the infinite line through `line` meets the circle, with
+/// their parameters along `line`.
+pub fn line_circle_intersections(line: &Line, center: Point, radius: f64) -> Vec<(f64, Point)> {
+    let d = (line.end.x - line.start.x, line.end.y - line.start.y);
+    let f = (line.start.x - center.x, line.start.y - center.y);
+    let a = d.0 * d.0 + d.1 * d.1;
+    if a < f64::EPSILON {
+        return Vec::new();
+    }
+    let b = 2.0 * (f.0 * d.0 + f.1 * d.1);
+    let c = f.0 * f.0 + f.1 * f.1 - radius * radius;
+    let disc = b * b - 4.0 * a * c;
+    if disc < 0.0 {
+        return Vec::new();
+    }
+    let root = disc.sqrt();
+    let mut ts = vec![(-b - root) / (2.0 * a)];
+    if root > f64::EPSILON {
+        ts.push((-b + root) / (2.0 * a));
+    }
+    ts.into_iter()
+        .map(|t| (t, line_point_at(line, t)))
+        .collect()
+}
+
+/// Points where two full circles meet.
+pub fn circle_intersections(c0: Point, r0: f64, c1: Point, r1: f64) -> Vec<Point> {
+    let (dx, dy) = (c1.x - c0.x, c1.y - c0.y);
+    let d = dx.hypot(dy);
+    if d < f64::EPSILON || d > r0 + r1 + PARAM_EPS || d < (r0 - r1).abs() - PARAM_EPS {
+        return Vec::new();
+    }
+    let a = (r0 * r0 - r1 * r1 + d * d) / (2.0 * d);
+    let h = (r0 * r0 - a * a).max(0.0).sqrt();
+    let (mx, my) = (c0.x + a * dx / d, c0.y + a * dy / d);
+    let mut pts = vec![Point::new(mx - h * dy / d, my + h * dx / d)];
+    if h > f64::EPSILON {
+        pts.push(Point::new(mx + h * dy / d, my - h * dx / d));
+    }
+    pts
+}
+
+/// Intersections of a line segment with an arc.
+pub fn segment_arc_intersections(line: &Line, arc: &Arc) -> Vec<Point> {
+    line_circle_intersections(line, arc.center, arc.radius)
+        .into_iter()
+        .filter(|(t, p)| on_segment(*t) && arc.contains_point(*p))
+        .map(|(_, p)| p)
+        .collect()
+}
+
+/// Intersections of two arcs.
+pub fn arc_arc_intersections(a: &Arc, b: &Arc) -> Vec<Point> {
+    circle_intersections(a.center, a.radius, b.center, b.radius)
+        .into_iter()
+        .filter(|p| a.contains_point(*p) && b.contains_point(*p))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    #[test]
+    fn segments_and_arcs() {
+        let a = Line::new(Point::new(0.0, 0.0), Point::new(2.0, 0.0));
+        let b = Line::new(Point::new(1.0, -1.0), Point::new(1.0, 1.0));
+        let p = segment_intersection(&a, &b).unwrap();
+        assert!((p.x - 1.0).abs() < 1e-12 && p.y.abs() < 1e-12);
+        let c = Line::new(Point::new(3.0, -1.0), Point::new(3.0, 1.0));
+        assert!(segment_intersection(&a, &c).is_none());
+
+        let upper = Arc::new(Point::new(1.0, 0.0), 1.0, 0.0, PI);
+        let pts = segment_arc_intersections(&b, &upper);
+        assert_eq!(pts.len(), 1);
+        assert!((pts[0].y - 1.0).abs() < 1e-12);
+
+        let other = Arc::new(Point::new(2.0, 0.0), 1.0, PI / 2.0, 3.0 * PI / 2.0);
+        let pts = arc_arc_intersections(&upper, &other);
+        assert_eq!(pts.len(), 1);
+        assert!((pts[0].x - 1.5).abs() < 1e-12 && pts[0].y > 0.0);
+    }
+}
@@ -5,12 +5,25 @@ pub mod line3;
 pub mod point;
 pub mod point3;
 pub mod dimension;
+pub mod intersect;
+pub mod edit;
+pub mod polyline3;
 
 pub use line::{Line, LineAnnotation, LineType, LineStyle};
 pub use line3::Line3;
+pub use polyline3::Polyline3;
 pub use point::{NamedPoint, Point, PointSymbol};
 pub use point3::Point3;
 pub use dimension::{LinearDimension, LinearDimension3};
+pub use intersect::{
+    arc_arc_intersections, circle_intersections, line_circle_intersections, line_line_params,
+    segment_arc_intersections, segment_intersection,
+};
+pub use edit::{
+    break_at, break_path, chamfer, extend, extend_path, fillet, join, join_order, offset_path,
+    path_from_bulges, path_to_bulges, segment_intersections, trim, trim_path, Corner,
+    PathSegment,
+};
 
 /// Calculates the Euclidean distance between two points.
 pub fn distance(a: Point, b: Point) -> f64 {
@@ -177,6 +190,72 @@ impl Arc {
         self.point_at((self.start_angle + self.end_angle) / 2.0)
     }
 
+    /// Counter-clockwise sweep from the start to the end angle, in
+    /// `0..=2π`.
+    pub fn sweep(&self) -> f64 {
+        let tau = 2.0 * std::f64::consts::PI;
+        let delta = self.end_angle - self.start_angle;
+        if delta.abs() >= tau - 1e-12 {
+            tau
+        } else {
+            delta.rem_euclid(tau)
+        }
+    }
+
+    /// DXF bulge of the arc traced from the start to the end angle,
+    /// negative when the end angle is the smaller.
+    pub fn bulge(&self) -> f64 {
+        ((self.end_angle - self.start_angle) / 4.0).tan()
+    }
+
+    /// Returns `true` if the arc sweeps a full circle.
+    pub fn is_full_circle(&self) -> bool {
+        self.sweep() >= 2.0 * std::f64::consts::PI - 1e-12
+    }
+
+    /// Returns `true` if `angle` lies within the counter-clockwise sweep.
+    pub fn contains_angle(&self, angle: f64) -> bool {
+        let tau = 2.0 * std::f64::consts::PI;
+        let d = (angle - self.start_angle).rem_euclid(tau);
+        d <= self.sweep() + 1e-9 || d >= tau - 1e-9
+    }
+
+    /// Returns `true` if the direction of `p` from the centre lies within
+    /// the sweep. The distance from the centre is not checked.
+    pub fn contains_point(&self, p: Point) -> bool {
+        self.contains_angle((p.y - self.center.y).atan2(p.x - self.center.x))
+    }
+
+    /// Points at 0, 90, 180 and 270 degrees that lie on the arc.
+    pub fn quadrant_points(&self) -> Vec<Point> {
+        (0..4)
+            .map(|i| i as f64 * std::f64::consts::FRAC_PI_2)
+            .filter(|a| self.contains_angle(*a))
+            .map(|a| self.point_at(a))
+            .collect()
+    }
+
+    /// Arc between `start` and `end` with the given DXF bulge, the tangent
+    /// of a quarter of the included angle. Positive bulges turn
+    /// counter-clockwise. Returns `None` for straight segments.
+    pub fn from_bulge(start: Point, end: Point, bulge: f64) -> Option<Self> {
+        let chord = distance(start, end);
+        if bulge.abs() < 1e-12 || chord < f64::EPSILON {
+            return None;
+        }
+        let theta = 4.0 * bulge.atan();
+        let radius = chord / (2.0 * (theta / 2.0).sin().abs());
+        let h = chord / 2.0 / (theta / 2.0).tan();
+        let (nx, ny) = (-(end.y - start.y) / chord, (end.x - start.x) / chord);
+        let center = Point::new(
+            (start.x + end.x) / 2.0 + nx * h,
+            (start.y + end.y) / 2.0 + ny * h,
+        );
+        let angle = |p: Point| (p.y - center.y).atan2(p.x - center.x);
+        let (a, b) = if bulge > 0.0 { (start, end) } else { (end, start) };
+        Some(Self::new(center, radius, angle(a), angle(b)))
+    }
+
     /// Returns the closest point on the arc to `p`.
     pub fn nearest_point(&self, p: Point) -> Point {
         let mut ang = (p.y - self.center.y).atan2(p.x - self.center.x);
@@ -1,7 +1,7 @@
 //! Basic 3D point type used throughout the crate.
 
 /// Representation of a 3D point.
-#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct Point3 {
     pub x: f64,
     pub y: f64,
@@ -0,0 +1,408 @@
+//! Polylines with elevations and circular arc segments.
+
+use super::edit::{join_order, offset_path, path_to_bulges, PathSegment};
+use super::{distance, Point, Point3, Polyline};
+
+/// Polyline with an elevation at each vertex and optional arc segments.
+///
+/// `bulges[i]` describes the segment leaving `vertices[i]` as in DXF, with
+/// missing entries straight. Elevations vary linearly along each segment.
+/// Closed polylines return to the first vertex.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Polyline3 {
+    pub vertices: Vec<Point3>,
+    #[serde(default)]
+    pub bulges: Vec<f64>,
+    #[serde(default)]
+    pub closed: bool,
+}
+
+fn plan(p: Point3) -> Point {
+    Point::new(p.x, p.y)
+}
+
+impl Polyline3 {
+    /// Creates an open polyline of straight segments.
+    pub fn new(vertices: Vec<Point3>) -> Self {
+        Self {
+            vertices,
+            bulges: Vec::new(),
+            closed: false,
+        }
+    }
+
+    /// Creates an open polyline whose segments may be arcs given by their
+    /// bulge.
+    pub fn with_bulges(vertices: Vec<Point3>, bulges: Vec<f64>) -> Self {
+        Self {
+            vertices,
+            bulges,
+            closed: false,
+        }
+    }
+
+    /// Returns the bulge of the segment leaving vertex `i`.
+    pub fn bulge(&self, i: usize) -> f64 {
+        self.bulges.get(i).copied().unwrap_or(0.0)
+    }
+
+    /// Returns `true` if any segment is a circular arc.
+    pub fn has_arcs(&self) -> bool {
+        self.bulges.iter().any(|b| *b != 0.0)
+    }
+
+    /// Number of segments including the closing one.
+    pub fn segment_count(&self) -> usize {
+        let n = self.vertices.len();
+        if self.closed && n >= 2 {
+            n
+        } else {
+            n.saturating_sub(1)
+        }
+    }
+
+    /// Plan geometry of segment `i`.
+    pub fn segment(&self, i: usize) -> PathSegment {
+        let n = self.vertices.len();
+        let (a, b) = (self.vertices[i], self.vertices[(i + 1) % n]);
+        PathSegment::from_bulge(plan(a), plan(b), self.bulge(i))
+    }
+
+    /// Plan geometry of every segment.
+    pub fn segments(&self) -> Vec<PathSegment> {
+        (0..self.segment_count()).map(|i| self.segment(i)).collect()
+    }
+
+    /// Elevations at the start and end of segment `i`.
+    fn segment_z(&self, i: usize) -> (f64, f64) {
+        let n = self.vertices.len();
+        (self.vertices[i].z, self.vertices[(i + 1) % n].z)
+    }
+
+    /// Plan length measured along arcs.
+    pub fn length(&self) -> f64 {
+        self.segments().iter().map(PathSegment::length).sum()
+    }
+
+    /// Slope length, following arcs in plan.
+    pub fn length_3d(&self) -> f64 {
+        self.segments()
+            .iter()
+            .enumerate()
+            .map(|(i, s)| {
+                let (z0, z1) = self.segment_z(i);
+                s.length().hypot(z1 - z0)
+            })
+            .sum()
+    }
+
+    /// Point at plan distance `station` from the start, or `None` beyond
+    /// the ends.
+    pub fn point_at(&self, station: f64) -> Option<Point3> {
+        if station < 0.0 {
+            return None;
+        }
+        let mut remaining = station;
+        for (i, s) in self.segments().iter().enumerate() {
+            let len = s.length();
+            if remaining <= len {
+                let t = if len > 0.0 { remaining / len } else { 0.0 };
+                return Some(self.lift(i, t, s.point_at(t)));
+            }
+            remaining -= len;
+        }
+        None
+    }
+
+    fn lift(&self, i: usize, t: f64, p: Point) -> Point3 {
+        let (z0, z1) = self.segment_z(i);
+        Point3::new(p.x, p.y, z0 + (z1 - z0) * t)
+    }
+
+    /// Segment, parameter and plan point closest to `p`.
+    fn nearest(&self, p: Point) -> Option<(usize, f64, Point)> {
+        self.segments()
+            .iter()
+            .enumerate()
+            .map(|(i, s)| {
+                let q = s.nearest_point(p);
+                (i, s.param_of(q).clamp(0.0, 1.0), q)
+            })
+            .min_by(|a, b| distance(a.2, p).total_cmp(&distance(b.2, p)))
+    }
+
+    /// Point on the polyline closest to `p` in plan, with its elevation.
+    pub fn nearest_point(&self, p: Point) -> Option<Point3> {
+        self.nearest(p).map(|(i, t, q)| self.lift(i, t, q))
+    }
+
+    /// Plan distance from the start to the point closest to `p`.
+    pub fn station_at(&self, p: Point) -> Option<f64> {
+        let (i, t, _) = self.nearest(p)?;
+        let segments = self.segments();
+        Some(segments[..i].iter().map(PathSegment::length).sum::<f64>() + segments[i].length() * t)
+    }
+
+    /// Elevation at the point closest to `p` in plan. Beyond the ends of an
+    /// open polyline the grade of the end segment carries on.
+    pub fn elevation_at(&self, p: Point) -> Option<f64> {
+        let (i, t, _) = self.nearest(p)?;
+        let u = self.segment(i).param_of(p);
+        let last = self.segment_count() - 1;
+        let t = match self.closed {
+            false if (i == 0 && u < 0.0) || (i == last && u > 1.0) => u,
+            _ => t,
+        };
+        let (z0, z1) = self.segment_z(i);
+        Some(z0 + (z1 - z0) * t)
+    }
+
+    /// Polyline along a plan `path` with elevations from
+    /// [`Polyline3::elevation_at`], for paths edited from this one.
+    /// Vertices kept from this polyline keep their own elevation.
+    pub fn lift_path(&self, path: &[PathSegment], closed: bool) -> Self {
+        let (vertices, bulges) = path_to_bulges(path, closed)
+            .into_iter()
+            .map(|(p, bulge)| {
+                let z = self
+                    .vertices
+                    .iter()
+                    .find(|v| distance(plan(**v), p) < 1e-9)
+                    .map(|v| v.z)
+                    .or_else(|| self.elevation_at(p))
+                    .unwrap_or(0.0);
+                (Point3::new(p.x, p.y, z), bulge)
+            })
+            .unzip();
+        Self {
+            vertices,
+            bulges,
+            closed,
+        }
+    }
+
+    /// Copy moved `dist` to the left of the direction of travel, with
+    /// elevations taken from the closest point of this polyline. Corners
+    /// are mitred or rounded as in [`offset_path`].
+    pub fn offset(&self, dist: f64) -> Self {
+        self.lift_path(
+            &offset_path(&self.segments(), dist, self.closed),
+            self.closed,
+        )
+    }
+
+    /// The same polyline traced in the opposite direction.
+    pub fn reversed(&self) -> Self {
+        let n = self.vertices.len();
+        Self {
+            vertices: self.vertices.iter().rev().copied().collect(),
+            bulges: (0..n).map(|k| -self.bulge((2 * n - 2 - k) % n)).collect(),
+            closed: self.closed,
+        }
+    }
+
+    /// Chains open polylines whose ends meet within `tol` in plan, in the
+    /// order of [`join_order`], keeping the elevations of every piece.
+    /// Returns the pieces used by each chain with the joined polyline,
+    /// closed when its ends meet.
+    pub fn join(pieces: &[Polyline3], tol: f64) -> Vec<(Vec<usize>, Polyline3)> {
+        let used: Vec<usize> = (0..pieces.len())
+            .filter(|&i| !pieces[i].vertices.is_empty())
+            .collect();
+        let ends: Vec<(Point, Point)> = used
+            .iter()
+            .map(|&i| {
+                let v = &pieces[i].vertices;
+                (plan(v[0]), plan(v[v.len() - 1]))
+            })
+            .collect();
+        join_order(&ends, tol)
+            .into_iter()
+            .map(|order| {
+                let mut joined = Polyline3::default();
+                let mut indices = Vec::new();
+                for (k, reversed) in order {
+                    indices.push(used[k]);
+                    let piece = &pieces[used[k]];
+                    let piece = if reversed {
+                        piece.reversed()
+                    } else {
+                        piece.clone()
+                    };
+                    match joined.vertices.len() {
+                        0 => joined.bulges = piece.bulges,
+                        n => {
+                            joined.bulges.resize(n - 1, 0.0);
+                            let m = piece.vertices.len();
+                            joined.bulges.extend((0..m).map(|j| piece.bulge(j)));
+                        }
+                    }
+                    // the joining vertex is taken from the chain so far
+                    let skip = usize::from(!joined.vertices.is_empty());
+                    joined.vertices.extend_from_slice(&piece.vertices[skip..]);
+                }
+                let n = joined.vertices.len();
+                if n > 2 && distance(plan(joined.vertices[0]), plan(joined.vertices[n - 1])) <= tol
+                {
+                    joined.vertices.pop();
+                    joined.bulges.truncate(n - 1);
+                    joined.closed = true;
+                }
+                (indices, joined)
+            })
+            .collect()
+    }
+
+    /// Plan area enclosed by the polyline, adding or removing the circular
+    /// segment between each arc and its chord. Open polylines are closed by
+    /// a straight segment.
+    pub fn area(&self) -> f64 {
+        let n = self.vertices.len();
+        if n < 3 && !self.has_arcs() {
+            return 0.0;
+        }
+        let mut twice = 0.0;
+        for i in 0..n {
+            let (a, b) = (self.vertices[i], self.vertices[(i + 1) % n]);
+            twice += a.x * b.y - b.x * a.y;
+        }
+        let mut area = twice / 2.0;
+        for s in self.segments() {
+            if let PathSegment::Arc { radius, sweep, .. } = s {
+                let delta = sweep.abs();
+                area += sweep.signum() * radius * radius * (delta - delta.sin()) / 2.0;
+            }
+        }
+        area.abs()
+    }
+
+    /// Vertices with arcs replaced by chords each subtending at most
+    /// `max_angle` radians. Closed polylines don't repeat the first vertex.
+    pub fn densify(&self, max_angle: f64) -> Vec<Point3> {
+        let mut pts: Vec<Point3> = self.vertices.first().copied().into_iter().collect();
+        for (i, s) in self.segments().iter().enumerate() {
+            let steps = match s {
+                PathSegment::Arc { sweep, .. } if max_angle > 0.0 => {
+                    ((sweep.abs() / max_angle).ceil() as usize).max(1)
+                }
+                _ => 1,
+            };
+            for k in 1..=steps {
+                let t = k as f64 / steps as f64;
+                pts.push(self.lift(i, t, s.point_at(t)));
+            }
+        }
+        if self.closed && pts.len() > 1 {
+            pts.pop();
+        }
+        pts
+    }
+
+    /// Plan polyline with arcs densified as in [`Polyline3::densify`].
+    /// Closed polylines repeat the first vertex at the end.
+    pub fn to_polyline(&self, max_angle: f64) -> Polyline {
+        let mut pts: Vec<Point> = self.densify(max_angle).into_iter().map(plan).collect();
+        if self.closed {
+            pts.extend(pts.first().copied());
+        }
+        Polyline::new(pts)
+    }
+}
+
+impl From<&Polyline> for Polyline3 {
+    fn from(pl: &Polyline) -> Self {
+        Self::new(
+            pl.vertices
+                .iter()
+                .map(|p| Point3::new(p.x, p.y, 0.0))
+                .collect(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    fn close(a: Point3, b: Point3) -> bool {
+        (a.x - b.x).abs() < 1e-9 && (a.y - b.y).abs() < 1e-9 && (a.z - b.z).abs() < 1e-9
+    }
+
+    #[test]
+    fn arcs_and_elevations() {
+        // 10 m straight then a left quarter circle of radius 5, rising 1 m
+        // on each segment.
+        let pl = Polyline3::with_bulges(
+            vec![
+                Point3::new(0.0, 0.0, 100.0),
+                Point3::new(10.0, 0.0, 101.0),
+                Point3::new(15.0, 5.0, 102.0),
+            ],
+            vec![0.0, (PI / 8.0).tan()],
+        );
+        let arc_len = 5.0 * PI / 2.0;
+        assert!((pl.length() - (10.0 + arc_len)).abs() < 1e-9);
+        assert!((pl.length_3d() - (101f64.sqrt() + arc_len.hypot(1.0))).abs() < 1e-9);
+
+        let mid = pl.point_at(10.0 + arc_len / 2.0).unwrap();
+        let s = (PI / 4.0).sin() * 5.0;
+        assert!(close(mid, Point3::new(10.0 + s, 5.0 - s, 101.5)));
+        assert!(pl.point_at(100.0).is_none());
+
+        // On the ray from the arc centre through its midpoint.
+        let pick = Point::new(10.0 + 2.0 * s, 5.0 - 2.0 * s);
+        let near = pl.nearest_point(pick).unwrap();
+        assert!(close(near, mid));
+        let station = pl.station_at(pick).unwrap();
+        assert!((station - (10.0 + arc_len / 2.0)).abs() < 1e-9);
+
+        let off = pl.offset(1.0);
+        assert_eq!(off.vertices.len(), 3);
+        assert!(close(off.vertices[0], Point3::new(0.0, 1.0, 100.0)));
+        assert!((off.length() - (10.0 + 4.0 * PI / 2.0)).abs() < 1e-9);
+
+        let dense = pl.densify(PI / 8.0);
+        assert_eq!(dense.len(), 6);
+        assert!(close(dense[5], pl.vertices[2]));
+    }
+
+    #[test]
+    fn closed_area_with_arc() {
+        // Square with its top edge bulged out into a semicircle.
+        let mut pl = Polyline3::with_bulges(
+            vec![
+                Point3::new(0.0, 0.0, 0.0),
+                Point3::new(10.0, 0.0, 0.0),
+                Point3::new(10.0, 10.0, 0.0),
+                Point3::new(0.0, 10.0, 0.0),
+            ],
+            vec![0.0, 0.0, 1.0, 0.0],
+        );
+        pl.closed = true;
+        assert_eq!(pl.segment_count(), 4);
+        assert!((pl.area() - (100.0 + PI * 25.0 / 2.0)).abs() < 1e-9);
+        assert!((pl.length() - (30.0 + 5.0 * PI)).abs() < 1e-9);
+        let plan = pl.to_polyline(PI / 4.0);
+        assert_eq!(plan.vertices.len(), 8);
+        assert_eq!(plan.vertices[0], plan.vertices[7]);
+    }
+
+    #[test]
+    fn closed_two_vertex_circle() {
+        // Two half-circle bulges make the usual DXF circle.
+        let mut pl = Polyline3::with_bulges(
+            vec![Point3::new(0.0, 0.0, 5.0), Point3::new(10.0, 0.0, 5.0)],
+            vec![1.0, 1.0],
+        );
+        pl.closed = true;
+        assert_eq!(pl.segment_count(), 2);
+        assert!((pl.length() - 10.0 * PI).abs() < 1e-9);
+        assert!((pl.area() - 25.0 * PI).abs() < 1e-9);
+        let top = pl.nearest_point(Point::new(5.0, 8.0)).unwrap();
+        assert!(close(top, Point3::new(5.0, 5.0, 5.0)));
+        let plan = pl.to_polyline(PI / 4.0);
+        assert_eq!(plan.vertices.len(), 9);
+        assert_eq!(plan.vertices[0], plan.vertices[8]);
+    }
+}
@@ -0,0 +1,148 @@
+//! Readers for processed GNSS baseline vector exports.
+//!
+//! Two layouts are recognised:
+//!
+//! * Star*Net style `G1`/`G2`/`G3` records as exported by most office
+//!   packages: `G1 FROM-TO dX dY dZ`, `G2 varX varY varZ` and
+//!   `G3 covXY covXZ covYZ`.
+//! * Comma separated files with a header row naming the columns `from`,
+//!   `to`, `dx`, `dy`, `dz` and either the covariance terms `cxx`, `cxy`,
+//!   `cxz`, `cyy`, `cyz`, `czz` or the standard deviations `sdx`, `sdy`,
+//!   `sdz`. Column names are case insensitive.
+
+use crate::surveying::BaselineVector;
+use std::io;
+
+fn invalid(line: usize, msg: impl std::fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("line {line}: {msg}"))
+}
+
+fn num(s: &str, line: usize) -> io::Result<f64> {
+    s.trim()
+        .parse()
+        .map_err(|_| invalid(line, format!("invalid number '{}'", s.trim())))
+}
+
+/// Parses Star*Net style `G` records.
+pub fn parse_baselines_gnss(text: &str) -> io::Result<Vec<BaselineVector>> {
+    let mut out: Vec<BaselineVector> = Vec::new();
+    for (idx, line) in text.lines().enumerate() {
+        let no = idx + 1;
+        let line = line.split('#').next().unwrap_or("").trim();
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let Some(&kind) = fields.first() else {
+            continue;
+        };
+        match kind.to_ascii_uppercase().as_str() {
+            "G1" => {
+                if fields.len() < 5 {
+                    return Err(invalid(no, "G1 record needs FROM-TO dX dY dZ"));
+                }
+                let (from, to) = fields[1]
+                    .split_once('-')
+                    .ok_or_else(|| invalid(no, "expected FROM-TO station pair"))?;
+                out.push(BaselineVector {
+                    from: from.to_string(),
+                    to: to.to_string(),
+                    dx: num(fields[2], no)?,
+                    dy: num(fields[3], no)?,
+                    dz: num(fields[4], no)?,
+                    covariance: [0.0; 6],
+                });
+            }
+            "G2" | "G3" => {
+                let b = out
+                    .last_mut()
+                    .ok_or_else(|| invalid(no, format!("{kind} record without G1")))?;
+                if fields.len() < 4 {
+                    return Err(invalid(no, format!("{kind} record needs three values")));
+                }
+                let v = [
+                    num(fields[1], no)?,
+                    num(fields[2], no)?,
+                    num(fields[3], no)?,
+                ];
+                if kind.eq_ignore_ascii_case("G2") {
+                    b.covariance[0] = v[0];
+                    b.covariance[3] = v[1];
+                    b.covariance[5] = v[2];
+                } else {
+                    b.covariance[1] = v[0];
+                    b.covariance[2] = v[1];
+                    b.covariance[4] = v[2];
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(out)
+}
+
+/// Parses a comma separated baseline export with a header row.
+pub fn parse_baselines_csv(text: &str) -> io::Result<Vec<BaselineVector>> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty());
+    let Some((_, header)) = lines.next() else {
+        return Ok(Vec::new());
+    };
+    let names: Vec<String> = header
+        .split(',')
+        .map(|h| h.trim().to_ascii_lowercase())
+        .collect();
+    let find = |name: &str| names.iter().position(|n| n == name);
+    let required =
+        |name: &str| find(name).ok_or_else(|| invalid(1, format!("missing column '{name}'")));
+    let (from, to) = (required("from")?, required("to")?);
+    let (dx, dy, dz) = (required("dx")?, required("dy")?, required("dz")?);
+    let cov_cols: Option<Vec<usize>> = ["cxx", "cxy", "cxz", "cyy", "cyz", "czz"]
+        .iter()
+        .map(|n| find(n))
+        .collect();
+    let sd_cols: Option<Vec<usize>> = ["sdx", "sdy", "sdz"].iter().map(|n| find(n)).collect();
+
+    let mut out = Vec::new();
+    for (idx, line) in lines {
+        let no = idx + 1;
+        let f: Vec<&str> = line.split(',').map(str::trim).collect();
+        let get = |i: usize| {
+            f.get(i)
+                .copied()
+                .ok_or_else(|| invalid(no, "missing field"))
+        };
+        let mut covariance = [0.0; 6];
+        if let Some(cols) = &cov_cols {
+            for (c, &i) in covariance.iter_mut().zip(cols) {
+                *c = num(get(i)?, no)?;
+            }
+        } else if let Some(cols) = &sd_cols {
+            for (k, &i) in [0usize, 3, 5].iter().zip(cols) {
+                covariance[*k] = num(get(i)?, no)?.powi(2);
+            }
+        }
+        out.push(BaselineVector {
+            from: get(from)?.to_string(),
+            to: get(to)?.to_string(),
+            dx: num(get(dx)?, no)?,
+            dy: num(get(dy)?, no)?,
+            dz: num(get(dz)?, no)?,
+            covariance,
+        });
+    }
+    Ok(out)
+}
+
+/// Reads a baseline export, detecting `G` records or a CSV header.
+pub fn read_baselines(path: &str) -> io::Result<Vec<BaselineVector>> {
+    let text = std::fs::read_to_string(path)?;
+    let is_gnss = text.lines().any(|l| {
+        let l = l.trim_start();
+        l.len() > 2 && l[..2].eq_ignore_ascii_case("G1") && l.as_bytes()[2].is_ascii_whitespace()
+    });
+    if is_gnss {
+        parse_baselines_gnss(&text)
+    } else {
+        parse_baselines_csv(&text)
+    }
+}
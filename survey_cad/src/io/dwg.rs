@@ -0,0 +1,2322 @@
+//! Native DWG reader.
+//!
+//! Reads the entities, layers, linetypes and block definitions of R2000 to
+//! R2018 drawings (`AC1015`, `AC1018`, `AC1024`, `AC1027` and `AC1032`) into
+//! the same [`DxfDocument`] model as the DXF reader, without any external
+//! tools. R2007 (`AC1021`) files use a different container, with Reed-Solomon
+//! coded pages and their own compression, which isn't decoded here: reading
+//! them from a file, like other versions the native reader doesn't handle,
+//! falls back to the `dwg2dxf` command from the LibreDWG project. Objects
+//! that aren't interpreted, such as dimensions, hatches and dictionaries, are
+//! skipped. Objects that fail to decode are left out and listed in
+//! [`DwgReport`].
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::io;
+
+use super::dxf::{
+    decode_text, invalid, polyline_2d, unescape_unicode, DxfAttribute, DxfBlock, DxfDash,
+    DxfDocument, DxfEntity, DxfLayer, DxfLinetype, DxfProperties, DxfVertex,
+};
+use crate::geometry::{Arc, Point, Point3};
+
+/// File versions that can be read. Features the specification lists for
+/// R2007 and later apply from `R2010` since R2007 files aren't read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Version {
+    R2000,
+    R2004,
+    R2010,
+    R2013,
+    R2018,
+}
+
+const TEXT: u16 = 1;
+const ATTRIB: u16 = 2;
+const INSERT: u16 = 7;
+const VERTEX_2D: u16 = 10;
+const VERTEX_3D: u16 = 11;
+const POLYLINE_2D: u16 = 15;
+const POLYLINE_3D: u16 = 16;
+const ARC: u16 = 17;
+const CIRCLE: u16 = 18;
+const LINE: u16 = 19;
+const POINT: u16 = 27;
+const FACE_3D: u16 = 28;
+const MTEXT: u16 = 44;
+const BLOCK_HEADER: u16 = 49;
+const LAYER: u16 = 51;
+const LTYPE: u16 = 57;
+const LWPOLYLINE: u16 = 77;
+
+/// Lineweights in hundredths of a millimetre by their index in a DWG file.
+const LINEWEIGHTS: [i16; 24] = [
+    0, 5, 9, 13, 15, 18, 20, 25, 30, 35, 40, 50, 53, 60, 70, 80, 90, 100, 106, 120, 140, 158, 200,
+    211,
+];
+
+fn eof() -> io::Error {
+    invalid("unexpected end of DWG data")
+}
+
+fn le_u32(data: &[u8], at: usize) -> io::Result<u32> {
+    let b = data.get(at..at + 4).ok_or_else(eof)?;
+    Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn le_u64(data: &[u8], at: usize) -> io::Result<u64> {
+    Ok(le_u32(data, at)? as u64 | (le_u32(data, at + 4)? as u64) << 32)
+}
+
+/// Reader for the bit-packed values of object data, most significant bit
+/// first.
+#[derive(Clone)]
+struct Bits<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Bits<'a> {
+    fn new(data: &'a [u8], pos: usize) -> Self {
+        Self { data, pos }
+    }
+
+    fn bit(&mut self) -> io::Result<u8> {
+        let byte = *self.data.get(self.pos >> 3).ok_or_else(eof)?;
+        let b = (byte >> (7 - (self.pos & 7))) & 1;
+        self.pos += 1;
+        Ok(b)
+    }
+
+    fn b(&mut self) -> io::Result<bool> {
+        Ok(self.bit()? == 1)
+    }
+
+    fn bb(&mut self) -> io::Result<u8> {
+        Ok(self.bit()? << 1 | self.bit()?)
+    }
+
+    fn rc(&mut self) -> io::Result<u8> {
+        let at = self.pos >> 3;
+        let shift = self.pos & 7;
+        let hi = *self.data.get(at).ok_or_else(eof)?;
+        let byte = if shift == 0 {
+            hi
+        } else {
+            let lo = *self.data.get(at + 1).ok_or_else(eof)?;
+            hi << shift | lo >> (8 - shift)
+        };
+        self.pos += 8;
+        Ok(byte)
+    }
+
+    fn raw<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        let mut out = [0; N];
+        for b in &mut out {
+            *b = self.rc()?;
+        }
+        Ok(out)
+    }
+
+    fn rs(&mut self) -> io::Result<u16> {
+        Ok(u16::from_le_bytes(self.raw()?))
+    }
+
+    fn rl(&mut self) -> io::Result<u32> {
+        Ok(u32::from_le_bytes(self.raw()?))
+    }
+
+    fn rd(&mut self) -> io::Result<f64> {
+        Ok(f64::from_le_bytes(self.raw()?))
+    }
+
+    fn bs(&mut self) -> io::Result<u16> {
+        Ok(match self.bb()? {
+            0 => self.rs()?,
+            1 => self.rc()? as u16,
+            2 => 0,
+            _ => 256,
+        })
+    }
+
+    fn bl(&mut self) -> io::Result<u32> {
+        match self.bb()? {
+            0 => self.rl(),
+            1 => Ok(self.rc()? as u32),
+            2 => Ok(0),
+            _ => Err(invalid("invalid bit long")),
+        }
+    }
+
+    fn bll(&mut self) -> io::Result<u64> {
+        let len = self.bit()? << 2 | self.bb()?;
+        let mut value = 0u64;
+        for i in 0..len {
+            value |= (self.rc()? as u64) << (8 * i);
+        }
+        Ok(value)
+    }
+
+    fn bd(&mut self) -> io::Result<f64> {
+        match self.bb()? {
+            0 => self.rd(),
+            1 => Ok(1.0),
+            2 => Ok(0.0),
+            _ => Err(invalid("invalid bit double")),
+        }
+    }
+
+    /// Bit double stored as the bytes that differ from `default`.
+    fn dd(&mut self, default: f64) -> io::Result<f64> {
+        let mut bytes = default.to_le_bytes();
+        match self.bb()? {
+            0 => {}
+            1 => bytes[..4].copy_from_slice(&self.raw::<4>()?),
+            2 => {
+                bytes[4..6].copy_from_slice(&self.raw::<2>()?);
+                bytes[..4].copy_from_slice(&self.raw::<4>()?);
+            }
+            _ => return self.rd(),
+        }
+        Ok(f64::from_le_bytes(bytes))
+    }
+
+    fn bd3(&mut self) -> io::Result<Point3> {
+        Ok(Point3::new(self.bd()?, self.bd()?, self.bd()?))
+    }
+
+    fn thickness(&mut self) -> io::Result<f64> {
+        if self.b()? {
+            Ok(0.0)
+        } else {
+            self.bd()
+        }
+    }
+
+    fn extrusion(&mut self) -> io::Result<Point3> {
+        if self.b()? {
+            Ok(Point3::new(0.0, 0.0, 1.0))
+        } else {
+            self.bd3()
+        }
+    }
+
+    fn ot(&mut self) -> io::Result<u16> {
+        Ok(match self.bb()? {
+            0 => self.rc()? as u16,
+            1 => self.rc()? as u16 + 0x1F0,
+            _ => self.rs()?,
+        })
+    }
+
+    /// Handle reference as its code and value.
+    fn handle(&mut self) -> io::Result<(u8, u64)> {
+        let head = self.rc()?;
+        let mut value = 0u64;
+        for _ in 0..head & 0xF {
+            value = value << 8 | self.rc()? as u64;
+        }
+        Ok((head >> 4, value))
+    }
+
+    /// Handle reference resolved against the handle of the object holding it.
+    fn href(&mut self, own: u64) -> io::Result<u64> {
+        let (code, value) = self.handle()?;
+        Ok(match code {
+            6 => own + 1,
+            8 => own.wrapping_sub(1),
+            0xA => own + value,
+            0xC => own.wrapping_sub(value),
+            _ => value,
+        })
+    }
+
+    fn tv(&mut self) -> io::Result<String> {
+        let len = self.bs()? as usize;
+        let mut bytes = Vec::with_capacity(len);
+        for _ in 0..len {
+            bytes.push(self.rc()?);
+        }
+        while bytes.last() == Some(&0) {
+            bytes.pop();
+        }
+        Ok(unescape_unicode(&decode_text(&bytes)))
+    }
+
+    fn tu(&mut self) -> io::Result<String> {
+        let len = self.bs()? as usize;
+        let mut units = Vec::with_capacity(len);
+        for _ in 0..len {
+            units.push(self.rs()?);
+        }
+        while units.last() == Some(&0) {
+            units.pop();
+        }
+        Ok(String::from_utf16_lossy(&units))
+    }
+
+    fn skip(&mut self, bits: usize) {
+        self.pos += bits;
+    }
+}
+
+/// Modular char at a byte position; signed values keep their sign in bit
+/// 0x40 of the last byte.
+fn modular_char(data: &[u8], pos: &mut usize, signed: bool) -> io::Result<i64> {
+    let mut value = 0i64;
+    let mut shift = 0;
+    loop {
+        let b = *data.get(*pos).ok_or_else(eof)?;
+        *pos += 1;
+        if b & 0x80 != 0 {
+            value |= ((b & 0x7F) as i64) << shift;
+            shift += 7;
+            if shift > 56 {
+                return Err(invalid("modular char too long"));
+            }
+        } else if signed {
+            value |= ((b & 0x3F) as i64) << shift;
+            return Ok(if b & 0x40 != 0 { -value } else { value });
+        } else {
+            return Ok(value | (b as i64) << shift);
+        }
+    }
+}
+
+fn modular_short(data: &[u8], pos: &mut usize) -> io::Result<usize> {
+    let mut value = 0usize;
+    let mut shift = 0;
+    loop {
+        let b = data.get(*pos..*pos + 2).ok_or_else(eof)?;
+        *pos += 2;
+        let word = u16::from_le_bytes([b[0], b[1]]);
+        value |= ((word & 0x7FFF) as usize) << shift;
+        if word & 0x8000 == 0 {
+            return Ok(value);
+        }
+        shift += 15;
+        if shift > 45 {
+            return Err(invalid("modular short too long"));
+        }
+    }
+}
+
+/// Decompresses a page of an R2004 and later file.
+fn decompress(src: &[u8], limit: usize) -> io::Result<Vec<u8>> {
+    struct Input<'a> {
+        data: &'a [u8],
+        pos: usize,
+    }
+    impl Input<'_> {
+        fn byte(&mut self) -> io::Result<usize> {
+            let b = *self.data.get(self.pos).ok_or_else(eof)?;
+            self.pos += 1;
+            Ok(b as usize)
+        }
+
+        fn literal_length(&mut self) -> io::Result<usize> {
+            match self.data.get(self.pos) {
+                Some(&b) if b <= 0x0F => {
+                    self.pos += 1;
+                    let mut n = b as usize;
+                    if n == 0 {
+                        n = 0x0F + self.long_length()?;
+                    }
+                    Ok(n + 3)
+                }
+                _ => Ok(0),
+            }
+        }
+
+        fn long_length(&mut self) -> io::Result<usize> {
+            let mut n = 0;
+            loop {
+                match self.byte()? {
+                    0 => n += 0xFF,
+                    b => return Ok(n + b),
+                }
+            }
+        }
+
+        fn two_byte_offset(&mut self) -> io::Result<(usize, usize)> {
+            let first = self.byte()?;
+            let offset = first >> 2 | self.byte()? << 6;
+            Ok((offset, first & 3))
+        }
+
+        fn literal(&mut self, n: usize, out: &mut Vec<u8>) -> io::Result<()> {
+            let bytes = self.data.get(self.pos..self.pos + n).ok_or_else(eof)?;
+            out.extend_from_slice(bytes);
+            self.pos += n;
+            Ok(())
+        }
+    }
+
+    let mut input = Input { data: src, pos: 0 };
+    let mut out = Vec::with_capacity(limit);
+    let n = input.literal_length()?;
+    input.literal(n, &mut out)?;
+    while input.pos < src.len() {
+        let op = input.byte()?;
+        let (count, offset, mut literals) = match op {
+            0x10 => {
+                let count = input.long_length()? + 9;
+                let (offset, lit) = input.two_byte_offset()?;
+                (count, offset + 0x3FFF, lit)
+            }
+            0x11 => break,
+            0x12..=0x1F => {
+                let (offset, lit) = input.two_byte_offset()?;
+                ((op & 0x0F) + 2, offset + 0x3FFF, lit)
+            }
+            0x20 => {
+                let count = input.long_length()? + 0x21;
+                let (offset, lit) = input.two_byte_offset()?;
+                (count, offset, lit)
+            }
+            0x21..=0x3F => {
+                let (offset, lit) = input.two_byte_offset()?;
+                (op - 0x1E, offset, lit)
+            }
+            0x40.. => {
+                let offset = input.byte()? << 2 | (op & 0x0C) >> 2;
+                ((op >> 4) - 1, offset, op & 3)
+            }
+            _ => return Err(invalid(format!("invalid compression opcode {op:#04x}"))),
+        };
+        if literals == 0 {
+            literals = input.literal_length()?;
+        }
+        let from = out
+            .len()
+            .checked_sub(offset + 1)
+            .ok_or_else(|| invalid("compressed data refers before its start"))?;
+        for i in 0..count {
+            out.push(out[from + i]);
+        }
+        input.literal(literals, &mut out)?;
+        if out.len() > limit {
+            return Err(invalid("compressed page larger than its stated size"));
+        }
+    }
+    Ok(out)
+}
+
+/// Returns the object map and object data of an R2000 file. Object
+/// offsets are relative to the start of the file.
+fn r2000_sections(data: &[u8]) -> io::Result<(&[u8], &[u8])> {
+    let count = le_u32(data, 0x15)? as usize;
+    for i in 0..count.min(16) {
+        let at = 0x19 + 9 * i;
+        let number = *data.get(at).ok_or_else(eof)?;
+        if number == 2 {
+            let seeker = le_u32(data, at + 1)? as usize;
+            let size = le_u32(data, at + 5)? as usize;
+            let map = data.get(seeker..seeker + size).ok_or_else(eof)?;
+            return Ok((map, data));
+        }
+    }
+    Err(invalid("DWG file has no object map"))
+}
+
+/// Decrypts the R2004 file header.
+fn decrypt_header(bytes: &mut [u8]) {
+    let mut seed: u32 = 1;
+    for b in bytes {
+        seed = seed.wrapping_mul(0x343FD).wrapping_add(0x269EC3);
+        *b ^= (seed >> 16) as u8;
+    }
+}
+
+/// Reads a compressed system page holding the page map or section map.
+fn system_page(data: &[u8], address: usize, kind: u32) -> io::Result<Vec<u8>> {
+    if le_u32(data, address)? != kind {
+        return Err(invalid(format!("no system page at {address:#x}")));
+    }
+    let size = le_u32(data, address + 4)? as usize;
+    let compressed = le_u32(data, address + 8)? as usize;
+    let compression = le_u32(data, address + 12)?;
+    let start = address + 20;
+    let raw = data.get(start..start + compressed).ok_or_else(eof)?;
+    if compression == 2 {
+        decompress(raw, size)
+    } else {
+        Ok(raw.to_vec())
+    }
+}
+
+struct SectionPage {
+    number: i32,
+    offset: usize,
+}
+
+struct Section {
+    name: String,
+    size: usize,
+    max_size: usize,
+    compressed: bool,
+    encrypted: bool,
+    pages: Vec<SectionPage>,
+}
+
+fn section_data(
+    data: &[u8],
+    pages: &HashMap<i32, usize>,
+    section: &Section,
+) -> io::Result<Vec<u8>> {
+    if section.encrypted {
+        return Err(invalid(format!("section {} is encrypted", section.name)));
+    }
+    if section.size > section.pages.len() * section.max_size {
+        return Err(invalid(format!("section {} is too large", section.name)));
+    }
+    let mut out = vec![0; section.size];
+    for page in &section.pages {
+        let address = *pages
+            .get(&page.number)
+            .ok_or_else(|| invalid(format!("missing page {}", page.number)))?;
+        let mut header = [0u32; 8];
+        for (i, h) in header.iter_mut().enumerate() {
+            *h = le_u32(data, address + 4 * i)? ^ 0x4164_536B ^ address as u32;
+        }
+        if header[0] != 0x4163_043B {
+            return Err(invalid(format!("no data page at {address:#x}")));
+        }
+        let start = address + 32;
+        let raw = data
+            .get(start..start + header[2] as usize)
+            .ok_or_else(eof)?;
+        let bytes = if section.compressed {
+            Cow::Owned(decompress(raw, section.max_size)?)
+        } else {
+            Cow::Borrowed(raw)
+        };
+        let from = page.offset.min(out.len());
+        let to = (page.offset + bytes.len()).min(out.len());
+        out[from..to].copy_from_slice(&bytes[..to - from]);
+    }
+    Ok(out)
+}
+
+/// Returns the object map and object data of an R2004 or later file. The
+/// object data is the `AcDb:AcDbObjects` section, which offsets are relative
+/// to.
+fn r2004_sections(data: &[u8]) -> io::Result<(Vec<u8>, Vec<u8>)> {
+    let mut header = data.get(0x80..0x80 + 0x6C).ok_or_else(eof)?.to_vec();
+    decrypt_header(&mut header);
+    if !header.starts_with(b"AcFssFcAJMB") {
+        return Err(invalid("invalid DWG file header"));
+    }
+    let page_map_address = le_u64(&header, 0x54)? as usize + 0x100;
+    let section_map_id = le_u32(&header, 0x5C)? as i32;
+
+    let page_map = system_page(data, page_map_address, 0x4163_0E3B)?;
+    let mut pages = HashMap::new();
+    let mut address = 0x100;
+    let mut pos = 0;
+    while pos + 8 <= page_map.len() {
+        let number = le_u32(&page_map, pos)? as i32;
+        let size = le_u32(&page_map, pos + 4)? as usize;
+        pos += 8;
+        if number < 0 {
+            pos += 16;
+        } else {
+            pages.insert(number, address);
+        }
+        address += size;
+    }
+
+    let section_map_address = *pages
+        .get(&section_map_id)
+        .ok_or_else(|| invalid("DWG file has no section map"))?;
+    let map = system_page(data, section_map_address, 0x4163_003B)?;
+    let count = le_u32(&map, 0)?;
+    let mut pos = 20;
+    let mut sections = Vec::new();
+    for _ in 0..count {
+        let size = le_u64(&map, pos)? as usize;
+        let page_count = le_u32(&map, pos + 8)? as usize;
+        let max_size = le_u32(&map, pos + 12)? as usize;
+        let compressed = le_u32(&map, pos + 20)? == 2;
+        let encrypted = le_u32(&map, pos + 28)? == 1;
+        let name = map.get(pos + 32..pos + 96).ok_or_else(eof)?;
+        let name = name.split(|&b| b == 0).next().unwrap_or_default();
+        pos += 96;
+        let mut section_pages = Vec::new();
+        for _ in 0..page_count {
+            section_pages.push(SectionPage {
+                number: le_u32(&map, pos)? as i32,
+                offset: le_u64(&map, pos + 8)? as usize,
+            });
+            pos += 16;
+        }
+        sections.push(Section {
+            name: String::from_utf8_lossy(name).into_owned(),
+            size,
+            max_size,
+            compressed,
+            encrypted,
+            pages: section_pages,
+        });
+    }
+    let find = |name: &str| {
+        let section = sections
+            .iter()
+            .find(|s| s.name == name)
+            .ok_or_else(|| invalid(format!("DWG file has no {name} section")))?;
+        section_data(data, &pages, section)
+    };
+    Ok((find("AcDb:Handles")?, find("AcDb:AcDbObjects")?))
+}
+
+/// Reads the object map as handles and offsets, in handle order.
+fn object_map(map: &[u8]) -> io::Result<Vec<(u64, usize)>> {
+    let mut objects = Vec::new();
+    let mut pos = 0;
+    while pos + 2 <= map.len() {
+        let size = u16::from_be_bytes([map[pos], map[pos + 1]]) as usize;
+        if size <= 2 {
+            break;
+        }
+        let end = pos + size;
+        if end > map.len() {
+            return Err(eof());
+        }
+        pos += 2;
+        let mut handle = 0i64;
+        let mut offset = 0i64;
+        while pos < end {
+            handle += modular_char(map, &mut pos, false)?;
+            offset += modular_char(map, &mut pos, true)?;
+            if offset >= 0 {
+                objects.push((handle as u64, offset as usize));
+            }
+        }
+        // skip the CRC
+        pos = end + 2;
+    }
+    Ok(objects)
+}
+
+/// Object with its data, handle and (from R2010) string streams positioned
+/// after the common header.
+struct Object<'a> {
+    version: Version,
+    kind: u16,
+    handle: u64,
+    data: Bits<'a>,
+    handles: Bits<'a>,
+    strings: Option<Bits<'a>>,
+}
+
+impl<'a> Object<'a> {
+    fn read(data: &'a [u8], offset: usize, version: Version) -> io::Result<Self> {
+        let mut pos = offset;
+        let size = modular_short(data, &mut pos)?;
+        let body = data.get(pos..pos + size).ok_or_else(eof)?;
+        let mut bits = Bits::new(body, 0);
+        let handle_bits = if version >= Version::R2010 {
+            let mut p = 0;
+            let n = modular_char(body, &mut p, false)? as usize;
+            bits.pos = p * 8;
+            Some(n)
+        } else {
+            None
+        };
+        let kind = if version >= Version::R2010 {
+            bits.ot()?
+        } else {
+            bits.bs()?
+        };
+        let bit_size = match handle_bits {
+            Some(n) => (size * 8)
+                .checked_sub(n)
+                .ok_or_else(|| invalid("invalid object size"))?,
+            None => bits.rl()? as usize,
+        };
+        let handle = bits.handle()?.1;
+        loop {
+            let n = bits.bs()? as usize;
+            if n == 0 {
+                break;
+            }
+            bits.handle()?;
+            bits.skip(n * 8);
+        }
+        let strings = if version >= Version::R2010 {
+            string_stream(body, bit_size)?
+        } else {
+            None
+        };
+        Ok(Self {
+            version,
+            kind,
+            handle,
+            data: bits,
+            handles: Bits::new(body, bit_size),
+            strings,
+        })
+    }
+
+    fn text(&mut self) -> io::Result<String> {
+        if self.version < Version::R2010 {
+            return self.data.tv();
+        }
+        match &mut self.strings {
+            Some(s) => s.tu(),
+            None => Ok(String::new()),
+        }
+    }
+
+    fn href(&mut self) -> io::Result<u64> {
+        self.handles.href(self.handle)
+    }
+
+    /// Common data of non-entity objects, returning the owner handle.
+    fn object_common(&mut self) -> io::Result<u64> {
+        let reactors = self.data.bl()?;
+        let xdic_missing = self.version >= Version::R2004 && self.data.b()?;
+        if self.version >= Version::R2013 {
+            self.data.b()?;
+        }
+        let owner = self.href()?;
+        for _ in 0..reactors {
+            self.href()?;
+        }
+        if !xdic_missing {
+            self.href()?;
+        }
+        Ok(owner)
+    }
+
+    /// Common data of table records: the name and the xref-dependent flag.
+    fn table_common(&mut self) -> io::Result<(String, bool)> {
+        self.object_common()?;
+        let name = self.text()?;
+        self.data.b()?;
+        self.data.bs()?;
+        let xdep = self.data.b()?;
+        Ok((name, xdep))
+    }
+
+    /// Colour of a table record as an index and optional true colour.
+    fn cmc(&mut self) -> io::Result<(i16, Option<i32>)> {
+        let index = self.data.bs()? as i16;
+        if self.version < Version::R2004 {
+            return Ok((index, None));
+        }
+        let rgb = self.data.bl()?;
+        let flags = self.data.rc()?;
+        if flags & 1 != 0 {
+            self.text()?;
+        }
+        if flags & 2 != 0 {
+            self.text()?;
+        }
+        Ok(match rgb >> 24 {
+            0xC0 => (256, None),
+            0xC1 => (0, None),
+            0xC2 => (
+                if index == 0 { 7 } else { index },
+                Some((rgb & 0xFF_FFFF) as i32),
+            ),
+            0xC3 => ((rgb & 0xFF) as i16, None),
+            _ => (index, None),
+        })
+    }
+}
+
+/// Locates the string stream that R2007 and later files keep at the end of
+/// the object data.
+fn string_stream(body: &[u8], bit_size: usize) -> io::Result<Option<Bits<'_>>> {
+    let bad = || invalid("invalid object string stream");
+    let mut start = bit_size.checked_sub(1).ok_or_else(bad)?;
+    let mut r = Bits::new(body, start);
+    if !r.b()? {
+        return Ok(None);
+    }
+    start = start.checked_sub(16).ok_or_else(bad)?;
+    r.pos = start;
+    let mut size = r.rs()? as usize;
+    if size & 0x8000 != 0 {
+        start = start.checked_sub(16).ok_or_else(bad)?;
+        r.pos = start;
+        let hi = r.rs()? as usize;
+        size = (size & 0x7FFF) | hi << 15;
+    }
+    r.pos = start.checked_sub(size).ok_or_else(bad)?;
+    Ok(Some(r))
+}
+
+/// Names of the table records referenced by entities, keyed by handle.
+#[derive(Default)]
+struct Names {
+    layers: HashMap<u64, String>,
+    linetypes: HashMap<u64, String>,
+    blocks: HashMap<u64, String>,
+}
+
+fn hex(handle: u64) -> String {
+    format!("{handle:X}")
+}
+
+fn lineweight(index: u8) -> Option<i16> {
+    match index {
+        29 => None,
+        30 => Some(-2),
+        31 => Some(-3),
+        i => LINEWEIGHTS.get(i as usize).copied(),
+    }
+}
+
+fn push_double(props: &mut DxfProperties, code: i32, value: f64) {
+    props.extra.push((code, value.to_string()));
+}
+
+/// Records thickness and a non-default extrusion direction as the DXF
+/// codes the writer emits.
+fn push_solid(props: &mut DxfProperties, thickness: f64, extrusion: Point3) {
+    if thickness != 0.0 {
+        push_double(props, 39, thickness);
+    }
+    if extrusion != Point3::new(0.0, 0.0, 1.0) {
+        push_double(props, 210, extrusion.x);
+        push_double(props, 220, extrusion.y);
+        push_double(props, 230, extrusion.z);
+    }
+}
+
+/// Reads the data and handles common to every entity, returning the owner
+/// handle of entities outside model and paper space.
+fn entity_common(obj: &mut Object, names: &Names) -> io::Result<(Option<u64>, DxfProperties)> {
+    let v = obj.version;
+    let d = &mut obj.data;
+    if d.b()? {
+        let size = if v >= Version::R2010 {
+            d.bll()?
+        } else {
+            d.rl()? as u64
+        };
+        d.skip(size as usize * 8);
+    }
+    let entmode = d.bb()?;
+    let reactors = d.bl()?;
+    let xdic_missing = v >= Version::R2004 && d.b()?;
+    if v >= Version::R2013 {
+        d.b()?;
+    }
+    let nolinks = v > Version::R2000 || d.b()?;
+
+    let mut props = DxfProperties {
+        handle: Some(hex(obj.handle)),
+        ..Default::default()
+    };
+    let mut color_book = false;
+    let raw = d.bs()?;
+    let mut index = raw as i16;
+    if v >= Version::R2004 {
+        let flags = raw >> 8;
+        index = (raw & 0x1FF) as i16;
+        if flags & 0x80 != 0 {
+            let rgb = d.bl()?;
+            match rgb >> 24 {
+                0xC2 => props.true_color = Some((rgb & 0xFF_FFFF) as i32),
+                0xC3 => index = (rgb & 0xFF) as i16,
+                _ => {}
+            }
+        }
+        color_book = flags & 0x40 != 0;
+        if flags & 0x20 != 0 {
+            let alpha = d.bl()?;
+            props.extra.push((440, (alpha as i32).to_string()));
+        }
+    }
+    if index != 256 {
+        props.color = Some(index);
+    }
+    d.bd()?;
+    let ltype_flags = d.bb()?;
+    let plotstyle_flags = d.bb()?;
+    let mut material_flags = 0;
+    let mut visual_styles = 0;
+    if v >= Version::R2010 {
+        material_flags = d.bb()?;
+        d.rc()?;
+        for _ in 0..3 {
+            visual_styles += d.b()? as usize;
+        }
+    }
+    if d.bs()? & 1 != 0 {
+        props.extra.push((60, "1".into()));
+    }
+    props.lineweight = lineweight(d.rc()?);
+
+    let owner = if entmode == 0 {
+        Some(obj.href()?)
+    } else {
+        None
+    };
+    for _ in 0..reactors {
+        obj.href()?;
+    }
+    if !xdic_missing {
+        obj.href()?;
+    }
+    if !nolinks {
+        obj.href()?;
+        obj.href()?;
+    }
+    if color_book {
+        obj.href()?;
+    }
+    let layer = obj.href()?;
+    props.layer = names
+        .layers
+        .get(&layer)
+        .cloned()
+        .unwrap_or_else(|| "0".into());
+    props.linetype = match ltype_flags {
+        0 => None,
+        1 => Some("BYBLOCK".into()),
+        2 => Some("CONTINUOUS".into()),
+        _ => names.linetypes.get(&obj.href()?).cloned(),
+    };
+    if material_flags == 3 {
+        obj.href()?;
+    }
+    if plotstyle_flags == 3 {
+        obj.href()?;
+    }
+    for _ in 0..visual_styles {
+        obj.href()?;
+    }
+    let paper = match owner {
+        Some(h) => names
+            .blocks
+            .get(&h)
+            .is_some_and(|n| n.to_uppercase().starts_with("*PAPER_SPACE")),
+        None => entmode == 1,
+    };
+    if paper {
+        props.extra.push((67, "1".into()));
+    }
+    Ok((owner, props))
+}
+
+/// Decoded entity, with polylines and inserts waiting for their vertices
+/// and attributes.
+enum Item {
+    Entity(DxfEntity),
+    Polyline2D {
+        closed: bool,
+        elevation: f64,
+        props: DxfProperties,
+    },
+    Polyline3D {
+        closed: bool,
+        props: DxfProperties,
+    },
+    Vertex(DxfVertex),
+    Attribute(DxfAttribute),
+}
+
+struct Text {
+    position: Point3,
+    height: f64,
+    rotation: f64,
+    value: String,
+}
+
+/// Reads the fields shared by `TEXT` and `ATTRIB`.
+fn text_data(obj: &mut Object, props: &mut DxfProperties) -> io::Result<Text> {
+    let flags = obj.data.rc()?;
+    let elevation = if flags & 1 == 0 { obj.data.rd()? } else { 0.0 };
+    let (x, y) = (obj.data.rd()?, obj.data.rd()?);
+    if flags & 2 == 0 {
+        obj.data.dd(x)?;
+        obj.data.dd(y)?;
+    }
+    let extrusion = obj.data.extrusion()?;
+    let thickness = obj.data.thickness()?;
+    push_solid(props, thickness, extrusion);
+    if flags & 4 == 0 {
+        obj.data.rd()?;
+    }
+    let rotation = if flags & 8 == 0 { obj.data.rd()? } else { 0.0 };
+    let height = obj.data.rd()?;
+    if flags & 0x10 == 0 {
+        let width = obj.data.rd()?;
+        if width != 1.0 {
+            push_double(props, 41, width);
+        }
+    }
+    let value = obj.text()?;
+    for (bit, code) in [(0x20, 71), (0x40, 72), (0x80, 73)] {
+        if flags & bit == 0 {
+            let v = obj.data.bs()?;
+            if v != 0 {
+                props.extra.push((code, v.to_string()));
+            }
+        }
+    }
+    Ok(Text {
+        position: Point3::new(x, y, elevation),
+        height,
+        rotation,
+        value,
+    })
+}
+
+fn parse_entity(obj: &mut Object, names: &Names) -> io::Result<Option<(Option<u64>, Item)>> {
+    if !matches!(
+        obj.kind,
+        TEXT | ATTRIB
+            | INSERT
+            | VERTEX_2D
+            | VERTEX_3D
+            | POLYLINE_2D
+            | POLYLINE_3D
+            | ARC
+            | CIRCLE
+            | LINE
+            | POINT
+            | FACE_3D
+            | MTEXT
+            | LWPOLYLINE
+    ) {
+        return Ok(None);
+    }
+    let (owner, mut props) = entity_common(obj, names)?;
+    let v = obj.version;
+    let item = match obj.kind {
+        TEXT => {
+            let t = text_data(obj, &mut props)?;
+            Item::Entity(
+                DxfEntity::Text3D {
+                    position: t.position,
+                    height: t.height,
+                    rotation: t.rotation,
+                    value: t.value,
+                    props,
+                }
+                .simplified(),
+            )
+        }
+        ATTRIB => {
+            let t = text_data(obj, &mut props)?;
+            if v >= Version::R2010 {
+                obj.data.rc()?;
+            }
+            if v >= Version::R2018 && obj.data.rc()? != 1 {
+                return Err(invalid("multiline attributes aren't supported"));
+            }
+            let tag = obj.text()?;
+            obj.data.bs()?;
+            let flags = obj.data.rc()?;
+            if flags != 0 {
+                props.extra.push((70, flags.to_string()));
+            }
+            Item::Attribute(DxfAttribute {
+                tag,
+                value: t.value,
+                position: t.position,
+                height: t.height,
+                rotation: t.rotation,
+                props,
+            })
+        }
+        INSERT => {
+            let d = &mut obj.data;
+            let position = d.bd3()?;
+            let scale = match d.bb()? {
+                0 => {
+                    let x = d.rd()?;
+                    [x, d.dd(x)?, d.dd(x)?]
+                }
+                1 => [1.0, d.dd(1.0)?, d.dd(1.0)?],
+                2 => [d.rd()?; 3],
+                _ => [1.0; 3],
+            };
+            let rotation = d.bd()?;
+            let extrusion = d.bd3()?;
+            push_solid(&mut props, 0.0, extrusion);
+            let block = obj.href()?;
+            Item::Entity(DxfEntity::Insert {
+                block: names.blocks.get(&block).cloned().unwrap_or_default(),
+                position,
+                scale,
+                rotation,
+                attributes: Vec::new(),
+                props,
+            })
+        }
+        VERTEX_2D => {
+            let d = &mut obj.data;
+            d.rc()?;
+            let point = d.bd3()?;
+            let start_width = d.bd()?;
+            let end_width = if start_width < 0.0 {
+                -start_width
+            } else {
+                d.bd()?
+            };
+            Item::Vertex(DxfVertex {
+                point,
+                bulge: d.bd()?,
+                start_width: start_width.abs(),
+                end_width,
+            })
+        }
+        VERTEX_3D => {
+            obj.data.rc()?;
+            Item::Vertex(DxfVertex::new(obj.data.bd3()?, 0.0))
+        }
+        POLYLINE_2D => {
+            let d = &mut obj.data;
+            let flags = d.bs()?;
+            d.bs()?;
+            d.bd()?;
+            d.bd()?;
+            let thickness = d.thickness()?;
+            let elevation = d.bd()?;
+            let extrusion = d.extrusion()?;
+            push_solid(&mut props, thickness, extrusion);
+            Item::Polyline2D {
+                closed: flags & 1 != 0,
+                elevation,
+                props,
+            }
+        }
+        POLYLINE_3D => {
+            obj.data.rc()?;
+            let closed = obj.data.rc()? & 1 != 0;
+            Item::Polyline3D { closed, props }
+        }
+        ARC | CIRCLE => {
+            let d = &mut obj.data;
+            let center = d.bd3()?;
+            let radius = d.bd()?;
+            let thickness = d.thickness()?;
+            let extrusion = d.extrusion()?;
+            push_solid(&mut props, thickness, extrusion);
+            if obj.kind == CIRCLE {
+                Item::Entity(DxfEntity::Circle {
+                    center,
+                    radius,
+                    props,
+                })
+            } else {
+                let (start, end) = (d.bd()?, d.bd()?);
+                Item::Entity(
+                    DxfEntity::Arc3D {
+                        arc: Arc::new(Point::new(center.x, center.y), radius, start, end),
+                        elevation: center.z,
+                        props,
+                    }
+                    .simplified(),
+                )
+            }
+        }
+        LINE => {
+            let d = &mut obj.data;
+            let flat = d.b()?;
+            let sx = d.rd()?;
+            let ex = d.dd(sx)?;
+            let sy = d.rd()?;
+            let ey = d.dd(sy)?;
+            let (sz, ez) = if flat {
+                (0.0, 0.0)
+            } else {
+                let sz = d.rd()?;
+                (sz, d.dd(sz)?)
+            };
+            let thickness = d.thickness()?;
+            let extrusion = d.extrusion()?;
+            push_solid(&mut props, thickness, extrusion);
+            Item::Entity(
+                DxfEntity::Line3D {
+                    start: Point3::new(sx, sy, sz),
+                    end: Point3::new(ex, ey, ez),
+                    props,
+                }
+                .simplified(),
+            )
+        }
+        POINT => {
+            let d = &mut obj.data;
+            let point = d.bd3()?;
+            let thickness = d.thickness()?;
+            let extrusion = d.extrusion()?;
+            push_solid(&mut props, thickness, extrusion);
+            let angle = d.bd()?;
+            if angle != 0.0 {
+                push_double(&mut props, 50, angle.to_degrees());
+            }
+            Item::Entity(DxfEntity::Point3D { point, props }.simplified())
+        }
+        FACE_3D => {
+            let d = &mut obj.data;
+            let no_flags = d.b()?;
+            let flat = d.b()?;
+            let (x, y) = (d.rd()?, d.rd()?);
+            let z = if flat { 0.0 } else { d.rd()? };
+            let mut corners = [Point3::new(x, y, z); 4];
+            for i in 1..4 {
+                let p = corners[i - 1];
+                corners[i] = Point3::new(d.dd(p.x)?, d.dd(p.y)?, d.dd(p.z)?);
+            }
+            if !no_flags {
+                let invisible = d.bs()?;
+                if invisible != 0 {
+                    props.extra.push((70, invisible.to_string()));
+                }
+            }
+            Item::Entity(DxfEntity::Face3D { corners, props })
+        }
+        MTEXT => {
+            let d = &mut obj.data;
+            let position = d.bd3()?;
+            let extrusion = d.bd3()?;
+            push_solid(&mut props, 0.0, extrusion);
+            let direction = d.bd3()?;
+            let width = d.bd()?;
+            if v >= Version::R2010 {
+                d.bd()?;
+            }
+            let height = d.bd()?;
+            let attachment = d.bs()?;
+            props.extra.push((71, attachment.to_string()));
+            d.bs()?;
+            d.bd()?;
+            d.bd()?;
+            let value = obj.text()?;
+            Item::Entity(DxfEntity::MText {
+                position,
+                height,
+                width,
+                rotation: direction.y.atan2(direction.x),
+                value,
+                props,
+            })
+        }
+        _ => {
+            let d = &mut obj.data;
+            let flags = d.bs()?;
+            let width = if flags & 4 != 0 { d.bd()? } else { 0.0 };
+            let elevation = if flags & 8 != 0 { d.bd()? } else { 0.0 };
+            let thickness = if flags & 2 != 0 { d.bd()? } else { 0.0 };
+            let extrusion = if flags & 1 != 0 {
+                d.bd3()?
+            } else {
+                Point3::new(0.0, 0.0, 1.0)
+            };
+            if width != 0.0 {
+                push_double(&mut props, 43, width);
+            }
+            push_solid(&mut props, thickness, extrusion);
+            let count = d.bl()? as usize;
+            let bulges = if flags & 16 != 0 { d.bl()? as usize } else { 0 };
+            let ids = if v >= Version::R2010 && flags & 1024 != 0 {
+                d.bl()? as usize
+            } else {
+                0
+            };
+            let widths = if flags & 32 != 0 { d.bl()? as usize } else { 0 };
+            if count > d.data.len() * 8 {
+                return Err(invalid("invalid lightweight polyline vertex count"));
+            }
+            let mut vertices: Vec<DxfVertex> = Vec::with_capacity(count);
+            for i in 0..count {
+                let (x, y) = match vertices.last() {
+                    Some(p) if i > 0 => (d.dd(p.point.x)?, d.dd(p.point.y)?),
+                    _ => (d.rd()?, d.rd()?),
+                };
+                vertices.push(DxfVertex::new(Point3::new(x, y, elevation), 0.0));
+            }
+            for i in 0..bulges {
+                let bulge = d.bd()?;
+                if let Some(v) = vertices.get_mut(i) {
+                    v.bulge = bulge;
+                }
+            }
+            for _ in 0..ids {
+                d.bl()?;
+            }
+            for i in 0..widths {
+                let (start, end) = (d.bd()?, d.bd()?);
+                if let Some(v) = vertices.get_mut(i) {
+                    v.start_width = start;
+                    v.end_width = end;
+                }
+            }
+            Item::Entity(DxfEntity::LwPolyline {
+                vertices,
+                closed: flags & 512 != 0,
+                elevation,
+                props,
+            })
+        }
+    };
+    Ok(Some((owner, item)))
+}
+
+fn parse_block_header(obj: &mut Object) -> io::Result<DxfBlock> {
+    let (name, xdep) = obj.table_common()?;
+    let d = &mut obj.data;
+    let mut flags = 0;
+    for bit in [1, 2, 4, 8] {
+        if d.b()? {
+            flags |= bit;
+        }
+    }
+    d.b()?;
+    if obj.version >= Version::R2004 && flags & 12 == 0 {
+        d.bl()?;
+    }
+    if xdep {
+        flags |= 16;
+    }
+    let mut block = DxfBlock::new(&name, d.bd3()?, Vec::new());
+    block.flags = flags;
+    Ok(block)
+}
+
+/// Reads a layer record and the handle of its linetype.
+fn parse_layer(obj: &mut Object) -> io::Result<(DxfLayer, u64)> {
+    let (name, xdep) = obj.table_common()?;
+    let flags = obj.data.bs()?;
+    let (color, true_color) = obj.cmc()?;
+    let mut layer = DxfLayer::new(&name, color);
+    layer.true_color = true_color;
+    if flags & 2 != 0 {
+        layer.color = -color.abs();
+    }
+    layer.flags = (flags & 1) as i16
+        | if flags & 4 != 0 { 2 } else { 0 }
+        | if flags & 8 != 0 { 4 } else { 0 }
+        | if xdep { 16 } else { 0 };
+    layer.lineweight = lineweight(((flags & 0x3E0) >> 5) as u8);
+    if flags & 16 == 0 {
+        layer.extra.push((290, "0".into()));
+    }
+    layer.handle = Some(hex(obj.handle));
+    obj.href()?;
+    obj.href()?;
+    if obj.version >= Version::R2010 {
+        obj.href()?;
+    }
+    Ok((layer, obj.href()?))
+}
+
+fn parse_linetype(obj: &mut Object) -> io::Result<DxfLinetype> {
+    let (name, xdep) = obj.table_common()?;
+    let description = obj.text()?;
+    let d = &mut obj.data;
+    d.bd()?;
+    d.rc()?;
+    let count = d.rc()?;
+    let mut pattern = Vec::new();
+    for _ in 0..count {
+        let length = d.bd()?;
+        let shape = d.bs()?;
+        let (x, y) = (d.rd()?, d.rd()?);
+        let (scale, rotation) = (d.bd()?, d.bd()?);
+        let flags = d.bs()?;
+        let mut codes = Vec::new();
+        if flags != 0 {
+            codes.push((74, flags.to_string()));
+            codes.push((75, shape.to_string()));
+            codes.push((46, scale.to_string()));
+            codes.push((50, rotation.to_degrees().to_string()));
+            codes.push((44, x.to_string()));
+            codes.push((45, y.to_string()));
+        }
+        pattern.push(DxfDash { length, codes });
+    }
+    let mut linetype = DxfLinetype::new(&name, &description, &[]);
+    linetype.pattern = pattern;
+    linetype.handle = Some(hex(obj.handle));
+    if xdep {
+        linetype.flags = 16;
+    }
+    Ok(linetype)
+}
+
+/// Document read from a DWG file with the objects that were left out.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DwgReport {
+    pub document: DxfDocument,
+    /// Handles of table records and entities that failed to decode.
+    pub skipped: Vec<u64>,
+}
+
+fn native_version(tag: &[u8]) -> Option<Version> {
+    match tag {
+        b"AC1015" => Some(Version::R2000),
+        b"AC1018" => Some(Version::R2004),
+        b"AC1024" => Some(Version::R2010),
+        b"AC1027" => Some(Version::R2013),
+        b"AC1032" => Some(Version::R2018),
+        _ => None,
+    }
+}
+
+/// Parses DWG data from R2000 to R2018, except R2007. A warning is logged
+/// when objects fail to decode; use [`parse_dwg_report`] to get them.
+pub fn parse_dwg(data: &[u8]) -> io::Result<DxfDocument> {
+    let report = parse_dwg_report(data)?;
+    if !report.skipped.is_empty() {
+        log::warn!(
+            "{} DWG objects failed to decode and were skipped",
+            report.skipped.len()
+        );
+    }
+    Ok(report.document)
+}
+
+/// Parses DWG data, listing the objects that failed to decode.
+pub fn parse_dwg_report(data: &[u8]) -> io::Result<DwgReport> {
+    let tag = data
+        .get(..6)
+        .ok_or_else(|| invalid("file too short for a DWG header"))?;
+    let version = match native_version(tag) {
+        Some(version) => version,
+        None if tag == b"AC1021" => {
+            return Err(invalid("R2007 (AC1021) DWG files aren't read natively"))
+        }
+        None => {
+            return Err(invalid(format!(
+                "unsupported DWG version `{}`",
+                String::from_utf8_lossy(tag)
+            )))
+        }
+    };
+    let (map, objects): (Cow<[u8]>, Cow<[u8]>) = if version == Version::R2000 {
+        let (map, objects) = r2000_sections(data)?;
+        (Cow::Borrowed(map), Cow::Borrowed(objects))
+    } else {
+        let (map, objects) = r2004_sections(data)?;
+        (Cow::Owned(map), Cow::Owned(objects))
+    };
+    let offsets = object_map(&map)?;
+
+    let mut doc = DxfDocument::new();
+    doc.set_header("$ACADVER", 1, std::str::from_utf8(tag).unwrap_or_default());
+    let mut names = Names::default();
+    let mut layers = Vec::new();
+    let mut block_index = HashMap::new();
+    let mut skipped = Vec::new();
+    for &(handle, offset) in &offsets {
+        let Ok(mut obj) = Object::read(&objects, offset, version) else {
+            // counted with the entities below
+            continue;
+        };
+        let decoded = match obj.kind {
+            BLOCK_HEADER => parse_block_header(&mut obj).map(|block| {
+                names.blocks.insert(obj.handle, block.name.clone());
+                let upper = block.name.to_uppercase();
+                if !upper.starts_with("*MODEL_SPACE") && !upper.starts_with("*PAPER_SPACE") {
+                    block_index.insert(obj.handle, doc.blocks.len());
+                }
+                doc.blocks.push(block);
+            }),
+            LAYER => parse_layer(&mut obj).map(|(layer, ltype)| {
+                names.layers.insert(obj.handle, layer.name.clone());
+                layers.push((layer, ltype));
+            }),
+            LTYPE => parse_linetype(&mut obj).map(|linetype| {
+                names.linetypes.insert(obj.handle, linetype.name.clone());
+                doc.linetypes.push(linetype);
+            }),
+            _ => Ok(()),
+        };
+        if decoded.is_err() {
+            skipped.push(handle);
+        }
+    }
+    for (mut layer, ltype) in layers {
+        if let Some(name) = names.linetypes.get(&ltype) {
+            layer.linetype = name.clone();
+        }
+        doc.layers.push(layer);
+    }
+
+    let mut items = Vec::new();
+    let mut children: HashMap<u64, Vec<Item>> = HashMap::new();
+    for &(handle, offset) in &offsets {
+        let Ok(mut obj) = Object::read(&objects, offset, version) else {
+            skipped.push(handle);
+            continue;
+        };
+        let (owner, item) = match parse_entity(&mut obj, &names) {
+            Ok(Some(decoded)) => decoded,
+            Ok(None) => continue,
+            Err(_) => {
+                skipped.push(handle);
+                continue;
+            }
+        };
+        match (&item, owner) {
+            (Item::Vertex(_) | Item::Attribute(_), Some(owner)) => {
+                children.entry(owner).or_default().push(item)
+            }
+            (Item::Vertex(_) | Item::Attribute(_), None) => {}
+            _ => items.push((handle, owner, item)),
+        }
+    }
+    for (handle, owner, item) in items {
+        let kids = children.remove(&handle).unwrap_or_default();
+        let vertices = || {
+            kids.iter()
+                .filter_map(|k| match k {
+                    Item::Vertex(v) => Some(*v),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        let entity = match item {
+            Item::Entity(DxfEntity::Insert {
+                block,
+                position,
+                scale,
+                rotation,
+                props,
+                ..
+            }) => DxfEntity::Insert {
+                block,
+                position,
+                scale,
+                rotation,
+                attributes: kids
+                    .into_iter()
+                    .filter_map(|k| match k {
+                        Item::Attribute(a) => Some(a),
+                        _ => None,
+                    })
+                    .collect(),
+                props,
+            },
+            Item::Entity(e) => e,
+            Item::Polyline2D {
+                closed,
+                elevation,
+                props,
+            } => polyline_2d(vertices(), closed, elevation, props),
+            Item::Polyline3D { closed, props } => DxfEntity::Polyline3D {
+                vertices: vertices(),
+                closed,
+                props,
+            },
+            Item::Vertex(_) | Item::Attribute(_) => continue,
+        };
+        match owner {
+            None => doc.entities.push(entity),
+            Some(owner) => match block_index.get(&owner) {
+                Some(&i) => doc.blocks[i].entities.push(entity),
+                None if names.blocks.contains_key(&owner) => doc.entities.push(entity),
+                None => {}
+            },
+        }
+    }
+    skipped.sort_unstable();
+    Ok(DwgReport {
+        document: doc,
+        skipped,
+    })
+}
+
+/// True for DWG data of a version the native reader doesn't handle, such
+/// as R2007 or anything before R2000.
+fn needs_converter(data: &[u8]) -> bool {
+    data.get(..6)
+        .is_some_and(|tag| tag.starts_with(b"AC") && native_version(tag).is_none())
+}
+
+/// Converts a DWG file to a temporary DXF with the external `dwg2dxf`
+/// command from the LibreDWG project and reads that.
+fn convert_dwg(path: &str) -> io::Result<DxfDocument> {
+    use std::process::Command;
+
+    let tmp = tempfile::Builder::new().suffix(".dxf").tempfile()?;
+    let status = Command::new("dwg2dxf")
+        .arg("-y")
+        .arg("-o")
+        .arg(tmp.path())
+        .arg(path)
+        .status()
+        .map_err(|e| io::Error::other(format!("failed to spawn dwg2dxf: {e}")))?;
+    if !status.success() {
+        return Err(io::Error::other("dwg2dxf failed"));
+    }
+    super::dxf::read_dxf_document(tmp.path().to_str().unwrap())
+}
+
+/// Reads a DWG file into a [`DxfDocument`]. Versions the native reader
+/// doesn't handle are converted with `dwg2dxf`, which must be installed.
+pub fn read_dwg_document(path: &str) -> io::Result<DxfDocument> {
+    let data = std::fs::read(path)?;
+    if needs_converter(&data) {
+        return convert_dwg(path);
+    }
+    parse_dwg(&data)
+}
+
+/// Reads a DWG file, listing the objects that failed to decode. Converted
+/// files list none.
+pub fn read_dwg_report(path: &str) -> io::Result<DwgReport> {
+    let data = std::fs::read(path)?;
+    if needs_converter(&data) {
+        return Ok(DwgReport {
+            document: convert_dwg(path)?,
+            skipped: Vec::new(),
+        });
+    }
+    parse_dwg_report(&data)
+}
+
+/// Reads the model and paper space entities of a DWG file. R2007
+/// (`AC1021`) drawings go through `dwg2dxf`; see [`read_dwg_document`].
+pub fn read_dwg(path: &str) -> io::Result<Vec<DxfEntity>> {
+    Ok(read_dwg_document(path)?.entities)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct BitWriter {
+        bytes: Vec<u8>,
+        len: usize,
+    }
+
+    impl BitWriter {
+        fn b(&mut self, bit: bool) {
+            if self.len & 7 == 0 {
+                self.bytes.push(0);
+            }
+            if bit {
+                *self.bytes.last_mut().unwrap() |= 0x80 >> (self.len % 8);
+            }
+            self.len += 1;
+        }
+
+        fn bits(&mut self, value: u64, n: usize) {
+            for i in (0..n).rev() {
+                self.b(value >> i & 1 == 1);
+            }
+        }
+
+        fn bb(&mut self, v: u8) {
+            self.bits(v as u64, 2);
+        }
+
+        fn raw(&mut self, bytes: &[u8]) {
+            for &b in bytes {
+                self.bits(b as u64, 8);
+            }
+        }
+
+        fn rc(&mut self, v: u8) {
+            self.raw(&[v]);
+        }
+
+        fn rs(&mut self, v: u16) {
+            self.raw(&v.to_le_bytes());
+        }
+
+        fn rd(&mut self, v: f64) {
+            self.raw(&v.to_le_bytes());
+        }
+
+        fn bs(&mut self, v: u16) {
+            match v {
+                0 => self.bb(2),
+                256 => self.bb(3),
+                1..=255 => {
+                    self.bb(1);
+                    self.rc(v as u8);
+                }
+                _ => {
+                    self.bb(0);
+                    self.rs(v);
+                }
+            }
+        }
+
+        fn bl(&mut self, v: u32) {
+            match v {
+                0 => self.bb(2),
+                1..=255 => {
+                    self.bb(1);
+                    self.rc(v as u8);
+                }
+                _ => {
+                    self.bb(0);
+                    self.raw(&v.to_le_bytes());
+                }
+            }
+        }
+
+        fn bd(&mut self, v: f64) {
+            if v == 0.0 {
+                self.bb(2);
+            } else if v == 1.0 {
+                self.bb(1);
+            } else {
+                self.bb(0);
+                self.rd(v);
+            }
+        }
+
+        fn dd(&mut self, v: f64, default: f64) {
+            if v == default {
+                self.bb(0);
+            } else {
+                self.bb(3);
+                self.rd(v);
+            }
+        }
+
+        fn bd3(&mut self, p: Point3) {
+            self.bd(p.x);
+            self.bd(p.y);
+            self.bd(p.z);
+        }
+
+        fn handle(&mut self, code: u8, value: u64) {
+            let bytes = value.to_be_bytes();
+            let skip = bytes.iter().take_while(|&&b| b == 0).count();
+            self.rc(code << 4 | (8 - skip) as u8);
+            self.raw(&bytes[skip..]);
+        }
+
+        fn append(&mut self, other: &BitWriter) {
+            for i in 0..other.len {
+                self.b(other.bytes[i / 8] >> (7 - i % 8) & 1 == 1);
+            }
+        }
+    }
+
+    fn modular(out: &mut Vec<u8>, value: i64, signed: bool) {
+        let mut a = value.unsigned_abs();
+        let last = if signed { 0x40 } else { 0x80 };
+        while a >= last {
+            out.push((a & 0x7F) as u8 | 0x80);
+            a >>= 7;
+        }
+        out.push(a as u8 | if value < 0 { 0x40 } else { 0 });
+    }
+
+    struct ObjectWriter {
+        version: Version,
+        kind: u16,
+        handle: u64,
+        data: BitWriter,
+        handles: BitWriter,
+        strings: BitWriter,
+    }
+
+    impl ObjectWriter {
+        fn new(version: Version, kind: u16, handle: u64) -> Self {
+            Self {
+                version,
+                kind,
+                handle,
+                data: BitWriter::default(),
+                handles: BitWriter::default(),
+                strings: BitWriter::default(),
+            }
+        }
+
+        fn text(&mut self, s: &str) {
+            if self.version >= Version::R2010 {
+                let units: Vec<u16> = s.encode_utf16().chain([0]).collect();
+                self.strings.bs(units.len() as u16);
+                units.iter().for_each(|&u| self.strings.rs(u));
+            } else {
+                self.data.bs(s.len() as u16 + 1);
+                self.data.raw(s.as_bytes());
+                self.data.rc(0);
+            }
+        }
+
+        /// Object bytes with their size in front and a blank CRC.
+        fn finish(self) -> Vec<u8> {
+            let mut body = BitWriter::default();
+            let mut out = Vec::new();
+            if self.version >= Version::R2010 {
+                if self.kind < 0x1F0 {
+                    body.bb(0);
+                    body.rc(self.kind as u8);
+                } else {
+                    body.bb(1);
+                    body.rc((self.kind - 0x1F0) as u8);
+                }
+                body.handle(0, self.handle);
+                body.bs(0);
+                body.append(&self.data);
+                if self.strings.len > 0 {
+                    body.append(&self.strings);
+                    body.rs(self.strings.len as u16);
+                    body.b(true);
+                } else {
+                    body.b(false);
+                }
+                let total = body.len + self.handles.len;
+                let handle_bits = self.handles.len + (8 - total % 8) % 8;
+                let mut head = Vec::new();
+                modular(&mut head, handle_bits as i64, false);
+                body.append(&self.handles);
+                out.extend(((head.len() + body.bytes.len()) as u16).to_le_bytes());
+                out.extend(head);
+            } else {
+                body.bs(self.kind);
+                let at = body.len;
+                body.raw(&[0; 4]);
+                body.handle(0, self.handle);
+                body.bs(0);
+                body.append(&self.data);
+                let mut size = BitWriter::default();
+                size.raw(&(body.len as u32).to_le_bytes());
+                for i in 0..32 {
+                    let bit = size.bytes[i / 8] >> (7 - i % 8) & 1;
+                    let (byte, mask) = ((at + i) / 8, 0x80 >> ((at + i) % 8));
+                    if bit == 1 {
+                        body.bytes[byte] |= mask;
+                    }
+                }
+                body.append(&self.handles);
+                out.extend((body.bytes.len() as u16).to_le_bytes());
+            }
+            out.extend(body.bytes);
+            out.extend([0, 0]);
+            out
+        }
+    }
+
+    fn entity(v: Version, kind: u16, handle: u64, layer: u64, owner: Option<u64>) -> ObjectWriter {
+        let mut o = ObjectWriter::new(v, kind, handle);
+        let d = &mut o.data;
+        d.b(false);
+        d.bb(if owner.is_some() { 0 } else { 2 });
+        d.bl(0);
+        if v >= Version::R2004 {
+            d.b(true);
+        }
+        if v >= Version::R2013 {
+            d.b(false);
+        }
+        if v == Version::R2000 {
+            d.b(true);
+        }
+        d.bs(256);
+        d.bd(1.0);
+        d.bb(0);
+        d.bb(0);
+        if v >= Version::R2010 {
+            d.bb(0);
+            d.rc(0);
+            d.bits(0, 3);
+        }
+        d.bs(0);
+        d.rc(29);
+        if let Some(owner) = owner {
+            // owners of vertices and attributes use relative references
+            if owner + 1 == handle {
+                o.handles.handle(8, 0);
+            } else {
+                o.handles.handle(4, owner);
+            }
+        }
+        if v == Version::R2000 {
+            o.handles.handle(3, 0);
+        }
+        o.handles.handle(5, layer);
+        o
+    }
+
+    fn record(v: Version, kind: u16, handle: u64, name: &str) -> ObjectWriter {
+        let mut o = ObjectWriter::new(v, kind, handle);
+        o.data.bl(0);
+        if v >= Version::R2004 {
+            o.data.b(true);
+        }
+        if v >= Version::R2013 {
+            o.data.b(false);
+        }
+        o.handles.handle(4, 1);
+        if v == Version::R2000 {
+            o.handles.handle(3, 0);
+        }
+        o.text(name);
+        o.data.b(false);
+        o.data.bs(0);
+        o.data.b(false);
+        o
+    }
+
+    fn layer(v: Version, handle: u64, name: &str, flags: u16, color: u8, ltype: u64) -> Vec<u8> {
+        let mut o = record(v, LAYER, handle, name);
+        o.data.bs(flags);
+        if v >= Version::R2004 {
+            o.data.bs(0);
+            o.data.bl(0xC300_0000 | color as u32);
+            o.data.rc(0);
+        } else {
+            o.data.bs(color as u16);
+        }
+        o.handles.handle(5, 0);
+        o.handles.handle(5, 0);
+        if v >= Version::R2010 {
+            o.handles.handle(5, 0);
+        }
+        o.handles.handle(5, ltype);
+        o.finish()
+    }
+
+    fn linetype(v: Version, handle: u64, name: &str, pattern: &[f64]) -> Vec<u8> {
+        let mut o = record(v, LTYPE, handle, name);
+        o.text("");
+        let d = &mut o.data;
+        d.bd(pattern.iter().map(|l| l.abs()).sum());
+        d.rc(b'A');
+        d.rc(pattern.len() as u8);
+        for &length in pattern {
+            d.bd(length);
+            d.bs(0);
+            d.rd(0.0);
+            d.rd(0.0);
+            d.bd(1.0);
+            d.bd(0.0);
+            d.bs(0);
+        }
+        o.finish()
+    }
+
+    fn block_header(v: Version, handle: u64, name: &str, base: Point3) -> Vec<u8> {
+        let mut o = record(v, BLOCK_HEADER, handle, name);
+        o.data.bits(0, 5);
+        if v >= Version::R2004 {
+            o.data.bl(0);
+        }
+        o.data.bd3(base);
+        o.finish()
+    }
+
+    /// Text fields of `TEXT` and `ATTRIB` with an elevation and rotation.
+    fn text_fields(
+        o: &mut ObjectWriter,
+        position: Point3,
+        height: f64,
+        rotation: f64,
+        value: &str,
+    ) {
+        let d = &mut o.data;
+        d.rc(0xF6);
+        d.rd(position.z);
+        d.rd(position.x);
+        d.rd(position.y);
+        d.b(true);
+        d.b(true);
+        d.rd(rotation);
+        d.rd(height);
+        o.text(value);
+    }
+
+    fn drawing_objects(v: Version) -> Vec<(u64, Vec<u8>)> {
+        let mut objects = vec![
+            (0x10, layer(v, 0x10, "0", 16 | 31 << 5, 7, 0x14)),
+            (0x11, layer(v, 0x11, "EOP", 16 | 2 | 29 << 5, 3, 0x15)),
+            (0x14, linetype(v, 0x14, "CONTINUOUS", &[])),
+            (0x15, linetype(v, 0x15, "DASHED", &[0.5, -0.25])),
+            (
+                0x1F,
+                block_header(v, 0x1F, "*Model_Space", Point3::new(0.0, 0.0, 0.0)),
+            ),
+            (
+                0x20,
+                block_header(v, 0x20, "TREE", Point3::new(1.0, 2.0, 0.0)),
+            ),
+        ];
+
+        let mut o = entity(v, LINE, 0x30, 0x11, None);
+        let d = &mut o.data;
+        d.b(false);
+        d.rd(1.0);
+        d.dd(4.0, 1.0);
+        d.rd(2.0);
+        d.dd(2.0, 2.0);
+        d.rd(3.0);
+        d.dd(6.0, 3.0);
+        d.b(true);
+        d.b(true);
+        objects.push((0x30, o.finish()));
+
+        let mut o = entity(v, CIRCLE, 0x31, 0x10, Some(0x20));
+        o.data.bd3(Point3::new(1.0, 2.0, 0.0));
+        o.data.bd(1.5);
+        o.data.b(true);
+        o.data.b(true);
+        objects.push((0x31, o.finish()));
+
+        let mut o = entity(v, LWPOLYLINE, 0x32, 0x11, None);
+        let d = &mut o.data;
+        d.bs(512 | 16 | 8);
+        d.bd(10.0);
+        d.bl(3);
+        d.bl(1);
+        d.rd(0.0);
+        d.rd(0.0);
+        d.dd(20.0, 0.0);
+        d.dd(0.0, 0.0);
+        d.dd(20.0, 20.0);
+        d.dd(15.0, 0.0);
+        d.bd(0.5);
+        objects.push((0x32, o.finish()));
+
+        let mut o = entity(v, TEXT, 0x33, 0x10, None);
+        text_fields(&mut o, Point3::new(5.0, 6.0, 1.0), 2.5, 0.5, "STA 0+000");
+        objects.push((0x33, o.finish()));
+
+        let mut o = entity(v, INSERT, 0x34, 0x10, None);
+        let d = &mut o.data;
+        d.bd3(Point3::new(100.0, 200.0, 0.0));
+        d.bb(2);
+        d.rd(2.0);
+        d.bd(0.25);
+        d.bd3(Point3::new(0.0, 0.0, 1.0));
+        d.b(true);
+        if v >= Version::R2004 {
+            d.bl(1);
+        }
+        o.handles.handle(5, 0x20);
+        objects.push((0x34, o.finish()));
+
+        let mut o = entity(v, ATTRIB, 0x35, 0x10, Some(0x34));
+        text_fields(&mut o, Point3::new(100.0, 199.0, 0.0), 0.5, 0.25, "Oak");
+        if v >= Version::R2010 {
+            o.data.rc(0);
+        }
+        if v >= Version::R2018 {
+            o.data.rc(1);
+        }
+        o.text("SPECIES");
+        o.data.bs(0);
+        o.data.rc(0);
+        objects.push((0x35, o.finish()));
+
+        let mut o = entity(v, POLYLINE_3D, 0x36, 0x11, None);
+        o.data.rc(0);
+        o.data.rc(0);
+        objects.push((0x36, o.finish()));
+        for (handle, z) in [(0x37, 10.0), (0x38, 11.0)] {
+            let mut o = entity(v, VERTEX_3D, handle, 0x11, Some(0x36));
+            o.data.rc(32);
+            o.data.bd3(Point3::new(z - 10.0, 1.0, z));
+            objects.push((handle, o.finish()));
+        }
+
+        // object of a custom class, which is skipped
+        objects.push((0x40, ObjectWriter::new(v, 500, 0x40).finish()));
+        objects
+    }
+
+    fn object_map_bytes(entries: &[(u64, usize)]) -> Vec<u8> {
+        let mut data = Vec::new();
+        let (mut handle, mut offset) = (0, 0);
+        for &(h, o) in entries {
+            modular(&mut data, (h - handle) as i64, false);
+            modular(&mut data, o as i64 - offset as i64, true);
+            (handle, offset) = (h, o);
+        }
+        let mut out = ((data.len() + 2) as u16).to_be_bytes().to_vec();
+        out.extend(data);
+        out.extend([0, 0, 0, 2, 0, 0]);
+        out
+    }
+
+    fn r2000_file(objects: &[(u64, Vec<u8>)]) -> Vec<u8> {
+        let mut out = b"AC1015".to_vec();
+        out.resize(0x15, 0);
+        out.extend(1u32.to_le_bytes());
+        out.extend([2; 9]);
+        out.resize(out.len() + 18, 0);
+        let mut entries = Vec::new();
+        for (handle, bytes) in objects {
+            entries.push((*handle, out.len()));
+            out.extend(bytes);
+        }
+        let map = object_map_bytes(&entries);
+        let seeker = out.len() as u32;
+        out[0x1A..0x1E].copy_from_slice(&seeker.to_le_bytes());
+        out[0x1E..0x22].copy_from_slice(&(map.len() as u32).to_le_bytes());
+        out.extend(map);
+        out
+    }
+
+    /// Stores `data` as a single literal run.
+    fn compress(data: &[u8]) -> Vec<u8> {
+        assert!(data.len() >= 4);
+        let mut out = Vec::new();
+        if data.len() <= 18 {
+            out.push(data.len() as u8 - 3);
+        } else {
+            out.push(0);
+            let mut rest = data.len() - 18;
+            while rest > 255 {
+                out.push(0);
+                rest -= 255;
+            }
+            out.push(rest as u8);
+        }
+        out.extend(data);
+        out.push(0x11);
+        out
+    }
+
+    fn system_page(out: &mut Vec<u8>, kind: u32, data: &[u8]) -> usize {
+        let start = out.len();
+        let packed = compress(data);
+        for v in [kind, data.len() as u32, packed.len() as u32, 2, 0] {
+            out.extend(v.to_le_bytes());
+        }
+        out.extend(packed);
+        out.len() - start
+    }
+
+    fn data_page(out: &mut Vec<u8>, data: &[u8]) -> (usize, usize) {
+        let address = out.len();
+        let packed = compress(data);
+        let mask = 0x4164_536B ^ address as u32;
+        for v in [
+            0x4163_043B,
+            1,
+            packed.len() as u32,
+            data.len() as u32,
+            0,
+            0,
+            0,
+            0,
+        ] {
+            out.extend((v ^ mask).to_le_bytes());
+        }
+        out.extend(&packed);
+        (out.len() - address, packed.len())
+    }
+
+    fn r2004_file(tag: &[u8], objects: &[(u64, Vec<u8>)]) -> Vec<u8> {
+        let mut section = 0x0DCAu32.to_le_bytes().to_vec();
+        let mut entries = Vec::new();
+        for (handle, bytes) in objects {
+            entries.push((*handle, section.len()));
+            section.extend(bytes);
+        }
+        let map = object_map_bytes(&entries);
+
+        let mut out = tag.to_vec();
+        out.resize(0x100, 0);
+        let (objects_page, objects_packed) = data_page(&mut out, &section);
+        let (handles_page, handles_packed) = data_page(&mut out, &map);
+
+        let mut sections = Vec::new();
+        for v in [2u32, 2, 0x7400, 0, 2] {
+            sections.extend(v.to_le_bytes());
+        }
+        for (id, name, size, page, packed) in [
+            (
+                1u32,
+                "AcDb:AcDbObjects",
+                section.len(),
+                1u32,
+                objects_packed,
+            ),
+            (2, "AcDb:Handles", map.len(), 2, handles_packed),
+        ] {
+            sections.extend((size as u64).to_le_bytes());
+            for v in [1u32, 0x7400, 1, 2, id, 0] {
+                sections.extend(v.to_le_bytes());
+            }
+            let mut padded = name.as_bytes().to_vec();
+            padded.resize(64, 0);
+            sections.extend(padded);
+            sections.extend(page.to_le_bytes());
+            sections.extend((packed as u32).to_le_bytes());
+            sections.extend(0u64.to_le_bytes());
+        }
+        let section_map_page = system_page(&mut out, 0x4163_003B, &sections);
+
+        let page_map_address = out.len();
+        let page_map_size = 20 + compress(&[0; 32]).len();
+        let mut pages = Vec::new();
+        for (number, size) in [
+            (1u32, objects_page),
+            (2, handles_page),
+            (3, section_map_page),
+            (4, page_map_size),
+        ] {
+            pages.extend(number.to_le_bytes());
+            pages.extend((size as u32).to_le_bytes());
+        }
+        system_page(&mut out, 0x4163_0E3B, &pages);
+
+        let mut header = vec![0; 0x6C];
+        header[..11].copy_from_slice(b"AcFssFcAJMB");
+        header[0x50..0x54].copy_from_slice(&4u32.to_le_bytes());
+        header[0x54..0x5C].copy_from_slice(&(page_map_address as u64 - 0x100).to_le_bytes());
+        header[0x5C..0x60].copy_from_slice(&3u32.to_le_bytes());
+        decrypt_header(&mut header);
+        out[0x80..0x80 + 0x6C].copy_from_slice(&header);
+        out
+    }
+
+    fn props(layer: &str, handle: &str) -> DxfProperties {
+        let mut props = DxfProperties::on_layer(layer);
+        props.handle = Some(handle.into());
+        props
+    }
+
+    #[test]
+    fn reads_drawings_from_r2000_to_r2018() {
+        for (version, tag) in [
+            (Version::R2000, b"AC1015"),
+            (Version::R2004, b"AC1018"),
+            (Version::R2010, b"AC1024"),
+            (Version::R2013, b"AC1027"),
+            (Version::R2018, b"AC1032"),
+        ] {
+            let objects = drawing_objects(version);
+            let data = if version == Version::R2000 {
+                r2000_file(&objects)
+            } else {
+                r2004_file(tag, &objects)
+            };
+            let doc = parse_dwg(&data).unwrap();
+            assert_eq!(doc.version().as_bytes(), tag);
+
+            assert_eq!(doc.layers.len(), 2);
+            assert_eq!(doc.layers[0].lineweight, Some(-3));
+            let eop = doc.layer("EOP").unwrap();
+            assert!(eop.is_off());
+            assert_eq!(eop.color, -3);
+            assert_eq!(eop.linetype, "DASHED");
+            assert_eq!(eop.handle.as_deref(), Some("11"));
+            assert_eq!(doc.linetype("DASHED").unwrap().pattern_length(), 0.75);
+
+            let tree = doc.block("TREE").unwrap();
+            assert_eq!(tree.base_point, Point3::new(1.0, 2.0, 0.0));
+            assert_eq!(
+                tree.entities,
+                vec![DxfEntity::Circle {
+                    center: Point3::new(1.0, 2.0, 0.0),
+                    radius: 1.5,
+                    props: props("0", "31"),
+                }]
+            );
+
+            let expected = vec![
+                DxfEntity::Line3D {
+                    start: Point3::new(1.0, 2.0, 3.0),
+                    end: Point3::new(4.0, 2.0, 6.0),
+                    props: props("EOP", "30"),
+                },
+                DxfEntity::LwPolyline {
+                    vertices: vec![
+                        DxfVertex::new(Point3::new(0.0, 0.0, 10.0), 0.5),
+                        DxfVertex::new(Point3::new(20.0, 0.0, 10.0), 0.0),
+                        DxfVertex::new(Point3::new(20.0, 15.0, 10.0), 0.0),
+                    ],
+                    closed: true,
+                    elevation: 10.0,
+                    props: props("EOP", "32"),
+                },
+                DxfEntity::Text3D {
+                    position: Point3::new(5.0, 6.0, 1.0),
+                    height: 2.5,
+                    rotation: 0.5,
+                    value: "STA 0+000".into(),
+                    props: props("0", "33"),
+                },
+                DxfEntity::Insert {
+                    block: "TREE".into(),
+                    position: Point3::new(100.0, 200.0, 0.0),
+                    scale: [2.0; 3],
+                    rotation: 0.25,
+                    attributes: vec![DxfAttribute {
+                        tag: "SPECIES".into(),
+                        value: "Oak".into(),
+                        position: Point3::new(100.0, 199.0, 0.0),
+                        height: 0.5,
+                        rotation: 0.25,
+                        props: props("0", "35"),
+                    }],
+                    props: props("0", "34"),
+                },
+                DxfEntity::Polyline3D {
+                    vertices: vec![
+                        DxfVertex::new(Point3::new(0.0, 1.0, 10.0), 0.0),
+                        DxfVertex::new(Point3::new(1.0, 1.0, 11.0), 0.0),
+                    ],
+                    closed: false,
+                    props: props("EOP", "36"),
+                },
+            ];
+            assert_eq!(doc.entities, expected, "{tag:?}");
+        }
+    }
+
+    #[test]
+    fn reports_objects_that_fail_to_decode() {
+        for (version, tag) in [(Version::R2000, b"AC1015"), (Version::R2018, b"AC1032")] {
+            let mut objects = drawing_objects(version);
+            // a polyline claiming far more vertices than it holds
+            let mut o = entity(version, LWPOLYLINE, 0x41, 0x11, None);
+            o.data.bs(0);
+            o.data.bl(100_000);
+            objects.push((0x41, o.finish()));
+            let data = if version == Version::R2000 {
+                r2000_file(&objects)
+            } else {
+                r2004_file(tag, &objects)
+            };
+            let report = parse_dwg_report(&data).unwrap();
+            assert_eq!(report.skipped, vec![0x41], "{tag:?}");
+            assert_eq!(report.document.entities.len(), 5);
+            assert_eq!(parse_dwg(&data).unwrap(), report.document);
+        }
+    }
+
+    #[test]
+    fn decompresses_back_references() {
+        let src = [
+            1, b'a', b'b', b'c', b'd', 0x22, 0x0C, 0, 0x45, 0, b'z', 0x11,
+        ];
+        assert_eq!(decompress(&src, 64).unwrap(), b"abcdabcdcdcz");
+        assert!(decompress(&[0x22, 0x0C, 0, 0x11], 64).is_err());
+    }
+
+    #[test]
+    fn rejects_unsupported_and_damaged_files() {
+        let mut r2007 = b"AC1021".to_vec();
+        r2007.resize(0x100, 0);
+        let err = parse_dwg(&r2007).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().contains("R2007"));
+        assert!(parse_dwg(b"AC1009 R12").is_err());
+        assert!(needs_converter(&r2007) && needs_converter(b"AC1009 R12"));
+        assert!(!needs_converter(b"AC1032") && !needs_converter(b"not a drawing"));
+        let path = std::env::temp_dir().join("r2007_drawing.dwg");
+        std::fs::write(&path, &r2007).unwrap();
+        let err = read_dwg(path.to_str().unwrap()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::Other);
+        assert!(err.to_string().contains("dwg2dxf"));
+        std::fs::remove_file(path).ok();
+
+        let data = r2004_file(b"AC1018", &drawing_objects(Version::R2004));
+        assert!(parse_dwg(&data[..0x200]).is_err());
+        let mut scrambled = data.clone();
+        scrambled[0x80] ^= 0xFF;
+        assert!(parse_dwg(&scrambled).is_err());
+    }
+}
@@ -0,0 +1,2430 @@
+//! DXF reader and writer working on group code pairs.
+//!
+//! Files are read into a [`DxfDocument`] holding the header variables, the
+//! layer and linetype tables, block definitions and the entities of model
+//! space. ASCII files from R12 to R2018 are supported as well as binary DXF.
+//! Entities and table records keep their handles, colours and any group codes
+//! that aren't interpreted (extended data, reactors, sub-entity options) so a
+//! file exported from Civil 3D or MicroStation can be written back without
+//! loss. Sections and tables that aren't modelled are kept verbatim.
+
+use std::fmt::Display;
+use std::io;
+
+use crate::geometry::edit::PathSegment;
+use crate::geometry::{Arc, Line, Point, Point3, Polyline, Polyline3};
+use crate::parcel::Parcel;
+
+/// A single DXF group code and its value as written in an ASCII file.
+pub type DxfPair = (i32, String);
+
+const BINARY_SENTINEL: &[u8] = b"AutoCAD Binary DXF\r\n\x1a\0";
+
+/// Common properties carried by DXF entities and table records.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DxfProperties {
+    pub layer: String,
+    /// AutoCAD colour index. `None` or `256` is BYLAYER, `0` BYBLOCK.
+    pub color: Option<i16>,
+    /// 24-bit true colour (group code 420) as `0xRRGGBB`.
+    pub true_color: Option<i32>,
+    pub linetype: Option<String>,
+    /// Lineweight in hundredths of a millimetre (negative for BYLAYER etc.).
+    pub lineweight: Option<i16>,
+    pub handle: Option<String>,
+    /// Group codes that aren't interpreted, including reactors, owner
+    /// handles and extended entity data, in the order they were read.
+    pub extra: Vec<DxfPair>,
+}
+
+impl Default for DxfProperties {
+    fn default() -> Self {
+        Self::on_layer("0")
+    }
+}
+
+impl DxfProperties {
+    /// Properties with every value BYLAYER on the given layer.
+    pub fn on_layer(layer: &str) -> Self {
+        Self {
+            layer: layer.to_string(),
+            color: None,
+            true_color: None,
+            linetype: None,
+            lineweight: None,
+            handle: None,
+            extra: Vec::new(),
+        }
+    }
+
+    /// Returns `true` if only the layer is set.
+    pub fn is_plain(&self) -> bool {
+        self.color.is_none()
+            && self.true_color.is_none()
+            && self.linetype.is_none()
+            && self.lineweight.is_none()
+            && self.handle.is_none()
+            && self.extra.is_empty()
+    }
+
+    fn simple_layer(&self) -> Option<String> {
+        (self.layer != "0").then(|| self.layer.clone())
+    }
+}
+
+/// Vertex of a lightweight or 3D polyline.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DxfVertex {
+    pub point: Point3,
+    /// Tangent of a quarter of the included angle of the arc to the next
+    /// vertex. Positive bulges turn counter-clockwise.
+    pub bulge: f64,
+    pub start_width: f64,
+    pub end_width: f64,
+}
+
+impl DxfVertex {
+    pub fn new(point: Point3, bulge: f64) -> Self {
+        Self {
+            point,
+            bulge,
+            start_width: 0.0,
+            end_width: 0.0,
+        }
+    }
+}
+
+/// Attribute attached to a block insert.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DxfAttribute {
+    pub tag: String,
+    pub value: String,
+    pub position: Point3,
+    pub height: f64,
+    /// Rotation in radians.
+    pub rotation: f64,
+    pub props: DxfProperties,
+}
+
+/// DXF entity types supported by the reader and writer.
+///
+/// The first five variants are the simple 2D entities used throughout the
+/// crate. The reader returns them when nothing but the layer would be lost;
+/// anything with an elevation, colour, handle or uninterpreted data comes
+/// back as one of the richer variants.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DxfEntity {
+    Point {
+        point: Point,
+        layer: Option<String>,
+    },
+    Line {
+        line: Line,
+        layer: Option<String>,
+    },
+    Polyline {
+        polyline: Polyline,
+        layer: Option<String>,
+    },
+    Arc {
+        arc: Arc,
+        layer: Option<String>,
+    },
+    Text {
+        position: Point,
+        height: f64,
+        value: String,
+        layer: Option<String>,
+    },
+    Point3D {
+        point: Point3,
+        props: DxfProperties,
+    },
+    Line3D {
+        start: Point3,
+        end: Point3,
+        props: DxfProperties,
+    },
+    Circle {
+        center: Point3,
+        radius: f64,
+        props: DxfProperties,
+    },
+    Arc3D {
+        arc: Arc,
+        elevation: f64,
+        props: DxfProperties,
+    },
+    /// `LWPOLYLINE`, also used for 2D `POLYLINE` entities with bulges,
+    /// widths or an elevation.
+    LwPolyline {
+        vertices: Vec<DxfVertex>,
+        closed: bool,
+        elevation: f64,
+        props: DxfProperties,
+    },
+    /// 3D `POLYLINE` with `VERTEX` children.
+    Polyline3D {
+        vertices: Vec<DxfVertex>,
+        closed: bool,
+        props: DxfProperties,
+    },
+    Face3D {
+        corners: [Point3; 4],
+        props: DxfProperties,
+    },
+    Text3D {
+        position: Point3,
+        height: f64,
+        /// Rotation in radians.
+        rotation: f64,
+        value: String,
+        props: DxfProperties,
+    },
+    MText {
+        position: Point3,
+        height: f64,
+        /// Reference rectangle width, `0` for no wrapping.
+        width: f64,
+        /// Rotation in radians.
+        rotation: f64,
+        value: String,
+        props: DxfProperties,
+    },
+    Insert {
+        block: String,
+        position: Point3,
+        scale: [f64; 3],
+        /// Rotation in radians.
+        rotation: f64,
+        attributes: Vec<DxfAttribute>,
+        props: DxfProperties,
+    },
+    /// `ATTDEF` inside a block definition, giving the tag, prompt, default
+    /// value and placement of an attribute of each insert.
+    AttributeDefinition {
+        tag: String,
+        prompt: String,
+        default: String,
+        position: Point3,
+        height: f64,
+        /// Rotation in radians.
+        rotation: f64,
+        props: DxfProperties,
+    },
+    /// Entity that isn't interpreted, kept as its raw group codes. Any
+    /// `0` codes in `codes` start the sub-entities that followed it.
+    Other {
+        kind: String,
+        codes: Vec<DxfPair>,
+    },
+}
+
+impl DxfEntity {
+    /// Layer the entity is drawn on.
+    pub fn layer(&self) -> &str {
+        match self {
+            DxfEntity::Point { layer, .. }
+            | DxfEntity::Line { layer, .. }
+            | DxfEntity::Polyline { layer, .. }
+            | DxfEntity::Arc { layer, .. }
+            | DxfEntity::Text { layer, .. } => layer.as_deref().unwrap_or("0"),
+            DxfEntity::Other { codes, .. } => codes
+                .iter()
+                .find(|(c, _)| *c == 8)
+                .map(|(_, v)| v.as_str())
+                .unwrap_or("0"),
+            _ => &self.props().unwrap().layer,
+        }
+    }
+
+    /// Returns the equivalent simple 2D variant of a point, line, arc or
+    /// text when nothing but the layer would be lost.
+    pub(crate) fn simplified(self) -> DxfEntity {
+        match self {
+            DxfEntity::Point3D { point, props } if props.is_plain() && point.z == 0.0 => {
+                DxfEntity::Point {
+                    point: Point::new(point.x, point.y),
+                    layer: props.simple_layer(),
+                }
+            }
+            DxfEntity::Line3D { start, end, props }
+                if props.is_plain() && start.z == 0.0 && end.z == 0.0 =>
+            {
+                DxfEntity::Line {
+                    line: Line::new(Point::new(start.x, start.y), Point::new(end.x, end.y)),
+                    layer: props.simple_layer(),
+                }
+            }
+            DxfEntity::Arc3D {
+                arc,
+                elevation,
+                props,
+            } if props.is_plain() && elevation == 0.0 => DxfEntity::Arc {
+                arc,
+                layer: props.simple_layer(),
+            },
+            DxfEntity::Text3D {
+                position,
+                height,
+                rotation,
+                value,
+                props,
+            } if props.is_plain() && position.z == 0.0 && rotation == 0.0 => DxfEntity::Text {
+                position: Point::new(position.x, position.y),
+                height,
+                value,
+                layer: props.simple_layer(),
+            },
+            e => e,
+        }
+    }
+
+    /// Properties of the richer entity variants.
+    pub fn props(&self) -> Option<&DxfProperties> {
+        match self {
+            DxfEntity::Point3D { props, .. }
+            | DxfEntity::Line3D { props, .. }
+            | DxfEntity::Circle { props, .. }
+            | DxfEntity::Arc3D { props, .. }
+            | DxfEntity::LwPolyline { props, .. }
+            | DxfEntity::Polyline3D { props, .. }
+            | DxfEntity::Face3D { props, .. }
+            | DxfEntity::Text3D { props, .. }
+            | DxfEntity::MText { props, .. }
+            | DxfEntity::Insert { props, .. }
+            | DxfEntity::AttributeDefinition { props, .. } => Some(props),
+            _ => None,
+        }
+    }
+
+    /// Line, arc or polyline as a [`Polyline3`], keeping its elevations
+    /// through the edits in [`crate::geometry::edit`].
+    pub fn path(&self) -> Option<Polyline3> {
+        let lift = |p: Point, z: f64| Point3::new(p.x, p.y, z);
+        match self {
+            DxfEntity::Line { line, .. } => Some(Polyline3::new(vec![
+                lift(line.start, 0.0),
+                lift(line.end, 0.0),
+            ])),
+            DxfEntity::Line3D { start, end, .. } => Some(Polyline3::new(vec![*start, *end])),
+            DxfEntity::Arc { arc, .. } => Some(arc_path(arc, 0.0)),
+            DxfEntity::Arc3D { arc, elevation, .. } => Some(arc_path(arc, *elevation)),
+            _ => self.polyline3(),
+        }
+    }
+
+    /// Line, arc or polyline drawing `path` with `props`: a single straight
+    /// segment becomes a line, a single level arc an arc and anything else
+    /// a polyline as in [`DxfEntity::from_polyline3`].
+    pub fn from_path(path: &Polyline3, props: DxfProperties) -> DxfEntity {
+        match path.vertices[..] {
+            [start, end] if !path.closed && path.bulge(0) == 0.0 => {
+                DxfEntity::Line3D { start, end, props }
+            }
+            [start, end] if !path.closed && start.z == end.z => DxfEntity::Arc3D {
+                arc: path.segment(0).to_arc().unwrap(),
+                elevation: start.z,
+                props,
+            },
+            _ => DxfEntity::from_polyline3(path, props),
+        }
+        .simplified()
+    }
+
+    /// Polyline with elevations and bulges from a 2D, lightweight or 3D
+    /// polyline.
+    pub fn polyline3(&self) -> Option<Polyline3> {
+        match self {
+            DxfEntity::Polyline { polyline, .. } => Some(Polyline3::from(polyline)),
+            DxfEntity::LwPolyline {
+                vertices,
+                closed,
+                elevation,
+                ..
+            } => Some(Polyline3 {
+                vertices: vertices
+                    .iter()
+                    .map(|v| Point3::new(v.point.x, v.point.y, *elevation))
+                    .collect(),
+                bulges: vertices.iter().map(|v| v.bulge).collect(),
+                closed: *closed,
+            }),
+            DxfEntity::Polyline3D {
+                vertices, closed, ..
+            } => Some(Polyline3 {
+                vertices: vertices.iter().map(|v| v.point).collect(),
+                bulges: vertices.iter().map(|v| v.bulge).collect(),
+                closed: *closed,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Lightweight polyline when `pl` is level and a 3D polyline otherwise.
+    /// 3D polylines can't hold arcs, so those are split into chords of at
+    /// most one degree.
+    pub fn from_polyline3(pl: &Polyline3, props: DxfProperties) -> DxfEntity {
+        let elevation = pl.vertices.first().map_or(0.0, |v| v.z);
+        if pl.vertices.iter().all(|v| v.z == elevation) {
+            return DxfEntity::LwPolyline {
+                vertices: pl
+                    .vertices
+                    .iter()
+                    .enumerate()
+                    .map(|(i, v)| DxfVertex::new(Point3::new(v.x, v.y, 0.0), pl.bulge(i)))
+                    .collect(),
+                closed: pl.closed,
+                elevation,
+                props,
+            };
+        }
+        let vertices = if pl.has_arcs() {
+            pl.densify(1f64.to_radians())
+        } else {
+            pl.vertices.clone()
+        };
+        DxfEntity::Polyline3D {
+            vertices: vertices
+                .into_iter()
+                .map(|v| DxfVertex::new(v, 0.0))
+                .collect(),
+            closed: pl.closed,
+            props,
+        }
+    }
+
+    /// Properties of the entity, built from the layer for the simple 2D
+    /// variants.
+    pub fn properties(&self) -> DxfProperties {
+        self.props()
+            .cloned()
+            .unwrap_or_else(|| DxfProperties::on_layer(self.layer()))
+    }
+}
+
+/// Two-vertex polyline tracing `arc` at `elevation`.
+fn arc_path(arc: &Arc, elevation: f64) -> Polyline3 {
+    let segment = PathSegment::from_arc(arc);
+    let lift = |p: Point| Point3::new(p.x, p.y, elevation);
+    Polyline3::with_bulges(
+        vec![lift(segment.start_point()), lift(segment.end_point())],
+        vec![segment.bulge()],
+    )
+}
+
+/// Header variable such as `$ACADVER` with its group codes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DxfHeaderVariable {
+    pub name: String,
+    pub values: Vec<DxfPair>,
+}
+
+/// Entry of the `LAYER` table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DxfLayer {
+    pub name: String,
+    /// Colour index, negative when the layer is off.
+    pub color: i16,
+    pub true_color: Option<i32>,
+    pub linetype: String,
+    /// Standard flags: 1 frozen, 4 locked.
+    pub flags: i16,
+    pub lineweight: Option<i16>,
+    pub handle: Option<String>,
+    pub extra: Vec<DxfPair>,
+}
+
+impl DxfLayer {
+    pub fn new(name: &str, color: i16) -> Self {
+        Self {
+            name: name.to_string(),
+            color,
+            true_color: None,
+            linetype: "CONTINUOUS".into(),
+            flags: 0,
+            lineweight: None,
+            handle: None,
+            extra: Vec::new(),
+        }
+    }
+
+    pub fn is_off(&self) -> bool {
+        self.color < 0
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.flags & 1 != 0
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.flags & 4 != 0
+    }
+}
+
+/// Element of a linetype pattern. Positive lengths are dashes, negative
+/// lengths gaps and zero a dot. Shape and text options of complex
+/// linetypes are kept in `codes`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DxfDash {
+    pub length: f64,
+    pub codes: Vec<DxfPair>,
+}
+
+/// Entry of the `LTYPE` table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DxfLinetype {
+    pub name: String,
+    pub description: String,
+    pub flags: i16,
+    pub pattern: Vec<DxfDash>,
+    pub handle: Option<String>,
+    pub extra: Vec<DxfPair>,
+}
+
+impl DxfLinetype {
+    pub fn new(name: &str, description: &str, pattern: &[f64]) -> Self {
+        Self {
+            name: name.to_string(),
+            description: description.to_string(),
+            flags: 0,
+            pattern: pattern
+                .iter()
+                .map(|&length| DxfDash {
+                    length,
+                    codes: Vec::new(),
+                })
+                .collect(),
+            handle: None,
+            extra: Vec::new(),
+        }
+    }
+
+    /// Length of one repetition of the pattern.
+    pub fn pattern_length(&self) -> f64 {
+        self.pattern.iter().map(|d| d.length.abs()).sum()
+    }
+}
+
+/// Table kept as raw group codes. For the `LAYER` and `LTYPE` tables
+/// `entries` is empty and the document's parsed records are written instead.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DxfTable {
+    pub name: String,
+    /// Codes of the `TABLE` record after the name, excluding the entry count.
+    pub codes: Vec<DxfPair>,
+    pub entries: Vec<DxfPair>,
+}
+
+impl DxfTable {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            codes: Vec::new(),
+            entries: Vec::new(),
+        }
+    }
+}
+
+/// Block definition from the `BLOCKS` section.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DxfBlock {
+    pub name: String,
+    pub base_point: Point3,
+    pub flags: i16,
+    pub entities: Vec<DxfEntity>,
+    pub props: DxfProperties,
+    /// Properties of the closing `ENDBLK` record.
+    pub end_props: DxfProperties,
+}
+
+impl DxfBlock {
+    pub fn new(name: &str, base_point: Point3, entities: Vec<DxfEntity>) -> Self {
+        Self {
+            name: name.to_string(),
+            base_point,
+            flags: 0,
+            entities,
+            props: DxfProperties::default(),
+            end_props: DxfProperties::default(),
+        }
+    }
+}
+
+/// Section that isn't modelled, such as `CLASSES` or `OBJECTS`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DxfSection {
+    pub name: String,
+    pub codes: Vec<DxfPair>,
+}
+
+/// Contents of a DXF file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DxfDocument {
+    pub header: Vec<DxfHeaderVariable>,
+    /// All tables in file order.
+    pub tables: Vec<DxfTable>,
+    pub layers: Vec<DxfLayer>,
+    pub linetypes: Vec<DxfLinetype>,
+    pub blocks: Vec<DxfBlock>,
+    pub entities: Vec<DxfEntity>,
+    pub sections: Vec<DxfSection>,
+}
+
+impl DxfDocument {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Builds a document for the given entities with a layer table listing
+    /// every layer they use.
+    pub fn from_entities(entities: Vec<DxfEntity>) -> Self {
+        let mut layers = vec![DxfLayer::new("0", 7)];
+        for e in &entities {
+            let name = e.layer();
+            if !layers.iter().any(|l| l.name == name) {
+                layers.push(DxfLayer::new(name, 7));
+            }
+        }
+        Self {
+            tables: vec![DxfTable::new("LTYPE"), DxfTable::new("LAYER")],
+            layers,
+            linetypes: vec![DxfLinetype::new("CONTINUOUS", "Solid line", &[])],
+            entities,
+            ..Default::default()
+        }
+    }
+
+    /// First value of a header variable.
+    pub fn header_value(&self, name: &str) -> Option<&str> {
+        self.header
+            .iter()
+            .find(|v| v.name.eq_ignore_ascii_case(name))
+            .and_then(|v| v.values.first())
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Sets a header variable holding a single value.
+    pub fn set_header(&mut self, name: &str, code: i32, value: &str) {
+        let values = vec![(code, value.to_string())];
+        match self.header.iter_mut().find(|v| v.name == name) {
+            Some(v) => v.values = values,
+            None => self.header.push(DxfHeaderVariable {
+                name: name.to_string(),
+                values,
+            }),
+        }
+    }
+
+    /// AutoCAD version string from `$ACADVER`, `AC1009` (R12) if missing.
+    pub fn version(&self) -> &str {
+        self.header_value("$ACADVER").unwrap_or("AC1009")
+    }
+
+    pub fn layer(&self, name: &str) -> Option<&DxfLayer> {
+        self.layers
+            .iter()
+            .find(|l| l.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn linetype(&self, name: &str) -> Option<&DxfLinetype> {
+        self.linetypes
+            .iter()
+            .find(|l| l.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn block(&self, name: &str) -> Option<&DxfBlock> {
+        self.blocks
+            .iter()
+            .find(|b| b.name.eq_ignore_ascii_case(name))
+    }
+
+    /// Display colour of an entity with BYLAYER resolved through the layer
+    /// table.
+    pub fn rgb(&self, props: &DxfProperties) -> [u8; 3] {
+        if let Some(c) = props.true_color {
+            return true_color_rgb(c);
+        }
+        match props.color {
+            Some(c) if c != 256 && c != 0 => aci_to_rgb(c.unsigned_abs().min(255) as u8),
+            _ => match self.layer(&props.layer) {
+                Some(l) => match l.true_color {
+                    Some(c) => true_color_rgb(c),
+                    None => aci_to_rgb(l.color.unsigned_abs().min(255) as u8),
+                },
+                None => aci_to_rgb(7),
+            },
+        }
+    }
+}
+
+fn true_color_rgb(c: i32) -> [u8; 3] {
+    [(c >> 16) as u8, (c >> 8) as u8, c as u8]
+}
+
+/// RGB value of an AutoCAD colour index using the standard palette.
+pub fn aci_to_rgb(index: u8) -> [u8; 3] {
+    match index {
+        1 => [255, 0, 0],
+        2 => [255, 255, 0],
+        3 => [0, 255, 0],
+        4 => [0, 255, 255],
+        5 => [0, 0, 255],
+        6 => [255, 0, 255],
+        8 => [128, 128, 128],
+        9 => [192, 192, 192],
+        10..=249 => {
+            let i = index - 10;
+            let hue = (i / 10) as f64 * 15.0;
+            let shade = [1.0, 0.65, 0.5, 0.3, 0.15][((i % 10) / 2) as usize];
+            let saturation = [1.0, 0.5][(i % 2) as usize];
+            let (r, g, b) = hsv_to_rgb(hue, saturation, shade);
+            [r, g, b]
+        }
+        250..=255 => {
+            let v = [51, 91, 132, 173, 214, 255][(index - 250) as usize];
+            [v, v, v]
+        }
+        _ => [255, 255, 255],
+    }
+}
+
+fn hsv_to_rgb(h: f64, s: f64, v: f64) -> (u8, u8, u8) {
+    let c = v * s;
+    let x = c * (1.0 - ((h / 60.0) % 2.0 - 1.0).abs());
+    let m = v - c;
+    let (r, g, b) = match (h / 60.0) as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let to = |v: f64| ((v + m) * 255.0).round() as u8;
+    (to(r), to(g), to(b))
+}
+
+pub(crate) fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ValueType {
+    Text,
+    Double,
+    Int16,
+    Int32,
+    Int64,
+    Bool,
+    Binary,
+}
+
+fn value_type(code: i32) -> ValueType {
+    match code {
+        10..=59 | 110..=149 | 210..=239 | 460..=469 | 1010..=1059 => ValueType::Double,
+        60..=79 | 170..=179 | 270..=289 | 370..=389 | 400..=409 | 1060..=1070 => ValueType::Int16,
+        90..=99 | 420..=429 | 440..=459 | 1071 => ValueType::Int32,
+        160..=169 => ValueType::Int64,
+        290..=299 => ValueType::Bool,
+        310..=319 | 1004 => ValueType::Binary,
+        _ => ValueType::Text,
+    }
+}
+
+pub(crate) fn decode_text(bytes: &[u8]) -> String {
+    match std::str::from_utf8(bytes) {
+        Ok(s) => s.to_string(),
+        // pre-2007 files use the drawing code page, read it as Latin-1
+        Err(_) => bytes.iter().map(|&b| b as char).collect(),
+    }
+}
+
+/// Replaces `\U+XXXX` escapes with the characters they stand for.
+pub(crate) fn unescape_unicode(value: &str) -> String {
+    if !value.contains("\\U+") {
+        return value.to_string();
+    }
+    let mut out = String::new();
+    let mut rest = value;
+    while let Some(i) = rest.find("\\U+") {
+        out.push_str(&rest[..i]);
+        let ch = rest
+            .get(i + 3..i + 7)
+            .and_then(|h| u32::from_str_radix(h, 16).ok())
+            .and_then(char::from_u32);
+        match ch {
+            Some(c) => {
+                out.push(c);
+                rest = &rest[i + 7..];
+            }
+            None => {
+                out.push_str("\\U+");
+                rest = &rest[i + 3..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn ascii_pairs(text: &str) -> io::Result<Vec<DxfPair>> {
+    let mut lines = text.lines().enumerate();
+    let mut pairs = Vec::new();
+    while let Some((n, code)) = lines.next() {
+        let code = code.trim();
+        if code.is_empty() {
+            continue;
+        }
+        let code: i32 = code
+            .parse()
+            .map_err(|_| invalid(format!("line {}: invalid group code `{code}`", n + 1)))?;
+        let (_, value) = lines
+            .next()
+            .ok_or_else(|| invalid(format!("line {}: missing value", n + 2)))?;
+        let value = value.trim_end_matches('\r');
+        let value = match value_type(code) {
+            ValueType::Text if matches!(code, 1 | 3) => unescape_unicode(value),
+            ValueType::Text => unescape_unicode(value.trim()),
+            // formatted the way the binary reader formats doubles
+            ValueType::Double => match value.trim().parse::<f64>() {
+                Ok(d) => d.to_string(),
+                Err(_) => value.trim().to_string(),
+            },
+            _ => value.trim().to_string(),
+        };
+        let eof = code == 0 && value == "EOF";
+        pairs.push((code, value));
+        if eof {
+            break;
+        }
+    }
+    Ok(pairs)
+}
+
+struct BinaryReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl BinaryReader<'_> {
+    fn take(&mut self, n: usize) -> io::Result<&[u8]> {
+        if self.pos + n > self.data.len() {
+            return Err(invalid(format!(
+                "byte {}: unexpected end of file",
+                self.pos
+            )));
+        }
+        let s = &self.data[self.pos..self.pos + n];
+        self.pos += n;
+        Ok(s)
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn i16(&mut self) -> io::Result<i16> {
+        Ok(i16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+
+    fn string(&mut self) -> io::Result<String> {
+        let rest = &self.data[self.pos..];
+        let end = rest
+            .iter()
+            .position(|&b| b == 0)
+            .ok_or_else(|| invalid(format!("byte {}: unterminated string", self.pos)))?;
+        let s = unescape_unicode(&decode_text(&rest[..end]));
+        self.pos += end + 1;
+        Ok(s)
+    }
+}
+
+fn binary_pairs(data: &[u8]) -> io::Result<Vec<DxfPair>> {
+    let mut r = BinaryReader {
+        data,
+        pos: BINARY_SENTINEL.len(),
+    };
+    // R12 binary files use single byte group codes
+    let short_codes = data.get(r.pos + 1) == Some(&b'S');
+    let mut pairs = Vec::new();
+    while r.pos < data.len() {
+        let code = if short_codes {
+            match r.u8()? {
+                255 => r.i16()? as i32,
+                c => c as i32,
+            }
+        } else {
+            r.i16()? as i32
+        };
+        let value = match value_type(code) {
+            ValueType::Text => r.string()?,
+            ValueType::Double => f64::from_le_bytes(r.take(8)?.try_into().unwrap()).to_string(),
+            ValueType::Int16 => r.i16()?.to_string(),
+            ValueType::Int32 => i32::from_le_bytes(r.take(4)?.try_into().unwrap()).to_string(),
+            ValueType::Int64 => i64::from_le_bytes(r.take(8)?.try_into().unwrap()).to_string(),
+            ValueType::Bool => r.u8()?.to_string(),
+            ValueType::Binary => {
+                let n = r.u8()? as usize;
+                r.take(n)?.iter().map(|b| format!("{b:02X}")).collect()
+            }
+        };
+        let eof = code == 0 && value == "EOF";
+        pairs.push((code, value));
+        if eof {
+            break;
+        }
+    }
+    Ok(pairs)
+}
+
+/// Encodes text for the file version, using `\U+XXXX` escapes for
+/// characters outside Latin-1 in files older than R2007.
+fn encode_text(value: &str, unicode: bool, out: &mut Vec<u8>) {
+    if unicode {
+        out.extend_from_slice(value.as_bytes());
+        return;
+    }
+    for ch in value.chars() {
+        let c = ch as u32;
+        if c <= 0xFF {
+            out.push(c as u8);
+        } else {
+            out.extend_from_slice(format!("\\U+{c:04X}").as_bytes());
+        }
+    }
+}
+
+fn encode_ascii(pairs: &[DxfPair], unicode: bool) -> Vec<u8> {
+    let mut out = Vec::new();
+    for (code, value) in pairs {
+        out.extend_from_slice(format!("{code:>3}\n").as_bytes());
+        encode_text(value, unicode, &mut out);
+        out.push(b'\n');
+    }
+    out
+}
+
+fn encode_binary(pairs: &[DxfPair], unicode: bool) -> io::Result<Vec<u8>> {
+    let bad =
+        |code: i32, value: &str| invalid(format!("invalid value `{value}` for group code {code}"));
+    let mut out = BINARY_SENTINEL.to_vec();
+    for (code, value) in pairs {
+        out.extend_from_slice(&(*code as i16).to_le_bytes());
+        let v = value.trim();
+        match value_type(*code) {
+            ValueType::Text => {
+                encode_text(value, unicode, &mut out);
+                out.push(0);
+            }
+            ValueType::Double => {
+                let d: f64 = v.parse().map_err(|_| bad(*code, value))?;
+                out.extend_from_slice(&d.to_le_bytes());
+            }
+            ValueType::Int16 => {
+                let i: i16 = v.parse().map_err(|_| bad(*code, value))?;
+                out.extend_from_slice(&i.to_le_bytes());
+            }
+            ValueType::Int32 => {
+                let i: i32 = v.parse().map_err(|_| bad(*code, value))?;
+                out.extend_from_slice(&i.to_le_bytes());
+            }
+            ValueType::Int64 => {
+                let i: i64 = v.parse().map_err(|_| bad(*code, value))?;
+                out.extend_from_slice(&i.to_le_bytes());
+            }
+            ValueType::Bool => {
+                let i: u8 = v.parse().map_err(|_| bad(*code, value))?;
+                out.push(i);
+            }
+            ValueType::Binary => {
+                if v.len() % 2 != 0 || v.len() > 510 {
+                    return Err(bad(*code, value));
+                }
+                let bytes = (0..v.len())
+                    .step_by(2)
+                    .map(|i| u8::from_str_radix(&v[i..i + 2], 16))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| bad(*code, value))?;
+                out.push(bytes.len() as u8);
+                out.extend_from_slice(&bytes);
+            }
+        }
+    }
+    Ok(out)
+}
+
+struct Record {
+    kind: String,
+    codes: Vec<DxfPair>,
+}
+
+fn records(pairs: Vec<DxfPair>) -> Vec<Record> {
+    let mut out: Vec<Record> = Vec::new();
+    for (code, value) in pairs {
+        if code == 0 {
+            out.push(Record {
+                kind: value.trim().to_string(),
+                codes: Vec::new(),
+            });
+        } else if let Some(r) = out.last_mut() {
+            r.codes.push((code, value));
+        }
+    }
+    out
+}
+
+fn flatten(records: &[Record]) -> Vec<DxfPair> {
+    let mut out = Vec::new();
+    for r in records {
+        out.push((0, r.kind.clone()));
+        out.extend(r.codes.iter().cloned());
+    }
+    out
+}
+
+fn num<T: std::str::FromStr>(code: i32, value: &str) -> io::Result<T> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| invalid(format!("invalid value `{value}` for group code {code}")))
+}
+
+/// Splits the common properties off an entity or table record. Subclass
+/// markers are dropped since the writer regenerates them; the remaining
+/// codes are returned for the caller to interpret.
+fn split_props(codes: &[DxfPair]) -> io::Result<(DxfProperties, Vec<DxfPair>)> {
+    let mut props = DxfProperties::default();
+    let mut body = Vec::new();
+    let mut in_group = false;
+    let mut xdata = false;
+    for (code, value) in codes {
+        let code = *code;
+        if xdata || code >= 1000 {
+            xdata = true;
+            props.extra.push((code, value.clone()));
+            continue;
+        }
+        if code == 102 {
+            in_group = value.trim_start().starts_with('{');
+            props.extra.push((code, value.clone()));
+            continue;
+        }
+        if in_group {
+            props.extra.push((code, value.clone()));
+            continue;
+        }
+        match code {
+            5 => props.handle = Some(value.clone()),
+            8 => props.layer = value.clone(),
+            6 => props.linetype = Some(value.clone()),
+            62 => props.color = Some(num(code, value)?),
+            420 => props.true_color = Some(num(code, value)?),
+            370 => props.lineweight = Some(num(code, value)?),
+            100 => {}
+            _ => body.push((code, value.clone())),
+        }
+    }
+    Ok((props, body))
+}
+
+/// Numeric values of a record keyed by group code.
+#[derive(Default)]
+struct Coords {
+    values: std::collections::HashMap<i32, f64>,
+}
+
+impl Coords {
+    fn set(&mut self, code: i32, value: &str) -> io::Result<()> {
+        self.values.insert(code, num(code, value)?);
+        Ok(())
+    }
+
+    fn get(&self, code: i32) -> f64 {
+        self.values.get(&code).copied().unwrap_or(0.0)
+    }
+
+    fn point(&self, code: i32) -> Point3 {
+        Point3::new(self.get(code), self.get(code + 10), self.get(code + 20))
+    }
+
+    fn has(&self, code: i32) -> bool {
+        self.values.contains_key(&code)
+    }
+}
+
+/// Interprets the listed numeric codes of `body` and moves everything else
+/// to the entity's extra codes.
+fn collect(body: Vec<DxfPair>, codes: &[i32], props: &mut DxfProperties) -> io::Result<Coords> {
+    let mut c = Coords::default();
+    let mut rest = Vec::new();
+    for (code, value) in body {
+        if codes.contains(&code) && !c.has(code) {
+            c.set(code, &value)?;
+        } else {
+            rest.push((code, value));
+        }
+    }
+    insert_body_extras(props, rest);
+    Ok(c)
+}
+
+/// Puts uninterpreted body codes ahead of any extended data.
+fn insert_body_extras(props: &mut DxfProperties, rest: Vec<DxfPair>) {
+    if rest.is_empty() {
+        return;
+    }
+    let at = props
+        .extra
+        .iter()
+        .position(|(c, _)| *c >= 1000)
+        .unwrap_or(props.extra.len());
+    props.extra.splice(at..at, rest);
+}
+
+fn take_string(body: &mut Vec<DxfPair>, code: i32) -> Option<String> {
+    let i = body.iter().position(|(c, _)| *c == code)?;
+    Some(body.remove(i).1)
+}
+
+fn parse_entities(recs: &[Record]) -> io::Result<Vec<DxfEntity>> {
+    let mut out = Vec::new();
+    let mut i = 0;
+    while i < recs.len() {
+        let r = &recs[i];
+        i += 1;
+        match r.kind.as_str() {
+            "POLYLINE" => {
+                let start = i;
+                while i < recs.len() && recs[i].kind == "VERTEX" {
+                    i += 1;
+                }
+                let vertices = &recs[start..i];
+                let seqend = recs.get(i).filter(|s| s.kind == "SEQEND");
+                if seqend.is_some() {
+                    i += 1;
+                }
+                out.push(parse_polyline(r, vertices, seqend)?);
+            }
+            "INSERT" => {
+                let start = i;
+                while i < recs.len() && recs[i].kind == "ATTRIB" {
+                    i += 1;
+                }
+                let attribs = &recs[start..i];
+                if !attribs.is_empty() && recs.get(i).is_some_and(|s| s.kind == "SEQEND") {
+                    i += 1;
+                }
+                out.push(parse_insert(r, attribs)?);
+            }
+            "VERTEX" | "SEQEND" | "ATTRIB" => {}
+            _ => out.push(parse_entity(r)?),
+        }
+    }
+    Ok(out)
+}
+
+fn parse_entity(r: &Record) -> io::Result<DxfEntity> {
+    let (mut props, mut body) = split_props(&r.codes)?;
+    let entity = match r.kind.as_str() {
+        "POINT" => {
+            let c = collect(body, &[10, 20, 30], &mut props)?;
+            DxfEntity::Point3D {
+                point: c.point(10),
+                props,
+            }
+            .simplified()
+        }
+        "LINE" => {
+            let c = collect(body, &[10, 20, 30, 11, 21, 31], &mut props)?;
+            DxfEntity::Line3D {
+                start: c.point(10),
+                end: c.point(11),
+                props,
+            }
+            .simplified()
+        }
+        "CIRCLE" => {
+            let c = collect(body, &[10, 20, 30, 40], &mut props)?;
+            DxfEntity::Circle {
+                center: c.point(10),
+                radius: c.get(40),
+                props,
+            }
+        }
+        "ARC" => {
+            let c = collect(body, &[10, 20, 30, 40, 50, 51], &mut props)?;
+            let center = c.point(10);
+            let arc = Arc::new(
+                Point::new(center.x, center.y),
+                c.get(40),
+                c.get(50).to_radians(),
+                c.get(51).to_radians(),
+            );
+            DxfEntity::Arc3D {
+                arc,
+                elevation: center.z,
+                props,
+            }
+            .simplified()
+        }
+        "LWPOLYLINE" => {
+            let mut vertices: Vec<DxfVertex> = Vec::new();
+            let mut closed = false;
+            let mut elevation = 0.0;
+            let mut rest = Vec::new();
+            for (code, value) in body {
+                match code {
+                    10 => vertices.push(DxfVertex::new(
+                        Point3::new(num(code, &value)?, 0.0, 0.0),
+                        0.0,
+                    )),
+                    20 | 40 | 41 | 42 if !vertices.is_empty() => {
+                        let v = vertices.last_mut().unwrap();
+                        let n: f64 = num(code, &value)?;
+                        match code {
+                            20 => v.point.y = n,
+                            40 => v.start_width = n,
+                            41 => v.end_width = n,
+                            _ => v.bulge = n,
+                        }
+                    }
+                    70 => closed = num::<i16>(code, &value)? & 1 != 0,
+                    38 => elevation = num(code, &value)?,
+                    90 => {}
+                    _ => rest.push((code, value)),
+                }
+            }
+            for v in &mut vertices {
+                v.point.z = elevation;
+            }
+            insert_body_extras(&mut props, rest);
+            DxfEntity::LwPolyline {
+                vertices,
+                closed,
+                elevation,
+                props,
+            }
+        }
+        "3DFACE" => {
+            let codes: Vec<i32> = (10..=13).flat_map(|c| [c, c + 10, c + 20]).collect();
+            let c = collect(body, &codes, &mut props)?;
+            let mut corners = [c.point(10), c.point(11), c.point(12), c.point(13)];
+            if !c.has(13) {
+                corners[3] = corners[2];
+            }
+            DxfEntity::Face3D { corners, props }
+        }
+        "TEXT" => {
+            let value = take_string(&mut body, 1).unwrap_or_default();
+            let c = collect(body, &[10, 20, 30, 40, 50], &mut props)?;
+            DxfEntity::Text3D {
+                position: c.point(10),
+                height: c.get(40),
+                rotation: c.get(50).to_radians(),
+                value,
+                props,
+            }
+            .simplified()
+        }
+        "MTEXT" => {
+            let mut value = String::new();
+            let mut rest = Vec::new();
+            for (code, v) in body {
+                match code {
+                    3 => value.push_str(&v),
+                    1 => {
+                        value.push_str(&v);
+                    }
+                    _ => rest.push((code, v)),
+                }
+            }
+            let c = collect(rest, &[10, 20, 30, 40, 41, 50], &mut props)?;
+            DxfEntity::MText {
+                position: c.point(10),
+                height: c.get(40),
+                width: c.get(41),
+                rotation: c.get(50).to_radians(),
+                value,
+                props,
+            }
+        }
+        "ATTDEF" => {
+            let default = take_string(&mut body, 1).unwrap_or_default();
+            let tag = take_string(&mut body, 2).unwrap_or_default();
+            let prompt = take_string(&mut body, 3).unwrap_or_default();
+            let c = collect(body, &[10, 20, 30, 40, 50], &mut props)?;
+            DxfEntity::AttributeDefinition {
+                tag,
+                prompt,
+                default,
+                position: c.point(10),
+                height: c.get(40),
+                rotation: c.get(50).to_radians(),
+                props,
+            }
+        }
+        _ => DxfEntity::Other {
+            kind: r.kind.clone(),
+            codes: r.codes.clone(),
+        },
+    };
+    Ok(entity)
+}
+
+fn parse_polyline(
+    r: &Record,
+    vertex_recs: &[Record],
+    seqend: Option<&Record>,
+) -> io::Result<DxfEntity> {
+    let (mut props, body) = split_props(&r.codes)?;
+    let flags = body
+        .iter()
+        .find(|(c, _)| *c == 70)
+        .map(|(c, v)| num::<i16>(*c, v))
+        .transpose()?
+        .unwrap_or(0);
+    // polyface and polygon meshes stay raw
+    if flags & (16 | 64) != 0 {
+        let mut codes = r.codes.clone();
+        codes.extend(flatten(vertex_recs));
+        if let Some(s) = seqend {
+            codes.push((0, s.kind.clone()));
+            codes.extend(s.codes.iter().cloned());
+        }
+        return Ok(DxfEntity::Other {
+            kind: r.kind.clone(),
+            codes,
+        });
+    }
+    let body: Vec<DxfPair> = body
+        .into_iter()
+        .filter(|(c, _)| !matches!(c, 66 | 70 | 10 | 20))
+        .collect();
+    let c = collect(body, &[30], &mut props)?;
+    let elevation = c.get(30);
+    let mut vertices = Vec::new();
+    for v in vertex_recs {
+        let (_, body) = split_props(&v.codes)?;
+        let mut vp = DxfProperties::default();
+        let c = collect(body, &[10, 20, 30, 40, 41, 42], &mut vp)?;
+        vertices.push(DxfVertex {
+            point: c.point(10),
+            bulge: c.get(42),
+            start_width: c.get(40),
+            end_width: c.get(41),
+        });
+    }
+    let closed = flags & 1 != 0;
+    if flags & 8 != 0 {
+        return Ok(DxfEntity::Polyline3D {
+            vertices,
+            closed,
+            props,
+        });
+    }
+    Ok(polyline_2d(vertices, closed, elevation, props))
+}
+
+/// Entity for a 2D `POLYLINE`: the simple [`DxfEntity::Polyline`] when it is
+/// open and flat with straight segments, otherwise an `LwPolyline`.
+pub(crate) fn polyline_2d(
+    mut vertices: Vec<DxfVertex>,
+    closed: bool,
+    elevation: f64,
+    props: DxfProperties,
+) -> DxfEntity {
+    let straight = vertices
+        .iter()
+        .all(|v| v.bulge == 0.0 && v.start_width == 0.0 && v.end_width == 0.0);
+    if props.is_plain() && !closed && elevation == 0.0 && straight {
+        return DxfEntity::Polyline {
+            polyline: Polyline::new(
+                vertices
+                    .iter()
+                    .map(|v| Point::new(v.point.x, v.point.y))
+                    .collect(),
+            ),
+            layer: props.simple_layer(),
+        };
+    }
+    for v in &mut vertices {
+        v.point.z = elevation;
+    }
+    DxfEntity::LwPolyline {
+        vertices,
+        closed,
+        elevation,
+        props,
+    }
+}
+
+fn parse_insert(r: &Record, attrib_recs: &[Record]) -> io::Result<DxfEntity> {
+    let (mut props, mut body) = split_props(&r.codes)?;
+    let block = take_string(&mut body, 2).unwrap_or_default();
+    body.retain(|(c, _)| *c != 66);
+    let c = collect(body, &[10, 20, 30, 41, 42, 43, 50], &mut props)?;
+    let scale = |code| if c.has(code) { c.get(code) } else { 1.0 };
+    let mut attributes = Vec::new();
+    for a in attrib_recs {
+        let (mut aprops, mut body) = split_props(&a.codes)?;
+        let tag = take_string(&mut body, 2).unwrap_or_default();
+        let value = take_string(&mut body, 1).unwrap_or_default();
+        let c = collect(body, &[10, 20, 30, 40, 50], &mut aprops)?;
+        attributes.push(DxfAttribute {
+            tag,
+            value,
+            position: c.point(10),
+            height: c.get(40),
+            rotation: c.get(50).to_radians(),
+            props: aprops,
+        });
+    }
+    Ok(DxfEntity::Insert {
+        block,
+        position: c.point(10),
+        scale: [scale(41), scale(42), scale(43)],
+        rotation: c.get(50).to_radians(),
+        attributes,
+        props,
+    })
+}
+
+fn parse_layer(codes: &[DxfPair]) -> io::Result<DxfLayer> {
+    let (mut props, mut body) = split_props(codes)?;
+    let name = take_string(&mut body, 2).unwrap_or_default();
+    let flags = take_string(&mut body, 70)
+        .map(|v| num(70, &v))
+        .transpose()?
+        .unwrap_or(0);
+    insert_body_extras(&mut props, body);
+    Ok(DxfLayer {
+        name,
+        color: props.color.unwrap_or(7),
+        true_color: props.true_color,
+        linetype: props.linetype.unwrap_or_else(|| "CONTINUOUS".into()),
+        flags,
+        lineweight: props.lineweight,
+        handle: props.handle,
+        extra: props.extra,
+    })
+}
+
+fn parse_linetype(codes: &[DxfPair]) -> io::Result<DxfLinetype> {
+    let (mut props, body) = split_props(codes)?;
+    let mut lt = DxfLinetype::new("", "", &[]);
+    let mut rest = Vec::new();
+    for (code, value) in body {
+        match code {
+            2 => lt.name = value,
+            3 => lt.description = value,
+            70 => lt.flags = num(code, &value)?,
+            72 | 73 | 40 => {}
+            49 => lt.pattern.push(DxfDash {
+                length: num(code, &value)?,
+                codes: Vec::new(),
+            }),
+            74 | 75 | 340 | 46 | 50 | 44 | 45 | 9 if !lt.pattern.is_empty() => {
+                lt.pattern.last_mut().unwrap().codes.push((code, value))
+            }
+            _ => rest.push((code, value)),
+        }
+    }
+    insert_body_extras(&mut props, rest);
+    lt.handle = props.handle;
+    lt.extra = props.extra;
+    Ok(lt)
+}
+
+fn parse_tables(recs: &[Record], doc: &mut DxfDocument) -> io::Result<()> {
+    let mut i = 0;
+    while i < recs.len() {
+        if recs[i].kind != "TABLE" {
+            i += 1;
+            continue;
+        }
+        let mut codes = recs[i].codes.clone();
+        let name = take_string(&mut codes, 2).unwrap_or_default();
+        codes.retain(|(c, _)| *c != 70);
+        i += 1;
+        let start = i;
+        while i < recs.len() && recs[i].kind != "ENDTAB" {
+            i += 1;
+        }
+        let entries = &recs[start..i];
+        i += 1;
+        let raw = match name.as_str() {
+            "LAYER" => {
+                for e in entries {
+                    doc.layers.push(parse_layer(&e.codes)?);
+                }
+                Vec::new()
+            }
+            "LTYPE" => {
+                for e in entries {
+                    doc.linetypes.push(parse_linetype(&e.codes)?);
+                }
+                Vec::new()
+            }
+            _ => flatten(entries),
+        };
+        doc.tables.push(DxfTable {
+            name,
+            codes,
+            entries: raw,
+        });
+    }
+    Ok(())
+}
+
+fn parse_blocks(recs: &[Record], doc: &mut DxfDocument) -> io::Result<()> {
+    let mut i = 0;
+    while i < recs.len() {
+        if recs[i].kind != "BLOCK" {
+            i += 1;
+            continue;
+        }
+        let (mut props, mut body) = split_props(&recs[i].codes)?;
+        let name = take_string(&mut body, 2).unwrap_or_default();
+        take_string(&mut body, 3);
+        let mut flags = 0;
+        let mut rest = Vec::new();
+        for (code, value) in body {
+            match code {
+                70 => flags = num(code, &value)?,
+                _ => rest.push((code, value)),
+            }
+        }
+        let c = collect(rest, &[10, 20, 30], &mut props)?;
+        i += 1;
+        let start = i;
+        while i < recs.len() && recs[i].kind != "ENDBLK" {
+            i += 1;
+        }
+        let entities = parse_entities(&recs[start..i])?;
+        let end_props = match recs.get(i) {
+            Some(r) => {
+                let (mut p, body) = split_props(&r.codes)?;
+                insert_body_extras(&mut p, body);
+                p
+            }
+            None => DxfProperties::default(),
+        };
+        i += 1;
+        doc.blocks.push(DxfBlock {
+            name,
+            base_point: c.point(10),
+            flags,
+            entities,
+            props,
+            end_props,
+        });
+    }
+    Ok(())
+}
+
+fn parse_header(codes: &[DxfPair], doc: &mut DxfDocument) {
+    for (code, value) in codes {
+        if *code == 9 {
+            doc.header.push(DxfHeaderVariable {
+                name: value.clone(),
+                values: Vec::new(),
+            });
+        } else if let Some(v) = doc.header.last_mut() {
+            v.values.push((*code, value.clone()));
+        }
+    }
+}
+
+/// Parses DXF data, detecting binary files by their sentinel.
+pub fn parse_dxf(data: &[u8]) -> io::Result<DxfDocument> {
+    let pairs = if data.starts_with(BINARY_SENTINEL) {
+        binary_pairs(data)?
+    } else {
+        ascii_pairs(&decode_text(data))?
+    };
+    let recs = records(pairs);
+    let mut doc = DxfDocument::new();
+    let mut i = 0;
+    while i < recs.len() {
+        if recs[i].kind != "SECTION" {
+            i += 1;
+            continue;
+        }
+        let mut codes = recs[i].codes.clone();
+        let name = take_string(&mut codes, 2).unwrap_or_default();
+        i += 1;
+        let start = i;
+        while i < recs.len() && recs[i].kind != "ENDSEC" {
+            i += 1;
+        }
+        let body = &recs[start..i];
+        i += 1;
+        match name.as_str() {
+            "HEADER" => parse_header(&codes, &mut doc),
+            "TABLES" => parse_tables(body, &mut doc)?,
+            "BLOCKS" => parse_blocks(body, &mut doc)?,
+            "ENTITIES" => doc.entities.extend(parse_entities(body)?),
+            _ => {
+                codes.extend(flatten(body));
+                doc.sections.push(DxfSection { name, codes });
+            }
+        }
+    }
+    Ok(doc)
+}
+
+/// Reads an ASCII or binary DXF file.
+pub fn read_dxf_document(path: &str) -> io::Result<DxfDocument> {
+    parse_dxf(&std::fs::read(path)?)
+}
+
+/// Reads the model space entities of a DXF file.
+pub fn read_dxf(path: &str) -> io::Result<Vec<DxfEntity>> {
+    Ok(read_dxf_document(path)?.entities)
+}
+
+struct Writer {
+    pairs: Vec<DxfPair>,
+    /// Whether subclass markers are written (R13 and later).
+    markers: bool,
+}
+
+impl Writer {
+    fn push(&mut self, code: i32, value: impl Display) {
+        self.pairs.push((code, value.to_string()));
+    }
+
+    fn point(&mut self, code: i32, p: Point3) {
+        self.push(code, p.x);
+        self.push(code + 10, p.y);
+        self.push(code + 20, p.z);
+    }
+
+    fn marker(&mut self, name: &str) {
+        if self.markers {
+            self.push(100, name);
+        }
+    }
+
+    fn extend(&mut self, codes: &[DxfPair]) {
+        self.pairs.extend(codes.iter().cloned());
+    }
+
+    /// Writes the record type, handle and common properties, returning the
+    /// uninterpreted codes for the body and the extended data.
+    fn start<'a>(
+        &mut self,
+        kind: &str,
+        props: &'a DxfProperties,
+        subclasses: &[&str],
+    ) -> (Vec<DxfPair>, &'a [DxfPair]) {
+        let xdata_at = props
+            .extra
+            .iter()
+            .position(|(c, _)| *c >= 1000)
+            .unwrap_or(props.extra.len());
+        let (extra, xdata) = props.extra.split_at(xdata_at);
+        let mut owner = Vec::new();
+        let mut space = Vec::new();
+        let mut common = Vec::new();
+        let mut body = Vec::new();
+        let mut in_group = false;
+        for (code, value) in extra {
+            let pair = (*code, value.clone());
+            if *code == 102 {
+                in_group = value.trim_start().starts_with('{');
+                owner.push(pair);
+            } else if in_group || matches!(code, 330 | 360) {
+                owner.push(pair);
+            } else if matches!(code, 67 | 410) {
+                space.push(pair);
+            } else if matches!(code, 48 | 60 | 284 | 347 | 390 | 440) {
+                common.push(pair);
+            } else {
+                body.push(pair);
+            }
+        }
+        self.push(0, kind);
+        if let Some(h) = &props.handle {
+            self.push(5, h);
+        }
+        self.extend(&owner);
+        self.marker("AcDbEntity");
+        self.extend(&space);
+        self.push(8, &props.layer);
+        if let Some(lt) = &props.linetype {
+            self.push(6, lt);
+        }
+        if let Some(c) = props.color {
+            self.push(62, c);
+        }
+        if let Some(lw) = props.lineweight {
+            self.push(370, lw);
+        }
+        if let Some(c) = props.true_color {
+            self.push(420, c);
+        }
+        self.extend(&common);
+        for s in subclasses {
+            self.marker(s);
+        }
+        (body, xdata)
+    }
+
+    /// Writes a table record which uses `AcDbSymbolTableRecord` rather than
+    /// `AcDbEntity`.
+    fn start_record(
+        &mut self,
+        kind: &str,
+        handle: &Option<String>,
+        extra: &[DxfPair],
+        subclass: &str,
+    ) -> Vec<DxfPair> {
+        let mut owner = Vec::new();
+        let mut rest = Vec::new();
+        let mut in_group = false;
+        for (code, value) in extra {
+            let pair = (*code, value.clone());
+            if *code == 102 {
+                in_group = value.trim_start().starts_with('{');
+                owner.push(pair);
+            } else if in_group || *code == 330 {
+                owner.push(pair);
+            } else {
+                rest.push(pair);
+            }
+        }
+        self.push(0, kind);
+        if let Some(h) = handle {
+            self.push(5, h);
+        }
+        self.extend(&owner);
+        self.marker("AcDbSymbolTableRecord");
+        self.marker(subclass);
+        rest
+    }
+
+    fn seqend(&mut self, layer: &str) {
+        self.push(0, "SEQEND");
+        self.marker("AcDbEntity");
+        self.push(8, layer);
+    }
+
+    fn simple(&mut self, kind: &str, layer: &Option<String>, subclass: &str) {
+        self.push(0, kind);
+        self.marker("AcDbEntity");
+        self.push(8, layer.as_deref().unwrap_or("0"));
+        self.marker(subclass);
+    }
+
+    fn entity(&mut self, e: &DxfEntity) {
+        match e {
+            DxfEntity::Point { point, layer } => {
+                self.simple("POINT", layer, "AcDbPoint");
+                self.point(10, Point3::new(point.x, point.y, 0.0));
+            }
+            DxfEntity::Line { line, layer } => {
+                self.simple("LINE", layer, "AcDbLine");
+                self.point(10, Point3::new(line.start.x, line.start.y, 0.0));
+                self.point(11, Point3::new(line.end.x, line.end.y, 0.0));
+            }
+            DxfEntity::Polyline { polyline, layer } => {
+                self.simple("POLYLINE", layer, "AcDb2dPolyline");
+                self.push(66, 1);
+                self.point(10, Point3::new(0.0, 0.0, 0.0));
+                self.push(70, 0);
+                for v in &polyline.vertices {
+                    self.simple("VERTEX", layer, "AcDbVertex");
+                    self.marker("AcDb2dVertex");
+                    self.point(10, Point3::new(v.x, v.y, 0.0));
+                }
+                self.seqend(layer.as_deref().unwrap_or("0"));
+            }
+            DxfEntity::Arc { arc, layer } => {
+                self.simple("ARC", layer, "AcDbCircle");
+                self.point(10, Point3::new(arc.center.x, arc.center.y, 0.0));
+                self.push(40, arc.radius);
+                self.marker("AcDbArc");
+                self.push(50, arc.start_angle.to_degrees());
+                self.push(51, arc.end_angle.to_degrees());
+            }
+            DxfEntity::Text {
+                position,
+                height,
+                value,
+                layer,
+            } => {
+                self.simple("TEXT", layer, "AcDbText");
+                self.point(10, Point3::new(position.x, position.y, 0.0));
+                self.push(40, height);
+                self.push(1, value);
+                self.marker("AcDbText");
+            }
+            DxfEntity::Point3D { point, props } => {
+                let (body, xdata) = self.start("POINT", props, &["AcDbPoint"]);
+                self.extend(&body);
+                self.point(10, *point);
+                self.extend(xdata);
+            }
+            DxfEntity::Line3D { start, end, props } => {
+                let (body, xdata) = self.start("LINE", props, &["AcDbLine"]);
+                self.extend(&body);
+                self.point(10, *start);
+                self.point(11, *end);
+                self.extend(xdata);
+            }
+            DxfEntity::Circle {
+                center,
+                radius,
+                props,
+            } => {
+                let (body, xdata) = self.start("CIRCLE", props, &["AcDbCircle"]);
+                self.extend(&body);
+                self.point(10, *center);
+                self.push(40, radius);
+                self.extend(xdata);
+            }
+            DxfEntity::Arc3D {
+                arc,
+                elevation,
+                props,
+            } => {
+                let (body, xdata) = self.start("ARC", props, &["AcDbCircle"]);
+                self.extend(&body);
+                self.point(10, Point3::new(arc.center.x, arc.center.y, *elevation));
+                self.push(40, arc.radius);
+                self.marker("AcDbArc");
+                self.push(50, arc.start_angle.to_degrees());
+                self.push(51, arc.end_angle.to_degrees());
+                self.extend(xdata);
+            }
+            DxfEntity::LwPolyline {
+                vertices,
+                closed,
+                elevation,
+                props,
+            } => {
+                let (body, xdata) = self.start("LWPOLYLINE", props, &["AcDbPolyline"]);
+                self.push(90, vertices.len());
+                self.push(70, if *closed { 1 } else { 0 });
+                if *elevation != 0.0 {
+                    self.push(38, elevation);
+                }
+                self.extend(&body);
+                for v in vertices {
+                    self.push(10, v.point.x);
+                    self.push(20, v.point.y);
+                    if v.start_width != 0.0 || v.end_width != 0.0 {
+                        self.push(40, v.start_width);
+                        self.push(41, v.end_width);
+                    }
+                    if v.bulge != 0.0 {
+                        self.push(42, v.bulge);
+                    }
+                }
+                self.extend(xdata);
+            }
+            DxfEntity::Polyline3D {
+                vertices,
+                closed,
+                props,
+            } => {
+                let (body, xdata) = self.start("POLYLINE", props, &["AcDb3dPolyline"]);
+                self.push(66, 1);
+                self.point(10, Point3::new(0.0, 0.0, 0.0));
+                self.push(70, if *closed { 9 } else { 8 });
+                self.extend(&body);
+                self.extend(xdata);
+                let child = DxfProperties {
+                    handle: None,
+                    extra: Vec::new(),
+                    ..props.clone()
+                };
+                for v in vertices {
+                    self.start("VERTEX", &child, &["AcDbVertex", "AcDb3dPolylineVertex"]);
+                    self.point(10, v.point);
+                    self.push(70, 32);
+                }
+                self.seqend(&props.layer);
+            }
+            DxfEntity::Face3D { corners, props } => {
+                let (body, xdata) = self.start("3DFACE", props, &["AcDbFace"]);
+                self.extend(&body);
+                for (i, c) in corners.iter().enumerate() {
+                    self.point(10 + i as i32, *c);
+                }
+                self.extend(xdata);
+            }
+            DxfEntity::Text3D {
+                position,
+                height,
+                rotation,
+                value,
+                props,
+            } => {
+                let (body, xdata) = self.start("TEXT", props, &["AcDbText"]);
+                self.point(10, *position);
+                self.push(40, height);
+                self.push(1, value);
+                if *rotation != 0.0 {
+                    self.push(50, rotation.to_degrees());
+                }
+                self.extend(&body);
+                self.marker("AcDbText");
+                self.extend(xdata);
+            }
+            DxfEntity::MText {
+                position,
+                height,
+                width,
+                rotation,
+                value,
+                props,
+            } => {
+                let (body, xdata) = self.start("MTEXT", props, &["AcDbMText"]);
+                self.point(10, *position);
+                self.push(40, height);
+                self.push(41, width);
+                if *rotation != 0.0 {
+                    self.push(50, rotation.to_degrees());
+                }
+                self.extend(&body);
+                let chars: Vec<char> = value.chars().collect();
+                let mut chunks: Vec<String> =
+                    chars.chunks(250).map(|c| c.iter().collect()).collect();
+                let last = chunks.pop().unwrap_or_default();
+                for c in chunks {
+                    self.push(3, c);
+                }
+                self.push(1, last);
+                self.extend(xdata);
+            }
+            DxfEntity::Insert {
+                block,
+                position,
+                scale,
+                rotation,
+                attributes,
+                props,
+            } => {
+                let (body, xdata) = self.start("INSERT", props, &["AcDbBlockReference"]);
+                if !attributes.is_empty() {
+                    self.push(66, 1);
+                }
+                self.push(2, block);
+                self.point(10, *position);
+                for (i, s) in scale.iter().enumerate() {
+                    if *s != 1.0 {
+                        self.push(41 + i as i32, s);
+                    }
+                }
+                if *rotation != 0.0 {
+                    self.push(50, rotation.to_degrees());
+                }
+                self.extend(&body);
+                self.extend(xdata);
+                for a in attributes {
+                    let (body, xdata) = self.start("ATTRIB", &a.props, &["AcDbText"]);
+                    self.point(10, a.position);
+                    self.push(40, a.height);
+                    self.push(1, &a.value);
+                    if a.rotation != 0.0 {
+                        self.push(50, a.rotation.to_degrees());
+                    }
+                    self.marker("AcDbAttribute");
+                    self.push(2, &a.tag);
+                    self.extend(&body);
+                    self.extend(xdata);
+                }
+                if !attributes.is_empty() {
+                    self.seqend(&props.layer);
+                }
+            }
+            DxfEntity::AttributeDefinition {
+                tag,
+                prompt,
+                default,
+                position,
+                height,
+                rotation,
+                props,
+            } => {
+                let (body, xdata) = self.start("ATTDEF", props, &["AcDbText"]);
+                self.point(10, *position);
+                self.push(40, height);
+                self.push(1, default);
+                if *rotation != 0.0 {
+                    self.push(50, rotation.to_degrees());
+                }
+                self.marker("AcDbAttributeDefinition");
+                self.push(3, prompt);
+                self.push(2, tag);
+                if !body.iter().any(|(c, _)| *c == 70) {
+                    self.push(70, 0);
+                }
+                self.extend(&body);
+                self.extend(xdata);
+            }
+            DxfEntity::Other { kind, codes } => {
+                self.push(0, kind);
+                self.extend(codes);
+            }
+        }
+    }
+
+    fn tables(&mut self, doc: &DxfDocument) {
+        self.push(0, "SECTION");
+        self.push(2, "TABLES");
+        let mut tables: Vec<DxfTable> = doc.tables.clone();
+        let parsed = [
+            ("LAYER", doc.layers.is_empty()),
+            ("LTYPE", doc.linetypes.is_empty()),
+        ];
+        for (name, empty) in parsed {
+            if !empty && !tables.iter().any(|t| t.name == name) {
+                tables.insert(0, DxfTable::new(name));
+            }
+        }
+        for t in &tables {
+            self.push(0, "TABLE");
+            self.push(2, &t.name);
+            let count = match t.name.as_str() {
+                "LAYER" => doc.layers.len(),
+                "LTYPE" => doc.linetypes.len(),
+                _ => t.entries.iter().filter(|(c, _)| *c == 0).count(),
+            };
+            self.extend(&t.codes);
+            self.push(70, count);
+            match t.name.as_str() {
+                "LAYER" => {
+                    for l in &doc.layers {
+                        let rest =
+                            self.start_record("LAYER", &l.handle, &l.extra, "AcDbLayerTableRecord");
+                        self.push(2, &l.name);
+                        self.push(70, l.flags);
+                        self.push(62, l.color);
+                        self.push(6, &l.linetype);
+                        if let Some(c) = l.true_color {
+                            self.push(420, c);
+                        }
+                        if let Some(lw) = l.lineweight {
+                            self.push(370, lw);
+                        }
+                        self.extend(&rest);
+                    }
+                }
+                "LTYPE" => {
+                    for lt in &doc.linetypes {
+                        let rest = self.start_record(
+                            "LTYPE",
+                            &lt.handle,
+                            &lt.extra,
+                            "AcDbLinetypeTableRecord",
+                        );
+                        self.push(2, &lt.name);
+                        self.push(70, lt.flags);
+                        self.push(3, &lt.description);
+                        self.push(72, 65);
+                        self.push(73, lt.pattern.len());
+                        self.push(40, lt.pattern_length());
+                        for d in &lt.pattern {
+                            self.push(49, d.length);
+                            self.extend(&d.codes);
+                        }
+                        self.extend(&rest);
+                    }
+                }
+                _ => self.extend(&t.entries),
+            }
+            self.push(0, "ENDTAB");
+        }
+        self.push(0, "ENDSEC");
+    }
+
+    fn blocks(&mut self, doc: &DxfDocument) {
+        self.push(0, "SECTION");
+        self.push(2, "BLOCKS");
+        for b in &doc.blocks {
+            let (body, xdata) = self.start("BLOCK", &b.props, &["AcDbBlockBegin"]);
+            self.push(2, &b.name);
+            self.push(70, b.flags);
+            self.point(10, b.base_point);
+            self.push(3, &b.name);
+            self.extend(&body);
+            self.extend(xdata);
+            for e in &b.entities {
+                self.entity(e);
+            }
+            let (body, xdata) = self.start("ENDBLK", &b.end_props, &["AcDbBlockEnd"]);
+            self.extend(&body);
+            self.extend(xdata);
+        }
+        self.push(0, "ENDSEC");
+    }
+
+    fn document(doc: &DxfDocument) -> Self {
+        let mut w = Writer {
+            pairs: Vec::new(),
+            markers: doc.version() >= "AC1012",
+        };
+        if !doc.header.is_empty() {
+            w.push(0, "SECTION");
+            w.push(2, "HEADER");
+            for v in &doc.header {
+                w.push(9, &v.name);
+                w.extend(&v.values);
+            }
+            w.push(0, "ENDSEC");
+        }
+        let raw = |w: &mut Writer, s: &DxfSection| {
+            w.push(0, "SECTION");
+            w.push(2, &s.name);
+            w.extend(&s.codes);
+            w.push(0, "ENDSEC");
+        };
+        for s in doc.sections.iter().filter(|s| s.name == "CLASSES") {
+            raw(&mut w, s);
+        }
+        w.tables(doc);
+        w.blocks(doc);
+        w.push(0, "SECTION");
+        w.push(2, "ENTITIES");
+        for e in &doc.entities {
+            w.entity(e);
+        }
+        w.push(0, "ENDSEC");
+        for s in doc.sections.iter().filter(|s| s.name != "CLASSES") {
+            raw(&mut w, s);
+        }
+        w.push(0, "EOF");
+        w
+    }
+}
+
+fn unicode(doc: &DxfDocument) -> bool {
+    doc.version() >= "AC1021"
+}
+
+/// Serialises a document as ASCII DXF.
+pub fn dxf_to_bytes(doc: &DxfDocument) -> Vec<u8> {
+    encode_ascii(&Writer::document(doc).pairs, unicode(doc))
+}
+
+/// Writes a document as an ASCII DXF file.
+pub fn write_dxf_document(path: &str, doc: &DxfDocument) -> io::Result<()> {
+    std::fs::write(path, dxf_to_bytes(doc))
+}
+
+/// Writes a document as a binary DXF file.
+pub fn write_dxf_binary(path: &str, doc: &DxfDocument) -> io::Result<()> {
+    let bytes = encode_binary(&Writer::document(doc).pairs, unicode(doc))?;
+    std::fs::write(path, bytes)
+}
+
+/// Writes a collection of [`DxfEntity`] values to an ASCII DXF file with a
+/// layer table for the layers they use.
+pub fn write_dxf(path: &str, entities: &[DxfEntity]) -> io::Result<()> {
+    write_dxf_document(path, &DxfDocument::from_entities(entities.to_vec()))
+}
+
+/// Converts a parcel to a closed `LWPOLYLINE` on `layer`, keeping curved
+/// edges as bulges.
+pub fn parcel_to_dxf(parcel: &Parcel, layer: &str) -> DxfEntity {
+    DxfEntity::LwPolyline {
+        vertices: parcel
+            .boundary
+            .iter()
+            .enumerate()
+            .map(|(i, p)| DxfVertex::new(Point3::new(p.x, p.y, 0.0), parcel.bulge(i)))
+            .collect(),
+        closed: true,
+        elevation: 0.0,
+        props: DxfProperties::on_layer(layer),
+    }
+}
+
+/// Returns a parcel for every closed polyline in `entities`. Open polylines
+/// count as closed when their last vertex repeats the first.
+pub fn parcels_from_dxf(entities: &[DxfEntity]) -> Vec<Parcel> {
+    let mut parcels = Vec::new();
+    for e in entities {
+        let (mut boundary, mut bulges, closed): (Vec<Point>, Vec<f64>, bool) = match e {
+            DxfEntity::Polyline { polyline, .. } => (polyline.vertices.clone(), Vec::new(), false),
+            DxfEntity::LwPolyline {
+                vertices, closed, ..
+            }
+            | DxfEntity::Polyline3D {
+                vertices, closed, ..
+            } => (
+                vertices
+                    .iter()
+                    .map(|v| Point::new(v.point.x, v.point.y))
+                    .collect(),
+                vertices.iter().map(|v| v.bulge).collect(),
+                *closed,
+            ),
+            _ => continue,
+        };
+        if !closed {
+            if boundary.len() < 2 || boundary.first() != boundary.last() {
+                continue;
+            }
+            boundary.pop();
+            bulges.truncate(boundary.len());
+        }
+        if boundary.len() >= 3 {
+            parcels.push(Parcel::with_bulges(boundary, bulges));
+        }
+    }
+    parcels
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CIVIL: &str = "  0\nSECTION\n  2\nHEADER\n  9\n$ACADVER\n  1\nAC1027\n  9\n$INSBASE\n 10\n0.0\n 20\n0.0\n 30\n0.0\n  0\nENDSEC\n  0\nSECTION\n  2\nTABLES\n  0\nTABLE\n  2\nLTYPE\n  5\n5\n100\nAcDbSymbolTable\n 70\n1\n  0\nLTYPE\n  5\n14\n100\nAcDbSymbolTableRecord\n100\nAcDbLinetypeTableRecord\n  2\nDASHED\n 70\n0\n  3\n__ __ __\n 72\n65\n 73\n2\n 40\n0.75\n 49\n0.5\n 74\n0\n 49\n-0.25\n 74\n0\n  0\nENDTAB\n  0\nTABLE\n  2\nLAYER\n 70\n1\n  0\nLAYER\n  5\n10\n100\nAcDbSymbolTableRecord\n100\nAcDbLayerTableRecord\n  2\nC-TOPO\n 70\n4\n 62\n-3\n  6\nDASHED\n370\n25\n390\nF\n  0\nENDTAB\n  0\nTABLE\n  2\nSTYLE\n 70\n1\n  0\nSTYLE\n  2\nSTANDARD\n 70\n0\n  0\nENDTAB\n  0\nENDSEC\n  0\nSECTION\n  2\nBLOCKS\n  0\nBLOCK\n  5\n20\n100\nAcDbEntity\n  8\n0\n100\nAcDbBlockBegin\n  2\nMH\n 70\n2\n 10\n0.0\n 20\n0.0\n 30\n0.0\n  3\nMH\n  0\nCIRCLE\n  8\n0\n 10\n0.0\n 20\n0.0\n 30\n0.0\n 40\n0.6\n  0\nENDBLK\n  5\n21\n  8\n0\n  0\nENDSEC\n  0\nSECTION\n  2\nENTITIES\n  0\nLINE\n  5\n2A\n330\n1F\n100\nAcDbEntity\n  8\nC-TOPO\n 62\n1\n100\nAcDbLine\n 30\n101.5\n 20\n5000.0\n 10\n1000.0\n 21\n5010.0\n 11\n1010.0\n 31\n102.0\n1001\nAECC\n1000\nbreakline\n  0\nLWPOLYLINE\n  8\nC-TOPO\n 90\n3\n 70\n1\n 38\n95.0\n 43\n0.0\n 10\n0.0\n 20\n0.0\n 42\n1.0\n 10\n10.0\n 20\n0.0\n 10\n10.0\n 20\n10.0\n  0\n3DFACE\n  8\nTIN\n 10\n0\n 20\n0\n 30\n1\n 11\n1\n 21\n0\n 31\n2\n 12\n0\n 22\n1\n 32\n3\n 13\n0\n 23\n1\n 33\n3\n  0\nMTEXT\n  8\nANNO\n 10\n5\n 20\n5\n 30\n0\n 40\n2.5\n 41\n40\n 71\n1\n  3\nFirst part \n  1\nand last\n  0\nINSERT\n  8\nSTRM\n 66\n1\n  2\nMH\n 10\n50\n 20\n60\n 30\n99.5\n 41\n2\n 50\n90\n  0\nATTRIB\n  8\nSTRM\n 10\n50\n 20\n61\n 30\n0\n 40\n1\n  1\nMH-12\n  2\nID\n 70\n0\n  0\nSEQEND\n  8\nSTRM\n  0\nPOLYLINE\n  8\nFL\n 66\n1\n 10\n0\n 20\n0\n 30\n0\n 70\n8\n  0\nVERTEX\n  8\nFL\n 10\n1\n 20\n2\n 30\n3\n 70\n32\n  0\nVERTEX\n  8\nFL\n 10\n4\n 20\n5\n 30\n6\n 70\n32\n  0\nSEQEND\n  8\nFL\n  0\nHATCH\n  8\nH\n100\nAcDbHatch\n  2\nSOLID\n  0\nENDSEC\n  0\nSECTION\n  2\nOBJECTS\n  0\nDICTIONARY\n  5\nC\n  0\nENDSEC\n  0\nEOF\n";
+
+    #[test]
+    fn reads_civil3d_style_file() {
+        let doc = parse_dxf(CIVIL.as_bytes()).unwrap();
+        assert_eq!(doc.version(), "AC1027");
+        assert_eq!(doc.layers.len(), 1);
+        let layer = doc.layer("c-topo").unwrap();
+        assert!(layer.is_off() && layer.is_locked());
+        assert_eq!(layer.lineweight, Some(25));
+        assert_eq!(doc.linetype("DASHED").unwrap().pattern_length(), 0.75);
+        assert_eq!(doc.block("MH").unwrap().entities.len(), 1);
+        assert_eq!(doc.entities.len(), 7);
+        match &doc.entities[0] {
+            DxfEntity::Line3D { start, end, props } => {
+                assert_eq!(*start, Point3::new(1000.0, 5000.0, 101.5));
+                assert_eq!(end.z, 102.0);
+                assert_eq!(props.color, Some(1));
+                assert_eq!(props.handle.as_deref(), Some("2A"));
+                assert_eq!(props.extra.last().unwrap().1, "breakline");
+            }
+            e => panic!("unexpected {e:?}"),
+        }
+        match &doc.entities[1] {
+            DxfEntity::LwPolyline {
+                vertices, closed, ..
+            } => {
+                assert!(*closed);
+                assert_eq!(vertices[0].bulge, 1.0);
+                assert_eq!(vertices[2].point, Point3::new(10.0, 10.0, 95.0));
+            }
+            e => panic!("unexpected {e:?}"),
+        }
+        assert!(matches!(doc.entities[2], DxfEntity::Face3D { .. }));
+        match &doc.entities[3] {
+            DxfEntity::MText { value, width, .. } => {
+                assert_eq!(value, "First part and last");
+                assert_eq!(*width, 40.0);
+            }
+            e => panic!("unexpected {e:?}"),
+        }
+        match &doc.entities[4] {
+            DxfEntity::Insert {
+                block,
+                scale,
+                rotation,
+                attributes,
+                ..
+            } => {
+                assert_eq!(block, "MH");
+                assert_eq!(*scale, [2.0, 1.0, 1.0]);
+                assert!((rotation - std::f64::consts::FRAC_PI_2).abs() < 1e-12);
+                assert_eq!(attributes[0].value, "MH-12");
+            }
+            e => panic!("unexpected {e:?}"),
+        }
+        match &doc.entities[5] {
+            DxfEntity::Polyline3D { vertices, .. } => {
+                assert_eq!(vertices[1].point, Point3::new(4.0, 5.0, 6.0))
+            }
+            e => panic!("unexpected {e:?}"),
+        }
+        assert!(matches!(&doc.entities[6], DxfEntity::Other { kind, .. } if kind == "HATCH"));
+        assert_eq!(doc.sections[0].name, "OBJECTS");
+        assert_eq!(doc.rgb(doc.entities[0].props().unwrap()), [255, 0, 0]);
+    }
+
+    #[test]
+    fn ascii_and_binary_round_trip() {
+        let doc = parse_dxf(CIVIL.as_bytes()).unwrap();
+        let again = parse_dxf(&dxf_to_bytes(&doc)).unwrap();
+        assert_eq!(again, doc);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("civil.dxf");
+        let path = path.to_str().unwrap();
+        write_dxf_binary(path, &doc).unwrap();
+        assert!(std::fs::read(path).unwrap().starts_with(BINARY_SENTINEL));
+        assert_eq!(read_dxf_document(path).unwrap(), doc);
+    }
+
+    #[test]
+    fn non_latin_text_escaped_before_r2007() {
+        let mut doc = DxfDocument::from_entities(vec![DxfEntity::Text {
+            position: Point::new(0.0, 0.0),
+            height: 1.0,
+            value: "Δ=12°".into(),
+            layer: None,
+        }]);
+        doc.set_header("$ACADVER", 1, "AC1015");
+        let bytes = dxf_to_bytes(&doc);
+        assert!(decode_text(&bytes).contains("\\U+0394=12°"));
+        assert_eq!(parse_dxf(&bytes).unwrap().entities, doc.entities);
+        doc.set_header("$ACADVER", 1, "AC1032");
+        let read = parse_dxf(&dxf_to_bytes(&doc)).unwrap();
+        assert_eq!(read.entities, doc.entities);
+    }
+
+    #[test]
+    fn bad_group_code_reports_line() {
+        let err = parse_dxf(b"  0\nSECTION\nxx\nENTITIES\n").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().starts_with("line 3"));
+    }
+
+    #[test]
+    fn curved_parcel_round_trip() {
+        let parcel = Parcel::with_bulges(
+            vec![
+                Point::new(0.0, 0.0),
+                Point::new(20.0, 0.0),
+                Point::new(20.0, 20.0),
+                Point::new(0.0, 20.0),
+            ],
+            vec![0.0, 0.0, 0.4, 0.0],
+        );
+        let doc = DxfDocument::from_entities(vec![parcel_to_dxf(&parcel, "LOTS")]);
+        let read = parse_dxf(&dxf_to_bytes(&doc)).unwrap();
+        let parcels = parcels_from_dxf(&read.entities);
+        assert_eq!(parcels.len(), 1);
+        assert_eq!(parcels[0].bulges, parcel.bulges);
+        assert!((parcels[0].area() - parcel.area()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn edit_paths_round_trip() {
+        use std::f64::consts::FRAC_PI_2;
+        let poly = DxfEntity::LwPolyline {
+            vertices: vec![
+                DxfVertex::new(Point3::new(0.0, 0.0, 0.0), 0.0),
+                DxfVertex::new(Point3::new(10.0, 0.0, 0.0), 0.5),
+                DxfVertex::new(Point3::new(10.0, 10.0, 0.0), 0.0),
+            ],
+            closed: true,
+            elevation: 0.0,
+            props: DxfProperties::on_layer("EDIT"),
+        };
+        let path = poly.path().unwrap();
+        assert_eq!(path.segments().len(), 3);
+        let DxfEntity::LwPolyline {
+            vertices,
+            closed,
+            props,
+            ..
+        } = DxfEntity::from_path(&path, poly.properties())
+        else {
+            panic!("expected polyline");
+        };
+        assert!(closed && props.layer == "EDIT");
+        assert_eq!(vertices.len(), 3);
+        assert!((vertices[1].bulge - 0.5).abs() < 1e-12);
+        assert!((vertices[2].point.y - 10.0).abs() < 1e-12);
+
+        let line = DxfEntity::Line {
+            line: Line::new(Point::new(0.0, 0.0), Point::new(1.0, 1.0)),
+            layer: Some("L".into()),
+        };
+        let path = line.path().unwrap();
+        assert_eq!(DxfEntity::from_path(&path, line.properties()), line);
+
+        let arc = DxfEntity::Arc3D {
+            arc: Arc::new(Point::new(0.0, 0.0), 5.0, 0.0, FRAC_PI_2),
+            elevation: 42.0,
+            props: DxfProperties::on_layer("A"),
+        };
+        let DxfEntity::Arc3D {
+            arc: back,
+            elevation,
+            ..
+        } = DxfEntity::from_path(&arc.path().unwrap(), arc.properties())
+        else {
+            panic!("expected arc");
+        };
+        assert_eq!(elevation, 42.0);
+        assert!((back.radius - 5.0).abs() < 1e-9 && (back.end_angle - FRAC_PI_2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn join_keeps_elevations() {
+        let props = DxfProperties::on_layer("KERB");
+        let line = DxfEntity::Line3D {
+            start: Point3::new(0.0, 0.0, 10.0),
+            end: Point3::new(10.0, 0.0, 11.0),
+            props: props.clone(),
+        };
+        // Drawn back towards the line's end.
+        let poly = DxfEntity::Polyline3D {
+            vertices: vec![
+                DxfVertex::new(Point3::new(20.0, 5.0, 13.0), 0.0),
+                DxfVertex::new(Point3::new(15.0, 0.0, 12.0), 0.0),
+                DxfVertex::new(Point3::new(10.0, 0.0, 11.0), 0.0),
+            ],
+            closed: false,
+            props: props.clone(),
+        };
+        let pieces = [line.path().unwrap(), poly.path().unwrap()];
+        let joined = Polyline3::join(&pieces, 1e-6);
+        assert_eq!(joined.len(), 1);
+        let (used, path) = &joined[0];
+        assert_eq!(used, &vec![0, 1]);
+        let DxfEntity::Polyline3D {
+            vertices, closed, ..
+        } = DxfEntity::from_path(path, props)
+        else {
+            panic!("expected 3D polyline");
+        };
+        assert!(!closed);
+        let z: Vec<f64> = vertices.iter().map(|v| v.point.z).collect();
+        assert_eq!(z, [10.0, 11.0, 12.0, 13.0]);
+        assert_eq!(vertices[3].point, Point3::new(20.0, 5.0, 13.0));
+    }
+
+    #[test]
+    fn polyline3_round_trip() {
+        let mut level = Polyline3::with_bulges(
+            vec![
+                Point3::new(0.0, 0.0, 12.5),
+                Point3::new(10.0, 0.0, 12.5),
+                Point3::new(10.0, 10.0, 12.5),
+            ],
+            vec![0.0, 0.5, 0.0],
+        );
+        level.closed = true;
+        let props = DxfProperties::on_layer("FL");
+        let doc = DxfDocument::from_entities(vec![
+            DxfEntity::from_polyline3(&level, props.clone()),
+            DxfEntity::from_polyline3(
+                &Polyline3::with_bulges(
+                    vec![Point3::new(0.0, 0.0, 1.0), Point3::new(10.0, 0.0, 2.0)],
+                    vec![1.0],
+                ),
+                props,
+            ),
+        ]);
+        let read = parse_dxf(&dxf_to_bytes(&doc)).unwrap();
+        assert!(matches!(read.entities[0], DxfEntity::LwPolyline { .. }));
+        assert_eq!(read.entities[0].polyline3().unwrap(), level);
+        let DxfEntity::Polyline3D { vertices, .. } = &read.entities[1] else {
+            panic!("expected 3D polyline");
+        };
+        assert_eq!(vertices.len(), 181);
+        assert!((vertices[90].point.y + 5.0).abs() < 1e-9 && vertices[90].point.z == 1.5);
+    }
+}
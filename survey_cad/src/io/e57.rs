@@ -1,8 +1,15 @@
 use crate::geometry::Point3;
-use e57::{E57Reader, E57Writer, Record, RecordValue};
+use crate::lidar::tiling::{process_tiled, TileOptions, TileSummary};
+use crate::lidar::CloudPoint;
+use e57::{E57Reader, E57Writer, Record, RecordDataType, RecordName, RecordValue};
 use std::io;
+use std::path::Path;
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
 use uuid::Uuid;
 
+/// Points sent per message by [`stream_points_e57`].
+const CHUNK: usize = 65_536;
+
 /// Reads an E57 file and returns all point coordinates found in the file.
 pub fn read_points_e57(path: &str) -> io::Result<Vec<Point3>> {
     let mut reader =
@@ -22,6 +29,124 @@ pub fn read_points_e57(path: &str) -> io::Result<Vec<Point3>> {
     Ok(pts)
 }
 
+/// Scales a normalized E57 value to the full `u16` range.
+fn unit_to_u16(v: f32) -> u16 {
+    (v.clamp(0.0, 1.0) * u16::MAX as f32).round() as u16
+}
+
+/// Converts a point with valid cartesian coordinates, keeping its intensity
+/// and colour scaled to the full `u16` range.
+fn cloud_point(p: e57::Point) -> Option<CloudPoint> {
+    let e57::CartesianCoordinate::Valid { x, y, z } = p.cartesian else {
+        return None;
+    };
+    let mut point = CloudPoint::new(Point3::new(x, y, z));
+    point.intensity = p.intensity.map_or(0, unit_to_u16);
+    point.color = p.color.map(|c| {
+        [
+            unit_to_u16(c.red),
+            unit_to_u16(c.green),
+            unit_to_u16(c.blue),
+        ]
+    });
+    Some(point)
+}
+
+fn read_chunks(path: &str, tx: &SyncSender<io::Result<Vec<CloudPoint>>>) -> io::Result<()> {
+    let mut reader =
+        E57Reader::from_file(path).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    for pc in reader.pointclouds() {
+        let iter = reader
+            .pointcloud_simple(&pc)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let mut chunk = Vec::with_capacity(CHUNK);
+        for p in iter {
+            let p = p.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+            chunk.extend(cloud_point(p));
+            if chunk.len() == CHUNK {
+                let full = std::mem::replace(&mut chunk, Vec::with_capacity(CHUNK));
+                if tx.send(Ok(full)).is_err() {
+                    return Ok(());
+                }
+            }
+        }
+        if !chunk.is_empty() && tx.send(Ok(chunk)).is_err() {
+            return Ok(());
+        }
+    }
+    Ok(())
+}
+
+/// Streams the points of an E57 file. A background thread reads chunks of
+/// points a few at a time ahead of the consumer, so memory stays bounded.
+pub fn stream_points_e57(path: &str) -> impl Iterator<Item = io::Result<CloudPoint>> {
+    let (tx, rx): (_, Receiver<io::Result<Vec<CloudPoint>>>) = sync_channel(2);
+    let path = path.to_string();
+    std::thread::spawn(move || {
+        if let Err(e) = read_chunks(&path, &tx) {
+            let _ = tx.send(Err(e));
+        }
+    });
+    rx.into_iter().flat_map(|chunk| match chunk {
+        Ok(points) => points.into_iter().map(Ok).collect::<Vec<_>>(),
+        Err(e) => vec![Err(e)],
+    })
+}
+
+/// Streams an E57 file through [`process_tiled`] and writes every tile to
+/// `out_dir` as `<x>_<y>.e57` with the points' intensity and colour.
+pub fn tile_e57(input: &str, out_dir: &str, options: &TileOptions) -> io::Result<Vec<TileSummary>> {
+    std::fs::create_dir_all(out_dir)?;
+    process_tiled(stream_points_e57(input), options, |key, points| {
+        let path = Path::new(out_dir).join(format!("{}_{}.e57", key.x, key.y));
+        write_cloud_e57(&path.to_string_lossy(), &points)
+    })
+}
+
+/// Writes points with their intensity, and their colour when any point has
+/// one, as 16 bit integers.
+pub fn write_cloud_e57(path: &str, points: &[CloudPoint]) -> io::Result<()> {
+    let guid = Uuid::new_v4().to_string();
+    let mut writer = E57Writer::from_file(path, &guid).map_err(io::Error::other)?;
+    let color = points.iter().any(|p| p.color.is_some());
+    let mut prototype = vec![
+        Record::CARTESIAN_X_F64,
+        Record::CARTESIAN_Y_F64,
+        Record::CARTESIAN_Z_F64,
+        Record::INTENSITY_U16,
+    ];
+    if color {
+        for name in [
+            RecordName::ColorRed,
+            RecordName::ColorGreen,
+            RecordName::ColorBlue,
+        ] {
+            prototype.push(Record {
+                name,
+                data_type: RecordDataType::U16,
+            });
+        }
+    }
+    let mut pc_writer = writer
+        .add_pointcloud(&guid, prototype)
+        .map_err(io::Error::other)?;
+    for p in points {
+        let mut values = vec![
+            RecordValue::Double(p.position.x),
+            RecordValue::Double(p.position.y),
+            RecordValue::Double(p.position.z),
+            RecordValue::Integer(p.intensity.into()),
+        ];
+        if color {
+            let rgb = p.color.unwrap_or_default();
+            values.extend(rgb.map(|c| RecordValue::Integer(c.into())));
+        }
+        pc_writer.add_point(values).map_err(io::Error::other)?;
+    }
+    pc_writer.finalize().map_err(io::Error::other)?;
+    writer.finalize().map_err(io::Error::other)
+}
+
 /// Writes a list of 3D points to an E57 file.
 pub fn write_points_e57(path: &str, points: &[Point3]) -> io::Result<()> {
     let guid = Uuid::new_v4().to_string();
@@ -45,3 +170,40 @@ pub fn write_points_e57(path: &str, points: &[Point3]) -> io::Result<()> {
     pc_writer.finalize().map_err(io::Error::other)?;
     writer.finalize().map_err(io::Error::other)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attributes_survive_tiling() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("scan.e57");
+        let input = input.to_str().unwrap();
+        let points: Vec<CloudPoint> = (0..20)
+            .map(|i| {
+                let mut p = CloudPoint::new(Point3::new(i as f64, 0.5, 1.0));
+                p.intensity = 1000 * i as u16;
+                p.color = Some([i as u16 * 3000, 65535, 0]);
+                p
+            })
+            .collect();
+        write_cloud_e57(input, &points).unwrap();
+        let read: Vec<CloudPoint> = stream_points_e57(input).map(Result::unwrap).collect();
+        assert_eq!(read, points);
+
+        let out = dir.path().join("tiles");
+        let out = out.to_str().unwrap();
+        let options = TileOptions {
+            tile_size: 10.0,
+            ..Default::default()
+        };
+        assert_eq!(tile_e57(input, out, &options).unwrap().len(), 2);
+        let mut tiled: Vec<CloudPoint> = ["0_0.e57", "1_0.e57"]
+            .iter()
+            .flat_map(|f| stream_points_e57(&format!("{out}/{f}")).map(Result::unwrap))
+            .collect();
+        tiled.sort_by_key(|p| p.intensity);
+        assert_eq!(tiled, points);
+    }
+}
@@ -0,0 +1,932 @@
+//! Native GeoPackage reader and writer.
+//!
+//! Each layer is a feature table with an integer `fid`, a `geom` column
+//! holding GeoPackage geometry blobs (a `GP` header and envelope followed by
+//! little-endian ISO WKB) and one column per attribute. Writing a layer into
+//! an existing file replaces a layer of the same name and keeps the others.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io;
+
+use rusqlite::types::ValueRef;
+use rusqlite::{params, Connection, OptionalExtension};
+
+use crate::crs::Crs;
+use crate::dtm::Tin;
+use crate::geometry::{Point, Point3, Polyline};
+use crate::gis::Feature;
+use crate::parcel::Parcel;
+use crate::surveying::SurveyPoint;
+
+/// `GPKG` in ASCII, the SQLite application id of GeoPackage files.
+const APPLICATION_ID: i32 = 0x4750_4B47;
+/// GeoPackage 1.3.
+const USER_VERSION: i32 = 10300;
+
+/// Geometry stored in a GeoPackage layer. Rings of polygons are closed in
+/// the file but open here.
+#[derive(Debug, Clone, PartialEq)]
+pub enum GpkgGeometry {
+    Point(Point3),
+    LineString(Vec<Point3>),
+    /// Exterior ring followed by any holes.
+    Polygon(Vec<Vec<Point3>>),
+    Multi(Vec<GpkgGeometry>),
+}
+
+impl GpkgGeometry {
+    fn type_name(&self) -> &'static str {
+        match self {
+            GpkgGeometry::Point(_) => "POINT",
+            GpkgGeometry::LineString(_) => "LINESTRING",
+            GpkgGeometry::Polygon(_) => "POLYGON",
+            GpkgGeometry::Multi(_) => "GEOMETRY",
+        }
+    }
+
+    fn for_each_point(&self, f: &mut impl FnMut(Point3)) {
+        match self {
+            GpkgGeometry::Point(p) => f(*p),
+            GpkgGeometry::LineString(pts) => pts.iter().copied().for_each(f),
+            GpkgGeometry::Polygon(rings) => rings.iter().flatten().copied().for_each(f),
+            GpkgGeometry::Multi(parts) => parts.iter().for_each(|g| g.for_each_point(f)),
+        }
+    }
+
+    /// `[min_x, max_x, min_y, max_y]`, or `None` for empty geometries.
+    fn envelope(&self) -> Option<[f64; 4]> {
+        let mut env: Option<[f64; 4]> = None;
+        self.for_each_point(&mut |p| {
+            let e = env.get_or_insert([p.x, p.x, p.y, p.y]);
+            e[0] = e[0].min(p.x);
+            e[1] = e[1].max(p.x);
+            e[2] = e[2].min(p.y);
+            e[3] = e[3].max(p.y);
+        });
+        env
+    }
+}
+
+fn sql_err(e: rusqlite::Error) -> io::Error {
+    io::Error::other(e)
+}
+
+fn invalid(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg.into())
+}
+
+fn quote(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+// --- geometry blobs ---------------------------------------------------------
+
+fn put_point(out: &mut Vec<u8>, p: Point3, z: bool) {
+    out.extend_from_slice(&p.x.to_le_bytes());
+    out.extend_from_slice(&p.y.to_le_bytes());
+    if z {
+        out.extend_from_slice(&p.z.to_le_bytes());
+    }
+}
+
+fn put_ring(out: &mut Vec<u8>, pts: &[Point3], z: bool, close: bool) {
+    let closing = close && pts.len() > 2 && pts.first() != pts.last();
+    out.extend_from_slice(&((pts.len() + closing as usize) as u32).to_le_bytes());
+    for p in pts {
+        put_point(out, *p, z);
+    }
+    if closing {
+        put_point(out, pts[0], z);
+    }
+}
+
+fn put_wkb(out: &mut Vec<u8>, geom: &GpkgGeometry, z: bool) {
+    let code = match geom {
+        GpkgGeometry::Point(_) => 1,
+        GpkgGeometry::LineString(_) => 2,
+        GpkgGeometry::Polygon(_) => 3,
+        GpkgGeometry::Multi(_) => 7,
+    };
+    out.push(1);
+    out.extend_from_slice(&(code + if z { 1000u32 } else { 0 }).to_le_bytes());
+    match geom {
+        GpkgGeometry::Point(p) => put_point(out, *p, z),
+        GpkgGeometry::LineString(pts) => put_ring(out, pts, z, false),
+        GpkgGeometry::Polygon(rings) => {
+            out.extend_from_slice(&(rings.len() as u32).to_le_bytes());
+            for r in rings {
+                put_ring(out, r, z, true);
+            }
+        }
+        GpkgGeometry::Multi(parts) => {
+            out.extend_from_slice(&(parts.len() as u32).to_le_bytes());
+            for g in parts {
+                put_wkb(out, g, z);
+            }
+        }
+    }
+}
+
+/// Encodes a geometry as a GeoPackage blob with an XY envelope.
+pub fn encode_gpkg_geometry(geom: &GpkgGeometry, srs_id: i32, z: bool) -> Vec<u8> {
+    let env = geom.envelope();
+    let mut out = vec![b'G', b'P', 0];
+    // little endian, envelope type 1 (or none and the empty flag)
+    out.push(if env.is_some() {
+        0b0000_0011
+    } else {
+        0b0001_0001
+    });
+    out.extend_from_slice(&srs_id.to_le_bytes());
+    for v in env.into_iter().flatten() {
+        out.extend_from_slice(&v.to_le_bytes());
+    }
+    put_wkb(&mut out, geom, z);
+    out
+}
+
+struct WkbReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl WkbReader<'_> {
+    fn take(&mut self, n: usize) -> io::Result<&[u8]> {
+        let bytes = self
+            .data
+            .get(self.pos..self.pos + n)
+            .ok_or_else(|| invalid("truncated WKB geometry"))?;
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn u32(&mut self, le: bool) -> io::Result<u32> {
+        let b: [u8; 4] = self.take(4)?.try_into().unwrap();
+        Ok(if le {
+            u32::from_le_bytes(b)
+        } else {
+            u32::from_be_bytes(b)
+        })
+    }
+
+    fn f64(&mut self, le: bool) -> io::Result<f64> {
+        let b: [u8; 8] = self.take(8)?.try_into().unwrap();
+        Ok(if le {
+            f64::from_le_bytes(b)
+        } else {
+            f64::from_be_bytes(b)
+        })
+    }
+
+    fn points(&mut self, le: bool, dims: usize, has_z: bool) -> io::Result<Vec<Point3>> {
+        let n = self.u32(le)? as usize;
+        (0..n).map(|_| self.point(le, dims, has_z)).collect()
+    }
+
+    fn point(&mut self, le: bool, dims: usize, has_z: bool) -> io::Result<Point3> {
+        let x = self.f64(le)?;
+        let y = self.f64(le)?;
+        let z = if has_z { self.f64(le)? } else { 0.0 };
+        for _ in 2 + has_z as usize..dims {
+            self.f64(le)?;
+        }
+        Ok(Point3::new(x, y, z))
+    }
+
+    fn geometry(&mut self) -> io::Result<GpkgGeometry> {
+        let le = self.take(1)?[0] == 1;
+        let raw = self.u32(le)?;
+        // ISO codes (1000s for Z, 2000s for M) and EWKB high-bit flags
+        let ewkb_z = raw & 0x8000_0000 != 0;
+        let ewkb_m = raw & 0x4000_0000 != 0;
+        let code = raw & 0x0FFF_FFFF;
+        let (base, has_z, has_m) = (
+            code % 1000,
+            ewkb_z || matches!(code / 1000, 1 | 3),
+            ewkb_m || matches!(code / 1000, 2 | 3),
+        );
+        let dims = 2 + has_z as usize + has_m as usize;
+        Ok(match base {
+            1 => GpkgGeometry::Point(self.point(le, dims, has_z)?),
+            2 => GpkgGeometry::LineString(self.points(le, dims, has_z)?),
+            3 => {
+                let n = self.u32(le)?;
+                let mut rings = Vec::with_capacity(n as usize);
+                for _ in 0..n {
+                    let mut ring = self.points(le, dims, has_z)?;
+                    if ring.len() > 1 && ring.first() == ring.last() {
+                        ring.pop();
+                    }
+                    rings.push(ring);
+                }
+                GpkgGeometry::Polygon(rings)
+            }
+            4..=7 => {
+                let n = self.u32(le)?;
+                let parts = (0..n)
+                    .map(|_| self.geometry())
+                    .collect::<io::Result<Vec<_>>>()?;
+                GpkgGeometry::Multi(parts)
+            }
+            _ => return Err(invalid(format!("unsupported WKB geometry type {raw}"))),
+        })
+    }
+}
+
+/// Decodes a GeoPackage geometry blob.
+pub fn decode_gpkg_geometry(blob: &[u8]) -> io::Result<GpkgGeometry> {
+    if blob.len() < 8 || &blob[..2] != b"GP" {
+        return Err(invalid("missing GeoPackage geometry header"));
+    }
+    let flags = blob[3];
+    let envelope_len = match (flags >> 1) & 0b111 {
+        0 => 0,
+        1 => 32,
+        2 | 3 => 48,
+        4 => 64,
+        e => return Err(invalid(format!("invalid envelope type {e}"))),
+    };
+    let mut reader = WkbReader {
+        data: blob,
+        pos: 8 + envelope_len,
+    };
+    reader.geometry()
+}
+
+// --- database ---------------------------------------------------------------
+
+/// Opens or creates a GeoPackage with the required metadata tables.
+fn open(path: &str) -> io::Result<Connection> {
+    let conn = Connection::open(path).map_err(sql_err)?;
+    conn.execute_batch(&format!(
+        "PRAGMA application_id = {APPLICATION_ID};
+         PRAGMA user_version = {USER_VERSION};
+         CREATE TABLE IF NOT EXISTS gpkg_spatial_ref_sys (
+             srs_name TEXT NOT NULL,
+             srs_id INTEGER PRIMARY KEY,
+             organization TEXT NOT NULL,
+             organization_coordsys_id INTEGER NOT NULL,
+             definition TEXT NOT NULL,
+             description TEXT
+         );
+         CREATE TABLE IF NOT EXISTS gpkg_contents (
+             table_name TEXT NOT NULL PRIMARY KEY,
+             data_type TEXT NOT NULL,
+             identifier TEXT UNIQUE,
+             description TEXT DEFAULT '',
+             last_change DATETIME NOT NULL DEFAULT (strftime('%Y-%m-%dT%H:%M:%fZ','now')),
+             min_x DOUBLE, min_y DOUBLE, max_x DOUBLE, max_y DOUBLE,
+             srs_id INTEGER,
+             CONSTRAINT fk_gc_r_srs_id FOREIGN KEY (srs_id) REFERENCES gpkg_spatial_ref_sys(srs_id)
+         );
+         CREATE TABLE IF NOT EXISTS gpkg_geometry_columns (
+             table_name TEXT NOT NULL,
+             column_name TEXT NOT NULL,
+             geometry_type_name TEXT NOT NULL,
+             srs_id INTEGER NOT NULL,
+             z TINYINT NOT NULL,
+             m TINYINT NOT NULL,
+             CONSTRAINT pk_geom_cols PRIMARY KEY (table_name, column_name)
+         );
+         INSERT OR IGNORE INTO gpkg_spatial_ref_sys VALUES
+             ('Undefined cartesian SRS', -1, 'NONE', -1, 'undefined', 'undefined cartesian coordinate reference system'),
+             ('Undefined geographic SRS', 0, 'NONE', 0, 'undefined', 'undefined geographic coordinate reference system'),
+             ('WGS 84 geodetic', 4326, 'EPSG', 4326, 'GEOGCS[\"WGS 84\",DATUM[\"WGS_1984\",SPHEROID[\"WGS 84\",6378137,298.257223563]],PRIMEM[\"Greenwich\",0],UNIT[\"degree\",0.0174532925199433]]', 'longitude/latitude coordinates in decimal degrees on the WGS 84 spheroid');"
+    ))
+    .map_err(sql_err)?;
+    Ok(conn)
+}
+
+/// Registers the CRS in `gpkg_spatial_ref_sys` with its WKT and returns its
+/// `srs_id`. CRSs without an EPSG code are stored as the undefined cartesian
+/// SRS unless PROJ can describe them as WKT.
+fn srs_id(conn: &Connection, crs: &Crs) -> io::Result<i32> {
+    let wkt = crs.to_wkt();
+    let id = match (crs.epsg(), &wkt) {
+        (Some(code), _) => code as i32,
+        (None, Some(definition)) => {
+            let max: i32 = conn
+                .query_row("SELECT MAX(srs_id) FROM gpkg_spatial_ref_sys", [], |r| {
+                    r.get(0)
+                })
+                .map_err(sql_err)?;
+            let existing: Option<i32> = conn
+                .query_row(
+                    "SELECT srs_id FROM gpkg_spatial_ref_sys WHERE definition = ?1",
+                    [definition],
+                    |r| r.get(0),
+                )
+                .optional()
+                .map_err(sql_err)?;
+            return match existing {
+                Some(id) => Ok(id),
+                None => {
+                    let id = (max + 1).max(100_000);
+                    conn.execute(
+                        "INSERT INTO gpkg_spatial_ref_sys VALUES (?1, ?2, 'NONE', ?2, ?3, NULL)",
+                        params![format!("Custom {id}"), id, definition],
+                    )
+                    .map_err(sql_err)?;
+                    Ok(id)
+                }
+            };
+        }
+        (None, None) => return Ok(-1),
+    };
+    conn.execute(
+        "INSERT OR IGNORE INTO gpkg_spatial_ref_sys VALUES (?1, ?2, 'EPSG', ?2, ?3, NULL)",
+        params![
+            format!("EPSG:{id}"),
+            id,
+            wkt.as_deref().unwrap_or("undefined")
+        ],
+    )
+    .map_err(sql_err)?;
+    Ok(id)
+}
+
+/// SQL type of an attribute column.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    Integer,
+    Real,
+    Text,
+}
+
+impl ColumnType {
+    fn sql(self) -> &'static str {
+        match self {
+            ColumnType::Integer => "INTEGER",
+            ColumnType::Real => "REAL",
+            ColumnType::Text => "TEXT",
+        }
+    }
+}
+
+/// Writes features to the layer `layer` of the GeoPackage at `path`,
+/// creating the file if needed. Attribute columns are the union of the
+/// features' attribute keys. Columns listed in `types` get that type and
+/// the others are TEXT, so codes such as `007` keep their leading zeros.
+/// `z` stores elevations in the geometries.
+pub fn write_gpkg_layer(
+    path: &str,
+    layer: &str,
+    features: &[Feature<GpkgGeometry>],
+    crs: &Crs,
+    z: bool,
+    types: &[(&str, ColumnType)],
+) -> io::Result<()> {
+    let mut conn = open(path)?;
+    let srs = srs_id(&conn, crs)?;
+    let columns: BTreeSet<&str> = features
+        .iter()
+        .flat_map(|f| f.attributes.keys().map(String::as_str))
+        .filter(|k| !matches!(*k, "fid" | "geom"))
+        .collect();
+    let types: Vec<ColumnType> = columns
+        .iter()
+        .map(|c| {
+            types
+                .iter()
+                .find(|(name, _)| name == c)
+                .map_or(ColumnType::Text, |(_, t)| *t)
+        })
+        .collect();
+    let geometry_type = match features.first() {
+        Some(f)
+            if features
+                .iter()
+                .all(|g| g.geometry.type_name() == f.geometry.type_name()) =>
+        {
+            f.geometry.type_name()
+        }
+        _ => "GEOMETRY",
+    };
+    let mut env: Option<[f64; 4]> = None;
+    for e in features.iter().filter_map(|f| f.geometry.envelope()) {
+        let acc = env.get_or_insert(e);
+        *acc = [
+            acc[0].min(e[0]),
+            acc[1].max(e[1]),
+            acc[2].min(e[2]),
+            acc[3].max(e[3]),
+        ];
+    }
+
+    let tx = conn.transaction().map_err(sql_err)?;
+    let table = quote(layer);
+    let defs: String = columns
+        .iter()
+        .zip(&types)
+        .map(|(c, t)| format!(", {} {}", quote(c), t.sql()))
+        .collect();
+    tx.execute_batch(&format!(
+        "DROP TABLE IF EXISTS {table};
+         CREATE TABLE {table} (fid INTEGER PRIMARY KEY AUTOINCREMENT, geom {geometry_type}{defs});"
+    ))
+    .map_err(sql_err)?;
+    tx.execute(
+        "DELETE FROM gpkg_geometry_columns WHERE table_name = ?1",
+        [layer],
+    )
+    .map_err(sql_err)?;
+    tx.execute(
+        "INSERT INTO gpkg_geometry_columns VALUES (?1, 'geom', ?2, ?3, ?4, 0)",
+        params![layer, geometry_type, srs, z as i32],
+    )
+    .map_err(sql_err)?;
+    tx.execute(
+        "INSERT OR REPLACE INTO gpkg_contents (table_name, data_type, identifier, min_x, min_y, max_x, max_y, srs_id)
+         VALUES (?1, 'features', ?1, ?2, ?3, ?4, ?5, ?6)",
+        params![
+            layer,
+            env.map(|e| e[0]),
+            env.map(|e| e[2]),
+            env.map(|e| e[1]),
+            env.map(|e| e[3]),
+            srs
+        ],
+    )
+    .map_err(sql_err)?;
+    {
+        let names: String = columns.iter().map(|c| format!(", {}", quote(c))).collect();
+        let marks: String = (0..columns.len())
+            .map(|i| format!(", ?{}", i + 2))
+            .collect();
+        let mut stmt = tx
+            .prepare(&format!(
+                "INSERT INTO {table} (geom{names}) VALUES (?1{marks})"
+            ))
+            .map_err(sql_err)?;
+        for f in features {
+            let mut values: Vec<rusqlite::types::Value> =
+                vec![encode_gpkg_geometry(&f.geometry, srs, z).into()];
+            for (c, t) in columns.iter().zip(&types) {
+                use rusqlite::types::Value;
+                let not_a_number = |v: &str| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("{c}: {v:?} is not a number"),
+                    )
+                };
+                values.push(match (f.attributes.get(*c).filter(|v| !v.is_empty()), t) {
+                    (None, _) => Value::Null,
+                    (Some(v), ColumnType::Integer) => {
+                        Value::Integer(v.parse().map_err(|_| not_a_number(v))?)
+                    }
+                    (Some(v), ColumnType::Real) => {
+                        Value::Real(v.parse().map_err(|_| not_a_number(v))?)
+                    }
+                    (Some(v), ColumnType::Text) => Value::Text(v.clone()),
+                });
+            }
+            stmt.execute(rusqlite::params_from_iter(values))
+                .map_err(sql_err)?;
+        }
+    }
+    tx.commit().map_err(sql_err)
+}
+
+/// Names of the feature layers of a GeoPackage.
+pub fn list_gpkg_layers(path: &str) -> io::Result<Vec<String>> {
+    let conn = Connection::open(path).map_err(sql_err)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT table_name FROM gpkg_contents WHERE data_type = 'features' ORDER BY table_name",
+        )
+        .map_err(sql_err)?;
+    let names = stmt
+        .query_map([], |r| r.get(0))
+        .map_err(sql_err)?
+        .collect::<rusqlite::Result<Vec<String>>>()
+        .map_err(sql_err)?;
+    Ok(names)
+}
+
+/// EPSG code of a layer's SRS, if it has one.
+pub fn gpkg_layer_crs(path: &str, layer: &str) -> io::Result<Option<Crs>> {
+    let conn = Connection::open(path).map_err(sql_err)?;
+    let row: Option<(String, i64, String)> = conn
+        .query_row(
+            "SELECT s.organization, s.organization_coordsys_id, s.definition
+             FROM gpkg_geometry_columns g JOIN gpkg_spatial_ref_sys s ON g.srs_id = s.srs_id
+             WHERE g.table_name = ?1",
+            [layer],
+            |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)),
+        )
+        .optional()
+        .map_err(sql_err)?;
+    Ok(row.and_then(|(org, code, def)| {
+        if org.eq_ignore_ascii_case("EPSG") {
+            Some(Crs::from_epsg(code as u32))
+        } else if def.contains('[') {
+            Some(Crs::from_wkt(&def))
+        } else {
+            None
+        }
+    }))
+}
+
+/// Reads every feature of a layer. The layer name becomes the feature class
+/// and all non-geometry columns except the primary key become attributes.
+pub fn read_gpkg_layer(path: &str, layer: &str) -> io::Result<Vec<Feature<GpkgGeometry>>> {
+    let conn = Connection::open(path).map_err(sql_err)?;
+    let geom_col: String = conn
+        .query_row(
+            "SELECT column_name FROM gpkg_geometry_columns WHERE table_name = ?1",
+            [layer],
+            |r| r.get(0),
+        )
+        .optional()
+        .map_err(sql_err)?
+        .ok_or_else(|| invalid(format!("no feature layer named {layer}")))?;
+    let mut info = conn
+        .prepare(&format!("PRAGMA table_info({})", quote(layer)))
+        .map_err(sql_err)?;
+    let columns: Vec<(String, bool)> = info
+        .query_map([], |r| {
+            Ok((r.get::<_, String>(1)?, r.get::<_, i64>(5)? > 0))
+        })
+        .map_err(sql_err)?
+        .collect::<rusqlite::Result<_>>()
+        .map_err(sql_err)?;
+    let attrs: Vec<&str> = columns
+        .iter()
+        .filter(|(name, pk)| !pk && *name != geom_col)
+        .map(|(name, _)| name.as_str())
+        .collect();
+    let select: String = std::iter::once(quote(&geom_col))
+        .chain(attrs.iter().map(|a| quote(a)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut stmt = conn
+        .prepare(&format!("SELECT {select} FROM {}", quote(layer)))
+        .map_err(sql_err)?;
+    let mut rows = stmt.query([]).map_err(sql_err)?;
+    let mut features = Vec::new();
+    while let Some(row) = rows.next().map_err(sql_err)? {
+        let blob = match row.get_ref(0).map_err(sql_err)? {
+            ValueRef::Blob(b) => b,
+            _ => continue,
+        };
+        let mut attributes = BTreeMap::new();
+        for (i, name) in attrs.iter().enumerate() {
+            let value = match row.get_ref(i + 1).map_err(sql_err)? {
+                ValueRef::Null | ValueRef::Blob(_) => continue,
+                ValueRef::Integer(v) => v.to_string(),
+                ValueRef::Real(v) => v.to_string(),
+                ValueRef::Text(t) => String::from_utf8_lossy(t).into_owned(),
+            };
+            attributes.insert(name.to_string(), value);
+        }
+        features.push(Feature {
+            class: Some(layer.to_string()),
+            attributes,
+            geometry: decode_gpkg_geometry(blob)?,
+        });
+    }
+    Ok(features)
+}
+
+// --- survey data ------------------------------------------------------------
+
+fn point3(p: Point) -> Point3 {
+    Point3::new(p.x, p.y, 0.0)
+}
+
+fn point2(p: Point3) -> Point {
+    Point::new(p.x, p.y)
+}
+
+/// Writes survey points with `number`, `description` and `codes` columns.
+pub fn write_points_gpkg(
+    path: &str,
+    layer: &str,
+    points: &[SurveyPoint],
+    crs: &Crs,
+) -> io::Result<()> {
+    let features: Vec<Feature<GpkgGeometry>> = points
+        .iter()
+        .map(|p| {
+            let mut f = Feature::new(GpkgGeometry::Point(p.point));
+            if let Some(n) = p.number {
+                f.attributes.insert("number".into(), n.to_string());
+            }
+            if let Some(d) = &p.description {
+                f.attributes.insert("description".into(), d.clone());
+            }
+            if !p.codes.is_empty() {
+                f.attributes.insert("codes".into(), p.codes.join(" "));
+            }
+            f
+        })
+        .collect();
+    write_gpkg_layer(
+        path,
+        layer,
+        &features,
+        crs,
+        true,
+        &[("number", ColumnType::Integer)],
+    )
+}
+
+/// Writes polyline figures as line strings with their attributes.
+pub fn write_polylines_gpkg(
+    path: &str,
+    layer: &str,
+    polylines: &[Feature<Polyline>],
+    crs: &Crs,
+) -> io::Result<()> {
+    let features: Vec<Feature<GpkgGeometry>> = polylines
+        .iter()
+        .map(|f| Feature {
+            class: f.class.clone(),
+            attributes: f.attributes.clone(),
+            geometry: GpkgGeometry::LineString(
+                f.geometry.vertices.iter().copied().map(point3).collect(),
+            ),
+        })
+        .collect();
+    write_gpkg_layer(path, layer, &features, crs, false, &[])
+}
+
+/// Writes parcels as polygons with `name` and `area` columns. Curved edges
+/// are written as the chords of [`Parcel::to_polygon`] while `area` keeps
+/// the exact area.
+pub fn write_parcels_gpkg(
+    path: &str,
+    layer: &str,
+    parcels: &[Parcel],
+    crs: &Crs,
+) -> io::Result<()> {
+    let features: Vec<Feature<GpkgGeometry>> = parcels
+        .iter()
+        .map(|p| {
+            let ring = p.to_polygon().into_iter().map(point3).collect();
+            let mut f = Feature::new(GpkgGeometry::Polygon(vec![ring]));
+            if let Some(name) = &p.name {
+                f.attributes.insert("name".into(), name.clone());
+            }
+            f.attributes.insert("area".into(), p.area().to_string());
+            f
+        })
+        .collect();
+    write_gpkg_layer(
+        path,
+        layer,
+        &features,
+        crs,
+        false,
+        &[("area", ColumnType::Real)],
+    )
+}
+
+/// Writes the triangles of a surface as 3D polygons.
+pub fn write_surface_gpkg(path: &str, layer: &str, tin: &Tin, crs: &Crs) -> io::Result<()> {
+    let features: Vec<Feature<GpkgGeometry>> = tin
+        .triangles
+        .iter()
+        .map(|t| {
+            Feature::new(GpkgGeometry::Polygon(vec![t
+                .iter()
+                .map(|&i| tin.vertices[i])
+                .collect()]))
+        })
+        .collect();
+    write_gpkg_layer(path, layer, &features, crs, true, &[])
+}
+
+fn flatten(geom: GpkgGeometry, out: &mut Vec<GpkgGeometry>) {
+    match geom {
+        GpkgGeometry::Multi(parts) => parts.into_iter().for_each(|g| flatten(g, out)),
+        g => out.push(g),
+    }
+}
+
+/// Splits multi-part features into one feature per part.
+fn parts(features: Vec<Feature<GpkgGeometry>>) -> impl Iterator<Item = Feature<GpkgGeometry>> {
+    features.into_iter().flat_map(|f| {
+        let mut geoms = Vec::new();
+        flatten(f.geometry, &mut geoms);
+        geoms.into_iter().map(move |geometry| Feature {
+            class: f.class.clone(),
+            attributes: f.attributes.clone(),
+            geometry,
+        })
+    })
+}
+
+/// Reads the points of a layer with their attributes.
+pub fn read_points_gpkg(path: &str, layer: &str) -> io::Result<Vec<Feature<Point3>>> {
+    Ok(parts(read_gpkg_layer(path, layer)?)
+        .filter_map(|f| match f.geometry {
+            GpkgGeometry::Point(p) => Some(Feature {
+                class: f.class,
+                attributes: f.attributes,
+                geometry: p,
+            }),
+            _ => None,
+        })
+        .collect())
+}
+
+/// Reads the line strings of a layer as polylines.
+pub fn read_polylines_gpkg(path: &str, layer: &str) -> io::Result<Vec<Feature<Polyline>>> {
+    Ok(parts(read_gpkg_layer(path, layer)?)
+        .filter_map(|f| match f.geometry {
+            GpkgGeometry::LineString(pts) => Some(Feature {
+                class: f.class,
+                attributes: f.attributes,
+                geometry: Polyline::new(pts.into_iter().map(point2).collect()),
+            }),
+            _ => None,
+        })
+        .collect())
+}
+
+/// Reads the exterior rings of a layer's polygons.
+pub fn read_polygons_gpkg(path: &str, layer: &str) -> io::Result<Vec<Feature<Vec<Point>>>> {
+    Ok(parts(read_gpkg_layer(path, layer)?)
+        .filter_map(|f| match f.geometry {
+            GpkgGeometry::Polygon(rings) => Some(Feature {
+                class: f.class,
+                attributes: f.attributes,
+                geometry: rings.into_iter().next()?.into_iter().map(point2).collect(),
+            }),
+            _ => None,
+        })
+        .collect())
+}
+
+/// Reads polygons as parcels, naming them from a `name` attribute.
+pub fn read_parcels_gpkg(path: &str, layer: &str) -> io::Result<Vec<Parcel>> {
+    Ok(read_polygons_gpkg(path, layer)?
+        .into_iter()
+        .map(|f| {
+            let mut parcel = Parcel::new(f.geometry);
+            parcel.name = f
+                .attributes
+                .get("name")
+                .or_else(|| f.attributes.get("NAME"))
+                .cloned();
+            parcel
+        })
+        .collect())
+}
+
+/// Rebuilds a surface from a layer of triangles, merging shared vertices.
+pub fn read_surface_gpkg(path: &str, layer: &str) -> io::Result<Tin> {
+    let mut index: HashMap<[u64; 3], usize> = HashMap::new();
+    let mut tin = Tin {
+        vertices: Vec::new(),
+        triangles: Vec::new(),
+    };
+    for f in parts(read_gpkg_layer(path, layer)?) {
+        let GpkgGeometry::Polygon(rings) = f.geometry else {
+            continue;
+        };
+        let Some([a, b, c]) = rings
+            .first()
+            .and_then(|r| <[Point3; 3]>::try_from(r.as_slice()).ok())
+        else {
+            continue;
+        };
+        let mut tri = [0; 3];
+        for (slot, p) in tri.iter_mut().zip([a, b, c]) {
+            *slot = *index
+                .entry([p.x.to_bits(), p.y.to_bits(), p.z.to_bits()])
+                .or_insert_with(|| {
+                    tin.vertices.push(p);
+                    tin.vertices.len() - 1
+                });
+        }
+        tin.triangles.push(tri);
+    }
+    Ok(tin)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn geometry_blob_round_trip() {
+        let poly = GpkgGeometry::Polygon(vec![vec![
+            Point3::new(0.0, 0.0, 1.0),
+            Point3::new(4.0, 0.0, 2.0),
+            Point3::new(4.0, 3.0, 3.0),
+        ]]);
+        let blob = encode_gpkg_geometry(&poly, 2193, true);
+        assert_eq!(&blob[..4], &[b'G', b'P', 0, 3]);
+        assert_eq!(i32::from_le_bytes(blob[4..8].try_into().unwrap()), 2193);
+        assert_eq!(f64::from_le_bytes(blob[16..24].try_into().unwrap()), 4.0);
+        assert_eq!(decode_gpkg_geometry(&blob).unwrap(), poly);
+
+        let line =
+            GpkgGeometry::LineString(vec![Point3::new(1.0, 2.0, 0.0), Point3::new(3.0, 4.0, 0.0)]);
+        let multi = GpkgGeometry::Multi(vec![line.clone(), line]);
+        let blob = encode_gpkg_geometry(&multi, -1, false);
+        assert_eq!(decode_gpkg_geometry(&blob).unwrap(), multi);
+        assert!(decode_gpkg_geometry(b"XX").is_err());
+    }
+
+    #[test]
+    fn layers_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("survey.gpkg");
+        let path = path.to_str().unwrap();
+        let crs = Crs::from_epsg(2193);
+
+        let points = vec![
+            SurveyPoint::new(
+                Some(1),
+                Point3::new(10.0, 20.0, 5.0),
+                Some("iron pin".into()),
+                vec!["IP".into(), "BDY".into()],
+            ),
+            SurveyPoint::new(None, Point3::new(11.0, 21.0, 6.0), None, Vec::new()),
+        ];
+        write_points_gpkg(path, "points", &points, &crs).unwrap();
+        let mut fence = Feature::new(Polyline::new(vec![
+            Point::new(0.0, 0.0),
+            Point::new(5.0, 5.0),
+        ]));
+        fence.attributes.insert("code".into(), "FENCE".into());
+        write_polylines_gpkg(path, "figures", &[fence.clone()], &crs).unwrap();
+        let mut lot = Parcel::new(vec![
+            Point::new(0.0, 0.0),
+            Point::new(10.0, 0.0),
+            Point::new(10.0, 10.0),
+        ]);
+        lot.name = Some("Lot 1".into());
+        write_parcels_gpkg(path, "parcels", &[lot], &crs).unwrap();
+        let tin = Tin::from_points(vec![
+            Point3::new(0.0, 0.0, 1.0),
+            Point3::new(1.0, 0.0, 2.0),
+            Point3::new(0.0, 1.0, 3.0),
+            Point3::new(1.0, 1.0, 4.0),
+        ]);
+        write_surface_gpkg(path, "surface", &tin, &crs).unwrap();
+
+        assert_eq!(
+            list_gpkg_layers(path).unwrap(),
+            vec!["figures", "parcels", "points", "surface"]
+        );
+        assert_eq!(
+            gpkg_layer_crs(path, "points").unwrap().unwrap().epsg(),
+            Some(2193)
+        );
+
+        let read = read_points_gpkg(path, "points").unwrap();
+        assert_eq!(read.len(), 2);
+        assert_eq!(read[0].geometry, points[0].point);
+        assert_eq!(read[0].attributes["number"], "1");
+        assert_eq!(read[0].attributes["codes"], "IP BDY");
+        assert!(!read[1].attributes.contains_key("number"));
+        assert_eq!(read[0].class.as_deref(), Some("points"));
+
+        let lines = read_polylines_gpkg(path, "figures").unwrap();
+        assert_eq!(lines[0].geometry.vertices, fence.geometry.vertices);
+        assert_eq!(lines[0].attributes["code"], "FENCE");
+
+        let parcels = read_parcels_gpkg(path, "parcels").unwrap();
+        assert_eq!(parcels[0].name.as_deref(), Some("Lot 1"));
+        assert!((parcels[0].area() - 50.0).abs() < 1e-9);
+
+        let surface = read_surface_gpkg(path, "surface").unwrap();
+        assert_eq!(surface.vertices.len(), 4);
+        assert_eq!(surface.triangles.len(), tin.triangles.len());
+
+        // user attributes stay text unless declared numeric
+        let mut code = Feature::new(GpkgGeometry::Point(Point3::new(0.0, 0.0, 0.0)));
+        code.attributes.insert("code".into(), "007".into());
+        code.attributes.insert("count".into(), "3".into());
+        let types = [("count", ColumnType::Integer)];
+        write_gpkg_layer(path, "codes", &[code.clone()], &crs, false, &types).unwrap();
+        let read = read_gpkg_layer(path, "codes").unwrap();
+        assert_eq!(read[0].attributes, code.attributes);
+        let conn = Connection::open(path).unwrap();
+        let kinds: (String, String) = conn
+            .query_row("SELECT typeof(code), typeof(count) FROM codes", [], |r| {
+                Ok((r.get(0)?, r.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(kinds, ("text".into(), "integer".into()));
+        code.attributes.insert("count".into(), "many".into());
+        assert!(write_gpkg_layer(path, "codes", &[code], &crs, false, &types).is_err());
+
+        // CRSs without an EPSG code keep their WKT
+        let wkt = r#"LOCAL_CS["site grid",LOCAL_DATUM["site",0],UNIT["metre",1]]"#;
+        write_polylines_gpkg(path, "local", &[fence.clone()], &Crs::from_wkt(wkt)).unwrap();
+        assert_eq!(
+            gpkg_layer_crs(path, "local").unwrap().unwrap().definition(),
+            wkt
+        );
+
+        // rewriting a layer replaces it
+        write_points_gpkg(path, "points", &points[..1], &crs).unwrap();
+        assert_eq!(read_points_gpkg(path, "points").unwrap().len(), 1);
+        assert_eq!(list_gpkg_layers(path).unwrap().len(), 6);
+    }
+}
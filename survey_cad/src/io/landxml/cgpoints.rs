@@ -0,0 +1,211 @@
+//! `<CgPoints>` with codes and point groups.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io;
+
+use roxmltree::Document;
+use serde::{Deserialize, Serialize};
+
+use super::{escape, fmt_ne, parse_xml, point_ne, today, write_document_start};
+use crate::geometry::Point3;
+use crate::io::{read_to_string, write_string};
+use crate::surveying::SurveyPoint;
+
+/// Named coordinate geometry point.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CgPoint {
+    pub name: String,
+    pub point: Point3,
+    #[serde(default)]
+    pub code: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+impl CgPoint {
+    pub fn new(name: impl Into<String>, point: Point3) -> Self {
+        Self {
+            name: name.into(),
+            point,
+            code: None,
+            description: None,
+        }
+    }
+}
+
+impl From<&SurveyPoint> for CgPoint {
+    fn from(p: &SurveyPoint) -> Self {
+        Self {
+            name: p.number.map(|n| n.to_string()).unwrap_or_default(),
+            point: p.point,
+            code: (!p.codes.is_empty()).then(|| p.codes.join(" ")),
+            description: p.description.clone(),
+        }
+    }
+}
+
+impl From<&CgPoint> for SurveyPoint {
+    fn from(p: &CgPoint) -> Self {
+        SurveyPoint::new(
+            p.name.parse().ok(),
+            p.point,
+            p.description.clone(),
+            p.code
+                .as_deref()
+                .map(|c| c.split_whitespace().map(str::to_string).collect())
+                .unwrap_or_default(),
+        )
+    }
+}
+
+/// Named group of CgPoints, written as a nested `<CgPoints>` collection.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CgPointGroup {
+    pub name: String,
+    pub points: Vec<String>,
+}
+
+/// Points and groups of a document's `<CgPoints>` collections.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CgPointSet {
+    pub points: Vec<CgPoint>,
+    pub groups: Vec<CgPointGroup>,
+}
+
+impl CgPointSet {
+    /// Coordinates by point name, used to resolve `pntRef` attributes.
+    pub fn lookup(&self) -> HashMap<String, Point3> {
+        self.points
+            .iter()
+            .filter(|p| !p.name.is_empty())
+            .map(|p| (p.name.clone(), p.point))
+            .collect()
+    }
+}
+
+/// Collects every `<CgPoint>` carrying coordinates and every named nested
+/// `<CgPoints>` collection of the document.
+pub(crate) fn parse_cg_points(doc: &Document) -> CgPointSet {
+    let mut set = CgPointSet::default();
+    for node in doc.descendants().filter(|n| n.has_tag_name("CgPoint")) {
+        let Some(point) = point_ne(node) else {
+            continue;
+        };
+        set.points.push(CgPoint {
+            name: node.attribute("name").unwrap_or_default().to_string(),
+            point,
+            code: node.attribute("code").map(str::to_string),
+            description: node.attribute("desc").map(str::to_string),
+        });
+    }
+    for node in doc.descendants().filter(|n| n.has_tag_name("CgPoints")) {
+        let nested = node
+            .parent_element()
+            .is_some_and(|p| p.has_tag_name("CgPoints"));
+        let Some(name) = node.attribute("name").filter(|_| nested) else {
+            continue;
+        };
+        set.groups.push(CgPointGroup {
+            name: name.to_string(),
+            points: node
+                .children()
+                .filter(|c| c.has_tag_name("CgPoint"))
+                .filter_map(|c| c.attribute("pntRef").or_else(|| c.attribute("name")))
+                .map(str::to_string)
+                .collect(),
+        });
+    }
+    set
+}
+
+fn write_cg_point(xml: &mut String, indent: &str, p: &CgPoint) {
+    write!(xml, "{indent}<CgPoint").unwrap();
+    if !p.name.is_empty() {
+        write!(xml, " name=\"{}\"", escape(&p.name)).unwrap();
+    }
+    if let Some(code) = &p.code {
+        write!(xml, " code=\"{}\"", escape(code)).unwrap();
+    }
+    if let Some(desc) = &p.description {
+        write!(xml, " desc=\"{}\"", escape(desc)).unwrap();
+    }
+    writeln!(xml, ">{}</CgPoint>", fmt_ne(p.point)).unwrap();
+}
+
+/// Writes a `<CgPoints>` collection with groups as nested collections that
+/// reference their members by name.
+pub(crate) fn write_cg_points(xml: &mut String, points: &[CgPoint], groups: &[CgPointGroup]) {
+    if points.is_empty() && groups.is_empty() {
+        return;
+    }
+    writeln!(xml, "  <CgPoints>").unwrap();
+    for p in points {
+        write_cg_point(xml, "    ", p);
+    }
+    for g in groups {
+        writeln!(xml, "    <CgPoints name=\"{}\">", escape(&g.name)).unwrap();
+        for name in &g.points {
+            writeln!(xml, "      <CgPoint pntRef=\"{}\"/>", escape(name)).unwrap();
+        }
+        writeln!(xml, "    </CgPoints>").unwrap();
+    }
+    writeln!(xml, "  </CgPoints>").unwrap();
+}
+
+/// Reads the CgPoints and point groups of a LandXML file.
+pub fn read_landxml_points(path: &str) -> io::Result<CgPointSet> {
+    let xml = read_to_string(path)?;
+    let doc = parse_xml(&xml)?;
+    Ok(parse_cg_points(&doc))
+}
+
+/// Writes CgPoints and point groups to a LandXML 1.2 file.
+pub fn write_landxml_points(
+    path: &str,
+    points: &[CgPoint],
+    groups: &[CgPointGroup],
+) -> io::Result<()> {
+    let mut xml = String::new();
+    write_document_start(&mut xml, today());
+    write_cg_points(&mut xml, points, groups);
+    writeln!(&mut xml, "</LandXML>").unwrap();
+    write_string(path, &xml)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn points_and_groups_round_trip() {
+        let mut a = CgPoint::new("1", Point3::new(100.0, 200.0, 10.0));
+        a.code = Some("IP FENCE".into());
+        a.description = Some("iron & pin".into());
+        let b = CgPoint::new("CP2", Point3::new(150.0, 250.0, 12.5));
+        let group = CgPointGroup {
+            name: "Control".into(),
+            points: vec!["CP2".into()],
+        };
+        let mut xml = String::new();
+        write_document_start(&mut xml, today());
+        write_cg_points(
+            &mut xml,
+            &[a.clone(), b.clone()],
+            std::slice::from_ref(&group),
+        );
+        xml.push_str("</LandXML>\n");
+        assert!(xml.contains("<CgPoint name=\"CP2\">250 150 12.5</CgPoint>"));
+
+        let doc = Document::parse(&xml).unwrap();
+        let set = parse_cg_points(&doc);
+        assert_eq!(set.points, vec![a.clone(), b]);
+        assert_eq!(set.groups, vec![group]);
+        assert_eq!(set.lookup()["CP2"], Point3::new(150.0, 250.0, 12.5));
+
+        let sp = SurveyPoint::from(&a);
+        assert_eq!(sp.number, Some(1));
+        assert_eq!(sp.codes, vec!["IP", "FENCE"]);
+        assert_eq!(CgPoint::from(&sp), a);
+    }
+}
@@ -0,0 +1,238 @@
+//! `<CoordGeom>` geometry shared by parcels and plan features.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+
+use roxmltree::Node;
+
+use super::{fmt_ne, numbers, point_ne};
+use crate::geometry::{Arc, PathSegment, Point, Point3};
+use crate::parcel::ParcelEdge;
+
+/// Straight or circular segment of a `<CoordGeom>`. The bulge follows the
+/// DXF convention and is zero for straight segments.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Segment {
+    pub start: Point3,
+    pub end: Point3,
+    pub bulge: f64,
+}
+
+impl Segment {
+    fn edge(&self) -> ParcelEdge {
+        ParcelEdge::new(
+            Point::new(self.start.x, self.start.y),
+            Point::new(self.end.x, self.end.y),
+            self.bulge,
+        )
+    }
+}
+
+/// Coordinate of a `<Start>`, `<Center>`, `<End>` or `<PI>` child, taken
+/// from its text or from the CgPoint named by its `pntRef`.
+fn child_point(node: Node, tag: &str, points: &HashMap<String, Point3>) -> Option<Point3> {
+    let child = node.children().find(|c| c.has_tag_name(tag))?;
+    point_ne(child).or_else(|| points.get(child.attribute("pntRef")?).copied())
+}
+
+/// Bulge of an arc from its start, centre and end and its direction.
+fn curve_bulge(start: Point3, center: Point3, end: Point3, clockwise: bool) -> f64 {
+    let c = Point::new(center.x, center.y);
+    let angle = |p: Point3| (p.y - c.y).atan2(p.x - c.x);
+    let (a0, a1) = (angle(start), angle(end));
+    if clockwise {
+        -PathSegment::from_arc(&Arc::new(c, 0.0, a1, a0)).bulge()
+    } else {
+        PathSegment::from_arc(&Arc::new(c, 0.0, a0, a1)).bulge()
+    }
+}
+
+fn push_list(list: Node, dims: usize, segments: &mut Vec<Segment>) {
+    let pts: Vec<Point3> = numbers(list)
+        .chunks_exact(dims)
+        .map(|c| Point3::new(c[1], c[0], if dims == 3 { c[2] } else { 0.0 }))
+        .collect();
+    for w in pts.windows(2) {
+        segments.push(Segment {
+            start: w[0],
+            end: w[1],
+            bulge: 0.0,
+        });
+    }
+}
+
+/// Reads the lines, curves and point lists of a `<CoordGeom>`. Spirals are
+/// taken as their chord. `points` resolves `pntRef` attributes.
+pub fn read_coord_geom(node: Node, points: &HashMap<String, Point3>) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    for child in node.children().filter(|c| c.is_element()) {
+        match child.tag_name().name() {
+            "Line" | "Spiral" => {
+                if let (Some(start), Some(end)) = (
+                    child_point(child, "Start", points),
+                    child_point(child, "End", points),
+                ) {
+                    segments.push(Segment {
+                        start,
+                        end,
+                        bulge: 0.0,
+                    });
+                }
+            }
+            "Curve" => {
+                if let (Some(start), Some(center), Some(end)) = (
+                    child_point(child, "Start", points),
+                    child_point(child, "Center", points),
+                    child_point(child, "End", points),
+                ) {
+                    let cw = child.attribute("rot") == Some("cw");
+                    segments.push(Segment {
+                        start,
+                        end,
+                        bulge: curve_bulge(start, center, end, cw),
+                    });
+                }
+            }
+            "IrregularLine" => {
+                if let Some(list) = child.children().find(|c| c.has_tag_name("PntList3D")) {
+                    push_list(list, 3, &mut segments);
+                } else if let Some(list) = child.children().find(|c| c.has_tag_name("PntList2D")) {
+                    push_list(list, 2, &mut segments);
+                }
+            }
+            "PntList3D" => push_list(child, 3, &mut segments),
+            "PntList2D" => push_list(child, 2, &mut segments),
+            _ => {}
+        }
+    }
+    segments
+}
+
+/// Writes `segments` as `<Line>` and `<Curve>` elements of a `<CoordGeom>`
+/// indented by `indent` spaces.
+pub fn write_coord_geom(xml: &mut String, indent: usize, segments: &[Segment]) {
+    let pad = " ".repeat(indent);
+    writeln!(xml, "{pad}<CoordGeom>").unwrap();
+    for s in segments {
+        let edge = s.edge();
+        match (edge.center(), edge.radius()) {
+            (Some(c), Some(r)) => {
+                let rot = if s.bulge < 0.0 { "cw" } else { "ccw" };
+                let center = Point3::new(c.x, c.y, s.start.z);
+                writeln!(
+                    xml,
+                    "{pad}  <Curve rot=\"{rot}\" radius=\"{r}\" length=\"{}\">",
+                    edge.length()
+                )
+                .unwrap();
+                writeln!(xml, "{pad}    <Start>{}</Start>", fmt_ne(s.start)).unwrap();
+                writeln!(xml, "{pad}    <Center>{}</Center>", fmt_ne(center)).unwrap();
+                writeln!(xml, "{pad}    <End>{}</End>", fmt_ne(s.end)).unwrap();
+                writeln!(xml, "{pad}  </Curve>").unwrap();
+            }
+            _ => {
+                writeln!(xml, "{pad}  <Line>").unwrap();
+                writeln!(xml, "{pad}    <Start>{}</Start>", fmt_ne(s.start)).unwrap();
+                writeln!(xml, "{pad}    <End>{}</End>", fmt_ne(s.end)).unwrap();
+                writeln!(xml, "{pad}  </Line>").unwrap();
+            }
+        }
+    }
+    writeln!(xml, "{pad}</CoordGeom>").unwrap();
+}
+
+/// Segments joining `vertices`, with the closing segment when `closed`.
+pub fn segments_from_vertices(vertices: &[Point3], bulges: &[f64], closed: bool) -> Vec<Segment> {
+    let n = vertices.len();
+    let count = if closed && n >= 2 {
+        n
+    } else {
+        n.saturating_sub(1)
+    };
+    (0..count)
+        .map(|i| Segment {
+            start: vertices[i],
+            end: vertices[(i + 1) % n],
+            bulge: bulges.get(i).copied().unwrap_or(0.0),
+        })
+        .collect()
+}
+
+/// Chains segments into vertices and bulges. The chain is closed when the
+/// last segment ends where the first one starts.
+pub fn vertices_from_segments(segments: &[Segment]) -> (Vec<Point3>, Vec<f64>, bool) {
+    let Some(first) = segments.first() else {
+        return (Vec::new(), Vec::new(), false);
+    };
+    let mut vertices = vec![first.start];
+    let mut bulges = Vec::with_capacity(segments.len());
+    for s in segments {
+        vertices.push(s.end);
+        bulges.push(s.bulge);
+    }
+    let last = vertices[vertices.len() - 1];
+    let closed = segments.len() > 1 && last.x == first.start.x && last.y == first.start.y;
+    if closed {
+        vertices.pop();
+    } else {
+        bulges.push(0.0);
+    }
+    if bulges.iter().all(|b| *b == 0.0) {
+        bulges.clear();
+    }
+    (vertices, bulges, closed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_lines_curves_and_point_refs() {
+        let xml = r#"<CoordGeom>
+            <Line><Start pntRef="A"/><End>0 10</End></Line>
+            <Curve rot="ccw" radius="5"><Start>0 10</Start><Center>5 10</Center><End>10 10</End></Curve>
+            <IrregularLine><PntList2D>10 10 10 0 0 0</PntList2D></IrregularLine>
+        </CoordGeom>"#;
+        let doc = roxmltree::Document::parse(xml).unwrap();
+        let points = HashMap::from([("A".to_string(), Point3::new(0.0, 0.0, 0.0))]);
+        let segs = read_coord_geom(doc.root_element(), &points);
+        assert_eq!(segs.len(), 4);
+        assert_eq!(segs[0].end, Point3::new(10.0, 0.0, 0.0));
+        assert!((segs[1].bulge - 1.0).abs() < 1e-12);
+        let (vertices, bulges, closed) = vertices_from_segments(&segs);
+        assert!(closed);
+        assert_eq!(vertices.len(), 4);
+        assert_eq!(bulges.len(), 4);
+
+        let mut out = String::new();
+        write_coord_geom(
+            &mut out,
+            0,
+            &segments_from_vertices(&vertices, &bulges, closed),
+        );
+        let doc = roxmltree::Document::parse(&out).unwrap();
+        let again = read_coord_geom(doc.root_element(), &HashMap::new());
+        for (a, b) in again.iter().zip(&segs) {
+            assert!((a.bulge - b.bulge).abs() < 1e-9);
+            assert!((a.end.x - b.end.x).abs() < 1e-9 && (a.end.y - b.end.y).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn closed_two_vertex_circle_round_trips() {
+        let xml = r#"<CoordGeom>
+            <Curve rot="ccw" radius="5"><Start>0 0</Start><Center>0 5</Center><End>0 10</End></Curve>
+            <Curve rot="ccw" radius="5"><Start>0 10</Start><Center>0 5</Center><End>0 0</End></Curve>
+        </CoordGeom>"#;
+        let doc = roxmltree::Document::parse(xml).unwrap();
+        let segs = read_coord_geom(doc.root_element(), &HashMap::new());
+        let (vertices, bulges, closed) = vertices_from_segments(&segs);
+        assert!(closed);
+        assert_eq!(vertices.len(), 2);
+        let again = segments_from_vertices(&vertices, &bulges, closed);
+        assert_eq!(again.len(), 2);
+        assert_eq!(again[1].end, segs[1].end);
+        assert!((again[1].bulge - segs[1].bulge).abs() < 1e-12);
+    }
+}
@@ -1,3 +1,12 @@
+//! LandXML import and export.
+//!
+//! The single-object readers and writers in this module (surfaces,
+//! alignments, profiles, cross sections, superelevation) write easting before
+//! northing. The submodules added for CgPoints, Parcels, PlanFeatures,
+//! PipeNetworks, Survey and whole projects follow the LandXML 1.2 schema
+//! order of northing, easting, elevation so files can be exchanged with
+//! Civil 3D and 12d.
+
 use std::fmt::Write as _;
 use std::io;
 
@@ -6,17 +15,84 @@ use roxmltree::Document;
 use crate::alignment::{HorizontalAlignment, HorizontalElement};
 use crate::corridor::CrossSection;
 use crate::dtm::Tin;
+use crate::geoid::VerticalDatum;
 use crate::geometry::{Arc, Point, Point3};
 use crate::superelevation::SuperelevationPoint;
 
 use super::{read_to_string, write_string};
 
+pub mod cgpoints;
+pub use cgpoints::{
+    read_landxml_points, write_landxml_points, CgPoint, CgPointGroup, CgPointSet,
+};
+
+pub mod coord_geom;
+
+pub mod parcels;
+pub use parcels::{
+    read_landxml_parcels, read_landxml_plan_features, write_landxml_parcels,
+    write_landxml_plan_features, PlanFeature,
+};
+
+pub mod pipes;
+pub use pipes::{
+    read_landxml_pipe_networks, write_landxml_pipe_networks, NetworkPipe, PipeNetwork,
+    PipeStructure,
+};
+
+pub mod survey;
+pub use survey::{import_landxml_survey, read_landxml_survey, write_landxml_survey};
+
+pub mod project;
+pub use project::{
+    project_from_landxml, project_to_landxml, read_landxml_project, validate_landxml,
+    write_landxml_project,
+};
+
 /// Additional attributes found in LandXML files.
 #[derive(Debug, Default, Clone)]
 pub struct LandxmlExtras {
     pub units: Option<String>,
     pub style: Option<String>,
     pub description: Option<String>,
+    /// Written as the `verticalDatum`/`geoidName` attributes of
+    /// `<CoordinateSystem>`.
+    pub vertical_datum: Option<VerticalDatum>,
+}
+
+fn read_vertical_datum(doc: &Document) -> Option<VerticalDatum> {
+    let cs = doc
+        .descendants()
+        .find(|n| n.has_tag_name("CoordinateSystem"))?;
+    let name = cs.attribute("verticalDatum")?;
+    Some(VerticalDatum::new(name, cs.attribute("geoidName")))
+}
+
+fn write_header(xml: &mut String, extras: Option<&LandxmlExtras>) {
+    let Some(ex) = extras else {
+        return;
+    };
+    if let Some(u) = &ex.units {
+        writeln!(xml, "  <Units linearUnit=\"{u}\"/>").unwrap();
+    }
+    write_coordinate_system(xml, extras);
+}
+
+/// Writes the `<CoordinateSystem>` element carrying the vertical datum.
+fn write_coordinate_system(xml: &mut String, extras: Option<&LandxmlExtras>) {
+    if let Some(vd) = extras.and_then(|ex| ex.vertical_datum.as_ref()) {
+        let geoid = vd
+            .geoid_model
+            .as_deref()
+            .map(|g| format!(" geoidName=\"{g}\""))
+            .unwrap_or_default();
+        writeln!(
+            xml,
+            "  <CoordinateSystem verticalDatum=\"{}\"{geoid}/>",
+            vd.name
+        )
+        .unwrap();
+    }
 }
 
 /// Reads a LandXML file containing a surface and returns it and any extra metadata.
@@ -67,6 +143,7 @@ pub fn read_landxml_surface(path: &str) -> io::Result<(Tin, LandxmlExtras)> {
             .find(|n| n.has_tag_name("Surface"))
             .and_then(|n| n.attribute("desc"))
             .map(|s| s.to_string()),
+        vertical_datum: read_vertical_datum(&doc),
     };
     Ok((
         Tin {
@@ -82,11 +159,7 @@ pub fn write_landxml_surface(path: &str, tin: &Tin, extras: Option<&LandxmlExtra
     let mut xml = String::new();
     writeln!(&mut xml, "<?xml version=\"1.0\"?>").unwrap();
     writeln!(&mut xml, "<LandXML>").unwrap();
-    if let Some(ex) = extras {
-        if let Some(u) = &ex.units {
-            writeln!(&mut xml, "  <Units linearUnit=\"{u}\"/>").unwrap();
-        }
-    }
+    write_header(&mut xml, extras);
     writeln!(&mut xml, "  <Surfaces>").unwrap();
     let style_attr = extras
         .and_then(|e| e.style.as_deref())
@@ -325,6 +398,7 @@ pub fn read_landxml_alignment(path: &str) -> io::Result<(HorizontalAlignment, La
             .find(|n| n.has_tag_name("Alignment"))
             .and_then(|n| n.attribute("desc"))
             .map(|s| s.to_string()),
+        vertical_datum: read_vertical_datum(&doc),
     };
     Ok((HorizontalAlignment { elements }, extras))
 }
@@ -338,11 +412,7 @@ pub fn write_landxml_alignment(
     let mut xml = String::new();
     writeln!(&mut xml, "<?xml version=\"1.0\"?>").unwrap();
     writeln!(&mut xml, "<LandXML>").unwrap();
-    if let Some(ex) = extras {
-        if let Some(u) = &ex.units {
-            writeln!(&mut xml, "  <Units linearUnit=\"{u}\"/>").unwrap();
-        }
-    }
+    write_header(&mut xml, extras);
     writeln!(&mut xml, "  <Alignments>").unwrap();
     let style_attr = extras
         .and_then(|e| e.style.as_deref())
@@ -546,6 +616,7 @@ pub fn read_landxml_cross_sections(path: &str) -> io::Result<(Vec<CrossSection>,
             .map(|s| s.to_string()),
         style: None,
         description: None,
+        vertical_datum: read_vertical_datum(&doc),
     };
     Ok((sections, extras))
 }
@@ -559,11 +630,7 @@ pub fn write_landxml_cross_sections(
     let mut xml = String::new();
     writeln!(&mut xml, "<?xml version=\"1.0\"?>").unwrap();
     writeln!(&mut xml, "<LandXML>").unwrap();
-    if let Some(ex) = extras {
-        if let Some(u) = &ex.units {
-            writeln!(&mut xml, "  <Units linearUnit=\"{u}\"/>").unwrap();
-        }
-    }
+    write_header(&mut xml, extras);
     writeln!(&mut xml, "  <CrossSections>").unwrap();
     for sec in sections {
         writeln!(&mut xml, "    <CrossSection sta=\"{}\">", sec.station).unwrap();
@@ -634,3 +701,137 @@ pub fn write_landxml_superelevation(path: &str, table: &[SuperelevationPoint]) -
     writeln!(&mut xml, "</LandXML>").unwrap();
     write_string(path, &xml)
 }
+
+fn parse_xml(xml: &str) -> io::Result<Document<'_>> {
+    Document::parse(xml).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Escapes `&`, `<`, `>` and quotes for use in attributes and text.
+pub(crate) fn escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Whitespace separated numbers of an element's text.
+pub(crate) fn numbers(node: roxmltree::Node) -> Vec<f64> {
+    node.text()
+        .unwrap_or_default()
+        .split_whitespace()
+        .filter_map(|s| s.parse().ok())
+        .collect()
+}
+
+/// Parses a numeric attribute, failing on values that aren't numbers.
+pub(crate) fn attr_f64(node: roxmltree::Node, name: &str) -> io::Result<Option<f64>> {
+    node.attribute(name)
+        .map(|v| {
+            v.trim().parse().map_err(|_| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid {name} \"{v}\" on <{}>", node.tag_name().name()),
+                )
+            })
+        })
+        .transpose()
+}
+
+/// Reads a `northing easting [elevation]` coordinate from an element.
+pub(crate) fn point_ne(node: roxmltree::Node) -> Option<Point3> {
+    match numbers(node)[..] {
+        [n, e, z, ..] => Some(Point3::new(e, n, z)),
+        [n, e] => Some(Point3::new(e, n, 0.0)),
+        _ => None,
+    }
+}
+
+/// Formats a coordinate as `northing easting elevation`.
+pub(crate) fn fmt_ne(p: Point3) -> String {
+    format!("{} {} {}", p.y, p.x, p.z)
+}
+
+/// Angular units declared by a LandXML `<Units>` element.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AngularUnit {
+    #[default]
+    Radians,
+    DecimalDegrees,
+    /// Packed degrees, minutes and seconds such as `123.4530`.
+    DecimalDms,
+    Grads,
+}
+
+impl AngularUnit {
+    /// Parses a LandXML `angularType`, defaulting to radians.
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "decimal degrees" => AngularUnit::DecimalDegrees,
+            "decimal dd.mm.ss" => AngularUnit::DecimalDms,
+            "grads" => AngularUnit::Grads,
+            _ => AngularUnit::Radians,
+        }
+    }
+
+    /// Converts a value in this unit to radians.
+    pub fn to_radians(self, v: f64) -> f64 {
+        match self {
+            AngularUnit::Radians => v,
+            AngularUnit::DecimalDegrees => v.to_radians(),
+            AngularUnit::DecimalDms => {
+                let sign = v.signum();
+                let v = v.abs();
+                let d = v.trunc();
+                let m = ((v - d) * 100.0 + 1e-9).trunc();
+                let s = ((v - d) * 100.0 - m) * 100.0;
+                sign * (d + m / 60.0 + s / 3600.0).to_radians()
+            }
+            AngularUnit::Grads => v * std::f64::consts::PI / 200.0,
+        }
+    }
+}
+
+/// Angular and direction units of a document's `<Metric>` or `<Imperial>`
+/// units.
+pub(crate) fn angular_units(doc: &Document) -> (AngularUnit, AngularUnit) {
+    let units = doc
+        .descendants()
+        .find(|n| n.has_tag_name("Metric") || n.has_tag_name("Imperial"));
+    let get = |name| {
+        units
+            .and_then(|n| n.attribute(name))
+            .map(AngularUnit::parse)
+            .unwrap_or_default()
+    };
+    (get("angularUnit"), get("directionUnit"))
+}
+
+/// Writes the XML declaration, the `<LandXML>` start tag with the schema
+/// namespace and metric units with angles in radians.
+pub(crate) fn write_document_start(xml: &mut String, date: chrono::NaiveDate) {
+    writeln!(xml, "<?xml version=\"1.0\" encoding=\"UTF-8\"?>").unwrap();
+    writeln!(
+        xml,
+        "<LandXML xmlns=\"http://www.landxml.org/schema/LandXML-1.2\" version=\"1.2\" date=\"{}\" time=\"00:00:00\">",
+        date.format("%Y-%m-%d")
+    )
+    .unwrap();
+    writeln!(
+        xml,
+        "  <Units>\n    <Metric areaUnit=\"squareMeter\" linearUnit=\"meter\" volumeUnit=\"cubicMeter\" temperatureUnit=\"celsius\" pressureUnit=\"milliBars\" angularUnit=\"radians\" directionUnit=\"radians\"/>\n  </Units>"
+    )
+    .unwrap();
+}
+
+/// Date used for new documents.
+pub(crate) fn today() -> chrono::NaiveDate {
+    chrono::Utc::now().date_naive()
+}
@@ -0,0 +1,247 @@
+//! `<Parcels>` and `<PlanFeatures>` described by `<CoordGeom>` elements.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io;
+
+use roxmltree::Document;
+use serde::{Deserialize, Serialize};
+
+use super::cgpoints::parse_cg_points;
+use super::coord_geom::{
+    read_coord_geom, segments_from_vertices, vertices_from_segments, write_coord_geom,
+};
+use super::{
+    escape, parse_xml, today, write_coordinate_system, write_document_start, LandxmlExtras,
+};
+use crate::geometry::{Point, Point3};
+use crate::io::{read_to_string, write_string};
+use crate::parcel::Parcel;
+
+/// Linework such as fences, kerbs or easements stored as a LandXML
+/// `<PlanFeature>`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlanFeature {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub vertices: Vec<Point3>,
+    /// Bulge of the segment leaving each vertex; empty for straight linework.
+    #[serde(default)]
+    pub bulges: Vec<f64>,
+    #[serde(default)]
+    pub closed: bool,
+}
+
+impl PlanFeature {
+    /// Creates a straight, open feature through `vertices`.
+    pub fn new(name: impl Into<String>, vertices: Vec<Point3>) -> Self {
+        Self {
+            name: name.into(),
+            vertices,
+            ..Default::default()
+        }
+    }
+}
+
+pub(crate) fn parse_parcels(doc: &Document, points: &HashMap<String, Point3>) -> Vec<Parcel> {
+    let mut parcels = Vec::new();
+    for node in doc.descendants().filter(|n| n.has_tag_name("Parcel")) {
+        let Some(coord) = node.children().find(|c| c.has_tag_name("CoordGeom")) else {
+            continue;
+        };
+        let segments = read_coord_geom(coord, points);
+        if segments.len() < 2 {
+            continue;
+        }
+        let (vertices, bulges, _) = vertices_from_segments(&segments);
+        let boundary = vertices.iter().map(|p| Point::new(p.x, p.y)).collect();
+        let mut parcel = Parcel::with_bulges(boundary, bulges);
+        parcel.name = node.attribute("name").map(str::to_string);
+        parcels.push(parcel);
+    }
+    parcels
+}
+
+pub(crate) fn write_parcels(xml: &mut String, parcels: &[Parcel]) {
+    if parcels.is_empty() {
+        return;
+    }
+    writeln!(xml, "  <Parcels>").unwrap();
+    for (i, parcel) in parcels.iter().enumerate() {
+        let name = parcel
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("Parcel {}", i + 1));
+        writeln!(
+            xml,
+            "    <Parcel name=\"{}\" area=\"{}\">",
+            escape(&name),
+            parcel.area()
+        )
+        .unwrap();
+        let vertices: Vec<Point3> = parcel
+            .boundary
+            .iter()
+            .map(|p| Point3::new(p.x, p.y, 0.0))
+            .collect();
+        write_coord_geom(
+            xml,
+            6,
+            &segments_from_vertices(&vertices, &parcel.bulges, true),
+        );
+        writeln!(xml, "    </Parcel>").unwrap();
+    }
+    writeln!(xml, "  </Parcels>").unwrap();
+}
+
+pub(crate) fn parse_plan_features(
+    doc: &Document,
+    points: &HashMap<String, Point3>,
+) -> Vec<PlanFeature> {
+    let mut features = Vec::new();
+    for node in doc.descendants().filter(|n| n.has_tag_name("PlanFeature")) {
+        let Some(coord) = node.children().find(|c| c.has_tag_name("CoordGeom")) else {
+            continue;
+        };
+        let (vertices, bulges, closed) = vertices_from_segments(&read_coord_geom(coord, points));
+        if vertices.len() < 2 {
+            continue;
+        }
+        features.push(PlanFeature {
+            name: node.attribute("name").unwrap_or_default().to_string(),
+            description: node.attribute("desc").map(str::to_string),
+            vertices,
+            bulges,
+            closed,
+        });
+    }
+    features
+}
+
+pub(crate) fn write_plan_features(xml: &mut String, features: &[PlanFeature]) {
+    if features.is_empty() {
+        return;
+    }
+    writeln!(xml, "  <PlanFeatures name=\"Plan Features\">").unwrap();
+    for (i, f) in features.iter().enumerate() {
+        let name = if f.name.is_empty() {
+            format!("Feature {}", i + 1)
+        } else {
+            f.name.clone()
+        };
+        write!(xml, "    <PlanFeature name=\"{}\"", escape(&name)).unwrap();
+        if let Some(desc) = &f.description {
+            write!(xml, " desc=\"{}\"", escape(desc)).unwrap();
+        }
+        writeln!(xml, ">").unwrap();
+        write_coord_geom(
+            xml,
+            6,
+            &segments_from_vertices(&f.vertices, &f.bulges, f.closed),
+        );
+        writeln!(xml, "    </PlanFeature>").unwrap();
+    }
+    writeln!(xml, "  </PlanFeatures>").unwrap();
+}
+
+/// Reads every `<Parcel>` from a LandXML file. Boundaries come from the
+/// lines, curves and point lists of each parcel's `<CoordGeom>`; `pntRef`
+/// attributes are resolved against the file's CgPoints.
+pub fn read_landxml_parcels(path: &str) -> io::Result<Vec<Parcel>> {
+    let xml = read_to_string(path)?;
+    let doc = parse_xml(&xml)?;
+    Ok(parse_parcels(&doc, &parse_cg_points(&doc).lookup()))
+}
+
+/// Writes parcels to a LandXML `<Parcels>` collection with their boundaries
+/// as `<Line>` and `<Curve>` elements.
+pub fn write_landxml_parcels(
+    path: &str,
+    parcels: &[Parcel],
+    extras: Option<&LandxmlExtras>,
+) -> io::Result<()> {
+    let mut xml = String::new();
+    write_document_start(&mut xml, today());
+    write_coordinate_system(&mut xml, extras);
+    write_parcels(&mut xml, parcels);
+    writeln!(&mut xml, "</LandXML>").unwrap();
+    write_string(path, &xml)
+}
+
+/// Reads every `<PlanFeature>` from a LandXML file.
+pub fn read_landxml_plan_features(path: &str) -> io::Result<Vec<PlanFeature>> {
+    let xml = read_to_string(path)?;
+    let doc = parse_xml(&xml)?;
+    Ok(parse_plan_features(&doc, &parse_cg_points(&doc).lookup()))
+}
+
+/// Writes linework to a LandXML `<PlanFeatures>` collection.
+pub fn write_landxml_plan_features(path: &str, features: &[PlanFeature]) -> io::Result<()> {
+    let mut xml = String::new();
+    write_document_start(&mut xml, today());
+    write_plan_features(&mut xml, features);
+    writeln!(&mut xml, "</LandXML>").unwrap();
+    write_string(path, &xml)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plan_features_round_trip() {
+        let mut fence = PlanFeature::new(
+            "Fence",
+            vec![
+                Point3::new(0.0, 0.0, 1.0),
+                Point3::new(10.0, 0.0, 1.5),
+                Point3::new(10.0, 10.0, 2.0),
+            ],
+        );
+        fence.description = Some("timber".into());
+        fence.bulges = vec![0.0, 0.5, 0.0];
+        let mut kerb = PlanFeature::new(
+            "Kerb",
+            vec![
+                Point3::new(0.0, 0.0, 0.0),
+                Point3::new(5.0, 0.0, 0.0),
+                Point3::new(5.0, 5.0, 0.0),
+            ],
+        );
+        kerb.closed = true;
+        let mut xml = String::new();
+        write_plan_features(&mut xml, &[fence.clone(), kerb.clone()]);
+        let doc = Document::parse(&xml).unwrap();
+        let read = parse_plan_features(&doc, &HashMap::new());
+        assert_eq!(read.len(), 2);
+        assert_eq!(read[0].description.as_deref(), Some("timber"));
+        assert_eq!(read[0].vertices, fence.vertices);
+        assert!((read[0].bulges[1] - 0.5).abs() < 1e-9);
+        assert!(read[1].closed);
+        assert_eq!(read[1].vertices, kerb.vertices);
+        assert!(read[1].bulges.is_empty());
+    }
+
+    #[test]
+    fn parcel_boundary_from_point_refs() {
+        let xml = r#"<LandXML>
+            <CgPoints>
+                <CgPoint name="1">0 0</CgPoint>
+                <CgPoint name="2">0 10</CgPoint>
+                <CgPoint name="3">10 10</CgPoint>
+            </CgPoints>
+            <Parcels><Parcel name="Lot 7"><CoordGeom>
+                <Line><Start pntRef="1"/><End pntRef="2"/></Line>
+                <Line><Start pntRef="2"/><End pntRef="3"/></Line>
+                <Line><Start pntRef="3"/><End pntRef="1"/></Line>
+            </CoordGeom></Parcel></Parcels>
+        </LandXML>"#;
+        let doc = Document::parse(xml).unwrap();
+        let parcels = parse_parcels(&doc, &parse_cg_points(&doc).lookup());
+        assert_eq!(parcels.len(), 1);
+        assert_eq!(parcels[0].name.as_deref(), Some("Lot 7"));
+        assert_eq!(parcels[0].boundary.len(), 3);
+        assert!((parcels[0].area() - 50.0).abs() < 1e-9);
+    }
+}
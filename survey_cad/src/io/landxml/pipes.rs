@@ -0,0 +1,307 @@
+//! `<PipeNetworks>` with structures, pipes and their inverts.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io;
+
+use roxmltree::{Document, Node};
+use serde::{Deserialize, Serialize};
+
+use super::{attr_f64, escape, fmt_ne, parse_xml, point_ne, today, write_document_start};
+use crate::geometry::Point3;
+use crate::io::{read_to_string, write_string};
+
+/// Structure of a pipe network such as a manhole or inlet. The centre's
+/// elevation is the rim when `rim` is not given.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PipeStructure {
+    pub name: String,
+    pub center: Point3,
+    #[serde(default)]
+    pub rim: Option<f64>,
+    #[serde(default)]
+    pub sump: Option<f64>,
+    /// Diameter of a circular structure.
+    #[serde(default)]
+    pub diameter: Option<f64>,
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// Pipe connecting two structures by name.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NetworkPipe {
+    pub name: String,
+    pub start: String,
+    pub end: String,
+    pub diameter: f64,
+    #[serde(default)]
+    pub start_invert: Option<f64>,
+    #[serde(default)]
+    pub end_invert: Option<f64>,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Extra values kept as `<Feature>` properties, e.g. roughness or design
+    /// flow.
+    #[serde(default)]
+    pub properties: Vec<(String, String)>,
+}
+
+impl NetworkPipe {
+    /// Looks up a property by label.
+    pub fn property(&self, label: &str) -> Option<&str> {
+        self.properties
+            .iter()
+            .find(|(l, _)| l == label)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Named network of structures and pipes. `kind` is the LandXML
+/// `pipeNetType`, e.g. `storm`, `sanitary` or `water`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PipeNetwork {
+    pub name: String,
+    pub kind: String,
+    pub structures: Vec<PipeStructure>,
+    pub pipes: Vec<NetworkPipe>,
+}
+
+fn properties(node: Node) -> Vec<(String, String)> {
+    node.children()
+        .filter(|c| c.has_tag_name("Feature"))
+        .flat_map(|f| f.children().filter(|c| c.has_tag_name("Property")))
+        .filter_map(|p| {
+            Some((
+                p.attribute("label")?.to_string(),
+                p.attribute("value")?.to_string(),
+            ))
+        })
+        .collect()
+}
+
+fn parse_network(node: Node) -> io::Result<PipeNetwork> {
+    let mut net = PipeNetwork {
+        name: node.attribute("name").unwrap_or_default().to_string(),
+        kind: node.attribute("pipeNetType").unwrap_or("other").to_string(),
+        ..Default::default()
+    };
+    // (pipe, flow direction) -> invert elevation
+    let mut inverts: HashMap<(String, bool), f64> = HashMap::new();
+    for s in node.descendants().filter(|n| n.has_tag_name("Struct")) {
+        let center = s
+            .children()
+            .find(|c| c.has_tag_name("Center"))
+            .and_then(point_ne)
+            .unwrap_or_default();
+        let diameter = match s.children().find(|c| c.has_tag_name("CircStruct")) {
+            Some(c) => attr_f64(c, "diameter")?,
+            None => None,
+        };
+        for inv in s.children().filter(|c| c.has_tag_name("Invert")) {
+            if let (Some(pipe), Some(elev)) = (inv.attribute("refPipe"), attr_f64(inv, "elev")?) {
+                let out = inv.attribute("flowDir") == Some("out");
+                inverts.insert((pipe.to_string(), out), elev);
+            }
+        }
+        net.structures.push(PipeStructure {
+            name: s.attribute("name").unwrap_or_default().to_string(),
+            center,
+            rim: attr_f64(s, "elevRim")?,
+            sump: attr_f64(s, "elevSump")?,
+            diameter,
+            description: s.attribute("desc").map(str::to_string),
+        });
+    }
+    for p in node.descendants().filter(|n| n.has_tag_name("Pipe")) {
+        let name = p.attribute("name").unwrap_or_default().to_string();
+        let diameter = match p
+            .children()
+            .find(|c| c.has_tag_name("CircPipe") || c.has_tag_name("EggPipe"))
+        {
+            Some(c) => attr_f64(c, "diameter")?.unwrap_or(0.0),
+            None => 0.0,
+        };
+        net.pipes.push(NetworkPipe {
+            start: p.attribute("refStart").unwrap_or_default().to_string(),
+            end: p.attribute("refEnd").unwrap_or_default().to_string(),
+            diameter,
+            start_invert: inverts.get(&(name.clone(), true)).copied(),
+            end_invert: inverts.get(&(name.clone(), false)).copied(),
+            description: p.attribute("desc").map(str::to_string),
+            properties: properties(p),
+            name,
+        });
+    }
+    Ok(net)
+}
+
+pub(crate) fn parse_pipe_networks(doc: &Document) -> io::Result<Vec<PipeNetwork>> {
+    doc.descendants()
+        .filter(|n| n.has_tag_name("PipeNetwork"))
+        .map(parse_network)
+        .collect()
+}
+
+fn write_structure(xml: &mut String, net: &PipeNetwork, s: &PipeStructure) {
+    write!(xml, "        <Struct name=\"{}\"", escape(&s.name)).unwrap();
+    write!(xml, " elevRim=\"{}\"", s.rim.unwrap_or(s.center.z)).unwrap();
+    if let Some(sump) = s.sump {
+        write!(xml, " elevSump=\"{sump}\"").unwrap();
+    }
+    if let Some(desc) = &s.description {
+        write!(xml, " desc=\"{}\"", escape(desc)).unwrap();
+    }
+    writeln!(xml, ">").unwrap();
+    writeln!(xml, "          <Center>{}</Center>", fmt_ne(s.center)).unwrap();
+    if let Some(d) = s.diameter {
+        writeln!(xml, "          <CircStruct diameter=\"{d}\"/>").unwrap();
+    }
+    for p in &net.pipes {
+        let ends = [
+            (&p.start, p.start_invert, "out"),
+            (&p.end, p.end_invert, "in"),
+        ];
+        for (structure, invert, dir) in ends {
+            if let (true, Some(elev)) = (*structure == s.name, invert) {
+                writeln!(
+                    xml,
+                    "          <Invert elev=\"{elev}\" flowDir=\"{dir}\" refPipe=\"{}\"/>",
+                    escape(&p.name)
+                )
+                .unwrap();
+            }
+        }
+    }
+    writeln!(xml, "        </Struct>").unwrap();
+}
+
+/// Writes a `<PipeNetworks>` collection. Pipe inverts are written as
+/// `<Invert>` elements of the structures at each end.
+pub(crate) fn write_pipe_networks(xml: &mut String, networks: &[PipeNetwork]) {
+    if networks.is_empty() {
+        return;
+    }
+    writeln!(xml, "  <PipeNetworks>").unwrap();
+    for net in networks {
+        writeln!(
+            xml,
+            "    <PipeNetwork name=\"{}\" pipeNetType=\"{}\">",
+            escape(&net.name),
+            escape(&net.kind)
+        )
+        .unwrap();
+        writeln!(xml, "      <Structs>").unwrap();
+        for s in &net.structures {
+            write_structure(xml, net, s);
+        }
+        writeln!(xml, "      </Structs>").unwrap();
+        writeln!(xml, "      <Pipes>").unwrap();
+        for p in &net.pipes {
+            write!(
+                xml,
+                "        <Pipe name=\"{}\" refStart=\"{}\" refEnd=\"{}\"",
+                escape(&p.name),
+                escape(&p.start),
+                escape(&p.end)
+            )
+            .unwrap();
+            if let Some(desc) = &p.description {
+                write!(xml, " desc=\"{}\"", escape(desc)).unwrap();
+            }
+            writeln!(xml, ">").unwrap();
+            writeln!(xml, "          <CircPipe diameter=\"{}\"/>", p.diameter).unwrap();
+            if !p.properties.is_empty() {
+                writeln!(xml, "          <Feature>").unwrap();
+                for (label, value) in &p.properties {
+                    writeln!(
+                        xml,
+                        "            <Property label=\"{}\" value=\"{}\"/>",
+                        escape(label),
+                        escape(value)
+                    )
+                    .unwrap();
+                }
+                writeln!(xml, "          </Feature>").unwrap();
+            }
+            writeln!(xml, "        </Pipe>").unwrap();
+        }
+        writeln!(xml, "      </Pipes>").unwrap();
+        writeln!(xml, "    </PipeNetwork>").unwrap();
+    }
+    writeln!(xml, "  </PipeNetworks>").unwrap();
+}
+
+/// Reads every `<PipeNetwork>` from a LandXML file.
+pub fn read_landxml_pipe_networks(path: &str) -> io::Result<Vec<PipeNetwork>> {
+    let xml = read_to_string(path)?;
+    let doc = parse_xml(&xml)?;
+    parse_pipe_networks(&doc)
+}
+
+/// Writes pipe networks to a LandXML 1.2 file.
+pub fn write_landxml_pipe_networks(path: &str, networks: &[PipeNetwork]) -> io::Result<()> {
+    let mut xml = String::new();
+    write_document_start(&mut xml, today());
+    write_pipe_networks(&mut xml, networks);
+    writeln!(&mut xml, "</LandXML>").unwrap();
+    write_string(path, &xml)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn storm() -> PipeNetwork {
+        PipeNetwork {
+            name: "Storm".into(),
+            kind: "storm".into(),
+            structures: vec![
+                PipeStructure {
+                    name: "MH1".into(),
+                    center: Point3::new(100.0, 200.0, 50.0),
+                    sump: Some(47.5),
+                    diameter: Some(1.2),
+                    ..Default::default()
+                },
+                PipeStructure {
+                    name: "MH2".into(),
+                    center: Point3::new(140.0, 200.0, 49.0),
+                    rim: Some(49.2),
+                    ..Default::default()
+                },
+            ],
+            pipes: vec![NetworkPipe {
+                name: "P1".into(),
+                start: "MH1".into(),
+                end: "MH2".into(),
+                diameter: 0.45,
+                start_invert: Some(48.0),
+                end_invert: Some(47.6),
+                properties: vec![("c".into(), "120".into())],
+                ..Default::default()
+            }],
+        }
+    }
+
+    #[test]
+    fn networks_round_trip() {
+        let mut xml = String::new();
+        write_pipe_networks(&mut xml, &[storm()]);
+        let doc = Document::parse(&xml).unwrap();
+        let read = parse_pipe_networks(&doc).unwrap();
+        let mut expected = storm();
+        expected.structures[0].rim = Some(50.0);
+        assert_eq!(read, vec![expected]);
+        assert_eq!(read[0].pipes[0].property("c"), Some("120"));
+    }
+
+    #[test]
+    fn invalid_number_is_an_error() {
+        let xml = r#"<PipeNetworks><PipeNetwork name="S"><Structs>
+            <Struct name="A" elevRim="high"><Center>0 0</Center></Struct>
+        </Structs></PipeNetwork></PipeNetworks>"#;
+        let doc = Document::parse(xml).unwrap();
+        assert!(parse_pipe_networks(&doc).is_err());
+    }
+}
@@ -0,0 +1,585 @@
+//! Whole-project LandXML 1.2 exchange and structural validation.
+//!
+//! Project points are written as unnamed CgPoints, and lines, polylines,
+//! polygons and arcs as PlanFeatures. Reading a project gives back unnamed
+//! CgPoints without a code or description as plain points. All other
+//! CgPoints are kept as named points, and linework stays as plan features.
+
+use std::collections::HashSet;
+use std::fmt::Write as _;
+use std::io;
+
+use roxmltree::{Document, Node};
+
+use super::cgpoints::{parse_cg_points, write_cg_points, CgPoint};
+use super::coord_geom::{read_coord_geom, write_coord_geom, Segment};
+use super::parcels::{
+    parse_parcels, parse_plan_features, write_parcels, write_plan_features, PlanFeature,
+};
+use super::pipes::{parse_pipe_networks, write_pipe_networks};
+use super::survey::{parse_surveys, write_surveys};
+use super::{
+    attr_f64, escape, fmt_ne, numbers, parse_xml, point_ne, read_vertical_datum, today,
+    write_document_start,
+};
+use crate::alignment::{
+    Alignment, HorizontalAlignment, HorizontalElement, VerticalAlignment, VerticalElement,
+};
+use crate::dtm::Tin;
+use crate::geometry::{Point, Point3};
+use crate::io::project::Project;
+use crate::io::{read_to_string, write_string};
+
+fn point3(p: Point) -> Point3 {
+    Point3::new(p.x, p.y, 0.0)
+}
+
+/// Plan features for the lines, polylines, polygons and arcs of a project.
+fn linework(project: &Project) -> Vec<PlanFeature> {
+    let mut features = Vec::new();
+    for (i, l) in project.lines.iter().enumerate() {
+        features.push(PlanFeature::new(
+            format!("Line {}", i + 1),
+            vec![point3(l.start), point3(l.end)],
+        ));
+    }
+    for (i, pl) in project.polylines.iter().enumerate() {
+        features.push(PlanFeature::new(
+            format!("Polyline {}", i + 1),
+            pl.vertices.iter().copied().map(point3).collect(),
+        ));
+    }
+    for (i, poly) in project.polygons.iter().enumerate() {
+        let mut f = PlanFeature::new(
+            format!("Polygon {}", i + 1),
+            poly.iter().copied().map(point3).collect(),
+        );
+        f.closed = true;
+        features.push(f);
+    }
+    for (i, arc) in project.arcs.iter().enumerate() {
+        let mut f = PlanFeature::new(
+            format!("Arc {}", i + 1),
+            vec![
+                point3(arc.point_at(arc.start_angle)),
+                point3(arc.point_at(arc.end_angle)),
+            ],
+        );
+        f.bulges = vec![arc.bulge(), 0.0];
+        features.push(f);
+    }
+    features
+}
+
+fn write_surfaces(xml: &mut String, project: &Project) {
+    if project.surfaces.is_empty() {
+        return;
+    }
+    writeln!(xml, "  <Surfaces>").unwrap();
+    for (i, tin) in project.surfaces.iter().enumerate() {
+        write!(xml, "    <Surface name=\"Surface {}\"", i + 1).unwrap();
+        if let Some(desc) = project
+            .surface_descriptions
+            .get(i)
+            .filter(|d| !d.is_empty())
+        {
+            write!(xml, " desc=\"{}\"", escape(desc)).unwrap();
+        }
+        writeln!(xml, ">").unwrap();
+        writeln!(xml, "      <Definition surfType=\"TIN\">").unwrap();
+        writeln!(xml, "        <Pnts>").unwrap();
+        for (j, v) in tin.vertices.iter().enumerate() {
+            writeln!(xml, "          <P id=\"{}\">{}</P>", j + 1, fmt_ne(*v)).unwrap();
+        }
+        writeln!(xml, "        </Pnts>").unwrap();
+        writeln!(xml, "        <Faces>").unwrap();
+        for t in &tin.triangles {
+            writeln!(
+                xml,
+                "          <F>{} {} {}</F>",
+                t[0] + 1,
+                t[1] + 1,
+                t[2] + 1
+            )
+            .unwrap();
+        }
+        writeln!(xml, "        </Faces>").unwrap();
+        writeln!(xml, "      </Definition>").unwrap();
+        writeln!(xml, "    </Surface>").unwrap();
+    }
+    writeln!(xml, "  </Surfaces>").unwrap();
+}
+
+fn parse_surface(node: Node) -> Option<Tin> {
+    let def = node.children().find(|c| c.has_tag_name("Definition"))?;
+    let pnts = def.children().find(|c| c.has_tag_name("Pnts"))?;
+    let mut ids = std::collections::HashMap::new();
+    let mut vertices = Vec::new();
+    for p in pnts.children().filter(|c| c.has_tag_name("P")) {
+        let Some(v) = point_ne(p) else {
+            continue;
+        };
+        ids.insert(p.attribute("id").unwrap_or_default(), vertices.len());
+        vertices.push(v);
+    }
+    let triangles = def
+        .descendants()
+        .filter(|n| n.has_tag_name("F"))
+        .filter_map(|f| {
+            let idx: Vec<usize> = f
+                .text()
+                .unwrap_or_default()
+                .split_whitespace()
+                .filter_map(|id| ids.get(id).copied())
+                .collect();
+            (idx.len() == 3).then(|| [idx[0], idx[1], idx[2]])
+        })
+        .collect();
+    Some(Tin {
+        vertices,
+        triangles,
+    })
+}
+
+/// Profile as `(station, elevation, curve length)` PVIs.
+fn pvis(profile: &VerticalAlignment) -> Vec<(f64, f64, f64)> {
+    let mut pvis = Vec::new();
+    let n = profile.elements.len();
+    for (i, e) in profile.elements.iter().enumerate() {
+        match *e {
+            VerticalElement::Grade {
+                start_station,
+                end_station,
+                start_elev,
+                end_elev,
+            } => {
+                if i == 0 {
+                    pvis.push((start_station, start_elev, 0.0));
+                }
+                let next_is_grade = matches!(
+                    profile.elements.get(i + 1),
+                    Some(VerticalElement::Grade { .. })
+                );
+                if next_is_grade || i + 1 == n {
+                    pvis.push((end_station, end_elev, 0.0));
+                }
+            }
+            VerticalElement::Parabola {
+                start_station,
+                end_station,
+                start_elev,
+                start_grade,
+                end_grade,
+            } => {
+                let len = end_station - start_station;
+                if i == 0 {
+                    pvis.push((start_station, start_elev, 0.0));
+                }
+                pvis.push((
+                    start_station + len / 2.0,
+                    start_elev + start_grade * len / 2.0,
+                    len,
+                ));
+                if i + 1 == n {
+                    let end_elev = start_elev + (start_grade + end_grade) / 2.0 * len;
+                    pvis.push((end_station, end_elev, 0.0));
+                }
+            }
+        }
+    }
+    pvis
+}
+
+/// Rebuilds grades and parabolic curves from PVIs.
+fn profile_from_pvis(pvis: &[(f64, f64, f64)]) -> VerticalAlignment {
+    let grade = |i: usize| {
+        let (a, b) = (pvis[i], pvis[i + 1]);
+        (b.1 - a.1) / (b.0 - a.0)
+    };
+    let mut elements = Vec::new();
+    let Some(&(mut sta, mut elev, _)) = pvis.first() else {
+        return VerticalAlignment { elements };
+    };
+    for i in 1..pvis.len() {
+        let (pvi_sta, pvi_elev, len) = pvis[i];
+        let (bvc, bvc_elev) = if len > 0.0 && i + 1 < pvis.len() {
+            (pvi_sta - len / 2.0, pvi_elev - grade(i - 1) * len / 2.0)
+        } else {
+            (pvi_sta, pvi_elev)
+        };
+        if bvc > sta {
+            elements.push(VerticalElement::Grade {
+                start_station: sta,
+                end_station: bvc,
+                start_elev: elev,
+                end_elev: bvc_elev,
+            });
+        }
+        (sta, elev) = (bvc, bvc_elev);
+        if bvc < pvi_sta {
+            elements.push(VerticalElement::Parabola {
+                start_station: bvc,
+                end_station: pvi_sta + len / 2.0,
+                start_elev: bvc_elev,
+                start_grade: grade(i - 1),
+                end_grade: grade(i),
+            });
+            (sta, elev) = (pvi_sta + len / 2.0, pvi_elev + grade(i) * len / 2.0);
+        }
+    }
+    VerticalAlignment { elements }
+}
+
+fn write_alignments(xml: &mut String, alignments: &[Alignment]) {
+    if alignments.is_empty() {
+        return;
+    }
+    writeln!(xml, "  <Alignments>").unwrap();
+    for (i, al) in alignments.iter().enumerate() {
+        writeln!(
+            xml,
+            "    <Alignment name=\"Alignment {}\" length=\"{}\" staStart=\"0\">",
+            i + 1,
+            al.horizontal.length()
+        )
+        .unwrap();
+        let segments: Vec<Segment> = al
+            .horizontal
+            .elements
+            .iter()
+            .map(|e| match e {
+                HorizontalElement::Tangent { start, end } => Segment {
+                    start: point3(*start),
+                    end: point3(*end),
+                    bulge: 0.0,
+                },
+                HorizontalElement::Curve { arc } => Segment {
+                    start: point3(arc.point_at(arc.start_angle)),
+                    end: point3(arc.point_at(arc.end_angle)),
+                    bulge: arc.bulge(),
+                },
+                HorizontalElement::Spiral { spiral } => Segment {
+                    start: point3(spiral.start_point()),
+                    end: point3(spiral.end_point()),
+                    bulge: 0.0,
+                },
+            })
+            .collect();
+        write_coord_geom(xml, 6, &segments);
+        let pvis = pvis(&al.vertical);
+        if !pvis.is_empty() {
+            writeln!(xml, "      <Profile>").unwrap();
+            writeln!(xml, "        <ProfAlign name=\"Profile {}\">", i + 1).unwrap();
+            for (j, (sta, elev, len)) in pvis.iter().enumerate() {
+                if *len > 0.0 && j > 0 && j + 1 < pvis.len() {
+                    writeln!(
+                        xml,
+                        "          <ParaCurve length=\"{len}\">{sta} {elev}</ParaCurve>"
+                    )
+                    .unwrap();
+                } else {
+                    writeln!(xml, "          <PVI>{sta} {elev}</PVI>").unwrap();
+                }
+            }
+            writeln!(xml, "        </ProfAlign>").unwrap();
+            writeln!(xml, "      </Profile>").unwrap();
+        }
+        writeln!(xml, "    </Alignment>").unwrap();
+    }
+    writeln!(xml, "  </Alignments>").unwrap();
+}
+
+fn parse_alignment(node: Node) -> io::Result<Alignment> {
+    let segments = node
+        .children()
+        .find(|c| c.has_tag_name("CoordGeom"))
+        .map(|c| read_coord_geom(c, &Default::default()))
+        .unwrap_or_default();
+    let elements = segments
+        .iter()
+        .map(|s| {
+            let (start, end) = (
+                Point::new(s.start.x, s.start.y),
+                Point::new(s.end.x, s.end.y),
+            );
+            match crate::parcel::ParcelEdge::new(start, end, s.bulge).arc() {
+                Some(arc) => HorizontalElement::Curve { arc },
+                None => HorizontalElement::Tangent { start, end },
+            }
+        })
+        .collect();
+    let mut pvis = Vec::new();
+    if let Some(prof) = node.descendants().find(|n| n.has_tag_name("ProfAlign")) {
+        for c in prof.children().filter(|c| c.is_element()) {
+            let len = match c.tag_name().name() {
+                "PVI" => 0.0,
+                "ParaCurve" | "CircCurve" => attr_f64(c, "length")?.unwrap_or(0.0),
+                _ => continue,
+            };
+            if let [sta, elev, ..] = numbers(c)[..] {
+                pvis.push((sta, elev, len));
+            }
+        }
+    }
+    Ok(Alignment::new(
+        HorizontalAlignment { elements },
+        profile_from_pvis(&pvis),
+    ))
+}
+
+/// Serializes a project to a LandXML 1.2 document.
+pub fn project_to_landxml(project: &Project) -> String {
+    let mut xml = String::new();
+    write_document_start(&mut xml, today());
+    write!(xml, "  <CoordinateSystem epsgCode=\"{}\"", project.crs_epsg).unwrap();
+    if let Some(vd) = &project.vertical_datum {
+        write!(xml, " verticalDatum=\"{}\"", escape(&vd.name)).unwrap();
+        if let Some(g) = &vd.geoid_model {
+            write!(xml, " geoidName=\"{}\"", escape(g)).unwrap();
+        }
+    }
+    writeln!(xml, "/>").unwrap();
+    let mut points = project.cg_points.clone();
+    points.extend(project.points.iter().map(|p| CgPoint::new("", point3(*p))));
+    write_cg_points(&mut xml, &points, &project.point_groups);
+    write_alignments(&mut xml, &project.alignments);
+    write_parcels(&mut xml, &project.parcels);
+    let mut features = project.plan_features.clone();
+    features.extend(linework(project));
+    write_plan_features(&mut xml, &features);
+    write_pipe_networks(&mut xml, &project.pipe_networks);
+    write_surfaces(&mut xml, project);
+    write_surveys(&mut xml, &project.observations);
+    writeln!(xml, "</LandXML>").unwrap();
+    xml
+}
+
+/// Builds a project from a LandXML document.
+pub fn project_from_landxml(xml: &str) -> io::Result<Project> {
+    let doc = parse_xml(xml)?;
+    let mut project = Project::new();
+    if let Some(cs) = doc
+        .descendants()
+        .find(|n| n.has_tag_name("CoordinateSystem"))
+    {
+        if let Some(code) = cs.attribute("epsgCode").and_then(|c| c.parse().ok()) {
+            project.crs_epsg = code;
+        }
+    }
+    project.vertical_datum = read_vertical_datum(&doc);
+    let set = parse_cg_points(&doc);
+    let lookup = set.lookup();
+    for p in set.points {
+        if p.name.is_empty() && p.code.is_none() && p.description.is_none() {
+            project.points.push(Point::new(p.point.x, p.point.y));
+        } else {
+            project.cg_points.push(p);
+        }
+    }
+    project.point_groups = set.groups;
+    project.parcels = parse_parcels(&doc, &lookup);
+    project.plan_features = parse_plan_features(&doc, &lookup);
+    project.pipe_networks = parse_pipe_networks(&doc)?;
+    for s in doc.descendants().filter(|n| n.has_tag_name("Surface")) {
+        if let Some(tin) = parse_surface(s) {
+            project.surfaces.push(tin);
+            project
+                .surface_descriptions
+                .push(s.attribute("desc").unwrap_or_default().to_string());
+        }
+    }
+    for a in doc.descendants().filter(|n| n.has_tag_name("Alignment")) {
+        project.alignments.push(parse_alignment(a)?);
+    }
+    project.observations = parse_surveys(&doc)?;
+    Ok(project)
+}
+
+/// Reads a whole project from a LandXML file.
+pub fn read_landxml_project(path: &str) -> io::Result<Project> {
+    project_from_landxml(&read_to_string(path)?)
+}
+
+/// Writes a whole project to a LandXML 1.2 file.
+pub fn write_landxml_project(path: &str, project: &Project) -> io::Result<()> {
+    write_string(path, &project_to_landxml(project))
+}
+
+/// Checks a LandXML document against the structural rules of the 1.2
+/// schema that other applications rely on: required header attributes and
+/// units, names on parcels and structures, coordinates or references on
+/// points, surface types and resolvable point, structure and setup
+/// references. Returns one message per problem.
+pub fn validate_landxml(xml: &str) -> Vec<String> {
+    let doc = match Document::parse(xml) {
+        Ok(doc) => doc,
+        Err(e) => return vec![e.to_string()],
+    };
+    let mut problems = Vec::new();
+    let root = doc.root_element();
+    if !root.has_tag_name("LandXML") {
+        problems.push(format!(
+            "root element is <{}>, expected <LandXML>",
+            root.tag_name().name()
+        ));
+        return problems;
+    }
+    for attr in ["version", "date", "time"] {
+        if root.attribute(attr).is_none() {
+            problems.push(format!(
+                "<LandXML> is missing the required {attr} attribute"
+            ));
+        }
+    }
+    if !root.children().any(|c| c.has_tag_name("Units")) {
+        problems.push("<LandXML> has no <Units> element".to_string());
+    }
+
+    let line = |n: Node| doc.text_pos_at(n.range().start).row;
+    let names: HashSet<&str> = doc
+        .descendants()
+        .filter(|n| n.has_tag_name("CgPoint") && n.attribute("pntRef").is_none())
+        .filter_map(|n| n.attribute("name"))
+        .collect();
+    for n in doc.descendants().filter(|n| n.is_element()) {
+        let tag = n.tag_name().name();
+        match tag {
+            "Parcel" | "Struct" | "Pipe" | "PipeNetwork" | "Surface" | "Alignment"
+                if n.attribute("name").is_none() =>
+            {
+                problems.push(format!("line {}: <{tag}> has no name", line(n)));
+            }
+            "CgPoint" if n.attribute("pntRef").is_none() && point_ne(n).is_none() => {
+                problems.push(format!("line {}: <CgPoint> has no coordinates", line(n)));
+            }
+            "Definition" if n.attribute("surfType").is_none() => {
+                problems.push(format!("line {}: <Definition> has no surfType", line(n)));
+            }
+            _ => {}
+        }
+        if let Some(r) = n.attribute("pntRef") {
+            if !names.contains(r) {
+                problems.push(format!(
+                    "line {}: pntRef \"{r}\" does not name a CgPoint",
+                    line(n)
+                ));
+            }
+        }
+    }
+    for net in doc.descendants().filter(|n| n.has_tag_name("PipeNetwork")) {
+        let structs: HashSet<&str> = net
+            .descendants()
+            .filter(|n| n.has_tag_name("Struct"))
+            .filter_map(|n| n.attribute("name"))
+            .collect();
+        for p in net.descendants().filter(|n| n.has_tag_name("Pipe")) {
+            for attr in ["refStart", "refEnd"] {
+                match p.attribute(attr) {
+                    Some(r) if structs.contains(r) => {}
+                    Some(r) => problems.push(format!(
+                        "line {}: {attr} \"{r}\" does not name a structure",
+                        line(p)
+                    )),
+                    None => problems.push(format!("line {}: <Pipe> has no {attr}", line(p))),
+                }
+            }
+        }
+    }
+    for survey in doc.descendants().filter(|n| n.has_tag_name("Survey")) {
+        let setups: HashSet<&str> = survey
+            .descendants()
+            .filter(|n| n.has_tag_name("InstrumentSetup") || n.has_tag_name("GPSSetup"))
+            .filter_map(|n| n.attribute("id"))
+            .collect();
+        for n in survey.descendants() {
+            for attr in ["setupID", "setupID_A", "setupID_B"] {
+                if let Some(r) = n.attribute(attr).filter(|r| !setups.contains(r)) {
+                    problems.push(format!(
+                        "line {}: {attr} \"{r}\" does not name a setup",
+                        line(n)
+                    ));
+                }
+            }
+        }
+    }
+    problems
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn profile_pvis_round_trip() {
+        let profile = VerticalAlignment {
+            elements: vec![
+                VerticalElement::Grade {
+                    start_station: 0.0,
+                    end_station: 40.0,
+                    start_elev: 100.0,
+                    end_elev: 100.8,
+                },
+                VerticalElement::Parabola {
+                    start_station: 40.0,
+                    end_station: 80.0,
+                    start_elev: 100.8,
+                    start_grade: 0.02,
+                    end_grade: -0.01,
+                },
+                VerticalElement::Grade {
+                    start_station: 80.0,
+                    end_station: 120.0,
+                    start_elev: 101.0,
+                    end_elev: 100.6,
+                },
+            ],
+        };
+        let p = pvis(&profile);
+        assert_eq!(p.len(), 3);
+        assert_eq!((p[1].0, p[1].2), (60.0, 40.0));
+        assert!((p[1].1 - 101.2).abs() < 1e-9);
+        let back = profile_from_pvis(&p);
+        assert_eq!(back.elements.len(), 3);
+        for (a, b) in back.elements.iter().zip(&profile.elements) {
+            match (a, b) {
+                (
+                    VerticalElement::Parabola {
+                        start_station: s1,
+                        end_grade: g1,
+                        ..
+                    },
+                    VerticalElement::Parabola {
+                        start_station: s2,
+                        end_grade: g2,
+                        ..
+                    },
+                ) => {
+                    assert!((s1 - s2).abs() < 1e-9 && (g1 - g2).abs() < 1e-9);
+                }
+                (
+                    VerticalElement::Grade { end_elev: e1, .. },
+                    VerticalElement::Grade { end_elev: e2, .. },
+                ) => assert!((e1 - e2).abs() < 1e-9),
+                _ => panic!("element kinds differ"),
+            }
+        }
+    }
+
+    #[test]
+    fn reports_structural_problems() {
+        let xml = r#"<LandXML version="1.2">
+            <CgPoints><CgPoint name="1"/></CgPoints>
+            <Parcels><Parcel><CoordGeom><Line><Start pntRef="9"/><End>0 0</End></Line></CoordGeom></Parcel></Parcels>
+            <PipeNetworks><PipeNetwork name="S"><Pipes><Pipe name="P" refStart="A"/></Pipes></PipeNetwork></PipeNetworks>
+        </LandXML>"#;
+        let problems = validate_landxml(xml);
+        let has = |s: &str| problems.iter().any(|p| p.contains(s));
+        assert!(has("required date"));
+        assert!(has("no <Units>"));
+        assert!(has("<CgPoint> has no coordinates"));
+        assert!(has("<Parcel> has no name"));
+        assert!(has("pntRef \"9\""));
+        assert!(has("refStart \"A\""));
+        assert!(has("<Pipe> has no refEnd"));
+    }
+}
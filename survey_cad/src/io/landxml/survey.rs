@@ -0,0 +1,567 @@
+//! `<Survey>` instrument setups, raw observations and GNSS data.
+//!
+//! Total station setups and shots become `<InstrumentSetup>` elements with
+//! `<RawObservation>`s in an `<ObservationGroup>`. GNSS positions and
+//! baselines are written as `<GPSSetup>`, `<GPSPosition>` and `<GPSVector>`
+//! elements. Level runs and traverses have no raw LandXML equivalent and are
+//! skipped.
+
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io;
+
+use chrono::NaiveDate;
+use roxmltree::{Document, Node};
+
+use super::{angular_units, attr_f64, escape, parse_xml, point_ne, today, write_document_start};
+use crate::io::{read_to_string, write_string};
+use crate::surveying::{ObsType, ObservationDB, ObservationData, ObservationRecord};
+
+const COVARIANCE: [&str; 6] = [
+    "covarianceXX",
+    "covarianceXY",
+    "covarianceXZ",
+    "covarianceYY",
+    "covarianceYZ",
+    "covarianceZZ",
+];
+
+/// Setups and observations of one `<Survey>` element being written.
+#[derive(Default)]
+struct SurveyWriter {
+    setups: String,
+    observations: String,
+    /// Setup id by station name for GNSS stations.
+    gps: HashMap<String, String>,
+    /// Id of the total station setup shots are currently taken from.
+    current: Option<(String, String)>,
+    count: usize,
+}
+
+impl SurveyWriter {
+    fn next_id(&mut self, prefix: &str) -> String {
+        self.count += 1;
+        format!("{prefix}{}", self.count)
+    }
+
+    fn instrument_setup(
+        &mut self,
+        station: &str,
+        height: f64,
+        backsight: Option<(&str, Option<f64>, Option<f64>)>,
+    ) -> String {
+        let id = self.next_id("S");
+        let xml = &mut self.setups;
+        writeln!(
+            xml,
+            "    <InstrumentSetup id=\"{id}\" stationName=\"{}\" instrumentHeight=\"{height}\">",
+            escape(station)
+        )
+        .unwrap();
+        writeln!(
+            xml,
+            "      <InstrumentPoint pntRef=\"{}\"/>",
+            escape(station)
+        )
+        .unwrap();
+        if let Some((point, azimuth, circle)) = backsight {
+            write!(xml, "      <Backsight").unwrap();
+            if let Some(a) = azimuth {
+                write!(xml, " azimuth=\"{a}\"").unwrap();
+            }
+            if let Some(c) = circle {
+                write!(xml, " circle=\"{c}\"").unwrap();
+            }
+            writeln!(xml, ">").unwrap();
+            writeln!(
+                xml,
+                "        <BacksightPoint pntRef=\"{}\"/>",
+                escape(point)
+            )
+            .unwrap();
+            writeln!(xml, "      </Backsight>").unwrap();
+        }
+        writeln!(xml, "    </InstrumentSetup>").unwrap();
+        self.current = Some((station.to_string(), id.clone()));
+        id
+    }
+
+    fn gps_setup(&mut self, station: &str) -> String {
+        if let Some(id) = self.gps.get(station) {
+            return id.clone();
+        }
+        let id = self.next_id("G");
+        writeln!(
+            self.setups,
+            "    <GPSSetup id=\"{id}\" stationName=\"{0}\">\n      <TargetPoint pntRef=\"{0}\"/>\n    </GPSSetup>",
+            escape(station)
+        )
+        .unwrap();
+        self.gps.insert(station.to_string(), id.clone());
+        id
+    }
+
+    fn add(&mut self, data: &ObservationData) {
+        match data {
+            ObservationData::Setup {
+                station,
+                instrument_height,
+                backsight,
+                backsight_azimuth,
+                backsight_circle,
+            } => {
+                let bs = backsight
+                    .as_deref()
+                    .map(|b| (b, *backsight_azimuth, *backsight_circle));
+                self.instrument_setup(station, *instrument_height, bs);
+            }
+            ObservationData::TotalStation {
+                from,
+                to,
+                horiz_angle,
+                vert_angle,
+                slope_distance,
+                instrument_height,
+                target_height,
+                code,
+            } => {
+                let setup = match &self.current {
+                    Some((station, id)) if station == from => id.clone(),
+                    _ => self.instrument_setup(from, *instrument_height, None),
+                };
+                let code = code
+                    .as_deref()
+                    .map(|c| format!(" code=\"{}\"", escape(c)))
+                    .unwrap_or_default();
+                writeln!(
+                    self.observations,
+                    "      <RawObservation setupID=\"{setup}\" horizAngle=\"{horiz_angle}\" zenithAngle=\"{vert_angle}\" slopeDistance=\"{slope_distance}\" targetHeight=\"{target_height}\">\n        <TargetPoint pntRef=\"{}\"{code}/>\n      </RawObservation>",
+                    escape(to)
+                )
+                .unwrap();
+            }
+            ObservationData::Gnss {
+                point,
+                northing,
+                easting,
+                elevation,
+            } => {
+                let setup = self.gps_setup(point);
+                writeln!(
+                    self.observations,
+                    "      <GPSPosition setupID=\"{setup}\">\n        <TargetPoint pntRef=\"{}\">{northing} {easting} {elevation}</TargetPoint>\n      </GPSPosition>",
+                    escape(point)
+                )
+                .unwrap();
+            }
+            ObservationData::GnssBaseline {
+                from,
+                to,
+                dx,
+                dy,
+                dz,
+                covariance,
+            } => {
+                let a = self.gps_setup(from);
+                let b = self.gps_setup(to);
+                let xml = &mut self.observations;
+                writeln!(
+                    xml,
+                    "      <GPSVector setupID_A=\"{a}\" setupID_B=\"{b}\" dX=\"{dx}\" dY=\"{dy}\" dZ=\"{dz}\">"
+                )
+                .unwrap();
+                write!(xml, "        <GPSQCInfoLevel2").unwrap();
+                for (name, v) in COVARIANCE.iter().zip(covariance) {
+                    write!(xml, " {name}=\"{v}\"").unwrap();
+                }
+                writeln!(xml, "/>\n      </GPSVector>").unwrap();
+            }
+            ObservationData::LevelRun { .. } | ObservationData::Traverse { .. } => {}
+        }
+    }
+}
+
+/// Writes `<Survey>` elements. Consecutive records sharing a date,
+/// instrument and crew go into the same survey.
+pub(crate) fn write_surveys(xml: &mut String, records: &[ObservationRecord]) {
+    let mut start = 0;
+    let mut writer = SurveyWriter::default();
+    while start < records.len() {
+        let first = &records[start];
+        let len = records[start..]
+            .iter()
+            .take_while(|r| {
+                r.date == first.date && r.instrument == first.instrument && r.crew == first.crew
+            })
+            .count();
+        let group = &records[start..start + len];
+        start += len;
+        writer = SurveyWriter {
+            count: writer.count,
+            ..Default::default()
+        };
+        for r in group {
+            writer.add(&r.data);
+        }
+        if writer.setups.is_empty() {
+            continue;
+        }
+        writeln!(xml, "  <Survey>").unwrap();
+        writeln!(
+            xml,
+            "    <SurveyHeader name=\"Survey {}\" startTime=\"{}T00:00:00\">",
+            first.date, first.date
+        )
+        .unwrap();
+        if let Some(crew) = &first.crew {
+            writeln!(xml, "      <Personnel name=\"{}\"/>", escape(crew)).unwrap();
+        }
+        writeln!(xml, "    </SurveyHeader>").unwrap();
+        if let Some(instrument) = &first.instrument {
+            writeln!(
+                xml,
+                "    <Equipment>\n      <InstrumentDetails id=\"{0}\" model=\"{0}\"/>\n    </Equipment>",
+                escape(instrument)
+            )
+            .unwrap();
+        }
+        xml.push_str(&writer.setups);
+        if !writer.observations.is_empty() {
+            let id = writer.next_id("OG");
+            writeln!(xml, "    <ObservationGroup id=\"{id}\">").unwrap();
+            xml.push_str(&writer.observations);
+            writeln!(xml, "    </ObservationGroup>").unwrap();
+        }
+        writeln!(xml, "  </Survey>").unwrap();
+    }
+}
+
+fn child_ref<'a>(node: Node<'a, '_>, tag: &str) -> Option<&'a str> {
+    node.children()
+        .find(|c| c.has_tag_name(tag))
+        .and_then(|c| c.attribute("pntRef"))
+}
+
+struct Station {
+    name: String,
+    height: f64,
+}
+
+/// Reads the observations of every `<Survey>` in a parsed document.
+pub(crate) fn parse_surveys(doc: &Document) -> io::Result<Vec<ObservationRecord>> {
+    let (angular, direction) = angular_units(doc);
+    let angle = |node: Node, name: &str| -> io::Result<Option<f64>> {
+        Ok(attr_f64(node, name)?.map(|v| angular.to_radians(v)))
+    };
+    let mut records = Vec::new();
+    for survey in doc.descendants().filter(|n| n.has_tag_name("Survey")) {
+        let header = survey.children().find(|c| c.has_tag_name("SurveyHeader"));
+        let date = header
+            .and_then(|h| h.attribute("startTime").or_else(|| h.attribute("date")))
+            .and_then(|t| NaiveDate::parse_from_str(t.get(..10)?, "%Y-%m-%d").ok())
+            .unwrap_or_else(today);
+        let crew = header.and_then(|h| {
+            let names: Vec<&str> = h
+                .children()
+                .filter(|c| c.has_tag_name("Personnel"))
+                .filter_map(|c| c.attribute("name"))
+                .collect();
+            (!names.is_empty()).then(|| names.join(", "))
+        });
+        let instrument = survey
+            .descendants()
+            .find(|n| n.has_tag_name("InstrumentDetails"))
+            .and_then(|n| n.attribute("model").or_else(|| n.attribute("id")))
+            .map(str::to_string);
+        let record = |obs_type, data| ObservationRecord {
+            id: None,
+            obs_type,
+            date,
+            instrument: instrument.clone(),
+            crew: crew.clone(),
+            control_point: None,
+            data,
+        };
+
+        let mut stations: HashMap<&str, Station> = HashMap::new();
+        for node in survey.descendants() {
+            let Some(id) = node.attribute("id") else {
+                continue;
+            };
+            if node.has_tag_name("InstrumentSetup") {
+                let station = node
+                    .attribute("stationName")
+                    .or_else(|| child_ref(node, "InstrumentPoint"))
+                    .unwrap_or(id)
+                    .to_string();
+                let height = attr_f64(node, "instrumentHeight")?.unwrap_or(0.0);
+                let bs = node.children().find(|c| c.has_tag_name("Backsight"));
+                records.push(record(
+                    ObsType::TotalStation,
+                    ObservationData::Setup {
+                        station: station.clone(),
+                        instrument_height: height,
+                        backsight: bs
+                            .and_then(|b| child_ref(b, "BacksightPoint"))
+                            .map(str::to_string),
+                        backsight_azimuth: match bs {
+                            Some(b) => attr_f64(b, "azimuth")?.map(|v| direction.to_radians(v)),
+                            None => None,
+                        },
+                        backsight_circle: match bs {
+                            Some(b) => angle(b, "circle")?,
+                            None => None,
+                        },
+                    },
+                ));
+                stations.insert(
+                    id,
+                    Station {
+                        name: station,
+                        height,
+                    },
+                );
+            } else if node.has_tag_name("GPSSetup") {
+                let station = node
+                    .attribute("stationName")
+                    .or_else(|| child_ref(node, "TargetPoint"))
+                    .unwrap_or(id)
+                    .to_string();
+                let height = attr_f64(node, "antennaHeight")?.unwrap_or(0.0);
+                stations.insert(
+                    id,
+                    Station {
+                        name: station,
+                        height,
+                    },
+                );
+            }
+        }
+
+        for node in survey.descendants() {
+            if node.has_tag_name("RawObservation") {
+                // Shots may reference their setup or be nested inside it.
+                let setup = node.attribute("setupID").or_else(|| {
+                    node.ancestors()
+                        .find(|a| a.has_tag_name("InstrumentSetup"))
+                        .and_then(|a| a.attribute("id"))
+                });
+                let Some(station) = setup.and_then(|s| stations.get(s)) else {
+                    continue;
+                };
+                let target = node.children().find(|c| c.has_tag_name("TargetPoint"));
+                let (Some(hz), Some(distance)) =
+                    (angle(node, "horizAngle")?, attr_f64(node, "slopeDistance")?)
+                else {
+                    continue;
+                };
+                records.push(record(
+                    ObsType::TotalStation,
+                    ObservationData::TotalStation {
+                        from: station.name.clone(),
+                        to: target
+                            .and_then(|t| t.attribute("pntRef").or_else(|| t.attribute("name")))
+                            .unwrap_or_default()
+                            .to_string(),
+                        horiz_angle: hz,
+                        vert_angle: angle(node, "zenithAngle")?
+                            .unwrap_or(std::f64::consts::FRAC_PI_2),
+                        slope_distance: distance,
+                        instrument_height: station.height,
+                        target_height: attr_f64(node, "targetHeight")?.unwrap_or(0.0),
+                        code: target.and_then(|t| t.attribute("code")).map(str::to_string),
+                    },
+                ));
+            } else if node.has_tag_name("GPSPosition") {
+                let Some(target) = node.children().find(|c| c.has_tag_name("TargetPoint")) else {
+                    continue;
+                };
+                let Some(p) = point_ne(target) else {
+                    continue;
+                };
+                let point = target
+                    .attribute("pntRef")
+                    .or_else(|| {
+                        node.attribute("setupID")
+                            .and_then(|s| stations.get(s))
+                            .map(|s| s.name.as_str())
+                    })
+                    .unwrap_or_default()
+                    .to_string();
+                records.push(record(
+                    ObsType::Gnss,
+                    ObservationData::Gnss {
+                        point,
+                        northing: p.y,
+                        easting: p.x,
+                        elevation: p.z,
+                    },
+                ));
+            } else if node.has_tag_name("GPSVector") {
+                let name = |attr| {
+                    node.attribute(attr)
+                        .and_then(|s| stations.get(s))
+                        .map(|s| s.name.clone())
+                };
+                let (Some(from), Some(to)) = (name("setupID_A"), name("setupID_B")) else {
+                    continue;
+                };
+                let mut covariance = [0.0; 6];
+                if let Some(qc) = node.children().find(|c| c.has_tag_name("GPSQCInfoLevel2")) {
+                    for (c, attr) in covariance.iter_mut().zip(COVARIANCE) {
+                        *c = attr_f64(qc, attr)?.unwrap_or(0.0);
+                    }
+                }
+                records.push(record(
+                    ObsType::Gnss,
+                    ObservationData::GnssBaseline {
+                        from,
+                        to,
+                        dx: attr_f64(node, "dX")?.unwrap_or(0.0),
+                        dy: attr_f64(node, "dY")?.unwrap_or(0.0),
+                        dz: attr_f64(node, "dZ")?.unwrap_or(0.0),
+                        covariance,
+                    },
+                ));
+            }
+        }
+    }
+    Ok(records)
+}
+
+/// Reads instrument setups, raw observations and GNSS data from the
+/// `<Survey>` elements of a LandXML file. Angles are converted to radians
+/// according to the file's units.
+pub fn read_landxml_survey(path: &str) -> io::Result<Vec<ObservationRecord>> {
+    let xml = read_to_string(path)?;
+    let doc = parse_xml(&xml)?;
+    parse_surveys(&doc)
+}
+
+/// Writes observation records to a LandXML 1.2 file.
+pub fn write_landxml_survey(path: &str, records: &[ObservationRecord]) -> io::Result<()> {
+    let mut xml = String::new();
+    write_document_start(&mut xml, today());
+    write_surveys(&mut xml, records);
+    writeln!(&mut xml, "</LandXML>").unwrap();
+    write_string(path, &xml)
+}
+
+/// Reads the survey data of a LandXML file into `db` and returns the number
+/// of records inserted.
+pub fn import_landxml_survey(path: &str, db: &ObservationDB) -> io::Result<usize> {
+    let records = read_landxml_survey(path)?;
+    for r in &records {
+        db.insert(r).map_err(io::Error::other)?;
+    }
+    Ok(records.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rec(obs_type: ObsType, data: ObservationData) -> ObservationRecord {
+        ObservationRecord {
+            id: None,
+            obs_type,
+            date: NaiveDate::from_ymd_opt(2024, 5, 2).unwrap(),
+            instrument: Some("TS16".into()),
+            crew: Some("Crew A".into()),
+            control_point: None,
+            data,
+        }
+    }
+
+    #[test]
+    fn setups_shots_and_gnss_round_trip() {
+        let records = vec![
+            rec(
+                ObsType::TotalStation,
+                ObservationData::Setup {
+                    station: "CP1".into(),
+                    instrument_height: 1.55,
+                    backsight: Some("CP2".into()),
+                    backsight_azimuth: Some(1.2),
+                    backsight_circle: Some(0.0),
+                },
+            ),
+            rec(
+                ObsType::TotalStation,
+                ObservationData::TotalStation {
+                    from: "CP1".into(),
+                    to: "100".into(),
+                    horiz_angle: 0.75,
+                    vert_angle: 1.55,
+                    slope_distance: 42.125,
+                    instrument_height: 1.55,
+                    target_height: 1.8,
+                    code: Some("FENCE".into()),
+                },
+            ),
+            rec(
+                ObsType::Gnss,
+                ObservationData::Gnss {
+                    point: "CP2".into(),
+                    northing: 5000.0,
+                    easting: 2000.0,
+                    elevation: 101.5,
+                },
+            ),
+            rec(
+                ObsType::Gnss,
+                ObservationData::GnssBaseline {
+                    from: "CP2".into(),
+                    to: "CP3".into(),
+                    dx: 10.0,
+                    dy: -5.0,
+                    dz: 0.5,
+                    covariance: [1e-6, 0.0, 0.0, 2e-6, 0.0, 3e-6],
+                },
+            ),
+        ];
+        let mut xml = String::new();
+        write_document_start(&mut xml, today());
+        write_surveys(&mut xml, &records);
+        xml.push_str("</LandXML>\n");
+        let doc = Document::parse(&xml).unwrap();
+        assert_eq!(parse_surveys(&doc).unwrap(), records);
+    }
+
+    #[test]
+    fn converts_degree_angles() {
+        let xml = r#"<LandXML>
+            <Units><Metric angularUnit="decimal degrees" directionUnit="decimal dd.mm.ss"/></Units>
+            <Survey>
+                <InstrumentSetup id="A" stationName="1" instrumentHeight="1.5">
+                    <Backsight azimuth="90.3000"><BacksightPoint pntRef="2"/></Backsight>
+                    <RawObservation horizAngle="180" zenithAngle="90" slopeDistance="10">
+                        <TargetPoint pntRef="3"/>
+                    </RawObservation>
+                </InstrumentSetup>
+            </Survey>
+        </LandXML>"#;
+        let doc = Document::parse(xml).unwrap();
+        let records = parse_surveys(&doc).unwrap();
+        assert_eq!(records.len(), 2);
+        match &records[0].data {
+            ObservationData::Setup {
+                backsight_azimuth, ..
+            } => assert!((backsight_azimuth.unwrap() - 90.5f64.to_radians()).abs() < 1e-12),
+            d => panic!("unexpected {d:?}"),
+        }
+        match &records[1].data {
+            ObservationData::TotalStation {
+                from,
+                horiz_angle,
+                instrument_height,
+                ..
+            } => {
+                assert_eq!(from, "1");
+                assert!((horiz_angle - std::f64::consts::PI).abs() < 1e-12);
+                assert_eq!(*instrument_height, 1.5);
+            }
+            d => panic!("unexpected {d:?}"),
+        }
+    }
+}
@@ -1,35 +1,571 @@
+use crate::crs::Crs;
 use crate::geometry::Point3;
-use las::{point::Format, point::Point as LasPoint, Builder, Reader, Version, Writer};
+use crate::lidar::tiling::{process_tiled, TileOptions, TileSummary};
+use crate::lidar::{CloudPoint, PointCloud, Quantization, WavePacket};
+use las::point::{Classification, Format, ScanDirection};
+use las::raw::point::Waveform;
+use las::{
+    point::Point as LasPoint, Builder, Color, Reader, Transform, Vector, Version, Vlr, Writer,
+};
 use std::io;
+use std::path::Path;
+
+const PROJECTION_USER_ID: &str = "LASF_Projection";
+const GEO_KEY_DIRECTORY: u16 = 34735;
+const OGC_WKT: u16 = 2112;
+const GT_MODEL_TYPE: u16 = 1024;
+const GEOGRAPHIC_TYPE: u16 = 2048;
+const PROJECTED_CS_TYPE: u16 = 3072;
+const OVERLAP_CLASS: u8 = 12;
 
 /// Reads a LAS file and returns the contained points.
 pub fn read_points_las(path: &str) -> io::Result<Vec<Point3>> {
-    let mut reader =
-        Reader::from_path(path).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-    let mut pts = Vec::new();
-    for wrapped in reader.points() {
-        let p: LasPoint = wrapped.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
-        pts.push(Point3::new(p.x, p.y, p.z));
-    }
-    Ok(pts)
+    Ok(read_las(path)?.positions())
 }
 
 /// Writes points to a LAS or LAZ file. Compression is inferred from the
-/// file extension when the `laz` feature of the `las` crate is enabled.
+/// file extension when the `laz` feature is enabled.
 pub fn write_points_las(path: &str, points: &[Point3]) -> io::Result<()> {
+    write_las(path, &PointCloud::from_positions(points))
+}
+
+/// Streams the points of a LAS or LAZ file with all their attributes.
+pub struct LasPoints {
+    reader: Reader,
+    pub point_format: u8,
+    pub crs: Option<Crs>,
+    pub quantization: Quantization,
+}
+
+impl LasPoints {
+    /// Opens a file and reads its header and projection records.
+    pub fn open(path: &str) -> io::Result<Self> {
+        let reader =
+            Reader::from_path(path).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let header = reader.header();
+        let point_format = header
+            .point_format()
+            .to_u8()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let crs = crs_from_vlrs(header.vlrs().iter().chain(header.evlrs()));
+        let t = header.transforms();
+        let quantization = Quantization {
+            scale: [t.x.scale, t.y.scale, t.z.scale],
+            offset: [t.x.offset, t.y.offset, t.z.offset],
+        };
+        Ok(Self {
+            reader,
+            point_format,
+            crs,
+            quantization,
+        })
+    }
+}
+
+impl Iterator for LasPoints {
+    type Item = io::Result<CloudPoint>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let point_format = self.point_format;
+        self.reader.points().next().map(|wrapped| {
+            wrapped
+                .map(|p| from_las_point(p, point_format))
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+        })
+    }
+}
+
+/// Reads a LAS or LAZ file with every point attribute and the CRS from its
+/// WKT or GeoTIFF projection records.
+pub fn read_las(path: &str) -> io::Result<PointCloud> {
+    let mut source = LasPoints::open(path)?;
+    let points = source.by_ref().collect::<io::Result<Vec<_>>>()?;
+    Ok(PointCloud {
+        points,
+        point_format: source.point_format,
+        crs: source.crs,
+        quantization: Some(source.quantization),
+    })
+}
+
+/// Streams a LAS or LAZ file through [`process_tiled`] and writes every
+/// tile to `out_dir` as `<x>_<y>.las`, or `.laz` for LAZ input, keeping the
+/// input's point format, CRS and quantization.
+pub fn tile_las(input: &str, out_dir: &str, options: &TileOptions) -> io::Result<Vec<TileSummary>> {
+    let source = LasPoints::open(input)?;
+    let (point_format, crs) = (source.point_format, source.crs.clone());
+    let quantization = Some(source.quantization);
+    let extension = match Path::new(input).extension().and_then(|e| e.to_str()) {
+        Some(e) if e.eq_ignore_ascii_case("laz") => "laz",
+        _ => "las",
+    };
+    std::fs::create_dir_all(out_dir)?;
+    process_tiled(source, options, |key, points| {
+        let path = Path::new(out_dir).join(format!("{}_{}.{extension}", key.x, key.y));
+        let cloud = PointCloud {
+            points,
+            point_format,
+            crs: crs.clone(),
+            quantization,
+        };
+        write_las(&path.to_string_lossy(), &cloud)
+    })
+}
+
+/// Writes a point cloud as its `point_format`. Attributes the format lacks
+/// are dropped and attributes it requires but a point lacks are zeroed.
+/// Coordinates keep the cloud's quantization when every point fits it and
+/// otherwise use steps of 1e-7 for degrees and 0.001 for linear units.
+/// The CRS is written as a GeoTIFF key directory when it has an EPSG code
+/// and as an OGC WKT record when it is defined by WKT. Point formats 6 and
+/// up only allow WKT, so the CRS is converted and the header's WKT bit set.
+/// A `.laz` path is compressed when the `laz` feature is enabled.
+pub fn write_las(path: &str, cloud: &PointCloud) -> io::Result<()> {
+    let mut format = Format::new(cloud.point_format)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    let extra = cloud.points.first().map_or(0, |p| p.extra_bytes.len());
+    if cloud.points.iter().any(|p| p.extra_bytes.len() != extra) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "points have differing numbers of extra bytes",
+        ));
+    }
+    format.extra_bytes = extra as u16;
+
     let mut builder = Builder::default();
-    builder.point_format = Format::new(0).unwrap();
-    builder.version = Version::new(1, 2);
+    builder.version = match cloud.point_format {
+        0..=3 => Version::new(1, 2),
+        4 | 5 => Version::new(1, 3),
+        _ => Version::new(1, 4),
+    };
+    builder.point_format = format;
+    if let Some(q) = quantization(cloud) {
+        let transform = |i: usize| Transform {
+            scale: q.scale[i],
+            offset: q.offset[i],
+        };
+        builder.transforms = Vector {
+            x: transform(0),
+            y: transform(1),
+            z: transform(2),
+        };
+    }
+    if let Some(crs) = &cloud.crs {
+        let wkt_only = cloud.point_format >= 6;
+        builder.vlrs.extend(crs_vlrs(crs, wkt_only)?);
+        builder.has_wkt_crs = wkt_only;
+    }
     let header = builder.into_header().map_err(io::Error::other)?;
     let mut writer = Writer::from_path(path, header).map_err(io::Error::other)?;
-    for p in points {
-        let lp = LasPoint {
-            x: p.x,
-            y: p.y,
-            z: p.z,
+    for p in &cloud.points {
+        writer
+            .write_point(to_las_point(p, &format))
+            .map_err(io::Error::other)?;
+    }
+    writer.close().map_err(io::Error::other)
+}
+
+/// Quantization for writing `cloud`: its own when every point fits in the
+/// 32-bit integers, otherwise one from the CRS units offset to the
+/// minimum. `None` for an empty cloud.
+fn quantization(cloud: &PointCloud) -> Option<Quantization> {
+    let min = cloud
+        .points
+        .iter()
+        .map(|p| p.position)
+        .reduce(|a, b| Point3::new(a.x.min(b.x), a.y.min(b.y), a.z.min(b.z)))?;
+    let fits = |q: &Quantization| {
+        cloud.points.iter().all(|p| {
+            [p.position.x, p.position.y, p.position.z]
+                .iter()
+                .enumerate()
+                .all(|(i, v)| ((v - q.offset[i]) / q.scale[i]).abs() <= i32::MAX as f64)
+        })
+    };
+    if let Some(q) = cloud.quantization.filter(fits) {
+        return Some(q);
+    }
+    let plan = if cloud.crs.as_ref().is_some_and(Crs::is_geographic) {
+        1e-7
+    } else {
+        0.001
+    };
+    Some(Quantization {
+        scale: [plan, plan, 0.001],
+        offset: [min.x.floor(), min.y.floor(), min.z.floor()],
+    })
+}
+
+/// Puts back the NIR value and wave packet of a format 10 point. las 0.9
+/// reads the wave packet first although the format, and its own writer,
+/// store NIR first.
+fn reorder_format_10(p: &mut LasPoint) {
+    let Some(w) = p.waveform else {
+        return;
+    };
+    // the bytes as read, which in file order are NIR then the packet
+    let mut bytes = vec![w.wave_packet_descriptor_index];
+    bytes.extend(w.byte_offset_to_waveform_data.to_le_bytes());
+    bytes.extend(w.waveform_packet_size_in_bytes.to_le_bytes());
+    for f in [w.return_point_waveform_location, w.x_t, w.y_t, w.z_t] {
+        bytes.extend(f.to_le_bytes());
+    }
+    bytes.extend(p.nir.unwrap_or(0).to_le_bytes());
+    let word = |i: usize| <[u8; 4]>::try_from(&bytes[i..i + 4]).unwrap();
+    let nir = u16::from_le_bytes([bytes[0], bytes[1]]);
+    p.nir = (nir != 0).then_some(nir);
+    p.waveform = Some(Waveform {
+        wave_packet_descriptor_index: bytes[2],
+        byte_offset_to_waveform_data: u64::from_le_bytes(bytes[3..11].try_into().unwrap()),
+        waveform_packet_size_in_bytes: u32::from_le_bytes(word(11)),
+        return_point_waveform_location: f32::from_le_bytes(word(15)),
+        x_t: f32::from_le_bytes(word(19)),
+        y_t: f32::from_le_bytes(word(23)),
+        z_t: f32::from_le_bytes(word(27)),
+    });
+}
+
+fn from_las_point(mut p: LasPoint, point_format: u8) -> CloudPoint {
+    if point_format == 10 {
+        reorder_format_10(&mut p);
+    }
+    CloudPoint {
+        position: Point3::new(p.x, p.y, p.z),
+        intensity: p.intensity,
+        return_number: p.return_number,
+        number_of_returns: p.number_of_returns,
+        classification: u8::from(p.classification),
+        scan_direction: p.scan_direction == ScanDirection::LeftToRight,
+        is_edge_of_flight_line: p.is_edge_of_flight_line,
+        is_synthetic: p.is_synthetic,
+        is_key_point: p.is_key_point,
+        is_withheld: p.is_withheld,
+        is_overlap: p.is_overlap,
+        scanner_channel: p.scanner_channel,
+        scan_angle: p.scan_angle,
+        user_data: p.user_data,
+        point_source_id: p.point_source_id,
+        gps_time: p.gps_time,
+        color: p.color.map(|c| [c.red, c.green, c.blue]),
+        nir: p.nir,
+        waveform: p.waveform.map(|w| WavePacket {
+            descriptor_index: w.wave_packet_descriptor_index,
+            byte_offset: w.byte_offset_to_waveform_data,
+            size_in_bytes: w.waveform_packet_size_in_bytes,
+            return_point_location: w.return_point_waveform_location,
+            x_t: w.x_t,
+            y_t: w.y_t,
+            z_t: w.z_t,
+        }),
+        extra_bytes: p.extra_bytes,
+    }
+}
+
+fn to_las_point(p: &CloudPoint, format: &Format) -> LasPoint {
+    // class 12 is stored as the overlap flag rather than a class code
+    let (classification, is_overlap) = if p.classification == OVERLAP_CLASS {
+        (Classification::Unclassified, true)
+    } else {
+        (
+            Classification::new(p.classification).unwrap_or(Classification::Unclassified),
+            p.is_overlap,
+        )
+    };
+    let wave = p.waveform.unwrap_or_default();
+    LasPoint {
+        x: p.position.x,
+        y: p.position.y,
+        z: p.position.z,
+        intensity: p.intensity,
+        return_number: p.return_number,
+        number_of_returns: p.number_of_returns,
+        scan_direction: if p.scan_direction {
+            ScanDirection::LeftToRight
+        } else {
+            ScanDirection::RightToLeft
+        },
+        is_edge_of_flight_line: p.is_edge_of_flight_line,
+        classification,
+        is_synthetic: p.is_synthetic,
+        is_key_point: p.is_key_point,
+        is_withheld: p.is_withheld,
+        is_overlap,
+        scanner_channel: p.scanner_channel,
+        scan_angle: p.scan_angle,
+        user_data: p.user_data,
+        point_source_id: p.point_source_id,
+        gps_time: format.has_gps_time.then(|| p.gps_time.unwrap_or(0.0)),
+        color: format.has_color.then(|| {
+            let [r, g, b] = p.color.unwrap_or_default();
+            Color::new(r, g, b)
+        }),
+        waveform: format.has_waveform.then_some(Waveform {
+            wave_packet_descriptor_index: wave.descriptor_index,
+            byte_offset_to_waveform_data: wave.byte_offset,
+            waveform_packet_size_in_bytes: wave.size_in_bytes,
+            return_point_waveform_location: wave.return_point_location,
+            x_t: wave.x_t,
+            y_t: wave.y_t,
+            z_t: wave.z_t,
+        }),
+        nir: format.has_nir.then(|| p.nir.unwrap_or(0)),
+        extra_bytes: p.extra_bytes.clone(),
+    }
+}
+
+/// Finds the CRS in the projection records, preferring OGC WKT over the
+/// GeoTIFF keys.
+fn crs_from_vlrs<'a>(vlrs: impl Iterator<Item = &'a Vlr>) -> Option<Crs> {
+    let mut geotiff = None;
+    for vlr in vlrs.filter(|v| v.user_id == PROJECTION_USER_ID) {
+        match vlr.record_id {
+            OGC_WKT => {
+                let wkt = String::from_utf8_lossy(&vlr.data);
+                let wkt = wkt.trim_end_matches('\0').trim();
+                if !wkt.is_empty() {
+                    return Some(
+                        epsg_from_wkt(wkt).map_or_else(|| Crs::from_wkt(wkt), Crs::from_epsg),
+                    );
+                }
+            }
+            GEO_KEY_DIRECTORY => geotiff = geotiff.or_else(|| epsg_from_geo_keys(&vlr.data)),
+            _ => {}
+        }
+    }
+    geotiff.map(Crs::from_epsg)
+}
+
+/// Reads the projected or geographic EPSG code from a GeoKeyDirectory.
+fn epsg_from_geo_keys(data: &[u8]) -> Option<u32> {
+    let shorts: Vec<u16> = data
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect();
+    let count = *shorts.get(3)? as usize;
+    let keys: Vec<&[u16]> = shorts.get(4..4 + count * 4)?.chunks_exact(4).collect();
+    let find = |id: u16| {
+        keys.iter()
+            .find(|k| k[0] == id && k[1] == 0 && k[3] != 0 && k[3] != 32767)
+            .map(|k| k[3] as u32)
+    };
+    find(PROJECTED_CS_TYPE).or_else(|| find(GEOGRAPHIC_TYPE))
+}
+
+/// Reads the EPSG code of the outermost CRS from WKT 1 `AUTHORITY` or WKT 2
+/// `ID` nodes, which come last in their parent.
+fn epsg_from_wkt(wkt: &str) -> Option<u32> {
+    let start = wkt
+        .rfind("AUTHORITY[\"EPSG\",")
+        .or_else(|| wkt.rfind("ID[\"EPSG\","))?;
+    let tail = &wkt[start..];
+    let args = &tail[tail.find(',')? + 1..tail.find(']')?];
+    if tail[tail.find(']')? + 1..].trim() != "]" {
+        return None;
+    }
+    args.trim().trim_matches('"').parse().ok()
+}
+
+/// Projection records for a CRS: a GeoKeyDirectory when the EPSG code is
+/// known and OGC WKT when the definition is WKT. With `wkt_only` the CRS is
+/// always written as WKT, converting it through PROJ when needed.
+fn crs_vlrs(crs: &Crs, wkt_only: bool) -> io::Result<Vec<Vlr>> {
+    let mut vlrs = Vec::new();
+    let code = crs.epsg().filter(|c| !wkt_only && *c <= u16::MAX as u32);
+    if let Some(code) = code {
+        let model = if crs.is_geographic() {
+            (2, GEOGRAPHIC_TYPE)
+        } else {
+            (1, PROJECTED_CS_TYPE)
+        };
+        let shorts = [
+            1,
+            1,
+            0,
+            2,
+            GT_MODEL_TYPE,
+            0,
+            1,
+            model.0,
+            model.1,
+            0,
+            1,
+            code as u16,
+        ];
+        vlrs.push(Vlr {
+            user_id: PROJECTION_USER_ID.to_string(),
+            record_id: GEO_KEY_DIRECTORY,
+            description: "GeoTiff GeoKeyDirectoryTag".to_string(),
+            data: shorts.iter().flat_map(|s| s.to_le_bytes()).collect(),
+        });
+    }
+    let wkt = if wkt_only {
+        Some(crs.to_wkt().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("cannot convert {} to WKT", crs.definition()),
+            )
+        })?)
+    } else {
+        Some(crs.definition().to_string()).filter(|d| d.contains('['))
+    };
+    if let Some(wkt) = wkt {
+        let mut data = wkt.into_bytes();
+        data.push(0);
+        vlrs.push(Vlr {
+            user_id: PROJECTION_USER_ID.to_string(),
+            record_id: OGC_WKT,
+            description: "OGC WKT".to_string(),
+            data,
+        });
+    }
+    Ok(vlrs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_cloud(point_format: u8) -> PointCloud {
+        let mut a = CloudPoint::new(Point3::new(1000.125, 2000.5, 10.25));
+        a.intensity = 812;
+        a.classification = crate::lidar::asprs::GROUND;
+        a.return_number = 1;
+        a.number_of_returns = 2;
+        a.scan_direction = true;
+        a.scan_angle = -12.0;
+        a.user_data = 7;
+        a.point_source_id = 3;
+        a.gps_time = Some(123_456.5);
+        a.color = Some([100, 200, 300]);
+        a.nir = Some(400);
+        a.waveform = Some(WavePacket {
+            descriptor_index: 1,
+            byte_offset: 60,
+            size_in_bytes: 256,
+            ..Default::default()
+        });
+        let mut b = CloudPoint::new(Point3::new(1001.0, 2001.0, 11.0));
+        b.classification = crate::lidar::asprs::BUILDING;
+        PointCloud {
+            points: vec![a, b],
+            point_format,
+            crs: Some(Crs::from_epsg(26910)),
             ..Default::default()
+        }
+    }
+
+    #[test]
+    fn every_point_format_round_trips() {
+        let dir = tempfile::tempdir().unwrap();
+        for format in 0..=10u8 {
+            let path = dir.path().join(format!("f{format}.las"));
+            let path = path.to_str().unwrap();
+            let cloud = sample_cloud(format);
+            write_las(path, &cloud).unwrap();
+            let read = read_las(path).unwrap();
+            let fmt = Format::new(format).unwrap();
+            assert_eq!(read.point_format, format);
+            assert_eq!(read.crs.as_ref().and_then(Crs::epsg), Some(26910));
+            let (p, q) = (&cloud.points[0], &read.points[0]);
+            assert!((p.position.x - q.position.x).abs() < 1e-3);
+            assert_eq!(q.intensity, 812);
+            assert_eq!(q.classification, crate::lidar::asprs::GROUND);
+            assert_eq!(q.number_of_returns, 2);
+            assert!(q.scan_direction);
+            assert_eq!(q.point_source_id, 3);
+            assert_eq!(q.gps_time.is_some(), fmt.has_gps_time);
+            assert_eq!(q.color.is_some(), fmt.has_color);
+            assert_eq!(q.nir, fmt.has_nir.then_some(400));
+            assert_eq!(
+                q.waveform.map(|w| w.size_in_bytes),
+                fmt.has_waveform.then_some(256)
+            );
+            assert_eq!(read.points[1].classification, crate::lidar::asprs::BUILDING);
+        }
+    }
+
+    #[test]
+    fn quantization_follows_source_and_crs() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("geo.las");
+        let path = path.to_str().unwrap();
+        let mut cloud = PointCloud::from_positions(&[
+            Point3::new(-123.123_456_7, 49.282_828_3, 71.5),
+            Point3::new(-123.1, 49.3, 80.0),
+        ]);
+        cloud.crs = Some(Crs::from_epsg(4326));
+        write_las(path, &cloud).unwrap();
+        let read = read_las(path).unwrap();
+        let q = read.quantization.unwrap();
+        assert_eq!(q.scale, [1e-7, 1e-7, 0.001]);
+        let p = read.points[0].position;
+        assert!((p.x + 123.123_456_7).abs() < 1e-7 && (p.y - 49.282_828_3).abs() < 1e-7);
+
+        // The source quantization is written back unchanged.
+        let custom = Quantization {
+            scale: [0.01, 0.01, 0.01],
+            offset: [-100.0, 40.0, 0.0],
         };
-        writer.write_point(lp).map_err(io::Error::other)?;
+        cloud.quantization = Some(custom);
+        write_las(path, &cloud).unwrap();
+        assert_eq!(read_las(path).unwrap().quantization, Some(custom));
+    }
+
+    #[test]
+    fn tiles_keep_format_and_crs() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = dir.path().join("in.las");
+        let input = input.to_str().unwrap();
+        write_las(input, &sample_cloud(3)).unwrap();
+        let out = dir.path().join("tiles");
+        let options = TileOptions {
+            tile_size: 0.5,
+            buffer: 0.0,
+            ..Default::default()
+        };
+        let summaries = tile_las(input, out.to_str().unwrap(), &options).unwrap();
+        assert_eq!(summaries.len(), 2);
+        let tile = read_las(out.join("2000_4001.las").to_str().unwrap()).unwrap();
+        assert_eq!(tile.point_format, 3);
+        assert_eq!(tile.crs, Some(Crs::from_epsg(26910)));
+        assert_eq!(tile.points.len(), 1);
+    }
+
+    #[test]
+    fn geo_keys_round_trip() {
+        let vlrs = crs_vlrs(&Crs::from_epsg(4326), false).unwrap();
+        assert_eq!(vlrs.len(), 1);
+        assert_eq!(epsg_from_geo_keys(&vlrs[0].data), Some(4326));
+        let wkt = Crs::from_wkt("PROJCS[\"local\"]");
+        let read = crs_from_vlrs(crs_vlrs(&wkt, false).unwrap().iter()).unwrap();
+        assert_eq!(read.definition(), "PROJCS[\"local\"]");
+    }
+
+    #[test]
+    fn format_6_writes_wkt_crs() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("f6.las");
+        let path = path.to_str().unwrap();
+        write_las(path, &sample_cloud(6)).unwrap();
+        let reader = Reader::from_path(path).unwrap();
+        let header = reader.header();
+        assert!(header.has_wkt_crs());
+        let records: Vec<u16> = header.vlrs().iter().map(|v| v.record_id).collect();
+        assert_eq!(records, [OGC_WKT]);
+        let wkt = String::from_utf8_lossy(&header.vlrs()[0].data);
+        assert!(wkt.starts_with("PROJCS["));
+        assert_eq!(epsg_from_wkt(wkt.trim_end_matches('\0')), Some(26910));
+    }
+
+    #[test]
+    fn epsg_comes_from_outermost_authority() {
+        let wkt = "PROJCS[\"x\",GEOGCS[\"y\",AUTHORITY[\"EPSG\",\"4326\"]],AUTHORITY[\"EPSG\",\"26910\"]]";
+        assert_eq!(epsg_from_wkt(wkt), Some(26910));
+        let nested = "PROJCS[\"x\",GEOGCS[\"y\",AUTHORITY[\"EPSG\",\"4326\"]]]";
+        assert_eq!(epsg_from_wkt(nested), None);
+        assert_eq!(
+            epsg_from_wkt("PROJCRS[\"x\",ID[\"EPSG\",32610]]"),
+            Some(32610)
+        );
     }
-    writer.close().map_err(io::Error::other)
 }
@@ -5,12 +5,17 @@ use std::io::{self, BufRead, BufReader, Read, Write};
 
 use crate::crs::Crs;
 
-use crate::geometry::{Arc, Point, Point3, Polyline};
+use crate::geometry::{Point, Point3};
+use crate::local_grid::GroundCoordinateSystem;
 
+pub mod baseline;
+pub mod dwg;
+pub mod dxf;
 #[cfg(feature = "e57")]
 pub mod e57;
 #[cfg(feature = "fgdb")]
 pub mod fgdb;
+pub mod gpkg;
 pub mod ifc;
 #[cfg(feature = "kml")]
 pub mod kml;
@@ -18,9 +23,13 @@ pub mod landxml;
 #[cfg(feature = "las")]
 pub mod las;
 pub mod project;
+pub mod rinex;
 #[cfg(feature = "shapefile")]
 pub mod shp;
 
+pub use dwg::read_dwg;
+pub use dxf::{read_dxf, write_dxf, DxfEntity};
+
 /// Reads a file to string.
 pub fn read_to_string(path: &str) -> io::Result<String> {
     let mut buffer = String::new();
@@ -117,6 +126,31 @@ pub fn write_points_csv(
     Ok(())
 }
 
+/// Reads grid coordinates like [`read_points_csv`] and converts them to the
+/// project ground coordinate system.
+pub fn read_points_csv_ground(
+    path: &str,
+    src_epsg: Option<u32>,
+    dst_epsg: Option<u32>,
+    ground: &GroundCoordinateSystem,
+) -> io::Result<Vec<Point>> {
+    let pts = read_points_csv(path, src_epsg, dst_epsg)?;
+    Ok(pts.into_iter().map(|p| ground.grid_to_ground(p)).collect())
+}
+
+/// Converts ground coordinates back to grid and writes them like
+/// [`write_points_csv`].
+pub fn write_points_csv_ground(
+    path: &str,
+    points: &[Point],
+    src_epsg: Option<u32>,
+    dst_epsg: Option<u32>,
+    ground: &GroundCoordinateSystem,
+) -> io::Result<()> {
+    let grid: Vec<Point> = points.iter().map(|p| ground.ground_to_grid(*p)).collect();
+    write_points_csv(path, &grid, src_epsg, dst_epsg)
+}
+
 use crate::point_database::PointDatabase;
 
 pub fn read_point_database_csv(
@@ -276,298 +310,6 @@ pub fn write_points_dxf(
     Ok(())
 }
 
-/// Basic DXF entity types supported by the simple reader and writer.
-#[derive(Debug, Clone, PartialEq)]
-pub enum DxfEntity {
-    Point {
-        point: Point,
-        layer: Option<String>,
-    },
-    Line {
-        line: crate::geometry::Line,
-        layer: Option<String>,
-    },
-    Polyline {
-        polyline: Polyline,
-        layer: Option<String>,
-    },
-    Arc {
-        arc: Arc,
-        layer: Option<String>,
-    },
-    Text {
-        position: Point,
-        height: f64,
-        value: String,
-        layer: Option<String>,
-    },
-}
-
-/// Writes a collection of [`DxfEntity`] instances to a very simple DXF file.
-pub fn write_dxf(path: &str, entities: &[DxfEntity]) -> io::Result<()> {
-    let mut file = File::create(path)?;
-    writeln!(file, "0")?;
-    writeln!(file, "SECTION")?;
-    writeln!(file, "2")?;
-    writeln!(file, "ENTITIES")?;
-    for e in entities {
-        match e {
-            DxfEntity::Point { point, layer } => {
-                writeln!(file, "0")?;
-                writeln!(file, "POINT")?;
-                if let Some(l) = layer {
-                    writeln!(file, "8")?;
-                    writeln!(file, "{l}")?;
-                }
-                writeln!(file, "10")?;
-                writeln!(file, "{x}", x = point.x)?;
-                writeln!(file, "20")?;
-                writeln!(file, "{y}", y = point.y)?;
-                writeln!(file, "30")?;
-                writeln!(file, "0.0")?;
-            }
-            DxfEntity::Line { line, layer } => {
-                writeln!(file, "0")?;
-                writeln!(file, "LINE")?;
-                if let Some(l) = layer {
-                    writeln!(file, "8")?;
-                    writeln!(file, "{l}")?;
-                }
-                writeln!(file, "10")?;
-                writeln!(file, "{x}", x = line.start.x)?;
-                writeln!(file, "20")?;
-                writeln!(file, "{y}", y = line.start.y)?;
-                writeln!(file, "11")?;
-                writeln!(file, "{x}", x = line.end.x)?;
-                writeln!(file, "21")?;
-                writeln!(file, "{y}", y = line.end.y)?;
-            }
-            DxfEntity::Polyline { polyline, layer } => {
-                writeln!(file, "0")?;
-                writeln!(file, "POLYLINE")?;
-                if let Some(l) = layer {
-                    writeln!(file, "8")?;
-                    writeln!(file, "{l}")?;
-                }
-                writeln!(file, "66")?;
-                writeln!(file, "1")?;
-                writeln!(file, "70")?;
-                writeln!(file, "0")?;
-                for v in &polyline.vertices {
-                    writeln!(file, "0")?;
-                    writeln!(file, "VERTEX")?;
-                    if let Some(l) = layer {
-                        writeln!(file, "8")?;
-                        writeln!(file, "{l}")?;
-                    }
-                    writeln!(file, "10")?;
-                    writeln!(file, "{x}", x = v.x)?;
-                    writeln!(file, "20")?;
-                    writeln!(file, "{y}", y = v.y)?;
-                    writeln!(file, "30")?;
-                    writeln!(file, "0.0")?;
-                }
-                writeln!(file, "0")?;
-                writeln!(file, "SEQEND")?;
-            }
-            DxfEntity::Arc { arc, layer } => {
-                writeln!(file, "0")?;
-                writeln!(file, "ARC")?;
-                if let Some(l) = layer {
-                    writeln!(file, "8")?;
-                    writeln!(file, "{l}")?;
-                }
-                writeln!(file, "10")?;
-                writeln!(file, "{x}", x = arc.center.x)?;
-                writeln!(file, "20")?;
-                writeln!(file, "{y}", y = arc.center.y)?;
-                writeln!(file, "40")?;
-                writeln!(file, "{radius}", radius = arc.radius)?;
-                writeln!(file, "50")?;
-                writeln!(file, "{angle}", angle = arc.start_angle.to_degrees())?;
-                writeln!(file, "51")?;
-                writeln!(file, "{angle}", angle = arc.end_angle.to_degrees())?;
-            }
-            DxfEntity::Text {
-                position,
-                height,
-                value,
-                layer,
-            } => {
-                writeln!(file, "0")?;
-                writeln!(file, "TEXT")?;
-                if let Some(l) = layer {
-                    writeln!(file, "8")?;
-                    writeln!(file, "{l}")?;
-                }
-                writeln!(file, "10")?;
-                writeln!(file, "{x}", x = position.x)?;
-                writeln!(file, "20")?;
-                writeln!(file, "{y}", y = position.y)?;
-                writeln!(file, "40")?;
-                writeln!(file, "{height}")?;
-                writeln!(file, "1")?;
-                writeln!(file, "{value}")?;
-            }
-        }
-    }
-    writeln!(file, "0")?;
-    writeln!(file, "ENDSEC")?;
-    writeln!(file, "0")?;
-    writeln!(file, "EOF")?;
-    Ok(())
-}
-
-/// Reads a very simple DXF file and returns any supported [`DxfEntity`] values.
-pub fn read_dxf(path: &str) -> io::Result<Vec<DxfEntity>> {
-    let lines = read_lines(path)?;
-    let mut iter = lines.iter();
-    let mut entities = Vec::new();
-    while let (Some(code), Some(value)) = (iter.next(), iter.next()) {
-        if code.trim() != "0" {
-            continue;
-        }
-        match value.trim() {
-            "POINT" => {
-                let mut x = None;
-                let mut y = None;
-                let mut layer = None;
-                while let (Some(c), Some(v)) = (iter.next(), iter.next()) {
-                    match c.trim() {
-                        "8" => layer = Some(v.trim().to_string()),
-                        "10" => x = v.trim().parse().ok(),
-                        "20" => y = v.trim().parse().ok(),
-                        "30" => break,
-                        _ => {}
-                    }
-                }
-                if let (Some(x), Some(y)) = (x, y) {
-                    entities.push(DxfEntity::Point {
-                        point: Point::new(x, y),
-                        layer,
-                    });
-                }
-            }
-            "LINE" => {
-                let mut sx = None;
-                let mut sy = None;
-                let mut ex = None;
-                let mut ey = None;
-                let mut layer = None;
-                while let (Some(c), Some(v)) = (iter.next(), iter.next()) {
-                    match c.trim() {
-                        "8" => layer = Some(v.trim().to_string()),
-                        "10" => sx = v.trim().parse().ok(),
-                        "20" => sy = v.trim().parse().ok(),
-                        "11" => ex = v.trim().parse().ok(),
-                        "21" => {
-                            ey = v.trim().parse().ok();
-                            break;
-                        }
-                        _ => {}
-                    }
-                }
-                if let (Some(sx), Some(sy), Some(ex), Some(ey)) = (sx, sy, ex, ey) {
-                    entities.push(DxfEntity::Line {
-                        line: crate::geometry::Line::new(Point::new(sx, sy), Point::new(ex, ey)),
-                        layer,
-                    });
-                }
-            }
-            "POLYLINE" => {
-                let mut verts = Vec::new();
-                let mut layer = None;
-                while let (Some(c), Some(v)) = (iter.next(), iter.next()) {
-                    match c.trim() {
-                        "8" => layer = Some(v.trim().to_string()),
-                        "0" if v.trim() == "VERTEX" => {
-                            let mut vx = None;
-                            let mut vy = None;
-                            while let (Some(c2), Some(v2)) = (iter.next(), iter.next()) {
-                                match c2.trim() {
-                                    "10" => vx = v2.trim().parse().ok(),
-                                    "20" => vy = v2.trim().parse().ok(),
-                                    "30" => break,
-                                    _ => {}
-                                }
-                            }
-                            if let (Some(x), Some(y)) = (vx, vy) {
-                                verts.push(Point::new(x, y));
-                            }
-                        }
-                        "0" if v.trim() == "SEQEND" => break,
-                        _ => {}
-                    }
-                }
-                if !verts.is_empty() {
-                    entities.push(DxfEntity::Polyline {
-                        polyline: Polyline::new(verts),
-                        layer,
-                    });
-                }
-            }
-            "ARC" => {
-                let mut cx = None;
-                let mut cy = None;
-                let mut radius = None;
-                let mut start = None;
-                let mut end = None;
-                let mut layer = None;
-                while let (Some(c), Some(v)) = (iter.next(), iter.next()) {
-                    match c.trim() {
-                        "8" => layer = Some(v.trim().to_string()),
-                        "10" => cx = v.trim().parse().ok(),
-                        "20" => cy = v.trim().parse().ok(),
-                        "40" => radius = v.trim().parse().ok(),
-                        "50" => start = v.trim().parse::<f64>().ok().map(|d| d.to_radians()),
-                        "51" => {
-                            end = v.trim().parse::<f64>().ok().map(|d| d.to_radians());
-                            break;
-                        }
-                        _ => {}
-                    }
-                }
-                if let (Some(cx), Some(cy), Some(r), Some(sa), Some(ea)) =
-                    (cx, cy, radius, start, end)
-                {
-                    let arc = Arc::new(Point::new(cx, cy), r, sa, ea);
-                    entities.push(DxfEntity::Arc { arc, layer });
-                }
-            }
-            "TEXT" => {
-                let mut x = None;
-                let mut y = None;
-                let mut h = None;
-                let mut val = None;
-                let mut layer = None;
-                while let (Some(c), Some(v)) = (iter.next(), iter.next()) {
-                    match c.trim() {
-                        "8" => layer = Some(v.trim().to_string()),
-                        "10" => x = v.trim().parse().ok(),
-                        "20" => y = v.trim().parse().ok(),
-                        "40" => h = v.trim().parse().ok(),
-                        "1" => {
-                            val = Some(v.trim().to_string());
-                            break;
-                        }
-                        _ => {}
-                    }
-                }
-                if let (Some(x), Some(y), Some(h), Some(val)) = (x, y, h, val) {
-                    entities.push(DxfEntity::Text {
-                        position: Point::new(x, y),
-                        height: h,
-                        value: val,
-                        layer,
-                    });
-                }
-            }
-            _ => {}
-        }
-    }
-    Ok(entities)
-}
-
 /// Writes supported [`DxfEntity`] values to a DWG file using the external
 /// `dxf2dwg` command from the LibreDWG project. The entities are first written
 /// to a temporary DXF file and then converted to DWG. An error is returned if
@@ -589,26 +331,6 @@ pub fn write_dwg(path: &str, entities: &[DxfEntity]) -> io::Result<()> {
     Ok(())
 }
 
-/// Reads supported [`DxfEntity`] values from a DWG file using the external
-/// `dwg2dxf` command from the LibreDWG project. The DWG is converted to a
-/// temporary DXF file which is then parsed using [`read_dxf`]. An error is
-/// returned if the command fails or is not available.
-pub fn read_dwg(path: &str) -> io::Result<Vec<DxfEntity>> {
-    use std::process::Command;
-    use tempfile::NamedTempFile;
-
-    let tmp = NamedTempFile::new()?;
-    let status = Command::new("dwg2dxf")
-        .arg(path)
-        .arg(tmp.path())
-        .status()
-        .map_err(|e| io::Error::other(format!("failed to spawn dwg2dxf: {e}")))?;
-    if !status.success() {
-        return Err(io::Error::other("dwg2dxf failed"));
-    }
-    read_dxf(tmp.path().to_str().unwrap())
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -617,7 +339,7 @@ mod tests {
     };
     use crate::corridor::CrossSection;
     use crate::dtm::Tin;
-    use crate::geometry::Point3;
+    use crate::geometry::{Arc, Point3, Polyline};
     use crate::superelevation::SuperelevationPoint;
 
     #[test]
@@ -697,7 +419,7 @@ mod tests {
     }
 
     #[test]
-    fn dwg_functions_return_error_without_tools() {
+    fn write_dwg_returns_error_without_dxf2dwg() {
         let path = std::env::temp_dir().join("dummy.dwg");
         let path_str = path.to_str().unwrap();
         let entities = vec![DxfEntity::Point {
@@ -706,8 +428,13 @@ mod tests {
         }];
         let err = write_dwg(path_str, &entities).unwrap_err();
         assert_eq!(err.kind(), io::ErrorKind::Other);
-        let err2 = read_dwg(path_str).unwrap_err();
-        assert_eq!(err2.kind(), io::ErrorKind::Other);
+    }
+
+    #[test]
+    fn read_dwg_reports_missing_file() {
+        let path = std::env::temp_dir().join("missing_drawing.dwg");
+        let err = read_dwg(path.to_str().unwrap()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
     }
 
     #[test]
@@ -791,6 +518,30 @@ mod tests {
         std::fs::remove_file(path).ok();
     }
 
+    #[test]
+    fn write_and_read_landxml_parcels() {
+        let path = std::env::temp_dir().join("parcels.xml");
+        let mut lot = crate::parcel::Parcel::with_bulges(
+            vec![
+                Point::new(0.0, 0.0),
+                Point::new(20.0, 0.0),
+                Point::new(20.0, 20.0),
+                Point::new(0.0, 20.0),
+            ],
+            vec![0.0, -0.3, 1.0, 0.0],
+        );
+        lot.name = Some("Lot 1".into());
+        landxml::write_landxml_parcels(path.to_str().unwrap(), &[lot.clone()], None).unwrap();
+        let read = landxml::read_landxml_parcels(path.to_str().unwrap()).unwrap();
+        assert_eq!(read.len(), 1);
+        assert_eq!(read[0].name.as_deref(), Some("Lot 1"));
+        for (a, b) in read[0].bulges.iter().zip(&lot.bulges) {
+            assert!((a - b).abs() < 1e-9);
+        }
+        assert!((read[0].area() - lot.area()).abs() < 1e-6);
+        std::fs::remove_file(path).ok();
+    }
+
     #[test]
     fn write_and_read_landxml_profile() {
         let path = std::env::temp_dir().join("profile.xml");
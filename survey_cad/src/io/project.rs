@@ -1,5 +1,7 @@
 use serde::{Deserialize, Serialize};
 
+use crate::alignment::{map_arc, map_point};
+use crate::crs::CoordinateTransform;
 use crate::dtm::Tin;
 use crate::geometry::{Arc, Line, Point, Polyline};
 use crate::layers::Layer;
@@ -48,10 +50,30 @@ pub struct Project {
     pub grid: GridSettings,
     #[serde(default)]
     pub crs_epsg: u32,
+    /// Vertical datum the point and surface elevations refer to.
+    #[serde(default)]
+    pub vertical_datum: Option<crate::geoid::VerticalDatum>,
+    /// Ground coordinate system the project coordinates are expressed in.
+    #[serde(default)]
+    pub ground: Option<crate::local_grid::GroundCoordinateSystem>,
     #[serde(default)]
     pub point_label_font: String,
     #[serde(default)]
     pub point_label_offset: [f32; 2],
+    /// Named points with codes, e.g. from a LandXML `<CgPoints>` collection.
+    #[serde(default)]
+    pub cg_points: Vec<crate::io::landxml::CgPoint>,
+    #[serde(default)]
+    pub point_groups: Vec<crate::io::landxml::CgPointGroup>,
+    #[serde(default)]
+    pub parcels: Vec<crate::parcel::Parcel>,
+    #[serde(default)]
+    pub plan_features: Vec<crate::io::landxml::PlanFeature>,
+    #[serde(default)]
+    pub pipe_networks: Vec<crate::io::landxml::PipeNetwork>,
+    /// Raw field observations of the project.
+    #[serde(default)]
+    pub observations: Vec<crate::surveying::ObservationRecord>,
 }
 
 impl Project {
@@ -74,8 +96,80 @@ impl Project {
             polygon_style_indices: Vec::new(),
             grid: GridSettings::default(),
             crs_epsg: 4326,
+            vertical_datum: None,
+            ground: None,
             point_label_font: "DejaVuSans".to_string(),
             point_label_offset: [5.0, 5.0],
+            cg_points: Vec::new(),
+            point_groups: Vec::new(),
+            parcels: Vec::new(),
+            plan_features: Vec::new(),
+            pipe_networks: Vec::new(),
+            observations: Vec::new(),
+        }
+    }
+}
+
+impl Project {
+    /// Applies a coordinate transformation, e.g. a fitted site calibration,
+    /// to all geometry in the project. Arcs and alignment curves are mapped
+    /// using the local rotation and scale at their centre or start point.
+    pub fn transform_with(&mut self, transform: &impl CoordinateTransform) {
+        let map = |p: Point| map_point(transform, p);
+        for p in &mut self.points {
+            *p = map(*p);
+        }
+        for l in &mut self.lines {
+            l.start = map(l.start);
+            l.end = map(l.end);
+        }
+        for poly in &mut self.polygons {
+            for p in poly.iter_mut() {
+                *p = map(*p);
+            }
+        }
+        for pl in &mut self.polylines {
+            for p in &mut pl.vertices {
+                *p = map(*p);
+            }
+        }
+        for arc in &mut self.arcs {
+            map_arc(transform, arc);
+        }
+        for d in &mut self.dimensions {
+            d.start = map(d.start);
+            d.end = map(d.end);
+        }
+        for tin in &mut self.surfaces {
+            for v in &mut tin.vertices {
+                if let Some((x, y, z)) = transform.transform(v.x, v.y, v.z) {
+                    *v = crate::geometry::Point3::new(x, y, z);
+                }
+            }
+        }
+        let map3 = |p: &mut crate::geometry::Point3| {
+            if let Some((x, y, z)) = transform.transform(p.x, p.y, p.z) {
+                *p = crate::geometry::Point3::new(x, y, z);
+            }
+        };
+        for p in &mut self.cg_points {
+            map3(&mut p.point);
+        }
+        for parcel in &mut self.parcels {
+            for p in &mut parcel.boundary {
+                *p = map(*p);
+            }
+        }
+        for f in &mut self.plan_features {
+            f.vertices.iter_mut().for_each(map3);
+        }
+        for net in &mut self.pipe_networks {
+            for s in &mut net.structures {
+                map3(&mut s.center);
+            }
+        }
+        for al in &mut self.alignments {
+            al.horizontal.transform_with(transform);
         }
     }
 }
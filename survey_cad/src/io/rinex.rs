@@ -0,0 +1,450 @@
+//! RINEX 2.x and 3.x observation and navigation file readers.
+//!
+//! Header records are identified by the label in columns 61-80. Observation
+//! epochs keep the raw values in the order given by the header observation
+//! types for the satellite system. Navigation records keep the clock terms
+//! and the broadcast orbit parameters as written.
+
+use chrono::{NaiveDate, NaiveDateTime};
+use std::collections::HashMap;
+use std::io;
+
+/// Header information shared by observation and navigation files.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RinexHeader {
+    pub version: f64,
+    /// File type character (`O` observation, `N`/`G` navigation).
+    pub file_type: char,
+    /// Satellite system character (`G`, `R`, `E`, `C`, `M` for mixed...).
+    pub satellite_system: char,
+    pub marker_name: Option<String>,
+    pub marker_number: Option<String>,
+    pub receiver_number: Option<String>,
+    pub receiver_type: Option<String>,
+    pub antenna_number: Option<String>,
+    pub antenna_type: Option<String>,
+    /// Approximate marker position in ECEF metres.
+    pub approx_position: Option<[f64; 3]>,
+    /// Antenna height, east and north eccentricities in metres.
+    pub antenna_delta: Option<[f64; 3]>,
+    /// Observation types per satellite system. RINEX 2 types are stored
+    /// under the file's satellite system.
+    pub observation_types: HashMap<char, Vec<String>>,
+    pub interval: Option<f64>,
+    pub first_observation: Option<NaiveDateTime>,
+}
+
+impl RinexHeader {
+    /// Observation types recorded for the given satellite system.
+    pub fn types_for(&self, system: char) -> &[String] {
+        self.observation_types
+            .get(&system)
+            .or_else(|| self.observation_types.get(&self.satellite_system))
+            .map(Vec::as_slice)
+            .unwrap_or(&[])
+    }
+}
+
+/// Observations of one satellite within an epoch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SatelliteObservation {
+    /// Satellite id such as `G05`.
+    pub satellite: String,
+    /// Values in header order; blank fields are `None`.
+    pub values: Vec<Option<f64>>,
+}
+
+/// A single observation epoch.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RinexEpoch {
+    pub time: NaiveDateTime,
+    /// Epoch flag (0 = OK, 1 = power failure, >1 = event).
+    pub flag: u8,
+    pub satellites: Vec<SatelliteObservation>,
+}
+
+/// Contents of a RINEX observation file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RinexObservation {
+    pub header: RinexHeader,
+    pub epochs: Vec<RinexEpoch>,
+}
+
+/// Broadcast ephemeris record from a navigation file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ephemeris {
+    pub satellite: String,
+    /// Time of clock.
+    pub toc: NaiveDateTime,
+    pub clock_bias: f64,
+    pub clock_drift: f64,
+    pub clock_drift_rate: f64,
+    /// Broadcast orbit values in file order, four per line, with blank
+    /// fields stored as zero so each index is always the same parameter.
+    pub orbit: Vec<f64>,
+}
+
+/// Contents of a RINEX navigation file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RinexNavigation {
+    pub header: RinexHeader,
+    pub ephemerides: Vec<Ephemeris>,
+}
+
+fn invalid(line: usize, msg: impl std::fmt::Display) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, format!("line {line}: {msg}"))
+}
+
+fn col(line: &str, start: usize, len: usize) -> &str {
+    let end = (start + len).min(line.len());
+    line.get(start.min(end)..end).unwrap_or("").trim()
+}
+
+fn opt_string(s: &str) -> Option<String> {
+    let s = s.trim();
+    (!s.is_empty()).then(|| s.to_string())
+}
+
+fn parse_f64(s: &str, line: usize) -> io::Result<f64> {
+    s.trim()
+        .replace(['D', 'd'], "E")
+        .parse()
+        .map_err(|_| invalid(line, format!("invalid number '{}'", s.trim())))
+}
+
+fn parse_opt_f64(s: &str, line: usize) -> io::Result<Option<f64>> {
+    if s.trim().is_empty() {
+        Ok(None)
+    } else {
+        parse_f64(s, line).map(Some)
+    }
+}
+
+fn parse_time(fields: &[&str], line: usize) -> io::Result<NaiveDateTime> {
+    if fields.len() < 6 {
+        return Err(invalid(line, "incomplete epoch time"));
+    }
+    let int = |s: &str| -> io::Result<i64> {
+        s.parse()
+            .map_err(|_| invalid(line, format!("invalid time field '{s}'")))
+    };
+    let mut year = int(fields[0])?;
+    if year < 100 {
+        year += if year < 80 { 2000 } else { 1900 };
+    }
+    let sec = parse_f64(fields[5], line)?;
+    let whole = sec.trunc();
+    let nanos = ((sec - whole) * 1e9).round() as u32;
+    let (month, day) = (int(fields[1])? as u32, int(fields[2])? as u32);
+    let (hour, min) = (int(fields[3])? as u32, int(fields[4])? as u32);
+    NaiveDate::from_ymd_opt(year as i32, month, day)
+        .and_then(|d| d.and_hms_nano_opt(hour, min, whole as u32, nanos))
+        .ok_or_else(|| invalid(line, "invalid epoch time"))
+}
+
+/// Parses the header returning it with the index of the first data line.
+fn parse_header(lines: &[&str]) -> io::Result<(RinexHeader, usize)> {
+    let mut h = RinexHeader::default();
+    let mut pending_types: Option<(char, usize)> = None;
+    for (i, line) in lines.iter().enumerate() {
+        let no = i + 1;
+        let label = col(line, 60, 20);
+        let data = line.get(..60.min(line.len())).unwrap_or("");
+        match label {
+            "RINEX VERSION / TYPE" => {
+                h.version = parse_f64(col(line, 0, 9), no)?;
+                h.file_type = col(line, 20, 1).chars().next().unwrap_or(' ');
+                h.satellite_system = col(line, 40, 1).chars().next().unwrap_or('G');
+                if h.file_type == 'N' && h.version < 3.0 {
+                    h.satellite_system = 'G';
+                } else if h.file_type == 'G' {
+                    h.satellite_system = 'R';
+                }
+            }
+            "MARKER NAME" => h.marker_name = opt_string(data),
+            "MARKER NUMBER" => h.marker_number = opt_string(data),
+            "REC # / TYPE / VERS" => {
+                h.receiver_number = opt_string(col(line, 0, 20));
+                h.receiver_type = opt_string(col(line, 20, 20));
+            }
+            "ANT # / TYPE" => {
+                h.antenna_number = opt_string(col(line, 0, 20));
+                h.antenna_type = opt_string(col(line, 20, 20));
+            }
+            "APPROX POSITION XYZ" => {
+                h.approx_position = Some([
+                    parse_f64(col(line, 0, 14), no)?,
+                    parse_f64(col(line, 14, 14), no)?,
+                    parse_f64(col(line, 28, 14), no)?,
+                ]);
+            }
+            "ANTENNA: DELTA H/E/N" => {
+                h.antenna_delta = Some([
+                    parse_f64(col(line, 0, 14), no)?,
+                    parse_f64(col(line, 14, 14), no)?,
+                    parse_f64(col(line, 28, 14), no)?,
+                ]);
+            }
+            "# / TYPES OF OBSERV" => {
+                let sys = h.satellite_system;
+                let count = match col(line, 0, 6) {
+                    "" => pending_types.map(|(_, n)| n).unwrap_or(0),
+                    n => {
+                        h.observation_types.insert(sys, Vec::new());
+                        n.parse().unwrap_or(0)
+                    }
+                };
+                let types = h.observation_types.entry(sys).or_default();
+                for k in 0..9 {
+                    let t = col(line, 6 + k * 6, 6);
+                    if !t.is_empty() && types.len() < count {
+                        types.push(t.to_string());
+                    }
+                }
+                pending_types = (types.len() < count).then_some((sys, count));
+            }
+            "SYS / # / OBS TYPES" => {
+                let sys_field = col(line, 0, 1);
+                let (sys, count) = if sys_field.is_empty() {
+                    pending_types.ok_or_else(|| invalid(no, "orphan observation types line"))?
+                } else {
+                    let sys = sys_field.chars().next().unwrap_or('G');
+                    h.observation_types.insert(sys, Vec::new());
+                    (sys, col(line, 3, 3).parse().unwrap_or(0))
+                };
+                let types = h.observation_types.entry(sys).or_default();
+                for k in 0..13 {
+                    let t = col(line, 7 + k * 4, 3);
+                    if !t.is_empty() && types.len() < count {
+                        types.push(t.to_string());
+                    }
+                }
+                pending_types = (types.len() < count).then_some((sys, count));
+            }
+            "INTERVAL" => h.interval = parse_opt_f64(col(line, 0, 10), no)?,
+            "TIME OF FIRST OBS" => {
+                let fields: Vec<&str> = data.split_whitespace().collect();
+                h.first_observation = Some(parse_time(&fields, no)?);
+            }
+            "END OF HEADER" => return Ok((h, i + 1)),
+            _ => {}
+        }
+    }
+    Err(invalid(lines.len(), "missing END OF HEADER"))
+}
+
+/// Parses the contents of a RINEX 2.x or 3.x observation file.
+pub fn parse_rinex_obs(text: &str) -> io::Result<RinexObservation> {
+    let lines: Vec<&str> = text.lines().collect();
+    let (header, start) = parse_header(&lines)?;
+    if header.file_type != 'O' {
+        return Err(invalid(1, "not a RINEX observation file"));
+    }
+    let epochs = if header.version >= 3.0 {
+        parse_obs_v3(&header, &lines, start)?
+    } else {
+        parse_obs_v2(&header, &lines, start)?
+    };
+    Ok(RinexObservation { header, epochs })
+}
+
+fn parse_values(text: &str, count: usize, line: usize) -> io::Result<Vec<Option<f64>>> {
+    // each value is F14.3 followed by LLI and signal strength digits
+    (0..count)
+        .map(|k| parse_opt_f64(col(text, k * 16, 14), line))
+        .collect()
+}
+
+fn parse_obs_v3(h: &RinexHeader, lines: &[&str], start: usize) -> io::Result<Vec<RinexEpoch>> {
+    let mut epochs = Vec::new();
+    let mut i = start;
+    while i < lines.len() {
+        let line = lines[i];
+        i += 1;
+        if !line.starts_with('>') {
+            if line.trim().is_empty() {
+                continue;
+            }
+            return Err(invalid(i, "expected epoch record"));
+        }
+        let fields: Vec<&str> = line[1..].split_whitespace().collect();
+        let time = parse_time(&fields, i)?;
+        let flag: u8 = fields.get(6).and_then(|f| f.parse().ok()).unwrap_or(0);
+        let count: usize = fields.get(7).and_then(|f| f.parse().ok()).unwrap_or(0);
+        let mut satellites = Vec::with_capacity(count);
+        for _ in 0..count {
+            let Some(line) = lines.get(i) else {
+                return Err(invalid(i, "truncated epoch"));
+            };
+            i += 1;
+            if flag > 1 {
+                // event records carry header lines instead of observations
+                continue;
+            }
+            let satellite = col(line, 0, 3).replace(' ', "0");
+            let system = satellite.chars().next().unwrap_or('G');
+            let n = h.types_for(system).len();
+            let values = parse_values(line.get(3..).unwrap_or(""), n, i)?;
+            satellites.push(SatelliteObservation { satellite, values });
+        }
+        if flag <= 1 {
+            epochs.push(RinexEpoch {
+                time,
+                flag,
+                satellites,
+            });
+        }
+    }
+    Ok(epochs)
+}
+
+fn parse_obs_v2(h: &RinexHeader, lines: &[&str], start: usize) -> io::Result<Vec<RinexEpoch>> {
+    let mut epochs = Vec::new();
+    let n_types = h.types_for(h.satellite_system).len();
+    let lines_per_sat = n_types.div_ceil(5).max(1);
+    let mut i = start;
+    while i < lines.len() {
+        let line = lines[i];
+        i += 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let flag: u8 = col(line, 28, 1).parse().unwrap_or(0);
+        let count: usize = col(line, 29, 3)
+            .parse()
+            .map_err(|_| invalid(i, "invalid satellite count"))?;
+        if flag > 1 {
+            // event flag: `count` is the number of special records that
+            // follow and the epoch time may be blank
+            i += count;
+            continue;
+        }
+        let fields: Vec<&str> = col(line, 0, 26).split_whitespace().collect();
+        let time = parse_time(&fields, i)?;
+        let mut ids = Vec::with_capacity(count);
+        let mut list = line.get(32..).unwrap_or("").to_string();
+        while ids.len() < count {
+            for k in 0..12 {
+                if ids.len() == count {
+                    break;
+                }
+                let id = col(&list, k * 3, 3);
+                if id.is_empty() {
+                    break;
+                }
+                let mut id = id.replace(' ', "0");
+                if id.len() < 3 || id.starts_with(|c: char| c.is_ascii_digit()) {
+                    id = format!("{}{:0>2}", h.satellite_system, id.trim_start_matches('0'));
+                }
+                ids.push(id);
+            }
+            if ids.len() < count {
+                let Some(next) = lines.get(i) else {
+                    return Err(invalid(i, "truncated satellite list"));
+                };
+                i += 1;
+                list = next.get(32..).unwrap_or("").to_string();
+            }
+        }
+        let mut satellites = Vec::with_capacity(count);
+        for satellite in ids {
+            let mut joined = String::new();
+            for _ in 0..lines_per_sat {
+                let Some(l) = lines.get(i) else {
+                    return Err(invalid(i, "truncated epoch"));
+                };
+                i += 1;
+                joined.push_str(&format!("{l:<80}"));
+            }
+            // values are in 80 character blocks of five
+            let mut values = Vec::with_capacity(n_types);
+            for k in 0..n_types {
+                let block = k / 5;
+                let offset = block * 80 + (k % 5) * 16;
+                values.push(parse_opt_f64(col(&joined, offset, 14), i)?);
+            }
+            satellites.push(SatelliteObservation { satellite, values });
+        }
+        epochs.push(RinexEpoch {
+            time,
+            flag,
+            satellites,
+        });
+    }
+    Ok(epochs)
+}
+
+/// Parses the contents of a RINEX 2.x or 3.x navigation file.
+pub fn parse_rinex_nav(text: &str) -> io::Result<RinexNavigation> {
+    let lines: Vec<&str> = text.lines().collect();
+    let (header, start) = parse_header(&lines)?;
+    if !matches!(header.file_type, 'N' | 'G') {
+        return Err(invalid(1, "not a RINEX navigation file"));
+    }
+    let v3 = header.version >= 3.0;
+    let mut ephemerides = Vec::new();
+    let mut i = start;
+    while i < lines.len() {
+        let line = lines[i];
+        i += 1;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (satellite, time_text, values_start, orbit_indent) = if v3 {
+            (col(line, 0, 3).replace(' ', "0"), col(line, 4, 19), 23, 4)
+        } else {
+            let prn: u32 = col(line, 0, 2)
+                .parse()
+                .map_err(|_| invalid(i, "invalid satellite number"))?;
+            (
+                format!("{}{prn:02}", header.satellite_system),
+                col(line, 3, 19),
+                22,
+                3,
+            )
+        };
+        let system = satellite.chars().next().unwrap_or('G');
+        let fields: Vec<&str> = time_text.split_whitespace().collect();
+        let toc = parse_time(&fields, i)?;
+        let clock_bias = parse_f64(col(line, values_start, 19), i)?;
+        let clock_drift = parse_f64(col(line, values_start + 19, 19), i)?;
+        let clock_drift_rate = parse_f64(col(line, values_start + 38, 19), i)?;
+        let orbit_lines = match system {
+            'R' | 'S' => 3,
+            _ => 7,
+        };
+        let mut orbit = Vec::with_capacity(orbit_lines * 4);
+        for _ in 0..orbit_lines {
+            let Some(l) = lines.get(i) else {
+                return Err(invalid(i, "truncated navigation record"));
+            };
+            i += 1;
+            for k in 0..4 {
+                let v = parse_opt_f64(col(l, orbit_indent + k * 19, 19), i)?;
+                orbit.push(v.unwrap_or(0.0));
+            }
+        }
+        ephemerides.push(Ephemeris {
+            satellite,
+            toc,
+            clock_bias,
+            clock_drift,
+            clock_drift_rate,
+            orbit,
+        });
+    }
+    Ok(RinexNavigation {
+        header,
+        ephemerides,
+    })
+}
+
+/// Reads a RINEX observation file.
+pub fn read_rinex_obs(path: &str) -> io::Result<RinexObservation> {
+    parse_rinex_obs(&std::fs::read_to_string(path)?)
+}
+
+/// Reads a RINEX navigation file.
+pub fn read_rinex_nav(path: &str) -> io::Result<RinexNavigation> {
+    parse_rinex_nav(&std::fs::read_to_string(path)?)
+}
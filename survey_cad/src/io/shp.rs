@@ -1,5 +1,6 @@
-use crate::geometry::{Point, Point3};
+use crate::geometry::{Point, Point3, Polyline3};
 use crate::gis::Feature;
+use crate::parcel::Parcel;
 use shapefile::dbase::TableWriterBuilder;
 use shapefile::dbase::{FieldName, FieldValue, Record};
 use shapefile::{
@@ -222,6 +223,64 @@ pub fn read_polylines_shp(path: &str) -> io::Result<PolylineOutput> {
     Ok((lines, lines3))
 }
 
+/// Reads PolyLine and PolyLineZ parts as [`Polyline3`]s. Parts without Z
+/// get zero elevations and parts ending on their first vertex are closed.
+pub fn read_polylines3_shp(path: &str) -> io::Result<Vec<Polyline3>> {
+    let mut reader =
+        ShapeReader::from_path(path).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    let mut lines = Vec::new();
+    for record in reader.iter_shapes() {
+        let parts: Vec<Vec<Point3>> =
+            match record.map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))? {
+                Shape::Polyline(pl) => pl
+                    .parts()
+                    .iter()
+                    .map(|part| part.iter().map(|p| Point3::new(p.x, p.y, 0.0)).collect())
+                    .collect(),
+                Shape::PolylineZ(pl) => pl
+                    .parts()
+                    .iter()
+                    .map(|part| part.iter().map(|p| Point3::new(p.x, p.y, p.z)).collect())
+                    .collect(),
+                _ => continue,
+            };
+        for mut verts in parts {
+            let closed = verts.len() > 3 && verts.first() == verts.last();
+            if closed {
+                verts.pop();
+            }
+            let mut pl = Polyline3::new(verts);
+            pl.closed = closed;
+            lines.push(pl);
+        }
+    }
+    Ok(lines)
+}
+
+/// Writes [`Polyline3`]s to a PolyLineZ shapefile. Shapefiles have no
+/// arcs, so these are split into chords subtending at most `max_angle`
+/// radians.
+pub fn write_polylines3_shp(path: &str, polylines: &[Polyline3], max_angle: f64) -> io::Result<()> {
+    let mut writer = ShapeWriter::from_path(path).map_err(io::Error::other)?;
+    for pl in polylines {
+        let mut pts = pl.densify(max_angle);
+        if pl.closed {
+            pts.extend(pts.first().copied());
+        }
+        if pts.len() < 2 {
+            continue;
+        }
+        let shp_pts: Vec<ShpPointZ> = pts
+            .iter()
+            .map(|p| ShpPointZ::new(p.x, p.y, p.z, NO_DATA))
+            .collect();
+        writer
+            .write_shape(&PolylineZ::new(shp_pts))
+            .map_err(io::Error::other)?;
+    }
+    writer.finalize().map_err(io::Error::other)
+}
+
 /// Writes a list of [`Polyline`]s to a shapefile.
 pub fn write_polylines_shp(
     path: &str,
@@ -563,3 +622,41 @@ pub fn write_polygon_records_shp(path: &str, records: &[PolygonRecord]) -> io::R
     }
     Ok(())
 }
+
+/// Writes parcels as polygons with `NAME` and `AREA` attributes. Shapefiles
+/// can't hold arcs, so curved edges are written as the chords of
+/// [`Parcel::to_polygon`] while `AREA` keeps the exact area.
+pub fn write_parcels_shp(path: &str, parcels: &[Parcel]) -> io::Result<()> {
+    let records: Vec<PolygonRecord> = parcels
+        .iter()
+        .map(|p| {
+            let mut attrs = BTreeMap::new();
+            attrs.insert("NAME".to_string(), FieldValue::Character(p.name.clone()));
+            attrs.insert("AREA".to_string(), FieldValue::Numeric(Some(p.area())));
+            PolygonRecord {
+                geom: p.to_polygon(),
+                geom_z: None,
+                attrs,
+            }
+        })
+        .collect();
+    write_polygon_records_shp(path, &records)
+}
+
+/// Reads polygon rings as parcels, naming them from a `NAME` attribute.
+pub fn read_parcels_shp(path: &str) -> io::Result<Vec<Parcel>> {
+    Ok(read_polygon_records_shp(path)?
+        .into_iter()
+        .map(|rec| {
+            let mut boundary = rec.geom;
+            if boundary.len() > 1 && boundary.first() == boundary.last() {
+                boundary.pop();
+            }
+            let mut parcel = Parcel::new(boundary);
+            if let Some(FieldValue::Character(Some(name))) = rec.attrs.get("NAME") {
+                parcel.name = Some(name.trim().to_string());
+            }
+            parcel
+        })
+        .collect())
+}
@@ -1,9 +1,11 @@
 //! Core library for the Survey CAD application.
 
 pub mod alignment;
+pub mod blocks;
 pub mod corridor;
 pub mod crs;
 pub mod dtm;
+pub mod geoid;
 pub mod geometry;
 pub mod grip;
 pub mod intersection;
@@ -26,6 +28,7 @@ pub mod surveying;
 pub mod qa;
 #[cfg(feature = "reporting")]
 pub mod reporting;
+pub mod transformation;
 pub mod truck_integration;
 pub mod variable_offset;
 pub mod workspace;
@@ -33,6 +36,9 @@ pub mod point_database;
 
 #[cfg(feature = "render")]
 pub use lidar::point_cloud_to_mesh;
-pub use lidar::{classify_points, extract_breaklines, filter_noise, Classification};
-pub use local_grid::LocalGrid;
+pub use lidar::{
+    classify_cloud, classify_points, extract_breaklines, filter_noise, Classification, CloudPoint,
+    PointCloud,
+};
+pub use local_grid::{GroundCoordinateSystem, LocalGrid};
 pub use point_database::{PointDatabase, PointGroup};
@@ -0,0 +1,539 @@
+//! Bare-earth filters and ground surface decimation.
+//!
+//! Two filters are available: the progressive morphological filter of
+//! Zhang et al. (2003), which opens a minimum-elevation raster with growing
+//! windows and height thresholds that follow the terrain slope, and the
+//! cloth simulation filter of Zhang et al. (2016), which drops a stiff cloth
+//! onto the inverted cloud and keeps the points the cloth settles on.
+
+use super::{asprs, PointCloud};
+use crate::dtm::Tin;
+use crate::geometry::{convex_hull, Point, Point3};
+use std::collections::HashMap;
+
+/// Parameters of the progressive morphological filter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PmfParams {
+    /// Raster cell size.
+    pub cell_size: f64,
+    /// Largest opening window in ground units, roughly the size of the
+    /// largest building to remove.
+    pub max_window: f64,
+    /// Terrain slope (rise over run) used to grow the height threshold.
+    pub slope: f64,
+    /// Height threshold of the first window.
+    pub initial_distance: f64,
+    /// Upper limit of the height threshold.
+    pub max_distance: f64,
+}
+
+impl Default for PmfParams {
+    fn default() -> Self {
+        Self {
+            cell_size: 1.0,
+            max_window: 20.0,
+            slope: 0.3,
+            initial_distance: 0.15,
+            max_distance: 2.5,
+        }
+    }
+}
+
+/// Parameters of the cloth simulation filter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CsfParams {
+    /// Spacing of the cloth particles.
+    pub cloth_resolution: f64,
+    /// Stiffness of the cloth from 1 (steep terrain) to 3 (flat terrain).
+    pub rigidness: u32,
+    pub time_step: f64,
+    /// Largest distance from the settled cloth for a ground point.
+    pub class_threshold: f64,
+    pub max_iterations: usize,
+}
+
+impl Default for CsfParams {
+    fn default() -> Self {
+        Self {
+            cloth_resolution: 0.5,
+            rigidness: 2,
+            time_step: 0.65,
+            class_threshold: 0.5,
+            max_iterations: 500,
+        }
+    }
+}
+
+/// Ground filter used by [`classify_ground`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GroundMethod {
+    Pmf(PmfParams),
+    Csf(CsfParams),
+}
+
+/// Regular XY raster covering a set of points.
+struct Raster {
+    min_x: f64,
+    min_y: f64,
+    cell: f64,
+    cols: usize,
+    rows: usize,
+}
+
+impl Raster {
+    fn covering(points: &[Point3], cell: f64) -> Self {
+        let cell = if cell > 0.0 { cell } else { 1.0 };
+        let (mut min_x, mut min_y) = (f64::INFINITY, f64::INFINITY);
+        let (mut max_x, mut max_y) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for p in points {
+            min_x = min_x.min(p.x);
+            min_y = min_y.min(p.y);
+            max_x = max_x.max(p.x);
+            max_y = max_y.max(p.y);
+        }
+        Self {
+            min_x,
+            min_y,
+            cell,
+            cols: ((max_x - min_x) / cell).floor() as usize + 1,
+            rows: ((max_y - min_y) / cell).floor() as usize + 1,
+        }
+    }
+
+    fn index(&self, p: Point3) -> usize {
+        let c = (((p.x - self.min_x) / self.cell).floor() as usize).min(self.cols - 1);
+        let r = (((p.y - self.min_y) / self.cell).floor() as usize).min(self.rows - 1);
+        r * self.cols + c
+    }
+
+    /// Bilinear interpolation of values stored at cell centres.
+    fn interpolate(&self, values: &[f64], x: f64, y: f64) -> f64 {
+        let fx = ((x - self.min_x) / self.cell - 0.5).clamp(0.0, (self.cols - 1) as f64);
+        let fy = ((y - self.min_y) / self.cell - 0.5).clamp(0.0, (self.rows - 1) as f64);
+        let (c0, r0) = (fx.floor() as usize, fy.floor() as usize);
+        let (c1, r1) = ((c0 + 1).min(self.cols - 1), (r0 + 1).min(self.rows - 1));
+        let (tx, ty) = (fx - c0 as f64, fy - r0 as f64);
+        let v = |c: usize, r: usize| values[r * self.cols + c];
+        let bottom = v(c0, r0) * (1.0 - tx) + v(c1, r0) * tx;
+        let top = v(c0, r1) * (1.0 - tx) + v(c1, r1) * tx;
+        bottom * (1.0 - ty) + top * ty
+    }
+
+    /// Fills non-finite cells with the mean of their filled neighbours,
+    /// growing outwards from the cells holding points.
+    fn fill_empty(&self, values: &mut [f64]) {
+        loop {
+            let mut changed = false;
+            let snapshot = values.to_vec();
+            for r in 0..self.rows {
+                for c in 0..self.cols {
+                    let i = r * self.cols + c;
+                    if snapshot[i].is_finite() {
+                        continue;
+                    }
+                    let (mut sum, mut n) = (0.0, 0);
+                    for (dc, dr) in [(-1i64, 0i64), (1, 0), (0, -1), (0, 1)] {
+                        let (nc, nr) = (c as i64 + dc, r as i64 + dr);
+                        if nc < 0 || nr < 0 || nc >= self.cols as i64 || nr >= self.rows as i64 {
+                            continue;
+                        }
+                        let v = snapshot[nr as usize * self.cols + nc as usize];
+                        if v.is_finite() {
+                            sum += v;
+                            n += 1;
+                        }
+                    }
+                    if n > 0 {
+                        values[i] = sum / n as f64;
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                return;
+            }
+        }
+    }
+
+    /// Minimum (`erode`) or maximum filter over a square window of
+    /// `2 * radius + 1` cells, applied separably. The window shrinks
+    /// symmetrically at the raster edges so sloping terrain is not cut down
+    /// there.
+    fn morph(&self, values: &[f64], radius: usize, erode: bool) -> Vec<f64> {
+        let pick = |a: f64, b: f64| if erode { a.min(b) } else { a.max(b) };
+        let mut rows_pass = vec![0.0; values.len()];
+        for r in 0..self.rows {
+            for c in 0..self.cols {
+                let w = radius.min(c).min(self.cols - 1 - c);
+                let (lo, hi) = (c - w, c + w);
+                rows_pass[r * self.cols + c] = (lo..=hi)
+                    .map(|k| values[r * self.cols + k])
+                    .reduce(pick)
+                    .unwrap();
+            }
+        }
+        let mut out = vec![0.0; values.len()];
+        for r in 0..self.rows {
+            let w = radius.min(r).min(self.rows - 1 - r);
+            let (lo, hi) = (r - w, r + w);
+            for c in 0..self.cols {
+                out[r * self.cols + c] = (lo..=hi)
+                    .map(|k| rows_pass[k * self.cols + c])
+                    .reduce(pick)
+                    .unwrap();
+            }
+        }
+        out
+    }
+}
+
+/// Progressive morphological filter. Returns `true` for ground points.
+pub fn pmf_ground(points: &[Point3], params: &PmfParams) -> Vec<bool> {
+    if points.is_empty() {
+        return Vec::new();
+    }
+    let raster = Raster::covering(points, params.cell_size);
+    let cells: Vec<usize> = points.iter().map(|p| raster.index(*p)).collect();
+    let mut surface = vec![f64::INFINITY; raster.cols * raster.rows];
+    for (p, &c) in points.iter().zip(&cells) {
+        surface[c] = surface[c].min(p.z);
+    }
+    raster.fill_empty(&mut surface);
+
+    let mut ground = vec![true; points.len()];
+    let mut previous = 1usize;
+    for k in 0.. {
+        let window = 2 * (1usize << k) + 1;
+        if k > 0 && window as f64 * raster.cell > params.max_window {
+            break;
+        }
+        let eroded = raster.morph(&surface, window / 2, true);
+        let opened = raster.morph(&eroded, window / 2, false);
+        let threshold = if k == 0 {
+            params.initial_distance
+        } else {
+            params.slope * (window - previous) as f64 * raster.cell + params.initial_distance
+        }
+        .min(params.max_distance);
+        for (i, p) in points.iter().enumerate() {
+            if ground[i] && p.z - opened[cells[i]] > threshold {
+                ground[i] = false;
+            }
+        }
+        surface = opened;
+        previous = window;
+    }
+    ground
+}
+
+/// Cloth simulation filter. Returns `true` for ground points.
+pub fn csf_ground(points: &[Point3], params: &CsfParams) -> Vec<bool> {
+    if points.is_empty() {
+        return Vec::new();
+    }
+    const GRAVITY: f64 = 0.2;
+    const DAMPING: f64 = 0.01;
+    let raster = Raster::covering(points, params.cloth_resolution);
+    let n = raster.cols * raster.rows;
+    // heights in the inverted cloud, where the terrain is the upper surface
+    let mut floor = vec![f64::NEG_INFINITY; n];
+    for p in points {
+        let c = raster.index(*p);
+        floor[c] = floor[c].max(-p.z);
+    }
+    raster.fill_empty(&mut floor);
+    let start = floor.iter().copied().fold(f64::NEG_INFINITY, f64::max) + 1.0;
+    let mut height = vec![start; n];
+    let mut previous = height.clone();
+    let mut movable = vec![true; n];
+    let step = GRAVITY * params.time_step * params.time_step;
+    let mut springs = Vec::new();
+    for r in 0..raster.rows {
+        for c in 0..raster.cols {
+            let i = r * raster.cols + c;
+            if c + 1 < raster.cols {
+                springs.push((i, i + 1));
+            }
+            if r + 1 < raster.rows {
+                springs.push((i, i + raster.cols));
+            }
+        }
+    }
+
+    for _ in 0..params.max_iterations {
+        for i in (0..n).filter(|&i| movable[i]) {
+            let current = height[i];
+            height[i] = current + (current - previous[i]) * (1.0 - DAMPING) - step;
+            previous[i] = current;
+        }
+        for _ in 0..params.rigidness.max(1) {
+            for &(a, b) in &springs {
+                let diff = height[b] - height[a];
+                match (movable[a], movable[b]) {
+                    (true, true) => {
+                        height[a] += diff * 0.25;
+                        height[b] -= diff * 0.25;
+                    }
+                    (true, false) => height[a] += diff * 0.5,
+                    (false, true) => height[b] -= diff * 0.5,
+                    (false, false) => {}
+                }
+            }
+        }
+        let mut largest = 0.0f64;
+        for i in 0..n {
+            if movable[i] && height[i] <= floor[i] {
+                height[i] = floor[i];
+                movable[i] = false;
+            }
+            if movable[i] {
+                largest = largest.max((height[i] - previous[i]).abs());
+            }
+        }
+        if largest < 1e-4 {
+            break;
+        }
+    }
+
+    points
+        .iter()
+        .map(|p| (-p.z - raster.interpolate(&height, p.x, p.y)).abs() <= params.class_threshold)
+        .collect()
+}
+
+/// Runs a ground filter over a cloud. Ground points get ASPRS class 2 and
+/// points previously classed as ground that fail the filter become
+/// unclassified. Noise points are ignored.
+pub fn classify_ground(cloud: &mut PointCloud, method: &GroundMethod) {
+    let noise = |c: u8| c == asprs::LOW_NOISE || c == asprs::HIGH_NOISE;
+    let candidates: Vec<usize> = (0..cloud.points.len())
+        .filter(|&i| !noise(cloud.points[i].classification))
+        .collect();
+    let positions: Vec<Point3> = candidates
+        .iter()
+        .map(|&i| cloud.points[i].position)
+        .collect();
+    let ground = match method {
+        GroundMethod::Pmf(p) => pmf_ground(&positions, p),
+        GroundMethod::Csf(p) => csf_ground(&positions, p),
+    };
+    for (i, is_ground) in candidates.into_iter().zip(ground) {
+        let point = &mut cloud.points[i];
+        if is_ground {
+            point.classification = asprs::GROUND;
+        } else if point.classification == asprs::GROUND {
+            point.classification = asprs::UNCLASSIFIED;
+        }
+    }
+}
+
+/// Positions of the points classed as ground.
+pub fn ground_points(cloud: &PointCloud) -> Vec<Point3> {
+    cloud
+        .points
+        .iter()
+        .filter(|p| p.classification == asprs::GROUND)
+        .map(|p| p.position)
+        .collect()
+}
+
+fn barycentric_z(p: Point3, a: Point3, b: Point3, c: Point3) -> Option<f64> {
+    let det = (b.y - c.y) * (a.x - c.x) + (c.x - b.x) * (a.y - c.y);
+    if det.abs() < f64::EPSILON {
+        return None;
+    }
+    let u = ((b.y - c.y) * (p.x - c.x) + (c.x - b.x) * (p.y - c.y)) / det;
+    let v = ((c.y - a.y) * (p.x - c.x) + (a.x - c.x) * (p.y - c.y)) / det;
+    let w = 1.0 - u - v;
+    (u >= -1e-9 && v >= -1e-9 && w >= -1e-9).then_some(u * a.z + v * b.z + w * c.z)
+}
+
+/// Selects a subset of `points` whose triangulation stays within
+/// `tolerance` vertically of every input point, ready for
+/// [`Tin::from_points`].
+///
+/// Starting from the convex hull, each round triangulates the selected
+/// points and adds the worst point of every triangle that is out of
+/// tolerance. Points sharing an XY position keep the lowest.
+pub fn decimate_ground(points: &[Point3], tolerance: f64) -> Vec<Point3> {
+    let mut unique: HashMap<(u64, u64), Point3> = HashMap::new();
+    for p in points {
+        unique
+            .entry((p.x.to_bits(), p.y.to_bits()))
+            .and_modify(|q| {
+                if p.z < q.z {
+                    *q = *p
+                }
+            })
+            .or_insert(*p);
+    }
+    let mut pts: Vec<Point3> = unique.into_values().collect();
+    pts.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+    if pts.len() <= 3 {
+        return pts;
+    }
+    let index: HashMap<(u64, u64), usize> = pts
+        .iter()
+        .enumerate()
+        .map(|(i, p)| ((p.x.to_bits(), p.y.to_bits()), i))
+        .collect();
+    let flat: Vec<Point> = pts.iter().map(|p| Point::new(p.x, p.y)).collect();
+    let mut selected = vec![false; pts.len()];
+    for h in convex_hull(&flat) {
+        selected[index[&(h.x.to_bits(), h.y.to_bits())]] = true;
+    }
+
+    loop {
+        let chosen: Vec<usize> = (0..pts.len()).filter(|&i| selected[i]).collect();
+        let tin = Tin::from_points(chosen.iter().map(|&i| pts[i]).collect());
+        let raster = Raster::covering(&tin.vertices, {
+            let extent = Raster::covering(&tin.vertices, 1.0);
+            ((extent.cols * extent.rows) as f64 / tin.triangles.len().max(1) as f64)
+                .sqrt()
+                .max(1e-6)
+        });
+        let mut buckets: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (t, tri) in tin.triangles.iter().enumerate() {
+            let corners = tri.map(|i| tin.vertices[i]);
+            let lo = raster.index(Point3::new(
+                corners.iter().map(|p| p.x).fold(f64::INFINITY, f64::min),
+                corners.iter().map(|p| p.y).fold(f64::INFINITY, f64::min),
+                0.0,
+            ));
+            let hi = raster.index(Point3::new(
+                corners
+                    .iter()
+                    .map(|p| p.x)
+                    .fold(f64::NEG_INFINITY, f64::max),
+                corners
+                    .iter()
+                    .map(|p| p.y)
+                    .fold(f64::NEG_INFINITY, f64::max),
+                0.0,
+            ));
+            for r in lo / raster.cols..=hi / raster.cols {
+                for c in lo % raster.cols..=hi % raster.cols {
+                    buckets.entry(r * raster.cols + c).or_default().push(t);
+                }
+            }
+        }
+        let mut worst: HashMap<usize, (usize, f64)> = HashMap::new();
+        for i in (0..pts.len()).filter(|&i| !selected[i]) {
+            let p = pts[i];
+            let found = buckets.get(&raster.index(p)).and_then(|ts| {
+                ts.iter().find_map(|&t| {
+                    let [a, b, c] = tin.triangles[t].map(|v| tin.vertices[v]);
+                    barycentric_z(p, a, b, c).map(|z| (t, (p.z - z).abs()))
+                })
+            });
+            match found {
+                Some((t, err)) if err > tolerance => {
+                    let entry = worst.entry(t).or_insert((i, err));
+                    if err > entry.1 {
+                        *entry = (i, err);
+                    }
+                }
+                Some(_) => {}
+                // outside the triangulation, e.g. collinear hull points
+                None => {
+                    worst.insert(usize::MAX - i, (i, f64::INFINITY));
+                }
+            }
+        }
+        if worst.is_empty() {
+            return chosen.into_iter().map(|i| pts[i]).collect();
+        }
+        for (i, _) in worst.into_values() {
+            selected[i] = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Terrain sloping at 25% with a 6 m square building and a few trees.
+    fn sloped_site() -> (Vec<Point3>, Vec<bool>) {
+        let mut pts = Vec::new();
+        let mut truth = Vec::new();
+        for i in 0..40 {
+            for j in 0..40 {
+                let (x, y) = (i as f64, j as f64);
+                let ground_z = 0.25 * x + 0.05 * y;
+                let building = (15.0..21.0).contains(&x) && (15.0..21.0).contains(&y);
+                let tree = (i % 13 == 5) && (j % 11 == 3);
+                if building {
+                    pts.push(Point3::new(x, y, ground_z + 6.0));
+                    truth.push(false);
+                } else if tree {
+                    pts.push(Point3::new(x, y, ground_z + 4.0));
+                    truth.push(false);
+                    pts.push(Point3::new(
+                        x + 0.3,
+                        y + 0.3,
+                        0.25 * (x + 0.3) + 0.05 * (y + 0.3),
+                    ));
+                    truth.push(true);
+                } else {
+                    pts.push(Point3::new(x, y, ground_z));
+                    truth.push(true);
+                }
+            }
+        }
+        (pts, truth)
+    }
+
+    fn errors(got: &[bool], truth: &[bool]) -> usize {
+        got.iter().zip(truth).filter(|(a, b)| a != b).count()
+    }
+
+    #[test]
+    fn pmf_separates_ground_on_slope() {
+        let (pts, truth) = sloped_site();
+        let ground = pmf_ground(&pts, &PmfParams::default());
+        assert_eq!(errors(&ground, &truth), 0);
+    }
+
+    #[test]
+    fn csf_separates_ground() {
+        let (pts, truth) = sloped_site();
+        let ground = csf_ground(
+            &pts,
+            &CsfParams {
+                cloth_resolution: 1.0,
+                rigidness: 3,
+                ..Default::default()
+            },
+        );
+        assert_eq!(errors(&ground, &truth), 0);
+    }
+
+    #[test]
+    fn classify_ground_and_decimate() {
+        let (pts, _) = sloped_site();
+        let mut cloud = PointCloud::from_positions(&pts);
+        cloud.points[0].classification = asprs::LOW_NOISE;
+        classify_ground(&mut cloud, &GroundMethod::Pmf(PmfParams::default()));
+        assert_eq!(cloud.points[0].classification, asprs::LOW_NOISE);
+        let ground = ground_points(&cloud);
+        assert!(ground.len() > 1500);
+
+        // a plane needs only its hull
+        let planar = decimate_ground(&ground, 0.01);
+        assert!(planar.len() < 20, "{}", planar.len());
+
+        let bumpy: Vec<Point3> = ground
+            .iter()
+            .map(|p| Point3::new(p.x, p.y, p.z + (p.x * 0.7).sin() * 0.5))
+            .collect();
+        let kept = decimate_ground(&bumpy, 0.1);
+        assert!(kept.len() < bumpy.len());
+        let tin = Tin::from_points(kept);
+        for p in &bumpy {
+            if let Some(z) = tin.elevation_at(p.x, p.y) {
+                assert!((z - p.z).abs() <= 0.1 + 1e-9);
+            }
+        }
+    }
+}
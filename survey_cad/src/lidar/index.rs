@@ -0,0 +1,116 @@
+//! Uniform XY grid index for radius and nearest-neighbour queries.
+
+use crate::geometry::Point3;
+use std::collections::HashMap;
+
+/// Buckets point indices by the XY grid cell they fall in.
+#[derive(Debug, Clone)]
+pub struct GridIndex {
+    cell: f64,
+    cells: HashMap<(i64, i64), Vec<usize>>,
+}
+
+impl GridIndex {
+    /// Indexes `points` with square cells of `cell` size. Non-positive sizes
+    /// fall back to one unit.
+    pub fn new(points: &[Point3], cell: f64) -> Self {
+        let cell = if cell > 0.0 { cell } else { 1.0 };
+        let mut cells: HashMap<(i64, i64), Vec<usize>> = HashMap::new();
+        for (i, p) in points.iter().enumerate() {
+            cells
+                .entry(Self::key_for(cell, p.x, p.y))
+                .or_default()
+                .push(i);
+        }
+        Self { cell, cells }
+    }
+
+    fn key_for(cell: f64, x: f64, y: f64) -> (i64, i64) {
+        ((x / cell).floor() as i64, (y / cell).floor() as i64)
+    }
+
+    /// Indices of points whose 3D distance to `center` is at most `radius`.
+    pub fn within<'a>(
+        &'a self,
+        points: &'a [Point3],
+        center: Point3,
+        radius: f64,
+    ) -> impl Iterator<Item = usize> + 'a {
+        let (cx, cy) = Self::key_for(self.cell, center.x, center.y);
+        let reach = (radius / self.cell).ceil().max(0.0) as i64;
+        (cx - reach..=cx + reach)
+            .flat_map(move |x| (cy - reach..=cy + reach).map(move |y| (x, y)))
+            .filter_map(|k| self.cells.get(&k))
+            .flatten()
+            .copied()
+            .filter(move |&i| {
+                let q = points[i];
+                let (dx, dy, dz) = (q.x - center.x, q.y - center.y, q.z - center.z);
+                dx * dx + dy * dy + dz * dz <= radius * radius
+            })
+    }
+
+    /// Index of the point closest to `center` in XY within `radius`.
+    pub fn nearest_xy(&self, points: &[Point3], center: Point3, radius: f64) -> Option<usize> {
+        let (cx, cy) = Self::key_for(self.cell, center.x, center.y);
+        let reach = (radius / self.cell).ceil().max(0.0) as i64;
+        let mut best: Option<(usize, f64)> = None;
+        for x in cx - reach..=cx + reach {
+            for y in cy - reach..=cy + reach {
+                for &i in self.cells.get(&(x, y)).into_iter().flatten() {
+                    let d = (points[i].x - center.x).hypot(points[i].y - center.y);
+                    if d <= radius && best.is_none_or(|(_, b)| d < b) {
+                        best = Some((i, d));
+                    }
+                }
+            }
+        }
+        best.map(|(i, _)| i)
+    }
+}
+
+/// Flags the points having at least `min_neighbors` other points within
+/// `radius`.
+pub fn noise_mask(points: &[Point3], radius: f64, min_neighbors: usize) -> Vec<bool> {
+    let index = GridIndex::new(points, radius);
+    points
+        .iter()
+        .enumerate()
+        .map(|(i, p)| {
+            index
+                .within(points, *p, radius)
+                .filter(|&j| j != i)
+                .take(min_neighbors)
+                .count()
+                >= min_neighbors
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn radius_and_nearest_queries() {
+        let pts = vec![
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(0.5, 0.0, 0.0),
+            Point3::new(3.0, 3.0, 0.0),
+            Point3::new(0.0, 0.0, 5.0),
+        ];
+        let index = GridIndex::new(&pts, 1.0);
+        let mut near: Vec<usize> = index.within(&pts, pts[0], 1.0).collect();
+        near.sort();
+        assert_eq!(near, vec![0, 1]);
+        assert_eq!(
+            index.nearest_xy(&pts, Point3::new(2.6, 2.9, 0.0), 1.0),
+            Some(2)
+        );
+        assert_eq!(
+            index.nearest_xy(&pts, Point3::new(9.0, 9.0, 0.0), 1.0),
+            None
+        );
+        assert_eq!(noise_mask(&pts, 1.0, 1), vec![true, true, false, false]);
+    }
+}
@@ -0,0 +1,414 @@
+use crate::crs::Crs;
+use crate::geometry::Point3;
+use std::collections::{BTreeMap, HashMap};
+
+pub mod ground;
+pub mod index;
+pub mod tiling;
+
+pub use ground::{
+    classify_ground, csf_ground, decimate_ground, ground_points, pmf_ground, CsfParams,
+    GroundMethod, PmfParams,
+};
+pub use index::{noise_mask, GridIndex};
+pub use tiling::{process_tiled, TileKey, TileOptions, TileSummary};
+
+/// Classification types for point cloud filtering.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Classification {
+    Ground,
+    Vegetation,
+    Building,
+    Noise,
+}
+
+/// ASPRS standard point classes used by LAS files.
+pub mod asprs {
+    pub const NEVER_CLASSIFIED: u8 = 0;
+    pub const UNCLASSIFIED: u8 = 1;
+    pub const GROUND: u8 = 2;
+    pub const LOW_VEGETATION: u8 = 3;
+    pub const MEDIUM_VEGETATION: u8 = 4;
+    pub const HIGH_VEGETATION: u8 = 5;
+    pub const BUILDING: u8 = 6;
+    pub const LOW_NOISE: u8 = 7;
+    pub const WATER: u8 = 9;
+    pub const RAIL: u8 = 10;
+    pub const ROAD_SURFACE: u8 = 11;
+    pub const WIRE_GUARD: u8 = 13;
+    pub const WIRE_CONDUCTOR: u8 = 14;
+    pub const TRANSMISSION_TOWER: u8 = 15;
+    pub const BRIDGE_DECK: u8 = 17;
+    pub const HIGH_NOISE: u8 = 18;
+}
+
+impl Classification {
+    /// ASPRS class code. Vegetation is reported as medium vegetation.
+    pub fn asprs_code(self) -> u8 {
+        match self {
+            Classification::Ground => asprs::GROUND,
+            Classification::Vegetation => asprs::MEDIUM_VEGETATION,
+            Classification::Building => asprs::BUILDING,
+            Classification::Noise => asprs::LOW_NOISE,
+        }
+    }
+
+    /// Maps an ASPRS class code onto the simple classes, if it has one.
+    pub fn from_asprs(code: u8) -> Option<Self> {
+        match code {
+            asprs::GROUND => Some(Classification::Ground),
+            asprs::LOW_VEGETATION..=asprs::HIGH_VEGETATION => Some(Classification::Vegetation),
+            asprs::BUILDING => Some(Classification::Building),
+            asprs::LOW_NOISE | asprs::HIGH_NOISE => Some(Classification::Noise),
+            _ => None,
+        }
+    }
+}
+
+/// Full waveform packet reference of LAS point formats 4, 5, 9 and 10.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct WavePacket {
+    pub descriptor_index: u8,
+    pub byte_offset: u64,
+    pub size_in_bytes: u32,
+    pub return_point_location: f32,
+    pub x_t: f32,
+    pub y_t: f32,
+    pub z_t: f32,
+}
+
+/// Point of a point cloud with its LAS attributes. Optional attributes are
+/// `None` when the source format does not carry them.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CloudPoint {
+    pub position: Point3,
+    pub intensity: u16,
+    pub return_number: u8,
+    pub number_of_returns: u8,
+    /// ASPRS class code, see [`asprs`].
+    pub classification: u8,
+    /// `true` when the scanner mirror was travelling left to right.
+    pub scan_direction: bool,
+    pub is_edge_of_flight_line: bool,
+    pub is_synthetic: bool,
+    pub is_key_point: bool,
+    pub is_withheld: bool,
+    pub is_overlap: bool,
+    pub scanner_channel: u8,
+    /// Scan angle in degrees.
+    pub scan_angle: f32,
+    pub user_data: u8,
+    pub point_source_id: u16,
+    pub gps_time: Option<f64>,
+    /// Red, green and blue.
+    pub color: Option<[u16; 3]>,
+    pub nir: Option<u16>,
+    pub waveform: Option<WavePacket>,
+    pub extra_bytes: Vec<u8>,
+}
+
+impl CloudPoint {
+    pub fn new(position: Point3) -> Self {
+        Self {
+            position,
+            return_number: 1,
+            number_of_returns: 1,
+            ..Default::default()
+        }
+    }
+}
+
+/// Scale and offset turning the stored integer X, Y and Z of a LAS file
+/// into coordinates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quantization {
+    pub scale: [f64; 3],
+    pub offset: [f64; 3],
+}
+
+/// Point cloud with per-point attributes, the LAS point data format it was
+/// read from (or should be written as) and its coordinate system.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PointCloud {
+    pub points: Vec<CloudPoint>,
+    /// LAS point data record format, 0 to 10.
+    pub point_format: u8,
+    pub crs: Option<Crs>,
+    /// Quantization of the source file, kept so that writing the cloud
+    /// again doesn't lose precision. `None` picks one from the CRS units.
+    pub quantization: Option<Quantization>,
+}
+
+impl PointCloud {
+    /// Creates a cloud of bare positions using point format 0.
+    pub fn from_positions(points: &[Point3]) -> Self {
+        Self {
+            points: points.iter().copied().map(CloudPoint::new).collect(),
+            point_format: 0,
+            crs: None,
+            quantization: None,
+        }
+    }
+
+    pub fn positions(&self) -> Vec<Point3> {
+        self.points.iter().map(|p| p.position).collect()
+    }
+
+    /// Number of points in each ASPRS class.
+    pub fn class_counts(&self) -> BTreeMap<u8, usize> {
+        let mut counts = BTreeMap::new();
+        for p in &self.points {
+            *counts.entry(p.classification).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    /// Smallest point format able to hold every attribute present in the
+    /// cloud.
+    pub fn minimal_point_format(&self) -> u8 {
+        let any = |f: fn(&CloudPoint) -> bool| self.points.iter().any(f);
+        let gps = any(|p| p.gps_time.is_some());
+        let color = any(|p| p.color.is_some());
+        let nir = any(|p| p.nir.is_some());
+        let wave = any(|p| p.waveform.is_some());
+        let extended = any(|p| {
+            p.is_overlap || p.scanner_channel > 0 || p.return_number > 7 || p.classification > 31
+        });
+        match (extended || nir, wave, color || nir) {
+            (true, true, true) => 10,
+            (true, true, false) => 9,
+            (true, false, true) => 8 - u8::from(!nir),
+            (true, false, false) => 6,
+            (false, true, true) => 5,
+            (false, true, false) => 4,
+            (false, false, true) => 2 + u8::from(gps),
+            (false, false, false) => u8::from(gps),
+        }
+    }
+}
+
+/// Simple statistical outlier removal based on neighbor counts.
+///
+/// Points with fewer than `min_neighbors` neighbours inside `radius`
+/// are considered noise and removed. Neighbours are found through a
+/// [`GridIndex`].
+pub fn filter_noise(points: &[Point3], radius: f64, min_neighbors: usize) -> Vec<Point3> {
+    points
+        .iter()
+        .zip(noise_mask(points, radius, min_neighbors))
+        .filter(|(_, keep)| *keep)
+        .map(|(p, _)| *p)
+        .collect()
+}
+
+/// Classify points into ground, vegetation and buildings using a simple
+/// grid-based minimum elevation approach.
+///
+/// * `cell_size` - size of the XY grid cell used to determine ground elevation
+/// * `ground_threshold` - maximum height difference from the cell minimum to be
+///   considered ground
+/// * `veg_threshold` - height difference above ground classified as vegetation;
+///   anything higher is considered building.
+pub fn classify_points(
+    points: &[Point3],
+    cell_size: f64,
+    ground_threshold: f64,
+    veg_threshold: f64,
+) -> Vec<Classification> {
+    let mut grid_min: HashMap<(i64, i64), f64> = HashMap::new();
+    for p in points {
+        let key = (
+            (p.x / cell_size).floor() as i64,
+            (p.y / cell_size).floor() as i64,
+        );
+        grid_min
+            .entry(key)
+            .and_modify(|z| {
+                if p.z < *z {
+                    *z = p.z
+                }
+            })
+            .or_insert(p.z);
+    }
+    let mut classes = Vec::with_capacity(points.len());
+    for p in points {
+        let key = (
+            (p.x / cell_size).floor() as i64,
+            (p.y / cell_size).floor() as i64,
+        );
+        let base_z = *grid_min.get(&key).unwrap_or(&p.z);
+        let dz = p.z - base_z;
+        if dz.abs() <= ground_threshold {
+            classes.push(Classification::Ground);
+        } else if dz <= veg_threshold {
+            classes.push(Classification::Vegetation);
+        } else {
+            classes.push(Classification::Building);
+        }
+    }
+    classes
+}
+
+/// Extract linear breaklines from a point cloud based on slope between
+/// nearby points.
+///
+/// Points within `radius` are connected when the vertical slope between
+/// them exceeds `slope_threshold` (rise over run). The result is a list of
+/// index pairs into the input array representing detected breaklines.
+pub fn extract_breaklines(
+    points: &[Point3],
+    radius: f64,
+    slope_threshold: f64,
+) -> Vec<(usize, usize)> {
+    let mut lines = Vec::new();
+    for (i, &a) in points.iter().enumerate() {
+        for (j, &b) in points.iter().enumerate().skip(i + 1) {
+            let dx = a.x - b.x;
+            let dy = a.y - b.y;
+            let horiz = (dx * dx + dy * dy).sqrt();
+            let dz = (a.z - b.z).abs();
+            if horiz <= radius {
+                if horiz <= f64::EPSILON {
+                    if dz > 0.0 {
+                        lines.push((i, j));
+                    }
+                } else if dz / horiz >= slope_threshold {
+                    lines.push((i, j));
+                }
+            }
+        }
+    }
+    lines
+}
+
+/// Classifies a point cloud with [`classify_points`] and stores the result
+/// as ASPRS codes. Points already flagged as noise keep their class.
+pub fn classify_cloud(
+    cloud: &mut PointCloud,
+    cell_size: f64,
+    ground_threshold: f64,
+    veg_threshold: f64,
+) {
+    let noise = |c: u8| c == asprs::LOW_NOISE || c == asprs::HIGH_NOISE;
+    let positions: Vec<Point3> = cloud
+        .points
+        .iter()
+        .filter(|p| !noise(p.classification))
+        .map(|p| p.position)
+        .collect();
+    let mut classes =
+        classify_points(&positions, cell_size, ground_threshold, veg_threshold).into_iter();
+    for p in cloud.points.iter_mut().filter(|p| !noise(p.classification)) {
+        if let Some(c) = classes.next() {
+            p.classification = c.asprs_code();
+        }
+    }
+}
+
+#[cfg(feature = "render")]
+use bevy::asset::RenderAssetUsages;
+#[cfg(feature = "render")]
+use bevy::prelude::Mesh;
+#[cfg(feature = "render")]
+use bevy::render::mesh::{Indices, PrimitiveTopology};
+
+/// Builds a textured mesh from a point cloud using Delaunay triangulation.
+///
+/// The mesh is suitable for basic visualization of the point cloud surface.
+#[cfg(feature = "render")]
+pub fn point_cloud_to_mesh(points: &[Point3]) -> Mesh {
+    use crate::dtm::Tin;
+    let tin = Tin::from_points(points.to_vec());
+    let mut mesh = Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    );
+    let positions: Vec<[f32; 3]> = tin
+        .vertices
+        .iter()
+        .map(|p| [p.x as f32, p.y as f32, p.z as f32])
+        .collect();
+    let min_x = positions.iter().fold(f32::INFINITY, |m, v| m.min(v[0]));
+    let max_x = positions.iter().fold(f32::NEG_INFINITY, |m, v| m.max(v[0]));
+    let min_y = positions.iter().fold(f32::INFINITY, |m, v| m.min(v[1]));
+    let max_y = positions.iter().fold(f32::NEG_INFINITY, |m, v| m.max(v[1]));
+    let width = (max_x - min_x).max(f32::EPSILON);
+    let height = (max_y - min_y).max(f32::EPSILON);
+    let uvs: Vec<[f32; 2]> = positions
+        .iter()
+        .map(|p| [(p[0] - min_x) / width, (p[1] - min_y) / height])
+        .collect();
+    let normals = vec![[0.0, 0.0, 1.0]; positions.len()];
+    let indices: Vec<u32> = tin
+        .triangles
+        .iter()
+        .flat_map(|t| [t[0] as u32, t[1] as u32, t[2] as u32])
+        .collect();
+    mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+    mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+    mesh.insert_indices(Indices::U32(indices));
+    mesh
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn breakline_detection_basic() {
+        let pts = vec![
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(1.0, 0.0, 0.0),
+            Point3::new(0.0, 0.0, 1.0),
+            Point3::new(1.0, 0.0, 1.0),
+        ];
+        let lines = extract_breaklines(&pts, 1.1, 0.5);
+        assert!(lines.contains(&(0, 2)) || lines.contains(&(2, 0)));
+        assert!(lines.contains(&(1, 3)) || lines.contains(&(3, 1)));
+    }
+
+    #[test]
+    fn classify_cloud_writes_asprs_codes() {
+        let mut cloud = PointCloud::from_positions(&[
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(0.5, 0.5, 1.0),
+            Point3::new(0.2, 0.2, 10.0),
+            Point3::new(0.3, 0.3, 50.0),
+        ]);
+        cloud.points[3].classification = asprs::HIGH_NOISE;
+        classify_cloud(&mut cloud, 1.0, 0.3, 2.0);
+        let codes: Vec<u8> = cloud.points.iter().map(|p| p.classification).collect();
+        assert_eq!(
+            codes,
+            vec![
+                asprs::GROUND,
+                asprs::MEDIUM_VEGETATION,
+                asprs::BUILDING,
+                asprs::HIGH_NOISE
+            ]
+        );
+        assert_eq!(
+            Classification::from_asprs(asprs::HIGH_VEGETATION),
+            Some(Classification::Vegetation)
+        );
+        assert_eq!(cloud.class_counts()[&asprs::GROUND], 1);
+    }
+
+    #[test]
+    fn minimal_point_format_covers_attributes() {
+        let mut cloud = PointCloud::from_positions(&[Point3::new(0.0, 0.0, 0.0)]);
+        assert_eq!(cloud.minimal_point_format(), 0);
+        cloud.points[0].gps_time = Some(1.0);
+        assert_eq!(cloud.minimal_point_format(), 1);
+        cloud.points[0].color = Some([1, 2, 3]);
+        assert_eq!(cloud.minimal_point_format(), 3);
+        cloud.points[0].nir = Some(4);
+        assert_eq!(cloud.minimal_point_format(), 8);
+        cloud.points[0].waveform = Some(WavePacket::default());
+        assert_eq!(cloud.minimal_point_format(), 10);
+        cloud.points[0].nir = None;
+        cloud.points[0].color = None;
+        assert_eq!(cloud.minimal_point_format(), 4);
+    }
+}
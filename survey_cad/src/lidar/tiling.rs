@@ -0,0 +1,689 @@
+//! Out-of-core point cloud processing in square XY tiles.
+//!
+//! Points are streamed from any source and spilled to one temporary file
+//! per tile. Each tile is then loaded together with a buffer of points from
+//! its neighbours, filtered for noise, classified and thinned on a pool of
+//! worker threads and handed to a sink. Only the spill buffers and the tiles
+//! being worked on are held in memory.
+
+use super::{
+    asprs, classify_ground, classify_points, noise_mask, CloudPoint, GroundMethod, PointCloud,
+    WavePacket,
+};
+use crate::geometry::{Point, Point3};
+use std::collections::{BTreeSet, HashMap};
+use std::fs::OpenOptions;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// Column and row of a tile. Tile `(0, 0)` spans `[0, size)` in X and Y.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct TileKey {
+    pub x: i64,
+    pub y: i64,
+}
+
+impl TileKey {
+    /// Tile containing a position.
+    pub fn of(p: Point3, size: f64) -> Self {
+        Self {
+            x: (p.x / size).floor() as i64,
+            y: (p.y / size).floor() as i64,
+        }
+    }
+
+    /// Lower-left and upper-right corners of the tile.
+    pub fn bounds(&self, size: f64) -> (Point, Point) {
+        (
+            Point::new(self.x as f64 * size, self.y as f64 * size),
+            Point::new((self.x + 1) as f64 * size, (self.y + 1) as f64 * size),
+        )
+    }
+}
+
+/// Parameters of the statistical noise filter, see
+/// [`filter_noise`](super::filter_noise).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NoiseFilter {
+    pub radius: f64,
+    pub min_neighbors: usize,
+}
+
+/// Ground classification run on each tile.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GroundFilter {
+    /// Cell minimum classification, see
+    /// [`classify_points`](super::classify_points).
+    Grid {
+        cell_size: f64,
+        ground_threshold: f64,
+        veg_threshold: f64,
+    },
+    /// Morphological or cloth filter, see
+    /// [`classify_ground`](super::classify_ground).
+    Method(GroundMethod),
+}
+
+/// Options of [`process_tiled`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct TileOptions {
+    pub tile_size: f64,
+    /// Width of the band of neighbouring points loaded around each tile so
+    /// filters see across tile edges. Should be at least the noise radius.
+    pub buffer: f64,
+    /// Points held in spill buffers before they are written to disk.
+    pub max_buffered_points: usize,
+    /// Worker threads, or 0 for one per available CPU.
+    pub threads: usize,
+    pub noise: Option<NoiseFilter>,
+    /// Keep noise points classified as low noise instead of dropping them.
+    pub keep_noise: bool,
+    pub ground: Option<GroundFilter>,
+    /// Voxel size for thinning to the point nearest each voxel centre.
+    pub thin: Option<f64>,
+}
+
+impl Default for TileOptions {
+    fn default() -> Self {
+        Self {
+            tile_size: 100.0,
+            buffer: 2.0,
+            max_buffered_points: 1_000_000,
+            threads: 0,
+            noise: None,
+            keep_noise: false,
+            ground: None,
+            thin: None,
+        }
+    }
+}
+
+/// Point counts of one processed tile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TileSummary {
+    pub key: TileKey,
+    pub input_points: usize,
+    pub noise_points: usize,
+    pub thinned_points: usize,
+    pub output_points: usize,
+}
+
+const FLAG_HALO: u8 = 1 << 6;
+const HAS_GPS: u8 = 1;
+const HAS_COLOR: u8 = 1 << 1;
+const HAS_NIR: u8 = 1 << 2;
+const HAS_WAVEFORM: u8 = 1 << 3;
+
+fn encode(p: &CloudPoint, halo: bool, out: &mut Vec<u8>) {
+    let flags = [
+        p.scan_direction,
+        p.is_edge_of_flight_line,
+        p.is_synthetic,
+        p.is_key_point,
+        p.is_withheld,
+        p.is_overlap,
+        halo,
+    ]
+    .iter()
+    .enumerate()
+    .fold(0u8, |f, (i, set)| f | (u8::from(*set) << i));
+    let present = [
+        (p.gps_time.is_some(), HAS_GPS),
+        (p.color.is_some(), HAS_COLOR),
+        (p.nir.is_some(), HAS_NIR),
+        (p.waveform.is_some(), HAS_WAVEFORM),
+    ]
+    .iter()
+    .filter(|(set, _)| *set)
+    .fold(0u8, |f, (_, bit)| f | bit);
+    for v in [p.position.x, p.position.y, p.position.z] {
+        out.extend_from_slice(&v.to_le_bytes());
+    }
+    out.extend_from_slice(&p.intensity.to_le_bytes());
+    out.extend_from_slice(&[
+        p.return_number,
+        p.number_of_returns,
+        p.classification,
+        flags,
+        p.scanner_channel,
+        p.user_data,
+        present,
+    ]);
+    out.extend_from_slice(&p.scan_angle.to_le_bytes());
+    out.extend_from_slice(&p.point_source_id.to_le_bytes());
+    if let Some(t) = p.gps_time {
+        out.extend_from_slice(&t.to_le_bytes());
+    }
+    for c in p.color.iter().flatten().chain(&p.nir) {
+        out.extend_from_slice(&c.to_le_bytes());
+    }
+    if let Some(w) = p.waveform {
+        out.push(w.descriptor_index);
+        out.extend_from_slice(&w.byte_offset.to_le_bytes());
+        out.extend_from_slice(&w.size_in_bytes.to_le_bytes());
+        for v in [w.return_point_location, w.x_t, w.y_t, w.z_t] {
+            out.extend_from_slice(&v.to_le_bytes());
+        }
+    }
+    out.extend_from_slice(&(p.extra_bytes.len() as u16).to_le_bytes());
+    out.extend_from_slice(&p.extra_bytes);
+}
+
+struct Decoder<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl Decoder<'_> {
+    fn bytes<const N: usize>(&mut self) -> io::Result<[u8; N]> {
+        let b = self
+            .data
+            .get(self.pos..self.pos + N)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "truncated tile"))?;
+        self.pos += N;
+        Ok(b.try_into().unwrap())
+    }
+
+    fn u8(&mut self) -> io::Result<u8> {
+        Ok(self.bytes::<1>()?[0])
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        Ok(u16::from_le_bytes(self.bytes()?))
+    }
+
+    fn f32(&mut self) -> io::Result<f32> {
+        Ok(f32::from_le_bytes(self.bytes()?))
+    }
+
+    fn f64(&mut self) -> io::Result<f64> {
+        Ok(f64::from_le_bytes(self.bytes()?))
+    }
+
+    fn point(&mut self) -> io::Result<(CloudPoint, bool)> {
+        let position = Point3::new(self.f64()?, self.f64()?, self.f64()?);
+        let intensity = self.u16()?;
+        let [return_number, number_of_returns, classification, flags, scanner_channel, user_data, present] =
+            self.bytes::<7>()?;
+        let flag = |i: u8| flags & (1 << i) != 0;
+        let scan_angle = self.f32()?;
+        let point_source_id = self.u16()?;
+        let gps_time = match present & HAS_GPS {
+            0 => None,
+            _ => Some(self.f64()?),
+        };
+        let color = match present & HAS_COLOR {
+            0 => None,
+            _ => Some([self.u16()?, self.u16()?, self.u16()?]),
+        };
+        let nir = match present & HAS_NIR {
+            0 => None,
+            _ => Some(self.u16()?),
+        };
+        let waveform = match present & HAS_WAVEFORM {
+            0 => None,
+            _ => Some(WavePacket {
+                descriptor_index: self.u8()?,
+                byte_offset: u64::from_le_bytes(self.bytes()?),
+                size_in_bytes: u32::from_le_bytes(self.bytes()?),
+                return_point_location: self.f32()?,
+                x_t: self.f32()?,
+                y_t: self.f32()?,
+                z_t: self.f32()?,
+            }),
+        };
+        let extra = self.u16()? as usize;
+        let extra_bytes = self
+            .data
+            .get(self.pos..self.pos + extra)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "truncated tile"))?
+            .to_vec();
+        self.pos += extra;
+        let point = CloudPoint {
+            position,
+            intensity,
+            return_number,
+            number_of_returns,
+            classification,
+            scan_direction: flag(0),
+            is_edge_of_flight_line: flag(1),
+            is_synthetic: flag(2),
+            is_key_point: flag(3),
+            is_withheld: flag(4),
+            is_overlap: flag(5),
+            scanner_channel,
+            scan_angle,
+            user_data,
+            point_source_id,
+            gps_time,
+            color,
+            nir,
+            waveform,
+            extra_bytes,
+        };
+        Ok((point, flags & FLAG_HALO != 0))
+    }
+}
+
+/// Per-tile byte buffers spilled to files in a temporary directory.
+struct Spill {
+    dir: PathBuf,
+    buffers: HashMap<TileKey, Vec<u8>>,
+    buffered: usize,
+    tiles: BTreeSet<TileKey>,
+}
+
+impl Spill {
+    fn path(dir: &Path, key: TileKey) -> PathBuf {
+        dir.join(format!("{}_{}.bin", key.x, key.y))
+    }
+
+    fn push(&mut self, key: TileKey, p: &CloudPoint, halo: bool) {
+        encode(p, halo, self.buffers.entry(key).or_default());
+        self.buffered += 1;
+        if !halo {
+            self.tiles.insert(key);
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        for (key, data) in self.buffers.drain() {
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(Self::path(&self.dir, key))?
+                .write_all(&data)?;
+        }
+        self.buffered = 0;
+        Ok(())
+    }
+}
+
+/// Neighbouring tiles whose buffer band contains `p`.
+fn halo_tiles(p: Point3, key: TileKey, size: f64, buffer: f64) -> Vec<TileKey> {
+    let mut out = Vec::new();
+    if buffer <= 0.0 {
+        return out;
+    }
+    // a buffer wider than a tile reaches past the adjacent ring
+    let rings = (buffer / size).ceil() as i64;
+    for dx in -rings..=rings {
+        for dy in -rings..=rings {
+            if dx == 0 && dy == 0 {
+                continue;
+            }
+            let n = TileKey {
+                x: key.x + dx,
+                y: key.y + dy,
+            };
+            let (min, max) = n.bounds(size);
+            let ex = (min.x - p.x).max(p.x - max.x).max(0.0);
+            let ey = (min.y - p.y).max(p.y - max.y).max(0.0);
+            if ex.hypot(ey) <= buffer {
+                out.push(n);
+            }
+        }
+    }
+    out
+}
+
+/// Loads, filters, classifies and thins one tile, returning its summary and
+/// the surviving points of the tile itself.
+fn process_tile(
+    dir: &Path,
+    key: TileKey,
+    options: &TileOptions,
+) -> io::Result<(TileSummary, Vec<CloudPoint>)> {
+    let data = std::fs::read(Spill::path(dir, key))?;
+    let mut decoder = Decoder {
+        data: &data,
+        pos: 0,
+    };
+    let mut points = Vec::new();
+    let mut halo = Vec::new();
+    while decoder.pos < data.len() {
+        let (p, h) = decoder.point()?;
+        points.push(p);
+        halo.push(h);
+    }
+    drop(data);
+    let positions: Vec<Point3> = points.iter().map(|p| p.position).collect();
+    let input_points = halo.iter().filter(|h| !**h).count();
+
+    let noise: Vec<bool> = match options.noise {
+        Some(f) => noise_mask(&positions, f.radius, f.min_neighbors)
+            .into_iter()
+            .map(|keep| !keep)
+            .collect(),
+        None => vec![false; points.len()],
+    };
+    let noise_points = noise.iter().zip(&halo).filter(|(n, h)| **n && !**h).count();
+    match options.ground {
+        Some(GroundFilter::Grid {
+            cell_size,
+            ground_threshold,
+            veg_threshold,
+        }) => {
+            let clean: Vec<usize> = (0..points.len()).filter(|&i| !noise[i]).collect();
+            let clean_positions: Vec<Point3> = clean.iter().map(|&i| positions[i]).collect();
+            let classes =
+                classify_points(&clean_positions, cell_size, ground_threshold, veg_threshold);
+            for (i, c) in clean.into_iter().zip(classes) {
+                points[i].classification = c.asprs_code();
+            }
+        }
+        Some(GroundFilter::Method(method)) => {
+            for (p, n) in points.iter_mut().zip(&noise) {
+                if *n {
+                    p.classification = asprs::LOW_NOISE;
+                }
+            }
+            let mut cloud = PointCloud {
+                points,
+                ..Default::default()
+            };
+            classify_ground(&mut cloud, &method);
+            points = cloud.points;
+        }
+        None => {}
+    }
+
+    let mut keep: Vec<bool> = (0..points.len())
+        .map(|i| !halo[i] && (!noise[i] || options.keep_noise))
+        .collect();
+    let mut thinned_points = 0;
+    if let Some(v) = options.thin.filter(|v| *v > 0.0) {
+        let mut voxels: HashMap<(i64, i64, i64), (usize, f64)> = HashMap::new();
+        let candidates: Vec<usize> = (0..points.len())
+            .filter(|&i| keep[i] && !noise[i])
+            .collect();
+        for i in candidates {
+            let p = positions[i];
+            let cell = [(p.x / v).floor(), (p.y / v).floor(), (p.z / v).floor()];
+            let centre = Point3::new(
+                (cell[0] + 0.5) * v,
+                (cell[1] + 0.5) * v,
+                (cell[2] + 0.5) * v,
+            );
+            let d = (p.x - centre.x).powi(2) + (p.y - centre.y).powi(2) + (p.z - centre.z).powi(2);
+            let k = (cell[0] as i64, cell[1] as i64, cell[2] as i64);
+            match voxels.get_mut(&k) {
+                Some(best) if d < best.1 => {
+                    keep[best.0] = false;
+                    *best = (i, d);
+                    thinned_points += 1;
+                }
+                Some(_) => {
+                    keep[i] = false;
+                    thinned_points += 1;
+                }
+                None => {
+                    voxels.insert(k, (i, d));
+                }
+            }
+        }
+    }
+
+    let mut out = Vec::new();
+    for (i, mut p) in points.into_iter().enumerate() {
+        if keep[i] {
+            if noise[i] {
+                p.classification = asprs::LOW_NOISE;
+            }
+            out.push(p);
+        }
+    }
+    let summary = TileSummary {
+        key,
+        input_points,
+        noise_points,
+        thinned_points,
+        output_points: out.len(),
+    };
+    Ok((summary, out))
+}
+
+/// Streams `points` into tiles and processes the tiles in parallel.
+///
+/// Each tile's surviving points are passed to `sink`, possibly from several
+/// threads at once. Noise points are dropped unless
+/// [`TileOptions::keep_noise`] is set; ground classification writes ASPRS
+/// codes. Summaries are returned in tile order.
+pub fn process_tiled<I, F>(
+    points: I,
+    options: &TileOptions,
+    sink: F,
+) -> io::Result<Vec<TileSummary>>
+where
+    I: IntoIterator<Item = io::Result<CloudPoint>>,
+    F: Fn(TileKey, Vec<CloudPoint>) -> io::Result<()> + Sync,
+{
+    let size = options.tile_size;
+    if size.is_nan() || size <= 0.0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "tile size must be positive",
+        ));
+    }
+    let dir = tempfile::tempdir()?;
+    let mut spill = Spill {
+        dir: dir.path().to_path_buf(),
+        buffers: HashMap::new(),
+        buffered: 0,
+        tiles: BTreeSet::new(),
+    };
+    for p in points {
+        let p = p?;
+        let key = TileKey::of(p.position, size);
+        spill.push(key, &p, false);
+        for n in halo_tiles(p.position, key, size, options.buffer) {
+            spill.push(n, &p, true);
+        }
+        if spill.buffered >= options.max_buffered_points.max(1) {
+            spill.flush()?;
+        }
+    }
+    spill.flush()?;
+
+    let tiles: Vec<TileKey> = spill.tiles.into_iter().collect();
+    let threads = match options.threads {
+        0 => std::thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    }
+    .min(tiles.len())
+    .max(1);
+    let next = AtomicUsize::new(0);
+    let failed = AtomicBool::new(false);
+    let mut results: Vec<(usize, io::Result<TileSummary>)> = std::thread::scope(|s| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                s.spawn(|| {
+                    let mut done = Vec::new();
+                    while !failed.load(Ordering::Relaxed) {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(&key) = tiles.get(i) else {
+                            break;
+                        };
+                        let result = process_tile(dir.path(), key, options)
+                            .and_then(|(summary, pts)| sink(key, pts).map(|_| summary));
+                        if result.is_err() {
+                            failed.store(true, Ordering::Relaxed);
+                        }
+                        done.push((i, result));
+                    }
+                    done
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|w| w.join().expect("tile worker panicked"))
+            .collect()
+    });
+    results.sort_by_key(|(i, _)| *i);
+    results.into_iter().map(|(_, r)| r).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lidar::{filter_noise, PmfParams};
+    use std::sync::Mutex;
+
+    fn grid(spacing: f64, n: usize) -> Vec<CloudPoint> {
+        let mut pts = Vec::new();
+        for i in 0..n {
+            for j in 0..n {
+                let mut p =
+                    CloudPoint::new(Point3::new(i as f64 * spacing, j as f64 * spacing, 0.0));
+                p.intensity = (i * n + j) as u16;
+                p.gps_time = Some(i as f64);
+                p.extra_bytes = vec![i as u8, j as u8];
+                pts.push(p);
+            }
+        }
+        pts
+    }
+
+    fn run(points: &[CloudPoint], options: &TileOptions) -> (Vec<TileSummary>, Vec<CloudPoint>) {
+        let out = Mutex::new(Vec::new());
+        let summaries = process_tiled(points.iter().cloned().map(Ok), options, |_, pts| {
+            out.lock().unwrap().extend(pts);
+            Ok(())
+        })
+        .unwrap();
+        let mut out = out.into_inner().unwrap();
+        out.sort_by_key(|p| p.intensity);
+        (summaries, out)
+    }
+
+    #[test]
+    fn tiles_preserve_points_and_attributes() {
+        let points = grid(1.0, 20);
+        let options = TileOptions {
+            tile_size: 7.0,
+            max_buffered_points: 50,
+            threads: 3,
+            ..Default::default()
+        };
+        let (summaries, out) = run(&points, &options);
+        assert_eq!(summaries.len(), 9);
+        assert!(summaries.windows(2).all(|w| w[0].key < w[1].key));
+        assert_eq!(
+            summaries.iter().map(|s| s.output_points).sum::<usize>(),
+            400
+        );
+        assert_eq!(out, points);
+    }
+
+    #[test]
+    fn noise_filter_matches_in_memory_filter_across_tiles() {
+        let mut points = grid(1.0, 12);
+        let mut outlier = CloudPoint::new(Point3::new(5.5, 5.5, 30.0));
+        outlier.intensity = 1000;
+        points.push(outlier);
+        let options = TileOptions {
+            tile_size: 4.0,
+            buffer: 1.5,
+            threads: 2,
+            noise: Some(NoiseFilter {
+                radius: 1.2,
+                min_neighbors: 3,
+            }),
+            ground: Some(GroundFilter::Grid {
+                cell_size: 2.0,
+                ground_threshold: 0.3,
+                veg_threshold: 2.0,
+            }),
+            ..Default::default()
+        };
+        let (summaries, out) = run(&points, &options);
+        let positions: Vec<Point3> = points.iter().map(|p| p.position).collect();
+        let expected = filter_noise(&positions, 1.2, 3);
+        let mut got: Vec<Point3> = out.iter().map(|p| p.position).collect();
+        got.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+        let mut expected = expected;
+        expected.sort_by(|a, b| a.x.total_cmp(&b.x).then(a.y.total_cmp(&b.y)));
+        assert_eq!(got, expected);
+        // the corners have only two neighbours
+        assert_eq!(summaries.iter().map(|s| s.noise_points).sum::<usize>(), 5);
+        assert!(out.iter().all(|p| p.classification == asprs::GROUND));
+
+        let keep = TileOptions {
+            keep_noise: true,
+            ..options
+        };
+        let (_, out) = run(&points, &keep);
+        assert_eq!(out.len(), points.len());
+        assert_eq!(out.last().unwrap().classification, asprs::LOW_NOISE);
+    }
+
+    #[test]
+    fn wide_buffers_reach_past_adjacent_tiles() {
+        let key = TileKey { x: 0, y: 0 };
+        let p = Point3::new(0.5, 0.5, 0.0);
+        let near = halo_tiles(p, key, 1.0, 0.4);
+        assert!(near.is_empty());
+        let wide = halo_tiles(p, key, 1.0, 1.6);
+        assert!(wide.contains(&TileKey { x: -2, y: 0 }));
+        assert!(wide.contains(&TileKey { x: 2, y: 1 }));
+        assert!(!wide.contains(&TileKey { x: 2, y: 2 }));
+        assert_eq!(wide.len(), 20);
+    }
+
+    #[test]
+    fn tiles_run_the_morphological_filter() {
+        let mut points = grid(1.0, 24);
+        let roof = |p: &CloudPoint| {
+            (10.0..14.0).contains(&p.position.x) && (10.0..14.0).contains(&p.position.y)
+        };
+        for p in points.iter_mut().filter(|p| roof(p)) {
+            p.position.z = 5.0;
+        }
+        let options = TileOptions {
+            tile_size: 12.0,
+            buffer: 6.0,
+            ground: Some(GroundFilter::Method(GroundMethod::Pmf(PmfParams {
+                max_window: 8.0,
+                ..Default::default()
+            }))),
+            ..Default::default()
+        };
+        let (_, out) = run(&points, &options);
+        assert_eq!(out.len(), points.len());
+        for p in &out {
+            let expected = if roof(p) {
+                asprs::NEVER_CLASSIFIED
+            } else {
+                asprs::GROUND
+            };
+            assert_eq!(p.classification, expected, "{:?}", p.position);
+        }
+    }
+
+    #[test]
+    fn thinning_keeps_one_point_per_voxel() {
+        let points = grid(0.25, 16);
+        let options = TileOptions {
+            tile_size: 2.0,
+            thin: Some(1.0),
+            ..Default::default()
+        };
+        let (summaries, out) = run(&points, &options);
+        assert_eq!(out.len(), 16);
+        assert_eq!(
+            summaries.iter().map(|s| s.thinned_points).sum::<usize>(),
+            240
+        );
+        assert!(process_tiled(
+            Vec::<io::Result<CloudPoint>>::new(),
+            &TileOptions {
+                tile_size: 0.0,
+                ..Default::default()
+            },
+            |_, _| Ok(())
+        )
+        .is_err());
+    }
+}
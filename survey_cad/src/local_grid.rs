@@ -1,6 +1,8 @@
-//! Local grid definition with origin, rotation and scale.
+//! Local grid definition with origin, rotation and scale, and project
+//! ground coordinate systems derived from a combined scale factor.
 
-use crate::geometry::Point;
+use crate::crs::{CoordinateTransform, Crs};
+use crate::geometry::{Point, Point3};
 
 /// Simple local grid definition.
 ///
@@ -64,6 +66,139 @@ impl LocalGrid {
     }
 }
 
+impl CoordinateTransform for LocalGrid {
+    fn transform(&self, x: f64, y: f64, z: f64) -> Option<(f64, f64, f64)> {
+        let p = self.to_local(Point::new(x, y));
+        Some((p.x, p.y, z))
+    }
+}
+
+/// Ground coordinate system scaled from a projected grid about a base point.
+///
+/// Ground coordinates are obtained by dividing grid offsets from the base
+/// point by the combined scale factor (CSF), so that ground distances match
+/// those measured in the field. The base point keeps its grid coordinates
+/// unless `ground_offset` is set, which is added to every ground coordinate.
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct GroundCoordinateSystem {
+    /// Grid coordinates of the base point.
+    pub base_point: Point,
+    /// Combined scale factor (grid distance / ground distance).
+    pub combined_scale_factor: f64,
+    /// Offset added to ground coordinates, e.g. to keep them distinct from
+    /// grid values.
+    #[serde(default = "zero_offset")]
+    pub ground_offset: Point,
+    /// EPSG code of the grid the base point refers to, if known.
+    #[serde(default)]
+    pub grid_epsg: Option<u32>,
+}
+
+fn zero_offset() -> Point {
+    Point::new(0.0, 0.0)
+}
+
+impl GroundCoordinateSystem {
+    /// Creates a ground system from a base point and a known CSF.
+    pub fn new(base_point: Point, combined_scale_factor: f64) -> Self {
+        Self {
+            base_point,
+            combined_scale_factor,
+            ground_offset: zero_offset(),
+            grid_epsg: None,
+        }
+    }
+
+    /// Derives the CSF at `base_point` from the grid scale factor and the
+    /// elevation factor for the given ellipsoidal height.
+    pub fn from_crs(crs: &Crs, base_point: Point, ellipsoidal_height: f64) -> Option<Self> {
+        let csf = crs.combined_scale_factor(base_point.x, base_point.y, ellipsoidal_height)?;
+        Some(Self {
+            grid_epsg: crs.epsg(),
+            ..Self::new(base_point, csf)
+        })
+    }
+
+    /// Converts a grid coordinate to ground.
+    pub fn grid_to_ground(&self, p: Point) -> Point {
+        Point::new(
+            self.base_point.x
+                + (p.x - self.base_point.x) / self.combined_scale_factor
+                + self.ground_offset.x,
+            self.base_point.y
+                + (p.y - self.base_point.y) / self.combined_scale_factor
+                + self.ground_offset.y,
+        )
+    }
+
+    /// Converts a ground coordinate to grid.
+    pub fn ground_to_grid(&self, p: Point) -> Point {
+        Point::new(
+            self.base_point.x
+                + (p.x - self.ground_offset.x - self.base_point.x) * self.combined_scale_factor,
+            self.base_point.y
+                + (p.y - self.ground_offset.y - self.base_point.y) * self.combined_scale_factor,
+        )
+    }
+
+    /// Converts a 3D grid coordinate to ground. Elevations are unchanged.
+    pub fn grid_to_ground3(&self, p: Point3) -> Point3 {
+        let g = self.grid_to_ground(Point::new(p.x, p.y));
+        Point3::new(g.x, g.y, p.z)
+    }
+
+    /// Converts a 3D ground coordinate to grid. Elevations are unchanged.
+    pub fn ground_to_grid3(&self, p: Point3) -> Point3 {
+        let g = self.ground_to_grid(Point::new(p.x, p.y));
+        Point3::new(g.x, g.y, p.z)
+    }
+
+    /// The reverse system, taking ground coordinates to grid.
+    pub fn inverse(&self) -> Self {
+        Self {
+            base_point: Point::new(
+                self.base_point.x + self.ground_offset.x,
+                self.base_point.y + self.ground_offset.y,
+            ),
+            combined_scale_factor: 1.0 / self.combined_scale_factor,
+            ground_offset: Point::new(-self.ground_offset.x, -self.ground_offset.y),
+            grid_epsg: self.grid_epsg,
+        }
+    }
+
+    /// Scales a grid distance to ground.
+    pub fn grid_distance_to_ground(&self, d: f64) -> f64 {
+        d / self.combined_scale_factor
+    }
+
+    /// Scales a ground distance to grid.
+    pub fn ground_distance_to_grid(&self, d: f64) -> f64 {
+        d * self.combined_scale_factor
+    }
+
+    /// Saves this ground system definition to a JSON file.
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json).map_err(|e| e.into())
+    }
+
+    /// Loads a ground system definition from a JSON file.
+    pub fn load(path: &str) -> std::io::Result<Self> {
+        let data = std::fs::read_to_string(path)?;
+        serde_json::from_str(&data)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// Converts grid coordinates to ground; use [`GroundCoordinateSystem::inverse`]
+/// for the other direction.
+impl CoordinateTransform for GroundCoordinateSystem {
+    fn transform(&self, x: f64, y: f64, z: f64) -> Option<(f64, f64, f64)> {
+        let p = self.grid_to_ground(Point::new(x, y));
+        Some((p.x, p.y, z))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -77,4 +212,20 @@ mod tests {
         assert!((back.x - global.x).abs() < 1e-6);
         assert!((back.y - global.y).abs() < 1e-6);
     }
+
+    #[test]
+    fn ground_round_trip() {
+        let mut g = GroundCoordinateSystem::new(Point::new(500_000.0, 5_900_000.0), 0.9996);
+        g.ground_offset = Point::new(-400_000.0, -5_800_000.0);
+        let grid = Point::new(500_999.6, 5_900_000.0);
+        let ground = g.grid_to_ground(grid);
+        assert!((ground.x - 101_000.0).abs() < 1e-6);
+        assert!((ground.y - 100_000.0).abs() < 1e-6);
+        let back = g.ground_to_grid(ground);
+        assert!((back.x - grid.x).abs() < 1e-6);
+        assert!((back.y - grid.y).abs() < 1e-6);
+        let inv = g.inverse().grid_to_ground(ground);
+        assert!((inv.x - grid.x).abs() < 1e-6);
+        assert!((inv.y - grid.y).abs() < 1e-6);
+    }
 }
@@ -0,0 +1,250 @@
+//! Straight and circular parcel boundary edges.
+
+use crate::geometry::{distance, Arc, Point};
+use crate::surveying::bearing;
+use std::f64::consts::TAU;
+
+/// Single edge of a parcel boundary.
+///
+/// Curved edges follow the DXF convention: the bulge is the tangent of a
+/// quarter of the included angle and is positive for counter-clockwise arcs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParcelEdge {
+    Line {
+        start: Point,
+        end: Point,
+    },
+    Arc {
+        start: Point,
+        end: Point,
+        bulge: f64,
+    },
+}
+
+impl ParcelEdge {
+    /// Creates an edge, returning a straight segment when `bulge` is zero.
+    pub fn new(start: Point, end: Point, bulge: f64) -> Self {
+        if bulge == 0.0 {
+            ParcelEdge::Line { start, end }
+        } else {
+            ParcelEdge::Arc { start, end, bulge }
+        }
+    }
+
+    /// Start point of the edge.
+    pub fn start(&self) -> Point {
+        match *self {
+            ParcelEdge::Line { start, .. } | ParcelEdge::Arc { start, .. } => start,
+        }
+    }
+
+    /// End point of the edge.
+    pub fn end(&self) -> Point {
+        match *self {
+            ParcelEdge::Line { end, .. } | ParcelEdge::Arc { end, .. } => end,
+        }
+    }
+
+    /// Bulge of the edge, zero for straight segments.
+    pub fn bulge(&self) -> f64 {
+        match *self {
+            ParcelEdge::Line { .. } => 0.0,
+            ParcelEdge::Arc { bulge, .. } => bulge,
+        }
+    }
+
+    /// Returns `true` for curved edges.
+    pub fn is_arc(&self) -> bool {
+        matches!(self, ParcelEdge::Arc { .. })
+    }
+
+    /// Straight-line distance between the end points.
+    pub fn chord_length(&self) -> f64 {
+        distance(self.start(), self.end())
+    }
+
+    /// Bearing in radians from the start to the end point.
+    pub fn chord_bearing(&self) -> f64 {
+        bearing(self.start(), self.end())
+    }
+
+    /// Signed included angle in radians, zero for straight segments.
+    pub fn delta(&self) -> f64 {
+        4.0 * self.bulge().atan()
+    }
+
+    /// Radius of a curved edge.
+    pub fn radius(&self) -> Option<f64> {
+        self.circle().map(|c| c.radius)
+    }
+
+    /// Centre of a curved edge.
+    pub fn center(&self) -> Option<Point> {
+        self.circle().map(|c| c.center)
+    }
+
+    /// Counter-clockwise arc through the end points, as for any bulge.
+    fn circle(&self) -> Option<Arc> {
+        Arc::from_bulge(self.start(), self.end(), self.bulge())
+    }
+
+    /// Length measured along the edge.
+    pub fn length(&self) -> f64 {
+        match self.radius() {
+            Some(r) => r * self.delta().abs(),
+            None => self.chord_length(),
+        }
+    }
+
+    /// Point halfway along the edge.
+    pub fn midpoint(&self) -> Point {
+        self.point_at(0.5)
+    }
+
+    /// Circular arc of a curved edge with angles running from start to end.
+    pub fn arc(&self) -> Option<Arc> {
+        let center = self.center()?;
+        let radius = self.radius()?;
+        let start = self.start();
+        let a0 = (start.y - center.y).atan2(start.x - center.x);
+        Some(Arc::new(center, radius, a0, a0 + self.delta()))
+    }
+
+    /// Point at fraction `t` of the way along the edge.
+    pub fn point_at(&self, t: f64) -> Point {
+        let (s, e) = (self.start(), self.end());
+        if t <= 0.0 {
+            return s;
+        }
+        if t >= 1.0 {
+            return e;
+        }
+        match self.arc() {
+            Some(arc) => arc.point_at(arc.start_angle + (arc.end_angle - arc.start_angle) * t),
+            None => Point::new(s.x + t * (e.x - s.x), s.y + t * (e.y - s.y)),
+        }
+    }
+
+    /// Fraction of the way along the edge of `p`, or `None` if `p` is
+    /// further than `tolerance` from the edge.
+    pub fn locate(&self, p: Point, tolerance: f64) -> Option<f64> {
+        let len = self.length();
+        if len == 0.0 {
+            return None;
+        }
+        let slack = tolerance / len;
+        let Some(arc) = self.arc() else {
+            let (s, e) = (self.start(), self.end());
+            let t = ((p.x - s.x) * (e.x - s.x) + (p.y - s.y) * (e.y - s.y)) / (len * len);
+            return ((-slack..=1.0 + slack).contains(&t)
+                && distance(p, self.point_at(t)) <= tolerance)
+                .then(|| t.clamp(0.0, 1.0));
+        };
+        if (distance(p, arc.center) - arc.radius).abs() > tolerance {
+            return None;
+        }
+        let delta = self.delta();
+        let angle = (p.y - arc.center.y).atan2(p.x - arc.center.x);
+        let swept = ((angle - arc.start_angle) * delta.signum()).rem_euclid(TAU);
+        let t = swept / delta.abs();
+        if t <= 1.0 + slack {
+            Some(t.min(1.0))
+        } else if (TAU - swept) * arc.radius <= tolerance {
+            // just before the start
+            Some(0.0)
+        } else {
+            None
+        }
+    }
+
+    /// Part of the edge between fractions `t0` and `t1` of the way along it.
+    pub fn part(&self, t0: f64, t1: f64) -> ParcelEdge {
+        let bulge = if self.is_arc() {
+            (self.delta() * (t1 - t0) / 4.0).tan()
+        } else {
+            0.0
+        };
+        ParcelEdge::new(self.point_at(t0), self.point_at(t1), bulge)
+    }
+
+    /// Unit direction of travel leaving the start point.
+    pub fn start_direction(&self) -> (f64, f64) {
+        let a = self.chord_angle() - self.delta() / 2.0;
+        (a.cos(), a.sin())
+    }
+
+    /// Unit direction of travel arriving at the end point.
+    pub fn end_direction(&self) -> (f64, f64) {
+        let a = self.chord_angle() + self.delta() / 2.0;
+        (a.cos(), a.sin())
+    }
+
+    fn chord_angle(&self) -> f64 {
+        let (s, e) = (self.start(), self.end());
+        (e.y - s.y).atan2(e.x - s.x)
+    }
+
+    /// Points along the edge spaced at most `step` apart, including both ends.
+    pub fn densify(&self, step: f64) -> Vec<Point> {
+        let Some(arc) = self.arc() else {
+            return vec![self.start(), self.end()];
+        };
+        let n = ((self.length() / step.max(f64::EPSILON)).ceil() as usize).max(1);
+        let mut pts = Vec::with_capacity(n + 1);
+        pts.push(self.start());
+        for i in 1..n {
+            let t = i as f64 / n as f64;
+            pts.push(arc.point_at(arc.start_angle + (arc.end_angle - arc.start_angle) * t));
+        }
+        pts.push(self.end());
+        pts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::{FRAC_PI_2, PI};
+
+    #[test]
+    fn quarter_circle_from_bulge() {
+        let bulge = (FRAC_PI_2 / 4.0).tan();
+        let e = ParcelEdge::new(Point::new(1.0, 0.0), Point::new(0.0, 1.0), bulge);
+        assert!((e.radius().unwrap() - 1.0).abs() < 1e-9);
+        let c = e.center().unwrap();
+        assert!(c.x.abs() < 1e-9 && c.y.abs() < 1e-9);
+        assert!((e.length() - FRAC_PI_2).abs() < 1e-9);
+        let m = e.midpoint();
+        let h = (0.5f64).sqrt();
+        assert!((m.x - h).abs() < 1e-9 && (m.y - h).abs() < 1e-9);
+        let arc = e.arc().unwrap();
+        assert!((arc.end_angle - FRAC_PI_2).abs() < 1e-9);
+    }
+
+    #[test]
+    fn clockwise_semicircle() {
+        let e = ParcelEdge::new(Point::new(0.0, 0.0), Point::new(2.0, 0.0), -1.0);
+        assert!((e.delta() + PI).abs() < 1e-9);
+        let m = e.midpoint();
+        assert!((m.x - 1.0).abs() < 1e-9 && (m.y - 1.0).abs() < 1e-9);
+        let pts = e.densify(0.5);
+        assert!(pts.iter().all(|p| p.y >= -1e-9));
+    }
+
+    #[test]
+    fn locate_and_split_arc() {
+        let e = ParcelEdge::new(Point::new(0.0, 0.0), Point::new(2.0, 0.0), -1.0);
+        let top = Point::new(1.0, 1.0);
+        assert!((e.locate(top, 1e-9).unwrap() - 0.5).abs() < 1e-9);
+        assert!(e.locate(Point::new(1.0, -1.0), 1e-9).is_none());
+        assert_eq!(e.locate(e.start(), 1e-9), Some(0.0));
+        let first = e.part(0.0, 0.5);
+        assert!(distance(first.end(), top) < 1e-9);
+        assert!((first.radius().unwrap() - 1.0).abs() < 1e-9);
+        assert!((first.length() + e.part(0.5, 1.0).length() - e.length()).abs() < 1e-9);
+        let (dx, dy) = e.start_direction();
+        assert!(dx.abs() < 1e-9 && (dy - 1.0).abs() < 1e-9);
+        let (dx, dy) = e.end_direction();
+        assert!(dx.abs() < 1e-9 && (dy + 1.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,300 @@
+//! Metes-and-bounds descriptions, line/curve tables and segment labels.
+
+use super::{Parcel, ParcelEdge};
+use crate::geometry::Point;
+use crate::io::DxfEntity;
+use crate::styles::format_dms;
+
+/// Layer used for generated parcel annotation.
+pub const LABEL_LAYER: &str = "PARCEL_LABELS";
+
+/// Formats a bearing in radians (counter-clockwise from east, as returned by
+/// [`crate::surveying::bearing`]) as a quadrant bearing such as `N 45°0'0" E`.
+pub fn quadrant_bearing(angle: f64) -> String {
+    let az = (90.0 - angle.to_degrees()).rem_euclid(360.0);
+    let (ns, deg, ew) = if az <= 90.0 {
+        ("N", az, "E")
+    } else if az <= 180.0 {
+        ("S", 180.0 - az, "E")
+    } else if az <= 270.0 {
+        ("S", az - 180.0, "W")
+    } else {
+        ("N", 360.0 - az, "W")
+    };
+    format!("{ns} {} {ew}", format_dms(deg))
+}
+
+/// Row of a line table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineTableRow {
+    pub label: String,
+    pub edge: usize,
+    pub bearing: String,
+    pub distance: f64,
+}
+
+/// Row of a curve table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CurveTableRow {
+    pub label: String,
+    pub edge: usize,
+    pub radius: f64,
+    pub length: f64,
+    pub delta: String,
+    pub chord_bearing: String,
+    pub chord: f64,
+}
+
+/// Line and curve tables for the tagged edges of a parcel.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ParcelTables {
+    pub lines: Vec<LineTableRow>,
+    pub curves: Vec<CurveTableRow>,
+}
+
+impl ParcelTables {
+    /// Returns the table tag assigned to edge `i`, if any.
+    pub fn label_for(&self, i: usize) -> Option<&str> {
+        self.lines
+            .iter()
+            .find(|r| r.edge == i)
+            .map(|r| r.label.as_str())
+            .or_else(|| {
+                self.curves
+                    .iter()
+                    .find(|r| r.edge == i)
+                    .map(|r| r.label.as_str())
+            })
+    }
+
+    /// Text rows of the line table including its header.
+    pub fn line_rows(&self) -> Vec<String> {
+        if self.lines.is_empty() {
+            return Vec::new();
+        }
+        let mut rows = vec!["LINE  BEARING  DISTANCE".to_string()];
+        rows.extend(
+            self.lines
+                .iter()
+                .map(|r| format!("{}  {}  {:.3}", r.label, r.bearing, r.distance)),
+        );
+        rows
+    }
+
+    /// Text rows of the curve table including its header.
+    pub fn curve_rows(&self) -> Vec<String> {
+        if self.curves.is_empty() {
+            return Vec::new();
+        }
+        let mut rows = vec!["CURVE  RADIUS  LENGTH  DELTA  CHORD BEARING  CHORD".to_string()];
+        rows.extend(self.curves.iter().map(|r| {
+            format!(
+                "{}  {:.3}  {:.3}  {}  {}  {:.3}",
+                r.label, r.radius, r.length, r.delta, r.chord_bearing, r.chord
+            )
+        }));
+        rows
+    }
+
+    /// Writes both tables as text entities with the top-left corner at
+    /// `origin`.
+    pub fn to_dxf(&self, origin: Point, text_height: f64) -> Vec<DxfEntity> {
+        let spacing = text_height * 1.5;
+        let mut rows = self.line_rows();
+        if !rows.is_empty() && !self.curves.is_empty() {
+            rows.push(String::new());
+        }
+        rows.extend(self.curve_rows());
+        rows.into_iter()
+            .enumerate()
+            .filter(|(_, r)| !r.is_empty())
+            .map(|(i, value)| DxfEntity::Text {
+                position: Point::new(origin.x, origin.y - spacing * (i as f64 + 1.0)),
+                height: text_height,
+                value,
+                layer: Some(LABEL_LAYER.to_string()),
+            })
+            .collect()
+    }
+}
+
+fn format_area(area: f64) -> String {
+    format!("{area:.3} m\u{00B2} ({:.4} ha)", area / 10_000.0)
+}
+
+impl Parcel {
+    /// Returns the quadrant bearing of each boundary edge formatted in DMS.
+    pub fn deed_bearings_dms(&self) -> Vec<String> {
+        self.deed_bearings()
+            .into_iter()
+            .map(quadrant_bearing)
+            .collect()
+    }
+
+    /// Builds line and curve tables. Every curve is tabulated, while straight
+    /// edges only go into the line table when shorter than `min_line_length`.
+    pub fn tables(&self, min_line_length: f64) -> ParcelTables {
+        let mut tables = ParcelTables::default();
+        for (i, e) in self.edges().iter().enumerate() {
+            match e {
+                ParcelEdge::Line { .. } if e.length() < min_line_length => {
+                    tables.lines.push(LineTableRow {
+                        label: format!("L{}", tables.lines.len() + 1),
+                        edge: i,
+                        bearing: quadrant_bearing(e.chord_bearing()),
+                        distance: e.length(),
+                    })
+                }
+                ParcelEdge::Line { .. } => {}
+                ParcelEdge::Arc { .. } => tables.curves.push(CurveTableRow {
+                    label: format!("C{}", tables.curves.len() + 1),
+                    edge: i,
+                    radius: e.radius().unwrap_or(0.0),
+                    length: e.length(),
+                    delta: format_dms(e.delta().abs().to_degrees()),
+                    chord_bearing: quadrant_bearing(e.chord_bearing()),
+                    chord: e.chord_length(),
+                }),
+            }
+        }
+        tables
+    }
+
+    /// Writes a metes-and-bounds description of the parcel starting at its
+    /// first boundary vertex.
+    pub fn legal_description(&self, name: &str) -> String {
+        let mut text = format!("{name}\n");
+        let Some(first) = self.boundary.first() else {
+            return text;
+        };
+        text.push_str(&format!(
+            "Beginning at a point with coordinates E {:.3}, N {:.3};\n",
+            first.x, first.y
+        ));
+        let edges = self.edges();
+        for (i, e) in edges.iter().enumerate() {
+            let call = match e {
+                ParcelEdge::Line { .. } => format!(
+                    "thence {} a distance of {:.3} m",
+                    quadrant_bearing(e.chord_bearing()),
+                    e.length()
+                ),
+                ParcelEdge::Arc { bulge, .. } => format!(
+                    "thence along a curve to the {} having a radius of {:.3} m, an arc length of {:.3} m, a central angle of {} and a chord bearing {} a chord distance of {:.3} m",
+                    if *bulge > 0.0 { "left" } else { "right" },
+                    e.radius().unwrap_or(0.0),
+                    e.length(),
+                    format_dms(e.delta().abs().to_degrees()),
+                    quadrant_bearing(e.chord_bearing()),
+                    e.chord_length()
+                ),
+            };
+            text.push_str(&call);
+            if i + 1 == edges.len() {
+                text.push_str(" to the point of beginning.\n");
+            } else {
+                text.push_str(";\n");
+            }
+        }
+        text.push_str(&format!("Containing {}.\n", format_area(self.area())));
+        text
+    }
+
+    /// Creates text labels at the middle of every edge. Tabulated edges are
+    /// labelled with their table tag, the rest with bearing and distance.
+    pub fn segment_labels(&self, tables: &ParcelTables, text_height: f64) -> Vec<DxfEntity> {
+        self.edges()
+            .iter()
+            .enumerate()
+            .map(|(i, e)| DxfEntity::Text {
+                position: e.midpoint(),
+                height: text_height,
+                value: match tables.label_for(i) {
+                    Some(tag) => tag.to_string(),
+                    None => format!("{} {:.3}", quadrant_bearing(e.chord_bearing()), e.length()),
+                },
+                layer: Some(LABEL_LAYER.to_string()),
+            })
+            .collect()
+    }
+
+    /// Returns the area label placed at an interior point of the parcel.
+    pub fn area_label(&self, text_height: f64) -> DxfEntity {
+        DxfEntity::Text {
+            position: super::subdivision::interior_point(&self.boundary),
+            height: text_height,
+            value: format_area(self.area()),
+            layer: Some(LABEL_LAYER.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lot() -> Parcel {
+        // 20 m square whose north side bulges outwards as a semicircle.
+        Parcel::with_bulges(
+            vec![
+                Point::new(0.0, 0.0),
+                Point::new(20.0, 0.0),
+                Point::new(20.0, 20.0),
+                Point::new(0.0, 20.0),
+            ],
+            vec![0.0, 0.0, 1.0, 0.0],
+        )
+    }
+
+    #[test]
+    fn quadrant_bearings() {
+        use std::f64::consts::{FRAC_PI_2, FRAC_PI_4, PI};
+        assert_eq!(quadrant_bearing(FRAC_PI_4), "N 45\u{00B0}0'0\" E");
+        assert_eq!(quadrant_bearing(-FRAC_PI_4), "S 45\u{00B0}0'0\" E");
+        assert_eq!(
+            quadrant_bearing(PI + 0.3_f64.to_radians()),
+            "S 89\u{00B0}42'0\" W"
+        );
+        assert_eq!(
+            quadrant_bearing(FRAC_PI_2 + FRAC_PI_4),
+            "N 45\u{00B0}0'0\" W"
+        );
+    }
+
+    #[test]
+    fn describes_lines_and_curves() {
+        let desc = lot().legal_description("Lot 1");
+        let lines: Vec<&str> = desc.lines().collect();
+        assert_eq!(lines[0], "Lot 1");
+        assert!(lines[2].starts_with("thence N 90\u{00B0}0'0\" E a distance of 20.000 m"));
+        assert!(lines[4].contains("curve to the left having a radius of 10.000 m"));
+        assert!(lines[4].contains("arc length of 31.416 m"));
+        assert!(lines[4].contains("central angle of 180\u{00B0}0'0\""));
+        assert!(lines[5].ends_with("to the point of beginning."));
+        assert!(lines[6].starts_with("Containing"));
+        assert!(lines[6].contains("ha)"));
+    }
+
+    #[test]
+    fn tables_and_labels() {
+        let p = lot();
+        let tables = p.tables(25.0);
+        assert_eq!(tables.lines.len(), 3);
+        assert_eq!(tables.curves.len(), 1);
+        assert_eq!(tables.curves[0].label, "C1");
+        assert_eq!(tables.curves[0].edge, 2);
+        let labels = p.segment_labels(&p.tables(10.0), 2.0);
+        assert_eq!(labels.len(), 4);
+        match &labels[2] {
+            DxfEntity::Text {
+                position, value, ..
+            } => {
+                assert_eq!(value, "C1");
+                assert!((position.y - 30.0).abs() < 1e-9);
+            }
+            _ => panic!("expected text"),
+        }
+        let rows = tables.to_dxf(Point::new(100.0, 0.0), 2.0);
+        assert_eq!(rows.len(), 1 + 3 + 1 + 1);
+    }
+}
@@ -0,0 +1,329 @@
+use crate::dtm::Tin;
+use crate::geometry::{polygon_area, Point};
+use crate::surveying::Traverse;
+use std::collections::HashMap;
+
+pub mod edge;
+pub use edge::ParcelEdge;
+
+pub mod legal;
+pub use legal::{quadrant_bearing, CurveTableRow, LineTableRow, ParcelTables};
+
+pub mod network;
+pub use network::{NetworkEdge, ParcelNetwork};
+
+pub mod subdivision;
+pub use subdivision::ParcelSplit;
+
+/// Representation of a land parcel defined by a closed boundary.
+///
+/// `bulges[i]` describes the edge leaving `boundary[i]`; missing entries are
+/// straight segments.
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct Parcel {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub boundary: Vec<Point>,
+    #[serde(default)]
+    pub bulges: Vec<f64>,
+}
+
+/// Summary of the closure accuracy for a parcel boundary.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ClosureReport {
+    pub delta_x: f64,
+    pub delta_y: f64,
+    pub misclosure: f64,
+    /// Boundary length measured along arcs.
+    pub perimeter: f64,
+    /// Ratio of perimeter to misclosure, infinite for a perfect closure.
+    pub precision: f64,
+}
+
+impl Parcel {
+    /// Creates a new parcel from its boundary polygon.
+    pub fn new(boundary: Vec<Point>) -> Self {
+        Self {
+            name: None,
+            boundary,
+            bulges: Vec::new(),
+        }
+    }
+
+    /// Creates a parcel whose edges may be circular arcs given by their bulge.
+    pub fn with_bulges(boundary: Vec<Point>, bulges: Vec<f64>) -> Self {
+        Self {
+            name: None,
+            boundary,
+            bulges,
+        }
+    }
+
+    /// Builds a parcel from a list of edges, each starting where the
+    /// previous one ended.
+    pub fn from_edges(edges: &[ParcelEdge]) -> Self {
+        Self::with_bulges(
+            edges.iter().map(ParcelEdge::start).collect(),
+            edges.iter().map(ParcelEdge::bulge).collect(),
+        )
+    }
+
+    /// Returns the bulge of the edge leaving vertex `i`.
+    pub fn bulge(&self, i: usize) -> f64 {
+        self.bulges.get(i).copied().unwrap_or(0.0)
+    }
+
+    /// Returns `true` if any boundary edge is a circular arc.
+    pub fn has_arcs(&self) -> bool {
+        self.bulges.iter().any(|b| *b != 0.0)
+    }
+
+    /// Returns the boundary edges including the closing edge.
+    pub fn edges(&self) -> Vec<ParcelEdge> {
+        let n = self.boundary.len();
+        if n < 2 {
+            return Vec::new();
+        }
+        (0..n)
+            .map(|i| ParcelEdge::new(self.boundary[i], self.boundary[(i + 1) % n], self.bulge(i)))
+            .collect()
+    }
+
+    /// Returns the boundary with arcs replaced by chords no longer than one
+    /// degree of arc.
+    pub fn to_polygon(&self) -> Vec<Point> {
+        if !self.has_arcs() {
+            return self.boundary.clone();
+        }
+        let mut ring = Vec::new();
+        for e in self.edges() {
+            let step = match e.radius() {
+                Some(r) => r * 1f64.to_radians(),
+                None => f64::INFINITY,
+            };
+            let pts = e.densify(step);
+            ring.extend_from_slice(&pts[..pts.len() - 1]);
+        }
+        ring
+    }
+
+    /// Calculates the area enclosed by the parcel boundary, adding or
+    /// removing the circular segment between each arc and its chord.
+    pub fn area(&self) -> f64 {
+        if !self.has_arcs() {
+            return polygon_area(&self.boundary);
+        }
+        self.signed_area().abs()
+    }
+
+    /// Area enclosed by the boundary, positive when it runs
+    /// counter-clockwise.
+    pub(crate) fn signed_area(&self) -> f64 {
+        let mut area = subdivision::signed_area(&self.boundary);
+        for e in self.edges() {
+            if let Some(r) = e.radius() {
+                let delta = e.delta().abs();
+                area += e.bulge().signum() * r * r * (delta - delta.sin()) / 2.0;
+            }
+        }
+        area
+    }
+
+    /// The same parcel with its boundary running the other way.
+    pub(crate) fn reversed(&self) -> Parcel {
+        let n = self.boundary.len();
+        let mut boundary = self.boundary.clone();
+        boundary.reverse();
+        let bulges = if self.has_arcs() {
+            (0..n).map(|k| -self.bulge((2 * n - 2 - k) % n)).collect()
+        } else {
+            Vec::new()
+        };
+        Parcel {
+            name: self.name.clone(),
+            boundary,
+            bulges,
+        }
+    }
+
+    /// Total boundary length measured along arcs.
+    pub fn perimeter(&self) -> f64 {
+        self.edges().iter().map(ParcelEdge::length).sum()
+    }
+
+    /// Builds a parcel from a survey traverse, keeping any curved legs.
+    pub fn from_traverse(tr: &Traverse) -> Self {
+        Self::with_bulges(tr.points.clone(), tr.bulges.clone())
+    }
+
+    /// Returns the bearing in radians of each boundary segment. Curved edges
+    /// report their chord bearing.
+    pub fn deed_bearings(&self) -> Vec<f64> {
+        self.edges().iter().map(ParcelEdge::chord_bearing).collect()
+    }
+
+    /// Generates a closure report summarizing misclosure of the boundary.
+    /// Curved edges contribute their chord to the misclosure and their arc
+    /// length to the perimeter.
+    pub fn closure_report(&self) -> ClosureReport {
+        let edges = self.edges();
+        if edges.len() < 2 {
+            return ClosureReport {
+                delta_x: 0.0,
+                delta_y: 0.0,
+                misclosure: 0.0,
+                perimeter: 0.0,
+                precision: f64::INFINITY,
+            };
+        }
+        let mut dx = 0.0;
+        let mut dy = 0.0;
+        for e in &edges {
+            dx += e.end().x - e.start().x;
+            dy += e.end().y - e.start().y;
+        }
+        let misclosure = (dx * dx + dy * dy).sqrt();
+        let perimeter = self.perimeter();
+        ClosureReport {
+            delta_x: dx,
+            delta_y: dy,
+            misclosure,
+            perimeter,
+            precision: if misclosure < f64::EPSILON {
+                f64::INFINITY
+            } else {
+                perimeter / misclosure
+            },
+        }
+    }
+
+    /// Builds a parcel from the outer boundary edges of a TIN surface.
+    pub fn from_tin_boundary(tin: &Tin) -> Self {
+        let mut edge_count: HashMap<(usize, usize), usize> = HashMap::new();
+        for tri in &tin.triangles {
+            for &(a, b) in [(tri[0], tri[1]), (tri[1], tri[2]), (tri[2], tri[0])].iter() {
+                let e = if a < b { (a, b) } else { (b, a) };
+                *edge_count.entry(e).or_insert(0) += 1;
+            }
+        }
+        let mut edges: Vec<(usize, usize)> = edge_count
+            .into_iter()
+            .filter_map(|(e, c)| if c == 1 { Some(e) } else { None })
+            .collect();
+        if edges.is_empty() {
+            return Self::new(Vec::new());
+        }
+        let (a, mut b) = edges.pop().unwrap();
+        let mut order = vec![a, b];
+        while !edges.is_empty() {
+            if let Some(pos) = edges.iter().position(|&(x, _)| x == b) {
+                let (_, nb) = edges.remove(pos);
+                order.push(nb);
+                b = nb;
+            } else if let Some(pos) = edges.iter().position(|&(_, y)| y == b) {
+                let (na, _) = edges.remove(pos);
+                order.push(na);
+                b = na;
+            } else {
+                break;
+            }
+        }
+        let pts = order
+            .into_iter()
+            .map(|idx| {
+                let v = tin.vertices[idx];
+                Point::new(v.x, v.y)
+            })
+            .collect();
+        Self::new(pts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geometry::Point3;
+
+    #[test]
+    fn parcel_area_square() {
+        let boundary = vec![
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 0.0),
+            Point::new(1.0, 1.0),
+            Point::new(0.0, 1.0),
+        ];
+        let p = Parcel::new(boundary);
+        assert!((p.area() - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn parcel_from_tin_boundary() {
+        let pts = vec![
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(1.0, 0.0, 0.0),
+            Point3::new(1.0, 1.0, 0.0),
+            Point3::new(0.0, 1.0, 0.0),
+        ];
+        let tin = Tin::from_points(pts);
+        let p = Parcel::from_tin_boundary(&tin);
+        assert!((p.area() - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn parcel_bearings_and_closure() {
+        let p = Parcel::new(vec![
+            Point::new(0.0, 0.0),
+            Point::new(1.0, 0.0),
+            Point::new(1.0, 1.0),
+            Point::new(0.0, 1.0),
+        ]);
+        let b = p.deed_bearings();
+        assert_eq!(b.len(), 4);
+        assert!((b[0] - 0.0).abs() < 1e-6);
+        assert!((b[1] - std::f64::consts::FRAC_PI_2).abs() < 1e-6);
+        assert!((b[2] - std::f64::consts::PI).abs() < 1e-6);
+        assert!((b[3] + std::f64::consts::FRAC_PI_2).abs() < 1e-6);
+        let rep = p.closure_report();
+        assert!(rep.misclosure.abs() < 1e-6);
+    }
+
+    #[test]
+    fn curved_parcel_area_and_perimeter() {
+        use std::f64::consts::PI;
+        // 20 m square with a semicircle added on the north side and one cut
+        // out of the west side.
+        let p = Parcel::with_bulges(
+            vec![
+                Point::new(0.0, 0.0),
+                Point::new(20.0, 0.0),
+                Point::new(20.0, 20.0),
+                Point::new(0.0, 20.0),
+            ],
+            vec![0.0, 0.0, 1.0, -1.0],
+        );
+        assert!((p.area() - 400.0).abs() < 1e-9);
+        let p = Parcel::with_bulges(p.boundary, vec![0.0, 0.0, 1.0, 0.0]);
+        assert!((p.area() - (400.0 + 50.0 * PI)).abs() < 1e-9);
+        let rep = p.closure_report();
+        assert!((rep.perimeter - (60.0 + 10.0 * PI)).abs() < 1e-9);
+        assert!(rep.misclosure < 1e-9);
+        assert!(rep.precision.is_infinite());
+        let poly = polygon_area(&p.to_polygon());
+        assert!((poly - p.area()).abs() / p.area() < 1e-3);
+    }
+
+    #[test]
+    fn parcel_from_curved_traverse() {
+        let mut tr = Traverse::new(vec![
+            Point::new(0.0, 0.0),
+            Point::new(10.0, 0.0),
+            Point::new(10.0, 10.0),
+        ]);
+        tr.bulges = vec![0.0, 0.0, -0.5];
+        let p = Parcel::from_traverse(&tr);
+        let edges = p.edges();
+        assert_eq!(edges.len(), 3);
+        assert!(edges[2].is_arc());
+        assert!(p.area() < 50.0);
+    }
+}
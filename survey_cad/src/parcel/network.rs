@@ -0,0 +1,560 @@
+//! Parcels sharing their boundary nodes.
+//!
+//! Every parcel of a [`ParcelNetwork`] is a ring of indices into a common
+//! node list. Neighbouring parcels reference the same nodes along their
+//! common edge, so moving a node or bending an edge updates both sides and
+//! subdividing one parcel adds the new corners to its neighbours as well.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use super::subdivision::{interior_point, point_in_ring, ParcelSplit};
+use super::{Parcel, ParcelEdge};
+use crate::alignment::HorizontalAlignment;
+use crate::geometry::{distance, Point, Polyline};
+
+/// Boundary edge of a [`ParcelNetwork`] with the parcels on either side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NetworkEdge {
+    pub start: usize,
+    pub end: usize,
+    /// Parcel to the left going from `start` to `end`.
+    pub left: Option<usize>,
+    /// Parcel to the right going from `start` to `end`.
+    pub right: Option<usize>,
+}
+
+impl NetworkEdge {
+    /// Returns `true` if parcels lie on both sides of the edge.
+    pub fn is_shared(&self) -> bool {
+        self.left.is_some() && self.right.is_some()
+    }
+}
+
+/// Parcels with shared boundary topology.
+#[derive(Debug, Clone, Default)]
+pub struct ParcelNetwork {
+    pub nodes: Vec<Point>,
+    /// Parcel boundaries as counter-clockwise rings of node indices.
+    pub rings: Vec<Vec<usize>>,
+    /// Parcel names, one per ring.
+    pub names: Vec<Option<String>>,
+    /// Bulges of curved edges keyed by their lower and higher node index,
+    /// measured going from the lower node.
+    pub bulges: BTreeMap<(usize, usize), f64>,
+    /// Distance within which points are merged into one node.
+    pub tolerance: f64,
+}
+
+impl ParcelNetwork {
+    pub fn new(tolerance: f64) -> Self {
+        Self {
+            nodes: Vec::new(),
+            rings: Vec::new(),
+            names: Vec::new(),
+            bulges: BTreeMap::new(),
+            tolerance,
+        }
+    }
+
+    /// Builds a network from separate parcels, merging corners closer than
+    /// `tolerance` and splitting edges at corners of neighbouring parcels.
+    pub fn from_parcels(parcels: &[Parcel], tolerance: f64) -> Self {
+        let mut network = Self::new(tolerance);
+        for p in parcels {
+            network.push_ring(p);
+        }
+        network.connect();
+        network
+    }
+
+    pub fn len(&self) -> usize {
+        self.rings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rings.is_empty()
+    }
+
+    /// Adds a parcel and returns its index.
+    pub fn add_parcel(&mut self, parcel: &Parcel) -> usize {
+        self.push_ring(parcel);
+        self.connect();
+        self.rings.len() - 1
+    }
+
+    /// Removes a parcel. Later parcels move down one index.
+    pub fn remove_parcel(&mut self, index: usize) -> Parcel {
+        let parcel = self.parcel(index);
+        self.rings.remove(index);
+        self.names.remove(index);
+        self.prune_bulges();
+        parcel
+    }
+
+    /// Parcel at `index` with its current node positions.
+    pub fn parcel(&self, index: usize) -> Parcel {
+        let ring = &self.rings[index];
+        let n = ring.len();
+        let bulges: Vec<f64> = (0..n)
+            .map(|k| self.bulge(ring[k], ring[(k + 1) % n]))
+            .collect();
+        Parcel {
+            name: self.names[index].clone(),
+            boundary: ring.iter().map(|&n| self.nodes[n]).collect(),
+            bulges: if bulges.iter().any(|b| *b != 0.0) {
+                bulges
+            } else {
+                Vec::new()
+            },
+        }
+    }
+
+    /// Bulge of the edge going from node `a` to node `b`.
+    pub fn bulge(&self, a: usize, b: usize) -> f64 {
+        match self.bulges.get(&(a.min(b), a.max(b))) {
+            Some(&bulge) if a < b => bulge,
+            Some(&bulge) => -bulge,
+            None => 0.0,
+        }
+    }
+
+    /// Sets the bulge of the edge going from node `a` to node `b` for every
+    /// parcel along it.
+    pub fn set_bulge(&mut self, a: usize, b: usize, bulge: f64) {
+        let key = (a.min(b), a.max(b));
+        if bulge == 0.0 {
+            self.bulges.remove(&key);
+        } else {
+            self.bulges.insert(key, if a < b { bulge } else { -bulge });
+        }
+    }
+
+    fn edge(&self, a: usize, b: usize) -> ParcelEdge {
+        ParcelEdge::new(self.nodes[a], self.nodes[b], self.bulge(a, b))
+    }
+
+    pub fn parcels(&self) -> Vec<Parcel> {
+        (0..self.rings.len()).map(|i| self.parcel(i)).collect()
+    }
+
+    /// Index of the node at `p`, adding one if none is within tolerance.
+    pub fn node(&mut self, p: Point) -> usize {
+        if let Some(i) = self.find_node(p) {
+            return i;
+        }
+        self.nodes.push(p);
+        self.nodes.len() - 1
+    }
+
+    /// Index of the node within tolerance of `p`.
+    pub fn find_node(&self, p: Point) -> Option<usize> {
+        self.nodes
+            .iter()
+            .position(|q| distance(p, *q) <= self.tolerance)
+    }
+
+    /// Moves a node, reshaping every parcel that uses it.
+    pub fn move_node(&mut self, node: usize, to: Point) {
+        self.nodes[node] = to;
+    }
+
+    /// Inserts a node at `p` on the edge between nodes `a` and `b` in every
+    /// parcel along that edge and returns it. A curved edge stays curved if
+    /// `p` lies on its arc and is replaced by two chords otherwise.
+    pub fn insert_node(&mut self, a: usize, b: usize, p: Point) -> usize {
+        let edge = self.edge(a, b);
+        let node = self.node(p);
+        if let Some(t) = edge.locate(self.nodes[node], self.tolerance) {
+            self.set_bulge(a, node, edge.part(0.0, t).bulge());
+            self.set_bulge(node, b, edge.part(t, 1.0).bulge());
+        }
+        for ring in &mut self.rings {
+            let n = ring.len();
+            if let Some(i) = (0..n).find(|&i| {
+                let (x, y) = (ring[i], ring[(i + 1) % n]);
+                (x, y) == (a, b) || (x, y) == (b, a)
+            }) {
+                ring.insert(i + 1, node);
+            }
+        }
+        self.prune_bulges();
+        node
+    }
+
+    /// Every boundary edge once, with the parcels on either side.
+    pub fn edges(&self) -> Vec<NetworkEdge> {
+        let mut edges: BTreeMap<(usize, usize), NetworkEdge> = BTreeMap::new();
+        for (i, ring) in self.rings.iter().enumerate() {
+            let n = ring.len();
+            for k in 0..n {
+                let (a, b) = (ring[k], ring[(k + 1) % n]);
+                let edge = edges.entry((a.min(b), a.max(b))).or_insert(NetworkEdge {
+                    start: a.min(b),
+                    end: a.max(b),
+                    left: None,
+                    right: None,
+                });
+                if a < b {
+                    edge.left = Some(i);
+                } else {
+                    edge.right = Some(i);
+                }
+            }
+        }
+        edges.into_values().collect()
+    }
+
+    /// Parcels sharing at least one edge with parcel `index`.
+    pub fn neighbours(&self, index: usize) -> Vec<usize> {
+        let mut out = BTreeSet::new();
+        for e in self.edges() {
+            match (e.left, e.right) {
+                (Some(l), Some(r)) if l == index && r != index => {
+                    out.insert(r);
+                }
+                (Some(l), Some(r)) if r == index && l != index => {
+                    out.insert(l);
+                }
+                _ => {}
+            }
+        }
+        out.into_iter().collect()
+    }
+
+    /// Replaces parcel `index` with `pieces`. The first piece keeps the
+    /// index and the others are appended; their indices are returned.
+    pub fn replace(&mut self, index: usize, pieces: Vec<Parcel>) -> Vec<usize> {
+        let mut indices = Vec::new();
+        for (k, piece) in pieces.iter().enumerate() {
+            let ring = self.ring_of(piece);
+            if k == 0 {
+                self.rings[index] = ring;
+                self.names[index] = piece.name.clone();
+                indices.push(index);
+            } else {
+                self.rings.push(ring);
+                self.names.push(piece.name.clone());
+                indices.push(self.rings.len() - 1);
+            }
+        }
+        self.connect();
+        indices
+    }
+
+    /// Cuts parcel `index` along a polyline.
+    pub fn split(&mut self, index: usize, cutter: &Polyline) -> Vec<usize> {
+        let pieces = self.parcel(index).split(cutter);
+        self.replace(index, pieces)
+    }
+
+    /// Cuts parcel `index` along the line through `a` and `b`, returning the
+    /// indices of the parcels left and right of it.
+    pub fn split_by_line(&mut self, index: usize, a: Point, b: Point) -> (Vec<usize>, Vec<usize>) {
+        let split = self.parcel(index).split_by_line(a, b);
+        self.apply(index, split)
+    }
+
+    /// Slides a line parallel to `a`–`b` across parcel `index` to cut off
+    /// `area` on its left. See [`Parcel::slide_to_area`].
+    pub fn slide_to_area(
+        &mut self,
+        index: usize,
+        a: Point,
+        b: Point,
+        area: f64,
+    ) -> Option<(Vec<usize>, Vec<usize>)> {
+        let split = self.parcel(index).slide_to_area(a, b, area)?;
+        Some(self.apply(index, split))
+    }
+
+    /// Swings a line about `pivot` to cut off `area` of parcel `index` on
+    /// its left. See [`Parcel::swing_to_area`].
+    pub fn swing_to_area(
+        &mut self,
+        index: usize,
+        pivot: Point,
+        through: Point,
+        area: f64,
+    ) -> Option<(Vec<usize>, Vec<usize>)> {
+        let split = self.parcel(index).swing_to_area(pivot, through, area)?;
+        Some(self.apply(index, split))
+    }
+
+    /// Divides parcel `index` into lots of equal frontage. See
+    /// [`Parcel::lots_along_frontage`].
+    pub fn lots_along_frontage(
+        &mut self,
+        index: usize,
+        frontage: &Polyline,
+        count: usize,
+    ) -> Vec<usize> {
+        let lots = self.parcel(index).lots_along_frontage(frontage, count);
+        self.replace(index, lots)
+    }
+
+    /// Cuts a right of way along an alignment out of every parcel it
+    /// crosses, returning the indices of the right-of-way parcels.
+    pub fn cut_right_of_way(
+        &mut self,
+        alignment: &HorizontalAlignment,
+        left: f64,
+        right: f64,
+        interval: f64,
+    ) -> Vec<usize> {
+        let row = Parcel::right_of_way(alignment, left, right, interval);
+        let mut cutter = row.boundary.clone();
+        if let Some(&first) = cutter.first() {
+            cutter.push(first);
+        }
+        let cutter = Polyline::new(cutter);
+        let mut taken = Vec::new();
+        for index in 0..self.rings.len() {
+            let pieces = self.parcel(index).split(&cutter);
+            if pieces.len() < 2 {
+                let inside = interior_point(&self.parcel(index).to_polygon());
+                if point_in_ring(inside, &row.boundary) {
+                    taken.push(index);
+                }
+                continue;
+            }
+            let inside: Vec<bool> = pieces
+                .iter()
+                .map(|p| point_in_ring(interior_point(&p.to_polygon()), &row.boundary))
+                .collect();
+            let indices = self.replace(index, pieces);
+            taken.extend(
+                indices
+                    .into_iter()
+                    .zip(inside)
+                    .filter_map(|(i, inside)| inside.then_some(i)),
+            );
+        }
+        taken.sort_unstable();
+        taken
+    }
+
+    fn apply(&mut self, index: usize, split: ParcelSplit) -> (Vec<usize>, Vec<usize>) {
+        let left = split.left.len();
+        let mut indices = self.replace(index, split.left.into_iter().chain(split.right).collect());
+        let right = indices.split_off(left.min(indices.len()));
+        (indices, right)
+    }
+
+    /// Ring of node indices for a parcel, counter-clockwise and without
+    /// repeated nodes, recording the bulges of its curved edges.
+    fn ring_of(&mut self, parcel: &Parcel) -> Vec<usize> {
+        let parcel = if parcel.signed_area() < 0.0 {
+            parcel.reversed()
+        } else {
+            parcel.clone()
+        };
+        let mut ring = Vec::new();
+        let mut bulges = Vec::new();
+        for e in parcel.edges() {
+            let (a, b) = (self.node(e.start()), self.node(e.end()));
+            if a != b {
+                ring.push(a);
+                bulges.push(e.bulge());
+            }
+        }
+        let n = ring.len();
+        for (k, bulge) in bulges.into_iter().enumerate() {
+            self.set_bulge(ring[k], ring[(k + 1) % n], bulge);
+        }
+        ring
+    }
+
+    fn push_ring(&mut self, parcel: &Parcel) {
+        let ring = self.ring_of(parcel);
+        self.rings.push(ring);
+        self.names.push(parcel.name.clone());
+    }
+
+    /// Forgets the bulges of edges no parcel uses any more.
+    fn prune_bulges(&mut self) {
+        let used: BTreeSet<(usize, usize)> = self
+            .rings
+            .iter()
+            .flat_map(|ring| {
+                let n = ring.len();
+                (0..n).map(move |k| {
+                    let (a, b) = (ring[k], ring[(k + 1) % n]);
+                    (a.min(b), a.max(b))
+                })
+            })
+            .collect();
+        self.bulges.retain(|key, _| used.contains(key));
+    }
+
+    /// Adds nodes lying on an edge of another parcel to that edge so that
+    /// neighbours share it. Curved edges are split along their arcs.
+    fn connect(&mut self) {
+        let used: BTreeSet<usize> = self.rings.iter().flatten().copied().collect();
+        let mut split = Vec::new();
+        for r in 0..self.rings.len() {
+            let ring = &self.rings[r];
+            let n = ring.len();
+            let mut out = Vec::with_capacity(n);
+            for k in 0..n {
+                let (a, b) = (ring[k], ring[(k + 1) % n]);
+                out.push(a);
+                let edge = self.edge(a, b);
+                if edge.chord_length() <= self.tolerance {
+                    continue;
+                }
+                let mut on: Vec<(f64, usize)> = used
+                    .iter()
+                    .copied()
+                    .filter(|&m| m != a && m != b)
+                    .filter_map(|m| {
+                        let t = edge.locate(self.nodes[m], self.tolerance)?;
+                        (t > 0.0 && t < 1.0).then_some((t, m))
+                    })
+                    .collect();
+                if on.is_empty() {
+                    continue;
+                }
+                on.sort_by(|x, y| x.0.total_cmp(&y.0));
+                let mut from = (0.0, a);
+                for &to in on.iter().chain([&(1.0, b)]) {
+                    split.push((from.1, to.1, edge.part(from.0, to.0).bulge()));
+                    from = to;
+                }
+                out.extend(on.into_iter().map(|(_, m)| m));
+            }
+            self.rings[r] = out;
+        }
+        for (a, b, bulge) in split {
+            self.set_bulge(a, b, bulge);
+        }
+        self.prune_bulges();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x0: f64, y0: f64, x1: f64, y1: f64) -> Parcel {
+        Parcel::new(vec![
+            Point::new(x0, y0),
+            Point::new(x1, y0),
+            Point::new(x1, y1),
+            Point::new(x0, y1),
+        ])
+    }
+
+    fn total_area(network: &ParcelNetwork) -> f64 {
+        network.parcels().iter().map(Parcel::area).sum()
+    }
+
+    #[test]
+    fn shared_edges_and_t_junctions() {
+        let network = ParcelNetwork::from_parcels(
+            &[
+                rect(0.0, 0.0, 20.0, 10.0),
+                rect(0.0, 10.0, 10.0, 20.0),
+                rect(10.0, 10.0, 20.0, 20.0),
+            ],
+            1e-6,
+        );
+        // the middle corner of the top lots splits the lower lot's edge
+        assert_eq!(network.rings[0].len(), 5);
+        let shared: Vec<_> = network
+            .edges()
+            .into_iter()
+            .filter(|e| e.is_shared())
+            .collect();
+        assert_eq!(shared.len(), 3);
+        assert_eq!(network.neighbours(0), vec![1, 2]);
+        assert_eq!(network.neighbours(1), vec![0, 2]);
+    }
+
+    #[test]
+    fn moving_a_shared_node_updates_neighbours() {
+        let mut network = ParcelNetwork::from_parcels(
+            &[rect(0.0, 0.0, 10.0, 10.0), rect(10.0, 0.0, 20.0, 10.0)],
+            1e-6,
+        );
+        let node = network.find_node(Point::new(10.0, 10.0)).unwrap();
+        network.move_node(node, Point::new(12.0, 10.0));
+        assert!((network.parcel(0).area() - 110.0).abs() < 1e-9);
+        assert!((network.parcel(1).area() - 90.0).abs() < 1e-9);
+
+        let (a, b) = (node, network.find_node(Point::new(10.0, 0.0)).unwrap());
+        let bend = network.insert_node(a, b, Point::new(8.0, 5.0));
+        assert!(network.rings.iter().all(|r| r.contains(&bend)));
+        assert!((total_area(&network) - 200.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn subdividing_keeps_topology() {
+        let mut network = ParcelNetwork::from_parcels(
+            &[rect(0.0, 0.0, 10.0, 10.0), rect(10.0, 0.0, 20.0, 10.0)],
+            1e-6,
+        );
+        let (left, right) = network.split_by_line(0, Point::new(10.0, 5.0), Point::new(0.0, 5.0));
+        assert_eq!((left.len(), right.len()), (1, 1));
+        assert!((network.parcel(left[0]).area() - 50.0).abs() < 1e-9);
+        // the new corner is part of the neighbour's boundary
+        let corner = network.find_node(Point::new(10.0, 5.0)).unwrap();
+        assert!(network.rings[1].contains(&corner));
+        assert_eq!(network.neighbours(1).len(), 2);
+        network.move_node(corner, Point::new(11.0, 5.0));
+        assert!((network.parcel(1).area() - 95.0).abs() < 1e-9);
+        assert!((total_area(&network) - 200.0).abs() < 1e-9);
+
+        let lots = network.lots_along_frontage(
+            1,
+            &Polyline::new(vec![Point::new(10.0, 0.0), Point::new(20.0, 0.0)]),
+            2,
+        );
+        assert_eq!(lots.len(), 2);
+        assert!((total_area(&network) - 200.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn curved_edges_and_names_survive() {
+        // both lots bulge east along their common edge
+        let mut a = Parcel::with_bulges(rect(0.0, 0.0, 20.0, 20.0).boundary, vec![0.0, 0.5]);
+        a.name = Some("Lot 1".into());
+        let b = Parcel::with_bulges(
+            rect(20.0, 0.0, 40.0, 20.0).boundary,
+            vec![0.0, 0.0, 0.0, -0.5],
+        );
+        let mut network = ParcelNetwork::from_parcels(&[a.clone(), b.clone()], 1e-6);
+        assert_eq!(network.bulges.len(), 1);
+        let lot = network.parcel(0);
+        assert_eq!(lot.name.as_deref(), Some("Lot 1"));
+        assert!((lot.area() - a.area()).abs() < 1e-9);
+        assert!((network.parcel(1).area() - b.area()).abs() < 1e-9);
+        assert!(network.remove_parcel(0).has_arcs());
+        network.add_parcel(&a);
+
+        // cutting one lot splits the shared arc in the other
+        network.split_by_line(1, Point::new(-5.0, 10.0), Point::new(45.0, 10.0));
+        let corner = network.find_node(Point::new(25.0, 10.0)).unwrap();
+        assert!(network.rings[0].contains(&corner));
+        assert!((network.parcel(0).area() - b.area()).abs() < 1e-9);
+        assert!((total_area(&network) - 800.0).abs() < 1e-9);
+        assert_eq!(network.bulges.len(), 2);
+    }
+
+    #[test]
+    fn right_of_way_is_cut_from_each_parcel() {
+        let mut network = ParcelNetwork::from_parcels(
+            &[rect(0.0, 0.0, 50.0, 40.0), rect(50.0, 0.0, 100.0, 40.0)],
+            1e-6,
+        );
+        let alignment =
+            HorizontalAlignment::new(vec![Point::new(-10.0, 20.0), Point::new(110.0, 20.0)]);
+        let row = network.cut_right_of_way(&alignment, 5.0, 5.0, 25.0);
+        assert_eq!(row.len(), 2);
+        for &i in &row {
+            assert!((network.parcel(i).area() - 500.0).abs() < 1e-6);
+        }
+        assert_eq!(network.len(), 6);
+        assert!((total_area(&network) - 4000.0).abs() < 1e-6);
+        assert!(network.neighbours(row[0]).contains(&row[1]));
+    }
+}
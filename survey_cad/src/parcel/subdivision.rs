@@ -0,0 +1,640 @@
+//! Subdivision of parcels by cutting lines.
+//!
+//! Cuts are made by overlaying the cutting polyline on the boundary and
+//! tracing the faces of the resulting planar graph, so concave parcels
+//! crossed several times come apart into every piece. Pieces keep the
+//! orientation of the parcel they were cut from.
+
+use std::collections::BTreeSet;
+
+use super::{Parcel, ParcelEdge};
+use crate::alignment::HorizontalAlignment;
+use crate::geometry::{distance, Line, Point, Polyline};
+use crate::surveying::bearing;
+
+/// Parcels on either side of a straight cut.
+#[derive(Debug, Clone)]
+pub struct ParcelSplit {
+    /// Two points on the cutting line, pointing so that `left` lies to its
+    /// left.
+    pub line: (Point, Point),
+    pub left: Vec<Parcel>,
+    pub right: Vec<Parcel>,
+}
+
+impl ParcelSplit {
+    /// Total area of the parcels left of the line.
+    pub fn left_area(&self) -> f64 {
+        self.left.iter().map(Parcel::area).sum()
+    }
+
+    /// Total area of the parcels right of the line.
+    pub fn right_area(&self) -> f64 {
+        self.right.iter().map(Parcel::area).sum()
+    }
+}
+
+pub(crate) fn signed_area(ring: &[Point]) -> f64 {
+    let n = ring.len();
+    (0..n)
+        .map(|i| {
+            let (a, b) = (ring[i], ring[(i + 1) % n]);
+            a.x * b.y - b.x * a.y
+        })
+        .sum::<f64>()
+        * 0.5
+}
+
+/// Distance below which two points of `ring` are treated as the same.
+pub(crate) fn tolerance(ring: &[Point]) -> f64 {
+    let extent = ring
+        .iter()
+        .map(|p| p.x.abs().max(p.y.abs()))
+        .fold(1.0, f64::max);
+    extent * 1e-9
+}
+
+fn cross(o: Point, a: Point, b: Point) -> f64 {
+    (a.x - o.x) * (b.y - o.y) - (a.y - o.y) * (b.x - o.x)
+}
+
+fn lerp(a: Point, b: Point, t: f64) -> Point {
+    Point::new(a.x + t * (b.x - a.x), a.y + t * (b.y - a.y))
+}
+
+/// Parameters along `ab` and `cd` of their intersection, if they cross.
+fn segment_intersection(a: Point, b: Point, c: Point, d: Point) -> Option<(f64, f64)> {
+    let r = (b.x - a.x, b.y - a.y);
+    let s = (d.x - c.x, d.y - c.y);
+    let denom = r.0 * s.1 - r.1 * s.0;
+    if denom.abs() < f64::EPSILON * (r.0.hypot(r.1) * s.0.hypot(s.1)).max(f64::MIN_POSITIVE) {
+        return None;
+    }
+    let q = (c.x - a.x, c.y - a.y);
+    let t = (q.0 * s.1 - q.1 * s.0) / denom;
+    let u = (q.0 * r.1 - q.1 * r.0) / denom;
+    let eps = 1e-12;
+    ((-eps..=1.0 + eps).contains(&t) && (-eps..=1.0 + eps).contains(&u))
+        .then(|| (t.clamp(0.0, 1.0), u.clamp(0.0, 1.0)))
+}
+
+pub(crate) fn point_in_ring(p: Point, ring: &[Point]) -> bool {
+    let n = ring.len();
+    let mut inside = false;
+    for i in 0..n {
+        let (a, b) = (ring[i], ring[(i + 1) % n]);
+        if (a.y > p.y) != (b.y > p.y) && p.x < a.x + (p.y - a.y) / (b.y - a.y) * (b.x - a.x) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+fn distance_to_ring(p: Point, ring: &[Point]) -> f64 {
+    let n = ring.len();
+    (0..n)
+        .map(|i| distance(p, Line::new(ring[i], ring[(i + 1) % n]).nearest_point(p)))
+        .fold(f64::INFINITY, f64::min)
+}
+
+/// A point strictly inside a simple polygon, found from a convex vertex and
+/// the vertex nearest to it inside the triangle of its neighbours.
+pub(crate) fn interior_point(ring: &[Point]) -> Point {
+    let n = ring.len();
+    if n < 3 {
+        return ring.first().copied().unwrap_or(Point::new(0.0, 0.0));
+    }
+    let sign = signed_area(ring).signum();
+    let i = (0..n)
+        .max_by(|&i, &j| {
+            let turn = |k: usize| sign * cross(ring[(k + n - 1) % n], ring[k], ring[(k + 1) % n]);
+            turn(i).total_cmp(&turn(j))
+        })
+        .unwrap();
+    let (a, v, c) = (ring[(i + n - 1) % n], ring[i], ring[(i + 1) % n]);
+    let inside_triangle = |p: Point| {
+        let (d1, d2, d3) = (cross(a, v, p), cross(v, c, p), cross(c, a, p));
+        (d1 > 0.0 && d2 > 0.0 && d3 > 0.0) || (d1 < 0.0 && d2 < 0.0 && d3 < 0.0)
+    };
+    let nearest = ring
+        .iter()
+        .copied()
+        .filter(|&p| inside_triangle(p))
+        .min_by(|p, q| distance(v, *p).total_cmp(&distance(v, *q)));
+    match nearest {
+        Some(q) => lerp(v, q, 0.5),
+        None => Point::new((a.x + v.x + c.x) / 3.0, (a.y + v.y + c.y) / 3.0),
+    }
+}
+
+/// Nodes of a planar graph, merging points closer than the tolerance.
+struct Nodes {
+    points: Vec<Point>,
+    tolerance: f64,
+}
+
+impl Nodes {
+    fn index(&mut self, p: Point) -> usize {
+        if let Some(i) = self
+            .points
+            .iter()
+            .position(|q| distance(p, *q) <= self.tolerance)
+        {
+            return i;
+        }
+        self.points.push(p);
+        self.points.len() - 1
+    }
+}
+
+/// Parameters along `edge` and along `ab` where they cross.
+fn edge_crossings(edge: &ParcelEdge, a: Point, b: Point, tol: f64) -> Vec<(f64, f64)> {
+    let Some(arc) = edge.arc() else {
+        return segment_intersection(edge.start(), edge.end(), a, b)
+            .into_iter()
+            .collect();
+    };
+    let d = (b.x - a.x, b.y - a.y);
+    let f = (a.x - arc.center.x, a.y - arc.center.y);
+    let qa = d.0 * d.0 + d.1 * d.1;
+    if qa == 0.0 {
+        return Vec::new();
+    }
+    let qb = 2.0 * (f.0 * d.0 + f.1 * d.1);
+    let qc = f.0 * f.0 + f.1 * f.1 - arc.radius * arc.radius;
+    let disc = qb * qb - 4.0 * qa * qc;
+    if disc < 0.0 {
+        return Vec::new();
+    }
+    let root = disc.sqrt();
+    let mut roots = vec![(-qb - root) / (2.0 * qa)];
+    if root > 0.0 {
+        roots.push((-qb + root) / (2.0 * qa));
+    }
+    let eps = 1e-12;
+    roots
+        .into_iter()
+        .filter(|u| (-eps..=1.0 + eps).contains(u))
+        .filter_map(|u| {
+            let u = u.clamp(0.0, 1.0);
+            edge.locate(lerp(a, b, u), tol).map(|t| (t, u))
+        })
+        .collect()
+}
+
+/// Cuts `parcel` along `cutter`, returning the pieces counter-clockwise.
+/// Curved edges are cut along their arcs and the pieces keep the bulges.
+fn cut_ring(parcel: &Parcel, cutter: &[Point]) -> Vec<Parcel> {
+    let boundary = parcel.edges();
+    let n = boundary.len();
+    let ring = parcel.to_polygon();
+    if n < 3 || cutter.len() < 2 {
+        let piece = if parcel.signed_area() < 0.0 {
+            parcel.reversed()
+        } else {
+            parcel.clone()
+        };
+        return vec![piece];
+    }
+    let tol = tolerance(&ring);
+    let mut nodes = Nodes {
+        points: Vec::new(),
+        tolerance: tol,
+    };
+    let mut on_boundary = vec![Vec::new(); n];
+    let mut on_cutter = vec![Vec::new(); cutter.len() - 1];
+    for (i, edge) in boundary.iter().enumerate() {
+        for (j, w) in cutter.windows(2).enumerate() {
+            for (t, u) in edge_crossings(edge, w[0], w[1], tol) {
+                let p = edge.point_at(t);
+                on_boundary[i].push(t);
+                on_cutter[j].push((u, p));
+            }
+        }
+    }
+
+    // graph edges as (from, to, bulge), each once
+    let mut edges: Vec<(usize, usize, f64)> = Vec::new();
+    let mut seen = BTreeSet::new();
+    let mut add_edge = |nodes: &mut Nodes, edge: ParcelEdge| {
+        let (a, b) = (nodes.index(edge.start()), nodes.index(edge.end()));
+        let bulge = if a < b { edge.bulge() } else { -edge.bulge() };
+        // adding zero turns -0.0 into 0.0
+        if a != b && seen.insert((a.min(b), a.max(b), (bulge + 0.0).to_bits())) {
+            edges.push((a, b, edge.bulge()));
+        }
+    };
+    for (edge, hits) in boundary.iter().zip(&mut on_boundary) {
+        hits.push(0.0);
+        hits.push(1.0);
+        hits.sort_by(f64::total_cmp);
+        for w in hits.windows(2) {
+            add_edge(&mut nodes, edge.part(w[0], w[1]));
+        }
+    }
+    let mut path = vec![cutter[0]];
+    for (j, hits) in on_cutter.iter_mut().enumerate() {
+        hits.sort_by(|x, y| x.0.total_cmp(&y.0));
+        path.extend(hits.iter().map(|h| h.1));
+        path.push(cutter[j + 1]);
+    }
+    for w in path.windows(2) {
+        let mid = lerp(w[0], w[1], 0.5);
+        if point_in_ring(mid, &ring) && distance_to_ring(mid, &ring) > tol {
+            add_edge(&mut nodes, ParcelEdge::new(w[0], w[1], 0.0));
+        }
+    }
+
+    // drop the loose ends of the cutter
+    let points = nodes.points;
+    let mut incident = vec![Vec::new(); points.len()];
+    for (e, &(a, b, _)) in edges.iter().enumerate() {
+        incident[a].push(e);
+        incident[b].push(e);
+    }
+    let mut alive = vec![true; edges.len()];
+    let degree =
+        |incident: &[usize], alive: &[bool]| incident.iter().filter(|&&e| alive[e]).count();
+    let mut loose: Vec<usize> = (0..points.len())
+        .filter(|&i| degree(&incident[i], &alive) == 1)
+        .collect();
+    while let Some(i) = loose.pop() {
+        let Some(&e) = incident[i].iter().find(|&&e| alive[e]) else {
+            continue;
+        };
+        alive[e] = false;
+        let (a, b, _) = edges[e];
+        let j = if a == i { b } else { a };
+        if degree(&incident[j], &alive) == 1 {
+            loose.push(j);
+        }
+    }
+
+    // half edge 2e runs from a to b and 2e + 1 back
+    let half = |h: usize| {
+        let (a, b, bulge) = edges[h / 2];
+        if h.is_multiple_of(2) {
+            (a, b, ParcelEdge::new(points[a], points[b], bulge))
+        } else {
+            (b, a, ParcelEdge::new(points[b], points[a], -bulge))
+        }
+    };
+    let mut leaving = vec![Vec::new(); points.len()];
+    for h in 0..2 * edges.len() {
+        if alive[h / 2] {
+            leaving[half(h).0].push(h);
+        }
+    }
+
+    // trace faces keeping them on the left, turning left as far as possible
+    let mut visited = vec![false; 2 * edges.len()];
+    let mut pieces = Vec::new();
+    for start in 0..2 * edges.len() {
+        if !alive[start / 2] || visited[start] {
+            continue;
+        }
+        let mut face = Vec::new();
+        let mut h = start;
+        while !visited[h] {
+            visited[h] = true;
+            let (_, to, edge) = half(h);
+            face.push(edge);
+            let din = edge.end_direction();
+            let next = leaving[to]
+                .iter()
+                .copied()
+                .filter(|&g| g != (h ^ 1) || leaving[to].len() == 1)
+                .max_by(|&g1, &g2| {
+                    let turn = |g: usize| {
+                        let d = half(g).2.start_direction();
+                        (din.0 * d.1 - din.1 * d.0).atan2(din.0 * d.0 + din.1 * d.1)
+                    };
+                    turn(g1).total_cmp(&turn(g2))
+                });
+            match next {
+                Some(g) => h = g,
+                None => break,
+            }
+        }
+        let mut piece = Parcel::from_edges(&face);
+        if !piece.has_arcs() {
+            piece.bulges.clear();
+        }
+        if piece.signed_area() > tol * tol {
+            pieces.push(piece);
+        }
+    }
+    pieces
+}
+
+impl Parcel {
+    /// Cuts the parcel along a polyline. Parts of the cutter outside the
+    /// parcel or ending inside it without reaching the boundary are ignored,
+    /// so a cutter that doesn't cross the parcel returns it unchanged.
+    /// Curved edges are cut along their arcs and stay curved in the pieces.
+    pub fn split(&self, cutter: &Polyline) -> Vec<Parcel> {
+        let clockwise = self.signed_area() < 0.0;
+        cut_ring(self, &cutter.vertices)
+            .into_iter()
+            .map(|piece| if clockwise { piece.reversed() } else { piece })
+            .collect()
+    }
+
+    /// Cuts the parcel along the infinite line through `a` and `b`.
+    pub fn split_by_line(&self, a: Point, b: Point) -> ParcelSplit {
+        let len = distance(a, b);
+        let mut split = ParcelSplit {
+            line: (a, b),
+            left: Vec::new(),
+            right: Vec::new(),
+        };
+        if len == 0.0 {
+            split.left.push(self.clone());
+            return split;
+        }
+        let d = ((b.x - a.x) / len, (b.y - a.y) / len);
+        let along = |p: &Point| (p.x - a.x) * d.0 + (p.y - a.y) * d.1;
+        let ring = self.to_polygon();
+        let min = ring.iter().map(along).fold(0.0, f64::min) - 1.0;
+        let max = ring.iter().map(along).fold(0.0, f64::max) + 1.0;
+        let cutter = Polyline::new(vec![
+            Point::new(a.x + d.0 * min, a.y + d.1 * min),
+            Point::new(a.x + d.0 * max, a.y + d.1 * max),
+        ]);
+        for piece in self.split(&cutter) {
+            if cross(a, b, interior_point(&piece.to_polygon())) > 0.0 {
+                split.left.push(piece);
+            } else {
+                split.right.push(piece);
+            }
+        }
+        split
+    }
+
+    /// Slides a line parallel to `a`–`b` across the parcel until the area
+    /// left of it equals `area`. Returns `None` if the area isn't between
+    /// zero and the parcel area.
+    pub fn slide_to_area(&self, a: Point, b: Point, area: f64) -> Option<ParcelSplit> {
+        let len = distance(a, b);
+        if len == 0.0 || area <= 0.0 || area >= self.area() {
+            return None;
+        }
+        let n = (-(b.y - a.y) / len, (b.x - a.x) / len);
+        let offset = |p: &Point| (p.x - a.x) * n.0 + (p.y - a.y) * n.1;
+        let ring = self.to_polygon();
+        let mut low = ring.iter().map(offset).fold(f64::INFINITY, f64::min);
+        let mut high = ring.iter().map(offset).fold(f64::NEG_INFINITY, f64::max);
+        let at = |o: f64| {
+            self.split_by_line(
+                Point::new(a.x + n.0 * o, a.y + n.1 * o),
+                Point::new(b.x + n.0 * o, b.y + n.1 * o),
+            )
+        };
+        // the left area shrinks as the line moves left
+        for _ in 0..100 {
+            let mid = 0.5 * (low + high);
+            if at(mid).left_area() > area {
+                low = mid;
+            } else {
+                high = mid;
+            }
+        }
+        Some(at(0.5 * (low + high)))
+    }
+
+    /// Swings a line about `pivot`, starting through `through`, until the
+    /// area left of it equals `area`. The solution reached with the smallest
+    /// rotation either way is returned.
+    pub fn swing_to_area(&self, pivot: Point, through: Point, area: f64) -> Option<ParcelSplit> {
+        if pivot == through || area <= 0.0 || area >= self.area() {
+            return None;
+        }
+        let start = bearing(pivot, through);
+        let at = |angle: f64| {
+            self.split_by_line(
+                pivot,
+                Point::new(pivot.x + angle.cos(), pivot.y + angle.sin()),
+            )
+        };
+        let error = |angle: f64| at(angle).left_area() - area;
+        let step = std::f64::consts::PI / 180.0;
+        let e0 = error(start);
+        let mut previous = [(start, e0), (start, e0)];
+        for k in 1..=180 {
+            for (i, dir) in [1.0, -1.0].into_iter().enumerate() {
+                let angle = start + dir * step * k as f64;
+                let e = error(angle);
+                let (mut lo, mut e_lo) = previous[i];
+                if e_lo.signum() != e.signum() {
+                    let mut hi = angle;
+                    for _ in 0..60 {
+                        let mid = 0.5 * (lo + hi);
+                        let e_mid = error(mid);
+                        if e_mid.signum() == e_lo.signum() {
+                            (lo, e_lo) = (mid, e_mid);
+                        } else {
+                            hi = mid;
+                        }
+                    }
+                    return Some(at(0.5 * (lo + hi)));
+                }
+                previous[i] = (angle, e);
+            }
+        }
+        None
+    }
+
+    /// Divides the parcel into `count` lots of equal frontage along
+    /// `frontage`, the road side of the parcel, with side lines square to
+    /// it. Lots are returned in order from the start of the frontage.
+    pub fn lots_along_frontage(&self, frontage: &Polyline, count: usize) -> Vec<Parcel> {
+        let step = frontage.length() / count.max(1) as f64;
+        let mut rest = self.clone();
+        let mut lots = Vec::new();
+        let mut leftover = Vec::new();
+        for k in 1..count {
+            let s = step * k as f64;
+            let (Some(p), Some((tx, ty))) = (frontage.point_at(s), frontage.direction_at(s)) else {
+                break;
+            };
+            // left of the side line is the part behind the station
+            let split = rest.split_by_line(p, Point::new(p.x - ty, p.y + tx));
+            if split.left.is_empty() || split.right.is_empty() {
+                continue;
+            }
+            lots.extend(split.left);
+            let mut ahead = split.right;
+            ahead.sort_by(|x, y| y.area().total_cmp(&x.area()));
+            rest = ahead.remove(0);
+            leftover.extend(ahead);
+        }
+        lots.push(rest);
+        lots.extend(leftover);
+        lots
+    }
+
+    /// Right-of-way parcel between offsets `left` and `right` of an
+    /// alignment, sampled every `interval` and at each element boundary.
+    pub fn right_of_way(
+        alignment: &HorizontalAlignment,
+        left: f64,
+        right: f64,
+        interval: f64,
+    ) -> Parcel {
+        let length = alignment.length();
+        let mut stations = alignment.stations();
+        if interval > 0.0 {
+            let count = (length / interval).floor() as usize;
+            stations.extend((1..=count).map(|i| i as f64 * interval));
+        }
+        stations.retain(|s| *s <= length);
+        stations.sort_by(f64::total_cmp);
+        stations.dedup_by(|a, b| (*a - *b).abs() < 1e-9);
+        let mut left_side = Vec::new();
+        let mut right_side = Vec::new();
+        for s in stations {
+            let (Some(p), Some((tx, ty))) = (alignment.point_at(s), alignment.direction_at(s))
+            else {
+                continue;
+            };
+            left_side.push(Point::new(p.x - ty * left, p.y + tx * left));
+            right_side.push(Point::new(p.x + ty * right, p.y - tx * right));
+        }
+        right_side.reverse();
+        left_side.extend(right_side);
+        Parcel::new(left_side)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x0: f64, y0: f64, x1: f64, y1: f64) -> Parcel {
+        Parcel::new(vec![
+            Point::new(x0, y0),
+            Point::new(x1, y0),
+            Point::new(x1, y1),
+            Point::new(x0, y1),
+        ])
+    }
+
+    #[test]
+    fn split_by_line_keeps_sides() {
+        let split =
+            rect(0.0, 0.0, 10.0, 10.0).split_by_line(Point::new(4.0, 0.0), Point::new(4.0, 1.0));
+        assert_eq!(split.left.len(), 1);
+        assert_eq!(split.right.len(), 1);
+        assert!((split.left_area() - 40.0).abs() < 1e-9);
+        assert!((split.right_area() - 60.0).abs() < 1e-9);
+        assert!(split.left[0].boundary.iter().all(|p| p.x <= 4.0 + 1e-9));
+    }
+
+    #[test]
+    fn concave_parcel_splits_into_every_piece() {
+        // U shape open to the north
+        let u = Parcel::new(vec![
+            Point::new(0.0, 0.0),
+            Point::new(30.0, 0.0),
+            Point::new(30.0, 20.0),
+            Point::new(20.0, 20.0),
+            Point::new(20.0, 10.0),
+            Point::new(10.0, 10.0),
+            Point::new(10.0, 20.0),
+            Point::new(0.0, 20.0),
+        ]);
+        let split = u.split_by_line(Point::new(-5.0, 15.0), Point::new(35.0, 15.0));
+        assert_eq!(split.left.len(), 2);
+        assert_eq!(split.right.len(), 1);
+        assert!((split.left_area() - 100.0).abs() < 1e-9);
+        assert!((split.right_area() + split.left_area() - u.area()).abs() < 1e-9);
+
+        // a cutter ending inside the parcel doesn't cut it
+        let stub = Polyline::new(vec![Point::new(5.0, -5.0), Point::new(5.0, 5.0)]);
+        assert_eq!(u.split(&stub).len(), 1);
+        let through = Polyline::new(vec![
+            Point::new(5.0, -5.0),
+            Point::new(5.0, 5.0),
+            Point::new(25.0, 5.0),
+            Point::new(25.0, -5.0),
+        ]);
+        let pieces = u.split(&through);
+        assert_eq!(pieces.len(), 2);
+        let mut areas: Vec<f64> = pieces.iter().map(Parcel::area).collect();
+        areas.sort_by(f64::total_cmp);
+        assert!((areas[0] - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn curved_edges_are_cut_along_the_arc() {
+        use std::f64::consts::PI;
+        // square with a semicircle on the north side
+        let parcel = Parcel::with_bulges(
+            rect(0.0, 0.0, 20.0, 20.0).boundary,
+            vec![0.0, 0.0, 1.0, 0.0],
+        );
+        let split = parcel.split_by_line(Point::new(10.0, -5.0), Point::new(10.0, 40.0));
+        assert!((split.left_area() - (200.0 + 25.0 * PI)).abs() < 1e-9);
+        assert!((split.right_area() - (200.0 + 25.0 * PI)).abs() < 1e-9);
+        let left = &split.left[0];
+        assert!(left.has_arcs());
+        let arc = left.edges().into_iter().find(ParcelEdge::is_arc).unwrap();
+        assert!((arc.radius().unwrap() - 10.0).abs() < 1e-9);
+        assert!(distance(arc.start(), Point::new(10.0, 30.0)) < 1e-9);
+
+        // the line crosses the arc twice, leaving a circular segment above
+        let split = parcel.split_by_line(Point::new(-5.0, 25.0), Point::new(30.0, 25.0));
+        let segment = 100.0 * PI / 3.0 - 5.0 * 75f64.sqrt();
+        assert_eq!(split.left.len(), 1);
+        assert!((split.left_area() - segment).abs() < 1e-9);
+        assert!((split.right_area() + segment - parcel.area()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn slide_and_swing_hit_target_area() {
+        let parcel = rect(0.0, 0.0, 100.0, 50.0);
+        let split = parcel
+            .slide_to_area(Point::new(0.0, 0.0), Point::new(0.0, 1.0), 1500.0)
+            .unwrap();
+        assert!((split.left_area() - 1500.0).abs() < 1e-6);
+        assert!((split.line.0.x - 30.0).abs() < 1e-6);
+        assert!(parcel
+            .slide_to_area(Point::new(0.0, 0.0), Point::new(0.0, 1.0), 6000.0)
+            .is_none());
+
+        let square = rect(0.0, 0.0, 10.0, 10.0);
+        let split = square
+            .swing_to_area(Point::new(0.0, 0.0), Point::new(10.0, 0.0), 25.0)
+            .unwrap();
+        assert!((split.left_area() - 25.0).abs() < 1e-6);
+        let (a, b) = split.line;
+        assert!(((b.y - a.y) / (b.x - a.x) - 2.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn lots_of_equal_frontage() {
+        let parcel = rect(0.0, 0.0, 100.0, 40.0);
+        let road = Polyline::new(vec![Point::new(0.0, 0.0), Point::new(100.0, 0.0)]);
+        let lots = parcel.lots_along_frontage(&road, 4);
+        assert_eq!(lots.len(), 4);
+        for (i, lot) in lots.iter().enumerate() {
+            assert!((lot.area() - 1000.0).abs() < 1e-6);
+            let min_x = lot
+                .boundary
+                .iter()
+                .map(|p| p.x)
+                .fold(f64::INFINITY, f64::min);
+            assert!((min_x - 25.0 * i as f64).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn right_of_way_from_alignment() {
+        let alignment =
+            HorizontalAlignment::new(vec![Point::new(0.0, 0.0), Point::new(100.0, 0.0)]);
+        let row = Parcel::right_of_way(&alignment, 10.0, 5.0, 20.0);
+        assert!((row.area() - 1500.0).abs() < 1e-6);
+        assert!(row.boundary.iter().any(|p| (p.y - 10.0).abs() < 1e-9));
+        assert!(row.boundary.iter().any(|p| (p.y + 5.0).abs() < 1e-9));
+    }
+}
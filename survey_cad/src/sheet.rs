@@ -11,6 +11,8 @@ use std::io::{self, Write};
 use crate::alignment::{Alignment, HorizontalAlignment, VerticalAlignment};
 use crate::corridor::CrossSection;
 use crate::geometry::Point;
+use crate::io::DxfEntity;
+use crate::parcel::Parcel;
 
 /// Scale factors used for plan/profile sheets.
 #[derive(Debug, Clone, Copy)]
@@ -20,6 +22,70 @@ pub struct PlanProfileScales {
     pub profile_v: f64,
 }
 
+/// Pipe drawn in a utility profile. `start` and `end` hold the station and
+/// invert at each end.
+#[derive(Debug, Clone)]
+pub struct ProfilePipe {
+    pub label: String,
+    pub start: Point,
+    pub end: Point,
+    pub inner_diameter: f64,
+    pub outer_diameter: f64,
+}
+
+/// Structure drawn in a utility profile as a box from its floor to its rim.
+#[derive(Debug, Clone)]
+pub struct ProfileStructure {
+    pub label: String,
+    pub station: f64,
+    pub width: f64,
+    pub floor: f64,
+    pub rim: f64,
+}
+
+/// Utility crossing the profile, drawn as its cross-section.
+#[derive(Debug, Clone)]
+pub struct ProfileCrossing {
+    pub label: String,
+    pub station: f64,
+    /// Elevation of the crossing pipe's centre.
+    pub centre: f64,
+    pub diameter: f64,
+}
+
+/// Station/elevation data for a utility profile sheet.
+#[derive(Debug, Clone, Default)]
+pub struct UtilityProfile {
+    pub ground: Vec<Point>,
+    pub pipes: Vec<ProfilePipe>,
+    pub structures: Vec<ProfileStructure>,
+    pub hgl: Vec<Point>,
+    pub crossings: Vec<ProfileCrossing>,
+}
+
+/// Pipe drawn in a utility plan.
+#[derive(Debug, Clone)]
+pub struct PlanPipe {
+    pub label: String,
+    pub start: Point,
+    pub end: Point,
+}
+
+/// Structure drawn in a utility plan as a circle of the given diameter.
+#[derive(Debug, Clone)]
+pub struct PlanStructure {
+    pub label: String,
+    pub position: Point,
+    pub diameter: f64,
+}
+
+/// Plan view data for a utility plan sheet.
+#[derive(Debug, Clone, Default)]
+pub struct UtilityPlan {
+    pub pipes: Vec<PlanPipe>,
+    pub structures: Vec<PlanStructure>,
+}
+
 fn sample_horizontal(halign: &HorizontalAlignment, step: f64) -> Vec<Point> {
     let len = halign.length();
     let mut pts = Vec::new();
@@ -89,6 +155,22 @@ fn write_line(file: &mut File, x1: f64, y1: f64, x2: f64, y2: f64, stroke: &str)
     )
 }
 
+fn write_polygon(file: &mut File, pts: &[Point], fill: &str, stroke: &str) -> io::Result<()> {
+    write!(file, "<polygon points='")?;
+    for p in pts {
+        write!(file, "{:.2},{:.2} ", p.x, p.y)?;
+    }
+    writeln!(file, "' fill='{fill}' stroke='{stroke}' stroke-width='0.5' />")
+}
+
+fn write_ellipse(file: &mut File, c: Point, rx: f64, ry: f64, stroke: &str) -> io::Result<()> {
+    writeln!(
+        file,
+        "<ellipse cx='{:.2}' cy='{:.2}' rx='{rx:.2}' ry='{ry:.2}' fill='none' stroke='{stroke}' stroke-width='0.5' />",
+        c.x, c.y
+    )
+}
+
 fn write_text(file: &mut File, x: f64, y: f64, text: &str) -> io::Result<()> {
     writeln!(
         file,
@@ -448,3 +530,198 @@ pub fn write_cross_section_sheet_svg(
 
     write_svg_footer(&mut f)
 }
+
+/// Writes a parcel plan with segment labels, the area label and line/curve
+/// tables to an SVG file. Drawing units are divided by `scale`; edges shorter
+/// than `min_line_length` and all curves are tabulated.
+pub fn write_parcel_plan_svg(
+    path: &str,
+    parcel: &Parcel,
+    scale: f64,
+    min_line_length: f64,
+) -> io::Result<()> {
+    let edges = parcel.edges();
+    let mut ring: Vec<Point> = Vec::new();
+    for e in &edges {
+        let pts = e.densify(scale);
+        ring.extend_from_slice(&pts[..pts.len() - 1]);
+    }
+    ring.extend(ring.first().copied());
+    let (min_x, min_y, max_x, max_y) = bbox(&ring).unwrap_or((0.0, 0.0, 0.0, 0.0));
+    let to_sheet = |p: Point| Point::new((p.x - min_x) / scale, (max_y - p.y) / scale);
+    let width = (max_x - min_x) / scale;
+    let height = (max_y - min_y) / scale;
+
+    let tables = parcel.tables(min_line_length);
+    let mut rows = tables.line_rows();
+    if !rows.is_empty() && !tables.curves.is_empty() {
+        rows.push(String::new());
+    }
+    rows.extend(tables.curve_rows());
+
+    let mut f = File::create(path)?;
+    write_svg_header(&mut f, width + 280.0, (height + 40.0).max(rows.len() as f64 * 12.0 + 40.0))?;
+    writeln!(f, "<g transform='translate(20,20)'>")?;
+    let scaled: Vec<Point> = ring.iter().map(|p| to_sheet(*p)).collect();
+    write_polyline(&mut f, &scaled, "black")?;
+    for label in parcel
+        .segment_labels(&tables, 1.0)
+        .into_iter()
+        .chain(std::iter::once(parcel.area_label(1.0)))
+    {
+        if let DxfEntity::Text { position, value, .. } = label {
+            let p = to_sheet(position);
+            write_text(&mut f, p.x, p.y, &value)?;
+        }
+    }
+    writeln!(f, "</g>")?;
+
+    writeln!(f, "<g transform='translate({},20)'>", width + 40.0)?;
+    for (i, row) in rows.iter().enumerate() {
+        if !row.is_empty() {
+            write_text(&mut f, 0.0, 12.0 * (i as f64 + 1.0), row)?;
+        }
+    }
+    writeln!(f, "</g>")?;
+
+    write_svg_footer(&mut f)
+}
+
+/// Writes a utility profile sheet with grid lines to an SVG file. Pipes are
+/// drawn as outside-diameter bands with the bore inside, structures as boxes
+/// from floor to rim, and crossings as ellipses at their centre. Stations are
+/// divided by `hscale` and elevations by `vscale`.
+pub fn write_utility_profile_svg(
+    path: &str,
+    profile: &UtilityProfile,
+    hscale: f64,
+    vscale: f64,
+    grid: f64,
+) -> io::Result<()> {
+    let mut extents: Vec<Point> = Vec::new();
+    extents.extend_from_slice(&profile.ground);
+    extents.extend_from_slice(&profile.hgl);
+    for p in &profile.pipes {
+        let wall = (p.outer_diameter - p.inner_diameter) / 2.0;
+        for end in [p.start, p.end] {
+            extents.push(Point::new(end.x, end.y - wall));
+            extents.push(Point::new(end.x, end.y + p.inner_diameter + wall));
+        }
+    }
+    for s in &profile.structures {
+        extents.push(Point::new(s.station - s.width / 2.0, s.floor));
+        extents.push(Point::new(s.station + s.width / 2.0, s.rim));
+    }
+    for c in &profile.crossings {
+        extents.push(Point::new(c.station, c.centre - c.diameter / 2.0));
+        extents.push(Point::new(c.station, c.centre + c.diameter / 2.0));
+    }
+    let (min_x, min_y, max_x, max_y) = bbox(&extents).unwrap_or((0.0, 0.0, 0.0, 0.0));
+    let to_sheet = |x: f64, z: f64| Point::new((x - min_x) / hscale, (max_y - z) / vscale);
+    let width = (max_x - min_x) / hscale;
+    let height = (max_y - min_y) / vscale;
+
+    let mut f = File::create(path)?;
+    write_svg_header(&mut f, width + 40.0, height + 60.0)?;
+    writeln!(f, "<g transform='translate(20,20)'>")?;
+    let mut y = 0.0;
+    while y <= height {
+        write_line(&mut f, 0.0, y, width, y, "#ccc")?;
+        y += grid;
+    }
+    let mut x = 0.0;
+    while x <= width {
+        write_line(&mut f, x, 0.0, x, height, "#ccc")?;
+        x += grid;
+    }
+
+    let ground: Vec<Point> = profile.ground.iter().map(|p| to_sheet(p.x, p.y)).collect();
+    write_polyline(&mut f, &ground, "green")?;
+
+    for s in &profile.structures {
+        let (x0, x1) = (s.station - s.width / 2.0, s.station + s.width / 2.0);
+        let outline = [
+            to_sheet(x0, s.rim),
+            to_sheet(x1, s.rim),
+            to_sheet(x1, s.floor),
+            to_sheet(x0, s.floor),
+        ];
+        write_polygon(&mut f, &outline, "none", "black")?;
+        let top = to_sheet(s.station, s.rim);
+        write_text(&mut f, top.x, top.y - 4.0, &s.label)?;
+    }
+
+    for p in &profile.pipes {
+        let wall = (p.outer_diameter - p.inner_diameter) / 2.0;
+        let band = |bottom: f64, top: f64| {
+            [
+                to_sheet(p.start.x, p.start.y + top),
+                to_sheet(p.end.x, p.end.y + top),
+                to_sheet(p.end.x, p.end.y + bottom),
+                to_sheet(p.start.x, p.start.y + bottom),
+            ]
+        };
+        let outside = band(-wall, p.inner_diameter + wall);
+        write_polygon(&mut f, &outside, "#999", "black")?;
+        write_polygon(&mut f, &band(0.0, p.inner_diameter), "white", "black")?;
+        let mid = to_sheet(
+            (p.start.x + p.end.x) / 2.0,
+            (p.start.y + p.end.y) / 2.0 - wall,
+        );
+        write_text(&mut f, mid.x, mid.y + 10.0, &p.label)?;
+    }
+
+    let hgl: Vec<Point> = profile.hgl.iter().map(|p| to_sheet(p.x, p.y)).collect();
+    write_polyline(&mut f, &hgl, "blue")?;
+
+    for c in &profile.crossings {
+        let centre = to_sheet(c.station, c.centre);
+        let r = c.diameter / 2.0;
+        write_ellipse(&mut f, centre, r / hscale, r / vscale, "red")?;
+        write_text(&mut f, centre.x + r / hscale + 2.0, centre.y, &c.label)?;
+    }
+    writeln!(f, "</g>")?;
+
+    write_svg_footer(&mut f)
+}
+
+/// Writes a utility plan sheet with pipe and structure labels to an SVG
+/// file. Drawing units are divided by `scale`.
+pub fn write_utility_plan_svg(path: &str, plan: &UtilityPlan, scale: f64) -> io::Result<()> {
+    let mut extents: Vec<Point> = Vec::new();
+    for p in &plan.pipes {
+        extents.push(p.start);
+        extents.push(p.end);
+    }
+    for s in &plan.structures {
+        let r = s.diameter / 2.0;
+        extents.push(Point::new(s.position.x - r, s.position.y - r));
+        extents.push(Point::new(s.position.x + r, s.position.y + r));
+    }
+    let (min_x, min_y, max_x, max_y) = bbox(&extents).unwrap_or((0.0, 0.0, 0.0, 0.0));
+    let to_sheet = |p: Point| Point::new((p.x - min_x) / scale, (max_y - p.y) / scale);
+    let width = (max_x - min_x) / scale;
+    let height = (max_y - min_y) / scale;
+
+    let mut f = File::create(path)?;
+    write_svg_header(&mut f, width + 40.0, height + 40.0)?;
+    writeln!(f, "<g transform='translate(20,20)'>")?;
+    for p in &plan.pipes {
+        let (a, b) = (to_sheet(p.start), to_sheet(p.end));
+        writeln!(
+            f,
+            "<line x1='{:.2}' y1='{:.2}' x2='{:.2}' y2='{:.2}' stroke='blue' stroke-width='1' />",
+            a.x, a.y, b.x, b.y
+        )?;
+        write_text(&mut f, (a.x + b.x) / 2.0, (a.y + b.y) / 2.0 - 3.0, &p.label)?;
+    }
+    for s in &plan.structures {
+        let c = to_sheet(s.position);
+        let r = s.diameter / 2.0 / scale;
+        write_ellipse(&mut f, c, r, r, "black")?;
+        write_text(&mut f, c.x + r + 2.0, c.y - r - 2.0, &s.label)?;
+    }
+    writeln!(f, "</g>")?;
+
+    write_svg_footer(&mut f)
+}
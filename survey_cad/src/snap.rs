@@ -1,6 +1,11 @@
-use crate::geometry::{distance, Line, Point};
-#[cfg(test)]
-use crate::geometry::Arc;
+use std::collections::HashMap;
+use std::f64::consts::{FRAC_PI_2, PI, TAU};
+
+use crate::geometry::intersect::line_point_at;
+use crate::geometry::{
+    arc_arc_intersections, distance, line_line_params, segment_arc_intersections,
+    segment_intersection, Arc, Line, Point,
+};
 use crate::io::DxfEntity;
 use crate::surveying::line_intersection;
 
@@ -51,6 +56,7 @@ pub fn snap_to_endpoint(target: Point, entities: &[DxfEntity], tol: f64) -> Opti
                     best = Some(*position);
                 }
             }
+            _ => {}
         }
     }
     best
@@ -173,10 +179,8 @@ pub fn snap_point_with_settings(
 
     for e in entities {
         match e {
-            DxfEntity::Point { point, .. } => {
-                if settings.endpoints {
-                    candidates.push(*point);
-                }
+            DxfEntity::Point { point, .. } if settings.endpoints => {
+                candidates.push(*point);
             }
             DxfEntity::Line { line, .. } => {
                 if settings.endpoints {
@@ -210,11 +214,10 @@ pub fn snap_point_with_settings(
                     candidates.push(arc.midpoint());
                 }
             }
-            DxfEntity::Text { position, .. } => {
-                if settings.endpoints {
-                    candidates.push(*position);
-                }
+            DxfEntity::Text { position, .. } if settings.endpoints => {
+                candidates.push(*position);
             }
+            _ => {}
         }
     }
 
@@ -270,6 +273,555 @@ pub fn snap_point(target: Point, entities: &[DxfEntity], tol: f64) -> Option<Poi
     snap_point_with_settings(target, entities, tol, SnapSettings::default())
 }
 
+/// Kind of point found by [`SnapEngine`], used to pick the glyph drawn at
+/// the cursor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SnapKind {
+    Endpoint,
+    Midpoint,
+    Centre,
+    Node,
+    Insertion,
+    Quadrant,
+    Intersection,
+    ApparentIntersection,
+    Extension,
+    Perpendicular,
+    Tangent,
+    Nearest,
+    PolarTracking,
+    ObjectTracking,
+}
+
+impl SnapKind {
+    /// Candidates with a lower priority win over closer ones with a higher.
+    fn priority(self) -> u8 {
+        match self {
+            Self::Nearest => 3,
+            Self::PolarTracking | Self::ObjectTracking => 2,
+            Self::Extension => 1,
+            _ => 0,
+        }
+    }
+}
+
+/// Snapped point with its kind and the index of the entity it came from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Snap {
+    pub point: Point,
+    pub kind: SnapKind,
+    pub entity: Option<usize>,
+}
+
+/// Object snap modes used by [`SnapEngine::snap`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SnapModes {
+    pub endpoint: bool,
+    pub midpoint: bool,
+    pub centre: bool,
+    pub node: bool,
+    pub insertion: bool,
+    pub quadrant: bool,
+    pub intersection: bool,
+    pub apparent_intersection: bool,
+    pub extension: bool,
+    pub perpendicular: bool,
+    pub tangent: bool,
+    pub nearest: bool,
+    pub polar_tracking: bool,
+    pub object_tracking: bool,
+}
+
+impl Default for SnapModes {
+    fn default() -> Self {
+        Self {
+            endpoint: true,
+            midpoint: true,
+            centre: true,
+            node: true,
+            insertion: true,
+            quadrant: true,
+            intersection: true,
+            apparent_intersection: true,
+            extension: true,
+            perpendicular: true,
+            tangent: true,
+            nearest: true,
+            polar_tracking: true,
+            object_tracking: true,
+        }
+    }
+}
+
+/// Most tracking points kept by [`SnapContext::acquire`].
+const MAX_ACQUIRED: usize = 7;
+
+/// Cursor state for the modes that depend on earlier picks.
+#[derive(Debug, Clone, Default)]
+pub struct SnapContext {
+    /// Last picked point, used for perpendicular, tangent and polar
+    /// tracking.
+    pub from: Option<Point>,
+    /// Points acquired for object snap tracking and extension, oldest first.
+    pub acquired: Vec<Point>,
+    /// Polar tracking increment in radians, 90 degrees when `None`.
+    pub polar_increment: Option<f64>,
+}
+
+impl SnapContext {
+    /// Acquires a tracking point, or releases it when already acquired.
+    pub fn acquire(&mut self, p: Point) {
+        if let Some(i) = self.acquired.iter().position(|q| distance(*q, p) < 1e-9) {
+            self.acquired.remove(i);
+        } else {
+            self.acquired.push(p);
+            if self.acquired.len() > MAX_ACQUIRED {
+                self.acquired.remove(0);
+            }
+        }
+    }
+
+    fn increment(&self) -> f64 {
+        self.polar_increment
+            .filter(|a| *a > 0.0)
+            .unwrap_or(FRAC_PI_2)
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Shape {
+    Node(Point),
+    Insertion(Point),
+    Segment(Line),
+    Arc(Arc),
+}
+
+impl Shape {
+    fn bounds(&self) -> (f64, f64, f64, f64) {
+        match self {
+            Shape::Node(p) | Shape::Insertion(p) => (p.x, p.y, p.x, p.y),
+            Shape::Segment(l) => (
+                l.start.x.min(l.end.x),
+                l.start.y.min(l.end.y),
+                l.start.x.max(l.end.x),
+                l.start.y.max(l.end.y),
+            ),
+            Shape::Arc(a) => (
+                a.center.x - a.radius,
+                a.center.y - a.radius,
+                a.center.x + a.radius,
+                a.center.y + a.radius,
+            ),
+        }
+    }
+}
+
+fn push_vertices(shapes: &mut Vec<Shape>, pts: &[(Point, f64)], closed: bool) {
+    let n = pts.len();
+    let count = if closed && n >= 2 {
+        n
+    } else {
+        n.saturating_sub(1)
+    };
+    for i in 0..count {
+        let ((a, bulge), (b, _)) = (pts[i], pts[(i + 1) % n]);
+        match Arc::from_bulge(a, b, bulge) {
+            Some(arc) => shapes.push(Shape::Arc(arc)),
+            None => shapes.push(Shape::Segment(Line::new(a, b))),
+        }
+    }
+}
+
+fn entity_shapes(e: &DxfEntity) -> Vec<Shape> {
+    let mut shapes = Vec::new();
+    match e {
+        DxfEntity::Point { point, .. } => shapes.push(Shape::Node(*point)),
+        DxfEntity::Point3D { point, .. } => shapes.push(Shape::Node(Point::new(point.x, point.y))),
+        DxfEntity::Line { line, .. } => shapes.push(Shape::Segment(*line)),
+        DxfEntity::Line3D { start, end, .. } => shapes.push(Shape::Segment(Line::new(
+            Point::new(start.x, start.y),
+            Point::new(end.x, end.y),
+        ))),
+        DxfEntity::Polyline { polyline, .. } => {
+            for seg in polyline.vertices.windows(2) {
+                shapes.push(Shape::Segment(Line::new(seg[0], seg[1])));
+            }
+        }
+        DxfEntity::LwPolyline {
+            vertices, closed, ..
+        }
+        | DxfEntity::Polyline3D {
+            vertices, closed, ..
+        } => {
+            let pts: Vec<(Point, f64)> = vertices
+                .iter()
+                .map(|v| (Point::new(v.point.x, v.point.y), v.bulge))
+                .collect();
+            push_vertices(&mut shapes, &pts, *closed);
+        }
+        DxfEntity::Arc { arc, .. } | DxfEntity::Arc3D { arc, .. } => shapes.push(Shape::Arc(*arc)),
+        DxfEntity::Circle { center, radius, .. } => shapes.push(Shape::Arc(Arc::new(
+            Point::new(center.x, center.y),
+            *radius,
+            0.0,
+            TAU,
+        ))),
+        DxfEntity::Text { position, .. } => shapes.push(Shape::Insertion(*position)),
+        DxfEntity::Text3D { position, .. }
+        | DxfEntity::MText { position, .. }
+        | DxfEntity::Insert { position, .. } => {
+            shapes.push(Shape::Insertion(Point::new(position.x, position.y)))
+        }
+        _ => {}
+    }
+    shapes
+}
+
+/// Shapes spanning more grid cells than this are kept in a list checked on
+/// every query instead of being bucketed.
+const MAX_SHAPE_CELLS: i64 = 1024;
+
+/// Object snap engine over a set of entities. Segments and arcs, including
+/// polyline bulges, are bucketed in a uniform grid so each query only looks
+/// at the geometry near the cursor.
+#[derive(Debug, Clone)]
+pub struct SnapEngine {
+    shapes: Vec<(usize, Shape)>,
+    cell: f64,
+    cells: HashMap<(i64, i64), Vec<usize>>,
+    large: Vec<usize>,
+}
+
+impl SnapEngine {
+    /// Indexes `entities` with square cells of `cell` size. Non-positive
+    /// sizes fall back to one unit.
+    pub fn new(entities: &[DxfEntity], cell: f64) -> Self {
+        let cell = if cell > 0.0 { cell } else { 1.0 };
+        let mut engine = Self {
+            shapes: Vec::new(),
+            cell,
+            cells: HashMap::new(),
+            large: Vec::new(),
+        };
+        for (i, e) in entities.iter().enumerate() {
+            for shape in entity_shapes(e) {
+                let id = engine.shapes.len();
+                engine.shapes.push((i, shape));
+                let (x0, y0, x1, y1) = shape.bounds();
+                let (k0, k1) = (engine.key(x0, y0), engine.key(x1, y1));
+                if (k1.0 - k0.0 + 1).saturating_mul(k1.1 - k0.1 + 1) > MAX_SHAPE_CELLS {
+                    engine.large.push(id);
+                    continue;
+                }
+                for x in k0.0..=k1.0 {
+                    for y in k0.1..=k1.1 {
+                        engine.cells.entry((x, y)).or_default().push(id);
+                    }
+                }
+            }
+        }
+        engine
+    }
+
+    fn key(&self, x: f64, y: f64) -> (i64, i64) {
+        (
+            (x / self.cell).floor() as i64,
+            (y / self.cell).floor() as i64,
+        )
+    }
+
+    /// Number of indexed segments, arcs and points.
+    pub fn len(&self) -> usize {
+        self.shapes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shapes.is_empty()
+    }
+
+    /// Shapes whose bounds come within `radius` of `center`.
+    fn query(&self, center: Point, radius: f64) -> Vec<usize> {
+        let (k0, k1) = (
+            self.key(center.x - radius, center.y - radius),
+            self.key(center.x + radius, center.y + radius),
+        );
+        let mut ids: Vec<usize> = (k0.0..=k1.0)
+            .flat_map(|x| (k0.1..=k1.1).map(move |y| (x, y)))
+            .filter_map(|k| self.cells.get(&k))
+            .flatten()
+            .chain(&self.large)
+            .copied()
+            .filter(|&id| {
+                let (x0, y0, x1, y1) = self.shapes[id].1.bounds();
+                center.x >= x0 - radius
+                    && center.x <= x1 + radius
+                    && center.y >= y0 - radius
+                    && center.y <= y1 + radius
+            })
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+
+    /// Segments and arcs with an end at `p`.
+    fn ending_at(&self, p: Point) -> Vec<usize> {
+        self.query(p, 1e-9)
+            .into_iter()
+            .filter(|&id| match self.shapes[id].1 {
+                Shape::Segment(l) => distance(l.start, p) < 1e-9 || distance(l.end, p) < 1e-9,
+                Shape::Arc(a) => {
+                    !a.is_full_circle()
+                        && (distance(a.start_point(), p) < 1e-9
+                            || distance(a.end_point(), p) < 1e-9)
+                }
+                _ => false,
+            })
+            .collect()
+    }
+
+    /// Finds the best snap within `tol` of `target`. Point snaps win over
+    /// extensions, then tracking, then nearest; the closest candidate wins
+    /// within each group.
+    ///
+    /// Apparent intersections are where the extensions of two segments meet,
+    /// taking segments near the cursor or ending at an acquired point.
+    pub fn snap(
+        &self,
+        target: Point,
+        tol: f64,
+        modes: SnapModes,
+        ctx: &SnapContext,
+    ) -> Option<Snap> {
+        let mut best: Option<(u8, f64, Snap)> = None;
+        let mut offer = |priority: u8, kind: SnapKind, point: Point, entity: Option<usize>| {
+            let d = distance(target, point);
+            if d <= tol && best.is_none_or(|(p, bd, _)| (priority, d) < (p, bd)) {
+                best = Some((
+                    priority,
+                    d,
+                    Snap {
+                        point,
+                        kind,
+                        entity,
+                    },
+                ));
+            }
+        };
+        let mut point_snap = |kind: SnapKind, point: Point, entity: usize| {
+            offer(kind.priority(), kind, point, Some(entity))
+        };
+
+        let near = self.query(target, tol);
+        for &id in &near {
+            let (entity, shape) = self.shapes[id];
+            match shape {
+                Shape::Node(p) if modes.node => point_snap(SnapKind::Node, p, entity),
+                Shape::Insertion(p) if modes.insertion => {
+                    point_snap(SnapKind::Insertion, p, entity)
+                }
+                Shape::Segment(l) => {
+                    if modes.endpoint {
+                        point_snap(SnapKind::Endpoint, l.start, entity);
+                        point_snap(SnapKind::Endpoint, l.end, entity);
+                    }
+                    if modes.midpoint {
+                        point_snap(SnapKind::Midpoint, l.midpoint(), entity);
+                    }
+                    if modes.nearest {
+                        point_snap(SnapKind::Nearest, l.nearest_point(target), entity);
+                    }
+                    if let (true, Some(from)) = (modes.perpendicular, ctx.from) {
+                        let normal = Line::new(
+                            from,
+                            Point::new(
+                                from.x - (l.end.y - l.start.y),
+                                from.y + (l.end.x - l.start.x),
+                            ),
+                        );
+                        if let Some(p) = line_line_params(&l, &normal)
+                            .filter(|(t, _)| (0.0..=1.0).contains(t))
+                            .map(|(t, _)| line_point_at(&l, t))
+                        {
+                            point_snap(SnapKind::Perpendicular, p, entity);
+                        }
+                    }
+                }
+                Shape::Arc(a) => {
+                    if !a.is_full_circle() {
+                        if modes.endpoint {
+                            point_snap(SnapKind::Endpoint, a.start_point(), entity);
+                            point_snap(SnapKind::Endpoint, a.end_point(), entity);
+                        }
+                        if modes.midpoint {
+                            point_snap(SnapKind::Midpoint, a.midpoint(), entity);
+                        }
+                    }
+                    if modes.centre {
+                        point_snap(SnapKind::Centre, a.center, entity);
+                    }
+                    if modes.quadrant {
+                        for q in a.quadrant_points() {
+                            point_snap(SnapKind::Quadrant, q, entity);
+                        }
+                    }
+                    if modes.nearest {
+                        point_snap(SnapKind::Nearest, a.nearest_point(target), entity);
+                    }
+                    if let Some(from) = ctx.from {
+                        let base = (from.y - a.center.y).atan2(from.x - a.center.x);
+                        if modes.perpendicular {
+                            for angle in [base, base + PI] {
+                                if a.contains_angle(angle) {
+                                    point_snap(SnapKind::Perpendicular, a.point_at(angle), entity);
+                                }
+                            }
+                        }
+                        let d = distance(from, a.center);
+                        if modes.tangent && d > a.radius {
+                            let phi = (a.radius / d).acos();
+                            for angle in [base + phi, base - phi] {
+                                if a.contains_angle(angle) {
+                                    point_snap(SnapKind::Tangent, a.point_at(angle), entity);
+                                }
+                            }
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        if modes.intersection {
+            for (i, &a) in near.iter().enumerate() {
+                for &b in &near[i + 1..] {
+                    let (entity, sa) = self.shapes[a];
+                    let pts = match (sa, self.shapes[b].1) {
+                        (Shape::Segment(l), Shape::Segment(m)) => {
+                            segment_intersection(&l, &m).into_iter().collect()
+                        }
+                        (Shape::Segment(l), Shape::Arc(c)) | (Shape::Arc(c), Shape::Segment(l)) => {
+                            segment_arc_intersections(&l, &c)
+                        }
+                        (Shape::Arc(c), Shape::Arc(d)) => arc_arc_intersections(&c, &d),
+                        _ => Vec::new(),
+                    };
+                    for p in pts {
+                        point_snap(SnapKind::Intersection, p, entity);
+                    }
+                }
+            }
+        }
+
+        let acquired: Vec<(Point, Vec<usize>)> = ctx
+            .acquired
+            .iter()
+            .map(|p| (*p, self.ending_at(*p)))
+            .collect();
+
+        if modes.apparent_intersection {
+            let mut segments: Vec<usize> = near
+                .iter()
+                .chain(acquired.iter().flat_map(|(_, ids)| ids))
+                .copied()
+                .filter(|&id| matches!(self.shapes[id].1, Shape::Segment(_)))
+                .collect();
+            segments.sort_unstable();
+            segments.dedup();
+            for (i, &a) in segments.iter().enumerate() {
+                for &b in &segments[i + 1..] {
+                    let (entity, Shape::Segment(l)) = self.shapes[a] else {
+                        continue;
+                    };
+                    let Shape::Segment(m) = self.shapes[b].1 else {
+                        continue;
+                    };
+                    if let Some((t, u)) = line_line_params(&l, &m) {
+                        if !((0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u)) {
+                            let p = line_point_at(&l, t);
+                            let kind = SnapKind::ApparentIntersection;
+                            offer(kind.priority(), kind, p, Some(entity));
+                        }
+                    }
+                }
+            }
+        }
+
+        if modes.extension {
+            for (p, ids) in &acquired {
+                for &id in ids {
+                    let (entity, shape) = self.shapes[id];
+                    match shape {
+                        Shape::Segment(l) => {
+                            let other = if distance(l.start, *p) < 1e-9 {
+                                l.end
+                            } else {
+                                l.start
+                            };
+                            let len = distance(other, *p);
+                            let dir = ((p.x - other.x) / len, (p.y - other.y) / len);
+                            let t = (target.x - p.x) * dir.0 + (target.y - p.y) * dir.1;
+                            if t > 0.0 {
+                                let q = Point::new(p.x + dir.0 * t, p.y + dir.1 * t);
+                                offer(1, SnapKind::Extension, q, Some(entity));
+                            }
+                        }
+                        Shape::Arc(a) => {
+                            let angle = (target.y - a.center.y).atan2(target.x - a.center.x);
+                            if !a.contains_angle(angle) {
+                                offer(1, SnapKind::Extension, a.point_at(angle), Some(entity));
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        // Tracking lines through acquired points and polar rays from the
+        // last pick, as (origin, unit direction, is_ray).
+        let inc = ctx.increment();
+        let mut tracks: Vec<(Point, (f64, f64), bool)> = Vec::new();
+        if modes.object_tracking {
+            let steps = ((PI / inc).round() as usize).max(1);
+            for (p, _) in &acquired {
+                for i in 0..steps {
+                    let a = i as f64 * inc;
+                    tracks.push((*p, (a.cos(), a.sin()), false));
+                }
+            }
+        }
+        if let (true, Some(from)) = (modes.polar_tracking, ctx.from) {
+            let a = ((target.y - from.y).atan2(target.x - from.x) / inc).round() * inc;
+            tracks.push((from, (a.cos(), a.sin()), true));
+        }
+        for (i, &(o, d, ray)) in tracks.iter().enumerate() {
+            let t = (target.x - o.x) * d.0 + (target.y - o.y) * d.1;
+            if !ray || t > 0.0 {
+                let kind = if ray {
+                    SnapKind::PolarTracking
+                } else {
+                    SnapKind::ObjectTracking
+                };
+                offer(2, kind, Point::new(o.x + d.0 * t, o.y + d.1 * t), None);
+            }
+            for &(o2, d2, _) in &tracks[i + 1..] {
+                if distance(o, o2) < 1e-9 {
+                    continue;
+                }
+                let l = Line::new(o, Point::new(o.x + d.0, o.y + d.1));
+                let m = Line::new(o2, Point::new(o2.x + d2.0, o2.y + d2.1));
+                if let Some((t, _)) = line_line_params(&l, &m) {
+                    let p = line_point_at(&l, t);
+                    offer(1, SnapKind::ObjectTracking, p, None);
+                }
+            }
+        }
+
+        best.map(|(_, _, snap)| snap)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -323,4 +875,148 @@ mod tests {
         let snapped2 = super::snap_to_nearest(Point::new(1.0, 2.0), &[line], 5.0).unwrap();
         assert!((snapped2.x - 1.0).abs() < 1e-6 && snapped2.y.abs() < 1e-6);
     }
+
+    fn line(x0: f64, y0: f64, x1: f64, y1: f64) -> DxfEntity {
+        DxfEntity::Line {
+            line: Line::new(Point::new(x0, y0), Point::new(x1, y1)),
+            layer: None,
+        }
+    }
+
+    #[test]
+    fn engine_intersections_and_quadrants() {
+        let ents = vec![
+            line(1.2, -2.0, 1.2, 2.0),
+            DxfEntity::Arc {
+                arc: Arc::new(Point::new(1.0, 0.0), 1.0, -FRAC_PI_2, FRAC_PI_2),
+                layer: None,
+            },
+            DxfEntity::Arc {
+                arc: Arc::new(Point::new(2.0, 0.0), 1.0, FRAC_PI_2, 3.0 * FRAC_PI_2),
+                layer: None,
+            },
+        ];
+        let engine = SnapEngine::new(&ents, 1.0);
+        let ctx = SnapContext::default();
+        let modes = SnapModes::default();
+
+        let s = engine
+            .snap(Point::new(1.25, 1.0), 0.3, modes, &ctx)
+            .unwrap();
+        assert_eq!((s.kind, s.entity), (SnapKind::Intersection, Some(0)));
+        assert!(distance(s.point, Point::new(1.2, 0.96f64.sqrt())) < 1e-9);
+
+        let s = engine
+            .snap(Point::new(1.45, 0.9), 0.3, modes, &ctx)
+            .unwrap();
+        assert_eq!((s.kind, s.entity), (SnapKind::Intersection, Some(1)));
+        assert!(distance(s.point, Point::new(1.5, 0.75f64.sqrt())) < 1e-9);
+
+        let s = engine
+            .snap(Point::new(1.05, -1.1), 0.3, modes, &ctx)
+            .unwrap();
+        assert_eq!((s.kind, s.entity), (SnapKind::Endpoint, Some(1)));
+    }
+
+    #[test]
+    fn engine_closed_two_vertex_polyline() {
+        use crate::{geometry::Point3, io::dxf::DxfVertex};
+
+        // Two half-circle bulges: the closing segment is the upper half.
+        let ents = vec![DxfEntity::LwPolyline {
+            vertices: vec![
+                DxfVertex::new(Point3::new(0.0, 0.0, 0.0), 1.0),
+                DxfVertex::new(Point3::new(2.0, 0.0, 0.0), 1.0),
+            ],
+            closed: true,
+            elevation: 0.0,
+            props: Default::default(),
+        }];
+        let engine = SnapEngine::new(&ents, 1.0);
+        let s = engine
+            .snap(Point::new(1.05, 1.05), 0.3, SnapModes::default(), &SnapContext::default())
+            .unwrap();
+        assert_eq!(s.entity, Some(0));
+        assert!(distance(s.point, Point::new(1.0, 1.0)) < 1e-9);
+    }
+
+    #[test]
+    fn engine_perpendicular_tangent_and_tracking() {
+        let circle = DxfEntity::Circle {
+            center: crate::geometry::Point3::new(0.0, 0.0, 0.0),
+            radius: 1.0,
+            props: Default::default(),
+        };
+        let ents = vec![
+            line(5.0, -5.0, 5.0, 7.0),
+            circle,
+            line(10.0, 0.0, 12.0, 0.0),
+        ];
+        let engine = SnapEngine::new(&ents, 2.0);
+        let modes = SnapModes::default();
+        let s = engine
+            .snap(Point::new(0.05, -1.1), 0.2, modes, &SnapContext::default())
+            .unwrap();
+        assert_eq!((s.kind, s.entity), (SnapKind::Quadrant, Some(1)));
+
+        let mut ctx = SnapContext {
+            from: Some(Point::new(2.0, 3.0)),
+            ..Default::default()
+        };
+
+        let s = engine.snap(Point::new(5.1, 2.9), 0.3, modes, &ctx).unwrap();
+        assert_eq!(s.kind, SnapKind::Perpendicular);
+        assert!(distance(s.point, Point::new(5.0, 3.0)) < 1e-9);
+
+        ctx.from = Some(Point::new(0.0, 2.0));
+        let tangent = Point::new(3f64.sqrt() / 2.0, 0.5);
+        let s = engine
+            .snap(Point::new(0.9, 0.55), 0.2, modes, &ctx)
+            .unwrap();
+        assert_eq!(s.kind, SnapKind::Tangent);
+        assert!(distance(s.point, tangent) < 1e-9);
+
+        ctx.from = None;
+        ctx.acquire(Point::new(10.0, 0.0));
+        let s = engine.snap(Point::new(8.0, 0.1), 0.3, modes, &ctx).unwrap();
+        assert_eq!(s.kind, SnapKind::Extension);
+        assert!(distance(s.point, Point::new(8.0, 0.0)) < 1e-9);
+        let s = engine.snap(Point::new(5.1, 0.1), 0.3, modes, &ctx).unwrap();
+        assert_eq!(s.kind, SnapKind::ApparentIntersection);
+        assert!(distance(s.point, Point::new(5.0, 0.0)) < 1e-9);
+        let s = engine
+            .snap(Point::new(10.1, 7.0), 0.3, modes, &ctx)
+            .unwrap();
+        assert_eq!(s.kind, SnapKind::ObjectTracking);
+        assert!(distance(s.point, Point::new(10.0, 7.0)) < 1e-9);
+
+        ctx.from = Some(Point::new(20.0, 20.0));
+        ctx.polar_increment = Some(PI / 4.0);
+        let s = engine
+            .snap(Point::new(23.0, 23.2), 0.3, modes, &ctx)
+            .unwrap();
+        assert_eq!(s.kind, SnapKind::PolarTracking);
+        assert!(distance(s.point, Point::new(23.1, 23.1)) < 1e-9);
+    }
+
+    #[test]
+    fn engine_scales_to_many_entities() {
+        let ents: Vec<DxfEntity> = (0..100_000)
+            .map(|i| {
+                let (x, y) = ((i % 1000) as f64 * 2.0, (i / 1000) as f64 * 2.0);
+                line(x, y, x + 1.0, y)
+            })
+            .collect();
+        let engine = SnapEngine::new(&ents, 2.0);
+        assert_eq!(engine.len(), 100_000);
+        let s = engine
+            .snap(
+                Point::new(1000.9, 100.05),
+                0.2,
+                SnapModes::default(),
+                &SnapContext::default(),
+            )
+            .unwrap();
+        assert_eq!((s.kind, s.entity), (SnapKind::Endpoint, Some(50_500)));
+    }
 }
@@ -2,7 +2,7 @@
 
 use super::cogo::bearing;
 use crate::geometry::Point;
-use nalgebra::{DMatrix, DVector};
+use nalgebra::{DMatrix, DVector, Matrix2};
 use std::collections::{HashMap, HashSet};
 
 /// Supported observation types for a 2D network.
@@ -23,12 +23,34 @@ pub enum Observation {
         value: f64,
         weight: f64,
     },
+    /// Coordinate difference `to - from` in the local frame, such as a GNSS
+    /// baseline rotated to east/north. Weighted by the inverse of the 2x2
+    /// `covariance` so correlated components are handled properly.
+    Baseline {
+        from: usize,
+        to: usize,
+        de: f64,
+        dn: f64,
+        covariance: [[f64; 2]; 2],
+    },
+}
+
+impl Observation {
+    /// Number of rows this observation contributes to the design matrix.
+    fn rows(&self) -> usize {
+        match self {
+            Observation::Baseline { .. } => 2,
+            _ => 1,
+        }
+    }
 }
 
 /// Result of a network adjustment.
 #[derive(Debug)]
 pub struct AdjustResult {
     pub points: Vec<Point>,
+    /// Residuals per design matrix row. Baselines contribute an east and a
+    /// north residual.
     pub residuals: Vec<f64>,
 }
 
@@ -83,12 +105,13 @@ fn build_matrices(
     index_map: &HashMap<usize, usize>,
     count: usize,
 ) -> (DMatrix<f64>, DVector<f64>, DMatrix<f64>) {
-    let num_obs = observations.len();
-    let mut a = DMatrix::<f64>::zeros(num_obs, count);
-    let mut l = DVector::<f64>::zeros(num_obs);
-    let mut w = DMatrix::<f64>::zeros(num_obs, num_obs);
+    let num_rows: usize = observations.iter().map(Observation::rows).sum();
+    let mut a = DMatrix::<f64>::zeros(num_rows, count);
+    let mut l = DVector::<f64>::zeros(num_rows);
+    let mut w = DMatrix::<f64>::zeros(num_rows, num_rows);
 
-    for (row, obs) in observations.iter().enumerate() {
+    let mut row = 0usize;
+    for obs in observations {
         match *obs {
             Observation::Distance {
                 from,
@@ -153,7 +176,47 @@ fn build_matrices(
                     a[(row, idx + 1)] = da_yt;
                 }
             }
+            Observation::Baseline {
+                from,
+                to,
+                de,
+                dn,
+                covariance,
+            } => {
+                let p = points[from];
+                let q = points[to];
+                l[row] = de - (q.x - p.x);
+                l[row + 1] = dn - (q.y - p.y);
+                let cov = Matrix2::new(
+                    covariance[0][0],
+                    covariance[0][1],
+                    covariance[1][0],
+                    covariance[1][1],
+                );
+                let weight = cov.try_inverse().unwrap_or_else(|| {
+                    Matrix2::new(
+                        1.0 / covariance[0][0].max(f64::EPSILON),
+                        0.0,
+                        0.0,
+                        1.0 / covariance[1][1].max(f64::EPSILON),
+                    )
+                });
+                for i in 0..2 {
+                    for j in 0..2 {
+                        w[(row + i, row + j)] = weight[(i, j)];
+                    }
+                }
+                if let Some(&idx) = index_map.get(&from) {
+                    a[(row, idx)] = -1.0;
+                    a[(row + 1, idx + 1)] = -1.0;
+                }
+                if let Some(&idx) = index_map.get(&to) {
+                    a[(row, idx)] = 1.0;
+                    a[(row + 1, idx + 1)] = 1.0;
+                }
+            }
         }
+        row += obs.rows();
     }
 
     (a, l, w)
@@ -287,4 +350,31 @@ mod tests {
         assert!((c.y - 40.0).abs() < 1e-2);
         assert!(res.residuals.iter().all(|v| v.abs() < 1e-6));
     }
+
+    #[test]
+    fn baselines_weighted_by_covariance() {
+        let pts = vec![Point::new(0.0, 0.0), Point::new(0.0, 0.0)];
+        // two baselines to the same point disagreeing by 4 cm in easting;
+        // the second is four times more precise
+        let obs = vec![
+            Observation::Baseline {
+                from: 0,
+                to: 1,
+                de: 100.00,
+                dn: 50.0,
+                covariance: [[4e-4, 0.0], [0.0, 4e-4]],
+            },
+            Observation::Baseline {
+                from: 0,
+                to: 1,
+                de: 100.04,
+                dn: 50.0,
+                covariance: [[1e-4, 0.0], [0.0, 1e-4]],
+            },
+        ];
+        let res = adjust_network(&pts, &[0], &obs);
+        assert_eq!(res.residuals.len(), 4);
+        assert!((res.points[1].x - 100.032).abs() < 1e-6);
+        assert!((res.points[1].y - 50.0).abs() < 1e-6);
+    }
 }
@@ -1,6 +1,7 @@
 //! Basic coordinate geometry (COGO) utilities used in surveying operations.
 
 use crate::geometry::Point;
+use crate::local_grid::GroundCoordinateSystem;
 
 /// Computes the bearing in radians from point `a` to point `b` measured from the
 /// positive X axis.
@@ -17,6 +18,27 @@ pub fn forward(start: Point, bearing: f64, distance: f64) -> Point {
     )
 }
 
+/// Computes a new grid point from a grid `start`, a bearing and a distance
+/// measured on the ground, scaling the distance by the combined scale factor.
+pub fn forward_ground(
+    start: Point,
+    bearing: f64,
+    ground_distance: f64,
+    ground: &GroundCoordinateSystem,
+) -> Point {
+    forward(
+        start,
+        bearing,
+        ground.ground_distance_to_grid(ground_distance),
+    )
+}
+
+/// Returns the bearing and ground distance between two grid points.
+pub fn inverse_ground(a: Point, b: Point, ground: &GroundCoordinateSystem) -> (f64, f64) {
+    let d = crate::geometry::distance(a, b);
+    (bearing(a, b), ground.grid_distance_to_ground(d))
+}
+
 /// Determines the intersection of two infinite lines defined by points
 /// `(p1, p2)` and `(p3, p4)`. Returns `None` if the lines are parallel.
 pub fn line_intersection(p1: Point, p2: Point, p3: Point, p4: Point) -> Option<Point> {
@@ -101,6 +123,16 @@ mod tests {
         assert!((p.y - 2.0).abs() < 1e-6);
     }
 
+    #[test]
+    fn ground_distances_are_scaled() {
+        let g = GroundCoordinateSystem::new(Point::new(0.0, 0.0), 0.9996);
+        let p = forward_ground(Point::new(0.0, 0.0), 0.0, 100.0, &g);
+        assert!((p.x - 99.96).abs() < 1e-9);
+        let (b, d) = inverse_ground(Point::new(0.0, 0.0), p, &g);
+        assert!(b.abs() < 1e-12);
+        assert!((d - 100.0).abs() < 1e-9);
+    }
+
     #[test]
     fn line_intersection_works() {
         let p1 = Point::new(0.0, 0.0);
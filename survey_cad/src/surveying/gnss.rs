@@ -0,0 +1,244 @@
+//! GNSS baseline vectors and geodetic helpers used to bring them into a local
+//! adjustment.
+
+use super::adjustment::Observation;
+use super::observation_db::{ObsType, ObservationData, ObservationRecord};
+use chrono::NaiveDate;
+use nalgebra::Matrix3;
+
+/// GRS80 semi-major axis in metres.
+pub const GRS80_A: f64 = 6_378_137.0;
+/// GRS80 flattening.
+pub const GRS80_F: f64 = 1.0 / 298.257_222_101;
+
+/// Converts earth-centred earth-fixed coordinates to geodetic latitude,
+/// longitude (radians) and ellipsoidal height on the GRS80 ellipsoid.
+pub fn ecef_to_geodetic(x: f64, y: f64, z: f64) -> (f64, f64, f64) {
+    let e2 = GRS80_F * (2.0 - GRS80_F);
+    let lon = y.atan2(x);
+    let p = (x * x + y * y).sqrt();
+    let mut lat = z.atan2(p * (1.0 - e2));
+    let mut h = 0.0;
+    for _ in 0..10 {
+        let sin = lat.sin();
+        let n = GRS80_A / (1.0 - e2 * sin * sin).sqrt();
+        h = p / lat.cos() - n;
+        let next = z.atan2(p * (1.0 - e2 * n / (n + h)));
+        if (next - lat).abs() < 1e-14 {
+            lat = next;
+            break;
+        }
+        lat = next;
+    }
+    (lat, lon, h)
+}
+
+/// Converts geodetic latitude, longitude (radians) and ellipsoidal height on
+/// the GRS80 ellipsoid to earth-centred earth-fixed coordinates.
+pub fn geodetic_to_ecef(lat: f64, lon: f64, h: f64) -> (f64, f64, f64) {
+    let e2 = GRS80_F * (2.0 - GRS80_F);
+    let sin = lat.sin();
+    let n = GRS80_A / (1.0 - e2 * sin * sin).sqrt();
+    (
+        (n + h) * lat.cos() * lon.cos(),
+        (n + h) * lat.cos() * lon.sin(),
+        (n * (1.0 - e2) + h) * sin,
+    )
+}
+
+fn enu_rotation(lat: f64, lon: f64) -> Matrix3<f64> {
+    let (sp, cp) = lat.sin_cos();
+    let (sl, cl) = lon.sin_cos();
+    Matrix3::new(-sl, cl, 0.0, -sp * cl, -sp * sl, cp, cp * cl, cp * sl, sp)
+}
+
+/// GNSS baseline expressed as an ECEF vector with its 3x3 covariance.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BaselineVector {
+    pub from: String,
+    pub to: String,
+    pub dx: f64,
+    pub dy: f64,
+    pub dz: f64,
+    /// Upper triangle of the covariance matrix in m²:
+    /// `[xx, xy, xz, yy, yz, zz]`.
+    pub covariance: [f64; 6],
+}
+
+/// Baseline rotated into a local east/north/up frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EnuBaseline {
+    pub de: f64,
+    pub dn: f64,
+    pub du: f64,
+    pub covariance: [[f64; 3]; 3],
+}
+
+impl BaselineVector {
+    /// Full covariance matrix of the ECEF vector.
+    pub fn covariance_matrix(&self) -> Matrix3<f64> {
+        let c = &self.covariance;
+        Matrix3::new(c[0], c[1], c[2], c[1], c[3], c[4], c[2], c[4], c[5])
+    }
+
+    /// Rotates the vector and covariance into the local frame at the given
+    /// geodetic latitude and longitude (radians).
+    pub fn to_enu(&self, lat: f64, lon: f64) -> EnuBaseline {
+        let r = enu_rotation(lat, lon);
+        let v = r * nalgebra::Vector3::new(self.dx, self.dy, self.dz);
+        let c = r * self.covariance_matrix() * r.transpose();
+        let mut covariance = [[0.0; 3]; 3];
+        for (i, row) in covariance.iter_mut().enumerate() {
+            for (j, val) in row.iter_mut().enumerate() {
+                *val = c[(i, j)];
+            }
+        }
+        EnuBaseline {
+            de: v[0],
+            dn: v[1],
+            du: v[2],
+            covariance,
+        }
+    }
+
+    /// Builds an observation record for storage in the observation database.
+    pub fn to_record(&self, date: NaiveDate) -> ObservationRecord {
+        ObservationRecord {
+            id: None,
+            obs_type: ObsType::Gnss,
+            date,
+            instrument: None,
+            crew: None,
+            control_point: Some(self.from.clone()),
+            data: ObservationData::GnssBaseline {
+                from: self.from.clone(),
+                to: self.to.clone(),
+                dx: self.dx,
+                dy: self.dy,
+                dz: self.dz,
+                covariance: self.covariance,
+            },
+        }
+    }
+
+    /// Extracts a baseline from stored observation data.
+    pub fn from_data(data: &ObservationData) -> Option<Self> {
+        match data {
+            ObservationData::GnssBaseline {
+                from,
+                to,
+                dx,
+                dy,
+                dz,
+                covariance,
+            } => Some(Self {
+                from: from.clone(),
+                to: to.clone(),
+                dx: *dx,
+                dy: *dy,
+                dz: *dz,
+                covariance: *covariance,
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl EnuBaseline {
+    /// Reduces the east and north components to a map grid by rotating
+    /// them through the meridian `convergence` in radians (geodetic azimuth
+    /// = grid azimuth + convergence) and multiplying them by the combined
+    /// `scale` factor. The up component is left as it is.
+    pub fn to_grid(&self, scale: f64, convergence: f64) -> EnuBaseline {
+        let (s, c) = convergence.sin_cos();
+        let m = Matrix3::new(
+            scale * c,
+            -scale * s,
+            0.0,
+            scale * s,
+            scale * c,
+            0.0,
+            0.0,
+            0.0,
+            1.0,
+        );
+        let v = m * nalgebra::Vector3::new(self.de, self.dn, self.du);
+        let cov = m * Matrix3::from_fn(|i, j| self.covariance[i][j]) * m.transpose();
+        EnuBaseline {
+            de: v[0],
+            dn: v[1],
+            du: v[2],
+            covariance: std::array::from_fn(|i| std::array::from_fn(|j| cov[(i, j)])),
+        }
+    }
+
+    /// Builds a horizontal adjustment observation between the point indices
+    /// `from` and `to`, weighted by the inverse of the east/north covariance.
+    pub fn observation(&self, from: usize, to: usize) -> Observation {
+        let c = &self.covariance;
+        Observation::Baseline {
+            from,
+            to,
+            de: self.de,
+            dn: self.dn,
+            covariance: [[c[0][0], c[0][1]], [c[1][0], c[1][1]]],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn geodetic_roundtrip() {
+        let (lat, lon, h) = (53.5f64.to_radians(), (-113.5f64).to_radians(), 650.0);
+        let (x, y, z) = geodetic_to_ecef(lat, lon, h);
+        let (lat2, lon2, h2) = ecef_to_geodetic(x, y, z);
+        assert!((lat - lat2).abs() < 1e-11);
+        assert!((lon - lon2).abs() < 1e-11);
+        assert!((h - h2).abs() < 1e-4);
+    }
+
+    #[test]
+    fn enu_rotation_of_local_offset() {
+        let (lat, lon) = (45f64.to_radians(), (-75f64).to_radians());
+        let (x0, y0, z0) = geodetic_to_ecef(lat, lon, 0.0);
+        // point 100 m north along the meridian
+        let dlat = 100.0 / 6_367_000.0;
+        let (x1, y1, z1) = geodetic_to_ecef(lat + dlat, lon, 0.0);
+        let b = BaselineVector {
+            from: "A".into(),
+            to: "B".into(),
+            dx: x1 - x0,
+            dy: y1 - y0,
+            dz: z1 - z0,
+            covariance: [1e-4, 0.0, 0.0, 1e-4, 0.0, 1e-4],
+        };
+        let enu = b.to_enu(lat, lon);
+        assert!(enu.de.abs() < 1e-3);
+        assert!((enu.dn - 100.0).abs() < 0.5);
+        // isotropic covariance is unchanged by the rotation
+        assert!((enu.covariance[0][0] - 1e-4).abs() < 1e-12);
+        assert!(enu.covariance[0][1].abs() < 1e-12);
+    }
+
+    #[test]
+    fn grid_reduction_turns_and_scales() {
+        let enu = EnuBaseline {
+            de: 0.0,
+            dn: 100.0,
+            du: 2.0,
+            covariance: [[4e-4, 0.0, 0.0], [0.0, 1e-4, 0.0], [0.0, 0.0, 9e-4]],
+        };
+        // east of the central meridian true north lies west of grid north
+        let grid = enu.to_grid(0.9996, 1f64.to_radians());
+        let az = grid.de.atan2(grid.dn).to_degrees();
+        assert!((az + 1.0).abs() < 1e-9);
+        assert!((grid.de.hypot(grid.dn) - 99.96).abs() < 1e-9);
+        assert_eq!(grid.du, 2.0);
+        let trace = grid.covariance[0][0] + grid.covariance[1][1];
+        assert!((trace - 5e-4 * 0.9996f64.powi(2)).abs() < 1e-15);
+        assert!((grid.covariance[2][2] - 9e-4).abs() < 1e-15);
+    }
+}
@@ -3,7 +3,7 @@
 use crate::geometry::{self, Point};
 
 pub mod cogo;
-pub use cogo::{bearing, forward, line_intersection};
+pub use cogo::{bearing, forward, forward_ground, inverse_ground, line_intersection};
 
 pub mod adjustment;
 pub use adjustment::{
@@ -29,8 +29,13 @@ pub use observation_db::{
     ObsType, ObservationDB, ObservationData, ObservationRecord, QueryFilter, TraverseLeg,
 };
 
+pub mod gnss;
+pub use gnss::{ecef_to_geodetic, geodetic_to_ecef, BaselineVector, EnuBaseline};
+
 pub mod stakeout;
-pub use stakeout::{grid_stakeout_points, optimal_stationing, stakeout_position};
+pub use stakeout::{
+    grid_stakeout_points, optimal_stationing, stakeout_position, stakeout_position_grid,
+};
 
 /// Representation of a simple survey station.
 #[derive(Debug)]
@@ -72,12 +77,17 @@ pub fn level_elevation(start_elev: f64, backsight: f64, foresight: f64) -> f64 {
 #[derive(Debug, Default)]
 pub struct Traverse {
     pub points: Vec<Point>,
+    /// Bulge of the leg leaving each point, empty when every leg is straight.
+    pub bulges: Vec<f64>,
 }
 
 impl Traverse {
     /// Creates a new traverse from a list of points.
     pub fn new(points: Vec<Point>) -> Self {
-        Self { points }
+        Self {
+            points,
+            bulges: Vec::new(),
+        }
     }
 
     /// Calculates the area of the traverse using the polygon area algorithm.
@@ -1,6 +1,7 @@
 use rusqlite::{params, Connection};
 use serde::{Deserialize, Serialize};
 use chrono::{NaiveDate, DateTime, Utc};
+use super::gnss::BaselineVector;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub enum ObsType {
@@ -21,12 +22,29 @@ pub struct TraverseLeg {
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(tag = "type", content = "data")]
 pub enum ObservationData {
+    /// Raw total station shot. Angles are in radians with the vertical angle
+    /// measured as a zenith angle.
     TotalStation {
         from: String,
         to: String,
         horiz_angle: f64,
         vert_angle: f64,
         slope_distance: f64,
+        #[serde(default)]
+        instrument_height: f64,
+        #[serde(default)]
+        target_height: f64,
+        #[serde(default)]
+        code: Option<String>,
+    },
+    /// Instrument setup over `station` with an optional backsight. The
+    /// backsight azimuth and circle reading are in radians.
+    Setup {
+        station: String,
+        instrument_height: f64,
+        backsight: Option<String>,
+        backsight_azimuth: Option<f64>,
+        backsight_circle: Option<f64>,
     },
     Gnss {
         point: String,
@@ -34,6 +52,16 @@ pub enum ObservationData {
         easting: f64,
         elevation: f64,
     },
+    /// GNSS baseline as an ECEF vector with the upper triangle of its
+    /// covariance matrix (`[xx, xy, xz, yy, yz, zz]`, m²).
+    GnssBaseline {
+        from: String,
+        to: String,
+        dx: f64,
+        dy: f64,
+        dz: f64,
+        covariance: [f64; 6],
+    },
     LevelRun {
         from: String,
         to: String,
@@ -184,6 +212,32 @@ impl ObservationDB {
         Ok(())
     }
 
+    /// Stores GNSS baseline vectors with their covariances, returning the
+    /// new row ids.
+    pub fn insert_baselines(
+        &self,
+        baselines: &[BaselineVector],
+        date: NaiveDate,
+    ) -> rusqlite::Result<Vec<i64>> {
+        baselines
+            .iter()
+            .map(|b| self.insert(&b.to_record(date)))
+            .collect()
+    }
+
+    /// Returns all stored GNSS baseline vectors.
+    pub fn baselines(&self) -> rusqlite::Result<Vec<BaselineVector>> {
+        let filter = QueryFilter {
+            obs_type: Some(ObsType::Gnss),
+            ..Default::default()
+        };
+        Ok(self
+            .query(&filter)?
+            .iter()
+            .filter_map(|r| BaselineVector::from_data(&r.data))
+            .collect())
+    }
+
     pub fn history(&self, observation_id: i64) -> rusqlite::Result<Vec<ObservationAuditEntry>> {
         let mut stmt = self.conn.prepare(
             "SELECT id, observation_id, timestamp, user, comment, data FROM observation_history WHERE observation_id=?1 ORDER BY id",
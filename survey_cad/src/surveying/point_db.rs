@@ -1,8 +1,10 @@
 use super::field_code::FieldCode;
 use super::Traverse;
 use super::{adjust_network, AdjustResult, Observation};
-use crate::crs::{Crs, CrsTransformer};
-use crate::geometry::{Point, Point3, Polyline};
+use crate::crs::{CoordinateTransform, Crs, CrsTransformer};
+use crate::geoid::{GeoidGrid, HeightConversion};
+use crate::geometry::{Point, Point3, Polyline, Polyline3};
+use crate::local_grid::GroundCoordinateSystem;
 use crate::parcel::Parcel;
 use chrono::{DateTime, Utc};
 
@@ -145,6 +147,66 @@ impl PointDatabase {
         }
     }
 
+    /// Transforms all points like [`transform`](Self::transform) and converts
+    /// their heights with a geoid model. The separation is looked up at each
+    /// point's position in `src`, so `src == dst` converts heights only.
+    ///
+    /// Returns the number of points whose height was converted; points
+    /// outside the grid keep their original height.
+    pub fn transform_with_geoid(
+        &mut self,
+        src: Crs,
+        dst: Crs,
+        geoid: &GeoidGrid,
+        conversion: HeightConversion,
+    ) -> usize {
+        let separations: Vec<Option<f64>> = self
+            .points
+            .iter()
+            .map(|p| {
+                let (lon, lat) = src.geographic_position(p.point.x, p.point.y)?;
+                geoid.undulation(lat, lon)
+            })
+            .collect();
+        self.transform(src, dst);
+        let mut converted = 0;
+        for (p, n) in self.points.iter_mut().zip(separations) {
+            if let Some(n) = n {
+                p.point.z = conversion.apply(p.point.z, n);
+                converted += 1;
+            }
+        }
+        converted
+    }
+
+    /// Applies any coordinate transformation, e.g. a fitted site
+    /// calibration, to all points. Points that fail to transform are left
+    /// unchanged and their indices returned.
+    pub fn transform_with(&mut self, transform: &impl CoordinateTransform) -> Vec<usize> {
+        let mut failed = Vec::new();
+        for (i, p) in self.points.iter_mut().enumerate() {
+            match transform.transform(p.point.x, p.point.y, p.point.z) {
+                Some((x, y, z)) => p.point = Point3::new(x, y, z),
+                None => failed.push(i),
+            }
+        }
+        failed
+    }
+
+    /// Converts all points from grid to ground coordinates.
+    pub fn to_ground(&mut self, ground: &GroundCoordinateSystem) {
+        for p in &mut self.points {
+            p.point = ground.grid_to_ground3(p.point);
+        }
+    }
+
+    /// Converts all points from ground to grid coordinates.
+    pub fn to_grid(&mut self, ground: &GroundCoordinateSystem) {
+        for p in &mut self.points {
+            p.point = ground.ground_to_grid3(p.point);
+        }
+    }
+
     /// Performs a least squares adjustment on the XY coordinates using the
     /// provided fixed point indices and observations.
     pub fn adjust(&mut self, fixed: &[usize], observations: &[Observation]) -> AdjustResult {
@@ -203,21 +265,21 @@ impl PointDatabase {
     }
 
     /// Generates linework by interpreting field codes using begin/continue/end
-    /// semantics. Each figure is returned as a polyline in the order it was
-    /// completed.
-    pub fn generate_figures(&self) -> Vec<Polyline> {
+    /// semantics. Each figure is returned as a polyline through the point
+    /// elevations in the order it was completed.
+    pub fn generate_figures(&self) -> Vec<Polyline3> {
         use super::field_code::CodeAction;
         use std::collections::BTreeMap;
-        let mut active: BTreeMap<String, Vec<Point>> = BTreeMap::new();
+        let mut active: BTreeMap<String, Vec<Point3>> = BTreeMap::new();
         let mut result = Vec::new();
         for p in &self.points {
-            let pt = Point::new(p.point.x, p.point.y);
+            let pt = p.point;
             for fc in p.field_codes() {
                 match fc.action {
                     CodeAction::Begin => {
                         if let Some(pts) = active.remove(&fc.code) {
                             if pts.len() >= 2 {
-                                result.push(Polyline::new(pts));
+                                result.push(Polyline3::new(pts));
                             }
                         }
                         active.insert(fc.code, vec![pt]);
@@ -229,7 +291,7 @@ impl PointDatabase {
                         if let Some(mut pts) = active.remove(&fc.code) {
                             pts.push(pt);
                             if pts.len() >= 2 {
-                                result.push(Polyline::new(pts));
+                                result.push(Polyline3::new(pts));
                             }
                         }
                     }
@@ -239,7 +301,7 @@ impl PointDatabase {
         }
         for (_, pts) in active {
             if pts.len() >= 2 {
-                result.push(Polyline::new(pts));
+                result.push(Polyline3::new(pts));
             }
         }
         result
@@ -429,13 +491,14 @@ mod tests {
         ));
         db.add_point(SurveyPoint::new(
             Some(3),
-            Point3::new(1.0, 1.0, 0.0),
+            Point3::new(1.0, 1.0, 0.4),
             None,
             vec!["ECURB".into()],
         ));
         let figs = db.generate_figures();
         assert_eq!(figs.len(), 1);
         assert_eq!(figs[0].vertices.len(), 3);
+        assert_eq!(figs[0].vertices[2].z, 0.4);
     }
 
     #[test]
@@ -1,5 +1,6 @@
 use crate::alignment::{HorizontalAlignment, HorizontalElement};
 use crate::geometry::{Arc, Point};
+use crate::local_grid::GroundCoordinateSystem;
 
 /// Computes the stakeout position at a given station and offset along a
 /// horizontal alignment. Tangent segments use a perpendicular offset while
@@ -42,6 +43,17 @@ pub fn stakeout_position(
     None
 }
 
+/// Computes the stakeout position for an alignment designed in ground
+/// coordinates and returns it on the grid, ready for a GNSS rover.
+pub fn stakeout_position_grid(
+    alignment: &HorizontalAlignment,
+    station: f64,
+    offset: f64,
+    ground: &GroundCoordinateSystem,
+) -> Option<Point> {
+    stakeout_position(alignment, station, offset).map(|p| ground.ground_to_grid(p))
+}
+
 fn tangent_point(start: Point, end: Point, distance: f64, offset: f64) -> Point {
     let dx = end.x - start.x;
     let dy = end.y - start.y;
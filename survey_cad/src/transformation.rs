@@ -0,0 +1,673 @@
+//! Estimation of coordinate transformations from matched control points.
+//!
+//! Site calibrations fit one of the [`TransformationModel`]s to pairs of
+//! source and target coordinates by least squares. The resulting
+//! [`TransformationFit`] reports the residual of every pair and can be saved
+//! to JSON. Fitted [`Transformation`]s implement [`CoordinateTransform`] so
+//! they can be applied wherever a [`CrsTransformer`](crate::crs::CrsTransformer)
+//! is used.
+
+use crate::crs::CoordinateTransform;
+use crate::geometry::{Point, Point3};
+use nalgebra::{DMatrix, DVector, Matrix3, Vector3};
+use serde::{Deserialize, Serialize};
+
+/// Transformation model to estimate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransformationModel {
+    /// 4 parameter conformal transformation (two shifts, rotation, scale).
+    Helmert2D,
+    /// 6 parameter affine transformation.
+    Affine2D,
+    /// 7 parameter similarity transformation (three shifts, three
+    /// rotations, scale).
+    Helmert3D,
+    /// Horizontal 2D Helmert combined with an inclined vertical plane, as
+    /// used for GNSS site calibrations.
+    Localization,
+}
+
+impl TransformationModel {
+    /// Minimum number of control pairs needed to solve the model.
+    pub fn min_pairs(self) -> usize {
+        match self {
+            Self::Helmert2D | Self::Localization => 2,
+            Self::Affine2D | Self::Helmert3D => 3,
+        }
+    }
+
+    fn has_vertical(self) -> bool {
+        matches!(self, Self::Helmert3D | Self::Localization)
+    }
+}
+
+/// Estimated transformation parameters.
+///
+/// 2D models leave heights unchanged.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "model")]
+pub enum Transformation {
+    /// `x' = a·x − b·y + tx`, `y' = b·x + a·y + ty`.
+    Helmert2D { a: f64, b: f64, tx: f64, ty: f64 },
+    /// `x' = a·x + b·y + tx`, `y' = c·x + d·y + ty`.
+    Affine2D {
+        a: f64,
+        b: f64,
+        c: f64,
+        d: f64,
+        tx: f64,
+        ty: f64,
+    },
+    /// `p' = scale · R · p + translation`.
+    Helmert3D {
+        scale: f64,
+        rotation: [[f64; 3]; 3],
+        translation: [f64; 3],
+    },
+    /// Horizontal Helmert parameters as for [`Transformation::Helmert2D`]
+    /// and `z' = z + dz + slope_e·(x − origin.x) + slope_n·(y − origin.y)`.
+    Localization {
+        a: f64,
+        b: f64,
+        tx: f64,
+        ty: f64,
+        origin: Point,
+        dz: f64,
+        slope_e: f64,
+        slope_n: f64,
+    },
+}
+
+impl Transformation {
+    /// Transforms a 2D point.
+    pub fn apply_point(&self, p: Point) -> Point {
+        let q = self.apply(Point3::new(p.x, p.y, 0.0));
+        Point::new(q.x, q.y)
+    }
+
+    /// Transforms a 3D point.
+    pub fn apply(&self, p: Point3) -> Point3 {
+        match *self {
+            Self::Helmert2D { a, b, tx, ty } => {
+                Point3::new(a * p.x - b * p.y + tx, b * p.x + a * p.y + ty, p.z)
+            }
+            Self::Affine2D { a, b, c, d, tx, ty } => {
+                Point3::new(a * p.x + b * p.y + tx, c * p.x + d * p.y + ty, p.z)
+            }
+            Self::Helmert3D {
+                scale,
+                rotation,
+                translation,
+            } => {
+                let r = Matrix3::from_fn(|i, j| rotation[i][j]);
+                let v = r * Vector3::new(p.x, p.y, p.z) * scale
+                    + Vector3::new(translation[0], translation[1], translation[2]);
+                Point3::new(v.x, v.y, v.z)
+            }
+            Self::Localization {
+                a,
+                b,
+                tx,
+                ty,
+                origin,
+                dz,
+                slope_e,
+                slope_n,
+            } => Point3::new(
+                a * p.x - b * p.y + tx,
+                b * p.x + a * p.y + ty,
+                p.z + dz + slope_e * (p.x - origin.x) + slope_n * (p.y - origin.y),
+            ),
+        }
+    }
+
+    /// Horizontal scale factor of conformal models.
+    pub fn scale(&self) -> Option<f64> {
+        match *self {
+            Self::Helmert2D { a, b, .. } | Self::Localization { a, b, .. } => Some(a.hypot(b)),
+            Self::Helmert3D { scale, .. } => Some(scale),
+            Self::Affine2D { .. } => None,
+        }
+    }
+
+    /// Counter-clockwise rotation in radians of the horizontal conformal
+    /// models.
+    pub fn rotation(&self) -> Option<f64> {
+        match *self {
+            Self::Helmert2D { a, b, .. } | Self::Localization { a, b, .. } => Some(b.atan2(a)),
+            _ => None,
+        }
+    }
+
+    /// Returns the transformation mapping target coordinates back to source.
+    pub fn inverse(&self) -> Option<Self> {
+        match *self {
+            Self::Helmert2D { a, b, tx, ty } => {
+                let (ia, ib, itx, ity) = invert_helmert(a, b, tx, ty)?;
+                Some(Self::Helmert2D {
+                    a: ia,
+                    b: ib,
+                    tx: itx,
+                    ty: ity,
+                })
+            }
+            Self::Affine2D { a, b, c, d, tx, ty } => {
+                let det = a * d - b * c;
+                if det.abs() < f64::EPSILON {
+                    return None;
+                }
+                let (ia, ib, ic, id) = (d / det, -b / det, -c / det, a / det);
+                Some(Self::Affine2D {
+                    a: ia,
+                    b: ib,
+                    c: ic,
+                    d: id,
+                    tx: -(ia * tx + ib * ty),
+                    ty: -(ic * tx + id * ty),
+                })
+            }
+            Self::Helmert3D {
+                scale,
+                rotation,
+                translation,
+            } => {
+                if scale.abs() < f64::EPSILON {
+                    return None;
+                }
+                let rt = Matrix3::from_fn(|i, j| rotation[j][i]);
+                let t =
+                    -(rt * Vector3::new(translation[0], translation[1], translation[2])) / scale;
+                Some(Self::Helmert3D {
+                    scale: 1.0 / scale,
+                    rotation: matrix_to_array(&rt),
+                    translation: [t.x, t.y, t.z],
+                })
+            }
+            Self::Localization {
+                a,
+                b,
+                tx,
+                ty,
+                origin,
+                dz,
+                slope_e,
+                slope_n,
+            } => {
+                let (ia, ib, itx, ity) = invert_helmert(a, b, tx, ty)?;
+                // express the vertical plane in target coordinates
+                Some(Self::Localization {
+                    a: ia,
+                    b: ib,
+                    tx: itx,
+                    ty: ity,
+                    origin: Point::new(
+                        a * origin.x - b * origin.y + tx,
+                        b * origin.x + a * origin.y + ty,
+                    ),
+                    dz: -dz,
+                    slope_e: -(slope_e * ia + slope_n * ib),
+                    slope_n: -(-slope_e * ib + slope_n * ia),
+                })
+            }
+        }
+    }
+}
+
+fn invert_helmert(a: f64, b: f64, tx: f64, ty: f64) -> Option<(f64, f64, f64, f64)> {
+    let d = a * a + b * b;
+    if d < f64::EPSILON {
+        return None;
+    }
+    let (ia, ib) = (a / d, -b / d);
+    Some((ia, ib, -(ia * tx - ib * ty), -(ib * tx + ia * ty)))
+}
+
+fn matrix_to_array(m: &Matrix3<f64>) -> [[f64; 3]; 3] {
+    [
+        [m[(0, 0)], m[(0, 1)], m[(0, 2)]],
+        [m[(1, 0)], m[(1, 1)], m[(1, 2)]],
+        [m[(2, 0)], m[(2, 1)], m[(2, 2)]],
+    ]
+}
+
+impl CoordinateTransform for Transformation {
+    fn transform(&self, x: f64, y: f64, z: f64) -> Option<(f64, f64, f64)> {
+        let p = self.apply(Point3::new(x, y, z));
+        Some((p.x, p.y, p.z))
+    }
+}
+
+/// Matched coordinates of a control point in the source and target systems.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ControlPair {
+    pub name: String,
+    pub source: Point3,
+    pub target: Point3,
+}
+
+impl ControlPair {
+    pub fn new(name: &str, source: Point3, target: Point3) -> Self {
+        Self {
+            name: name.to_string(),
+            source,
+            target,
+        }
+    }
+}
+
+/// Residual of a control pair: target minus transformed source.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PairResidual {
+    pub name: String,
+    pub de: f64,
+    pub dn: f64,
+    pub dz: f64,
+    /// `false` when the pair was rejected as an outlier.
+    pub used: bool,
+}
+
+impl PairResidual {
+    /// Horizontal residual length.
+    pub fn horizontal(&self) -> f64 {
+        self.de.hypot(self.dn)
+    }
+}
+
+/// Result of a transformation estimate.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TransformationFit {
+    pub model: TransformationModel,
+    pub transformation: Transformation,
+    /// One entry per control pair in input order.
+    pub residuals: Vec<PairResidual>,
+    /// RMS of the horizontal residuals of the pairs used.
+    pub horizontal_rms: f64,
+    /// RMS of the vertical residuals of the pairs used.
+    pub vertical_rms: f64,
+}
+
+impl TransformationFit {
+    /// Names of the pairs rejected as outliers.
+    pub fn rejected(&self) -> Vec<&str> {
+        self.residuals
+            .iter()
+            .filter(|r| !r.used)
+            .map(|r| r.name.as_str())
+            .collect()
+    }
+
+    /// Saves the fit to a JSON file.
+    pub fn save(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let json = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, json).map_err(|e| e.into())
+    }
+
+    /// Loads a fit from a JSON file.
+    pub fn load(path: &str) -> std::io::Result<Self> {
+        let data = std::fs::read_to_string(path)?;
+        serde_json::from_str(&data)
+            .map_err(|e| std::io::Error::new(std::io::ErrorKind::InvalidData, e))
+    }
+}
+
+fn centroid(points: impl Iterator<Item = Point3>) -> Point3 {
+    let mut n = 0.0;
+    let mut c = Point3::new(0.0, 0.0, 0.0);
+    for p in points {
+        c.x += p.x;
+        c.y += p.y;
+        c.z += p.z;
+        n += 1.0;
+    }
+    Point3::new(c.x / n, c.y / n, c.z / n)
+}
+
+fn solve(a: DMatrix<f64>, b: DVector<f64>) -> Option<DVector<f64>> {
+    let ata = a.transpose() * &a;
+    let atb = a.transpose() * b;
+    ata.lu().solve(&atb)
+}
+
+/// Returns `(a, b, tx, ty)` of a least squares 2D Helmert fit.
+fn fit_helmert(pairs: &[&ControlPair]) -> Option<(f64, f64, f64, f64)> {
+    let cs = centroid(pairs.iter().map(|p| p.source));
+    let ct = centroid(pairs.iter().map(|p| p.target));
+    let n = pairs.len();
+    let mut a = DMatrix::zeros(2 * n, 2);
+    let mut l = DVector::zeros(2 * n);
+    for (i, p) in pairs.iter().enumerate() {
+        let (x, y) = (p.source.x - cs.x, p.source.y - cs.y);
+        a[(2 * i, 0)] = x;
+        a[(2 * i, 1)] = -y;
+        a[(2 * i + 1, 0)] = y;
+        a[(2 * i + 1, 1)] = x;
+        l[2 * i] = p.target.x - ct.x;
+        l[2 * i + 1] = p.target.y - ct.y;
+    }
+    let x = solve(a, l)?;
+    let (ca, cb) = (x[0], x[1]);
+    Some((
+        ca,
+        cb,
+        ct.x - (ca * cs.x - cb * cs.y),
+        ct.y - (cb * cs.x + ca * cs.y),
+    ))
+}
+
+fn fit_affine(pairs: &[&ControlPair]) -> Option<Transformation> {
+    let cs = centroid(pairs.iter().map(|p| p.source));
+    let ct = centroid(pairs.iter().map(|p| p.target));
+    let n = pairs.len();
+    let mut a = DMatrix::zeros(n, 2);
+    let mut lx = DVector::zeros(n);
+    let mut ly = DVector::zeros(n);
+    for (i, p) in pairs.iter().enumerate() {
+        a[(i, 0)] = p.source.x - cs.x;
+        a[(i, 1)] = p.source.y - cs.y;
+        lx[i] = p.target.x - ct.x;
+        ly[i] = p.target.y - ct.y;
+    }
+    let x = solve(a.clone(), lx)?;
+    let y = solve(a, ly)?;
+    Some(Transformation::Affine2D {
+        a: x[0],
+        b: x[1],
+        c: y[0],
+        d: y[1],
+        tx: ct.x - (x[0] * cs.x + x[1] * cs.y),
+        ty: ct.y - (y[0] * cs.x + y[1] * cs.y),
+    })
+}
+
+/// Closed form similarity fit (Umeyama).
+fn fit_helmert3d(pairs: &[&ControlPair]) -> Option<Transformation> {
+    let cs = centroid(pairs.iter().map(|p| p.source));
+    let ct = centroid(pairs.iter().map(|p| p.target));
+    let mut sigma = Matrix3::zeros();
+    let mut var = 0.0;
+    for p in pairs {
+        let s = Vector3::new(p.source.x - cs.x, p.source.y - cs.y, p.source.z - cs.z);
+        let t = Vector3::new(p.target.x - ct.x, p.target.y - ct.y, p.target.z - ct.z);
+        sigma += t * s.transpose();
+        var += s.norm_squared();
+    }
+    if var < f64::EPSILON {
+        return None;
+    }
+    let svd = sigma.svd(true, true);
+    let (u, v_t) = (svd.u?, svd.v_t?);
+    let mut d = Matrix3::identity();
+    if (u * v_t).determinant() < 0.0 {
+        d[(2, 2)] = -1.0;
+    }
+    let r = u * d * v_t;
+    let scale = (Matrix3::from_diagonal(&svd.singular_values) * d).trace() / var;
+    let t = Vector3::new(ct.x, ct.y, ct.z) - r * Vector3::new(cs.x, cs.y, cs.z) * scale;
+    Some(Transformation::Helmert3D {
+        scale,
+        rotation: matrix_to_array(&r),
+        translation: [t.x, t.y, t.z],
+    })
+}
+
+fn fit_localization(pairs: &[&ControlPair]) -> Option<Transformation> {
+    let (a, b, tx, ty) = fit_helmert(pairs)?;
+    let cs = centroid(pairs.iter().map(|p| p.source));
+    let origin = Point::new(cs.x, cs.y);
+    let dzs: Vec<f64> = pairs.iter().map(|p| p.target.z - p.source.z).collect();
+    let mean = dzs.iter().sum::<f64>() / dzs.len() as f64;
+    let mut plane = (mean, 0.0, 0.0);
+    if pairs.len() >= 3 {
+        let mut m = DMatrix::zeros(pairs.len(), 3);
+        for (i, p) in pairs.iter().enumerate() {
+            m[(i, 0)] = 1.0;
+            m[(i, 1)] = p.source.x - origin.x;
+            m[(i, 2)] = p.source.y - origin.y;
+        }
+        // collinear control only supports a constant shift
+        if let Some(x) =
+            solve(m, DVector::from_vec(dzs)).filter(|x| x.iter().all(|v| v.is_finite()))
+        {
+            plane = (x[0], x[1], x[2]);
+        }
+    }
+    Some(Transformation::Localization {
+        a,
+        b,
+        tx,
+        ty,
+        origin,
+        dz: plane.0,
+        slope_e: plane.1,
+        slope_n: plane.2,
+    })
+}
+
+fn fit(model: TransformationModel, pairs: &[&ControlPair]) -> Option<Transformation> {
+    if pairs.len() < model.min_pairs() {
+        return None;
+    }
+    match model {
+        TransformationModel::Helmert2D => {
+            let (a, b, tx, ty) = fit_helmert(pairs)?;
+            Some(Transformation::Helmert2D { a, b, tx, ty })
+        }
+        TransformationModel::Affine2D => fit_affine(pairs),
+        TransformationModel::Helmert3D => fit_helmert3d(pairs),
+        TransformationModel::Localization => fit_localization(pairs),
+    }
+}
+
+fn build_fit(
+    model: TransformationModel,
+    transformation: Transformation,
+    pairs: &[ControlPair],
+    used: &[bool],
+) -> TransformationFit {
+    let residuals: Vec<PairResidual> = pairs
+        .iter()
+        .zip(used)
+        .map(|(p, &used)| {
+            let q = transformation.apply(p.source);
+            PairResidual {
+                name: p.name.clone(),
+                de: p.target.x - q.x,
+                dn: p.target.y - q.y,
+                dz: p.target.z - q.z,
+                used,
+            }
+        })
+        .collect();
+    let active: Vec<&PairResidual> = residuals.iter().filter(|r| r.used).collect();
+    let n = active.len().max(1) as f64;
+    let horizontal_rms = (active.iter().map(|r| r.horizontal().powi(2)).sum::<f64>() / n).sqrt();
+    let vertical_rms = (active.iter().map(|r| r.dz * r.dz).sum::<f64>() / n).sqrt();
+    TransformationFit {
+        model,
+        transformation,
+        residuals,
+        horizontal_rms,
+        vertical_rms,
+    }
+}
+
+/// Estimates `model` from all control pairs. Returns `None` when there are
+/// too few pairs or their geometry is degenerate.
+pub fn estimate_transformation(
+    model: TransformationModel,
+    pairs: &[ControlPair],
+) -> Option<TransformationFit> {
+    let refs: Vec<&ControlPair> = pairs.iter().collect();
+    let t = fit(model, &refs)?;
+    Some(build_fit(model, t, pairs, &vec![true; pairs.len()]))
+}
+
+/// Estimates `model` and repeatedly rejects the pair with the largest
+/// residual while it exceeds `tolerance` and enough pairs remain to keep
+/// the solution redundant. Vertical residuals are tested for models that
+/// transform heights.
+pub fn estimate_transformation_robust(
+    model: TransformationModel,
+    pairs: &[ControlPair],
+    tolerance: f64,
+) -> Option<TransformationFit> {
+    let mut used = vec![true; pairs.len()];
+    loop {
+        let refs: Vec<&ControlPair> = pairs
+            .iter()
+            .zip(&used)
+            .filter(|(_, &u)| u)
+            .map(|(p, _)| p)
+            .collect();
+        let t = fit(model, &refs)?;
+        let result = build_fit(model, t, pairs, &used);
+        if refs.len() <= model.min_pairs() {
+            return Some(result);
+        }
+        let worst = result
+            .residuals
+            .iter()
+            .enumerate()
+            .filter(|(_, r)| r.used)
+            .map(|(i, r)| {
+                let v = if model.has_vertical() {
+                    r.horizontal().max(r.dz.abs())
+                } else {
+                    r.horizontal()
+                };
+                (i, v)
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        match worst {
+            Some((i, v)) if v > tolerance => used[i] = false,
+            _ => return Some(result),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs(t: &Transformation, src: &[(f64, f64, f64)]) -> Vec<ControlPair> {
+        src.iter()
+            .enumerate()
+            .map(|(i, &(x, y, z))| {
+                let s = Point3::new(x, y, z);
+                ControlPair::new(&format!("CP{}", i + 1), s, t.apply(s))
+            })
+            .collect()
+    }
+
+    const SRC: [(f64, f64, f64); 5] = [
+        (1000.0, 5000.0, 100.0),
+        (1200.0, 5010.0, 102.0),
+        (1180.0, 5250.0, 98.0),
+        (990.0, 5230.0, 101.0),
+        (1100.0, 5120.0, 99.5),
+    ];
+
+    fn close(a: Point3, b: Point3) -> bool {
+        (a.x - b.x).abs() < 1e-6 && (a.y - b.y).abs() < 1e-6 && (a.z - b.z).abs() < 1e-6
+    }
+
+    #[test]
+    fn recovers_helmert2d_and_affine() {
+        let rot = 0.3f64;
+        let t = Transformation::Helmert2D {
+            a: 1.0002 * rot.cos(),
+            b: 1.0002 * rot.sin(),
+            tx: 300_000.0,
+            ty: 5_600_000.0,
+        };
+        let fit =
+            estimate_transformation(TransformationModel::Helmert2D, &pairs(&t, &SRC)).unwrap();
+        assert!(fit.horizontal_rms < 1e-6);
+        assert!((fit.transformation.rotation().unwrap() - rot).abs() < 1e-9);
+        assert!((fit.transformation.scale().unwrap() - 1.0002).abs() < 1e-9);
+
+        let aff = Transformation::Affine2D {
+            a: 1.001,
+            b: 0.02,
+            c: -0.01,
+            d: 0.999,
+            tx: 10.0,
+            ty: -20.0,
+        };
+        let fit =
+            estimate_transformation(TransformationModel::Affine2D, &pairs(&aff, &SRC)).unwrap();
+        let p = Point3::new(1050.0, 5100.0, 0.0);
+        assert!(close(fit.transformation.apply(p), aff.apply(p)));
+        let inv = fit.transformation.inverse().unwrap();
+        assert!(close(inv.apply(aff.apply(p)), p));
+    }
+
+    #[test]
+    fn recovers_helmert3d() {
+        let r = nalgebra::Rotation3::from_euler_angles(0.01, -0.02, 0.5);
+        let t = Transformation::Helmert3D {
+            scale: 0.9999,
+            rotation: matrix_to_array(r.matrix()),
+            translation: [100.0, -50.0, 12.0],
+        };
+        let fit =
+            estimate_transformation(TransformationModel::Helmert3D, &pairs(&t, &SRC)).unwrap();
+        assert!(fit.horizontal_rms < 1e-6 && fit.vertical_rms < 1e-6);
+        let p = Point3::new(1111.0, 5111.0, 90.0);
+        let q = fit.transformation.apply(p);
+        assert!(close(q, t.apply(p)));
+        assert!(close(fit.transformation.inverse().unwrap().apply(q), p));
+    }
+
+    #[test]
+    fn localization_inverse_round_trip() {
+        let t = Transformation::Localization {
+            a: 0.9,
+            b: 0.1,
+            tx: 5.0,
+            ty: 7.0,
+            origin: Point::new(1100.0, 5100.0),
+            dz: -1.5,
+            slope_e: 0.0002,
+            slope_n: -0.0001,
+        };
+        let fit =
+            estimate_transformation(TransformationModel::Localization, &pairs(&t, &SRC)).unwrap();
+        let p = Point3::new(1020.0, 5200.0, 100.0);
+        assert!(close(fit.transformation.apply(p), t.apply(p)));
+        let inv = t.inverse().unwrap();
+        assert!(close(inv.apply(t.apply(p)), p));
+    }
+
+    #[test]
+    fn robust_fit_drops_outlier() {
+        let t = Transformation::Helmert2D {
+            a: 1.0,
+            b: 0.0,
+            tx: 10.0,
+            ty: 20.0,
+        };
+        let mut cps = pairs(&t, &SRC);
+        cps[2].target.x += 0.25;
+        let plain = estimate_transformation(TransformationModel::Helmert2D, &cps).unwrap();
+        assert!(plain.horizontal_rms > 0.05);
+        let fit =
+            estimate_transformation_robust(TransformationModel::Helmert2D, &cps, 0.05).unwrap();
+        assert_eq!(fit.rejected(), vec!["CP3"]);
+        assert!(fit.horizontal_rms < 1e-6);
+        assert!((fit.residuals[2].de - 0.25).abs() < 1e-6);
+    }
+
+    #[test]
+    fn too_few_pairs() {
+        let t = Transformation::Helmert2D {
+            a: 1.0,
+            b: 0.0,
+            tx: 0.0,
+            ty: 0.0,
+        };
+        let cps = pairs(&t, &SRC[..2]);
+        assert!(estimate_transformation(TransformationModel::Affine2D, &cps).is_none());
+        assert!(estimate_transformation(TransformationModel::Helmert2D, &cps).is_some());
+    }
+}
@@ -1,8 +1,13 @@
 use truck_modeling::{self as truck, builder};
-use truck_modeling::base::{Point2 as TPoint2, Point3 as TPoint3, Vector3};
+use truck_modeling::base::{InnerSpace, Point2 as TPoint2, Point3 as TPoint3, Rad, Vector3};
 use truck_geometry::specifieds::Line as TLine;
 
-use crate::geometry::{Line, Point};
+use std::f64::consts::PI;
+
+use crate::geometry::{Line, Point, Point3};
+
+/// Truck solid type produced by the modelling helpers.
+pub type Solid = truck::topology::Solid;
 
 /// Convert our 2D [`Point`] to Truck [`TPoint2`].
 pub fn point_to_truck(p: Point) -> TPoint2 {
@@ -32,6 +37,35 @@ pub fn unit_cube() -> truck::topology::Solid {
     builder::tsweep(&f, Vector3::unit_z())
 }
 
+/// Convert our [`Point3`] to Truck [`TPoint3`].
+pub fn point3_to_truck(p: Point3) -> TPoint3 {
+    TPoint3::new(p.x, p.y, p.z)
+}
+
+/// Creates a solid cylinder of `radius` whose axis runs from `start` to
+/// `end`. Returns `None` when the two points coincide.
+///
+/// Truck splits a closed sweep into three arcs, so the solid has two planar
+/// caps and three side faces.
+pub fn cylinder_between(start: Point3, end: Point3, radius: f64) -> Option<Solid> {
+    let origin = point3_to_truck(start);
+    let axis = point3_to_truck(end) - origin;
+    if axis.magnitude() < f64::EPSILON || radius <= 0.0 {
+        return None;
+    }
+    let dir = axis.normalize();
+    let helper = if dir.z.abs() < 0.9 {
+        Vector3::unit_z()
+    } else {
+        Vector3::unit_x()
+    };
+    let radial = dir.cross(helper).normalize() * radius;
+    let v = builder::vertex(origin + radial);
+    let circle = builder::rsweep(&v, origin, dir, Rad(2.0 * PI));
+    let disk = builder::try_attach_plane(&[circle]).ok()?;
+    Some(builder::tsweep(&disk, axis))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -57,4 +91,41 @@ mod tests {
         write_points_geojson(tmp_json.to_str().unwrap(), &points, None, None).unwrap();
         fs::remove_file(tmp_json).ok();
     }
+
+    #[test]
+    fn cylinder_between_is_closed_and_sized() {
+        use truck_modeling::base::{BoundedCurve, ParametricCurve};
+        use truck_topology::shell::ShellCondition;
+
+        let start = Point3::new(10.0, 20.0, 5.0);
+        let end = Point3::new(13.0, 24.0, 5.0);
+        let solid = cylinder_between(start, end, 0.5).unwrap();
+        assert_eq!(solid.boundaries().len(), 1);
+        assert_eq!(
+            solid.boundaries()[0].shell_condition(),
+            ShellCondition::Closed
+        );
+        let mut sizes: Vec<usize> = solid.face_iter().map(|f| f.boundaries()[0].len()).collect();
+        sizes.sort_unstable();
+        assert_eq!(sizes, vec![3, 3, 4, 4, 4]);
+
+        // every edge lies on the 0.5 m radius and spans the 5 m axis
+        let origin = point3_to_truck(start);
+        let dir = (point3_to_truck(end) - origin).normalize();
+        let (mut lo, mut hi) = (f64::INFINITY, f64::NEG_INFINITY);
+        for edge in solid.edge_iter() {
+            let curve = edge.curve();
+            let (t0, t1) = curve.range_tuple();
+            for i in 0..=16 {
+                let p = curve.subs(t0 + (t1 - t0) * i as f64 / 16.0) - origin;
+                let along = p.dot(dir);
+                assert!(((p - dir * along).magnitude() - 0.5).abs() < 1e-6);
+                lo = lo.min(along);
+                hi = hi.max(along);
+            }
+        }
+        assert!(lo.abs() < 1e-6 && (hi - 5.0).abs() < 1e-6);
+        assert!(cylinder_between(start, start, 0.5).is_none());
+        assert!(cylinder_between(start, end, 0.0).is_none());
+    }
 }
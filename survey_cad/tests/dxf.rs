@@ -0,0 +1,186 @@
+use survey_cad::geometry::{Line, Point, Point3};
+use survey_cad::io::dxf::{
+    read_dxf_document, write_dxf_document, DxfDocument, DxfEntity, DxfLayer, DxfProperties,
+    DxfVertex,
+};
+use survey_cad::io::{read_dxf, write_dxf};
+
+// R12 export in the style of MicroStation: no subclass markers, heavy
+// polylines and colours set on the entities
+const MICROSTATION: &str = "0
+SECTION
+2
+HEADER
+9
+$ACADVER
+1
+AC1009
+0
+ENDSEC
+0
+SECTION
+2
+TABLES
+0
+TABLE
+2
+LAYER
+70
+2
+0
+LAYER
+2
+Default
+70
+0
+62
+7
+6
+CONTINUOUS
+0
+LAYER
+2
+EOP
+70
+0
+62
+3
+6
+CONTINUOUS
+0
+ENDTAB
+0
+ENDSEC
+0
+SECTION
+2
+ENTITIES
+0
+POLYLINE
+8
+EOP
+62
+3
+66
+1
+10
+0.0
+20
+0.0
+30
+250.5
+70
+0
+0
+VERTEX
+8
+EOP
+10
+0.0
+20
+0.0
+30
+250.5
+42
+0.5
+0
+VERTEX
+8
+EOP
+10
+20.0
+20
+0.0
+30
+250.5
+0
+SEQEND
+8
+EOP
+0
+TEXT
+8
+Default
+10
+1.0
+20
+2.0
+30
+0.0
+40
+1.5
+1
+STA 0+000
+50
+90.0
+0
+ENDSEC
+0
+EOF
+";
+
+#[test]
+fn microstation_r12_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("ms.dxf");
+    let path = path.to_str().unwrap();
+    std::fs::write(path, MICROSTATION).unwrap();
+    let doc = read_dxf_document(path).unwrap();
+    assert_eq!(doc.version(), "AC1009");
+    assert_eq!(doc.layer("EOP").unwrap().color, 3);
+    match &doc.entities[0] {
+        DxfEntity::LwPolyline {
+            vertices,
+            elevation,
+            props,
+            ..
+        } => {
+            assert_eq!(*elevation, 250.5);
+            assert_eq!(vertices[0].bulge, 0.5);
+            assert_eq!(props.color, Some(3));
+        }
+        e => panic!("unexpected {e:?}"),
+    }
+    assert!(matches!(&doc.entities[1], DxfEntity::Text3D { value, .. } if value == "STA 0+000"));
+
+    write_dxf_document(path, &doc).unwrap();
+    let text = std::fs::read_to_string(path).unwrap();
+    assert!(!text.contains("AcDbEntity"));
+    assert_eq!(read_dxf_document(path).unwrap(), doc);
+}
+
+#[test]
+fn simple_entities_and_z_values_round_trip() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("mixed.dxf");
+    let path = path.to_str().unwrap();
+    let mut props = DxfProperties::on_layer("BRKL");
+    props.color = Some(1);
+    let entities = vec![
+        DxfEntity::Line {
+            line: Line::new(Point::new(0.0, 0.0), Point::new(1.0, 0.0)),
+            layer: Some("L1".into()),
+        },
+        DxfEntity::Point3D {
+            point: Point3::new(5.0, 6.0, 101.25),
+            props: DxfProperties::on_layer("PTS"),
+        },
+        DxfEntity::Polyline3D {
+            vertices: vec![
+                DxfVertex::new(Point3::new(0.0, 0.0, 10.0), 0.0),
+                DxfVertex::new(Point3::new(5.0, 0.0, 11.0), 0.0),
+            ],
+            closed: false,
+            props,
+        },
+    ];
+    write_dxf(path, &entities).unwrap();
+    assert_eq!(read_dxf(path).unwrap(), entities);
+
+    let mut doc = DxfDocument::from_entities(entities);
+    assert_eq!(doc.layers.len(), 4);
+    doc.set_header("$ACADVER", 1, "AC1032");
+    doc.layers.push(DxfLayer::new("EMPTY", 5));
+    write_dxf_document(path, &doc).unwrap();
+    assert_eq!(read_dxf_document(path).unwrap(), doc);
+}
@@ -0,0 +1,93 @@
+use survey_cad::crs::Crs;
+use survey_cad::dtm::Tin;
+use survey_cad::geoid::{read_geoid_grid, write_gtx, GeoidGrid, HeightConversion, VerticalDatum};
+use survey_cad::geometry::Point3;
+use survey_cad::io::landxml::{read_landxml_surface, write_landxml_surface, LandxmlExtras};
+use survey_cad::io::project::{read_project_json, write_project_json, Project};
+use survey_cad::surveying::{PointDatabase, SurveyPoint};
+
+fn grid() -> GeoidGrid {
+    GeoidGrid {
+        name: "test".into(),
+        south: 53.0,
+        west: -114.0,
+        dlat: 1.0,
+        dlon: 1.0,
+        rows: 2,
+        cols: 2,
+        values: vec![-16.0, -17.0, -18.0, -19.0],
+    }
+}
+
+#[test]
+fn gtx_roundtrip() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("model.gtx");
+    let path = path.to_str().unwrap();
+    let mut g = grid();
+    g.values[3] = f32::NAN;
+    write_gtx(path, &g).unwrap();
+    let read = read_geoid_grid(path).unwrap();
+    assert_eq!(read.name, "model");
+    assert_eq!((read.rows, read.cols), (2, 2));
+    assert_eq!(read.undulation(53.0, -114.0), Some(-16.0));
+    assert!(read.values[3].is_nan());
+    assert!(read_geoid_grid("model.xyz").is_err());
+}
+
+#[test]
+fn point_database_heights_converted() {
+    let mut db = PointDatabase::new();
+    db.add_point(SurveyPoint::new(
+        Some(1),
+        Point3::new(-113.5, 53.5, 650.0),
+        None,
+        Vec::new(),
+    ));
+    db.add_point(SurveyPoint::new(
+        Some(2),
+        Point3::new(-100.0, 53.5, 650.0),
+        None,
+        Vec::new(),
+    ));
+    let n = db.transform_with_geoid(
+        Crs::wgs84(),
+        Crs::wgs84(),
+        &grid(),
+        HeightConversion::EllipsoidalToOrthometric,
+    );
+    assert_eq!(n, 1);
+    assert!((db.points[0].point.z - 667.5).abs() < 1e-9);
+    // outside the grid the height is left alone
+    assert_eq!(db.points[1].point.z, 650.0);
+}
+
+#[test]
+fn vertical_datum_recorded_in_project_and_landxml() {
+    let dir = tempfile::tempdir().unwrap();
+    let datum = VerticalDatum::new("CGVD2013", Some("CGG2013a"));
+
+    let mut project = Project::new();
+    project.vertical_datum = Some(datum.clone());
+    let path = dir.path().join("project.json");
+    write_project_json(path.to_str().unwrap(), &project).unwrap();
+    let read = read_project_json(path.to_str().unwrap()).unwrap();
+    assert_eq!(read.vertical_datum, Some(datum.clone()));
+
+    let tin = Tin {
+        vertices: vec![
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(1.0, 0.0, 0.0),
+            Point3::new(0.0, 1.0, 0.0),
+        ],
+        triangles: vec![[0, 1, 2]],
+    };
+    let extras = LandxmlExtras {
+        vertical_datum: Some(datum.clone()),
+        ..Default::default()
+    };
+    let path = dir.path().join("surface.xml");
+    write_landxml_surface(path.to_str().unwrap(), &tin, Some(&extras)).unwrap();
+    let (_, read) = read_landxml_surface(path.to_str().unwrap()).unwrap();
+    assert_eq!(read.vertical_datum, Some(datum));
+}
@@ -0,0 +1,209 @@
+use chrono::NaiveDate;
+use survey_cad::geometry::Point;
+use survey_cad::io::baseline::{parse_baselines_csv, parse_baselines_gnss, read_baselines};
+use survey_cad::io::rinex::{parse_rinex_nav, parse_rinex_obs};
+use survey_cad::surveying::{
+    adjust_network, ecef_to_geodetic, geodetic_to_ecef, BaselineVector, ObservationDB,
+};
+use tempfile::NamedTempFile;
+
+const RINEX3: &str = r"     3.04           OBSERVATION DATA    M                   RINEX VERSION / TYPE
+CP01                                                        MARKER NAME
+5012345             TRIMBLE R12         6.10                REC # / TYPE / VERS
+12345               TRM115000.00    NONE                    ANT # / TYPE
+ -1510000.1234 -3420000.5678  5110000.9012                  APPROX POSITION XYZ
+        1.5000        0.0000        0.0000                  ANTENNA: DELTA H/E/N
+G   16 C1C L1C D1C S1C C2W L2W D2W S2W C5Q L5Q D5Q S5Q C1L  SYS / # / OBS TYPES
+       L1L D1L S1L                                          SYS / # / OBS TYPES
+R    4 C1C L1C C2P L2P                                      SYS / # / OBS TYPES
+     1.000                                                  INTERVAL
+  2024     1     1     0     0    0.0000000     GPS         TIME OF FIRST OBS
+                                                            END OF HEADER
+> 2024 01 01 00 00  0.0000000  0  2
+G05  20000000.123   105000000.456                          45.000
+R10  21000000.500   112000000.250    21000001.500    87000000.750
+> 2024 01 01 00 00  1.0000000  0  1
+G05  20000001.000
+";
+
+const RINEX2: &str = r"     2.11           OBSERVATION DATA    G (GPS)             RINEX VERSION / TYPE
+BASE                                                        MARKER NAME
+ -1510000.0000 -3420000.0000  5110000.0000                  APPROX POSITION XYZ
+     7    C1    L1    L2    P2    S1    S2    D1            # / TYPES OF OBSERV
+                                                            END OF HEADER
+ 24  1  1  0  0 30.0000000  0 13G01G02G03G04G05G06G07G08G09G10G11G12
+                                G13
+  20000000.000   100000000.000    80000000.000    20000002.000          40.000
+        41.000
+  20000001.000   100000001.000    80000001.000    20000003.000          40.000
+        41.000
+  20000002.000   100000002.000    80000002.000    20000004.000          40.000
+        41.000
+  20000003.000   100000003.000    80000003.000    20000005.000          40.000
+        41.000
+  20000004.000   100000004.000    80000004.000    20000006.000          40.000
+        41.000
+  20000005.000   100000005.000    80000005.000    20000007.000          40.000
+        41.000
+  20000006.000   100000006.000    80000006.000    20000008.000          40.000
+        41.000
+  20000007.000   100000007.000    80000007.000    20000009.000          40.000
+        41.000
+  20000008.000   100000008.000    80000008.000    20000010.000          40.000
+        41.000
+  20000009.000   100000009.000    80000009.000    20000011.000          40.000
+        41.000
+  20000010.000   100000010.000    80000010.000    20000012.000          40.000
+        41.000
+  20000011.000   100000011.000    80000011.000    20000013.000          40.000
+        41.000
+  20000012.000   100000012.000    80000012.000    20000014.000          40.000
+        41.000
+";
+
+const NAV3: &str = r"     3.04           N: GNSS NAV DATA    G: GPS              RINEX VERSION / TYPE
+                                                            END OF HEADER
+G05 2024 01 01 02 00 00 1.500000000000D-05-2.000000000000D-12 0.000000000000D+00
+     1.000000000000D+00 2.000000000000D+00 3.000000000000D+00 4.000000000000D+00
+     5.000000000000D+00 6.000000000000D+00 7.000000000000D+00 8.000000000000D+00
+     9.000000000000D+00 1.000000000000D+01 1.100000000000D+01 1.200000000000D+01
+     1.300000000000D+01 1.400000000000D+01 1.500000000000D+01 1.600000000000D+01
+     1.700000000000D+01 1.800000000000D+01 1.900000000000D+01 2.000000000000D+01
+     2.100000000000D+01 2.200000000000D+01 2.300000000000D+01 2.400000000000D+01
+     2.500000000000D+01 2.600000000000D+01
+";
+
+#[test]
+fn rinex3_header_and_epochs() {
+    let obs = parse_rinex_obs(RINEX3).unwrap();
+    let h = &obs.header;
+    assert!((h.version - 3.04).abs() < 1e-9);
+    assert_eq!(h.marker_name.as_deref(), Some("CP01"));
+    assert_eq!(h.receiver_type.as_deref(), Some("TRIMBLE R12"));
+    assert_eq!(h.antenna_type.as_deref(), Some("TRM115000.00    NONE"));
+    assert_eq!(h.approx_position.unwrap()[2], 5110000.9012);
+    assert_eq!(h.antenna_delta.unwrap()[0], 1.5);
+    assert_eq!(h.types_for('G').len(), 16);
+    assert_eq!(h.types_for('G')[15], "S1L");
+    assert_eq!(h.types_for('R'), ["C1C", "L1C", "C2P", "L2P"]);
+    assert_eq!(h.interval, Some(1.0));
+
+    assert_eq!(obs.epochs.len(), 2);
+    let e = &obs.epochs[0];
+    assert_eq!(
+        e.time,
+        NaiveDate::from_ymd_opt(2024, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+    );
+    assert_eq!(e.satellites[0].satellite, "G05");
+    assert_eq!(e.satellites[0].values[0], Some(20000000.123));
+    assert_eq!(e.satellites[0].values[2], None);
+    assert_eq!(e.satellites[0].values[3], Some(45.0));
+    assert_eq!(e.satellites[1].values.len(), 4);
+    assert_eq!(e.satellites[1].values[3], Some(87000000.75));
+}
+
+#[test]
+fn rinex2_continuation_lines() {
+    let obs = parse_rinex_obs(RINEX2).unwrap();
+    assert_eq!(obs.header.types_for('G').len(), 7);
+    assert_eq!(obs.epochs.len(), 1);
+    let e = &obs.epochs[0];
+    assert_eq!(e.satellites.len(), 13);
+    assert_eq!(e.satellites[12].satellite, "G13");
+    let v = &e.satellites[12].values;
+    assert_eq!(v[0], Some(20000012.0));
+    assert_eq!(v[4], Some(40.0));
+    assert_eq!(v[5], Some(41.0));
+    assert_eq!(v[6], None);
+}
+
+#[test]
+fn rinex2_header_change_event() {
+    let text = RINEX2.replace(
+        "                                                            END OF HEADER\n",
+        "                                                            END OF HEADER\n                            4  2\nSITE MOVED                                                  COMMENT\n G05 G06                                                    COMMENT\n",
+    );
+    let obs = parse_rinex_obs(&text).unwrap();
+    assert_eq!(obs.epochs.len(), 1);
+    assert_eq!(obs.epochs[0].satellites.len(), 13);
+    assert_eq!(obs.epochs[0].satellites[0].satellite, "G01");
+}
+
+#[test]
+fn rinex3_navigation() {
+    let nav = parse_rinex_nav(NAV3).unwrap();
+    assert_eq!(nav.ephemerides.len(), 1);
+    let eph = &nav.ephemerides[0];
+    assert_eq!(eph.satellite, "G05");
+    assert!((eph.clock_bias - 1.5e-5).abs() < 1e-18);
+    // blank spare fields on the last line keep their slots
+    assert_eq!(eph.orbit.len(), 28);
+    assert_eq!(eph.orbit[25], 26.0);
+    assert_eq!(eph.orbit[27], 0.0);
+    assert!(parse_rinex_obs(NAV3).is_err());
+}
+
+#[test]
+fn baseline_formats() {
+    let g = "G1 BASE-P1 100.000 -50.000 25.000\nG2 4.0E-6 9.0E-6 1.6E-5\nG3 1.0E-7 2.0E-7 3.0E-7\n";
+    let a = parse_baselines_gnss(g).unwrap();
+    assert_eq!(a.len(), 1);
+    assert_eq!(a[0].from, "BASE");
+    assert_eq!(
+        a[0].covariance,
+        [4.0e-6, 1.0e-7, 2.0e-7, 9.0e-6, 3.0e-7, 1.6e-5]
+    );
+
+    let csv = "From,To,dX,dY,dZ,sdX,sdY,sdZ\nBASE,P1,100.0,-50.0,25.0,0.002,0.003,0.004\n";
+    let b = parse_baselines_csv(csv).unwrap();
+    assert_eq!(b[0].to, "P1");
+    assert!((b[0].covariance[3] - 9.0e-6).abs() < 1e-15);
+    assert!(parse_baselines_csv("from,to,dx\nA,B,1\n").is_err());
+}
+
+#[test]
+fn baselines_stored_and_adjusted() {
+    // control at BASE with two independent baselines to P1
+    let (lat, lon) = (53.5f64.to_radians(), (-113.5f64).to_radians());
+    let base = geodetic_to_ecef(lat, lon, 700.0);
+    let r = 6_378_137.0;
+    let p1 = geodetic_to_ecef(lat + 100.0 / r, lon + 200.0 / (r * lat.cos()), 700.0);
+    let file = NamedTempFile::new().unwrap();
+    let text = format!(
+        "G1 BASE-P1 {:.4} {:.4} {:.4}\nG2 1.0E-4 1.0E-4 1.0E-4\nG1 BASE-P1 {:.4} {:.4} {:.4}\nG2 2.5E-5 2.5E-5 2.5E-5\n",
+        p1.0 - base.0 + 0.05,
+        p1.1 - base.1,
+        p1.2 - base.2,
+        p1.0 - base.0,
+        p1.1 - base.1,
+        p1.2 - base.2,
+    );
+    std::fs::write(file.path(), text).unwrap();
+    let baselines = read_baselines(file.path().to_str().unwrap()).unwrap();
+    assert_eq!(baselines.len(), 2);
+
+    let db_file = NamedTempFile::new().unwrap();
+    let db = ObservationDB::open(db_file.path().to_str().unwrap()).unwrap();
+    db.insert_baselines(&baselines, NaiveDate::from_ymd_opt(2024, 6, 1).unwrap())
+        .unwrap();
+    let stored: Vec<BaselineVector> = db.baselines().unwrap();
+    assert_eq!(stored, baselines);
+
+    let (olat, olon, _) = ecef_to_geodetic(base.0, base.1, base.2);
+    let obs: Vec<_> = stored
+        .iter()
+        .map(|b| b.to_enu(olat, olon).observation(0, 1))
+        .collect();
+    let pts = vec![Point::new(0.0, 0.0), Point::new(0.0, 0.0)];
+    let res = adjust_network(&pts, &[0], &obs);
+    let exact = baselines[1].to_enu(olat, olon);
+    let biased = baselines[0].to_enu(olat, olon);
+    // weights 1:4 pull the solution 80% towards the precise baseline
+    let expected_e = exact.de + 0.2 * (biased.de - exact.de);
+    assert!((res.points[1].x - expected_e).abs() < 1e-6);
+    assert!((res.points[1].x - 200.0).abs() < 0.5);
+    assert!((res.points[1].y - 100.0).abs() < 0.5);
+}
@@ -0,0 +1,81 @@
+use survey_cad::alignment::HorizontalAlignment;
+use survey_cad::crs::Crs;
+use survey_cad::geometry::{Point, Point3};
+use survey_cad::io::project::{read_project_json, write_project_json, Project};
+use survey_cad::io::{read_points_csv_ground, write_points_csv_ground};
+use survey_cad::surveying::{stakeout_position_grid, PointDatabase, SurveyPoint};
+use survey_cad::GroundCoordinateSystem;
+
+fn ground() -> GroundCoordinateSystem {
+    let crs = Crs::from_epsg(26912);
+    let base = Point::new(335_000.0, 5_930_000.0);
+    GroundCoordinateSystem::from_crs(&crs, base, 650.0).unwrap()
+}
+
+#[test]
+fn csf_from_crs() {
+    let g = ground();
+    assert_eq!(g.grid_epsg, Some(26912));
+    // UTM 12 near Edmonton is well below unity once elevation is applied
+    assert!(g.combined_scale_factor > 0.9995 && g.combined_scale_factor < 1.0);
+}
+
+#[test]
+fn csv_import_export_through_ground() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("grid.csv");
+    let path = path.to_str().unwrap();
+    std::fs::write(path, "335000,5930000\n336000,5930000\n").unwrap();
+    let g = ground();
+    let pts = read_points_csv_ground(path, None, None, &g).unwrap();
+    assert_eq!(pts[0], Point::new(335_000.0, 5_930_000.0));
+    let ground_dist = pts[1].x - pts[0].x;
+    assert!((ground_dist - 1000.0 / g.combined_scale_factor).abs() < 1e-6);
+
+    write_points_csv_ground(path, &pts, None, None, &g).unwrap();
+    let text = std::fs::read_to_string(path).unwrap();
+    let x: f64 = text
+        .lines()
+        .nth(1)
+        .unwrap()
+        .split(',')
+        .next()
+        .unwrap()
+        .parse()
+        .unwrap();
+    assert!((x - 336_000.0).abs() < 1e-6);
+}
+
+#[test]
+fn point_database_and_stakeout() {
+    let g = ground();
+    let mut db = PointDatabase::new();
+    db.add_point(SurveyPoint::new(
+        Some(1),
+        Point3::new(335_100.0, 5_930_100.0, 640.0),
+        None,
+        Vec::new(),
+    ));
+    db.to_ground(&g);
+    db.to_grid(&g);
+    assert!((db.points[0].point.x - 335_100.0).abs() < 1e-6);
+    assert_eq!(db.points[0].point.z, 640.0);
+
+    let al = HorizontalAlignment::new(vec![
+        Point::new(335_000.0, 5_930_000.0),
+        Point::new(335_000.0, 5_931_000.0),
+    ]);
+    let p = stakeout_position_grid(&al, 500.0, 0.0, &g).unwrap();
+    assert!((p.y - (5_930_000.0 + 500.0 * g.combined_scale_factor)).abs() < 1e-6);
+}
+
+#[test]
+fn ground_system_saved_with_project() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("p.json");
+    let mut project = Project::new();
+    project.ground = Some(ground());
+    write_project_json(path.to_str().unwrap(), &project).unwrap();
+    let read = read_project_json(path.to_str().unwrap()).unwrap();
+    assert_eq!(read.ground, project.ground);
+}
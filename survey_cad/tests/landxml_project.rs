@@ -0,0 +1,155 @@
+use chrono::NaiveDate;
+use survey_cad::alignment::{Alignment, HorizontalAlignment, VerticalAlignment};
+use survey_cad::dtm::Tin;
+use survey_cad::geometry::{Line, Point, Point3};
+use survey_cad::io::landxml::{
+    self, CgPoint, CgPointGroup, NetworkPipe, PipeNetwork, PipeStructure,
+};
+use survey_cad::io::project::Project;
+use survey_cad::parcel::Parcel;
+use survey_cad::surveying::{
+    ObsType, ObservationDB, ObservationData, ObservationRecord, QueryFilter,
+};
+
+fn sample_project() -> Project {
+    let mut project = Project::new();
+    project.crs_epsg = 2193;
+    project.points.push(Point::new(1.0, 2.0));
+    let mut cp = CgPoint::new("CP1", Point3::new(1000.0, 5000.0, 25.0));
+    cp.code = Some("CTRL".into());
+    project.cg_points.push(cp);
+    project
+        .cg_points
+        .push(CgPoint::new("CP2", Point3::new(1040.0, 5000.0, 25.5)));
+    project.point_groups.push(CgPointGroup {
+        name: "Control".into(),
+        points: vec!["CP1".into(), "CP2".into()],
+    });
+    project
+        .lines
+        .push(Line::new(Point::new(0.0, 0.0), Point::new(10.0, 0.0)));
+    let mut lot = Parcel::with_bulges(
+        vec![
+            Point::new(0.0, 0.0),
+            Point::new(30.0, 0.0),
+            Point::new(30.0, 20.0),
+            Point::new(0.0, 20.0),
+        ],
+        vec![0.0, 0.0, 0.4, 0.0],
+    );
+    lot.name = Some("Lot 1".into());
+    project.parcels.push(lot);
+    project.surfaces.push(Tin::from_points(vec![
+        Point3::new(0.0, 0.0, 10.0),
+        Point3::new(10.0, 0.0, 11.0),
+        Point3::new(0.0, 10.0, 12.0),
+    ]));
+    project.surface_descriptions.push("existing ground".into());
+    project.alignments.push(Alignment::new(
+        HorizontalAlignment::new(vec![Point::new(0.0, 0.0), Point::new(100.0, 0.0)]),
+        VerticalAlignment::new(vec![(0.0, 10.0), (50.0, 11.0), (100.0, 10.5)]),
+    ));
+    project.pipe_networks.push(PipeNetwork {
+        name: "Storm".into(),
+        kind: "storm".into(),
+        structures: vec![
+            PipeStructure {
+                name: "MH1".into(),
+                center: Point3::new(0.0, 0.0, 20.0),
+                ..Default::default()
+            },
+            PipeStructure {
+                name: "MH2".into(),
+                center: Point3::new(50.0, 0.0, 19.0),
+                ..Default::default()
+            },
+        ],
+        pipes: vec![NetworkPipe {
+            name: "P1".into(),
+            start: "MH1".into(),
+            end: "MH2".into(),
+            diameter: 0.3,
+            start_invert: Some(18.0),
+            end_invert: Some(17.5),
+            ..Default::default()
+        }],
+    });
+    project.observations.push(ObservationRecord {
+        id: None,
+        obs_type: ObsType::TotalStation,
+        date: NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(),
+        instrument: None,
+        crew: None,
+        control_point: None,
+        data: ObservationData::TotalStation {
+            from: "CP1".into(),
+            to: "CP2".into(),
+            horiz_angle: 1.0,
+            vert_angle: 1.5,
+            slope_distance: 40.0,
+            instrument_height: 1.6,
+            target_height: 1.8,
+            code: None,
+        },
+    });
+    project
+}
+
+#[test]
+fn project_round_trip_is_valid_landxml() {
+    let project = sample_project();
+    let xml = landxml::project_to_landxml(&project);
+    assert_eq!(landxml::validate_landxml(&xml), Vec::<String>::new());
+
+    let read = landxml::project_from_landxml(&xml).unwrap();
+    assert_eq!(read.crs_epsg, 2193);
+    assert_eq!(read.points, project.points);
+    assert_eq!(read.cg_points, project.cg_points);
+    assert_eq!(read.point_groups, project.point_groups);
+    assert_eq!(read.parcels.len(), 1);
+    assert!((read.parcels[0].area() - project.parcels[0].area()).abs() < 1e-6);
+    assert_eq!(read.plan_features.len(), 1);
+    assert_eq!(read.plan_features[0].vertices.len(), 2);
+    assert_eq!(read.surfaces[0].vertices, project.surfaces[0].vertices);
+    assert_eq!(read.surfaces[0].triangles, project.surfaces[0].triangles);
+    assert_eq!(
+        read.surface_descriptions,
+        vec!["existing ground".to_string()]
+    );
+    let al = &read.alignments[0];
+    assert!((al.horizontal.length() - 100.0).abs() < 1e-9);
+    assert_eq!(al.vertical.elements.len(), 2);
+    assert!((al.vertical.elevation_at(50.0).unwrap() - 11.0).abs() < 1e-9);
+    assert_eq!(
+        read.pipe_networks,
+        project
+            .pipe_networks
+            .iter()
+            .cloned()
+            .map(|mut n| {
+                for s in &mut n.structures {
+                    s.rim = Some(s.center.z);
+                }
+                n
+            })
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(read.observations.len(), 2);
+}
+
+#[test]
+fn import_survey_into_observation_db() {
+    let dir = tempfile::tempdir().unwrap();
+    let xml_path = dir.path().join("survey.xml");
+    let xml_path = xml_path.to_str().unwrap();
+    landxml::write_landxml_survey(xml_path, &sample_project().observations).unwrap();
+
+    let db = ObservationDB::open(dir.path().join("obs.db").to_str().unwrap()).unwrap();
+    assert_eq!(landxml::import_landxml_survey(xml_path, &db).unwrap(), 2);
+    let stored = db.query(&QueryFilter::default()).unwrap();
+    assert!(matches!(stored[0].data, ObservationData::Setup { .. }));
+    assert!(matches!(
+        stored[1].data,
+        ObservationData::TotalStation { slope_distance, .. } if slope_distance == 40.0
+    ));
+}
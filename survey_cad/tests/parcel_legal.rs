@@ -0,0 +1,35 @@
+use assert_fs::prelude::*;
+use predicates::prelude::*;
+use survey_cad::{geometry::Point, parcel::Parcel, sheet::write_parcel_plan_svg};
+
+fn cul_de_sac_lot() -> Parcel {
+    Parcel::with_bulges(
+        vec![
+            Point::new(0.0, 0.0),
+            Point::new(30.0, 0.0),
+            Point::new(30.0, 25.0),
+            Point::new(3.0, 25.0),
+            Point::new(0.0, 22.0),
+        ],
+        vec![0.0, 0.0, -0.2, 0.0, 0.0],
+    )
+}
+
+#[test]
+fn legal_description_lists_every_call() {
+    let desc = cul_de_sac_lot().legal_description("Lot 7");
+    assert_eq!(desc.matches("thence").count(), 5);
+    assert!(desc.contains("curve to the right"));
+    assert!(desc.contains("m\u{00B2}"));
+}
+
+#[test]
+fn write_parcel_svg() {
+    let dir = assert_fs::TempDir::new().unwrap();
+    let file = dir.child("lot.svg");
+    write_parcel_plan_svg(file.path().to_str().unwrap(), &cul_de_sac_lot(), 0.1, 5.0).unwrap();
+    file.assert(predicate::str::contains("C1"));
+    file.assert(predicate::str::contains("L1"));
+    file.assert(predicate::str::contains("CURVE  RADIUS"));
+    dir.close().unwrap();
+}
@@ -80,3 +80,26 @@ fn polygon_record_roundtrip() {
     assert_eq!(records[0].geom.len(), rec.geom.len());
     assert_eq!(records[0].attrs, rec.attrs);
 }
+
+#[cfg(feature = "shapefile")]
+#[test]
+fn parcel_roundtrip() {
+    use survey_cad::io::shp::{read_parcels_shp, write_parcels_shp};
+    use survey_cad::parcel::Parcel;
+    use tempfile::NamedTempFile;
+    let mut parcel = Parcel::with_bulges(
+        vec![
+            Point::new(0.0, 0.0),
+            Point::new(10.0, 0.0),
+            Point::new(10.0, 10.0),
+            Point::new(0.0, 10.0),
+        ],
+        vec![0.0, 0.0, 0.5, 0.0],
+    );
+    parcel.name = Some("Lot 3".to_string());
+    let file = NamedTempFile::new().unwrap();
+    write_parcels_shp(file.path().to_str().unwrap(), &[parcel.clone()]).unwrap();
+    let parcels = read_parcels_shp(file.path().to_str().unwrap()).unwrap();
+    assert_eq!(parcels[0].name.as_deref(), Some("Lot 3"));
+    assert!((parcels[0].area() - parcel.area()).abs() / parcel.area() < 1e-3);
+}
@@ -1,14 +1,17 @@
 #[cfg(feature = "shapefile")]
-use survey_cad::io::shp::{read_points_shp, write_points_shp, read_polylines_shp, write_polylines_shp, read_polygons_shp, write_polygons_shp};
-#[cfg(feature = "shapefile")]
 use survey_cad::geometry::{Point, Point3, Polyline};
+#[cfg(feature = "shapefile")]
+use survey_cad::io::shp::{
+    read_points_shp, read_polygons_shp, read_polylines_shp, write_points_shp, write_polygons_shp,
+    write_polylines_shp,
+};
 
 #[cfg(feature = "shapefile")]
 #[test]
 fn points_z_roundtrip() {
     use tempfile::NamedTempFile;
-    let pts3 = vec![Point3::new(1.0,2.0,3.0), Point3::new(4.0,5.0,6.0)];
-    let pts2: Vec<Point> = pts3.iter().map(|p| Point::new(p.x,p.y)).collect();
+    let pts3 = vec![Point3::new(1.0, 2.0, 3.0), Point3::new(4.0, 5.0, 6.0)];
+    let pts2: Vec<Point> = pts3.iter().map(|p| Point::new(p.x, p.y)).collect();
     let file = NamedTempFile::new().unwrap();
     write_points_shp(file.path().to_str().unwrap(), &pts2, Some(&pts3)).unwrap();
     let (_pts, pts_read) = read_points_shp(file.path().to_str().unwrap()).unwrap();
@@ -19,10 +22,15 @@ fn points_z_roundtrip() {
 #[test]
 fn polylines_z_roundtrip() {
     use tempfile::NamedTempFile;
-    let line3 = vec![Point3::new(0.0,0.0,0.0), Point3::new(1.0,0.0,1.0)];
-    let line2: Vec<Point> = line3.iter().map(|p| Point::new(p.x,p.y)).collect();
+    let line3 = vec![Point3::new(0.0, 0.0, 0.0), Point3::new(1.0, 0.0, 1.0)];
+    let line2: Vec<Point> = line3.iter().map(|p| Point::new(p.x, p.y)).collect();
     let file = NamedTempFile::new().unwrap();
-    write_polylines_shp(file.path().to_str().unwrap(), &[Polyline::new(line2.clone())], Some(&[line3.clone()])).unwrap();
+    write_polylines_shp(
+        file.path().to_str().unwrap(),
+        &[Polyline::new(line2.clone())],
+        Some(std::slice::from_ref(&line3)),
+    )
+    .unwrap();
     let (_lines, lines3) = read_polylines_shp(file.path().to_str().unwrap()).unwrap();
     assert_eq!(lines3.unwrap()[0], line3);
 }
@@ -32,14 +40,45 @@ fn polylines_z_roundtrip() {
 fn polygons_z_roundtrip() {
     use tempfile::NamedTempFile;
     let poly3 = vec![
-        Point3::new(0.0,0.0,0.0),
-        Point3::new(1.0,1.0,0.0),
-        Point3::new(1.0,0.0,0.0),
-        Point3::new(0.0,0.0,0.0),
+        Point3::new(0.0, 0.0, 0.0),
+        Point3::new(1.0, 1.0, 0.0),
+        Point3::new(1.0, 0.0, 0.0),
+        Point3::new(0.0, 0.0, 0.0),
     ];
-    let poly2: Vec<Point> = poly3.iter().map(|p| Point::new(p.x,p.y)).collect();
+    let poly2: Vec<Point> = poly3.iter().map(|p| Point::new(p.x, p.y)).collect();
     let file = NamedTempFile::new().unwrap();
-    write_polygons_shp(file.path().to_str().unwrap(), &[poly2.clone()], Some(&[poly3.clone()])).unwrap();
+    write_polygons_shp(
+        file.path().to_str().unwrap(),
+        std::slice::from_ref(&poly2),
+        Some(std::slice::from_ref(&poly3)),
+    )
+    .unwrap();
     let (_polys, polys3) = read_polygons_shp(file.path().to_str().unwrap()).unwrap();
     assert_eq!(polys3.unwrap()[0], poly3);
 }
+
+#[cfg(feature = "shapefile")]
+#[test]
+fn polylines3_roundtrip() {
+    use survey_cad::geometry::Polyline3;
+    use survey_cad::io::shp::{read_polylines3_shp, write_polylines3_shp};
+    use tempfile::NamedTempFile;
+    let straight = Polyline3::new(vec![Point3::new(0.0, 0.0, 1.0), Point3::new(5.0, 0.0, 2.0)]);
+    let mut ring = Polyline3::with_bulges(
+        vec![
+            Point3::new(0.0, 0.0, 0.0),
+            Point3::new(10.0, 0.0, 0.0),
+            Point3::new(10.0, 10.0, 0.0),
+        ],
+        vec![0.0, 0.0, 1.0],
+    );
+    ring.closed = true;
+    let file = NamedTempFile::new().unwrap();
+    let path = file.path().to_str().unwrap();
+    write_polylines3_shp(path, &[straight.clone(), ring], 0.5).unwrap();
+    let read = read_polylines3_shp(path).unwrap();
+    assert_eq!(read[0], straight);
+    assert!(read[1].closed);
+    // The closing semicircle is split into seven chords.
+    assert_eq!(read[1].vertices.len(), 9);
+}
@@ -0,0 +1,87 @@
+use survey_cad::geometry::{Arc, Point, Point3};
+use survey_cad::io::project::Project;
+use survey_cad::surveying::{PointDatabase, SurveyPoint};
+use survey_cad::transformation::{
+    estimate_transformation, estimate_transformation_robust, ControlPair, Transformation,
+    TransformationFit, TransformationModel,
+};
+
+fn calibration() -> Vec<ControlPair> {
+    // local site grid rotated 90 degrees from the projection grid
+    let local = [
+        (1000.0, 1000.0, 100.0),
+        (1100.0, 1000.0, 101.0),
+        (1100.0, 1100.0, 102.0),
+        (1000.0, 1100.0, 101.0),
+    ];
+    local
+        .iter()
+        .enumerate()
+        .map(|(i, &(x, y, z))| {
+            ControlPair::new(
+                &format!("{}", i + 1),
+                Point3::new(x, y, z),
+                Point3::new(
+                    500_000.0 - (y - 1000.0),
+                    5_900_000.0 + (x - 1000.0),
+                    z - 2.0,
+                ),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn site_calibration_applied_to_points_and_project() {
+    let fit = estimate_transformation(TransformationModel::Localization, &calibration()).unwrap();
+    assert!(fit.horizontal_rms < 1e-6);
+    assert!(fit.vertical_rms < 1e-6);
+    let rot = fit.transformation.rotation().unwrap();
+    assert!((rot - std::f64::consts::FRAC_PI_2).abs() < 1e-9);
+
+    let mut db = PointDatabase::new();
+    db.add_point(SurveyPoint::new(
+        Some(10),
+        Point3::new(1050.0, 1000.0, 100.0),
+        None,
+        Vec::new(),
+    ));
+    assert!(db.transform_with(&fit.transformation).is_empty());
+    let p = db.points[0].point;
+    assert!((p.x - 500_000.0).abs() < 1e-6);
+    assert!((p.y - 5_900_050.0).abs() < 1e-6);
+    assert!((p.z - 98.0).abs() < 1e-6);
+
+    let mut project = Project::new();
+    project.points.push(Point::new(1000.0, 1050.0));
+    project
+        .arcs
+        .push(Arc::new(Point::new(1000.0, 1000.0), 10.0, 0.0, 1.0));
+    project.transform_with(&fit.transformation);
+    assert!((project.points[0].x - 499_950.0).abs() < 1e-6);
+    let arc = &project.arcs[0];
+    assert!((arc.radius - 10.0).abs() < 1e-6);
+    assert!((arc.start_angle - std::f64::consts::FRAC_PI_2).abs() < 1e-9);
+}
+
+#[test]
+fn fit_serialises_and_rejects_blunder() {
+    let mut pairs = calibration();
+    pairs.push(ControlPair::new(
+        "BAD",
+        Point3::new(1050.0, 1050.0, 100.0),
+        Point3::new(499_950.0, 5_900_050.5, 98.0),
+    ));
+    let fit = estimate_transformation_robust(TransformationModel::Helmert2D, &pairs, 0.02).unwrap();
+    assert_eq!(fit.rejected(), vec!["BAD"]);
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("calibration.json");
+    fit.save(path.to_str().unwrap()).unwrap();
+    let read = TransformationFit::load(path.to_str().unwrap()).unwrap();
+    assert_eq!(read, fit);
+    assert!(matches!(
+        read.transformation,
+        Transformation::Helmert2D { .. }
+    ));
+}
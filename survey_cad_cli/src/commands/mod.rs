@@ -1,5 +1,5 @@
 use cad_import::{read_point_file, PointFileFormat};
-use crate::{Commands, MacroAction};
+use crate::{Commands, EditAction, MacroAction};
 use std::io::BufRead;
 use std::str::FromStr;
 #[cfg(feature = "e57")]
@@ -17,8 +17,13 @@ use survey_cad::{
     corridor::{corridor_mass_haul, corridor_volume},
     crs::Crs,
     dtm::Tin,
-    geometry::{Point, Point3},
+    geometry::{
+        break_path, chamfer, extend_path, fillet, trim_path, Corner, Line, PathSegment, Point,
+        Point3, Polyline3,
+    },
     io::{
+        dxf::DxfDocument,
+        DxfEntity,
         landxml::read_landxml_surface, read_lines, read_points_csv, read_points_geojson,
         read_to_string, write_points_csv, write_points_csv_gnss, write_points_dxf,
         write_points_geojson, write_points_raw, write_string,
@@ -155,6 +160,13 @@ fn read_points_csv_3d(path: &str) -> std::io::Result<Vec<Point3>> {
     Ok(pts)
 }
 
+/// Whether a path names a LAS or LAZ file.
+#[cfg(feature = "las")]
+fn is_las_path(path: &str) -> bool {
+    let lower = path.to_ascii_lowercase();
+    lower.ends_with(".las") || lower.ends_with(".laz")
+}
+
 #[cfg(feature = "las")]
 fn write_points_classified(
     path: &str,
@@ -343,9 +355,181 @@ fn read_surface(path: &str) -> std::io::Result<Tin> {
     }
 }
 
+fn edit_path(doc: &DxfDocument, index: usize) -> Result<Polyline3, String> {
+    doc.entities
+        .get(index)
+        .ok_or_else(|| format!("no entity {index}"))?
+        .path()
+        .ok_or_else(|| format!("entity {index} is not a line, arc or polyline"))
+}
+
+fn edit_line(doc: &DxfDocument, index: usize) -> Result<Line, String> {
+    match edit_path(doc, index)?.segments()[..] {
+        [PathSegment::Line(line)] => Ok(line),
+        _ => Err(format!("entity {index} is not a line")),
+    }
+}
+
+/// Segments of every entity other than `skip`, used as cutting edges.
+fn edit_boundaries(doc: &DxfDocument, skip: usize) -> Vec<PathSegment> {
+    doc.entities
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != skip)
+        .filter_map(|(_, e)| e.path())
+        .flat_map(|path| path.segments())
+        .collect()
+}
+
+/// Replaces entity `index` with one entity per piece of its path, lifting
+/// each piece onto the elevations of the original.
+fn replace_entity(
+    doc: &mut DxfDocument,
+    index: usize,
+    path: &Polyline3,
+    pieces: Vec<Vec<PathSegment>>,
+) {
+    let props = doc.entities[index].properties();
+    let new: Vec<DxfEntity> = pieces
+        .iter()
+        .map(|p| DxfEntity::from_path(&path.lift_path(p, false), props.clone()))
+        .collect();
+    doc.entities.splice(index..index + 1, new);
+}
+
+fn apply_edit(doc: &mut DxfDocument, action: EditAction) -> Result<(), String> {
+    match action {
+        EditAction::Offset { distance, entity } => {
+            let paths = match entity {
+                Some(i) => vec![(i, edit_path(doc, i)?)],
+                None => (0..doc.entities.len())
+                    .filter_map(|i| Some((i, doc.entities[i].path()?)))
+                    .collect(),
+            };
+            for (i, path) in paths {
+                let offset = path.offset(distance);
+                if offset.segment_count() > 0 {
+                    let props = doc.entities[i].properties();
+                    doc.entities.push(DxfEntity::from_path(&offset, props));
+                }
+            }
+        }
+        EditAction::Trim { entity, x, y } => {
+            let path = edit_path(doc, entity)?;
+            let bounds = edit_boundaries(doc, entity);
+            let pieces = trim_path(&path.segments(), path.closed, &bounds, Point::new(x, y))
+                .ok_or("no entity crosses the picked segment")?;
+            replace_entity(doc, entity, &path, pieces);
+        }
+        EditAction::Extend { entity, x, y } => {
+            let path = edit_path(doc, entity)?;
+            if path.closed {
+                return Err(format!("entity {entity} is closed"));
+            }
+            let bounds = edit_boundaries(doc, entity);
+            let extended = extend_path(&path.segments(), &bounds, Point::new(x, y))
+                .ok_or("no entity lies in the way of the extension")?;
+            replace_entity(doc, entity, &path, vec![extended]);
+        }
+        EditAction::Fillet {
+            first,
+            second,
+            radius,
+        } => {
+            let corner = fillet(&edit_line(doc, first)?, &edit_line(doc, second)?, radius)
+                .ok_or("the lines are parallel or too short for the radius")?;
+            apply_corner(doc, first, second, corner);
+        }
+        EditAction::Chamfer {
+            first,
+            second,
+            d1,
+            d2,
+        } => {
+            let corner = chamfer(&edit_line(doc, first)?, &edit_line(doc, second)?, d1, d2)
+                .ok_or("the lines are parallel or too short for the chamfer")?;
+            apply_corner(doc, first, second, corner);
+        }
+        EditAction::Break { entity, x, y } => {
+            let path = edit_path(doc, entity)?;
+            let pieces = break_path(&path.segments(), path.closed, Point::new(x, y));
+            // an open path broken at one of its ends stays as it is
+            if pieces.len() > 1 || (path.closed && !pieces.is_empty()) {
+                replace_entity(doc, entity, &path, pieces);
+            }
+        }
+        EditAction::Join { tolerance } => {
+            // open paths on each layer with the entities they came from
+            let mut layers: Vec<(&str, Vec<usize>, Vec<Polyline3>)> = Vec::new();
+            for (i, e) in doc.entities.iter().enumerate() {
+                let Some(path) = e.path().filter(|p| !p.closed) else {
+                    continue;
+                };
+                match layers.iter_mut().find(|(layer, ..)| *layer == e.layer()) {
+                    Some((_, indices, paths)) => {
+                        indices.push(i);
+                        paths.push(path);
+                    }
+                    None => layers.push((e.layer(), vec![i], vec![path])),
+                }
+            }
+            // each chain takes the place of its first piece
+            let mut joined = Vec::new();
+            for (_, indices, paths) in layers {
+                for (used, path) in Polyline3::join(&paths, tolerance) {
+                    if used.len() > 1 || path.closed {
+                        let mut at: Vec<usize> = used.iter().map(|&k| indices[k]).collect();
+                        at.sort_unstable();
+                        joined.push((at, path));
+                    }
+                }
+            }
+            let mut removed = vec![false; doc.entities.len()];
+            for (at, path) in joined {
+                let props = doc.entities[at[0]].properties();
+                doc.entities[at[0]] = DxfEntity::from_path(&path, props);
+                for &i in &at[1..] {
+                    removed[i] = true;
+                }
+            }
+            let mut i = 0;
+            doc.entities.retain(|_| {
+                i += 1;
+                !removed[i - 1]
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Replaces two lines with the trimmed lines of `corner`, keeping their
+/// elevations, and adds its connector on the layer of the first.
+fn apply_corner(doc: &mut DxfDocument, first: usize, second: usize, corner: Corner) {
+    let lift = |i: usize, line: Line| {
+        let path = doc.entities[i].path().unwrap_or_default();
+        path.lift_path(&[PathSegment::Line(line)], false)
+    };
+    let (a, b) = (lift(first, corner.first), lift(second, corner.second));
+    let p1 = doc.entities[first].properties();
+    let p2 = doc.entities[second].properties();
+    if let Some(c) = corner.connector {
+        let (s, e) = (c.start_point(), c.end_point());
+        let connector = Polyline3::with_bulges(
+            vec![
+                Point3::new(s.x, s.y, a.elevation_at(s).unwrap_or(0.0)),
+                Point3::new(e.x, e.y, b.elevation_at(e).unwrap_or(0.0)),
+            ],
+            vec![c.bulge()],
+        );
+        doc.entities.push(DxfEntity::from_path(&connector, p1.clone()));
+    }
+    doc.entities[first] = DxfEntity::from_path(&a, p1);
+    doc.entities[second] = DxfEntity::from_path(&b, p2);
+}
+
 pub fn run(command: crate::Commands, epsg: u32) {
-    let _working_crs = Crs::from_epsg(epsg);
-    println!("Using CRS: {}", _working_crs.definition());
+    let working_crs = Crs::from_epsg(epsg);
+    println!("Using CRS: {}", working_crs.definition());
     match command {
         Commands::StationDistance {
             name_a,
@@ -624,6 +808,24 @@ pub fn run(command: crate::Commands, epsg: u32) {
             Err(e) => eprintln!("Error reading {input}: {e}"),
         },
         #[cfg(feature = "las")]
+        Commands::ClassifyCloud {
+            input,
+            output,
+            cell_size,
+            ground_threshold,
+            veg_threshold,
+        } if is_las_path(&input) => match survey_cad::io::las::read_las(&input) {
+            Ok(mut cloud) => {
+                survey_cad::classify_cloud(&mut cloud, cell_size, ground_threshold, veg_threshold);
+                if let Err(e) = survey_cad::io::las::write_las(&output, &cloud) {
+                    eprintln!("Error writing {output}: {e}");
+                } else {
+                    println!("Wrote {output}");
+                }
+            }
+            Err(e) => eprintln!("Error reading {input}: {e}"),
+        },
+        #[cfg(feature = "las")]
         Commands::ClassifyCloud {
             input,
             output,
@@ -642,6 +844,86 @@ pub fn run(command: crate::Commands, epsg: u32) {
             }
             Err(e) => eprintln!("Error reading {input}: {e}"),
         },
+        #[cfg(any(feature = "las", feature = "e57"))]
+        Commands::TileCloud {
+            input,
+            output_dir,
+            tile_size,
+            buffer,
+            noise_radius,
+            min_neighbors,
+            ground_cell,
+            ground_method,
+            ground_threshold,
+            veg_threshold,
+            thin,
+            threads,
+        } => {
+            use survey_cad::lidar::tiling::{GroundFilter, NoiseFilter, TileOptions};
+            use survey_cad::lidar::{CsfParams, GroundMethod, PmfParams};
+            let ground = match (ground_cell, ground_method.to_ascii_lowercase().as_str()) {
+                (None, _) => None,
+                (Some(cell_size), "grid") => Some(GroundFilter::Grid {
+                    cell_size,
+                    ground_threshold,
+                    veg_threshold,
+                }),
+                (Some(cell_size), "pmf") => {
+                    Some(GroundFilter::Method(GroundMethod::Pmf(PmfParams {
+                        cell_size,
+                        ..Default::default()
+                    })))
+                }
+                (Some(cloth_resolution), "csf") => {
+                    Some(GroundFilter::Method(GroundMethod::Csf(CsfParams {
+                        cloth_resolution,
+                        ..Default::default()
+                    })))
+                }
+                _ => {
+                    eprintln!("Unknown ground method {ground_method}");
+                    return;
+                }
+            };
+            let options = TileOptions {
+                tile_size,
+                buffer,
+                threads,
+                noise: noise_radius.map(|radius| NoiseFilter {
+                    radius,
+                    min_neighbors,
+                }),
+                ground,
+                thin,
+                ..Default::default()
+            };
+            let result = if input.to_ascii_lowercase().ends_with(".e57") {
+                #[cfg(feature = "e57")]
+                {
+                    survey_cad::io::e57::tile_e57(&input, &output_dir, &options)
+                }
+                #[cfg(not(feature = "e57"))]
+                {
+                    Err(std::io::Error::other("E57 support is not enabled"))
+                }
+            } else {
+                #[cfg(feature = "las")]
+                {
+                    survey_cad::io::las::tile_las(&input, &output_dir, &options)
+                }
+                #[cfg(not(feature = "las"))]
+                {
+                    Err(std::io::Error::other("LAS support is not enabled"))
+                }
+            };
+            match result {
+                Ok(tiles) => {
+                    let points: usize = tiles.iter().map(|t| t.output_points).sum();
+                    println!("Wrote {} tiles ({points} points) to {output_dir}", tiles.len());
+                }
+                Err(e) => eprintln!("Error tiling {input}: {e}"),
+            }
+        }
         #[cfg(feature = "render")]
         Commands::ViewPoints { input } => match read_points_csv(&input, None, None) {
             Ok(pts) => {
@@ -713,9 +995,15 @@ pub fn run(command: crate::Commands, epsg: u32) {
         Commands::NetworkAdjust {
             points,
             observations,
+            height,
         } => {
             use std::collections::HashMap;
-            use survey_cad::surveying::{adjust_network_report, Observation};
+            use survey_cad::surveying::{adjust_network_report, EnuBaseline, Observation};
+            let number = |s: &str| {
+                s.trim()
+                    .parse::<f64>()
+                    .map_err(|_| format!("invalid number '{}'", s.trim()))
+            };
             match (read_lines(&points), read_lines(&observations)) {
                 (Ok(p_lines), Ok(o_lines)) => {
                     let mut names = Vec::new();
@@ -730,64 +1018,106 @@ pub fn run(command: crate::Commands, epsg: u32) {
                             eprintln!("{} line {} invalid", points, idx + 1);
                             return;
                         }
-                        names.push(parts[0].trim().to_string());
-                        let x: f64 = parts[1].trim().parse().unwrap_or(0.0);
-                        let y: f64 = parts[2].trim().parse().unwrap_or(0.0);
+                        let (x, y) = match (number(parts[1]), number(parts[2])) {
+                            (Ok(x), Ok(y)) => (x, y),
+                            (Err(e), _) | (_, Err(e)) => {
+                                eprintln!("{} line {}: {e}", points, idx + 1);
+                                return;
+                            }
+                        };
                         if parts.get(3).is_some_and(|v| v.trim() == "1") {
-                            fixed.push(idx);
+                            fixed.push(pts.len());
                         }
+                        names.push(parts[0].trim().to_string());
                         pts.push(Point::new(x, y));
                     }
                     let mut index: HashMap<String, usize> = HashMap::new();
                     for (i, n) in names.iter().enumerate() {
                         index.insert(n.clone(), i);
                     }
+                    let station = |s: &str| {
+                        index
+                            .get(s.trim())
+                            .copied()
+                            .ok_or_else(|| format!("unknown point '{}'", s.trim()))
+                    };
+                    // east/north baselines are reduced to grid with the line
+                    // scale factor, the elevation factor and the convergence
+                    let to_grid = |from: usize, to: usize, enu: EnuBaseline| {
+                        let (a, b) = (pts[from], pts[to]);
+                        let mid = ((a.x + b.x) / 2.0, (a.y + b.y) / 2.0);
+                        let reduced = working_crs
+                            .line_scale_factor((a.x, a.y), (b.x, b.y))
+                            .zip(working_crs.elevation_factor(mid.0, mid.1, height))
+                            .zip(working_crs.convergence(mid.0, mid.1))
+                            .map(|((k, e), gamma)| enu.to_grid(k * e, gamma));
+                        reduced.ok_or_else(|| {
+                            format!(
+                                "baselines need a Transverse Mercator --epsg, not EPSG:{}",
+                                epsg
+                            )
+                        })
+                    };
+                    let parse = |parts: &[&str]| -> Result<Observation, String> {
+                        match parts[0].trim().to_ascii_lowercase().as_str() {
+                            "angle" => Ok(Observation::Angle {
+                                at: station(parts[1])?,
+                                from: station(parts[2])?,
+                                to: station(parts[3])?,
+                                value: number(parts[4])?,
+                                weight: 1.0,
+                            }),
+                            "baseline" => {
+                                let (from, to) = (station(parts[1])?, station(parts[2])?);
+                                let num: Vec<f64> = parts[3..8]
+                                    .iter()
+                                    .map(|v| number(v))
+                                    .collect::<Result<_, _>>()?;
+                                let enu = EnuBaseline {
+                                    de: num[0],
+                                    dn: num[1],
+                                    du: 0.0,
+                                    covariance: [
+                                        [num[2], num[3], 0.0],
+                                        [num[3], num[4], 0.0],
+                                        [0.0, 0.0, 0.0],
+                                    ],
+                                };
+                                Ok(to_grid(from, to, enu)?.observation(from, to))
+                            }
+                            _ => Ok(Observation::Distance {
+                                from: station(parts[1])?,
+                                to: station(parts[2])?,
+                                value: number(parts[3])?,
+                                weight: 1.0,
+                            }),
+                        }
+                    };
                     let mut obs = Vec::new();
                     for (idx, line) in o_lines.iter().enumerate() {
                         if line.trim().is_empty() {
                             continue;
                         }
                         let parts: Vec<&str> = line.split(',').collect();
-                        if parts.is_empty() {
-                            continue;
-                        }
-                        match parts[0].trim().to_ascii_lowercase().as_str() {
-                            "dist" | "distance" => {
-                                if parts.len() < 4 {
-                                    eprintln!("{} line {} invalid", observations, idx + 1);
-                                    return;
-                                }
-                                let from = index[parts[1].trim()];
-                                let to = index[parts[2].trim()];
-                                let val: f64 = parts[3].trim().parse().unwrap_or(0.0);
-                                obs.push(Observation::Distance {
-                                    from,
-                                    to,
-                                    value: val,
-                                    weight: 1.0,
-                                });
-                            }
-                            "angle" => {
-                                if parts.len() < 5 {
-                                    eprintln!("{} line {} invalid", observations, idx + 1);
-                                    return;
-                                }
-                                let at = index[parts[1].trim()];
-                                let from = index[parts[2].trim()];
-                                let to = index[parts[3].trim()];
-                                let val: f64 = parts[4].trim().parse().unwrap_or(0.0);
-                                obs.push(Observation::Angle {
-                                    at,
-                                    from,
-                                    to,
-                                    value: val,
-                                    weight: 1.0,
-                                });
-                            }
+                        let needed = match parts[0].trim().to_ascii_lowercase().as_str() {
+                            "dist" | "distance" => 4,
+                            "angle" => 5,
+                            "baseline" => 8,
                             _ => {
                                 eprintln!("{} line {} unknown obs", observations, idx + 1);
                                 return;
                             }
+                        };
+                        if parts.len() < needed {
+                            eprintln!("{} line {} invalid", observations, idx + 1);
+                            return;
+                        }
+                        match parse(&parts) {
+                            Ok(o) => obs.push(o),
+                            Err(e) => {
+                                eprintln!("{} line {}: {e}", observations, idx + 1);
+                                return;
+                            }
                         }
                     }
                     let (result, report) = adjust_network_report(&pts, &fixed, &obs, 1e-6, 10);
@@ -795,10 +1125,24 @@ pub fn run(command: crate::Commands, epsg: u32) {
                     for (name, p) in names.iter().zip(result.points.iter()) {
                         println!("{}, {:.3}, {:.3}", name, p.x, p.y);
                     }
-                    for (o, v) in obs.iter().zip(result.residuals.iter()) {
+                    let mut residuals = result.residuals.iter();
+                    for o in &obs {
                         match o {
-                            Observation::Distance { .. } => println!("distance residual {v:.4}"),
-                            Observation::Angle { .. } => println!("angle residual {v:.6}"),
+                            Observation::Distance { .. } => {
+                                if let Some(v) = residuals.next() {
+                                    println!("distance residual {v:.4}");
+                                }
+                            }
+                            Observation::Angle { .. } => {
+                                if let Some(v) = residuals.next() {
+                                    println!("angle residual {v:.6}");
+                                }
+                            }
+                            Observation::Baseline { .. } => {
+                                if let (Some(e), Some(n)) = (residuals.next(), residuals.next()) {
+                                    println!("baseline residual {e:.4},{n:.4}");
+                                }
+                            }
                         }
                     }
                 }
@@ -1064,12 +1408,40 @@ pub fn run(command: crate::Commands, epsg: u32) {
             rules,
             out_structs,
             out_pipes,
+            surface,
+            min_cover,
+            min_drop,
+            match_crowns,
+            max_spacing,
+            outfall_invert,
         } => match (
             pipe_network::read_network_csv(&structures, &pipes),
-            pipe_network::read_slope_rules_csv(&rules),
+            pipe_network::read_slope_limits_csv(&rules),
         ) {
-            (Ok(mut net), Ok(rules)) => {
-                pipe_network::apply_slope_rules(&mut net, &rules);
+            (Ok(mut net), Ok(slopes)) => {
+                let tin = match surface.as_deref().map(read_surface).transpose() {
+                    Ok(tin) => tin,
+                    Err(e) => {
+                        eprintln!("Error reading surface: {e}");
+                        return;
+                    }
+                };
+                let rules = pipe_network::DesignRules {
+                    slopes,
+                    min_cover,
+                    match_mode: if match_crowns {
+                        pipe_network::MatchMode::Crowns
+                    } else {
+                        pipe_network::MatchMode::Inverts
+                    },
+                    min_drop,
+                    max_spacing,
+                };
+                let violations =
+                    pipe_network::layout_inverts(&mut net, &rules, tin.as_ref(), outfall_invert);
+                for v in &violations {
+                    println!("{} {:?}: {:.3} (limit {:.3})", v.id, v.kind, v.value, v.limit);
+                }
                 if let Err(e) = pipe_network::write_network_csv(&net, &out_structs, &out_pipes) {
                     eprintln!("Error writing network: {e}");
                 }
@@ -1093,6 +1465,234 @@ pub fn run(command: crate::Commands, epsg: u32) {
             }
             Err(e) => eprintln!("Error reading network: {e}"),
         },
+        Commands::PipeNetworkSize {
+            structures,
+            pipes,
+            out_structs,
+            out_pipes,
+            max_depth_ratio,
+            min_velocity,
+            max_velocity,
+        } => match pipe_network::read_network_csv(&structures, &pipes) {
+            Ok(mut net) => {
+                let criteria = pipe_network::SizingCriteria {
+                    max_depth_ratio,
+                    min_velocity,
+                    max_velocity,
+                    ..Default::default()
+                };
+                for r in pipe_network::size_pipes(&mut net, &criteria) {
+                    let mut notes = Vec::new();
+                    if !r.meets_capacity {
+                        notes.push("over capacity");
+                    }
+                    if !r.meets_velocity {
+                        notes.push("velocity out of range");
+                    }
+                    println!(
+                        "{}: {:.3} m, {:.2} m/s {}",
+                        r.pipe,
+                        r.diameter,
+                        r.velocity,
+                        notes.join(", ")
+                    );
+                }
+                if let Err(e) = pipe_network::write_network_csv(&net, &out_structs, &out_pipes) {
+                    eprintln!("Error writing network: {e}");
+                }
+            }
+            Err(e) => eprintln!("Error reading network: {e}"),
+        },
+        Commands::PipeNetworkGrades {
+            structures,
+            pipes,
+            out_csv,
+            tailwater,
+            surface,
+            min_cover,
+            min_freeboard,
+        } => match pipe_network::read_network_csv(&structures, &pipes) {
+            Ok(mut net) => {
+                if let Some(surface) = surface {
+                    match read_surface(&surface) {
+                        Ok(tin) => {
+                            for id in net.set_rims_from_tin(&tin) {
+                                eprintln!("Structure {id} is outside {surface}");
+                            }
+                        }
+                        Err(e) => {
+                            eprintln!("Error reading {surface}: {e}");
+                            return;
+                        }
+                    }
+                }
+                let options = pipe_network::GradeOptions {
+                    tailwater,
+                    min_cover,
+                    min_freeboard,
+                };
+                let res = pipe_network::hydraulic_grade_lines(&net, &options);
+                let mut csv = String::from("pipe,hgl_up,hgl_down,egl_up,egl_down,surcharged\n");
+                for p in &res.pipes {
+                    csv.push_str(&format!(
+                        "{},{},{},{},{},{}\n",
+                        p.pipe, p.hgl_up, p.hgl_down, p.egl_up, p.egl_down, p.surcharged
+                    ));
+                }
+                if let Err(e) = std::fs::write(&out_csv, csv) {
+                    eprintln!("Error writing {out_csv}: {e}");
+                }
+                for v in &res.violations {
+                    println!("{} {:?}: {:.3} (limit {:.3})", v.id, v.kind, v.value, v.limit);
+                }
+            }
+            Err(e) => eprintln!("Error reading network: {e}"),
+        },
+        Commands::PipeNetworkProfile {
+            structures,
+            pipes,
+            path,
+            output,
+            surface,
+            tailwater,
+            crossing,
+            plan,
+            hscale,
+            vscale,
+            grid,
+        } => match pipe_network::read_network_csv(&structures, &pipes) {
+            Ok(net) => {
+                let tin = match surface.as_deref().map(read_surface).transpose() {
+                    Ok(tin) => tin,
+                    Err(e) => {
+                        eprintln!("Error reading surface: {e}");
+                        return;
+                    }
+                };
+                let grades = tailwater.map(|tailwater| {
+                    let options = pipe_network::GradeOptions {
+                        tailwater,
+                        min_cover: 0.0,
+                        min_freeboard: 0.0,
+                    };
+                    pipe_network::hydraulic_grade_lines(&net, &options)
+                });
+                let others = match crossing
+                    .chunks(2)
+                    .map(|c| pipe_network::read_network_csv(&c[0], &c[1]))
+                    .collect::<std::io::Result<Vec<_>>>()
+                {
+                    Ok(others) => others,
+                    Err(e) => {
+                        eprintln!("Error reading crossing network: {e}");
+                        return;
+                    }
+                };
+                let others: Vec<&pipe_network::Network> = others.iter().collect();
+                let ids: Vec<&str> = path.split(',').map(str::trim).collect();
+                match pipe_network::pipe_profile(&net, &ids, tin.as_ref(), grades.as_ref(), &others)
+                {
+                    Ok(profile) => {
+                        if let Err(e) = survey_cad::sheet::write_utility_profile_svg(
+                            &output, &profile, hscale, vscale, grid,
+                        ) {
+                            eprintln!("Error writing {output}: {e}");
+                        }
+                    }
+                    Err(e) => eprintln!("Error building profile: {e}"),
+                }
+                if let Some(plan) = plan {
+                    let sheet = pipe_network::network_plan(&net);
+                    if let Err(e) = survey_cad::sheet::write_utility_plan_svg(&plan, &sheet, hscale)
+                    {
+                        eprintln!("Error writing {plan}: {e}");
+                    }
+                }
+            }
+            Err(e) => eprintln!("Error reading network: {e}"),
+        },
+        Commands::PipeNetworkClash {
+            out_csv,
+            network,
+            surface,
+            clearance,
+            min_cover,
+            out_dxf,
+            marker_size,
+        } => {
+            let mut nets = Vec::new();
+            for n in network.chunks(3) {
+                match pipe_network::read_network_csv(&n[1], &n[2]) {
+                    Ok(net) => nets.push((n[0].as_str(), net)),
+                    Err(e) => {
+                        eprintln!("Error reading network {}: {e}", n[0]);
+                        return;
+                    }
+                }
+            }
+            let nets: Vec<(&str, &pipe_network::Network)> =
+                nets.iter().map(|(name, net)| (*name, net)).collect();
+            let mut clashes = pipe_network::network_clashes(&nets, clearance);
+            for path in &surface {
+                match read_surface(path) {
+                    Ok(tin) => clashes.extend(pipe_network::surface_clashes(
+                        &nets, &tin, path, min_cover,
+                    )),
+                    Err(e) => eprintln!("Error reading {path}: {e}"),
+                }
+            }
+            println!("{} clashes", clashes.len());
+            if let Err(e) = pipe_network::write_clash_csv(&out_csv, &clashes) {
+                eprintln!("Error writing {out_csv}: {e}");
+            }
+            if let Some(out_dxf) = out_dxf {
+                let markers = pipe_network::clash_markers(&clashes, marker_size);
+                if let Err(e) = survey_cad::io::write_dxf(&out_dxf, &markers) {
+                    eprintln!("Error writing {out_dxf}: {e}");
+                }
+            }
+        }
+        Commands::WaterNetworkSolve {
+            input,
+            out_nodes,
+            out_links,
+            max_iterations,
+            accuracy,
+        } => {
+            let options = pipe_network::SolverOptions {
+                max_iterations,
+                accuracy,
+            };
+            match pipe_network::read_inp(&input)
+                .and_then(|net| pipe_network::solve_pressure_network(&net, &options))
+            {
+                Ok(res) => {
+                    if !res.converged {
+                        eprintln!("Solver did not converge in {} iterations", res.iterations);
+                    }
+                    let mut nodes = String::from("id,head,pressure,demand\n");
+                    for n in &res.nodes {
+                        nodes.push_str(&format!(
+                            "{},{},{},{}\n",
+                            n.id, n.head, n.pressure, n.demand
+                        ));
+                    }
+                    let mut links = String::from("id,flow,velocity,headloss,state\n");
+                    for l in &res.links {
+                        links.push_str(&format!(
+                            "{},{},{},{},{:?}\n",
+                            l.id, l.flow, l.velocity, l.headloss, l.state
+                        ));
+                    }
+                    for (path, text) in [(&out_nodes, nodes), (&out_links, links)] {
+                        if let Err(e) = std::fs::write(path, text) {
+                            eprintln!("Error writing {path}: {e}");
+                        }
+                    }
+                }
+                Err(e) => eprintln!("Error solving {input}: {e}"),
+            }
+        }
         Commands::Stakeout {
             halign,
             output,
@@ -1134,6 +1734,20 @@ pub fn run(command: crate::Commands, epsg: u32) {
             }
             Err(e) => eprintln!("Error reading {halign}: {e}"),
         },
+        Commands::Edit {
+            input,
+            output,
+            action,
+        } => match survey_cad::io::dxf::read_dxf_document(&input) {
+            Ok(mut doc) => match apply_edit(&mut doc, action) {
+                Ok(()) => match survey_cad::io::dxf::write_dxf_document(&output, &doc) {
+                    Ok(()) => println!("Wrote {output}"),
+                    Err(e) => eprintln!("Error writing {output}: {e}"),
+                },
+                Err(e) => eprintln!("Error: {e}"),
+            },
+            Err(e) => eprintln!("Error reading {input}: {e}"),
+        },
         Commands::Macro { action } => match action {
             MacroAction::Record { file } => macro_record(&file),
             MacroAction::Play { file } => macro_play(&file, epsg),
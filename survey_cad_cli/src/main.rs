@@ -140,6 +140,7 @@ enum Commands {
         min_neighbors: usize,
     },
     /// Classify a CSV point cloud into ground, vegetation and buildings.
+    /// LAS/LAZ input is written back as LAS/LAZ with ASPRS class codes.
     #[cfg(feature = "las")]
     ClassifyCloud {
         input: String,
@@ -151,6 +152,38 @@ enum Commands {
         #[arg(long, default_value_t = 2.0)]
         veg_threshold: f64,
     },
+    /// Split a LAS/LAZ or E57 point cloud into tiles, filtering noise,
+    /// classifying ground and thinning each tile in parallel.
+    #[cfg(any(feature = "las", feature = "e57"))]
+    TileCloud {
+        input: String,
+        output_dir: String,
+        #[arg(long, default_value_t = 100.0)]
+        tile_size: f64,
+        #[arg(long, default_value_t = 2.0)]
+        buffer: f64,
+        /// Noise search radius; noise filtering is skipped when omitted.
+        #[arg(long)]
+        noise_radius: Option<f64>,
+        #[arg(long, default_value_t = 3)]
+        min_neighbors: usize,
+        /// Ground cell size, or PMF cell / CSF cloth resolution;
+        /// classification is skipped when omitted.
+        #[arg(long)]
+        ground_cell: Option<f64>,
+        /// Ground filter: grid, pmf or csf.
+        #[arg(long, default_value = "grid")]
+        ground_method: String,
+        #[arg(long, default_value_t = 0.3)]
+        ground_threshold: f64,
+        #[arg(long, default_value_t = 2.0)]
+        veg_threshold: f64,
+        /// Voxel size for thinning.
+        #[arg(long)]
+        thin: Option<f64>,
+        #[arg(long, default_value_t = 0)]
+        threads: usize,
+    },
     /// View points from a CSV file.
     #[cfg(feature = "render")]
     ViewPoints { input: String },
@@ -192,9 +225,13 @@ enum Commands {
         foresight: f64,
     },
     /// Adjust a 2D network from CSV files of points and observations.
+    /// Baselines are east/north vectors reduced to the grid of `--epsg`.
     NetworkAdjust {
         points: String,
         observations: String,
+        /// Mean ellipsoidal height of the network for the elevation factor.
+        #[arg(long, default_value_t = 0.0)]
+        height: f64,
     },
     /// Compute cut/fill volume between two surfaces along an alignment.
     CorridorVolume {
@@ -253,13 +290,29 @@ enum Commands {
         out_csv: String,
         out_xml: String,
     },
-    /// Apply slope rules to design pipe inverts.
+    /// Lay out pipe inverts from the outfall upstream to meet slope limits
+    /// (`diameter,min_slope[,max_slope]` lines), cover, drop and spacing
+    /// rules, then report remaining violations.
     PipeNetworkDesign {
         structures: String,
         pipes: String,
         rules: String,
         out_structs: String,
         out_pipes: String,
+        /// Ground surface for cover; structure rims are used when omitted.
+        #[arg(long)]
+        surface: Option<String>,
+        #[arg(long, default_value_t = 0.0)]
+        min_cover: f64,
+        #[arg(long, default_value_t = 0.0)]
+        min_drop: f64,
+        /// Match crowns instead of inverts through structures.
+        #[arg(long)]
+        match_crowns: bool,
+        #[arg(long)]
+        max_spacing: Option<f64>,
+        #[arg(long)]
+        outfall_invert: Option<f64>,
     },
     /// Detailed analysis including velocity.
     PipeNetworkAnalyzeDetailed {
@@ -268,6 +321,88 @@ enum Commands {
         out_csv: String,
         out_xml: String,
     },
+    /// Size pipes to the smallest standard diameter carrying their design
+    /// flows within Manning depth and velocity limits.
+    PipeNetworkSize {
+        structures: String,
+        pipes: String,
+        out_structs: String,
+        out_pipes: String,
+        #[arg(long, default_value_t = 0.8)]
+        max_depth_ratio: f64,
+        #[arg(long, default_value_t = 0.6)]
+        min_velocity: f64,
+        #[arg(long, default_value_t = 3.0)]
+        max_velocity: f64,
+    },
+    /// Compute HGL/EGL upstream from an outfall tailwater and report cover
+    /// and freeboard violations.
+    PipeNetworkGrades {
+        structures: String,
+        pipes: String,
+        out_csv: String,
+        #[arg(long)]
+        tailwater: f64,
+        /// Surface used to set structure rims.
+        #[arg(long)]
+        surface: Option<String>,
+        #[arg(long, default_value_t = 1.0)]
+        min_cover: f64,
+        #[arg(long, default_value_t = 0.3)]
+        min_freeboard: f64,
+    },
+    /// Draw a profile sheet along a comma-separated list of connected pipes,
+    /// with ground, HGL and crossing pipes, and optionally a plan sheet.
+    PipeNetworkProfile {
+        structures: String,
+        pipes: String,
+        path: String,
+        output: String,
+        #[arg(long)]
+        surface: Option<String>,
+        /// Outfall tailwater used to draw the HGL.
+        #[arg(long)]
+        tailwater: Option<f64>,
+        /// Structure and pipe CSV files of a crossing network.
+        #[arg(long, num_args = 2)]
+        crossing: Vec<String>,
+        #[arg(long)]
+        plan: Option<String>,
+        #[arg(long, default_value_t = 1.0)]
+        hscale: f64,
+        #[arg(long, default_value_t = 0.1)]
+        vscale: f64,
+        #[arg(long, default_value_t = 10.0)]
+        grid: f64,
+    },
+    /// Check clearances between networks given as `name structures pipes`
+    /// and cover below surfaces, writing a clash report and DXF markers.
+    PipeNetworkClash {
+        out_csv: String,
+        #[arg(long, num_args = 3)]
+        network: Vec<String>,
+        #[arg(long)]
+        surface: Vec<String>,
+        #[arg(long, default_value_t = 0.3)]
+        clearance: f64,
+        #[arg(long, default_value_t = 0.3)]
+        min_cover: f64,
+        #[arg(long)]
+        out_dxf: Option<String>,
+        #[arg(long, default_value_t = 1.0)]
+        marker_size: f64,
+    },
+    /// Solve a pressurised water network from an EPANET .inp file and write
+    /// node heads and pressures and link flows to CSV files.
+    WaterNetworkSolve {
+        input: String,
+        out_nodes: String,
+        out_links: String,
+        #[arg(long, default_value_t = 200)]
+        max_iterations: usize,
+        #[arg(long, default_value_t = 0.001)]
+        accuracy: f64,
+    },
     /// Compute optimal station points along an alignment and export to a file.
     Stakeout {
         halign: String,
@@ -278,6 +413,14 @@ enum Commands {
         #[arg(long, default_value_t = 0.0)]
         offset: f64,
     },
+    /// Apply a drafting edit to the lines, arcs and polylines of a DXF file.
+    /// Entities are addressed by their index in the file.
+    Edit {
+        input: String,
+        output: String,
+        #[command(subcommand)]
+        action: EditAction,
+    },
     /// Record or play a command macro.
     Macro {
         #[command(subcommand)]
@@ -293,6 +436,44 @@ enum MacroAction {
     Play { file: String },
 }
 
+#[derive(Subcommand)]
+enum EditAction {
+    /// Add a copy of an entity, or of every entity, offset to the left.
+    Offset {
+        distance: f64,
+        #[arg(long)]
+        entity: Option<usize>,
+    },
+    /// Cut the piece of an entity at the pick point back to the nearest
+    /// crossings with the other entities.
+    Trim { entity: usize, x: f64, y: f64 },
+    /// Extend the end of an entity nearest the pick point to the next
+    /// entity in its way.
+    Extend { entity: usize, x: f64, y: f64 },
+    /// Join two lines with an arc of the given radius.
+    Fillet {
+        first: usize,
+        second: usize,
+        radius: f64,
+    },
+    /// Bevel two lines by cutting back the given distances from their
+    /// intersection.
+    Chamfer {
+        first: usize,
+        second: usize,
+        d1: f64,
+        d2: f64,
+    },
+    /// Split an entity in two at the pick point.
+    Break { entity: usize, x: f64, y: f64 },
+    /// Join lines, arcs and open polylines on the same layer whose ends
+    /// meet into polylines.
+    Join {
+        #[arg(long, default_value_t = 0.001)]
+        tolerance: f64,
+    },
+}
+
 fn main() {
     let cli = Cli::parse();
     commands::run(cli.command, cli.epsg);
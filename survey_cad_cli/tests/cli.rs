@@ -300,3 +300,100 @@ fn create_full_intersection_command() {
         .stdout(predicate::str::contains("Horizontal alignment:"));
     dir.close().unwrap();
 }
+
+#[test]
+fn edit_join_keeps_elevations() {
+    use survey_cad::geometry::{Line, Point, Point3};
+    use survey_cad::io::dxf::{
+        read_dxf_document, write_dxf_document, DxfDocument, DxfProperties, DxfVertex,
+    };
+    use survey_cad::io::DxfEntity;
+
+    let dir = assert_fs::TempDir::new().unwrap();
+    let input = dir.child("kerb.dxf");
+    let output = dir.child("joined.dxf");
+    let props = DxfProperties::on_layer("KERB");
+    let stray = DxfEntity::Line {
+        line: Line::new(Point::new(50.0, 50.0), Point::new(60.0, 50.0)),
+        layer: Some("KERB".into()),
+    };
+    let doc = DxfDocument::from_entities(vec![
+        DxfEntity::Line3D {
+            start: Point3::new(0.0, 0.0, 10.0),
+            end: Point3::new(10.0, 0.0, 11.0),
+            props: props.clone(),
+        },
+        stray.clone(),
+        DxfEntity::Polyline3D {
+            vertices: vec![
+                DxfVertex::new(Point3::new(20.0, 5.0, 13.0), 0.0),
+                DxfVertex::new(Point3::new(10.0, 0.0, 11.0), 0.0),
+            ],
+            closed: false,
+            props,
+        },
+    ]);
+    write_dxf_document(input.path().to_str().unwrap(), &doc).unwrap();
+
+    Command::cargo_bin("survey_cad_cli")
+        .unwrap()
+        .args([
+            "edit",
+            input.path().to_str().unwrap(),
+            output.path().to_str().unwrap(),
+            "join",
+        ])
+        .assert()
+        .success();
+
+    let joined = read_dxf_document(output.path().to_str().unwrap()).unwrap();
+    assert_eq!(joined.entities.len(), 2);
+    let DxfEntity::Polyline3D { vertices, .. } = &joined.entities[0] else {
+        panic!("expected 3D polyline, got {:?}", joined.entities[0]);
+    };
+    let z: Vec<f64> = vertices.iter().map(|v| v.point.z).collect();
+    assert_eq!(z, [10.0, 11.0, 13.0]);
+    assert_eq!(joined.entities[1], stray);
+    dir.close().unwrap();
+}
+
+#[test]
+fn network_adjust_reduces_baselines_to_grid() {
+    let dir = assert_fs::TempDir::new().unwrap();
+    let points = dir.child("points.csv");
+    points
+        .write_str("A,600000,5000000,1\nB,600000,5000100\n")
+        .unwrap();
+    let obs = dir.child("obs.csv");
+    obs.write_str("baseline,A,B,0,100,1e-4,0,1e-4\n").unwrap();
+    let bad = dir.child("bad.csv");
+    bad.write_str("dist,A,B,100\ndist,A,C,100\n").unwrap();
+    let args = |o: &assert_fs::fixture::ChildPath| {
+        vec![
+            "--epsg".to_string(),
+            "32633".to_string(),
+            "network-adjust".to_string(),
+            points.path().to_str().unwrap().to_string(),
+            o.path().to_str().unwrap().to_string(),
+        ]
+    };
+
+    // 100 km east of the central meridian the grid is turned and shrunk
+    Command::cargo_bin("survey_cad_cli")
+        .unwrap()
+        .args(args(&obs))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("B, 599998.426, 5000099.960"));
+    Command::cargo_bin("survey_cad_cli")
+        .unwrap()
+        .args(args(&bad))
+        .assert()
+        .stderr(predicate::str::contains("line 2: unknown point 'C'"));
+    Command::cargo_bin("survey_cad_cli")
+        .unwrap()
+        .args(&args(&obs)[2..])
+        .assert()
+        .stderr(predicate::str::contains("Transverse Mercator"));
+    dir.close().unwrap();
+}
@@ -660,6 +660,9 @@ fn main() -> Result<(), slint::PlatformError> {
                                 .into_iter()
                                 .filter_map(|e| match e {
                                     survey_cad::io::DxfEntity::Point { point, .. } => Some(point),
+                                    survey_cad::io::DxfEntity::Point3D { point, .. } => {
+                                        Some(survey_cad::geometry::Point::new(point.x, point.y))
+                                    }
                                     _ => None,
                                 })
                                 .collect();
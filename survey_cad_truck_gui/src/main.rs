@@ -9,6 +9,7 @@ use std::rc::Rc;
 use std::time::Instant;
 
 use survey_cad::alignment::{Alignment, VerticalAlignment, VerticalElement};
+use survey_cad::blocks::{BlockEntity, BlockInsert, SymbolLibrary};
 use survey_cad::corridor;
 use survey_cad::crs::list_known_crs;
 use survey_cad::dtm::Tin;
@@ -17,7 +18,12 @@ use survey_cad::geometry::{
     Arc, Line, LineAnnotation, LineStyle, LineType, Point, PointSymbol, Polyline,
     convex_hull, Point3 as ScPoint3, LinearDimension,
 };
+use survey_cad::geometry::{
+    break_path, chamfer, extend_path, fillet, join, offset_path, path_from_bulges, trim_path,
+    Corner, PathSegment,
+};
 use survey_cad::layers::{Layer, LayerManager as ScLayerManager};
+use survey_cad::local_grid::GroundCoordinateSystem;
 use survey_cad::io::project::{read_project_json, write_project_json, Project, GridSettings};
 use survey_cad::point_database::PointDatabase;
 use survey_cad::styles::{
@@ -67,6 +73,8 @@ struct WorkspaceRenderData<'a> {
     dimensions: &'a [LinearDimension],
     surfaces: &'a [Tin],
     alignments: &'a [Alignment],
+    inserts: &'a [BlockInsert],
+    symbols: &'a SymbolLibrary,
 }
 
 #[derive(Default, Clone)]
@@ -89,6 +97,7 @@ struct CursorFeedback {
 }
 
 #[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
 struct SnapPrefs {
     snap_to_grid: bool,
     snap_to_entities: bool,
@@ -97,6 +106,15 @@ struct SnapPrefs {
     snap_midpoints: bool,
     snap_intersections: bool,
     snap_nearest: bool,
+    snap_centres: bool,
+    snap_quadrants: bool,
+    snap_insertions: bool,
+    snap_apparent_intersections: bool,
+    snap_extensions: bool,
+    snap_perpendiculars: bool,
+    snap_tangents: bool,
+    polar_tracking: bool,
+    object_tracking: bool,
     snap_tolerance: f32,
 }
 
@@ -110,11 +128,41 @@ impl Default for SnapPrefs {
             snap_midpoints: true,
             snap_intersections: true,
             snap_nearest: true,
+            snap_centres: true,
+            snap_quadrants: true,
+            snap_insertions: true,
+            snap_apparent_intersections: true,
+            snap_extensions: true,
+            snap_perpendiculars: true,
+            snap_tangents: true,
+            polar_tracking: false,
+            object_tracking: false,
             snap_tolerance: 5.0,
         }
     }
 }
 
+impl SnapPrefs {
+    fn options(&self) -> snap::SnapOptions {
+        snap::SnapOptions {
+            snap_points: self.snap_points,
+            snap_endpoints: self.snap_endpoints,
+            snap_midpoints: self.snap_midpoints,
+            snap_intersections: self.snap_intersections,
+            snap_nearest: self.snap_nearest,
+            snap_centres: self.snap_centres,
+            snap_quadrants: self.snap_quadrants,
+            snap_insertions: self.snap_insertions,
+            snap_apparent_intersections: self.snap_apparent_intersections,
+            snap_extensions: self.snap_extensions,
+            snap_perpendiculars: self.snap_perpendiculars,
+            snap_tangents: self.snap_tangents,
+            polar_tracking: self.polar_tracking,
+            object_tracking: self.object_tracking,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 struct Config {
     window_width: u32,
@@ -345,7 +393,7 @@ struct RenderState<'a> {
     selected_dimensions: &'a Rc<RefCell<Vec<usize>>>,
     drag: &'a Rc<RefCell<DragSelect>>,
     cursor_feedback: &'a Rc<RefCell<Option<CursorFeedback>>>,
-    snap_target: &'a Rc<RefCell<Option<Point>>>,
+    snap_target: &'a Rc<RefCell<Option<survey_cad::snap::Snap>>>,
 }
 
 struct RenderStyles<'a> {
@@ -500,6 +548,238 @@ fn spawn_line(
         .add_line([a.x, a.y, 0.0], [b.x, b.y, 0.0], [1.0, 1.0, 1.0, 1.0], 1.0);
 }
 
+/// Entity picked for a drafting edit.
+#[derive(Clone, Copy, PartialEq)]
+enum DraftTarget {
+    Line(usize),
+    Polyline(usize),
+    Arc(usize),
+}
+
+/// Workspace geometry touched by the drafting edit commands.
+#[derive(Clone)]
+struct DraftState {
+    lines: Rc<RefCell<Vec<(Point, Point)>>>,
+    line_styles: Rc<RefCell<Vec<usize>>>,
+    polylines: Rc<RefCell<Vec<Polyline>>>,
+    arcs: Rc<RefCell<Vec<Arc>>>,
+    selected_lines: Rc<RefCell<Vec<(Point, Point)>>>,
+    selected_polylines: Rc<RefCell<Vec<usize>>>,
+    selected_arcs: Rc<RefCell<Vec<usize>>>,
+    backend: Rc<RefCell<TruckBackend>>,
+}
+
+impl DraftState {
+    fn path(&self, target: DraftTarget) -> Vec<PathSegment> {
+        match target {
+            DraftTarget::Line(i) => {
+                let (a, b) = self.lines.borrow()[i];
+                vec![PathSegment::Line(Line::new(a, b))]
+            }
+            DraftTarget::Polyline(i) => {
+                let verts: Vec<(Point, f64)> = self.polylines.borrow()[i]
+                    .vertices
+                    .iter()
+                    .map(|p| (*p, 0.0))
+                    .collect();
+                path_from_bulges(&verts, false)
+            }
+            DraftTarget::Arc(i) => vec![PathSegment::from_arc(&self.arcs.borrow()[i])],
+        }
+    }
+
+    fn selected(&self) -> Vec<DraftTarget> {
+        let sel = self.selected_lines.borrow();
+        let lines = self.lines.borrow();
+        let mut targets: Vec<DraftTarget> = (0..lines.len())
+            .filter(|i| {
+                let (a, b) = lines[*i];
+                sel.iter().any(|(s, e)| (*s == a && *e == b) || (*s == b && *e == a))
+            })
+            .map(DraftTarget::Line)
+            .collect();
+        targets.extend(self.selected_polylines.borrow().iter().map(|i| DraftTarget::Polyline(*i)));
+        targets.extend(self.selected_arcs.borrow().iter().map(|i| DraftTarget::Arc(*i)));
+        targets
+    }
+
+    /// Line, polyline or arc closest to `p`.
+    fn nearest(&self, p: Point) -> Option<DraftTarget> {
+        let targets = (0..self.lines.borrow().len())
+            .map(DraftTarget::Line)
+            .chain((0..self.polylines.borrow().len()).map(DraftTarget::Polyline))
+            .chain((0..self.arcs.borrow().len()).map(DraftTarget::Arc));
+        targets
+            .map(|t| {
+                let d = self
+                    .path(t)
+                    .iter()
+                    .map(|s| survey_cad::geometry::distance(s.nearest_point(p), p))
+                    .fold(f64::INFINITY, f64::min);
+                (t, d)
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(t, _)| t)
+    }
+
+    /// Segments of the selected entities other than `skip`.
+    fn boundaries(&self, skip: DraftTarget) -> Vec<PathSegment> {
+        self.selected()
+            .into_iter()
+            .filter(|t| *t != skip)
+            .flat_map(|t| self.path(t))
+            .collect()
+    }
+
+    fn add_line(&self, a: Point, b: Point) {
+        self.lines.borrow_mut().push((a, b));
+        self.line_styles.borrow_mut().push(0);
+        self.backend
+            .borrow_mut()
+            .add_line([a.x, a.y, 0.0], [b.x, b.y, 0.0], [1.0, 1.0, 1.0, 1.0], 1.0);
+    }
+
+    /// Adds a path as a polyline when it is all straight and as separate
+    /// lines and arcs otherwise.
+    fn add_path(&self, path: &[PathSegment]) {
+        match path {
+            [] => {}
+            [PathSegment::Line(l)] => self.add_line(l.start, l.end),
+            _ if path.iter().all(|s| matches!(s, PathSegment::Line(_))) => {
+                let mut verts: Vec<Point> = path.iter().map(|s| s.start_point()).collect();
+                verts.push(path[path.len() - 1].end_point());
+                self.polylines.borrow_mut().push(Polyline::new(verts));
+            }
+            _ => {
+                for seg in path {
+                    match seg {
+                        PathSegment::Line(l) => self.add_line(l.start, l.end),
+                        PathSegment::Arc { .. } => {
+                            self.arcs.borrow_mut().extend(seg.to_arc());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Removes the targets, highest index first so the others stay valid.
+    fn remove(&self, targets: &[DraftTarget]) {
+        let mut targets = targets.to_vec();
+        targets.sort_by_key(|t| match t {
+            DraftTarget::Line(i) | DraftTarget::Polyline(i) | DraftTarget::Arc(i) => {
+                std::cmp::Reverse(*i)
+            }
+        });
+        for t in targets {
+            match t {
+                DraftTarget::Line(i) => {
+                    self.lines.borrow_mut().remove(i);
+                    if i < self.line_styles.borrow().len() {
+                        self.line_styles.borrow_mut().remove(i);
+                    }
+                    self.backend.borrow_mut().remove_line(i);
+                }
+                DraftTarget::Polyline(i) => {
+                    self.polylines.borrow_mut().remove(i);
+                }
+                DraftTarget::Arc(i) => {
+                    self.arcs.borrow_mut().remove(i);
+                }
+            }
+        }
+        self.selected_lines.borrow_mut().clear();
+        self.selected_polylines.borrow_mut().clear();
+        self.selected_arcs.borrow_mut().clear();
+    }
+
+    fn replace(&self, target: DraftTarget, paths: &[Vec<PathSegment>]) {
+        self.remove(&[target]);
+        for p in paths {
+            self.add_path(p);
+        }
+    }
+
+    fn two_lines(&self) -> Result<(DraftTarget, Line, DraftTarget, Line), String> {
+        match self.selected()[..] {
+            [a @ DraftTarget::Line(i), b @ DraftTarget::Line(j)] => {
+                let lines = self.lines.borrow();
+                Ok((
+                    a,
+                    Line::new(lines[i].0, lines[i].1),
+                    b,
+                    Line::new(lines[j].0, lines[j].1),
+                ))
+            }
+            _ => Err("Select exactly two lines".into()),
+        }
+    }
+
+    fn apply_corner(&self, a: DraftTarget, b: DraftTarget, corner: Corner) {
+        self.remove(&[a, b]);
+        self.add_line(corner.first.start, corner.first.end);
+        self.add_line(corner.second.start, corner.second.end);
+        if let Some(c) = corner.connector {
+            self.add_path(&[c]);
+        }
+    }
+
+    /// Runs the drafting edit `op` with the dialog values `a` and `b`.
+    fn apply(&self, op: &str, a: f64, b: f64) -> Result<(), String> {
+        let pick = Point::new(a, b);
+        match op {
+            "offset" => {
+                let paths: Vec<Vec<PathSegment>> =
+                    self.selected().into_iter().map(|t| self.path(t)).collect();
+                if paths.is_empty() {
+                    return Err("Nothing selected".into());
+                }
+                for p in paths {
+                    self.add_path(&offset_path(&p, a, false));
+                }
+            }
+            "trim" => {
+                let target = self.nearest(pick).ok_or("Nothing to trim")?;
+                let paths = trim_path(&self.path(target), false, &self.boundaries(target), pick)
+                    .ok_or("No selected entity crosses the picked segment")?;
+                self.replace(target, &paths);
+            }
+            "extend" => {
+                let target = self.nearest(pick).ok_or("Nothing to extend")?;
+                let path = extend_path(&self.path(target), &self.boundaries(target), pick)
+                    .ok_or("No selected entity lies in the way")?;
+                self.replace(target, &[path]);
+            }
+            "fillet" => {
+                let (ta, la, tb, lb) = self.two_lines()?;
+                let corner = fillet(&la, &lb, a).ok_or("Radius too large for the lines")?;
+                self.apply_corner(ta, tb, corner);
+            }
+            "chamfer" => {
+                let (ta, la, tb, lb) = self.two_lines()?;
+                let corner =
+                    chamfer(&la, &lb, a, b).ok_or("Distances too large for the lines")?;
+                self.apply_corner(ta, tb, corner);
+            }
+            "break" => {
+                let target = self.nearest(pick).ok_or("Nothing to break")?;
+                let paths = break_path(&self.path(target), false, pick);
+                self.replace(target, &paths);
+            }
+            "join" => {
+                let targets = self.selected();
+                let paths: Vec<Vec<PathSegment>> = targets.iter().map(|t| self.path(*t)).collect();
+                self.remove(&targets);
+                for p in join(&paths, 1e-6) {
+                    self.add_path(&p);
+                }
+            }
+            _ => return Err(format!("Unknown edit {op}")),
+        }
+        Ok(())
+    }
+}
+
 fn polyline_to_solid(pl: &Polyline, vector: Vector3) -> Option<Solid> {
     if pl.vertices.len() < 3 {
         return None;
@@ -859,6 +1139,103 @@ fn render_workspace(
         }
     }
 
+    paint.set_color(Color::from_rgba8(0, 200, 120, 255));
+    let block_stroke = Stroke { width: 1.0, ..Stroke::default() };
+    for insert in data.inserts {
+        let Some(parts) = data.symbols.explode(insert) else {
+            // Undefined blocks are drawn as a cross at the insertion point.
+            let (x, y) = (tx(insert.position.x as f32), ty(insert.position.y as f32));
+            let mut pb = PathBuilder::new();
+            pb.move_to(x - 4.0, y - 4.0);
+            pb.line_to(x + 4.0, y + 4.0);
+            pb.move_to(x - 4.0, y + 4.0);
+            pb.line_to(x + 4.0, y - 4.0);
+            if let Some(path) = pb.finish() {
+                pixmap.stroke_path(&path, &paint, &block_stroke, Transform::identity(), None);
+            }
+            continue;
+        };
+        for part in parts {
+            let mut pb = PathBuilder::new();
+            match part {
+                BlockEntity::Point(p) => {
+                    if let Some(rect) = tiny_skia::Rect::from_xywh(
+                        tx(p.x as f32) - 1.0,
+                        ty(p.y as f32) - 1.0,
+                        2.0,
+                        2.0,
+                    ) {
+                        pixmap.fill_rect(rect, &paint, Transform::identity(), None);
+                    }
+                }
+                BlockEntity::Line(a, b) => {
+                    pb.move_to(tx(a.x as f32), ty(a.y as f32));
+                    pb.line_to(tx(b.x as f32), ty(b.y as f32));
+                }
+                BlockEntity::Circle { center, radius } => {
+                    pb.push_circle(
+                        tx(center.x as f32),
+                        ty(center.y as f32),
+                        radius as f32 * zoom_val,
+                    );
+                }
+                BlockEntity::Arc { arc, .. } => {
+                    let steps = 32;
+                    for k in 0..=steps {
+                        let t = arc.start_angle + arc.sweep() * (k as f64 / steps as f64);
+                        let px = tx((arc.center.x + arc.radius * t.cos()) as f32);
+                        let py = ty((arc.center.y + arc.radius * t.sin()) as f32);
+                        if k == 0 {
+                            pb.move_to(px, py);
+                        } else {
+                            pb.line_to(px, py);
+                        }
+                    }
+                }
+                BlockEntity::Polyline(pl) => {
+                    let pts = pl.densify(std::f64::consts::PI / 16.0);
+                    for (k, p) in pts.iter().enumerate() {
+                        if k == 0 {
+                            pb.move_to(tx(p.x as f32), ty(p.y as f32));
+                        } else {
+                            pb.line_to(tx(p.x as f32), ty(p.y as f32));
+                        }
+                    }
+                    if pl.closed {
+                        pb.close();
+                    }
+                }
+                BlockEntity::Text {
+                    position,
+                    height,
+                    value,
+                    ..
+                } => {
+                    draw_text(
+                        &mut pixmap,
+                        &value,
+                        &FONT,
+                        tx(position.x as f32),
+                        ty(position.y as f32),
+                        Color::from_rgba8(0, 200, 120, 255),
+                        (height as f32 * zoom_val).max(6.0),
+                    );
+                }
+                BlockEntity::Insert(nested) => {
+                    // Nested inserts left by the library are drawn as a cross too.
+                    let (x, y) = (tx(nested.position.x as f32), ty(nested.position.y as f32));
+                    pb.move_to(x - 4.0, y - 4.0);
+                    pb.line_to(x + 4.0, y + 4.0);
+                    pb.move_to(x - 4.0, y + 4.0);
+                    pb.line_to(x + 4.0, y - 4.0);
+                }
+            }
+            if let Some(path) = pb.finish() {
+                pixmap.stroke_path(&path, &paint, &block_stroke, Transform::identity(), None);
+            }
+        }
+    }
+
     paint.set_color(Color::from_rgba8(200, 200, 0, 255));
     for (i, dim) in data.dimensions.iter().enumerate() {
         let selected = state.selected_dimensions.borrow().contains(&i);
@@ -1264,14 +1641,76 @@ fn render_workspace(
     }
 
     if let Some(sp) = state.snap_target.borrow().as_ref() {
+        use survey_cad::snap::SnapKind;
         paint.set_color(Color::from_rgba8(255, 0, 0, 255));
-        let r = 3.0;
-        let (sx, sy) = (tx(sp.x as f32), ty(sp.y as f32));
+        let r = 4.0;
+        let (sx, sy) = (tx(sp.point.x as f32), ty(sp.point.y as f32));
         let mut pb = PathBuilder::new();
-        pb.move_to(sx - r, sy);
-        pb.line_to(sx + r, sy);
-        pb.move_to(sx, sy - r);
-        pb.line_to(sx, sy + r);
+        match sp.kind {
+            SnapKind::Endpoint | SnapKind::Node => {
+                pb.push_rect(tiny_skia::Rect::from_ltrb(sx - r, sy - r, sx + r, sy + r).unwrap());
+            }
+            SnapKind::Midpoint => {
+                pb.move_to(sx - r, sy + r);
+                pb.line_to(sx, sy - r);
+                pb.line_to(sx + r, sy + r);
+                pb.close();
+            }
+            SnapKind::Centre => pb.push_circle(sx, sy, r),
+            SnapKind::Quadrant => {
+                pb.move_to(sx - r, sy);
+                pb.line_to(sx, sy - r);
+                pb.line_to(sx + r, sy);
+                pb.line_to(sx, sy + r);
+                pb.close();
+            }
+            SnapKind::Intersection | SnapKind::ApparentIntersection => {
+                pb.move_to(sx - r, sy - r);
+                pb.line_to(sx + r, sy + r);
+                pb.move_to(sx - r, sy + r);
+                pb.line_to(sx + r, sy - r);
+                if sp.kind == SnapKind::ApparentIntersection {
+                    pb.push_rect(
+                        tiny_skia::Rect::from_ltrb(sx - r, sy - r, sx + r, sy + r).unwrap(),
+                    );
+                }
+            }
+            SnapKind::Perpendicular => {
+                pb.move_to(sx - r, sy - r);
+                pb.line_to(sx - r, sy + r);
+                pb.line_to(sx + r, sy + r);
+                pb.move_to(sx - r, sy);
+                pb.line_to(sx, sy);
+                pb.line_to(sx, sy + r);
+            }
+            SnapKind::Tangent => {
+                pb.push_circle(sx, sy, r * 0.7);
+                pb.move_to(sx - r, sy - r);
+                pb.line_to(sx + r, sy - r);
+            }
+            SnapKind::Nearest => {
+                pb.move_to(sx - r, sy - r);
+                pb.line_to(sx + r, sy - r);
+                pb.line_to(sx - r, sy + r);
+                pb.line_to(sx + r, sy + r);
+                pb.close();
+            }
+            SnapKind::Insertion => {
+                pb.move_to(sx - r, sy - r);
+                pb.line_to(sx, sy - r);
+                pb.line_to(sx, sy);
+                pb.line_to(sx + r, sy);
+                pb.line_to(sx + r, sy + r);
+                pb.line_to(sx - r, sy + r);
+                pb.close();
+            }
+            SnapKind::Extension | SnapKind::PolarTracking | SnapKind::ObjectTracking => {
+                pb.move_to(sx - r, sy);
+                pb.line_to(sx + r, sy);
+                pb.move_to(sx, sy - r);
+                pb.line_to(sx, sy + r);
+            }
+        }
         if let Some(path) = pb.finish() {
             pixmap.stroke_path(
                 &path,
@@ -1508,8 +1947,12 @@ fn grade_at(profile: &VerticalAlignment, station: f64) -> Option<f64> {
     None
 }
 
-fn read_line_csv(path: &str, dst_epsg: u32) -> std::io::Result<(Point, Point)> {
-    let pts = survey_cad::io::read_points_csv(path, Some(4326), Some(dst_epsg))?;
+fn read_line_csv(
+    path: &str,
+    dst_epsg: u32,
+    ground: Option<GroundCoordinateSystem>,
+) -> std::io::Result<(Point, Point)> {
+    let pts = read_points_list(path, dst_epsg, ground)?;
     if pts.len() != 2 {
         return Err(std::io::Error::new(
             std::io::ErrorKind::InvalidData,
@@ -1519,8 +1962,34 @@ fn read_line_csv(path: &str, dst_epsg: u32) -> std::io::Result<(Point, Point)> {
     Ok((pts[0], pts[1]))
 }
 
-fn read_points_list(path: &str, dst_epsg: u32) -> std::io::Result<Vec<Point>> {
-    survey_cad::io::read_points_csv(path, Some(4326), Some(dst_epsg))
+fn read_points_list(
+    path: &str,
+    dst_epsg: u32,
+    ground: Option<GroundCoordinateSystem>,
+) -> std::io::Result<Vec<Point>> {
+    match ground {
+        Some(g) => survey_cad::io::read_points_csv_ground(path, Some(4326), Some(dst_epsg), &g),
+        None => survey_cad::io::read_points_csv(path, Some(4326), Some(dst_epsg)),
+    }
+}
+
+/// Converts grid coordinates read from a file to the project ground system,
+/// if one is set.
+fn to_ground(ground: Option<GroundCoordinateSystem>, p: Point) -> Point {
+    ground.map_or(p, |g| g.grid_to_ground(p))
+}
+
+/// Converts project coordinates back to grid for writing to a file.
+fn to_grid(ground: Option<GroundCoordinateSystem>, p: Point) -> Point {
+    ground.map_or(p, |g| g.ground_to_grid(p))
+}
+
+fn to_ground3(ground: Option<GroundCoordinateSystem>, p: ScPoint3) -> ScPoint3 {
+    ground.map_or(p, |g| g.grid_to_ground3(p))
+}
+
+fn to_grid3(ground: Option<GroundCoordinateSystem>, p: ScPoint3) -> ScPoint3 {
+    ground.map_or(p, |g| g.ground_to_grid3(p))
 }
 
 fn read_arc_csv(path: &str) -> std::io::Result<Arc> {
@@ -1870,6 +2339,8 @@ fn main() -> Result<(), slint::PlatformError> {
     let polygons = Rc::new(RefCell::new(Vec::<Vec<Point>>::new()));
     let polylines = Rc::new(RefCell::new(Vec::<Polyline>::new()));
     let arcs = Rc::new(RefCell::new(Vec::<Arc>::new()));
+    let block_inserts = Rc::new(RefCell::new(Vec::<BlockInsert>::new()));
+    let symbol_library = Rc::new(RefCell::new(SymbolLibrary::new()));
     let dimensions = Rc::new(RefCell::new(Vec::<LinearDimension>::new()));
     let surfaces = Rc::new(RefCell::new(Vec::<Tin>::new()));
     let surface_units = Rc::new(RefCell::new(Vec::<String>::new()));
@@ -1895,6 +2366,10 @@ fn main() -> Result<(), slint::PlatformError> {
     let offset = Rc::new(RefCell::new(Vec2::default()));
     let grid_settings = Rc::new(RefCell::new(GridSettings::default()));
     let workspace_crs = Rc::new(RefCell::new(4326u32));
+    let workspace_vertical_datum: Rc<RefCell<Option<survey_cad::geoid::VerticalDatum>>> =
+        Rc::new(RefCell::new(None));
+    let workspace_ground: Rc<RefCell<Option<GroundCoordinateSystem>>> =
+        Rc::new(RefCell::new(None));
     let pan_2d_flag = Rc::new(RefCell::new(false));
     let last_pos_2d = Rc::new(RefCell::new((0.0_f64, 0.0_f64)));
     let rotate_flag = Rc::new(RefCell::new(false));
@@ -1908,7 +2383,8 @@ fn main() -> Result<(), slint::PlatformError> {
     let selected_dimensions = Rc::new(RefCell::new(Vec::<usize>::new()));
     let drag_select = Rc::new(RefCell::new(DragSelect::default()));
     let cursor_feedback = Rc::new(RefCell::new(None));
-    let snap_target = Rc::new(RefCell::new(None::<Point>));
+    let snap_target = Rc::new(RefCell::new(None::<survey_cad::snap::Snap>));
+    let snap_state = Rc::new(RefCell::new(snap::SnapState::default()));
     let drawing_mode = Rc::new(RefCell::new(DrawingMode::None));
     let last_click = Rc::new(RefCell::new(None));
     let selected_surface = Rc::new(RefCell::new(None::<usize>));
@@ -2028,6 +2504,8 @@ fn main() -> Result<(), slint::PlatformError> {
     let render_image = {
         let app_weak = app.as_weak();
         let point_db = point_db.clone();
+        let block_inserts = block_inserts.clone();
+        let symbol_library = symbol_library.clone();
         let lines = lines.clone();
         let polygons = polygons.clone();
         let polylines = polylines.clone();
@@ -2074,6 +2552,8 @@ fn main() -> Result<(), slint::PlatformError> {
                     dimensions: &dimensions.borrow(),
                     surfaces: &surfaces.borrow(),
                     alignments: &alignments.borrow(),
+                    inserts: &block_inserts.borrow(),
+                    symbols: &symbol_library.borrow(),
                 },
                 &RenderState {
                     offset: &offset,
@@ -2717,6 +3197,7 @@ fn main() -> Result<(), slint::PlatformError> {
         let backend = backend.clone();
         let command_stack = command_stack.clone();
         let dimensions = dimensions.clone();
+        let snap_state = snap_state.clone();
         app.on_key_pressed(move |key| {
             if key.as_str() == "\u{001a}" {
                 let ctx = Context {
@@ -2746,6 +3227,7 @@ fn main() -> Result<(), slint::PlatformError> {
                 }
             } else if key.as_str() == "\u{001b}" {
                 *drawing_mode.borrow_mut() = DrawingMode::None;
+                snap_state.borrow_mut().reset();
                 if let Some(app) = weak.upgrade() {
                     if app.get_workspace_mode() == 0 {
                         app.set_workspace_image(render_image());
@@ -2838,6 +3320,8 @@ fn main() -> Result<(), slint::PlatformError> {
         let macro_playing = macro_playing.clone();
         let macro_recorder = macro_recorder.clone();
         let snap_target = snap_target.clone();
+        let snap_state = snap_state.clone();
+        let snap_prefs = snap_prefs.clone();
         app.on_workspace_pointer_pressed(move |x, y, ev| {
             if *drawing_mode.borrow() != DrawingMode::None {
                 if ev.button == PointerEventButton::Left {
@@ -2860,21 +3344,15 @@ fn main() -> Result<(), slint::PlatformError> {
                                 polylines: &polylines.borrow(),
                                 arcs: &arcs_ref.borrow(),
                             };
-                            let opts = snap::SnapOptions {
-                                snap_points: app.get_snap_points(),
-                                snap_endpoints: app.get_snap_endpoints(),
-                                snap_midpoints: app.get_snap_midpoints(),
-                                snap_intersections: app.get_snap_intersections(),
-                                snap_nearest: app.get_snap_nearest(),
-                            };
-                            if let Some(sp) = snap::resolve_snap(
+                            let opts = snap_prefs.borrow().options();
+                            if let Some(sp) = snap_state.borrow_mut().snap(
                                 p,
                                 &scene,
                                 app.get_snap_tolerance() as f64 / (zoom_factor as f64),
                                 opts,
                             ) {
                                 *snap_target.borrow_mut() = Some(sp);
-                                p = sp;
+                                p = sp.point;
                             } else {
                                 *snap_target.borrow_mut() = None;
                             }
@@ -2885,6 +3363,9 @@ fn main() -> Result<(), slint::PlatformError> {
                             p.x = p.x.round();
                             p.y = p.y.round();
                         }
+                        snap_state
+                            .borrow_mut()
+                            .pick(p, snap_target.borrow().as_ref());
                         let mut mode = drawing_mode.borrow_mut();
                         match &mut *mode {
                             DrawingMode::Line { start } => {
@@ -3010,7 +3491,11 @@ fn main() -> Result<(), slint::PlatformError> {
                             }
                             _ => {}
                         }
+                        let finished = *mode == DrawingMode::None;
                         drop(mode);
+                        if finished {
+                            snap_state.borrow_mut().reset();
+                        }
                         if app.get_workspace_mode() == 0 {
                             app.set_workspace_image(render_image());
                             app.window().request_redraw();
@@ -3208,6 +3693,8 @@ fn main() -> Result<(), slint::PlatformError> {
         let arcs_ref = arcs.clone();
         let current_line = current_line.clone();
         let snap_target = snap_target.clone();
+        let snap_state = snap_state.clone();
+        let snap_prefs = snap_prefs.clone();
         let weak = app.as_weak();
         let active_handle_ref = active_handle.clone();
         let backend_move = backend.clone();
@@ -3294,21 +3781,15 @@ fn main() -> Result<(), slint::PlatformError> {
                             polylines: &polylines.borrow(),
                             arcs: &arcs_ref.borrow(),
                         };
-                        let opts = snap::SnapOptions {
-                            snap_points: app.get_snap_points(),
-                            snap_endpoints: app.get_snap_endpoints(),
-                            snap_midpoints: app.get_snap_midpoints(),
-                            snap_intersections: app.get_snap_intersections(),
-                            snap_nearest: app.get_snap_nearest(),
-                        };
-                        if let Some(sp) = snap::resolve_snap(
+                        let opts = snap_prefs.borrow().options();
+                        if let Some(sp) = snap_state.borrow_mut().snap(
                             p,
                             &scene,
                             app.get_snap_tolerance() as f64 / (zoom_factor as f64),
                             opts,
                         ) {
                             *snap_target.borrow_mut() = Some(sp);
-                            p = sp;
+                            p = sp.point;
                         } else {
                             *snap_target.borrow_mut() = None;
                         }
@@ -3398,6 +3879,8 @@ fn main() -> Result<(), slint::PlatformError> {
         let dimensions = dimensions.clone();
         let selected_dimensions = selected_dimensions.clone();
         let workspace_crs = workspace_crs.clone();
+        let workspace_vertical_datum = workspace_vertical_datum.clone();
+        let workspace_ground = workspace_ground.clone();
         let crs_entries_rc = crs_entries_rc.clone();
         app.on_new_project(move || {
             point_db.borrow_mut().clear();
@@ -3422,6 +3905,8 @@ fn main() -> Result<(), slint::PlatformError> {
             if let Some(app) = weak.upgrade() {
                 app.set_status(SharedString::from("New project created"));
                 *workspace_crs.borrow_mut() = 4326;
+                *workspace_vertical_datum.borrow_mut() = None;
+                *workspace_ground.borrow_mut() = None;
                 if let Some(idx) = crs_entries_rc
                     .iter()
                     .position(|e| e.code == "EPSG:4326")
@@ -3457,6 +3942,8 @@ fn main() -> Result<(), slint::PlatformError> {
         let last_dir = last_folder.clone();
         let config_rc = config.clone();
         let workspace_crs = workspace_crs.clone();
+        let workspace_vertical_datum = workspace_vertical_datum.clone();
+        let workspace_ground = workspace_ground.clone();
         let crs_entries_rc = crs_entries_rc.clone();
         let alignments = alignments.clone();
         app.on_open_project(move || {
@@ -3472,6 +3959,8 @@ fn main() -> Result<(), slint::PlatformError> {
                     match read_project_json(p) {
                         Ok(proj) => {
                             *workspace_crs.borrow_mut() = proj.crs_epsg;
+                            *workspace_vertical_datum.borrow_mut() = proj.vertical_datum.clone();
+                            *workspace_ground.borrow_mut() = proj.ground;
                             if let Some(idx) = crs_entries_rc
                                 .iter()
                                 .position(|e| e.code == format!("EPSG:{}", proj.crs_epsg))
@@ -3579,6 +4068,8 @@ fn main() -> Result<(), slint::PlatformError> {
         let last_dir = last_folder.clone();
         let config_rc = config.clone();
         let workspace_crs = workspace_crs.clone();
+        let workspace_vertical_datum = workspace_vertical_datum.clone();
+        let workspace_ground = workspace_ground.clone();
         let surface_units_ref = surface_units.clone();
         let surface_styles_ref = surface_styles.clone();
         let surface_descriptions_ref = surface_descriptions.clone();
@@ -3611,8 +4102,11 @@ fn main() -> Result<(), slint::PlatformError> {
                         polygon_style_indices: polygon_style_indices.borrow().clone(),
                         grid: grid_settings.borrow().clone(),
                         crs_epsg: *workspace_crs.borrow(),
+                        vertical_datum: workspace_vertical_datum.borrow().clone(),
+                        ground: *workspace_ground.borrow(),
                         point_label_font: point_label_style.borrow().text_style.font.clone(),
                         point_label_offset: point_label_style.borrow().offset,
+                        ..Project::new()
                     };
                     let base = Path::new(p);
                     let _ = save_layers(&base.with_extension("layers.json"), &layers_ref.borrow());
@@ -3646,6 +4140,7 @@ fn main() -> Result<(), slint::PlatformError> {
         let macro_playing_outer = macro_playing.clone();
         let macro_recorder_outer = macro_recorder.clone();
         let workspace_crs_line = workspace_crs.clone();
+        let workspace_ground_line = workspace_ground.clone();
         app.on_add_line(move || {
             let macro_playing = macro_playing_outer.clone();
             let macro_recorder = macro_recorder_outer.clone();
@@ -3658,6 +4153,7 @@ fn main() -> Result<(), slint::PlatformError> {
                 let weak = weak.clone();
                 let dlg_weak = dlg_weak.clone();
                 let workspace_crs = workspace_crs_line.clone();
+                let workspace_ground = workspace_ground_line.clone();
                 let line_style_indices = line_style_indices.clone();
                 let refresh_line_style_dialogs = refresh_line_style_dialogs.clone();
                 let backend_render = backend_render.clone();
@@ -3670,7 +4166,7 @@ fn main() -> Result<(), slint::PlatformError> {
                         .pick_file()
                     {
                         if let Some(p) = path.to_str() {
-                            match read_line_csv(p, *workspace_crs.borrow()) {
+                            match read_line_csv(p, *workspace_crs.borrow(), *workspace_ground.borrow()) {
                                 Ok(l) => {
                                     lines.borrow_mut().push(l);
                                     let (s, e) = l;
@@ -3826,6 +4322,7 @@ fn main() -> Result<(), slint::PlatformError> {
         let macro_playing_outer = macro_playing.clone();
         let macro_recorder_outer = macro_recorder.clone();
         let workspace_crs_point = workspace_crs.clone();
+        let workspace_ground_point = workspace_ground.clone();
         app.on_add_point(move || {
             let macro_playing = macro_playing_outer.clone();
             let macro_recorder = macro_recorder_outer.clone();
@@ -3838,6 +4335,7 @@ fn main() -> Result<(), slint::PlatformError> {
                 let dlg_weak = dlg_weak.clone();
                 let point_style_indices = point_style_indices.clone();
                 let workspace_crs = workspace_crs_point.clone();
+                let workspace_ground = workspace_ground_point.clone();
                 let backend_render = backend_render.clone();
                 dlg.on_from_file(move || {
                     if let Some(path) = rfd::FileDialog::new()
@@ -3847,7 +4345,7 @@ fn main() -> Result<(), slint::PlatformError> {
                         .pick_file()
                     {
                         if let Some(p) = path.to_str() {
-                            match survey_cad::io::read_points_csv(p, Some(4326), Some(*workspace_crs.borrow())) {
+                            match read_points_list(p, *workspace_crs.borrow(), *workspace_ground.borrow()) {
                                 Ok(pts) => {
                                     let len = {
                                         let mut db = point_db.borrow_mut();
@@ -3982,6 +4480,7 @@ fn main() -> Result<(), slint::PlatformError> {
         let polygons = polygons.clone();
         let render_image = render_image.clone();
         let workspace_crs_polygon = workspace_crs.clone();
+        let workspace_ground_polygon = workspace_ground.clone();
         app.on_add_polygon(move || {
             let dlg = AddPolygonDialog::new().unwrap();
             let dlg_weak = dlg.as_weak();
@@ -3991,6 +4490,7 @@ fn main() -> Result<(), slint::PlatformError> {
                 let weak = weak.clone();
                 let dlg_weak = dlg_weak.clone();
                 let workspace_crs = workspace_crs_polygon.clone();
+                let workspace_ground = workspace_ground_polygon.clone();
                 dlg.on_from_file(move || {
                     if let Some(path) = rfd::FileDialog::new()
                         .add_filter("DWG", &["dwg"])
@@ -3999,7 +4499,7 @@ fn main() -> Result<(), slint::PlatformError> {
                         .pick_file()
                     {
                         if let Some(p) = path.to_str() {
-                            match read_points_list(p, *workspace_crs.borrow()) {
+                            match read_points_list(p, *workspace_crs.borrow(), *workspace_ground.borrow()) {
                                 Ok(pts) => {
                                     if pts.len() >= 3 {
                                         polygons.borrow_mut().push(pts);
@@ -4108,6 +4608,7 @@ fn main() -> Result<(), slint::PlatformError> {
         let polylines = polylines.clone();
         let render_image = render_image.clone();
         let workspace_crs_polyline = workspace_crs.clone();
+        let workspace_ground_polyline = workspace_ground.clone();
         app.on_add_polyline(move || {
             let dlg = AddPolylineDialog::new().unwrap();
             let dlg_weak = dlg.as_weak();
@@ -4117,13 +4618,14 @@ fn main() -> Result<(), slint::PlatformError> {
                 let weak = weak.clone();
                 let dlg_weak = dlg_weak.clone();
                 let workspace_crs = workspace_crs_polyline.clone();
+                let workspace_ground = workspace_ground_polyline.clone();
                 dlg.on_from_file(move || {
                     if let Some(path) = rfd::FileDialog::new()
                         .add_filter("CSV", &["csv"])
                         .pick_file()
                     {
                         if let Some(p) = path.to_str() {
-                            match read_points_list(p, *workspace_crs.borrow()) {
+                            match read_points_list(p, *workspace_crs.borrow(), *workspace_ground.borrow()) {
                                 Ok(pts) => {
                                     if pts.len() >= 2 {
                                         polylines.borrow_mut().push(Polyline::new(pts));
@@ -4376,10 +4878,12 @@ fn main() -> Result<(), slint::PlatformError> {
 
     {
         let weak = app.as_weak();
+        let workspace_ground = workspace_ground.clone();
         app.on_station_distance(move || {
             let dlg = StationDistanceDialog::new().unwrap();
             let dlg_weak = dlg.as_weak();
             let weak2 = weak.clone();
+            let ground = *workspace_ground.borrow();
             dlg.on_accept(move || {
                 if let Some(d) = dlg_weak.upgrade() {
                     let res = (|| {
@@ -4394,7 +4898,15 @@ fn main() -> Result<(), slint::PlatformError> {
                     })();
                     if let Some(app) = weak2.upgrade() {
                         if let Some(dist) = res {
-                            app.set_status(SharedString::from(format!("Distance: {dist:.3}")));
+                            // project coordinates are on the ground
+                            let msg = match ground {
+                                Some(g) => format!(
+                                    "Distance: {dist:.3} ground, {:.3} grid",
+                                    g.ground_distance_to_grid(dist)
+                                ),
+                                None => format!("Distance: {dist:.3}"),
+                            };
+                            app.set_status(SharedString::from(msg));
                         } else {
                             app.set_status(SharedString::from("Invalid input"));
                         }
@@ -4415,6 +4927,7 @@ fn main() -> Result<(), slint::PlatformError> {
     {
         let weak = app.as_weak();
         let workspace_crs = workspace_crs.clone();
+        let workspace_ground = workspace_ground.clone();
         app.on_traverse_area(move || {
             if let Some(path) = rfd::FileDialog::new()
                 .add_filter("DWG", &["dwg"])
@@ -4423,7 +4936,7 @@ fn main() -> Result<(), slint::PlatformError> {
                 .pick_file()
             {
                 if let (Some(p), Some(app)) = (path.to_str(), weak.upgrade()) {
-                    match survey_cad::io::read_points_csv(p, Some(4326), Some(*workspace_crs.borrow())) {
+                    match read_points_list(p, *workspace_crs.borrow(), *workspace_ground.borrow()) {
                         Ok(pts) => {
                             let trav = survey_cad::surveying::Traverse::new(pts);
                             app.set_status(SharedString::from(format!("Area: {:.3}", trav.area())));
@@ -4471,6 +4984,57 @@ fn main() -> Result<(), slint::PlatformError> {
         });
     }
 
+    {
+        let weak = app.as_weak();
+        let alignments = alignments.clone();
+        let workspace_ground = workspace_ground.clone();
+        app.on_stakeout(move || {
+            let dlg = StakeoutDialog::new().unwrap();
+            dlg.set_station("0".into());
+            dlg.set_offset("0".into());
+            let dlg_weak = dlg.as_weak();
+            let weak2 = weak.clone();
+            let alignments = alignments.clone();
+            let ground = *workspace_ground.borrow();
+            dlg.on_accept(move || {
+                if let Some(d) = dlg_weak.upgrade() {
+                    let res = (|| {
+                        let station = d.get_station().parse::<f64>().ok()?;
+                        let offset = d.get_offset().parse::<f64>().ok()?;
+                        let aligns = alignments.borrow();
+                        let hal = &aligns.first()?.horizontal;
+                        // alignments are designed on the ground; rovers work on the grid
+                        match ground {
+                            Some(g) => {
+                                survey_cad::surveying::stakeout_position_grid(hal, station, offset, &g)
+                            }
+                            None => survey_cad::surveying::stakeout_position(hal, station, offset),
+                        }
+                    })();
+                    if let Some(app) = weak2.upgrade() {
+                        if let Some(p) = res {
+                            let grid = if ground.is_some() { " (grid)" } else { "" };
+                            app.set_status(SharedString::from(format!(
+                                "Stakeout: E {:.3} N {:.3}{grid}",
+                                p.x, p.y
+                            )));
+                        } else {
+                            app.set_status(SharedString::from("Invalid input"));
+                        }
+                    }
+                    let _ = d.hide();
+                }
+            });
+            let dlg_weak2 = dlg.as_weak();
+            dlg.on_cancel(move || {
+                if let Some(d) = dlg_weak2.upgrade() {
+                    let _ = d.hide();
+                }
+            });
+            dlg.show().unwrap();
+        });
+    }
+
     {
         let weak = app.as_weak();
         let surfaces_clone = surfaces.clone();
@@ -4736,6 +5300,72 @@ fn main() -> Result<(), slint::PlatformError> {
         });
     }
 
+    {
+        let weak = app.as_weak();
+        let draft = DraftState {
+            lines: lines.clone(),
+            line_styles: line_style_indices.clone(),
+            polylines: polylines.clone(),
+            arcs: arcs.clone(),
+            selected_lines: selected_lines.clone(),
+            selected_polylines: selected_polylines.clone(),
+            selected_arcs: selected_arcs.clone(),
+            backend: backend.clone(),
+        };
+        let render_image = render_image.clone();
+        app.on_drafting_edit(move |op| {
+            let op = op.to_string();
+            let finish = {
+                let weak = weak.clone();
+                let draft = draft.clone();
+                let render_image = render_image.clone();
+                move |op: &str, a: f64, b: f64| {
+                    let result = draft.apply(op, a, b);
+                    if let Some(app) = weak.upgrade() {
+                        if let Err(e) = result {
+                            app.set_status(SharedString::from(e));
+                        }
+                        app.set_workspace_image(render_image());
+                        app.window().request_redraw();
+                    }
+                }
+            };
+            let (title, first, second) = match op.as_str() {
+                "join" => return finish(&op, 0.0, 0.0),
+                "offset" => ("Offset", "Distance:", None),
+                "fillet" => ("Fillet", "Radius:", None),
+                "chamfer" => ("Chamfer", "First distance:", Some("Second distance:")),
+                "trim" => ("Trim at Pick Point", "X:", Some("Y:")),
+                "extend" => ("Extend Nearest End to Pick Point", "X:", Some("Y:")),
+                "break" => ("Break at Point", "X:", Some("Y:")),
+                _ => return,
+            };
+            let dlg = DraftEditDialog::new().unwrap();
+            dlg.set_dialog_title(title.into());
+            dlg.set_first_label(first.into());
+            dlg.set_first_value("0".into());
+            dlg.set_show_second(second.is_some());
+            dlg.set_second_label(second.unwrap_or_default().into());
+            dlg.set_second_value("0".into());
+            let dlg_weak = dlg.as_weak();
+            dlg.on_accept(move || {
+                if let Some(d) = dlg_weak.upgrade() {
+                    let a = d.get_first_value().parse::<f64>().unwrap_or(0.0);
+                    let b = d.get_second_value().parse::<f64>().unwrap_or(0.0);
+                    finish(&op, a, b);
+                    let _ = d.hide();
+                }
+            });
+            let dlg_weak2 = dlg.as_weak();
+            dlg.on_cancel(move || {
+                if let Some(d) = dlg_weak2.upgrade() {
+                    let _ = d.hide();
+                }
+            });
+            dlg.show().unwrap();
+        });
+    }
+
     {
         let weak = app.as_weak();
         let polylines_ref = polylines.clone();
@@ -5342,6 +5972,7 @@ fn main() -> Result<(), slint::PlatformError> {
         let render_image = render_image.clone();
         let backend_render = backend.clone();
         let workspace_crs = workspace_crs.clone();
+        let workspace_ground = workspace_ground.clone();
         app.on_import_geojson(move || {
             if let Some(path) = rfd::FileDialog::new()
                 .add_filter("GeoJSON", &["geojson", "json"])
@@ -5354,7 +5985,8 @@ fn main() -> Result<(), slint::PlatformError> {
                             let len = {
                                 let mut db = point_db.borrow_mut();
                                 db.clear();
-                                db.extend(pts);
+                                let ground = *workspace_ground.borrow();
+                                db.extend(pts.into_iter().map(|p| to_ground(ground, p)));
                                 backend_render.borrow_mut().clear();
                                 for pt in db.iter() {
                                     backend_render.borrow_mut().add_point(pt.x, pt.y, 0.0);
@@ -5392,6 +6024,7 @@ fn main() -> Result<(), slint::PlatformError> {
         let point_db = point_db.clone();
         let render_image = render_image.clone();
         let backend_render = backend.clone();
+        let workspace_ground = workspace_ground.clone();
         app.on_import_kml(move || {
             if let Some(path) = rfd::FileDialog::new()
                 .add_filter("KML", &["kml", "kmz"])
@@ -5404,7 +6037,8 @@ fn main() -> Result<(), slint::PlatformError> {
                             let len = {
                                 let mut db = point_db.borrow_mut();
                                 db.clear();
-                                db.extend(pts);
+                                let ground = *workspace_ground.borrow();
+                                db.extend(pts.into_iter().map(|p| to_ground(ground, p)));
                                 db.len()
                             };
                             if let Some(app) = weak.upgrade() {
@@ -5440,28 +6074,55 @@ fn main() -> Result<(), slint::PlatformError> {
     {
         let weak = app.as_weak();
         let point_db = point_db.clone();
+        let block_inserts = block_inserts.clone();
+        let symbol_library = symbol_library.clone();
         let render_image = render_image.clone();
         let backend_render = backend.clone();
+        let workspace_ground = workspace_ground.clone();
         app.on_import_dxf(move || {
             if let Some(path) = rfd::FileDialog::new()
                 .add_filter("DXF", &["dxf"])
                 .pick_file()
             {
                 if let Some(p) = path.to_str() {
-                    match survey_cad::io::read_dxf(p) {
-                        Ok(ents) => {
+                    match survey_cad::io::dxf::read_dxf_document(p) {
+                        Ok(doc) => {
+                            {
+                                let mut symbols = symbol_library.borrow_mut();
+                                for def in SymbolLibrary::from_document(&doc).blocks {
+                                    symbols.insert(def);
+                                }
+                            }
+                            *block_inserts.borrow_mut() = doc
+                                .entities
+                                .iter()
+                                .filter_map(BlockInsert::from_dxf)
+                                .map(|mut b| {
+                                    b.position = to_ground3(*workspace_ground.borrow(), b.position);
+                                    b
+                                })
+                                .collect();
+                            let blocks = block_inserts.borrow().len();
+                            let ents = doc.entities;
                             let len = {
+                                let ground = *workspace_ground.borrow();
                                 let mut db = point_db.borrow_mut();
                                 db.clear();
                                 db.extend(ents.into_iter().filter_map(|e| match e {
-                                    survey_cad::io::DxfEntity::Point { point, .. } => Some(point),
+                                    survey_cad::io::DxfEntity::Point { point, .. } => {
+                                        Some(to_ground(ground, point))
+                                    }
+                                    survey_cad::io::DxfEntity::Point3D { point, .. } => {
+                                        let p = Point::new(point.x, point.y);
+                                        Some(to_ground(ground, p))
+                                    }
                                     _ => None,
                                 }));
                                 db.len()
                             };
                             if let Some(app) = weak.upgrade() {
                                 app.set_status(SharedString::from(format!(
-                                    "Imported {len} points"
+                                    "Imported {len} points and {blocks} blocks"
                                 )));
                                 if app.get_workspace_mode() == 0 {
                                     app.set_workspace_image(render_image());
@@ -5485,11 +6146,108 @@ fn main() -> Result<(), slint::PlatformError> {
         });
     }
 
+    {
+        let weak = app.as_weak();
+        let symbol_library = symbol_library.clone();
+        let render_image = render_image.clone();
+        app.on_load_symbol_library(move || {
+            let Some(path) = rfd::FileDialog::new()
+                .add_filter("DXF", &["dxf"])
+                .pick_file()
+            else {
+                return;
+            };
+            let Some(p) = path.to_str() else {
+                return;
+            };
+            let msg = match SymbolLibrary::from_dxf(p) {
+                Ok(lib) => {
+                    let count = lib.blocks.len();
+                    let mut symbols = symbol_library.borrow_mut();
+                    for def in lib.blocks {
+                        symbols.insert(def);
+                    }
+                    format!("Loaded {count} symbols")
+                }
+                Err(e) => format!("Failed to load symbols: {e}"),
+            };
+            if let Some(app) = weak.upgrade() {
+                app.set_status(SharedString::from(msg));
+                app.set_workspace_image(render_image());
+                app.window().request_redraw();
+            }
+        });
+    }
+
+    {
+        let weak = app.as_weak();
+        let point_db = point_db.clone();
+        let polylines = polylines.clone();
+        let block_inserts = block_inserts.clone();
+        let render_image = render_image.clone();
+        let workspace_ground = workspace_ground.clone();
+        app.on_field_to_finish(move || {
+            let Some(points_path) = rfd::FileDialog::new()
+                .set_title("Coded points")
+                .add_filter("LandXML", &["xml"])
+                .pick_file()
+            else {
+                return;
+            };
+            let Some(codes_path) = rfd::FileDialog::new()
+                .set_title("Code library")
+                .add_filter("JSON", &["json"])
+                .pick_file()
+            else {
+                return;
+            };
+            let (Some(pp), Some(cp)) = (points_path.to_str(), codes_path.to_str()) else {
+                return;
+            };
+            let ground = *workspace_ground.borrow();
+            let result = survey_cad::io::landxml::read_landxml_points(pp).and_then(|mut set| {
+                let library = survey_cad::surveying::CodeLibrary::from_json(cp)?;
+                let mut db = survey_cad::surveying::PointDatabase::new();
+                for p in &mut set.points {
+                    p.point = to_ground3(ground, p.point);
+                    db.add_point(survey_cad::surveying::SurveyPoint::from(&*p));
+                }
+                let (lines, blocks) = db.field_to_finish(&library);
+                Ok((set.points, lines, blocks))
+            });
+            let msg = match result {
+                Ok((points, lines, blocks)) => {
+                    let msg = format!(
+                        "Placed {} points, {} lines and {} blocks",
+                        points.len(),
+                        lines.len(),
+                        blocks.len()
+                    );
+                    point_db
+                        .borrow_mut()
+                        .extend(points.iter().map(|p| Point::new(p.point.x, p.point.y)));
+                    polylines.borrow_mut().extend(lines);
+                    block_inserts
+                        .borrow_mut()
+                        .extend(blocks.iter().map(BlockInsert::from));
+                    msg
+                }
+                Err(e) => format!("Failed field to finish: {e}"),
+            };
+            if let Some(app) = weak.upgrade() {
+                app.set_status(SharedString::from(msg));
+                app.set_workspace_image(render_image());
+                app.window().request_redraw();
+            }
+        });
+    }
+
     {
         let weak = app.as_weak();
         let point_db = point_db.clone();
         let render_image = render_image.clone();
         let backend_render = backend.clone();
+        let workspace_ground = workspace_ground.clone();
         app.on_import_dwg(move || {
             if let Some(path) = rfd::FileDialog::new()
                 .add_filter("DWG", &["dwg"])
@@ -5500,10 +6258,17 @@ fn main() -> Result<(), slint::PlatformError> {
                     match survey_cad::io::read_dwg(p) {
                         Ok(ents) => {
                             let len = {
+                                let ground = *workspace_ground.borrow();
                                 let mut db = point_db.borrow_mut();
                                 db.clear();
                                 db.extend(ents.into_iter().filter_map(|e| match e {
-                                    survey_cad::io::DxfEntity::Point { point, .. } => Some(point),
+                                    survey_cad::io::DxfEntity::Point { point, .. } => {
+                                        Some(to_ground(ground, point))
+                                    }
+                                    survey_cad::io::DxfEntity::Point3D { point, .. } => {
+                                        let p = Point::new(point.x, point.y);
+                                        Some(to_ground(ground, p))
+                                    }
                                     _ => None,
                                 }));
                                 db.len()
@@ -5543,6 +6308,7 @@ fn main() -> Result<(), slint::PlatformError> {
         let render_image = render_image.clone();
         let backend_render = backend.clone();
         let workspace_crs = workspace_crs.clone();
+        let workspace_ground = workspace_ground.clone();
         app.on_import_shp(move || {
             if let Some(path) = rfd::FileDialog::new()
                 .add_filter("SHP", &["shp"])
@@ -5560,6 +6326,7 @@ fn main() -> Result<(), slint::PlatformError> {
                                     p.x = x;
                                     p.y = y;
                                 }
+                                *p = to_ground(*workspace_ground.borrow(), *p);
                             }
                             let len = {
                                 let mut db = point_db.borrow_mut();
@@ -5603,6 +6370,7 @@ fn main() -> Result<(), slint::PlatformError> {
         let polylines_ref = polylines.clone();
         let render_image = render_image.clone();
         let backend_render = backend.clone();
+        let workspace_ground = workspace_ground.clone();
         app.on_import_polylines_shp(move || {
             if let Some(path) = rfd::FileDialog::new()
                 .add_filter("SHP", &["shp"])
@@ -5616,7 +6384,10 @@ fn main() -> Result<(), slint::PlatformError> {
                             let mut pls_vec = polylines_ref.borrow_mut();
                             lns.clear();
                             pls_vec.clear();
-                            for pl in pls {
+                            for mut pl in pls {
+                                for v in &mut pl.vertices {
+                                    *v = to_ground(*workspace_ground.borrow(), *v);
+                                }
                                 if pl.vertices.len() == 2 {
                                     lns.push((pl.vertices[0], pl.vertices[1]));
                                 } else {
@@ -5659,6 +6430,7 @@ fn main() -> Result<(), slint::PlatformError> {
         let polygons_ref = polygons.clone();
         let render_image = render_image.clone();
         let backend_render = backend.clone();
+        let workspace_ground = workspace_ground.clone();
         app.on_import_polygons_shp(move || {
             if let Some(path) = rfd::FileDialog::new()
                 .add_filter("SHP", &["shp"])
@@ -5669,9 +6441,12 @@ fn main() -> Result<(), slint::PlatformError> {
                     match survey_cad::io::shp::read_polygons_shp(p) {
                         Ok((polys, _)) => {
                             let len = {
+                                let ground = *workspace_ground.borrow();
                                 let mut pg = polygons_ref.borrow_mut();
                                 pg.clear();
-                                pg.extend(polys);
+                                pg.extend(polys.into_iter().map(|poly| {
+                                    poly.into_iter().map(|p| to_ground(ground, p)).collect()
+                                }));
                                 pg.len()
                             };
                             if let Some(app) = weak.upgrade() {
@@ -5709,6 +6484,7 @@ fn main() -> Result<(), slint::PlatformError> {
         let point_db = point_db.clone();
         let render_image = render_image.clone();
         let backend_render = backend.clone();
+        let workspace_ground = workspace_ground.clone();
         app.on_import_las(move || {
             if let Some(path) = rfd::FileDialog::new()
                 .add_filter("LAS", &["las", "laz"])
@@ -5721,7 +6497,11 @@ fn main() -> Result<(), slint::PlatformError> {
                             let len = {
                                 let mut db = point_db.borrow_mut();
                                 db.clear();
-                                db.extend(pts3.into_iter().map(|p3| Point::new(p3.x, p3.y)));
+                                let ground = *workspace_ground.borrow();
+                                db.extend(
+                                    pts3.into_iter()
+                                        .map(|p3| to_ground(ground, Point::new(p3.x, p3.y))),
+                                );
                                 db.len()
                             };
                             if let Some(app) = weak.upgrade() {
@@ -5759,6 +6539,7 @@ fn main() -> Result<(), slint::PlatformError> {
         let point_db = point_db.clone();
         let render_image = render_image.clone();
         let backend_render = backend.clone();
+        let workspace_ground = workspace_ground.clone();
         app.on_import_e57(move || {
             if let Some(path) = rfd::FileDialog::new()
                 .add_filter("E57", &["e57"])
@@ -5771,7 +6552,11 @@ fn main() -> Result<(), slint::PlatformError> {
                             let len = {
                                 let mut db = point_db.borrow_mut();
                                 db.clear();
-                                db.extend(pts3.into_iter().map(|p3| Point::new(p3.x, p3.y)));
+                                let ground = *workspace_ground.borrow();
+                                db.extend(
+                                    pts3.into_iter()
+                                        .map(|p3| to_ground(ground, Point::new(p3.x, p3.y))),
+                                );
                                 db.len()
                             };
                             if let Some(app) = weak.upgrade() {
@@ -5807,14 +6592,19 @@ fn main() -> Result<(), slint::PlatformError> {
     {
         let weak = app.as_weak();
         let point_db = point_db.clone();
+        let workspace_ground = workspace_ground.clone();
         app.on_export_geojson(move || {
             if let Some(path) = rfd::FileDialog::new()
                 .add_filter("GeoJSON", &["geojson", "json"])
                 .save_file()
             {
                 if let Some(p) = path.to_str() {
-                    if let Err(e) =
-                        survey_cad::io::write_points_geojson(p, &point_db.borrow(), None, None)
+                    let grid: Vec<Point> = point_db
+                        .borrow()
+                        .iter()
+                        .map(|pt| to_grid(*workspace_ground.borrow(), *pt))
+                        .collect();
+                    if let Err(e) = survey_cad::io::write_points_geojson(p, &grid, None, None)
                     {
                         if let Some(app) = weak.upgrade() {
                             app.set_status(SharedString::from(format!("Failed to export: {e}")));
@@ -5830,6 +6620,7 @@ fn main() -> Result<(), slint::PlatformError> {
     {
         let weak = app.as_weak();
         let point_db = point_db.clone();
+        let workspace_ground = workspace_ground.clone();
         app.on_export_kml(move || {
             if let Some(path) = rfd::FileDialog::new()
                 .add_filter("KML", &["kml"])
@@ -5837,7 +6628,13 @@ fn main() -> Result<(), slint::PlatformError> {
             {
                 if let Some(p) = path.to_str() {
                     #[cfg(feature = "kml")]
-                    if let Err(e) = survey_cad::io::kml::write_points_kml(p, &point_db.borrow()) {
+                    let grid: Vec<Point> = point_db
+                        .borrow()
+                        .iter()
+                        .map(|pt| to_grid(*workspace_ground.borrow(), *pt))
+                        .collect();
+                    #[cfg(feature = "kml")]
+                    if let Err(e) = survey_cad::io::kml::write_points_kml(p, &grid) {
                         if let Some(app) = weak.upgrade() {
                             app.set_status(SharedString::from(format!("Failed to export: {e}")));
                         }
@@ -5856,15 +6653,38 @@ fn main() -> Result<(), slint::PlatformError> {
     {
         let weak = app.as_weak();
         let point_db = point_db.clone();
+        let block_inserts = block_inserts.clone();
+        let symbol_library = symbol_library.clone();
+        let workspace_ground = workspace_ground.clone();
         app.on_export_dxf(move || {
             if let Some(path) = rfd::FileDialog::new()
                 .add_filter("DXF", &["dxf"])
                 .save_file()
             {
                 if let Some(p) = path.to_str() {
-                    if let Err(e) =
-                        survey_cad::io::write_points_dxf(p, &point_db.borrow(), None, None)
-                    {
+                    // inserts carry their attributes and block definitions
+                    let ground = *workspace_ground.borrow();
+                    let inserts: Vec<BlockInsert> = block_inserts
+                        .borrow()
+                        .iter()
+                        .map(|b| BlockInsert {
+                            position: to_grid3(ground, b.position),
+                            ..b.clone()
+                        })
+                        .collect();
+                    let result = symbol_library
+                        .borrow()
+                        .to_document(&inserts)
+                        .and_then(|mut doc| {
+                            doc.entities.extend(point_db.borrow().iter().map(|pt| {
+                                survey_cad::io::DxfEntity::Point {
+                                    point: to_grid(ground, *pt),
+                                    layer: None,
+                                }
+                            }));
+                            survey_cad::io::dxf::write_dxf_document(p, &doc)
+                        });
+                    if let Err(e) = result {
                         if let Some(app) = weak.upgrade() {
                             app.set_status(SharedString::from(format!("Failed to export: {e}")));
                         }
@@ -5879,6 +6699,7 @@ fn main() -> Result<(), slint::PlatformError> {
     {
         let weak = app.as_weak();
         let point_db = point_db.clone();
+        let workspace_ground = workspace_ground.clone();
         app.on_export_dwg(move || {
             if let Some(path) = rfd::FileDialog::new()
                 .add_filter("DWG", &["dwg"])
@@ -5889,7 +6710,10 @@ fn main() -> Result<(), slint::PlatformError> {
                     let ents: Vec<survey_cad::io::DxfEntity> = point_db
                         .borrow()
                         .iter()
-                        .map(|pt| survey_cad::io::DxfEntity::Point { point: *pt, layer: None })
+                        .map(|pt| survey_cad::io::DxfEntity::Point {
+                            point: to_grid(*workspace_ground.borrow(), *pt),
+                            layer: None,
+                        })
                         .collect();
                     match survey_cad::io::write_dwg(p, &ents) {
                         Ok(()) => {
@@ -5916,6 +6740,7 @@ fn main() -> Result<(), slint::PlatformError> {
     {
         let weak = app.as_weak();
         let point_db = point_db.clone();
+        let workspace_ground = workspace_ground.clone();
         app.on_export_shp(move || {
             if let Some(path) = rfd::FileDialog::new()
                 .add_filter("SHP", &["shp"])
@@ -5923,8 +6748,13 @@ fn main() -> Result<(), slint::PlatformError> {
             {
                 if let Some(p) = path.to_str() {
                     #[cfg(feature = "shapefile")]
-                    if let Err(e) =
-                        survey_cad::io::shp::write_points_shp(p, &point_db.borrow(), None)
+                    let grid: Vec<Point> = point_db
+                        .borrow()
+                        .iter()
+                        .map(|pt| to_grid(*workspace_ground.borrow(), *pt))
+                        .collect();
+                    #[cfg(feature = "shapefile")]
+                    if let Err(e) = survey_cad::io::shp::write_points_shp(p, &grid, None)
                     {
                         if let Some(app) = weak.upgrade() {
                             app.set_status(SharedString::from(format!("Failed to export: {e}")));
@@ -5945,6 +6775,7 @@ fn main() -> Result<(), slint::PlatformError> {
         let weak = app.as_weak();
         let lines_ref = lines.clone();
         let polylines_ref = polylines.clone();
+        let workspace_ground = workspace_ground.clone();
         app.on_export_polylines_shp(move || {
             if let Some(path) = rfd::FileDialog::new()
                 .add_filter("SHP", &["shp"])
@@ -5958,6 +6789,11 @@ fn main() -> Result<(), slint::PlatformError> {
                             out.push(Polyline::new(vec![*s, *e]));
                         }
                         out.extend(polylines_ref.borrow().iter().cloned());
+                        for pl in &mut out {
+                            for v in &mut pl.vertices {
+                                *v = to_grid(*workspace_ground.borrow(), *v);
+                            }
+                        }
                         if let Err(e) = survey_cad::io::shp::write_polylines_shp(p, &out, None) {
                             if let Some(app) = weak.upgrade() {
                                 app.set_status(SharedString::from(format!("Failed to export: {e}")));
@@ -5978,14 +6814,22 @@ fn main() -> Result<(), slint::PlatformError> {
     {
         let weak = app.as_weak();
         let polygons_ref = polygons.clone();
+        let workspace_ground = workspace_ground.clone();
         app.on_export_polygons_shp(move || {
             if let Some(path) = rfd::FileDialog::new()
                 .add_filter("SHP", &["shp"])
                 .save_file()
             {
                 if let Some(p) = path.to_str() {
+                    let ground = *workspace_ground.borrow();
+                    #[cfg(feature = "shapefile")]
+                    let polys: Vec<Vec<Point>> = polygons_ref
+                        .borrow()
+                        .iter()
+                        .map(|poly| poly.iter().map(|pt| to_grid(ground, *pt)).collect())
+                        .collect();
                     #[cfg(feature = "shapefile")]
-                    if let Err(e) = survey_cad::io::shp::write_polygons_shp(p, &polygons_ref.borrow(), None) {
+                    if let Err(e) = survey_cad::io::shp::write_polygons_shp(p, &polys, None) {
                         if let Some(app) = weak.upgrade() {
                             app.set_status(SharedString::from(format!("Failed to export: {e}")));
                         }
@@ -6004,6 +6848,7 @@ fn main() -> Result<(), slint::PlatformError> {
     {
         let weak = app.as_weak();
         let point_db = point_db.clone();
+        let workspace_ground = workspace_ground.clone();
         app.on_export_las(move || {
             if let Some(path) = rfd::FileDialog::new()
                 .add_filter("LAS", &["las", "laz"])
@@ -6015,7 +6860,10 @@ fn main() -> Result<(), slint::PlatformError> {
                         let pts3: Vec<survey_cad::geometry::Point3> = point_db
                             .borrow()
                             .iter()
-                            .map(|pt| survey_cad::geometry::Point3::new(pt.x, pt.y, 0.0))
+                            .map(|pt| {
+                                let p3 = survey_cad::geometry::Point3::new(pt.x, pt.y, 0.0);
+                                to_grid3(*workspace_ground.borrow(), p3)
+                            })
                             .collect();
                         if let Err(e) = survey_cad::io::las::write_points_las(p, &pts3) {
                             if let Some(app) = weak.upgrade() {
@@ -6039,6 +6887,7 @@ fn main() -> Result<(), slint::PlatformError> {
     {
         let weak = app.as_weak();
         let point_db = point_db.clone();
+        let workspace_ground = workspace_ground.clone();
         app.on_export_e57(move || {
             if let Some(path) = rfd::FileDialog::new()
                 .add_filter("E57", &["e57"])
@@ -6050,7 +6899,10 @@ fn main() -> Result<(), slint::PlatformError> {
                         let pts3: Vec<survey_cad::geometry::Point3> = point_db
                             .borrow()
                             .iter()
-                            .map(|pt| survey_cad::geometry::Point3::new(pt.x, pt.y, 0.0))
+                            .map(|pt| {
+                                let p3 = survey_cad::geometry::Point3::new(pt.x, pt.y, 0.0);
+                                to_grid3(*workspace_ground.borrow(), p3)
+                            })
                             .collect();
                         if let Err(e) = survey_cad::io::e57::write_points_e57(p, &pts3) {
                             if let Some(app) = weak.upgrade() {
@@ -6077,6 +6929,8 @@ fn main() -> Result<(), slint::PlatformError> {
         let surface_units_clone = surface_units.clone();
         let surface_styles_clone = surface_styles.clone();
         let surface_descriptions_clone = surface_descriptions.clone();
+        let workspace_vertical_datum = workspace_vertical_datum.clone();
+        let workspace_ground = workspace_ground.clone();
         app.on_export_landxml_surface(move || {
             if surfaces.borrow().is_empty() {
                 if let Some(app) = weak.upgrade() {
@@ -6089,13 +6943,17 @@ fn main() -> Result<(), slint::PlatformError> {
                 .save_file()
             {
                 if let Some(p) = path.to_str() {
-                    let tin = &surfaces.borrow()[0];
+                    let mut tin = surfaces.borrow()[0].clone();
+                    for v in &mut tin.vertices {
+                        *v = to_grid3(*workspace_ground.borrow(), *v);
+                    }
                     let extras = survey_cad::io::landxml::LandxmlExtras {
                         units: surface_units_clone.borrow().first().cloned(),
                         style: surface_styles_clone.borrow().first().cloned(),
                         description: surface_descriptions_clone.borrow().first().cloned(),
+                        vertical_datum: workspace_vertical_datum.borrow().clone(),
                     };
-                    if let Err(e) = survey_cad::io::landxml::write_landxml_surface(p, tin, Some(&extras)) {
+                    if let Err(e) = survey_cad::io::landxml::write_landxml_surface(p, &tin, Some(&extras)) {
                         if let Some(app) = weak.upgrade() {
                             app.set_status(SharedString::from(format!("Failed to export: {e}")));
                         }
@@ -6110,6 +6968,7 @@ fn main() -> Result<(), slint::PlatformError> {
     {
         let weak = app.as_weak();
         let alignments = alignments.clone();
+        let workspace_ground = workspace_ground.clone();
         app.on_export_landxml_alignment(move || {
             if alignments.borrow().is_empty() {
                 if let Some(app) = weak.upgrade() {
@@ -6122,8 +6981,11 @@ fn main() -> Result<(), slint::PlatformError> {
                 .save_file()
             {
                 if let Some(p) = path.to_str() {
-                    let al = &alignments.borrow()[0];
-                    if let Err(e) = survey_cad::io::landxml::write_landxml_alignment(p, &al.horizontal, None) {
+                    let mut hal = alignments.borrow()[0].horizontal.clone();
+                    if let Some(g) = *workspace_ground.borrow() {
+                        hal.transform_with(&g.inverse());
+                    }
+                    if let Err(e) = survey_cad::io::landxml::write_landxml_alignment(p, &hal, None) {
                         if let Some(app) = weak.upgrade() {
                             app.set_status(SharedString::from(format!("Failed to export: {e}")));
                         }
@@ -6142,6 +7004,7 @@ fn main() -> Result<(), slint::PlatformError> {
         let surface_styles_clone = surface_styles.clone();
         let surface_descriptions_clone = surface_descriptions.clone();
         let alignments = alignments.clone();
+        let workspace_vertical_datum = workspace_vertical_datum.clone();
         app.on_export_landxml_sections(move || {
             if surfaces.borrow().is_empty() || alignments.borrow().is_empty() {
                 if let Some(app) = weak.upgrade() {
@@ -6160,6 +7023,7 @@ fn main() -> Result<(), slint::PlatformError> {
                         units: surface_units_clone.borrow().first().cloned(),
                         style: surface_styles_clone.borrow().first().cloned(),
                         description: surface_descriptions_clone.borrow().first().cloned(),
+                        vertical_datum: workspace_vertical_datum.borrow().clone(),
                     };
                     if let Err(e) = survey_cad::io::landxml::write_landxml_cross_sections(p, &secs, Some(&extras)) {
                         if let Some(app) = weak.upgrade() {
@@ -6955,6 +7819,15 @@ fn main() -> Result<(), slint::PlatformError> {
             dlg.set_snap_midpoints(prefs.snap_midpoints);
             dlg.set_snap_intersections(prefs.snap_intersections);
             dlg.set_snap_nearest(prefs.snap_nearest);
+            dlg.set_snap_centres(prefs.snap_centres);
+            dlg.set_snap_quadrants(prefs.snap_quadrants);
+            dlg.set_snap_insertions(prefs.snap_insertions);
+            dlg.set_snap_apparent_intersections(prefs.snap_apparent_intersections);
+            dlg.set_snap_extensions(prefs.snap_extensions);
+            dlg.set_snap_perpendiculars(prefs.snap_perpendiculars);
+            dlg.set_snap_tangents(prefs.snap_tangents);
+            dlg.set_polar_tracking(prefs.polar_tracking);
+            dlg.set_object_tracking(prefs.object_tracking);
             drop(prefs);
             let dlg_weak = dlg.as_weak();
             let prefs_ref = snap_prefs_ref.clone();
@@ -6976,6 +7849,24 @@ fn main() -> Result<(), slint::PlatformError> {
                     cfg_ref.borrow_mut().snap.snap_intersections = d.get_snap_intersections();
                     prefs_ref.borrow_mut().snap_nearest = d.get_snap_nearest();
                     cfg_ref.borrow_mut().snap.snap_nearest = d.get_snap_nearest();
+                    prefs_ref.borrow_mut().snap_centres = d.get_snap_centres();
+                    cfg_ref.borrow_mut().snap.snap_centres = d.get_snap_centres();
+                    prefs_ref.borrow_mut().snap_quadrants = d.get_snap_quadrants();
+                    cfg_ref.borrow_mut().snap.snap_quadrants = d.get_snap_quadrants();
+                    prefs_ref.borrow_mut().snap_insertions = d.get_snap_insertions();
+                    cfg_ref.borrow_mut().snap.snap_insertions = d.get_snap_insertions();
+                    prefs_ref.borrow_mut().snap_apparent_intersections = d.get_snap_apparent_intersections();
+                    cfg_ref.borrow_mut().snap.snap_apparent_intersections = d.get_snap_apparent_intersections();
+                    prefs_ref.borrow_mut().snap_extensions = d.get_snap_extensions();
+                    cfg_ref.borrow_mut().snap.snap_extensions = d.get_snap_extensions();
+                    prefs_ref.borrow_mut().snap_perpendiculars = d.get_snap_perpendiculars();
+                    cfg_ref.borrow_mut().snap.snap_perpendiculars = d.get_snap_perpendiculars();
+                    prefs_ref.borrow_mut().snap_tangents = d.get_snap_tangents();
+                    cfg_ref.borrow_mut().snap.snap_tangents = d.get_snap_tangents();
+                    prefs_ref.borrow_mut().polar_tracking = d.get_polar_tracking();
+                    cfg_ref.borrow_mut().snap.polar_tracking = d.get_polar_tracking();
+                    prefs_ref.borrow_mut().object_tracking = d.get_object_tracking();
+                    cfg_ref.borrow_mut().snap.object_tracking = d.get_object_tracking();
                     if let Some(a) = app_weak.upgrade() {
                         a.set_snap_points(d.get_snap_points());
                         a.set_snap_endpoints(d.get_snap_endpoints());
@@ -7008,6 +7899,7 @@ fn main() -> Result<(), slint::PlatformError> {
         let surface_units = surface_units.clone();
         let surface_styles = surface_styles.clone();
         let surface_descriptions = surface_descriptions.clone();
+        let workspace_ground = workspace_ground.clone();
         app.on_import_landxml_surface(move || {
             if let Some(path) = rfd::FileDialog::new()
                 .add_filter("LandXML", &["xml"])
@@ -7015,7 +7907,10 @@ fn main() -> Result<(), slint::PlatformError> {
             {
                 if let Some(p) = path.to_str() {
                     match survey_cad::io::landxml::read_landxml_surface(p) {
-                        Ok((tin, extras)) => {
+                        Ok((mut tin, extras)) => {
+                            for v in &mut tin.vertices {
+                                *v = to_ground3(*workspace_ground.borrow(), *v);
+                            }
                             let verts: Vec<Point3> = tin
                                 .vertices
                                 .iter()
@@ -7057,6 +7952,7 @@ fn main() -> Result<(), slint::PlatformError> {
         let alignments = alignments.clone();
         let render_image = render_image.clone();
         let backend_render = backend.clone();
+        let workspace_ground = workspace_ground.clone();
         app.on_import_landxml_alignment(move || {
             if let Some(path) = rfd::FileDialog::new()
                 .add_filter("LandXML", &["xml"])
@@ -7064,7 +7960,10 @@ fn main() -> Result<(), slint::PlatformError> {
             {
                 if let Some(p) = path.to_str() {
                     match survey_cad::io::landxml::read_landxml_alignment(p) {
-                        Ok((hal, _)) => {
+                        Ok((mut hal, _)) => {
+                            if let Some(g) = *workspace_ground.borrow() {
+                                hal.transform_with(&g);
+                            }
                             let val = survey_cad::io::landxml::read_landxml_profile(p)
                                 .unwrap_or_else(|_| VerticalAlignment::new(vec![(0.0, 0.0), (hal.length(), 0.0)]));
                             alignments.borrow_mut().push(Alignment::new(hal, val));
@@ -7092,6 +7991,52 @@ fn main() -> Result<(), slint::PlatformError> {
         });
     }
 
+    {
+        let weak = app.as_weak();
+        let backend_render = backend.clone();
+        let workspace_ground = workspace_ground.clone();
+        app.on_import_landxml_pipe_network(move || {
+            if let Some(path) = rfd::FileDialog::new()
+                .add_filter("LandXML", &["xml"])
+                .pick_file()
+            {
+                if let Some(p) = path.to_str() {
+                    match pipe_network::read_network_landxml(p) {
+                        Ok(mut net) => {
+                            let ground = *workspace_ground.borrow();
+                            for st in &mut net.structures {
+                                let p = to_ground(ground, Point::new(st.x, st.y));
+                                (st.x, st.y) = (p.x, p.y);
+                            }
+                            let solids = pipe_network::network_model(&net).solids();
+                            let count = solids.len();
+                            for sol in solids {
+                                backend_render.borrow_mut().add_solid(sol);
+                            }
+                            if let Some(app) = weak.upgrade() {
+                                app.set_status(SharedString::from(format!(
+                                    "Imported pipe network ({count} solids)"
+                                )));
+                                if app.get_workspace_mode() == 1 {
+                                    let image = backend_render.borrow_mut().render();
+                                    app.set_workspace_texture(image);
+                                }
+                                app.window().request_redraw();
+                            }
+                        }
+                        Err(e) => {
+                            if let Some(app) = weak.upgrade() {
+                                app.set_status(SharedString::from(format!(
+                                    "Failed to import: {e}"
+                                )));
+                            }
+                        }
+                    }
+                }
+            }
+        });
+    }
+
     {
         let weak = app.as_weak();
         let point_db = point_db.clone();
@@ -7112,6 +8057,8 @@ fn main() -> Result<(), slint::PlatformError> {
         let macro_playing = macro_playing.clone();
         let macro_recorder = macro_recorder.clone();
         let snap_target = snap_target.clone();
+        let snap_state = snap_state.clone();
+        let snap_prefs = snap_prefs.clone();
         app.on_workspace_clicked(move |x, y| {
             if *drawing_mode.borrow() != DrawingMode::None {
                 if let Some(app) = weak.upgrade() {
@@ -7133,21 +8080,15 @@ fn main() -> Result<(), slint::PlatformError> {
                             polylines: &polylines.borrow(),
                             arcs: &arcs_ref.borrow(),
                         };
-                        let opts = snap::SnapOptions {
-                            snap_points: app.get_snap_points(),
-                            snap_endpoints: app.get_snap_endpoints(),
-                            snap_midpoints: app.get_snap_midpoints(),
-                            snap_intersections: app.get_snap_intersections(),
-                            snap_nearest: app.get_snap_nearest(),
-                        };
-                        if let Some(sp) = snap::resolve_snap(
+                        let opts = snap_prefs.borrow().options();
+                        if let Some(sp) = snap_state.borrow_mut().snap(
                             p,
                             &scene,
                             app.get_snap_tolerance() as f64 / (zoom_factor as f64),
                             opts,
                         ) {
                             *snap_target.borrow_mut() = Some(sp);
-                            p = sp;
+                            p = sp.point;
                         } else {
                             *snap_target.borrow_mut() = None;
                         }
@@ -7158,6 +8099,9 @@ fn main() -> Result<(), slint::PlatformError> {
                         p.x = p.x.round();
                         p.y = p.y.round();
                     }
+                    snap_state
+                        .borrow_mut()
+                        .pick(p, snap_target.borrow().as_ref());
                     let mut mode = drawing_mode.borrow_mut();
                     match &mut *mode {
                         DrawingMode::Line { start: Some(s) } => {
@@ -7221,7 +8165,11 @@ fn main() -> Result<(), slint::PlatformError> {
                         }
                         _ => {}
                     }
+                    let finished = *mode == DrawingMode::None;
                     drop(mode);
+                    if finished {
+                        snap_state.borrow_mut().reset();
+                    }
                     if app.get_workspace_mode() == 0 {
                         app.set_workspace_image(render_image());
                         app.window().request_redraw();
@@ -7247,21 +8195,15 @@ fn main() -> Result<(), slint::PlatformError> {
                             polylines: &polylines.borrow(),
                             arcs: &arcs.borrow(),
                         };
-                        let opts = snap::SnapOptions {
-                            snap_points: app.get_snap_points(),
-                            snap_endpoints: app.get_snap_endpoints(),
-                            snap_midpoints: app.get_snap_midpoints(),
-                            snap_intersections: app.get_snap_intersections(),
-                            snap_nearest: app.get_snap_nearest(),
-                        };
-                        if let Some(sp) = snap::resolve_snap(
+                        let opts = snap_prefs.borrow().options();
+                        if let Some(sp) = snap_state.borrow_mut().snap(
                             p,
                             &scene,
                             app.get_snap_tolerance() as f64 / (zoom_factor as f64),
                             opts,
                         ) {
                             *snap_target.borrow_mut() = Some(sp);
-                            p = sp;
+                            p = sp.point;
                         } else {
                             *snap_target.borrow_mut() = None;
                         }
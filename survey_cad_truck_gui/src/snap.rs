@@ -1,6 +1,6 @@
 use survey_cad::geometry::{Arc, Line, Point, Polyline};
 use survey_cad::io::DxfEntity;
-use survey_cad::snap::{snap_point_with_settings, SnapSettings};
+use survey_cad::snap::{Snap, SnapContext, SnapEngine, SnapKind, SnapModes};
 
 pub struct Scene<'a> {
     pub points: &'a [Point],
@@ -10,6 +10,35 @@ pub struct Scene<'a> {
     pub arcs: &'a [Arc],
 }
 
+/// Copy of the scene a [`SnapEngine`] was built from.
+struct BuiltScene {
+    points: Vec<Point>,
+    lines: Vec<(Point, Point)>,
+    polygons: Vec<Vec<Point>>,
+    polylines: Vec<Polyline>,
+    arcs: Vec<Arc>,
+}
+
+impl BuiltScene {
+    fn new(scene: &Scene) -> Self {
+        Self {
+            points: scene.points.to_vec(),
+            lines: scene.lines.to_vec(),
+            polygons: scene.polygons.to_vec(),
+            polylines: scene.polylines.to_vec(),
+            arcs: scene.arcs.to_vec(),
+        }
+    }
+
+    fn matches(&self, scene: &Scene) -> bool {
+        self.points == scene.points
+            && self.lines == scene.lines
+            && self.polygons == scene.polygons
+            && self.polylines == scene.polylines
+            && self.arcs == scene.arcs
+    }
+}
+
 #[derive(Default, Clone, Copy)]
 pub struct SnapOptions {
     pub snap_points: bool,
@@ -17,42 +46,122 @@ pub struct SnapOptions {
     pub snap_midpoints: bool,
     pub snap_intersections: bool,
     pub snap_nearest: bool,
+    pub snap_centres: bool,
+    pub snap_quadrants: bool,
+    pub snap_insertions: bool,
+    pub snap_apparent_intersections: bool,
+    pub snap_extensions: bool,
+    pub snap_perpendiculars: bool,
+    pub snap_tangents: bool,
+    pub polar_tracking: bool,
+    pub object_tracking: bool,
 }
 
-pub fn resolve_snap(
-    target: Point,
-    scene: &Scene,
-    tol: f64,
-    opts: SnapOptions,
-) -> Option<Point> {
-    let mut ents: Vec<DxfEntity> = Vec::new();
-    if opts.snap_points {
-        for p in scene.points {
-            ents.push(DxfEntity::Point { point: *p, layer: None });
+impl SnapOptions {
+    pub fn modes(&self) -> SnapModes {
+        SnapModes {
+            endpoint: self.snap_endpoints,
+            midpoint: self.snap_midpoints,
+            centre: self.snap_centres,
+            node: self.snap_points,
+            insertion: self.snap_insertions,
+            quadrant: self.snap_quadrants,
+            intersection: self.snap_intersections,
+            apparent_intersection: self.snap_apparent_intersections,
+            extension: self.snap_extensions,
+            perpendicular: self.snap_perpendiculars,
+            tangent: self.snap_tangents,
+            nearest: self.snap_nearest,
+            polar_tracking: self.polar_tracking,
+            object_tracking: self.object_tracking,
         }
     }
-    if opts.snap_endpoints || opts.snap_midpoints || opts.snap_intersections || opts.snap_nearest {
-        for (s, e) in scene.lines {
-            ents.push(DxfEntity::Line { line: Line::new(*s, *e), layer: None });
-        }
-        for poly in scene.polygons {
-            ents.push(DxfEntity::Polyline { polyline: Polyline::new(poly.clone()), layer: None });
-        }
-        for pl in scene.polylines {
-            ents.push(DxfEntity::Polyline { polyline: pl.clone(), layer: None });
+}
+
+/// Snap engine kept for the scene it was built from, rebuilt only when the
+/// scene changes, and the context of picked and acquired points.
+#[derive(Default)]
+pub struct SnapState {
+    built: Option<(BuiltScene, SnapEngine)>,
+    pub context: SnapContext,
+}
+
+impl SnapState {
+    pub fn snap(
+        &mut self,
+        target: Point,
+        scene: &Scene,
+        tol: f64,
+        opts: SnapOptions,
+    ) -> Option<Snap> {
+        if !self.built.as_ref().is_some_and(|(b, _)| b.matches(scene)) {
+            self.built = Some((BuiltScene::new(scene), build_engine(scene)));
         }
-        for arc in scene.arcs {
-            ents.push(DxfEntity::Arc { arc: *arc, layer: None });
+        let (_, engine) = self.built.as_ref()?;
+        engine.snap(target, tol, opts.modes(), &self.context)
+    }
+
+    /// Records a picked point as the base for perpendicular, tangent and
+    /// polar snaps, acquiring it for tracking when it snapped to an object.
+    pub fn pick(&mut self, p: Point, snap: Option<&Snap>) {
+        self.context.from = Some(p);
+        if let Some(s) = snap {
+            if !matches!(
+                s.kind,
+                SnapKind::Nearest
+                    | SnapKind::Extension
+                    | SnapKind::PolarTracking
+                    | SnapKind::ObjectTracking
+            ) {
+                self.context.acquire(s.point);
+            }
         }
     }
-    let settings = SnapSettings {
-        endpoints: opts.snap_points || opts.snap_endpoints,
-        midpoints: opts.snap_midpoints,
-        intersections: opts.snap_intersections,
-        nearest: opts.snap_nearest,
-    };
-    if ents.is_empty() {
-        return None;
+
+    /// Forgets picked and acquired points once a command finishes.
+    pub fn reset(&mut self) {
+        self.context.from = None;
+        self.context.acquired.clear();
+    }
+}
+
+fn build_engine(scene: &Scene) -> SnapEngine {
+    let mut ents: Vec<DxfEntity> = Vec::new();
+    for p in scene.points {
+        ents.push(DxfEntity::Point { point: *p, layer: None });
+    }
+    for (s, e) in scene.lines {
+        ents.push(DxfEntity::Line { line: Line::new(*s, *e), layer: None });
+    }
+    for poly in scene.polygons {
+        ents.push(DxfEntity::Polyline { polyline: Polyline::new(poly.clone()), layer: None });
+    }
+    for pl in scene.polylines {
+        ents.push(DxfEntity::Polyline { polyline: pl.clone(), layer: None });
+    }
+    for arc in scene.arcs {
+        ents.push(DxfEntity::Arc { arc: *arc, layer: None });
+    }
+    // cells of about a hundredth of the drawing extent
+    let (mut min, mut max) = (Point::new(f64::MAX, f64::MAX), Point::new(f64::MIN, f64::MIN));
+    let corners = scene
+        .points
+        .iter()
+        .chain(scene.lines.iter().flat_map(|(s, e)| [s, e]))
+        .chain(scene.polygons.iter().flatten())
+        .chain(scene.polylines.iter().flat_map(|pl| &pl.vertices))
+        .copied()
+        .chain(scene.arcs.iter().flat_map(|a| {
+            [
+                Point::new(a.center.x - a.radius, a.center.y - a.radius),
+                Point::new(a.center.x + a.radius, a.center.y + a.radius),
+            ]
+        }));
+    for p in corners {
+        min = Point::new(min.x.min(p.x), min.y.min(p.y));
+        max = Point::new(max.x.max(p.x), max.y.max(p.y));
     }
-    snap_point_with_settings(target, &ents, tol, settings)
+    let extent = (max.x - min.x).max(max.y - min.y);
+    let cell = if extent.is_finite() { extent / 100.0 } else { 1.0 };
+    SnapEngine::new(&ents, cell)
 }